@@ -63,10 +63,32 @@ impl tailcall::core::Cache for Cache {
         unimplemented!("Not needed for this bench")
     }
 
+    async fn set_with_tags<'a>(
+        &'a self,
+        _: Self::Key,
+        _: Self::Value,
+        _: NonZeroU64,
+        _: Vec<String>,
+    ) -> Result<(), cache::Error> {
+        unimplemented!("Not needed for this bench")
+    }
+
     async fn get<'a>(&'a self, _: &'a Self::Key) -> Result<Option<Self::Value>, cache::Error> {
         unimplemented!("Not needed for this bench")
     }
 
+    async fn invalidate<'a>(&'a self, _: &'a str) -> Result<usize, cache::Error> {
+        unimplemented!("Not needed for this bench")
+    }
+
+    async fn evict<'a>(&'a self, _: &'a Self::Key) -> Result<bool, cache::Error> {
+        unimplemented!("Not needed for this bench")
+    }
+
+    async fn clear<'a>(&'a self) -> Result<usize, cache::Error> {
+        unimplemented!("Not needed for this bench")
+    }
+
     fn hit_rate(&self) -> Option<f64> {
         unimplemented!("Not needed for this bench")
     }
@@ -85,8 +107,12 @@ pub fn benchmark_data_loader(c: &mut Criterion) {
                     file: Arc::new(File {}),
                     cache: Arc::new(Cache {}),
                     extensions: Arc::new(vec![]),
+                    body_transformers: Arc::new(vec![]),
+                    eval_middlewares: Arc::new(vec![]),
                     cmd_worker: None,
                     worker: None,
+                    rng: Default::default(),
+                    websocket: None,
                 };
                 let loader = HttpDataLoader::new(rt, None, false);
                 let loader = loader.to_data_loader(Batch::default().delay(1));
@@ -190,7 +190,19 @@ impl ResolverContextLike for MockGraphqlContext {
         false
     }
 
+    fn operation_name(&self) -> Option<&str> {
+        None
+    }
+
+    fn operation_type(&self) -> Option<async_graphql::parser::types::OperationType> {
+        None
+    }
+
     fn add_error(&self, _: async_graphql::ServerError) {}
+
+    fn response_path(&self) -> Vec<async_graphql::PathSegment> {
+        vec![]
+    }
 }
 
 // assert that everything was set up correctly for the benchmark
@@ -249,8 +261,12 @@ fn request_context() -> RequestContext {
         file: Arc::new(File {}),
         cache: Arc::new(InMemoryCache::default()),
         extensions: Arc::new(vec![]),
+        body_transformers: Arc::new(vec![]),
+        eval_middlewares: Arc::new(vec![]),
         cmd_worker: None,
         worker: None,
+        rng: Default::default(),
+        websocket: None,
     };
     RequestContext::new(runtime)
         .server(server)
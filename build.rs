@@ -0,0 +1,41 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Sources of the types the blueprint cache is serialized from, see
+/// `BlueprintCache`.
+const BLUEPRINT_SOURCES: &str = "src/core";
+
+fn main() {
+    println!("cargo:rerun-if-changed={BLUEPRINT_SOURCES}");
+
+    let mut files = vec![];
+    collect(Path::new(BLUEPRINT_SOURCES), &mut files);
+    files.sort();
+
+    let mut hasher = DefaultHasher::new();
+    for file in files {
+        file.hash(&mut hasher);
+        fs::read(&file).unwrap_or_default().hash(&mut hasher);
+    }
+
+    println!(
+        "cargo:rustc-env=BLUEPRINT_SCHEMA_HASH={:016x}",
+        hasher.finish()
+    );
+}
+
+fn collect(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            collect(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+}
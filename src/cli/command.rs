@@ -30,6 +30,17 @@ pub enum Command {
         /// production)
         #[arg(short, long, action = clap::ArgAction::Set, default_value_t = true)]
         verify_ssl: bool,
+
+        /// Path of the file the compiled configuration is cached in. The cache
+        /// is loaded on the next start if the configuration hasn't changed,
+        /// otherwise the configuration is compiled and the cache is replaced
+        #[arg(long)]
+        blueprint_cache: Option<String>,
+
+        /// Starts the server in the insecure mode for local development, in
+        /// which `@upstream(dangerAcceptInvalidCerts)` is honored
+        #[arg(long)]
+        insecure: bool,
     },
 
     /// Validate a composition spec
@@ -47,6 +58,11 @@ pub enum Command {
         #[arg(short, long)]
         schema: bool,
 
+        /// Display the upstream endpoints and the protected fields as JSON,
+        /// for security reviews
+        #[arg(long)]
+        inventory: bool,
+
         /// Controls SSL/TLS certificate verification for remote config files
         /// Set to false to skip certificate verification (not recommended for
         /// production)
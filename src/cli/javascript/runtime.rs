@@ -1,9 +1,12 @@
 use std::cell::{OnceCell, RefCell};
 use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::thread;
 
 use async_graphql_value::ConstValue;
 use rquickjs::{Context, Ctx, FromJs, Function, IntoJs, Value};
+use tokio::sync::{Semaphore, SemaphorePermit};
 
 use crate::core::worker::{Command, Event};
 use crate::core::{blueprint, worker, WorkerIO};
@@ -11,7 +14,7 @@ use crate::core::{blueprint, worker, WorkerIO};
 struct LocalRuntime(Context);
 
 thread_local! {
-    // Practically only one JS runtime is created for every Runtime because tokio_runtime is single threaded.
+    // Practically only one JS runtime is created for every worker because its tokio_runtime is single threaded.
   static LOCAL_RUNTIME: RefCell<OnceCell<LocalRuntime>> = const { RefCell::new(OnceCell::new()) };
 }
 
@@ -50,30 +53,29 @@ impl TryFrom<blueprint::Script> for LocalRuntime {
     }
 }
 
-pub struct Runtime {
-    script: blueprint::Script,
-    // Single threaded JS runtime, that's shared across all tokio workers.
+/// A worker of the pool, whose JS runtime lives on the only thread of its
+/// tokio runtime.
+struct Worker {
     tokio_runtime: Option<tokio::runtime::Runtime>,
+    // Calls handled by the worker, to know when its JS runtime is recreated.
+    calls: AtomicUsize,
 }
 
-impl Debug for Runtime {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Runtime {{ script: {:?} }}", self.script)
-    }
-}
-
-impl Runtime {
-    pub fn new(script: blueprint::Script) -> Self {
+impl Worker {
+    fn new() -> Self {
         let tokio_runtime = tokio::runtime::Builder::new_multi_thread()
             .worker_threads(1)
             .build()
             .expect("JS runtime not initialized");
 
-        Self { script, tokio_runtime: Some(tokio_runtime) }
+        Self {
+            tokio_runtime: Some(tokio_runtime),
+            calls: AtomicUsize::new(0),
+        }
     }
 }
 
-impl Drop for Runtime {
+impl Drop for Worker {
     fn drop(&mut self) {
         // implicit call implementation to shutdown the tokio runtime
         // without blocking. Otherwise it will panic on an attempt to
@@ -84,21 +86,125 @@ impl Drop for Runtime {
     }
 }
 
+pub struct Runtime {
+    script: blueprint::Script,
+    workers: Vec<Worker>,
+    // Indices of the workers that aren't handling a call.
+    idle: Mutex<Vec<usize>>,
+    permits: Semaphore,
+    // Calls that wait for a free worker.
+    queued: AtomicUsize,
+}
+
+impl Debug for Runtime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Runtime {{ script: {:?} }}", self.script)
+    }
+}
+
+/// A worker that's taken by a call, it's free again once the lease is dropped.
+struct Lease<'a> {
+    index: usize,
+    idle: &'a Mutex<Vec<usize>>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl Drop for Lease<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut idle) = self.idle.lock() {
+            idle.push(self.index);
+        }
+    }
+}
+
+/// A call that waits for a free worker, it leaves the queue once it's dropped.
+struct Queued<'a>(&'a AtomicUsize);
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Runtime {
+    pub fn new(script: blueprint::Script) -> Self {
+        let size = script.pool_size.get();
+        let workers = (0..size).map(|_| Worker::new()).collect();
+
+        Self {
+            script,
+            workers,
+            idle: Mutex::new((0..size).collect()),
+            permits: Semaphore::new(size),
+            queued: AtomicUsize::new(0),
+        }
+    }
+
+    /// Takes a free worker, waiting for one if all of them are busy. Fails
+    /// right away once the queue of the waiting calls is full.
+    async fn lease(&self) -> Result<Lease<'_>, worker::Error> {
+        let permit = match self.permits.try_acquire() {
+            Ok(permit) => permit,
+            Err(_) => {
+                let queued = self.queued.fetch_add(1, Ordering::SeqCst);
+                let _queued = Queued(&self.queued);
+                if self.script.max_queue.is_some_and(|max| queued >= max) {
+                    return Err(worker::Error::PoolSaturated(queued));
+                }
+                self.permits
+                    .acquire()
+                    .await
+                    .map_err(|_| worker::Error::JsRuntimeStopped)?
+            }
+        };
+        let index = self
+            .idle
+            .lock()
+            .map_err(|_| worker::Error::Communication)?
+            .pop()
+            .ok_or(worker::Error::Communication)?;
+
+        Ok(Lease { index, idle: &self.idle, _permit: permit })
+    }
+
+    /// Runs the task on a free worker, whose JS runtime is initialized first
+    /// and recreated after `maxRequests` calls.
+    async fn run<R, F>(&self, task: F) -> Result<R, worker::Error>
+    where
+        R: Send + 'static,
+        F: FnOnce() -> Result<R, worker::Error> + Send + 'static,
+    {
+        let lease = self.lease().await?;
+        let worker = &self.workers[lease.index];
+        let Some(runtime) = &worker.tokio_runtime else {
+            return Err(worker::Error::JsRuntimeStopped);
+        };
+
+        let calls = worker.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        let recycle = self
+            .script
+            .max_requests
+            .is_some_and(|max| calls % max.get() == 0);
+        let script = self.script.clone();
+
+        runtime
+            .spawn(async move {
+                init_rt(script)?;
+                let result = task();
+                if recycle {
+                    drop_rt();
+                }
+                result
+            })
+            .await?
+    }
+}
+
 #[async_trait::async_trait]
 impl WorkerIO<Event, Command> for Runtime {
     async fn call(&self, name: &str, event: Event) -> Result<Option<Command>, worker::Error> {
-        let script = self.script.clone();
         let name = name.to_string(); // TODO
-        if let Some(runtime) = &self.tokio_runtime {
-            runtime
-                .spawn(async move {
-                    init_rt(script)?;
-                    call(name, event)
-                })
-                .await?
-        } else {
-            Err(worker::Error::JsRuntimeStopped)
-        }
+        self.run(move || call(name, event)).await
     }
 }
 
@@ -109,19 +215,9 @@ impl WorkerIO<ConstValue, ConstValue> for Runtime {
         name: &str,
         input: ConstValue,
     ) -> Result<Option<ConstValue>, worker::Error> {
-        let script = self.script.clone();
         let name = name.to_string();
         let value = serde_json::to_string(&input)?;
-        if let Some(runtime) = &self.tokio_runtime {
-            runtime
-                .spawn(async move {
-                    init_rt(script)?;
-                    execute_inner(name, value).map(Some)
-                })
-                .await?
-        } else {
-            Err(worker::Error::JsRuntimeStopped)
-        }
+        self.run(move || execute_inner(name, value).map(Some)).await
     }
 }
 
@@ -141,6 +237,15 @@ fn init_rt(script: blueprint::Script) -> anyhow::Result<()> {
     })
 }
 
+/// Drops the JS runtime of the thread, it's created again on the next call.
+fn drop_rt() {
+    LOCAL_RUNTIME.with(|cell| {
+        let runtime = cell.borrow_mut().take();
+        tracing::debug!("JS Runtime recycled: {:?}", thread::current().name());
+        drop(runtime)
+    })
+}
+
 fn prepare_args<'js, T: IntoJs<'js>>(
     ctx: &Ctx<'js>,
     label: &str,
@@ -199,3 +304,77 @@ fn execute_inner(name: String, value: String) -> Result<ConstValue, worker::Erro
         })
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+
+    fn runtime(source: &str, pool_size: usize) -> Runtime {
+        let mut script = blueprint::Script::new(source.to_string());
+        script.pool_size = NonZeroUsize::new(pool_size).unwrap();
+        Runtime::new(script)
+    }
+
+    async fn call(runtime: &Runtime, name: &str) -> Result<Option<ConstValue>, worker::Error> {
+        WorkerIO::<ConstValue, ConstValue>::call(runtime, name, ConstValue::Null).await
+    }
+
+    #[tokio::test]
+    async fn test_pool_saturation() {
+        let source = r#"
+            function slow() {
+                const end = Date.now() + 100;
+                while (Date.now() < end) {}
+                return "1";
+            }
+        "#;
+        let mut runtime = runtime(source, 1);
+        runtime.script.max_queue = Some(1);
+
+        // the first call takes the only worker and the second one waits for it,
+        // while the third one fails as the queue is full
+        let (first, second, third) = tokio::join!(
+            call(&runtime, "slow"),
+            call(&runtime, "slow"),
+            call(&runtime, "slow")
+        );
+        assert_eq!(first.unwrap(), Some(ConstValue::from(1)));
+        assert_eq!(second.unwrap(), Some(ConstValue::from(1)));
+        assert!(matches!(third, Err(worker::Error::PoolSaturated(1))));
+
+        // the worker takes the calls again once it's free
+        assert!(call(&runtime, "slow").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_pool_without_queue_bound() {
+        let source = r#"function id() { return "1"; }"#;
+        let runtime = runtime(source, 2);
+
+        let calls = (0..8).map(|_| call(&runtime, "id"));
+        let results = futures_util::future::join_all(calls).await;
+        assert!(results.iter().all(Result::is_ok));
+    }
+
+    #[tokio::test]
+    async fn test_worker_recycling() {
+        // the count is kept by the JS runtime, it starts over once it's recreated
+        let source = r#"
+            let count = 0;
+            function count_calls() {
+                count += 1;
+                return JSON.stringify(count);
+            }
+        "#;
+        let mut runtime = runtime(source, 1);
+        runtime.script.max_requests = NonZeroUsize::new(2);
+
+        let mut counts = vec![];
+        for _ in 0..5 {
+            counts.push(call(&runtime, "count_calls").await.unwrap().unwrap());
+        }
+        assert_eq!(counts, [1, 2, 1, 2, 1].map(ConstValue::from));
+    }
+}
@@ -1,5 +1,7 @@
 use anyhow::{anyhow, Result};
+use opentelemetry::KeyValue;
 
+use crate::core::app_context::AppContext;
 use crate::core::runtime::TargetRuntime;
 
 fn cache_metrics(runtime: &TargetRuntime) -> Result<()> {
@@ -19,14 +21,35 @@ fn cache_metrics(runtime: &TargetRuntime) -> Result<()> {
     Ok(())
 }
 
+fn adaptive_timeout_metrics(app_ctx: &AppContext) -> Result<()> {
+    let Some(timeouts) = app_ctx.adaptive_timeouts.clone() else {
+        return Ok(());
+    };
+    let meter = opentelemetry::global::meter("upstream");
+    let gauge = meter
+        .u64_observable_gauge("upstream.adaptive_timeout")
+        .with_description("Adapted timeout of the requests to the upstream in milliseconds")
+        .init();
+
+    meter.register_callback(&[gauge.as_any()], move |observer| {
+        for (origin, timeout) in timeouts.timeouts() {
+            let attributes = [KeyValue::new("upstream", origin)];
+            observer.observe_u64(&gauge, timeout.as_millis() as u64, &attributes);
+        }
+    })?;
+
+    Ok(())
+}
+
 fn process_resources_metrics() -> Result<()> {
     let meter = opentelemetry::global::meter("process-resources");
 
     opentelemetry_system_metrics::init_process_observer(meter).map_err(|err| anyhow!(err))
 }
 
-pub fn init_metrics(runtime: &TargetRuntime) -> Result<()> {
-    cache_metrics(runtime)?;
+pub fn init_metrics(app_ctx: &AppContext) -> Result<()> {
+    cache_metrics(&app_ctx.runtime)?;
+    adaptive_timeout_metrics(app_ctx)?;
     process_resources_metrics()?;
 
     Ok(())
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+
+use super::timing;
+
+type Lookup = dyn Fn(&str) -> io::Result<Vec<SocketAddr>> + Send + Sync;
+
+/// Addresses of a host along with the time they were resolved at.
+type Entry = (Instant, Vec<SocketAddr>);
+
+/// Resolves the upstream hosts with the system resolver and reuses the
+/// addresses for the configured TTL. Once the TTL is over the next connection
+/// to the host resolves it again, so the changes to the DNS records are picked
+/// up by the new connections. The hosts whose connections are kept alive are
+/// resolved again with `refresh`, and the lookups whose addresses changed are
+/// counted, so that the clients could replace those connections.
+#[derive(Clone)]
+pub struct CachingResolver {
+    ttl: Duration,
+    lookup: Arc<Lookup>,
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+    changes: Arc<AtomicUsize>,
+}
+
+impl CachingResolver {
+    pub fn new(ttl: Duration) -> Self {
+        Self::with_lookup(ttl, |host| Ok((host, 0).to_socket_addrs()?.collect()))
+    }
+
+    fn with_lookup(
+        ttl: Duration,
+        lookup: impl Fn(&str) -> io::Result<Vec<SocketAddr>> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            ttl,
+            lookup: Arc::new(lookup),
+            entries: Default::default(),
+            changes: Default::default(),
+        }
+    }
+
+    /// The number of lookups whose addresses differ from the ones the host
+    /// was resolved to before.
+    pub fn changes(&self) -> usize {
+        self.changes.load(Ordering::SeqCst)
+    }
+
+    /// Resolves the host again once its addresses are due. The connections
+    /// that are kept alive don't resolve their host, so the changes of its
+    /// addresses wouldn't be noticed otherwise.
+    pub async fn refresh(&self, host: &str) -> io::Result<()> {
+        if self.ttl.is_zero() || self.cached(host).is_some() {
+            return Ok(());
+        }
+
+        self.resolve_blocking(host.to_string()).await.map(|_| ())
+    }
+
+    // the system resolver is blocking, same as the default resolver of hyper
+    async fn resolve_blocking(&self, host: String) -> io::Result<Vec<SocketAddr>> {
+        let resolver = self.clone();
+        tokio::task::spawn_blocking(move || resolver.resolve_host(&host))
+            .await
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?
+    }
+
+    fn cached(&self, host: &str) -> Option<Vec<SocketAddr>> {
+        let entries = self.entries.lock().unwrap();
+        let (resolved_at, addrs) = entries.get(host)?;
+
+        (resolved_at.elapsed() < self.ttl).then(|| addrs.clone())
+    }
+
+    fn resolve_host(&self, host: &str) -> io::Result<Vec<SocketAddr>> {
+        if let Some(addrs) = self.cached(host) {
+            return Ok(addrs);
+        }
+
+        let addrs = (self.lookup)(host)?;
+        if !self.ttl.is_zero() {
+            let previous = self
+                .entries
+                .lock()
+                .unwrap()
+                .insert(host.to_string(), (Instant::now(), addrs.clone()));
+            // the order of the addresses changes with every lookup of some hosts
+            if previous.is_some_and(|(_, previous)| sorted(previous) != sorted(addrs.clone())) {
+                self.changes.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        Ok(addrs)
+    }
+}
+
+fn sorted(mut addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    addrs.sort();
+    addrs
+}
+
+impl Resolve for CachingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let addrs = resolver.resolve_blocking(name.as_str().to_string()).await?;
+            timing::record(timing::DNS);
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolver(ttl: Duration) -> (CachingResolver, Arc<AtomicUsize>) {
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let counter = lookups.clone();
+        let resolver = CachingResolver::with_lookup(ttl, move |_| {
+            let n = counter.fetch_add(1, Ordering::SeqCst) as u8;
+            Ok(vec![SocketAddr::from(([10, 0, 0, n], 0))])
+        });
+
+        (resolver, lookups)
+    }
+
+    #[test]
+    fn test_reuse_within_ttl() {
+        let (resolver, lookups) = resolver(Duration::from_secs(60));
+
+        let first = resolver.resolve_host("foo.com").unwrap();
+        let second = resolver.resolve_host("foo.com").unwrap();
+        resolver.resolve_host("bar.com").unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(lookups.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_resolve_again_after_ttl() {
+        let (resolver, lookups) = resolver(Duration::from_millis(10));
+
+        let first = resolver.resolve_host("foo.com").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        let second = resolver.resolve_host("foo.com").unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(lookups.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_zero_ttl() {
+        let (resolver, lookups) = resolver(Duration::ZERO);
+
+        resolver.resolve_host("foo.com").unwrap();
+        resolver.resolve_host("foo.com").unwrap();
+
+        assert_eq!(lookups.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_changes() {
+        let (resolver, _) = resolver(Duration::from_millis(10));
+
+        resolver.resolve_host("foo.com").unwrap();
+        assert_eq!(resolver.changes(), 0);
+
+        std::thread::sleep(Duration::from_millis(20));
+        resolver.resolve_host("foo.com").unwrap();
+        assert_eq!(resolver.changes(), 1);
+    }
+
+    #[test]
+    fn test_reordered_addresses() {
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let resolver = CachingResolver::with_lookup(Duration::from_millis(10), move |_| {
+            let mut addrs = vec![
+                SocketAddr::from(([10, 0, 0, 1], 0)),
+                SocketAddr::from(([10, 0, 0, 2], 0)),
+            ];
+            addrs.rotate_left(lookups.fetch_add(1, Ordering::SeqCst) % 2);
+            Ok(addrs)
+        });
+
+        resolver.resolve_host("foo.com").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        resolver.resolve_host("foo.com").unwrap();
+
+        assert_eq!(resolver.changes(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_refresh() {
+        let (resolver, lookups) = resolver(Duration::from_millis(10));
+
+        resolver.resolve_host("foo.com").unwrap();
+        resolver.refresh("foo.com").await.unwrap();
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+
+        // the kept alive connections don't resolve the host, the refresh does
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        resolver.refresh("foo.com").await.unwrap();
+        assert_eq!(lookups.load(Ordering::SeqCst), 2);
+        assert_eq!(resolver.changes(), 1);
+    }
+}
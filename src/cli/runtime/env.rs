@@ -12,6 +12,10 @@ impl EnvIO for EnvNative {
     fn get(&self, key: &str) -> Option<Cow<'_, str>> {
         self.vars.get(key).map(Cow::from)
     }
+
+    fn get_live(&self, key: &str) -> Option<Cow<'_, str>> {
+        std::env::var(key).ok().map(Cow::from)
+    }
 }
 
 impl EnvNative {
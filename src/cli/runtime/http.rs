@@ -1,8 +1,15 @@
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use anyhow::Result;
+use dashmap::DashMap;
+use futures_util::future::{self, FutureExt};
+use futures_util::StreamExt;
+use http::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
 use http_cache_reqwest::{Cache, CacheMode, HttpCache, HttpCacheOptions};
 use hyper::body::Bytes;
+use hyper::client::HttpConnector;
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
 use once_cell::sync::Lazy;
 use opentelemetry::metrics::Counter;
 use opentelemetry::trace::SpanKind;
@@ -11,15 +18,23 @@ use opentelemetry_http::HeaderInjector;
 use opentelemetry_semantic_conventions::trace::{
     HTTP_REQUEST_METHOD, HTTP_RESPONSE_STATUS_CODE, NETWORK_PROTOCOL_VERSION, URL_FULL,
 };
+use reqwest::redirect::Policy;
 use reqwest::Client;
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use tailcall_http_cache::HttpCacheManager;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
+use super::dns::CachingResolver;
+#[cfg(all(feature = "http3", reqwest_unstable))]
+use super::http3::Http3;
+use super::timing::{self, Timing};
 use super::HttpIO;
 use crate::core::blueprint::telemetry::Telemetry;
 use crate::core::blueprint::Upstream;
-use crate::core::http::Response;
+use crate::core::http::upstream_size::UpstreamSize;
+use crate::core::http::{content_encoding, BodyStream, RedirectPolicy, Response, Trailers};
+use crate::core::ir::Error;
 
 static HTTP_CLIENT_REQUEST_COUNT: Lazy<Counter<u64>> = Lazy::new(|| {
     let meter = opentelemetry::global::meter("http_request");
@@ -67,69 +82,203 @@ fn get_response_status(response: &reqwest_middleware::Result<reqwest::Response>)
     KeyValue::new(HTTP_RESPONSE_STATUS_CODE, status_code as i64)
 }
 
+/// Limits the requests in flight over the HTTP/2 connection to each origin,
+/// the others wait for one of them to complete.
+struct Streams {
+    max: usize,
+    origins: DashMap<String, Arc<Semaphore>>,
+}
+
+impl Streams {
+    fn new(max: usize) -> Self {
+        Self { max, origins: DashMap::new() }
+    }
+
+    async fn acquire(&self, url: &reqwest::Url) -> OwnedSemaphorePermit {
+        let origin = url.origin().ascii_serialization();
+        let semaphore = self
+            .origins
+            .entry(origin)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.max)))
+            .clone();
+
+        semaphore
+            .acquire_owned()
+            .await
+            .expect("the semaphore is never closed")
+    }
+}
+
+/// The client of the upstream. It's built again once the addresses one of its
+/// hosts resolves to change, so that the connections that are kept alive by
+/// the requests don't outlive the addresses they were opened to. The
+/// connections of the previous client are closed once their requests are
+/// done, while the HTTP cache is shared by all the clients.
+struct Pool {
+    upstream: Upstream,
+    resolver: Arc<CachingResolver>,
+    http_cache: Option<HttpCacheManager>,
+    // the client along with the number of changes of the addresses it was
+    // built after
+    client: Mutex<(usize, ClientWithMiddleware)>,
+}
+
+impl Pool {
+    fn new(upstream: &Upstream, resolver: Arc<CachingResolver>) -> Self {
+        let http_cache = http_cache(upstream);
+        let client = build_client(upstream, resolver.clone(), &http_cache);
+
+        Self {
+            upstream: upstream.clone(),
+            resolver,
+            http_cache,
+            client: Mutex::new((0, client)),
+        }
+    }
+
+    /// Resolves the host of the request again once its addresses are due, and
+    /// returns the client that was built after their last change.
+    async fn client(&self, url: &reqwest::Url) -> ClientWithMiddleware {
+        if let Some(host) = url.host_str() {
+            // the failed lookups are reported by the connections to the host
+            let _ = self.resolver.refresh(host).await;
+        }
+
+        let changes = self.resolver.changes();
+        let mut client = self.client.lock().unwrap();
+        if client.0 != changes {
+            let rebuilt = build_client(&self.upstream, self.resolver.clone(), &self.http_cache);
+            *client = (changes, rebuilt);
+        }
+
+        client.1.clone()
+    }
+}
+
 #[derive(Clone)]
 pub struct NativeHttp {
-    client: ClientWithMiddleware,
+    pool: Arc<Pool>,
     http2_only: bool,
+    streams: Option<Arc<Streams>>,
+    max_decoded_size: usize,
     enable_telemetry: bool,
+    #[cfg(all(feature = "http3", reqwest_unstable))]
+    http3: Option<Arc<Http3>>,
+    // Reads the trailers of the streamed gRPC responses, that reqwest drops.
+    // It's `None` unless the upstream is `http2Only`.
+    trailers_client: Option<TrailersClient>,
 }
 
+type TrailersClient = hyper::Client<HttpsConnector<HttpConnector>>;
+
 impl Default for NativeHttp {
     fn default() -> Self {
-        Self {
-            client: ClientBuilder::new(Client::new()).build(),
-            http2_only: false,
-            enable_telemetry: false,
-        }
+        Self::init(&Upstream::default(), &Telemetry::default())
     }
 }
 
 impl NativeHttp {
     pub fn init(upstream: &Upstream, telemetry: &Telemetry) -> Self {
-        let mut builder = Client::builder()
-            .tcp_keepalive(Some(Duration::from_secs(upstream.tcp_keep_alive)))
-            .timeout(Duration::from_secs(upstream.timeout))
-            .connect_timeout(Duration::from_secs(upstream.connect_timeout))
-            .http2_keep_alive_interval(Some(Duration::from_secs(upstream.keep_alive_interval)))
-            .http2_keep_alive_timeout(Duration::from_secs(upstream.keep_alive_timeout))
-            .http2_keep_alive_while_idle(upstream.keep_alive_while_idle)
-            .pool_idle_timeout(Some(Duration::from_secs(upstream.pool_idle_timeout)))
-            .pool_max_idle_per_host(upstream.pool_max_idle_per_host)
-            .user_agent(upstream.user_agent.clone())
-            .danger_accept_invalid_certs(!upstream.verify_ssl);
-
-        // Add Http2 Prior Knowledge
-        if upstream.http2_only {
-            builder = builder.http2_prior_knowledge();
+        // the resolver replaces the one of the HTTP client to time the lookups, it
+        // caches the addresses only when a TTL is configured
+        let resolver = Arc::new(CachingResolver::new(Duration::from_secs(
+            upstream.dns_ttl.unwrap_or(0),
+        )));
+
+        #[cfg(not(all(feature = "http3", reqwest_unstable)))]
+        if upstream.http3.is_some() {
+            tracing::warn!("`@upstream(http3)` is ignored, tailcall is built without HTTP/3");
         }
 
-        // Add Http Proxy
-        if let Some(ref proxy) = upstream.proxy {
-            builder = builder.proxy(
-                reqwest::Proxy::http(proxy.url.clone())
-                    .expect("Failed to set proxy in http client"),
-            );
+        Self {
+            pool: Arc::new(Pool::new(upstream, resolver.clone())),
+            http2_only: upstream.http2_only,
+            streams: upstream
+                .http2_max_concurrent_streams
+                .filter(|_| upstream.http2_only)
+                .map(|max| Arc::new(Streams::new(max))),
+            max_decoded_size: upstream.max_decoded_size,
+            enable_telemetry: telemetry.export.is_some(),
+            #[cfg(all(feature = "http3", reqwest_unstable))]
+            http3: upstream
+                .http3
+                .map(|mode| Arc::new(Http3::new(upstream, resolver, mode))),
+            trailers_client: upstream
+                .http2_only
+                .then(|| trailers_client(upstream))
+                .flatten(),
         }
+    }
 
-        let mut client = ClientBuilder::new(builder.build().expect("Failed to build client"));
+    async fn execute_with(
+        &self,
+        client: &ClientWithMiddleware,
+        mut request: reqwest::Request,
+    ) -> Result<Response<Bytes>> {
+        // the buffered bodies are decoded before they are parsed
+        request
+            .headers_mut()
+            .entry(ACCEPT_ENCODING)
+            .or_insert_with(|| content_encoding::ACCEPT_ENCODING.clone());
+
+        let size = UpstreamSize::new(self.enable_telemetry, &request);
+        // the stream is held until the whole body is received
+        let _stream = self.acquire_stream(&request).await;
+        let (response, timing) = self.send(client, request).await?;
+        let status = response.status();
+        let headers = response.headers().to_owned();
+        let body = response.bytes().await?;
+        // the size of the body as it's received, before it's decoded
+        size.record_response(body.len());
+        if let Some(timing) = timing {
+            timing.record_total();
+        }
 
-        if upstream.http_cache > 0 {
-            client = client.with(Cache(HttpCache {
-                mode: CacheMode::Default,
-                manager: HttpCacheManager::new(upstream.http_cache),
-                options: HttpCacheOptions::default(),
-            }))
+        // the large bodies are decoded on a blocking thread, so that their
+        // decompression doesn't hold up the other requests of the worker
+        let max_decoded_size = self.max_decoded_size;
+        if body.len() > content_encoding::BLOCKING_DECODE_SIZE
+            && headers.contains_key(CONTENT_ENCODING)
+        {
+            return tokio::task::spawn_blocking(move || {
+                Response::decoded(status, headers, body, max_decoded_size)
+            })
+            .await?;
         }
-        Self {
-            client: client.build(),
-            http2_only: upstream.http2_only,
-            enable_telemetry: telemetry.export.is_some(),
+
+        Response::decoded(status, headers, body, max_decoded_size)
+    }
+
+    async fn stream_with(
+        &self,
+        client: &ClientWithMiddleware,
+        request: reqwest::Request,
+    ) -> Result<Response<BodyStream>> {
+        let size = UpstreamSize::new(self.enable_telemetry, &request);
+        let stream = self.acquire_stream(&request).await;
+        // the streams are consumed after the span of the request is over
+        let (response, _) = self.send(client, request).await?;
+        let response = Response::from_reqwest_stream(response);
+        // the stream of the connection is released once the body is dropped
+        let body = size
+            .record_stream(response.body)
+            .inspect(move |_| {
+                let _ = &stream;
+            })
+            .boxed();
+
+        Ok(Response { body, ..response })
+    }
+
+    /// Waits for a free stream of the connection to the origin of the request,
+    /// if the streams are limited.
+    async fn acquire_stream(&self, request: &reqwest::Request) -> Option<OwnedSemaphorePermit> {
+        match &self.streams {
+            Some(streams) => Some(streams.acquire(request.url()).await),
+            None => None,
         }
     }
-}
 
-#[async_trait::async_trait]
-impl HttpIO for NativeHttp {
     #[allow(clippy::blocks_in_conditions)]
     // because of the issue with tracing and clippy - https://github.com/rust-lang/rust-clippy/issues/12281
     #[tracing::instrument(
@@ -140,10 +289,19 @@ impl HttpIO for NativeHttp {
             otel.kind = ?SpanKind::Client,
             url.full = %request.url(),
             http.request.method = %request.method(),
-            network.protocol.version = ?request.version()
+            network.protocol.version = ?request.version(),
+            http.timing.dns_ms = tracing::field::Empty,
+            http.timing.connect_ms = tracing::field::Empty,
+            http.timing.tls_ms = tracing::field::Empty,
+            http.timing.ttfb_ms = tracing::field::Empty,
+            http.timing.total_ms = tracing::field::Empty,
         )
     )]
-    async fn execute(&self, mut request: reqwest::Request) -> Result<Response<Bytes>> {
+    async fn send(
+        &self,
+        client: &ClientWithMiddleware,
+        mut request: reqwest::Request,
+    ) -> Result<(reqwest::Response, Option<Timing>)> {
         if self.http2_only {
             *request.version_mut() = reqwest::Version::HTTP_2;
         }
@@ -166,8 +324,21 @@ impl HttpIO for NativeHttp {
             request.version()
         );
         tracing::debug!("request: {:?}", request);
-        let response = self.client.execute(request).await;
+        let timing = Timing::start();
+        #[cfg(all(feature = "http3", reqwest_unstable))]
+        let execute = async {
+            match &self.http3 {
+                Some(http3) => http3.execute(client, request).await,
+                None => client.execute(request).await,
+            }
+        };
+        #[cfg(not(all(feature = "http3", reqwest_unstable)))]
+        let execute = client.execute(request);
+        let response = Timing::scope(timing.as_ref(), execute).await;
         tracing::debug!("response: {:?}", response);
+        if let Some(timing) = &timing {
+            timing.record(timing::TTFB);
+        }
 
         req_counter.update(&response);
 
@@ -176,12 +347,204 @@ impl HttpIO for NativeHttp {
             tracing::Span::current().set_attribute(status_code.key, status_code.value);
         }
 
-        Ok(Response::from_reqwest(
-            response?
-                .error_for_status()
-                .map_err(|err| err.without_url())?,
-        )
-        .await?)
+        let response = response?
+            .error_for_status()
+            .map_err(|err| err.without_url())?;
+
+        Ok((response, timing))
+    }
+}
+
+/// Builds the HTTP/2 client of the streams whose trailers are read. The
+/// upstreams whose certificates aren't verified, or that are called through a
+/// proxy, are streamed by reqwest without their trailers.
+fn trailers_client(upstream: &Upstream) -> Option<TrailersClient> {
+    if upstream.accept_invalid_certs() || upstream.proxy.is_some() {
+        return None;
+    }
+
+    let tls = match HttpsConnectorBuilder::new().with_native_roots() {
+        Ok(tls) => tls,
+        Err(err) => {
+            tracing::warn!("The trailers of the gRPC streams aren't read: {}", err);
+            return None;
+        }
+    };
+    let mut connector = HttpConnector::new();
+    connector.enforce_http(false);
+    connector.set_connect_timeout(Some(Duration::from_secs(upstream.connect_timeout)));
+    connector.set_keepalive(Some(Duration::from_secs(upstream.tcp_keep_alive)));
+    let connector = tls.https_or_http().enable_http2().wrap_connector(connector);
+
+    let client = hyper::Client::builder()
+        .http2_only(true)
+        .http2_keep_alive_interval(Duration::from_secs(upstream.keep_alive_interval))
+        .http2_keep_alive_timeout(Duration::from_secs(upstream.keep_alive_timeout))
+        .http2_keep_alive_while_idle(upstream.keep_alive_while_idle)
+        .pool_idle_timeout(Duration::from_secs(upstream.pool_idle_timeout))
+        .build(connector);
+
+    Some(client)
+}
+
+fn build_client(
+    upstream: &Upstream,
+    resolver: Arc<CachingResolver>,
+    http_cache: &Option<HttpCacheManager>,
+) -> ClientWithMiddleware {
+    let mut builder = client_builder(upstream, resolver);
+    // the certificates that aren't verified are left to the TLS of reqwest
+    if !upstream.accept_invalid_certs() {
+        builder = builder.use_preconfigured_tls(timing::tls_config(upstream.http2_only));
+    }
+
+    with_middleware(builder, http_cache)
+}
+
+/// Builds the client that doesn't follow the redirects, they're followed by
+/// [NativeHttp] itself, with the policy of the request.
+pub(super) fn client_builder(
+    upstream: &Upstream,
+    resolver: Arc<CachingResolver>,
+) -> reqwest::ClientBuilder {
+    let mut builder = Client::builder()
+        .tcp_keepalive(Some(Duration::from_secs(upstream.tcp_keep_alive)))
+        .timeout(Duration::from_secs(upstream.timeout))
+        .connect_timeout(Duration::from_secs(upstream.connect_timeout))
+        .http2_keep_alive_interval(Some(Duration::from_secs(upstream.keep_alive_interval)))
+        .http2_keep_alive_timeout(Duration::from_secs(upstream.keep_alive_timeout))
+        .http2_keep_alive_while_idle(upstream.keep_alive_while_idle)
+        .pool_idle_timeout(Some(Duration::from_secs(upstream.pool_idle_timeout)))
+        .pool_max_idle_per_host(upstream.pool_max_idle_per_host)
+        .user_agent(upstream.user_agent.clone())
+        .danger_accept_invalid_certs(upstream.accept_invalid_certs())
+        .redirect(Policy::none())
+        .dns_resolver(resolver);
+
+    if let Some(dns_ttl) = upstream.dns_ttl {
+        let ttl = Duration::from_secs(dns_ttl);
+        // idle connections are pinned to the addresses resolved when they were
+        // opened, so they shouldn't outlive the resolved addresses
+        builder = builder.pool_idle_timeout(Some(
+            ttl.min(Duration::from_secs(upstream.pool_idle_timeout)),
+        ));
+        if ttl.is_zero() {
+            builder = builder.pool_max_idle_per_host(0);
+        }
+    }
+
+    // Add Http2 Prior Knowledge
+    if upstream.http2_only {
+        builder = builder.http2_prior_knowledge();
+    }
+
+    if upstream.http2_adaptive_window {
+        builder = builder.http2_adaptive_window(true);
+    }
+
+    // Add Http Proxy
+    if let Some(ref proxy) = upstream.proxy {
+        builder = builder.proxy(
+            reqwest::Proxy::http(proxy.url.clone()).expect("Failed to set proxy in http client"),
+        );
+    }
+
+    builder
+}
+
+pub(super) fn http_cache(upstream: &Upstream) -> Option<HttpCacheManager> {
+    (upstream.http_cache > 0).then(|| HttpCacheManager::new(upstream.http_cache))
+}
+
+pub(super) fn with_middleware(
+    builder: reqwest::ClientBuilder,
+    http_cache: &Option<HttpCacheManager>,
+) -> ClientWithMiddleware {
+    let mut client = ClientBuilder::new(builder.build().expect("Failed to build client"));
+
+    if let Some(manager) = http_cache {
+        client = client.with(Cache(HttpCache {
+            mode: CacheMode::Default,
+            manager: manager.clone(),
+            options: HttpCacheOptions::default(),
+        }))
+    }
+    client.build()
+}
+
+#[async_trait::async_trait]
+impl HttpIO for NativeHttp {
+    async fn execute(&self, request: reqwest::Request) -> Result<Response<Bytes>> {
+        let client = self.pool.client(request.url()).await;
+        RedirectPolicy::default()
+            .follow(request, |request| self.execute_with(&client, request))
+            .await
+    }
+
+    async fn execute_without_redirects(
+        &self,
+        request: reqwest::Request,
+    ) -> Result<Response<Bytes>> {
+        let client = self.pool.client(request.url()).await;
+        self.execute_with(&client, request).await
+    }
+
+    async fn execute_stream(&self, request: reqwest::Request) -> Result<Response<BodyStream>> {
+        let client = self.pool.client(request.url()).await;
+        RedirectPolicy::default()
+            .follow(request, |request| self.stream_with(&client, request))
+            .await
+    }
+
+    async fn execute_stream_with_trailers(
+        &self,
+        request: reqwest::Request,
+    ) -> Result<(Response<BodyStream>, Trailers)> {
+        let Some(client) = &self.trailers_client else {
+            let response = self.execute_stream(request).await?;
+            return Ok((response, future::ready(None).boxed()));
+        };
+
+        let size = UpstreamSize::new(self.enable_telemetry, &request);
+        let stream = self.acquire_stream(&request).await;
+        tracing::info!(
+            "{} {} {:?}",
+            request.method(),
+            request.url(),
+            reqwest::Version::HTTP_2
+        );
+
+        // the bodies of the gRPC requests are buffered
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map_or_else(Bytes::new, Bytes::copy_from_slice);
+        let mut upstream_request = hyper::Request::new(hyper::Body::from(body));
+        *upstream_request.method_mut() = request.method().clone();
+        *upstream_request.uri_mut() = request.url().as_str().parse()?;
+        *upstream_request.version_mut() = reqwest::Version::HTTP_2;
+        *upstream_request.headers_mut() = request.headers().clone();
+
+        let response = client.request(upstream_request).await?;
+        let status = response.status();
+        if status.is_client_error() || status.is_server_error() {
+            return Err(Error::HTTP {
+                status: status.as_u16(),
+                message: format!("HTTP status {}", status),
+            }
+            .into());
+        }
+
+        let (response, trailers) = Response::from_hyper_stream(response);
+        // the stream of the connection is released once the body is dropped
+        let body = size
+            .record_stream(response.body)
+            .inspect(move |_| {
+                let _ = &stream;
+            })
+            .boxed();
+
+        Ok((Response { body, ..response }, trailers))
     }
 }
 
@@ -234,6 +597,117 @@ mod tests {
         header_serv.assert_hits(2);
     }
 
+    #[tokio::test]
+    async fn test_native_http_decodes_compressed_response() {
+        use std::io::Write;
+
+        let server = start_mock_server();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"Hello").unwrap();
+        let body = encoder.finish().unwrap();
+
+        let mock = server.mock(|when, then| {
+            when.method(httpmock::Method::GET)
+                .path("/test")
+                .header("accept-encoding", "gzip, deflate, br, zstd");
+            then.status(200)
+                .header("content-encoding", "gzip")
+                .body(body);
+        });
+
+        let native_http = NativeHttp::init(&Default::default(), &Default::default());
+        let request_url = format!("http://localhost:{}/test", server.port());
+        let response = make_request(&request_url, &native_http).await;
+
+        assert_eq!(response.body, Bytes::from("Hello"));
+        assert!(response.headers.get("content-encoding").is_none());
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_native_http_decodes_large_response() {
+        use std::io::Write;
+
+        let server = start_mock_server();
+        // stored without compression, to be larger than the bodies decoded in place
+        let text = "Hello".repeat(4 * 1024);
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::none());
+        encoder.write_all(text.as_bytes()).unwrap();
+        let body = encoder.finish().unwrap();
+        assert!(body.len() > content_encoding::BLOCKING_DECODE_SIZE);
+
+        server.mock(|when, then| {
+            when.method(httpmock::Method::GET).path("/test");
+            then.status(200)
+                .header("content-encoding", "gzip")
+                .body(body);
+        });
+
+        let native_http = NativeHttp::init(&Default::default(), &Default::default());
+        let request_url = format!("http://localhost:{}/test", server.port());
+        let response = make_request(&request_url, &native_http).await;
+
+        assert_eq!(response.body, Bytes::from(text));
+        assert!(response.headers.get("content-encoding").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_native_http_limits_decoded_size() {
+        use std::io::Write;
+
+        let server = start_mock_server();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&[b' '; 1024]).unwrap();
+        let body = encoder.finish().unwrap();
+
+        server.mock(|when, then| {
+            when.method(httpmock::Method::GET).path("/test");
+            then.status(200)
+                .header("content-encoding", "gzip")
+                .body(body);
+        });
+
+        let upstream = Upstream { max_decoded_size: 1000, ..Default::default() };
+        let native_http = NativeHttp::init(&upstream, &Default::default());
+        let request_url = format!("http://localhost:{}/test", server.port());
+        let request = reqwest::Request::new(Method::GET, request_url.parse().unwrap());
+        let error = native_http.execute(request).await.unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "The `gzip` encoded response body exceeds the limit of 1000 bytes once decoded"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_native_http_redirects() {
+        let server = start_mock_server();
+
+        server.mock(|when, then| {
+            when.method(httpmock::Method::GET).path("/old");
+            then.status(302).header("location", "/new");
+        });
+
+        server.mock(|when, then| {
+            when.method(httpmock::Method::GET).path("/new");
+            then.status(200).body("Hello");
+        });
+
+        let native_http = NativeHttp::init(&Default::default(), &Default::default());
+        let request_url = format!("http://localhost:{}/old", server.port());
+
+        // the same client follows the redirects unless they're left to the caller
+        let response = make_request(&request_url, &native_http).await;
+        assert_eq!(response.body, Bytes::from("Hello"));
+
+        let request = reqwest::Request::new(Method::GET, request_url.parse().unwrap());
+        let response = native_http
+            .execute_without_redirects(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status.as_u16(), 302);
+    }
+
     #[tokio::test]
     async fn test_native_http_get_request_with_cache() {
         let server = start_mock_server();
@@ -282,4 +756,133 @@ mod tests {
         let resp = make_request(&url1, &native_http).await;
         assert_eq!(resp.headers.get("x-cache-lookup").unwrap(), "MISS");
     }
+
+    #[derive(Default)]
+    struct Connections {
+        opened: std::sync::atomic::AtomicUsize,
+        in_flight: std::sync::atomic::AtomicUsize,
+        max_in_flight: std::sync::atomic::AtomicUsize,
+    }
+
+    /// Starts an HTTP/2 server that counts the connections it accepts and the
+    /// requests that are in flight at once.
+    fn start_http2_server() -> (std::net::SocketAddr, Arc<Connections>) {
+        use std::convert::Infallible;
+        use std::sync::atomic::Ordering;
+
+        use hyper::service::{make_service_fn, service_fn};
+
+        let connections = Arc::new(Connections::default());
+        let counts = connections.clone();
+        let make_svc = make_service_fn(move |_conn| {
+            counts.opened.fetch_add(1, Ordering::SeqCst);
+            let counts = counts.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |_req| {
+                    let counts = counts.clone();
+                    async move {
+                        let in_flight = counts.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        counts.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(20)).await;
+                        counts.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                        Ok::<_, Infallible>(hyper::Response::new(hyper::Body::from("Hello")))
+                    }
+                }))
+            }
+        });
+
+        let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into())
+            .http2_only(true)
+            .serve(make_svc);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+
+        (addr, connections)
+    }
+
+    async fn execute_concurrently(native_http: &NativeHttp, addr: std::net::SocketAddr) {
+        let url = format!("http://{addr}/");
+        let requests = (0..100).map(|_| make_request(&url, native_http));
+        let responses = futures_util::future::join_all(requests).await;
+
+        assert!(responses.iter().all(|response| response.body == "Hello"));
+    }
+
+    #[tokio::test]
+    async fn test_http2_connection_reuse() {
+        use std::sync::atomic::Ordering;
+
+        let (addr, connections) = start_http2_server();
+        let upstream = Upstream::default().http2_only(true);
+        let native_http = NativeHttp::init(&upstream, &Default::default());
+
+        execute_concurrently(&native_http, addr).await;
+
+        // the calls are multiplexed over the same connection
+        assert_eq!(connections.opened.load(Ordering::SeqCst), 1);
+        assert!(connections.max_in_flight.load(Ordering::SeqCst) > 10);
+    }
+
+    #[tokio::test]
+    async fn test_http2_max_concurrent_streams() {
+        use std::sync::atomic::Ordering;
+
+        let (addr, connections) = start_http2_server();
+        let upstream = Upstream::default()
+            .http2_only(true)
+            .http2_max_concurrent_streams(Some(10));
+        let native_http = NativeHttp::init(&upstream, &Default::default());
+
+        execute_concurrently(&native_http, addr).await;
+
+        assert_eq!(connections.opened.load(Ordering::SeqCst), 1);
+        assert!(connections.max_in_flight.load(Ordering::SeqCst) <= 10);
+    }
+
+    #[tokio::test]
+    async fn test_http2_trailers() {
+        use std::convert::Infallible;
+
+        use hyper::service::{make_service_fn, service_fn};
+
+        // the server sends the status of the call in the trailers, like gRPC
+        let make_svc = make_service_fn(|_conn| async {
+            Ok::<_, Infallible>(service_fn(|_req| async {
+                let (mut sender, body) = hyper::Body::channel();
+                tokio::spawn(async move {
+                    sender.send_data(Bytes::from("Hello")).await.unwrap();
+                    let mut trailers = http::HeaderMap::new();
+                    trailers.insert("grpc-status", http::HeaderValue::from_static("0"));
+                    sender.send_trailers(trailers).await.unwrap();
+                });
+
+                Ok::<_, Infallible>(hyper::Response::new(body))
+            }))
+        });
+        let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into())
+            .http2_only(true)
+            .serve(make_svc);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+
+        let upstream = Upstream::default().http2_only(true);
+        let native_http = NativeHttp::init(&upstream, &Default::default());
+        let request =
+            reqwest::Request::new(Method::POST, format!("http://{addr}/").parse().unwrap());
+        let (response, trailers) = native_http
+            .execute_stream_with_trailers(request)
+            .await
+            .unwrap();
+
+        let body = response
+            .body
+            .map(|chunk| chunk.unwrap())
+            .collect::<Vec<_>>()
+            .await
+            .concat();
+        assert_eq!(body, b"Hello");
+        let trailers = trailers.await.unwrap();
+        assert_eq!(trailers.get("grpc-status").unwrap(), "0");
+    }
 }
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use http::header::ALT_SVC;
+use http::HeaderMap;
+use reqwest_middleware::ClientWithMiddleware;
+
+use super::dns::CachingResolver;
+use super::http::{client_builder, http_cache, with_middleware};
+use crate::core::blueprint::Upstream;
+use crate::core::config;
+
+/// The time an `Alt-Svc` advertisement is valid for without a `ma`, see the
+/// [RFC](https://www.rfc-editor.org/rfc/rfc7838#section-3.1).
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// HTTP/3 transport of the upstream requests, configured with
+/// `@upstream(http3)`. The requests to the upstreams that support it are sent
+/// over QUIC, the others over the regular client. A request whose QUIC
+/// connection can't be opened is resent over the regular client when its body
+/// can be replayed, and so is an idempotent request whose connection fails
+/// while it's in flight, eg. because the network changed. The
+/// upstreams discovered with `Alt-Svc` then aren't sent HTTP/3 requests until
+/// they advertise it again.
+pub struct Http3 {
+    client: ClientWithMiddleware,
+    mode: config::Http3,
+    /// The origins that advertised HTTP/3 and the time the advertisements
+    /// expire at.
+    origins: Mutex<HashMap<String, Instant>>,
+}
+
+impl Http3 {
+    pub fn new(upstream: &Upstream, resolver: Arc<CachingResolver>, mode: config::Http3) -> Self {
+        let builder = client_builder(upstream, resolver).http3_prior_knowledge();
+
+        Self {
+            client: with_middleware(builder, &http_cache(upstream)),
+            mode,
+            origins: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn is_supported(&self, origin: &str) -> bool {
+        match self.mode {
+            config::Http3::Force => true,
+            config::Http3::AltSvc => self
+                .origins
+                .lock()
+                .unwrap()
+                .get(origin)
+                .is_some_and(|expires| *expires > Instant::now()),
+        }
+    }
+
+    /// Records the HTTP/3 support the upstream advertised with the `Alt-Svc`
+    /// header of its response.
+    fn record(&self, origin: &str, url: &reqwest::Url, headers: &HeaderMap) {
+        let Some(alt_svc) = headers.get(ALT_SVC).and_then(|value| value.to_str().ok()) else {
+            return;
+        };
+
+        let mut origins = self.origins.lock().unwrap();
+        match max_age(alt_svc, url.port_or_known_default()) {
+            Some(max_age) => origins.insert(origin.to_string(), Instant::now() + max_age),
+            None => origins.remove(origin),
+        };
+    }
+
+    pub async fn execute(
+        &self,
+        fallback: &ClientWithMiddleware,
+        mut request: reqwest::Request,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        let url = request.url().clone();
+        let origin = url.origin().ascii_serialization();
+
+        if self.is_supported(&origin) {
+            let retry = request.try_clone();
+            let idempotent = is_idempotent(request.method());
+            *request.version_mut() = reqwest::Version::HTTP_3;
+
+            // a request that failed in flight could have been processed already
+            match (self.client.execute(request).await, retry) {
+                (Err(reqwest_middleware::Error::Reqwest(err)), Some(retry))
+                    if err.is_connect() || (idempotent && err.is_request()) =>
+                {
+                    tracing::warn!("Falling back to HTTP/2 for {}: {}", origin, err);
+                    self.origins.lock().unwrap().remove(&origin);
+                    request = retry;
+                }
+                (response, _) => return response,
+            }
+        }
+
+        let response = fallback.execute(request).await;
+        if let (config::Http3::AltSvc, Ok(response)) = (self.mode, &response) {
+            self.record(&origin, &url, response.headers());
+        }
+
+        response
+    }
+}
+
+/// Whether the request can be resent without changing the state of the
+/// upstream once more, see the
+/// [RFC](https://www.rfc-editor.org/rfc/rfc9110#section-9.2.2).
+fn is_idempotent(method: &reqwest::Method) -> bool {
+    use reqwest::Method;
+
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE
+    )
+}
+
+/// The time HTTP/3 is advertised for by the `Alt-Svc` header, on the origin
+/// of the request. `None` if it isn't advertised or the advertisements are
+/// cleared.
+fn max_age(alt_svc: &str, port: Option<u16>) -> Option<Duration> {
+    alt_svc.split(',').find_map(|alternative| {
+        let mut params = alternative.split(';').map(str::trim);
+        let (protocol, authority) = params.next()?.split_once('=')?;
+        let authority = authority.trim_matches('"');
+        // the QUIC connection is opened to the host and the port of the url
+        let same_origin = authority
+            .strip_prefix(':')
+            .is_some_and(|alt_port| alt_port.parse().ok() == port);
+        if protocol != "h3" || !same_origin {
+            return None;
+        }
+
+        let max_age = params
+            .filter_map(|param| param.strip_prefix("ma="))
+            .find_map(|max_age| max_age.parse().ok())
+            .map_or(DEFAULT_MAX_AGE, Duration::from_secs);
+        Some(max_age)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_idempotent() {
+        assert!(is_idempotent(&reqwest::Method::GET));
+        assert!(is_idempotent(&reqwest::Method::PUT));
+        assert!(!is_idempotent(&reqwest::Method::POST));
+        assert!(!is_idempotent(&reqwest::Method::PATCH));
+    }
+
+    #[test]
+    fn test_max_age() {
+        let port = Some(443);
+
+        assert_eq!(
+            max_age(r#"h3=":443"; ma=3600"#, port),
+            Some(Duration::from_secs(3600))
+        );
+        assert_eq!(
+            max_age(r#"h3-29=":443", h3=":443""#, port),
+            Some(DEFAULT_MAX_AGE)
+        );
+        // an alternative on another host or port, or for HTTP/2 only
+        assert_eq!(max_age(r#"h3=":8443"; ma=3600"#, port), None);
+        assert_eq!(max_age(r#"h3="alt.foo.com:443""#, port), None);
+        assert_eq!(max_age(r#"h2="alt.foo.com:443""#, port), None);
+        assert_eq!(max_age("clear", port), None);
+    }
+}
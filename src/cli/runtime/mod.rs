@@ -1,19 +1,30 @@
+mod dns;
 mod env;
 mod file;
 mod http;
+#[cfg(all(feature = "http3", reqwest_unstable))]
+mod http3;
+mod timing;
+mod websocket;
 
 use std::fs;
 use std::hash::Hash;
 use std::sync::Arc;
+use std::time::Duration;
 
+use async_graphql_value::ConstValue;
 pub use http::NativeHttp;
 use inquire::{Confirm, Select};
 
 use crate::core::blueprint::Blueprint;
-use crate::core::cache::InMemoryCache;
+use crate::core::cache::{CollisionCheck, InMemoryCache, MaxEntries, PressureAwareCache};
+use crate::core::ir::model::IoId;
 use crate::core::runtime::TargetRuntime;
 use crate::core::worker::{Command, Event};
-use crate::core::{blueprint, EnvIO, FileIO, HttpIO, WorkerIO};
+use crate::core::{blueprint, EntityCache, EnvIO, FileIO, HttpIO, WebSocketIO, WorkerIO};
+
+/// How often the entries of the cache are counted for its memory pressure.
+const PRESSURE_INTERVAL: Duration = Duration::from_millis(100);
 
 // Provides access to env in native rust environment
 fn init_env() -> Arc<dyn EnvIO> {
@@ -65,10 +76,32 @@ fn init_http2_only(blueprint: &Blueprint) -> Arc<dyn HttpIO> {
     ))
 }
 
-fn init_in_memory_cache<K: Hash + Eq, V: Clone>() -> InMemoryCache<K, V> {
+// Provides access to the upstream subscriptions in native rust environment
+fn init_websocket(blueprint: &Blueprint) -> Option<Arc<dyn WebSocketIO>> {
+    Some(Arc::new(websocket::NativeWebSocket::init(
+        &blueprint.upstream,
+    )))
+}
+
+fn init_in_memory_cache<K: Hash + Eq + Clone, V: Clone>() -> InMemoryCache<K, V> {
     InMemoryCache::default()
 }
 
+// Provides the cache of the `@cache` fields, that stops storing the new entries
+// while it holds the configured maximum of them
+fn init_cache(blueprint: &Blueprint) -> Arc<EntityCache> {
+    let cache = Arc::new(CollisionCheck::new(
+        init_in_memory_cache::<IoId, ConstValue>(),
+    ));
+    match blueprint.server.cache_max_entries {
+        Some(max) => {
+            let pressure = MaxEntries::new(cache.clone(), max, PRESSURE_INTERVAL);
+            Arc::new(PressureAwareCache::new(cache, Arc::new(pressure)))
+        }
+        None => cache,
+    }
+}
+
 pub fn init(blueprint: &Blueprint) -> TargetRuntime {
     #[cfg(not(feature = "js"))]
     tracing::warn!("JS capabilities are disabled in this build");
@@ -78,10 +111,14 @@ pub fn init(blueprint: &Blueprint) -> TargetRuntime {
         http2_only: init_http2_only(blueprint),
         env: init_env(),
         file: init_file(),
-        cache: Arc::new(init_in_memory_cache()),
+        cache: init_cache(blueprint),
         extensions: Arc::new(vec![]),
+        body_transformers: Arc::new(vec![]),
+        eval_middlewares: Arc::new(vec![]),
         cmd_worker: init_http_worker_io(blueprint.server.script.clone()),
         worker: init_resolver_worker_io(blueprint.server.script.clone()),
+        rng: Default::default(),
+        websocket: init_websocket(blueprint),
     }
 }
 
@@ -131,3 +168,45 @@ pub async fn create_directory(folder_path: &str) -> anyhow::Result<()> {
 pub fn select_prompt<T: std::fmt::Display>(message: &str, options: Vec<T>) -> anyhow::Result<T> {
     Ok(Select::new(message, options).prompt()?)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU64;
+
+    use tailcall_valid::Validator;
+
+    use super::*;
+    use crate::core::config::{Config, ConfigModule};
+
+    #[tokio::test]
+    async fn test_cache_max_entries() {
+        let sdl = r#"
+            schema @server(cacheMaxEntries: 1) @upstream {
+                query: Query
+            }
+
+            type Query {
+                hello: String @expr(body: "world")
+            }
+        "#;
+        let config = Config::from_sdl(sdl).to_result().unwrap();
+        let blueprint = Blueprint::try_from(&ConfigModule::from(config)).unwrap();
+        let cache = init(&blueprint).cache;
+        let ttl = NonZeroU64::new(60000).unwrap();
+        let value = ConstValue::from(1);
+
+        cache.set(IoId::new(1), value.clone(), ttl).await.unwrap();
+        tokio::time::sleep(PRESSURE_INTERVAL).await;
+
+        // the new entries aren't stored while the cache is full
+        cache.set(IoId::new(2), value.clone(), ttl).await.unwrap();
+        assert_eq!(cache.get(&IoId::new(1)).await.unwrap(), Some(value.clone()));
+        assert_eq!(cache.get(&IoId::new(2)).await.unwrap(), None);
+
+        // they're stored again once the stored entries are evicted
+        cache.evict(&IoId::new(1)).await.unwrap();
+        tokio::time::sleep(PRESSURE_INTERVAL).await;
+        cache.set(IoId::new(2), value.clone(), ttl).await.unwrap();
+        assert_eq!(cache.get(&IoId::new(2)).await.unwrap(), Some(value));
+    }
+}
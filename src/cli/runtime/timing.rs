@@ -0,0 +1,193 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use reqwest_rustls::client::{
+    ClientSessionMemoryCache, ClientSessionStore, Resumption, Tls12ClientSessionValue,
+    Tls13ClientSessionValue,
+};
+use reqwest_rustls::{
+    ClientConfig, KeyLog, NamedGroup, OwnedTrustAnchor, RootCertStore, ServerName,
+};
+use tracing::{Level, Span};
+
+pub const DNS: &str = "http.timing.dns_ms";
+pub const CONNECT: &str = "http.timing.connect_ms";
+pub const TLS: &str = "http.timing.tls_ms";
+pub const TTFB: &str = "http.timing.ttfb_ms";
+pub const TOTAL: &str = "http.timing.total_ms";
+
+tokio::task_local! {
+    static TIMING: Timing;
+}
+
+/// Records the phases of an upstream request as attributes of its span, in
+/// milliseconds. Each phase is recorded as the time since the previous one
+/// ended, so the lookup, the connection and the TLS handshake aren't a part of
+/// the time to the first byte, while the total is the time since the request
+/// is sent. The timings are recorded only when the debug logs are enabled, to
+/// avoid their overhead in production.
+#[derive(Clone)]
+pub struct Timing {
+    span: Span,
+    started: Instant,
+    phase: Arc<Mutex<Instant>>,
+}
+
+impl Timing {
+    pub fn start() -> Option<Self> {
+        tracing::enabled!(Level::DEBUG).then(|| {
+            let started = Instant::now();
+            Self {
+                span: Span::current(),
+                started,
+                phase: Arc::new(Mutex::new(started)),
+            }
+        })
+    }
+
+    /// Sends the request with its timing in scope, so the phases of the
+    /// connection that it opens are recorded in its span.
+    pub async fn scope<F: Future>(timing: Option<&Self>, future: F) -> F::Output {
+        match timing {
+            Some(timing) => TIMING.scope(timing.clone(), future).await,
+            None => future.await,
+        }
+    }
+
+    /// Records the time since the previous phase ended and starts the next one.
+    pub fn record(&self, field: &str) {
+        let mut phase = self.phase.lock().unwrap();
+        self.span.record(field, millis(phase.elapsed()));
+        *phase = Instant::now();
+    }
+
+    pub fn record_total(&self) {
+        self.span.record(TOTAL, millis(self.started.elapsed()));
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Records a phase of the connection of the request that's sent by the current
+/// task. The connections that are opened in the background, after the request
+/// got another connection from the pool, aren't recorded.
+pub fn record(field: &str) {
+    let _ = TIMING.try_with(|timing| timing.record(field));
+}
+
+/// Builds the TLS configuration of reqwest with the hooks that record the
+/// connections and the handshakes, it's otherwise the configuration reqwest
+/// builds itself. The plain HTTP connections have no such hook, so their
+/// connection is a part of the time to the first byte.
+pub fn tls_config(http2_only: bool) -> ClientConfig {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(
+            anchor.subject,
+            anchor.spki,
+            anchor.name_constraints,
+        )
+    }));
+
+    let mut config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    config.alpn_protocols = if http2_only {
+        vec![b"h2".to_vec()]
+    } else {
+        vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+    };
+    config.resumption = Resumption::store(Arc::new(Sessions(ClientSessionMemoryCache::new(256))));
+    config.key_log = Arc::new(Handshakes);
+
+    config
+}
+
+/// The sessions of the servers, which are looked up as the handshake starts,
+/// once the connection is open.
+struct Sessions(ClientSessionMemoryCache);
+
+impl ClientSessionStore for Sessions {
+    fn set_kx_hint(&self, server_name: &ServerName, group: NamedGroup) {
+        self.0.set_kx_hint(server_name, group)
+    }
+
+    fn kx_hint(&self, server_name: &ServerName) -> Option<NamedGroup> {
+        record(CONNECT);
+        self.0.kx_hint(server_name)
+    }
+
+    fn set_tls12_session(&self, server_name: &ServerName, value: Tls12ClientSessionValue) {
+        self.0.set_tls12_session(server_name, value)
+    }
+
+    fn tls12_session(&self, server_name: &ServerName) -> Option<Tls12ClientSessionValue> {
+        self.0.tls12_session(server_name)
+    }
+
+    fn remove_tls12_session(&self, server_name: &ServerName) {
+        self.0.remove_tls12_session(server_name)
+    }
+
+    fn insert_tls13_ticket(&self, server_name: &ServerName, value: Tls13ClientSessionValue) {
+        self.0.insert_tls13_ticket(server_name, value)
+    }
+
+    fn take_tls13_ticket(&self, server_name: &ServerName) -> Option<Tls13ClientSessionValue> {
+        self.0.take_tls13_ticket(server_name)
+    }
+}
+
+/// The key log, whose secrets are derived once the handshake is done. The
+/// secrets themselves are dropped.
+struct Handshakes;
+
+impl KeyLog for Handshakes {
+    fn log(&self, label: &str, _: &[u8], _: &[u8]) {
+        // the application secret of TLS 1.3, or the master secret of TLS 1.2
+        if matches!(label, "CLIENT_TRAFFIC_SECRET_0" | "CLIENT_RANDOM") {
+            record(TLS);
+        }
+    }
+
+    fn will_log(&self, label: &str) -> bool {
+        label == "CLIENT_TRAFFIC_SECRET_0"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::filter::LevelFilter;
+    use tracing_subscriber::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_start() {
+        let subscriber = tracing_subscriber::registry().with(LevelFilter::INFO);
+        tracing::subscriber::with_default(subscriber, || assert!(Timing::start().is_none()));
+
+        let subscriber = tracing_subscriber::registry().with(LevelFilter::DEBUG);
+        tracing::subscriber::with_default(subscriber, || assert!(Timing::start().is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_record_in_scope() {
+        let subscriber = tracing_subscriber::registry().with(LevelFilter::DEBUG);
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let timing = Timing::start().unwrap();
+        let started = *timing.phase.lock().unwrap();
+        std::thread::sleep(Duration::from_millis(1));
+
+        // outside of the scope of the request
+        record(DNS);
+        assert_eq!(*timing.phase.lock().unwrap(), started);
+
+        Timing::scope(Some(&timing), async { record(DNS) }).await;
+        assert!(*timing.phase.lock().unwrap() > started);
+    }
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{header, HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::core::blueprint::Upstream;
+use crate::core::{WebSocketConnection, WebSocketIO};
+
+pub struct NativeWebSocket {
+    connect_timeout: Duration,
+}
+
+impl NativeWebSocket {
+    pub fn init(upstream: &Upstream) -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(upstream.connect_timeout),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl WebSocketIO for NativeWebSocket {
+    async fn connect(
+        &self,
+        request: reqwest::Request,
+        protocol: &str,
+    ) -> Result<Box<dyn WebSocketConnection>> {
+        let mut url = request.url().clone();
+        let scheme = match url.scheme() {
+            "https" => "wss",
+            "http" => "ws",
+            scheme => scheme,
+        }
+        .to_string();
+        url.set_scheme(&scheme)
+            .map_err(|_| anyhow!("Invalid WebSocket url: {}", request.url()))?;
+
+        let mut ws_request = url.as_str().into_client_request()?;
+        let headers = ws_request.headers_mut();
+        for (name, value) in request.headers() {
+            // the body of the request is sent over the connection instead
+            if name == reqwest::header::CONTENT_TYPE || name == reqwest::header::CONTENT_LENGTH {
+                continue;
+            }
+            headers.insert(
+                HeaderName::from_bytes(name.as_str().as_bytes())?,
+                HeaderValue::from_bytes(value.as_bytes())?,
+            );
+        }
+        headers.insert(
+            header::SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_str(protocol)?,
+        );
+
+        tracing::info!("WebSocket {}", url);
+        let connect = tokio_tungstenite::connect_async(ws_request);
+        let (stream, _) = tokio::time::timeout(self.connect_timeout, connect)
+            .await
+            .map_err(|_| anyhow!("Timed out connecting to {}", url))??;
+
+        Ok(Box::new(NativeConnection(stream)))
+    }
+}
+
+struct NativeConnection(WebSocketStream<MaybeTlsStream<TcpStream>>);
+
+#[async_trait::async_trait]
+impl WebSocketConnection for NativeConnection {
+    async fn send(&mut self, message: String) -> Result<()> {
+        self.0.send(Message::Text(message)).await?;
+        Ok(())
+    }
+
+    async fn recv(&mut self) -> Result<Option<String>> {
+        while let Some(message) = self.0.next().await {
+            match message? {
+                Message::Text(text) => return Ok(Some(text)),
+                Message::Binary(bytes) => return Ok(Some(String::from_utf8(bytes)?)),
+                Message::Close(_) => return Ok(None),
+                // pings are answered by the connection itself
+                Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {}
+            }
+        }
+
+        Err(anyhow!("The connection was closed without a close frame"))
+    }
+}
@@ -1,9 +1,11 @@
+use std::future::Future;
 use std::sync::Arc;
 
 use hyper::service::{make_service_fn, service_fn};
 use tokio::sync::oneshot;
 
 use super::server_config::ServerConfig;
+use super::websocket;
 use crate::core::async_graphql_hyper::{GraphQLBatchRequest, GraphQLRequest};
 use crate::core::http::handle_request;
 use crate::core::Errata;
@@ -11,13 +13,21 @@ use crate::core::Errata;
 pub async fn start_http_1(
     sc: Arc<ServerConfig>,
     server_up_sender: Option<oneshot::Sender<()>>,
+    shutdown: impl Future<Output = ()>,
 ) -> anyhow::Result<()> {
     let addr = sc.addr();
     let make_svc_single_req = make_service_fn(|_conn| {
         let state = Arc::clone(&sc);
         async move {
             Ok::<_, anyhow::Error>(service_fn(move |req| {
-                handle_request::<GraphQLRequest>(req, state.app_ctx.clone())
+                let app_ctx = state.app_ctx.clone();
+                async move {
+                    if websocket::is_upgrade(&req, &app_ctx) {
+                        websocket::handle_upgrade(req, app_ctx)
+                    } else {
+                        handle_request::<GraphQLRequest>(req, app_ctx).await
+                    }
+                }
             }))
         }
     });
@@ -26,7 +36,14 @@ pub async fn start_http_1(
         let state = Arc::clone(&sc);
         async move {
             Ok::<_, anyhow::Error>(service_fn(move |req| {
-                handle_request::<GraphQLBatchRequest>(req, state.app_ctx.clone())
+                let app_ctx = state.app_ctx.clone();
+                async move {
+                    if websocket::is_upgrade(&req, &app_ctx) {
+                        websocket::handle_upgrade(req, app_ctx)
+                    } else {
+                        handle_request::<GraphQLBatchRequest>(req, app_ctx).await
+                    }
+                }
             }))
         }
     });
@@ -43,9 +60,15 @@ pub async fn start_http_1(
 
     let server: std::prelude::v1::Result<(), hyper::Error> =
         if sc.blueprint.server.enable_batch_requests {
-            builder.serve(make_svc_batch_req).await
+            builder
+                .serve(make_svc_batch_req)
+                .with_graceful_shutdown(shutdown)
+                .await
         } else {
-            builder.serve(make_svc_single_req).await
+            builder
+                .serve(make_svc_single_req)
+                .with_graceful_shutdown(shutdown)
+                .await
         };
 
     let result = server.map_err(Errata::from);
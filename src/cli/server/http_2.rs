@@ -1,4 +1,5 @@
 #![allow(clippy::too_many_arguments)]
+use std::future::Future;
 use std::sync::Arc;
 
 use hyper::server::conn::AddrIncoming;
@@ -19,6 +20,7 @@ pub async fn start_http_2(
     cert: Vec<CertificateDer<'static>>,
     key: PrivateKey,
     server_up_sender: Option<oneshot::Sender<()>>,
+    shutdown: impl Future<Output = ()>,
 ) -> anyhow::Result<()> {
     let addr = sc.addr();
     let incoming = AddrIncoming::bind(&addr)?;
@@ -56,9 +58,15 @@ pub async fn start_http_2(
 
     let server: std::prelude::v1::Result<(), hyper::Error> =
         if sc.blueprint.server.enable_batch_requests {
-            builder.serve(make_svc_batch_req).await
+            builder
+                .serve(make_svc_batch_req)
+                .with_graceful_shutdown(shutdown)
+                .await
         } else {
-            builder.serve(make_svc_single_req).await
+            builder
+                .serve(make_svc_single_req)
+                .with_graceful_shutdown(shutdown)
+                .await
         };
 
     let result = server.map_err(Errata::from);
@@ -1,25 +1,50 @@
 use std::ops::Deref;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::Result;
+use futures_util::future::{self, FutureExt};
 use tokio::sync::oneshot::{self};
 
 use super::http_1::start_http_1;
 use super::http_2::start_http_2;
 use super::server_config::ServerConfig;
 use crate::cli::telemetry::init_opentelemetry;
-use crate::core::blueprint::{Blueprint, Http};
+use crate::core::app_context::AppContext;
+use crate::core::blueprint::{Blueprint, BlueprintCache, Http};
+use crate::core::cache::Snapshot;
 use crate::core::config::ConfigModule;
 use crate::core::Errata;
 
 pub struct Server {
     config_module: ConfigModule,
     server_up_sender: Option<oneshot::Sender<()>>,
+    blueprint_cache: Option<PathBuf>,
+    insecure: bool,
 }
 
 impl Server {
     pub fn new(config_module: ConfigModule) -> Self {
-        Self { config_module, server_up_sender: None }
+        Self {
+            config_module,
+            server_up_sender: None,
+            blueprint_cache: None,
+            insecure: false,
+        }
+    }
+
+    /// Enables the insecure mode for local development, in which the upstream
+    /// options that are unsafe in production are honored.
+    pub fn insecure(mut self, insecure: bool) -> Self {
+        self.insecure = insecure;
+        self
+    }
+
+    /// Caches the compiled blueprint in the file to skip the compilation on
+    /// the next start.
+    pub fn blueprint_cache(mut self, path: impl Into<PathBuf>) -> Self {
+        self.blueprint_cache = Some(path.into());
+        self
     }
 
     pub fn server_up_receiver(&mut self) -> oneshot::Receiver<()> {
@@ -32,18 +57,89 @@ impl Server {
 
     /// Starts the server in the current Runtime
     pub async fn start(self) -> Result<()> {
-        let blueprint = Blueprint::try_from(&self.config_module).map_err(Errata::from)?;
+        let mut blueprint = self.blueprint()?;
+        blueprint.upstream.insecure = self.insecure;
+        if blueprint.upstream.danger_accept_invalid_certs {
+            if self.insecure {
+                tracing::warn!(
+                    "Certificates of the upstreams aren't verified, `dangerAcceptInvalidCerts` must not be used in production"
+                );
+            } else {
+                tracing::warn!(
+                    "`dangerAcceptInvalidCerts` is ignored, start tailcall with `--insecure` to enable it"
+                );
+            }
+        }
         let endpoints = self.config_module.extensions().endpoint_set.clone();
         let server_config = Arc::new(ServerConfig::new(blueprint.clone(), endpoints).await?);
 
-        init_opentelemetry(blueprint.telemetry.clone(), &server_config.app_ctx.runtime)?;
+        init_opentelemetry(blueprint.telemetry.clone(), &server_config.app_ctx)?;
+
+        // the server only shuts down gracefully when the cache is persisted,
+        // otherwise the process is left to be killed by the signal
+        let snapshot = blueprint.server.cache_snapshot.clone();
+        let shutdown = match snapshot.as_ref() {
+            Some(path) => {
+                load_cache_snapshot(path, &server_config.app_ctx).await;
+                shutdown_signal().boxed()
+            }
+            None => future::pending().boxed(),
+        };
 
-        match blueprint.server.http.clone() {
+        let result = match blueprint.server.http.clone() {
             Http::HTTP2 { cert, key } => {
-                start_http_2(server_config, cert, key, self.server_up_sender).await
+                start_http_2(
+                    server_config.clone(),
+                    cert,
+                    key,
+                    self.server_up_sender,
+                    shutdown,
+                )
+                .await
             }
-            Http::HTTP1 => start_http_1(server_config, self.server_up_sender).await,
+            Http::HTTP1 => {
+                start_http_1(server_config.clone(), self.server_up_sender, shutdown).await
+            }
+        };
+
+        if let Some(path) = snapshot.as_ref() {
+            save_cache_snapshot(path, &server_config.app_ctx);
+        }
+
+        result
+    }
+
+    /// Compiles the blueprint of the config, or loads it from the cache if it
+    /// was compiled for the same config before. Stale caches are replaced.
+    fn blueprint(&self) -> Result<Blueprint> {
+        let Some(path) = self.blueprint_cache.as_ref() else {
+            return Ok(Blueprint::try_from(&self.config_module).map_err(Errata::from)?);
+        };
+
+        let cache = std::fs::read(path)
+            .ok()
+            .and_then(|bytes| BlueprintCache::from_bytes(&bytes, &self.config_module));
+        if let Some(cache) = cache {
+            tracing::info!("Loaded the compiled blueprint from {}", path.display());
+            return Ok(cache
+                .into_blueprint(&self.config_module)
+                .map_err(Errata::from)?);
+        }
+
+        let blueprint = Blueprint::try_from(&self.config_module).map_err(Errata::from)?;
+        let written = BlueprintCache::new(&self.config_module, &blueprint)
+            .to_bytes()
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(std::fs::write(path, bytes)?));
+        if let Err(err) = written {
+            tracing::warn!(
+                "Failed to write the blueprint cache to {}: {}",
+                path.display(),
+                err
+            );
         }
+
+        Ok(blueprint)
     }
 
     /// Starts the server in its own multithreaded Runtime
@@ -59,3 +155,72 @@ impl Server {
         result
     }
 }
+
+/// Resolves once the process is asked to stop, ie. on Ctrl+C or, on unix, on
+/// SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            tracing::warn!("Failed to listen for Ctrl+C: {}", err);
+            future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                tracing::warn!("Failed to listen for SIGTERM: {}", err);
+                future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = future::pending::<()>();
+
+    future::select(Box::pin(ctrl_c), Box::pin(terminate)).await;
+    tracing::info!("Shutting down the server");
+}
+
+/// Warms the cache with the entries of the snapshot that are still valid. A
+/// missing or unreadable snapshot leaves the cache empty.
+async fn load_cache_snapshot(path: &Path, app_ctx: &AppContext) {
+    let Some(snapshot) = std::fs::read(path)
+        .ok()
+        .and_then(|bytes| Snapshot::from_bytes(&bytes))
+    else {
+        return;
+    };
+
+    let salt = app_ctx.blueprint.server.cache_salt;
+    match snapshot.restore(app_ctx.runtime.cache.as_ref(), salt).await {
+        Ok(count) => tracing::info!("Restored {} cached entries from {}", count, path.display()),
+        Err(err) => tracing::warn!(
+            "Failed to restore the cache from {}: {}",
+            path.display(),
+            err
+        ),
+    }
+}
+
+/// Writes the entries of the cache that haven't expired to the snapshot.
+fn save_cache_snapshot(path: &Path, app_ctx: &AppContext) {
+    let salt = app_ctx.blueprint.server.cache_salt;
+    let Some(snapshot) = Snapshot::take(app_ctx.runtime.cache.as_ref(), salt) else {
+        tracing::warn!("The cache store can't be persisted");
+        return;
+    };
+
+    let written = snapshot
+        .to_bytes()
+        .map_err(anyhow::Error::from)
+        .and_then(|bytes| Ok(std::fs::write(path, bytes)?));
+    match written {
+        Ok(()) => tracing::info!("Persisted the cache to {}", path.display()),
+        Err(err) => tracing::warn!("Failed to persist the cache to {}: {}", path.display(), err),
+    }
+}
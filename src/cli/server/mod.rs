@@ -3,6 +3,7 @@ pub mod http_2;
 pub mod http_server;
 pub mod playground;
 pub mod server_config;
+mod websocket;
 
 pub use http_server::Server;
 
@@ -0,0 +1,105 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_graphql::http::{WebSocket, WebSocketProtocols, WsMessage};
+use async_graphql::Data;
+use futures_util::{future, SinkExt, StreamExt};
+use hyper::header::{
+    CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_PROTOCOL, UPGRADE,
+};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::{CloseFrame, Role};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+use crate::core::app_context::AppContext;
+use crate::core::http::create_request_context;
+
+/// Checks if the request asks to upgrade the connection to the graphql
+/// endpoint to a WebSocket.
+pub fn is_upgrade(req: &Request<Body>, app_ctx: &AppContext) -> bool {
+    req.method() == Method::GET
+        && req.uri().path() == app_ctx.blueprint.server.routes.graphql()
+        && req
+            .headers()
+            .get(UPGRADE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.eq_ignore_ascii_case("websocket"))
+}
+
+/// Upgrades the connection and serves the subscriptions of the client over it,
+/// with either of the `graphql-ws` protocols.
+pub fn handle_upgrade(
+    mut req: Request<Body>,
+    app_ctx: Arc<AppContext>,
+) -> anyhow::Result<Response<Body>> {
+    let protocol = req
+        .headers()
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            value
+                .split(',')
+                .find_map(|protocol| WebSocketProtocols::from_str(protocol.trim()).ok())
+        });
+    let (Some(protocol), Some(key)) = (protocol, req.headers().get(SEC_WEBSOCKET_KEY)) else {
+        return Ok(Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .body(Body::empty())?);
+    };
+    let accept = derive_accept_key(key.as_bytes());
+
+    let req_ctx = Arc::new(create_request_context(&req, app_ctx.as_ref()));
+    let upgrade = hyper::upgrade::on(&mut req);
+
+    tokio::spawn(async move {
+        // the upstream subscriptions are closed along with the connection
+        let _cancel_on_drop = req_ctx.cancellation.drop_guard();
+        let upgraded = match upgrade.await {
+            Ok(upgraded) => upgraded,
+            Err(err) => {
+                tracing::error!("Failed to upgrade the connection: {}", err);
+                return;
+            }
+        };
+        let stream = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+        let (mut sink, stream) = stream.split();
+
+        let input = stream
+            .take_while(|message| future::ready(message.is_ok()))
+            .filter_map(|message| {
+                future::ready(match message {
+                    Ok(Message::Text(text)) => Some(text.into_bytes()),
+                    Ok(Message::Binary(bytes)) => Some(bytes),
+                    _ => None,
+                })
+            });
+
+        let mut data = Data::default();
+        data.insert(req_ctx.clone());
+        let mut output =
+            WebSocket::new(app_ctx.schema.clone(), input, protocol).connection_data(data);
+
+        while let Some(message) = output.next().await {
+            let message = match message {
+                WsMessage::Text(text) => Message::Text(text),
+                WsMessage::Close(code, reason) => Message::Close(Some(CloseFrame {
+                    code: code.into(),
+                    reason: reason.into(),
+                })),
+            };
+            if sink.send(message).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header(CONNECTION, "upgrade")
+        .header(UPGRADE, "websocket")
+        .header(SEC_WEBSOCKET_ACCEPT, accept)
+        .header(SEC_WEBSOCKET_PROTOCOL, protocol.sec_websocket_protocol())
+        .body(Body::empty())?)
+}
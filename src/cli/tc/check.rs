@@ -1,6 +1,6 @@
 use anyhow::Result;
 
-use super::helpers::{display_schema, log_endpoint_set};
+use super::helpers::{display_inventory, display_schema, log_endpoint_set};
 use crate::cli::fmt::Fmt;
 use crate::core::blueprint::Blueprint;
 use crate::core::config::reader::ConfigReader;
@@ -11,11 +11,12 @@ pub(super) struct CheckParams {
     pub(super) file_paths: Vec<String>,
     pub(super) n_plus_one_queries: bool,
     pub(super) schema: bool,
+    pub(super) inventory: bool,
     pub(super) runtime: TargetRuntime,
 }
 
 pub(super) async fn check_command(params: CheckParams, config_reader: &ConfigReader) -> Result<()> {
-    let CheckParams { file_paths, n_plus_one_queries, schema, runtime } = params;
+    let CheckParams { file_paths, n_plus_one_queries, schema, inventory, runtime } = params;
 
     let config_module = (config_reader.read_all(&file_paths)).await?;
     log_endpoint_set(&config_module.extensions().endpoint_set);
@@ -35,6 +36,9 @@ pub(super) async fn check_command(params: CheckParams, config_reader: &ConfigRea
             if schema {
                 display_schema(&blueprint);
             }
+            if inventory {
+                display_inventory(&blueprint)?;
+            }
 
             Ok(())
         }
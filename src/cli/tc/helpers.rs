@@ -1,7 +1,8 @@
+use anyhow::Result;
 use lazy_static::lazy_static;
 
 use crate::cli::fmt::Fmt;
-use crate::core::blueprint::Blueprint;
+use crate::core::blueprint::{Blueprint, Inventory};
 use crate::core::http::API_URL_PREFIX;
 use crate::core::print_schema;
 use crate::core::rest::{EndpointSet, Unchecked};
@@ -40,3 +41,9 @@ pub(super) fn display_schema(blueprint: &Blueprint) {
     let sdl = blueprint.to_schema();
     Fmt::display(format!("{}\n", print_schema::print_schema(sdl)));
 }
+
+pub(super) fn display_inventory(blueprint: &Blueprint) -> Result<()> {
+    let inventory = Inventory::from(blueprint);
+    Fmt::display(format!("{}\n", serde_json::to_string_pretty(&inventory)?));
+    Ok(())
+}
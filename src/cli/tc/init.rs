@@ -85,7 +85,7 @@ async fn confirm_and_write_yml(
 fn main_config() -> Config {
     let field = Field {
         type_of: Type::from("String".to_owned()).into_required(),
-        resolvers: Resolver::Expr(Expr { body: "Hello, World!".into() }).into(),
+        resolvers: Resolver::Expr(Expr { body: "Hello, World!".into(), runtime_env: false }).into(),
         ..Default::default()
     };
 
@@ -41,16 +41,22 @@ fn get_runtime_and_config_reader(verify_ssl: bool) -> (TargetRuntime, ConfigRead
 
 async fn run_command(cli: Cli) -> Result<()> {
     match cli.command {
-        Command::Start { file_paths, verify_ssl } => {
+        Command::Start { file_paths, verify_ssl, blueprint_cache, insecure } => {
             let (runtime, config_reader) = get_runtime_and_config_reader(verify_ssl);
             validate_rc_config_files(runtime, &file_paths).await;
-            start::start_command(file_paths, &config_reader).await?;
+            start::start_command(file_paths, blueprint_cache, insecure, &config_reader).await?;
         }
-        Command::Check { file_paths, n_plus_one_queries, schema, verify_ssl } => {
+        Command::Check {
+            file_paths,
+            n_plus_one_queries,
+            schema,
+            inventory,
+            verify_ssl,
+        } => {
             let (runtime, config_reader) = get_runtime_and_config_reader(verify_ssl);
             validate_rc_config_files(runtime.clone(), &file_paths).await;
             check::check_command(
-                check::CheckParams { file_paths, n_plus_one_queries, schema, runtime },
+                check::CheckParams { file_paths, n_plus_one_queries, schema, inventory, runtime },
                 &config_reader,
             )
             .await?;
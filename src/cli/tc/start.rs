@@ -7,12 +7,17 @@ use crate::core::config::reader::ConfigReader;
 
 pub(super) async fn start_command(
     file_paths: Vec<String>,
+    blueprint_cache: Option<String>,
+    insecure: bool,
     config_reader: &ConfigReader,
 ) -> Result<()> {
     let config_module = config_reader.read_all(&file_paths).await?;
     log_endpoint_set(&config_module.extensions().endpoint_set);
     Fmt::log_n_plus_one(false, config_module.config());
-    let server = Server::new(config_module);
+    let mut server = Server::new(config_module).insecure(insecure);
+    if let Some(path) = blueprint_cache {
+        server = server.blueprint_cache(path);
+    }
     server.fork_start().await?;
     Ok(())
 }
@@ -24,8 +24,8 @@ use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::{Layer, Registry};
 
 use super::metrics::init_metrics;
+use crate::core::app_context::AppContext;
 use crate::core::blueprint::telemetry::{OtlpExporter, Telemetry, TelemetryExporter};
-use crate::core::runtime::TargetRuntime;
 use crate::core::tracing::{
     default_tracing, default_tracing_tailcall, get_log_level, tailcall_filter_target,
 };
@@ -192,7 +192,7 @@ fn set_tracing_subscriber(subscriber: impl Subscriber + Send + Sync) {
     let _ = tracing::subscriber::set_global_default(subscriber);
 }
 
-pub fn init_opentelemetry(config: Telemetry, runtime: &TargetRuntime) -> anyhow::Result<()> {
+pub fn init_opentelemetry(config: Telemetry, app_ctx: &AppContext) -> anyhow::Result<()> {
     if let Some(export) = &config.export {
         global::set_error_handler(|error| {
             if !matches!(
@@ -236,7 +236,7 @@ pub fn init_opentelemetry(config: Telemetry, runtime: &TargetRuntime) -> anyhow:
                 get_log_level().unwrap_or(tracing::Level::INFO),
             ));
 
-        init_metrics(runtime)?;
+        init_metrics(app_ctx)?;
 
         set_tracing_subscriber(subscriber);
     } else {
@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_graphql::dynamic::{self, DynamicRequest};
 use async_graphql_value::ConstValue;
@@ -11,9 +12,12 @@ use crate::core::data_loader::{DataLoader, DedupeResult};
 use crate::core::graphql::GraphqlDataLoader;
 use crate::core::grpc;
 use crate::core::grpc::data_loader::GrpcDataLoader;
-use crate::core::http::{DataLoaderRequest, HttpDataLoader};
+use crate::core::http::{
+    AdaptiveTimeouts, AuthTokenHttp, BulkheadHttp, Bulkheads, DataLoaderRequest, HealthCheck,
+    HttpDataLoader,
+};
 use crate::core::ir::model::{DataLoaderId, IoId, IO, IR};
-use crate::core::ir::Error;
+use crate::core::ir::{DedupedValue, Error};
 use crate::core::jit::{OPHash, OperationPlan};
 use crate::core::rest::{Checked, EndpointSet};
 use crate::core::runtime::TargetRuntime;
@@ -26,10 +30,14 @@ pub struct AppContext {
     pub gql_data_loaders: Arc<Vec<DataLoader<DataLoaderRequest, GraphqlDataLoader>>>,
     pub grpc_data_loaders: Arc<Vec<DataLoader<grpc::DataLoaderRequest, GrpcDataLoader>>>,
     pub endpoints: EndpointSet<Checked>,
-    pub dedupe_handler: Arc<DedupeResult<IoId, ConstValue, Error>>,
+    pub dedupe_handler: Arc<DedupeResult<IoId, DedupedValue, Error>>,
+    pub cache_refreshes: Arc<DedupeResult<IoId, ConstValue, Error>>,
     pub dedupe_operation_handler: DedupeResult<OperationId, AnyResponse<Vec<u8>>, Error>,
     pub operation_plans: DashMap<OPHash, OperationPlan<async_graphql_value::Value>>,
     pub const_execution_cache: DashMap<OPHash, AnyResponse<Vec<u8>>>,
+    pub health_check: Option<HealthCheck>,
+    pub adaptive_timeouts: Option<Arc<AdaptiveTimeouts>>,
+    pub bulkheads: Option<Arc<Bulkheads>>,
 }
 
 impl AppContext {
@@ -38,6 +46,20 @@ impl AppContext {
         runtime: TargetRuntime,
         endpoints: EndpointSet<Checked>,
     ) -> Self {
+        let adaptive_timeouts = blueprint.upstream.adaptive_timeout.clone().map(|config| {
+            let default = Duration::from_secs(blueprint.upstream.timeout);
+            Arc::new(AdaptiveTimeouts::new(config, default))
+        });
+        let bulkheads = blueprint
+            .upstream
+            .bulkhead
+            .clone()
+            .map(|config| Arc::new(Bulkheads::new(config)));
+
+        // the data loaders are created with the clients that are limited by the
+        // bulkheads and send the tokens
+        let runtime = BulkheadHttp::wrap(runtime, bulkheads.clone(), adaptive_timeouts.clone());
+        let runtime = AuthTokenHttp::wrap(runtime, &blueprint.upstream.token_providers);
         let mut http_data_loaders = vec![];
         let mut gql_data_loaders = vec![];
         let mut grpc_data_loaders = vec![];
@@ -75,10 +97,20 @@ impl AppContext {
                                     result
                                 }
 
-                                IO::GraphQL { req_template, field_name, batch, dedupe, .. } => {
+                                IO::GraphQL {
+                                    req_template,
+                                    field_name,
+                                    batch,
+                                    batch_style,
+                                    batch_name,
+                                    dedupe,
+                                    error_policy,
+                                    ..
+                                } => {
                                     let dedupe = *dedupe;
                                     let graphql_data_loader =
                                         GraphqlDataLoader::new(runtime.clone(), *batch)
+                                            .style(*batch_style, batch_name.clone())
                                             .into_data_loader(
                                                 upstream_batch.clone().unwrap_or_default(),
                                             );
@@ -87,8 +119,11 @@ impl AppContext {
                                         req_template: req_template.clone(),
                                         field_name: field_name.clone(),
                                         batch: *batch,
+                                        batch_style: *batch_style,
+                                        batch_name: batch_name.clone(),
                                         dl_id: Some(DataLoaderId::new(gql_data_loaders.len())),
                                         dedupe,
+                                        error_policy: *error_policy,
                                     }));
 
                                     gql_data_loaders.push(graphql_data_loader);
@@ -130,6 +165,12 @@ impl AppContext {
             }
         }
 
+        let health_check = blueprint
+            .server
+            .health_check
+            .as_ref()
+            .map(|health_check| HealthCheck::new(&blueprint, health_check));
+
         let schema = blueprint
             .to_schema_with(SchemaModifiers::default().extensions(runtime.extensions.clone()));
 
@@ -143,9 +184,13 @@ impl AppContext {
             endpoints,
 
             dedupe_handler: Arc::new(DedupeResult::new(false)),
+            cache_refreshes: Arc::new(DedupeResult::new(false)),
             dedupe_operation_handler: DedupeResult::new(false),
             operation_plans: DashMap::new(),
             const_execution_cache: DashMap::default(),
+            health_check,
+            adaptive_timeouts,
+            bulkheads,
         }
     }
 
@@ -153,3 +198,159 @@ impl AppContext {
         self.schema.execute(request).await
     }
 }
+
+#[cfg(test)]
+pub mod test {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use async_graphql_value::ConstValue;
+    use futures_util::StreamExt;
+    use hyper::body::Bytes;
+    use tailcall_valid::Validator;
+
+    use super::AppContext;
+    use crate::core::blueprint::Blueprint;
+    use crate::core::config::{Config, ConfigModule};
+    use crate::core::http::{RequestContext, Response};
+    use crate::core::jit::{ConstValueExecutor, Request};
+    use crate::core::rest::EndpointSet;
+    use crate::core::runtime::TargetRuntime;
+    use crate::core::HttpIO;
+
+    type Respond = dyn Fn(&reqwest::Request) -> anyhow::Result<Response<Bytes>> + Send + Sync;
+
+    /// Upstream of the tests that responds with the function it's created
+    /// with, and records the urls of the requests that it receives.
+    pub struct TestHttp {
+        respond: Box<Respond>,
+        delay: Duration,
+        urls: Mutex<Vec<String>>,
+    }
+
+    impl TestHttp {
+        pub fn new(
+            respond: impl Fn(&reqwest::Request) -> anyhow::Result<Response<Bytes>>
+                + Send
+                + Sync
+                + 'static,
+        ) -> Arc<Self> {
+            Self::delayed(Duration::ZERO, respond)
+        }
+
+        /// Responds once the delay is over, eg. to keep the requests in
+        /// flight while they're deduplicated or cancelled.
+        pub fn delayed(
+            delay: Duration,
+            respond: impl Fn(&reqwest::Request) -> anyhow::Result<Response<Bytes>>
+                + Send
+                + Sync
+                + 'static,
+        ) -> Arc<Self> {
+            Arc::new(Self { respond: Box::new(respond), delay, urls: Mutex::new(vec![]) })
+        }
+
+        /// Responds with the body returned by the function and the `200`
+        /// status.
+        pub fn body<B: Into<Bytes>>(
+            respond: impl Fn(&reqwest::Request) -> anyhow::Result<B> + Send + Sync + 'static,
+        ) -> Arc<Self> {
+            Self::new(move |request| {
+                Ok(Response { body: respond(request)?.into(), ..Default::default() })
+            })
+        }
+
+        pub fn urls(&self) -> Vec<String> {
+            self.urls.lock().unwrap().clone()
+        }
+
+        pub fn paths(&self) -> Vec<String> {
+            self.urls()
+                .iter()
+                .map(|url| url::Url::parse(url).unwrap().path().to_string())
+                .collect()
+        }
+
+        pub fn hits(&self) -> usize {
+            self.urls.lock().unwrap().len()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HttpIO for TestHttp {
+        async fn execute(&self, request: reqwest::Request) -> anyhow::Result<Response<Bytes>> {
+            self.urls.lock().unwrap().push(request.url().to_string());
+            if !self.delay.is_zero() {
+                tokio::time::sleep(self.delay).await;
+            }
+            (self.respond)(&request)
+        }
+    }
+
+    /// App of the tests that is built from the SDL, its queries are executed
+    /// with the JIT executor and their responses are returned as JSON.
+    pub struct TestApp {
+        pub app_ctx: Arc<AppContext>,
+    }
+
+    impl TestApp {
+        /// The upstreams of the app are served by `http`.
+        pub fn new(sdl: &str, http: Arc<dyn HttpIO>) -> Self {
+            let mut runtime = crate::core::runtime::test::init(None);
+            runtime.http = http;
+
+            Self::with_runtime(sdl, runtime)
+        }
+
+        pub fn with_runtime(sdl: &str, runtime: TargetRuntime) -> Self {
+            let config = Config::from_sdl(sdl).to_result().unwrap();
+            let blueprint = Blueprint::try_from(&ConfigModule::from(config)).unwrap();
+
+            Self::from_blueprint(blueprint, runtime)
+        }
+
+        pub fn from_blueprint(blueprint: Blueprint, runtime: TargetRuntime) -> Self {
+            let app_ctx = AppContext::new(blueprint, runtime, EndpointSet::default());
+
+            Self { app_ctx: Arc::new(app_ctx) }
+        }
+
+        /// Executes the query in a request context of its own.
+        pub async fn execute(&self, query: &str) -> serde_json::Value {
+            self.execute_request(Request::new(query)).await
+        }
+
+        pub async fn execute_request(&self, request: Request<ConstValue>) -> serde_json::Value {
+            let req_ctx = RequestContext::from(self.app_ctx.as_ref());
+
+            self.execute_with(&req_ctx, request).await
+        }
+
+        /// Executes the request in the given context, eg. of an authenticated
+        /// client.
+        pub async fn execute_with(
+            &self,
+            req_ctx: &RequestContext,
+            request: Request<ConstValue>,
+        ) -> serde_json::Value {
+            let executor = ConstValueExecutor::try_new(&request, &self.app_ctx).unwrap();
+            let response = executor.execute(&self.app_ctx, req_ctx, request).await;
+
+            serde_json::from_slice(response.body.as_ref()).unwrap()
+        }
+
+        /// Executes the query with the incremental delivery of `@stream`,
+        /// returning the payloads in the order they are sent.
+        pub async fn execute_stream(&self, query: &str) -> Vec<serde_json::Value> {
+            let req_ctx = Arc::new(RequestContext::from(self.app_ctx.as_ref()));
+            let request = Request::new(query);
+            let executor = ConstValueExecutor::try_new(&request, &self.app_ctx).unwrap();
+
+            executor
+                .execute_stream(self.app_ctx.clone(), req_ctx, request)
+                .map(|payload| serde_json::from_slice(&payload).unwrap())
+                .collect()
+                .await
+        }
+    }
+}
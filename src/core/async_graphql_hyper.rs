@@ -1,9 +1,12 @@
 use std::any::Any;
+use std::convert::Infallible;
 use std::hash::{Hash, Hasher};
 
 use anyhow::Result;
 use async_graphql::parser::types::{ExecutableDocument, OperationType};
 use async_graphql::{BatchResponse, Executor, Value};
+use futures_util::stream::{self, BoxStream};
+use futures_util::StreamExt;
 use http::header::{HeaderMap, HeaderValue, CACHE_CONTROL, CONTENT_TYPE};
 use http::{Response, StatusCode};
 use hyper::Body;
@@ -11,7 +14,10 @@ use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use tailcall_hasher::TailcallHasher;
 
-use super::jit::{BatchResponse as JITBatchResponse, JITExecutor};
+use super::cancellation::DropGuard;
+use super::http::ResponseEncoding;
+use super::jit::graphql_error::Error as ExtensionError;
+use super::jit::{BatchResponse as JITBatchResponse, IncrementalResponse, JITExecutor};
 
 #[derive(PartialEq, Eq, Clone, Hash, Debug)]
 pub struct OperationId(u64);
@@ -25,6 +31,11 @@ pub trait GraphQLRequestLike: Hash + Send {
 
     async fn execute_with_jit(self, executor: JITExecutor) -> GraphQLArcResponse;
 
+    async fn execute_incremental_with_jit(
+        self,
+        executor: JITExecutor,
+    ) -> GraphQLIncrementalResponse;
+
     fn parse_query(&mut self) -> Option<&ExecutableDocument>;
 
     fn is_query(&mut self) -> bool {
@@ -79,7 +90,16 @@ impl GraphQLRequestLike for GraphQLBatchRequest {
     }
 
     async fn execute_with_jit(self, executor: JITExecutor) -> GraphQLArcResponse {
-        GraphQLArcResponse::new(executor.execute_batch(self.0).await)
+        let encoding = executor.response_encoding();
+        GraphQLArcResponse::new(executor.execute_batch(self.0).await).encoding(encoding)
+    }
+
+    // the operations of a batch are always delivered at once
+    async fn execute_incremental_with_jit(
+        self,
+        executor: JITExecutor,
+    ) -> GraphQLIncrementalResponse {
+        GraphQLIncrementalResponse::Single(self.execute_with_jit(executor).await)
     }
 
     /// Shortcut method to execute the request on the executor.
@@ -119,6 +139,20 @@ impl GraphQLRequestLike for GraphQLRequest {
     async fn execute_with_jit(self, executor: JITExecutor) -> GraphQLArcResponse {
         let response = executor.execute(self.0).await;
         GraphQLArcResponse::new(JITBatchResponse::Single(response))
+            .encoding(executor.response_encoding())
+    }
+
+    async fn execute_incremental_with_jit(
+        self,
+        executor: JITExecutor,
+    ) -> GraphQLIncrementalResponse {
+        match executor.execute_incremental(self.0).await {
+            IncrementalResponse::Single(response) => GraphQLIncrementalResponse::Single(
+                GraphQLArcResponse::new(JITBatchResponse::Single(response))
+                    .encoding(executor.response_encoding()),
+            ),
+            IncrementalResponse::Stream(payloads) => GraphQLIncrementalResponse::Stream(payloads),
+        }
     }
 
     /// Shortcut method to execute the request on the schema.
@@ -214,6 +248,18 @@ impl GraphQLResponse {
         self.build_response(StatusCode::OK, self.default_body()?)
     }
 
+    /// Serializes the response in the format negotiated with the client.
+    pub fn into_encoded_response(self, encoding: ResponseEncoding) -> Result<Response<Body>> {
+        let body = Body::from(encoding.to_vec(&self.0)?);
+        let mut response = self.build_response(StatusCode::OK, body)?;
+        response.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static(encoding.content_type()),
+        );
+
+        Ok(response)
+    }
+
     fn flatten_response(data: &Value) -> &Value {
         match data {
             Value::Object(map) if map.len() == 1 => map.iter().next().unwrap().1,
@@ -221,12 +267,36 @@ impl GraphQLResponse {
         }
     }
 
+    /// Status code of a failed REST response. The errors of the request
+    /// itself, eg. a variable of a wrong type, are located but have no path.
+    /// The status of the first error that maps to one is used for the errors
+    /// of the fields, falling back to an internal server error.
+    fn rest_status(&self) -> StatusCode {
+        let errors = match &self.0 {
+            BatchResponse::Single(res) => res.errors.iter().collect::<Vec<_>>(),
+            BatchResponse::Batch(list) => list.iter().flat_map(|res| &res.errors).collect(),
+        };
+
+        if errors
+            .iter()
+            .any(|err| err.path.is_empty() && !err.locations.is_empty())
+        {
+            return StatusCode::BAD_REQUEST;
+        }
+
+        errors
+            .iter()
+            .filter_map(|err| err.source::<ExtensionError>()?.status)
+            .find_map(|status| StatusCode::from_u16(status).ok())
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
     /// Transforms a plain `GraphQLResponse` into a `Response<Body>`.
     /// Differs as `to_response` by flattening the response's data
     /// `{"data": {"user": {"name": "John"}}}` becomes `{"name": "John"}`.
     pub fn into_rest_response(self) -> Result<Response<hyper::Body>> {
         if !self.0.is_ok() {
-            return self.build_response(StatusCode::INTERNAL_SERVER_ERROR, self.default_body()?);
+            return self.build_response(self.rest_status(), self.default_body()?);
         }
 
         match self.0 {
@@ -342,17 +412,27 @@ impl CacheControl {
 pub struct GraphQLArcResponse {
     response: JITBatchResponse<Vec<u8>>,
     cache_control: Option<CacheControl>,
+    encoding: ResponseEncoding,
 }
 
 impl GraphQLArcResponse {
     pub fn new(response: JITBatchResponse<Vec<u8>>) -> Self {
-        Self { response, cache_control: None }
+        Self {
+            response,
+            cache_control: None,
+            encoding: ResponseEncoding::default(),
+        }
+    }
+
+    /// Format the bodies of the responses were serialized in.
+    pub fn encoding(self, encoding: ResponseEncoding) -> Self {
+        Self { encoding, ..self }
     }
 
     pub fn set_cache_control(self, enable_cache_header: bool, max_age: i32, public: bool) -> Self {
         Self {
-            response: self.response,
             cache_control: enable_cache_header.then_some(CacheControl { max_age, public }),
+            ..self
         }
     }
 }
@@ -361,7 +441,7 @@ impl GraphQLArcResponse {
     fn build_response(&self, status: StatusCode, body: Body) -> Result<Response<Body>> {
         let mut response = Response::builder()
             .status(status)
-            .header(CONTENT_TYPE, APPLICATION_JSON.as_ref())
+            .header(CONTENT_TYPE, self.encoding.content_type())
             .body(body)?;
         if self.response.is_ok() {
             if let Some(cache_control) = self
@@ -382,23 +462,8 @@ impl GraphQLArcResponse {
     fn default_body(&self) -> Result<Body> {
         let str_repr: Vec<u8> = match &self.response {
             JITBatchResponse::Batch(resp) => {
-                // Use iterators and collect for more efficient concatenation
-                let combined = resp
-                    .iter()
-                    .enumerate()
-                    .flat_map(|(i, r)| {
-                        let mut v = if i > 0 {
-                            vec![b',']
-                        } else {
-                            Vec::with_capacity(r.body.as_ref().len())
-                        };
-                        v.extend_from_slice(r.body.as_ref());
-                        v
-                    })
-                    .collect::<Vec<u8>>();
-
-                // Wrap the result in square brackets
-                [b"[", &combined[..], b"]"].concat()
+                let bodies = resp.iter().map(|r| r.body.as_slice()).collect::<Vec<_>>();
+                self.encoding.join(&bodies)
             }
             JITBatchResponse::Single(resp) => resp.body.as_ref().to_owned(),
         };
@@ -410,9 +475,60 @@ impl GraphQLArcResponse {
     }
 }
 
+static MULTIPART_MIXED: Lazy<HeaderValue> =
+    Lazy::new(|| HeaderValue::from_static("multipart/mixed; boundary=\"-\"; deferSpec=20220824"));
+
+/// Response of a request that accepts the incremental delivery of its
+/// `@stream` fields with `multipart/mixed`.
+pub enum GraphQLIncrementalResponse {
+    Single(GraphQLArcResponse),
+    Stream(BoxStream<'static, Vec<u8>>),
+}
+
+impl GraphQLIncrementalResponse {
+    pub fn set_cache_control(self, enable_cache_header: bool, max_age: i32, public: bool) -> Self {
+        match self {
+            Self::Single(response) => {
+                Self::Single(response.set_cache_control(enable_cache_header, max_age, public))
+            }
+            response => response,
+        }
+    }
+
+    /// The payloads of a stream are written as the parts of a
+    /// `multipart/mixed` body. The body holds the guard until the last part is
+    /// written, so that dropping the body when the client disconnects cancels
+    /// the upstream calls that are still in-flight.
+    pub fn into_response(self, cancel_on_drop: DropGuard) -> Result<Response<hyper::Body>> {
+        let payloads = match self {
+            Self::Single(response) => return response.into_response(),
+            Self::Stream(payloads) => payloads,
+        };
+
+        let parts = payloads
+            .map(|payload| {
+                let mut part =
+                    b"\r\n---\r\nContent-Type: application/json; charset=utf-8\r\n\r\n".to_vec();
+                part.extend(payload);
+                Ok::<_, Infallible>(part)
+            })
+            .chain(stream::once(async move {
+                drop(cancel_on_drop);
+                Ok(b"\r\n-----\r\n".to_vec())
+            }));
+
+        Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, MULTIPART_MIXED.as_ref())
+            .body(Body::wrap_stream(parts))?)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use async_graphql::{Name, Response, ServerError, Value};
+    use std::sync::Arc;
+
+    use async_graphql::{Name, PathSegment, Pos, Response, ServerError, Value};
     use http::StatusCode;
     use indexmap::IndexMap;
     use serde_json::json;
@@ -506,6 +622,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_to_rest_response_status() {
+        let status = |error: ServerError| {
+            let mut response: Response = Default::default();
+            response.errors = vec![error];
+            let response = GraphQLResponse(BatchResponse::Single(response));
+            response.into_rest_response().unwrap().status()
+        };
+
+        let mut error = ServerError::new("Not Found", Some(Pos { line: 2, column: 3 }));
+        error.path = vec![PathSegment::Field("user".to_string())];
+        assert_eq!(status(error.clone()), StatusCode::INTERNAL_SERVER_ERROR);
+
+        error.source = Some(Arc::new(ExtensionError::new("Not Found").status(Some(404))));
+        assert_eq!(status(error), StatusCode::NOT_FOUND);
+
+        let error = ServerError::new("Invalid value", Some(Pos { line: 1, column: 8 }));
+        assert_eq!(status(error), StatusCode::BAD_REQUEST);
+    }
+
     #[test]
     fn to_value() {
         assert_eq!(CacheControl { public: true, max_age: 0 }.value(), None);
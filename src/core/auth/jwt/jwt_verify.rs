@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use headers::authorization::Bearer;
 use headers::{Authorization, HeaderMapExt};
 use serde::Deserialize;
@@ -20,6 +22,18 @@ pub enum OneOrMany<T> {
 pub struct JwtClaim {
     pub aud: Option<OneOrMany<String>>,
     pub iss: Option<String>,
+    /// Space separated list of the scopes granted to the token
+    pub scope: Option<String>,
+}
+
+impl JwtClaim {
+    pub fn scopes(&self) -> HashSet<String> {
+        self.scope
+            .iter()
+            .flat_map(|scope| scope.split_whitespace())
+            .map(String::from)
+            .collect()
+    }
 }
 
 pub struct JwtVerifier {
@@ -76,6 +90,17 @@ impl Verify for JwtVerifier {
 
         self.validate_token(&token).await
     }
+
+    async fn scopes(&self, request: &RequestContext) -> HashSet<String> {
+        let Ok(Some(token)) = self.resolve_token(request) else {
+            return HashSet::new();
+        };
+
+        match self.decoder.decode(&token) {
+            Ok(claims) if self.validate_claims(&claims) == Verification::Succeed => claims.scopes(),
+            _ => HashSet::new(),
+        }
+    }
 }
 
 pub fn validate_iss(options: &blueprint::Jwt, claims: &JwtClaim) -> bool {
@@ -316,4 +341,16 @@ pub mod tests {
             assert!(validate_aud(&options, &claims));
         }
     }
+
+    #[test]
+    fn jwt_claim_scopes() {
+        let mut claims = JwtClaim::default();
+        assert!(claims.scopes().is_empty());
+
+        claims.scope = Some("read  admin ".to_owned());
+        assert_eq!(
+            claims.scopes(),
+            HashSet::from_iter(["read".to_owned(), "admin".to_owned()])
+        );
+    }
 }
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use futures_util::join;
 
 use super::basic::BasicVerifier;
@@ -9,6 +11,12 @@ use crate::core::http::RequestContext;
 #[async_trait::async_trait]
 pub(crate) trait Verify {
     async fn verify(&self, req_ctx: &RequestContext) -> Verification;
+
+    /// Returns the scopes granted to the request, providers that can't grant
+    /// scopes return none.
+    async fn scopes(&self, _req_ctx: &RequestContext) -> HashSet<String> {
+        HashSet::new()
+    }
 }
 
 pub enum Verifier {
@@ -53,6 +61,13 @@ impl Verify for Verifier {
             Verifier::Jwt(jwt) => jwt.verify(req_ctx).await,
         }
     }
+
+    async fn scopes(&self, req_ctx: &RequestContext) -> HashSet<String> {
+        match self {
+            Verifier::Basic(basic) => basic.scopes(req_ctx).await,
+            Verifier::Jwt(jwt) => jwt.scopes(req_ctx).await,
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -69,6 +84,16 @@ impl Verify for AuthVerifier {
             }
         }
     }
+
+    async fn scopes(&self, req_ctx: &RequestContext) -> HashSet<String> {
+        match self {
+            AuthVerifier::Single(verifier) => verifier.scopes(req_ctx).await,
+            AuthVerifier::And(left, right) | AuthVerifier::Or(left, right) => {
+                let (left, right) = join!(left.scopes(req_ctx), right.scopes(req_ctx));
+                left.into_iter().chain(right).collect()
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -2,15 +2,16 @@ use std::collections::HashSet;
 use std::fmt::Debug;
 
 use jsonwebtoken::jwk::JwkSet;
+use serde::{Deserialize, Serialize};
 
 use crate::core::config::{ConfigModule, Content};
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Basic {
     pub htpasswd: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Jwt {
     pub issuer: Option<String>,
     pub audiences: HashSet<String>,
@@ -18,7 +19,7 @@ pub struct Jwt {
     pub jwks: JwkSet,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Provider {
     Basic(Basic),
     Jwt(Jwt),
@@ -66,7 +67,7 @@ impl Provider {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Auth {
     Provider(Provider),
     And(Box<Auth>, Box<Auth>),
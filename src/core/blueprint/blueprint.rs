@@ -5,12 +5,14 @@ use async_graphql::dynamic::{Schema, SchemaBuilder};
 use async_graphql::extensions::ApolloTracing;
 use async_graphql::ValidationMode;
 use derive_setters::Setters;
+use serde::{Deserialize, Serialize};
 
 use super::directive::Directive;
 use super::telemetry::Telemetry;
 use super::{GlobalTimeout, Index};
 use crate::core::blueprint::{Server, Upstream};
 use crate::core::ir::model::IR;
+use crate::core::scalar_codec::ScalarCodecs;
 use crate::core::schema_extension::SchemaExtension;
 use crate::core::{scalar, Type};
 
@@ -25,9 +27,10 @@ pub struct Blueprint {
     pub server: Server,
     pub upstream: Upstream,
     pub telemetry: Telemetry,
+    pub scalar_codecs: ScalarCodecs,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Definition {
     Interface(InterfaceTypeDefinition),
     Object(ObjectTypeDefinition),
@@ -62,7 +65,7 @@ impl Definition {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct InterfaceTypeDefinition {
     pub name: String,
     pub fields: Vec<FieldDefinition>,
@@ -71,7 +74,7 @@ pub struct InterfaceTypeDefinition {
     pub directives: Vec<Directive>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ObjectTypeDefinition {
     pub name: String,
     pub fields: Vec<FieldDefinition>,
@@ -80,7 +83,7 @@ pub struct ObjectTypeDefinition {
     pub directives: Vec<Directive>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct InputObjectTypeDefinition {
     pub name: String,
     pub fields: Vec<InputFieldDefinition>,
@@ -88,7 +91,7 @@ pub struct InputObjectTypeDefinition {
     pub directives: Vec<Directive>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EnumTypeDefinition {
     pub name: String,
     pub directives: Vec<Directive>,
@@ -96,7 +99,7 @@ pub struct EnumTypeDefinition {
     pub enum_values: Vec<EnumValueDefinition>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EnumValueDefinition {
     pub description: Option<String>,
     pub name: String,
@@ -104,14 +107,15 @@ pub struct EnumValueDefinition {
     pub alias: BTreeSet<String>,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct SchemaDefinition {
     pub query: String,
     pub mutation: Option<String>,
+    pub subscription: Option<String>,
     pub directives: Vec<Directive>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct InputFieldDefinition {
     pub name: String,
     pub of_type: Type,
@@ -119,7 +123,7 @@ pub struct InputFieldDefinition {
     pub description: Option<String>,
 }
 
-#[derive(Clone, Debug, Setters, Default)]
+#[derive(Clone, Debug, Setters, Default, Serialize, Deserialize)]
 pub struct FieldDefinition {
     pub name: String,
     pub args: Vec<InputFieldDefinition>,
@@ -140,7 +144,7 @@ impl FieldDefinition {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScalarTypeDefinition {
     pub name: String,
     pub directives: Vec<Directive>,
@@ -148,7 +152,7 @@ pub struct ScalarTypeDefinition {
     pub scalar: scalar::Scalar,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UnionTypeDefinition {
     pub name: String,
     pub directives: Vec<Directive>,
@@ -182,6 +186,10 @@ impl Blueprint {
         self.schema.mutation.clone()
     }
 
+    pub fn subscription(&self) -> Option<String> {
+        self.schema.subscription.clone()
+    }
+
     fn drop_resolvers(mut self) -> Self {
         for def in self.definitions.iter_mut() {
             if let Definition::Object(def) = def {
@@ -0,0 +1,194 @@
+use std::hash::{Hash, Hasher};
+
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use tailcall_hasher::TailcallHasher;
+use tailcall_valid::{ValidationError, Validator};
+
+use super::telemetry::{to_opentelemetry, Telemetry};
+use super::{
+    apply_batching, Blueprint, BlueprintError, Definition, SchemaDefinition, Server, Upstream,
+};
+use crate::core::config::ConfigModule;
+
+/// Tags the caches with the layout of the compiled resolvers they were written
+/// with. The hash of the sources of the blueprint types is computed by the
+/// build script, so the caches of a build with different types are considered
+/// stale without having to bump the version by hand.
+const VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    "+",
+    env!("BLUEPRINT_SCHEMA_HASH")
+);
+
+/// Starts the files of the caches, followed by the version of their format.
+/// The format is bumped when the encoding of the caches changes, so that the
+/// files of other formats are rejected before they're decoded.
+const MAGIC: &[u8] = b"TCBP";
+const FORMAT: u8 = 1;
+
+/// Compiled schema of a config, that can be stored and loaded on the next
+/// start instead of compiling the config again. Only the definitions and the
+/// schema are cached, they hold the resolvers of the fields that are the
+/// expensive part of the compilation. The rest of the blueprint is cheap to
+/// build and is always rebuilt from the config.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlueprintCache {
+    version: String,
+    hash: u64,
+    definitions: Vec<Definition>,
+    schema: SchemaDefinition,
+}
+
+impl BlueprintCache {
+    pub fn new(config_module: &ConfigModule, blueprint: &Blueprint) -> Self {
+        Self {
+            version: VERSION.to_string(),
+            hash: hash(config_module),
+            definitions: blueprint.definitions.clone(),
+            schema: blueprint.schema.clone(),
+        }
+    }
+
+    /// Encodes the cache as MessagePack, the fields are encoded with their
+    /// names as the definitions skip the ones that aren't set.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        let mut bytes = [MAGIC, &[FORMAT]].concat();
+        rmp_serde::encode::write_named(&mut bytes, self)?;
+
+        Ok(bytes)
+    }
+
+    /// Loads the cache, returns `None` if the cache can't be read or it was
+    /// written in a different format, by a different build or for a different
+    /// config.
+    pub fn from_bytes(bytes: &[u8], config_module: &ConfigModule) -> Option<Self> {
+        let bytes = bytes.strip_prefix(MAGIC)?.strip_prefix(&[FORMAT])?;
+        let cache: Self = rmp_serde::from_slice(bytes).ok()?;
+
+        (cache.version == VERSION && cache.hash == hash(config_module)).then_some(cache)
+    }
+
+    /// Builds the blueprint of the config with the cached definitions.
+    pub fn into_blueprint(
+        self,
+        config_module: &ConfigModule,
+    ) -> Result<Blueprint, ValidationError<BlueprintError>> {
+        let server = Server::try_from(config_module.clone())?;
+        let upstream = Upstream::try_from(config_module)?;
+        let telemetry = to_opentelemetry()
+            .try_fold(config_module, Telemetry::default())
+            .to_result()?;
+
+        let blueprint = Blueprint::default()
+            .definitions(self.definitions)
+            .schema(self.schema)
+            .server(server)
+            .upstream(upstream)
+            .telemetry(telemetry);
+
+        Ok(apply_batching(blueprint))
+    }
+}
+
+/// Hashes everything the blueprint is compiled from, the config and the
+/// linked files.
+fn hash(config_module: &ConfigModule) -> u64 {
+    let mut hasher = TailcallHasher::default();
+    let extensions = config_module.extensions();
+
+    config_module.config().to_sdl().hash(&mut hasher);
+
+    let mut files = extensions.grpc_file_descriptors.iter().collect::<Vec<_>>();
+    files.sort_by_key(|(name, _)| name.as_str());
+    for (name, file) in files {
+        name.hash(&mut hasher);
+        file.encode_to_vec().hash(&mut hasher);
+    }
+
+    extensions.script.hash(&mut hasher);
+    for htpasswd in extensions.htpasswd.iter() {
+        htpasswd.content.hash(&mut hasher);
+    }
+    for jwks in extensions.jwks.iter() {
+        serde_json::to_string(&jwks.content)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::Config;
+
+    fn to_config_module(sdl: &str) -> ConfigModule {
+        ConfigModule::from(Config::from_sdl(sdl).to_result().unwrap())
+    }
+
+    const SDL: &str = r#"
+        schema @server @upstream(timeout: 10) {
+            query: Query
+        }
+
+        type Query {
+            users: [User] @http(url: "http://jsonplaceholder.typicode.com/users") @cache(maxAge: 1000)
+            greeting: String @expr(body: "Hello {{.env.NAME}}")
+        }
+
+        type User {
+            id: Int
+            name: String
+            posts: [Post] @http(url: "http://jsonplaceholder.typicode.com/posts", query: [{key: "userId", value: "{{.value.id}}"}])
+        }
+
+        type Post {
+            id: Int
+            title: String
+        }
+    "#;
+
+    #[test]
+    fn test_round_trip() {
+        let config_module = to_config_module(SDL);
+        let blueprint = Blueprint::try_from(&config_module).unwrap();
+
+        let bytes = BlueprintCache::new(&config_module, &blueprint)
+            .to_bytes()
+            .unwrap();
+        let actual = BlueprintCache::from_bytes(&bytes, &config_module)
+            .unwrap()
+            .into_blueprint(&config_module)
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&actual.definitions).unwrap(),
+            serde_json::to_value(&blueprint.definitions).unwrap()
+        );
+        assert_eq!(actual.schema.query, blueprint.schema.query);
+        assert_eq!(actual.upstream.timeout, blueprint.upstream.timeout);
+    }
+
+    #[test]
+    fn test_stale_cache() {
+        let config_module = to_config_module(SDL);
+        let blueprint = Blueprint::try_from(&config_module).unwrap();
+        let cache = BlueprintCache::new(&config_module, &blueprint);
+
+        let changed = to_config_module(&SDL.replace("/users", "/people"));
+        assert!(BlueprintCache::from_bytes(&cache.to_bytes().unwrap(), &changed).is_none());
+
+        let mut other_format = cache.to_bytes().unwrap();
+        other_format[MAGIC.len()] = FORMAT + 1;
+        assert!(BlueprintCache::from_bytes(&other_format, &config_module).is_none());
+
+        let outdated = BlueprintCache { version: "0.0.0".to_string(), ..cache };
+        assert!(
+            BlueprintCache::from_bytes(&outdated.to_bytes().unwrap(), &config_module).is_none()
+        );
+
+        assert!(BlueprintCache::from_bytes(b"not a cache", &config_module).is_none());
+    }
+}
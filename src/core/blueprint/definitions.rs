@@ -10,9 +10,10 @@ use union_resolver::update_union_resolver;
 use crate::core::blueprint::*;
 use crate::core::config::{Config, Enum, Field, GraphQLOperationType, Protected, Union};
 use crate::core::directive::DirectiveCodec;
-use crate::core::ir::model::{Cache, IR};
+use crate::core::ir::model::{Adaptive, Cache, CacheOptions, Negative, Size, IR};
+use crate::core::ir::parse_status;
 use crate::core::try_fold::TryFold;
-use crate::core::{config, scalar, Type};
+use crate::core::{config, scalar, Mustache, Type};
 
 pub fn to_scalar_type_definition(name: &str) -> Valid<Definition, BlueprintError> {
     if scalar::Scalar::is_predefined(name) {
@@ -349,7 +350,13 @@ pub fn fix_dangling_resolvers<'a>() -> TryFold<
     TryFold::<(&ConfigModule, &Field, &config::Type, &str), FieldDefinition, BlueprintError>::new(
         move |(config, field, _, name), mut b_field| {
             let mut set = HashSet::new();
+            // the custom scalars are resolved from the parent value like the
+            // predefined ones, they have no fields of their own
+            let is_scalar = config
+                .find_type(field.type_of.name())
+                .is_some_and(|type_of| type_of.scalar());
             if !field.has_resolver()
+                && !is_scalar
                 && validate_field_has_resolver(name, field, &config.types, &mut set).is_succeed()
             {
                 b_field = b_field.resolver(Some(IR::Dynamic(DynamicValue::Value(
@@ -372,8 +379,54 @@ pub fn update_cache_resolvers<'a>() -> TryFold<
 > {
     TryFold::<(&ConfigModule, &Field, &config::Type, &str), FieldDefinition, BlueprintError>::new(
         move |(_config, field, typ, _name), mut b_field| {
-            if let Some(config::Cache { max_age }) = field.cache.as_ref().or(typ.cache.as_ref()) {
-                b_field.map_expr(|expression| Cache::wrap(*max_age, expression))
+            if let Some(config::Cache {
+                max_age,
+                tags,
+                namespace,
+                serve_stale_on_error,
+                vary,
+                negative,
+                key_args,
+                size,
+                adaptive,
+            }) = field.cache.as_ref().or(typ.cache.as_ref())
+            {
+                let tags = tags.iter().map(|tag| Mustache::parse(tag)).collect();
+                let namespace = namespace.as_deref().map(Mustache::parse);
+                let vary = vary.iter().map(|name| name.to_ascii_lowercase()).collect();
+
+                // the arguments are only known for the fields the directive is
+                // applied to, while the types apply it to fields of any arguments
+                let key_args_exist = Valid::from_iter(
+                    key_args.iter().flatten().filter(|_| field.cache.is_some()),
+                    |name| {
+                        Valid::<(), BlueprintError>::fail(BlueprintError::ArgumentNotFound(
+                            name.clone(),
+                        ))
+                        .when(|| !field.args.contains_key(name))
+                    },
+                )
+                .trace("keyArgs");
+
+                return key_args_exist
+                    .and(to_negative(negative.as_ref()))
+                    .zip(to_adaptive(adaptive.as_ref()))
+                    .map(move |(negative, adaptive)| {
+                        b_field.map_expr(|expression| {
+                            let options = CacheOptions {
+                                tags,
+                                namespace,
+                                serve_stale_on_error: *serve_stale_on_error,
+                                vary,
+                                negative,
+                                key_args: key_args.clone(),
+                                size: size.as_ref().map(to_size),
+                                adaptive,
+                            };
+                            Cache::wrap(*max_age, options, expression)
+                        });
+                        b_field
+                    });
             }
 
             Valid::succeed(b_field)
@@ -381,6 +434,56 @@ pub fn update_cache_resolvers<'a>() -> TryFold<
     )
 }
 
+fn to_negative(
+    negative: Option<&config::NegativeCache>,
+) -> Valid<Option<Negative>, BlueprintError> {
+    let Some(negative) = negative else {
+        return Valid::succeed(None);
+    };
+
+    Valid::from_iter(negative.status.iter(), |code| {
+        Valid::from_option(
+            parse_status(code),
+            BlueprintError::InvalidStatusCode(code.to_string()),
+        )
+    })
+    .trace("status")
+    .trace("negative")
+    .map(|status| {
+        Some(Negative {
+            max_age: negative.max_age,
+            null: negative.null.unwrap_or(true),
+            empty: negative.empty.unwrap_or(true),
+            status,
+        })
+    })
+}
+
+fn to_size(size: &config::SizeCache) -> Size {
+    Size { threshold: size.threshold, min_max_age: size.min_max_age }
+}
+
+fn to_adaptive(
+    adaptive: Option<&config::AdaptiveCache>,
+) -> Valid<Option<Adaptive>, BlueprintError> {
+    let Some(adaptive) = adaptive else {
+        return Valid::succeed(None);
+    };
+
+    Valid::<(), BlueprintError>::fail(BlueprintError::ValidateEmptyRange(
+        "minMaxAge".to_string(),
+        "maxMaxAge".to_string(),
+    ))
+    .when(|| adaptive.min_max_age > adaptive.max_max_age)
+    .trace("adaptive")
+    .map(|_| {
+        Some(Adaptive {
+            min_max_age: adaptive.min_max_age,
+            max_max_age: adaptive.max_max_age,
+        })
+    })
+}
+
 fn validate_field_type_exist(config: &Config, field: &Field) -> Valid<(), BlueprintError> {
     let field_type = field.type_of.name();
     if !scalar::Scalar::is_predefined(field_type) && !config.contains(field_type) {
@@ -402,6 +505,13 @@ fn to_fields(
         .eq(&Some(object_name))
     {
         GraphQLOperationType::Mutation
+    } else if config_module
+        .schema
+        .subscription
+        .as_deref()
+        .eq(&Some(object_name))
+    {
+        GraphQLOperationType::Subscription
     } else {
         GraphQLOperationType::Query
     };
@@ -432,15 +542,20 @@ fn to_fields(
             .iter()
             .filter(|(_, field)| !field.is_omitted()),
         |(name, field)| {
-            let mut result =
-                validate_field_type_exist(config_module, field).and(to_field_definition(
+            let mut result = validate_field_type_exist(config_module, field)
+                .and(to_field_definition(
                     field,
                     &operation_type,
                     object_name,
                     config_module,
                     type_of,
                     name,
-                ));
+                ))
+                .and_then(|b_field| {
+                    to_alias((config_module, field, type_of, name), &b_field)
+                        .trace(config::Modify::trace_name().as_str())
+                        .map(|alias| std::iter::once(b_field).chain(alias).collect::<Vec<_>>())
+                });
 
             if fields_with_different_auth_ids.contains(name) || parent_auth_ids.is_none() {
                 // if the field has a different auth id than the parent type or parent has no
@@ -527,7 +642,8 @@ fn to_fields(
     let added_fields = Valid::from_iter(type_of.added_fields.iter(), |added_field| {
         to_added_field(added_field, type_of)
     });
-    fields.zip(added_fields).map(|(mut fields, added_fields)| {
+    fields.zip(added_fields).map(|(fields, added_fields)| {
+        let mut fields = fields.into_iter().flatten().collect::<Vec<_>>();
         fields.extend(added_fields);
         fields
     })
@@ -547,6 +663,9 @@ pub fn to_field_definition(
         .and(update_modify().trace(config::Modify::trace_name().as_str()))
         .and(fix_dangling_resolvers())
         .and(update_cache_resolvers())
+        .and(update_mask().trace(config::Mask::trace_name().as_str()))
+        .and(update_compress().trace(config::Compress::trace_name().as_str()))
+        .and(update_validate().trace(config::Validate::trace_name().as_str()))
         .and(update_protected(object_name).trace(Protected::trace_name().as_str()))
         .and(update_enum_alias())
         .and(update_union_resolver())
@@ -2,13 +2,14 @@ use std::collections::HashMap;
 
 use async_graphql::parser::types::ConstDirective;
 use async_graphql::Name;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tailcall_valid::{Valid, ValidationError, Validator};
 
 use super::BlueprintError;
 use crate::core::{config, pos};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Directive {
     pub name: String,
     pub arguments: HashMap<String, Value>,
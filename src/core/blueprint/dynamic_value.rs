@@ -1,15 +1,26 @@
 use async_graphql_value::{ConstValue, Name};
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::core::mustache::Mustache;
+use crate::core::mustache::{Mustache, Segment};
+use crate::core::path::RUNTIME_ENV;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum DynamicValue<A> {
     Value(A),
     Mustache(Mustache),
     Object(IndexMap<Name, DynamicValue<A>>),
     Array(Vec<DynamicValue<A>>),
+    /// `{{ cond ? then : otherwise }}`, only the taken branch is rendered.
+    Cond(
+        Box<DynamicValue<A>>,
+        Box<DynamicValue<A>>,
+        Box<DynamicValue<A>>,
+    ),
+    /// `{{ value ?? default }}`, the default is rendered only when the value
+    /// is null or missing.
+    Coalesce(Box<DynamicValue<A>>, Box<DynamicValue<A>>),
 }
 
 impl<A: Default> Default for DynamicValue<A> {
@@ -49,6 +60,82 @@ impl<A> DynamicValue<A> {
                 let vec = vec.into_iter().map(|val| val.prepend(name)).collect();
                 DynamicValue::Array(vec)
             }
+            DynamicValue::Cond(cond, then, otherwise) => DynamicValue::Cond(
+                Box::new(cond.prepend(name)),
+                Box::new(then.prepend(name)),
+                Box::new(otherwise.prepend(name)),
+            ),
+            DynamicValue::Coalesce(value, default) => DynamicValue::Coalesce(
+                Box::new(value.prepend(name)),
+                Box::new(default.prepend(name)),
+            ),
+        }
+    }
+}
+
+impl<A> DynamicValue<A> {
+    /// Makes the `{{.env.*}}` expressions read the environment at the time
+    /// of evaluation, see [crate::core::EnvIO::get_live].
+    pub fn with_runtime_env(self) -> Self {
+        match self {
+            DynamicValue::Value(value) => DynamicValue::Value(value),
+            DynamicValue::Mustache(mut mustache) => {
+                for segment in mustache.segments_mut() {
+                    if let Segment::Expression(parts) = segment {
+                        if parts.len() > 1 && parts[0] == "env" {
+                            parts[0] = RUNTIME_ENV.to_string();
+                        }
+                    }
+                }
+                DynamicValue::Mustache(mustache)
+            }
+            DynamicValue::Object(index_map) => DynamicValue::Object(
+                index_map
+                    .into_iter()
+                    .map(|(key, val)| (key, val.with_runtime_env()))
+                    .collect(),
+            ),
+            DynamicValue::Array(vec) => {
+                DynamicValue::Array(vec.into_iter().map(|val| val.with_runtime_env()).collect())
+            }
+            DynamicValue::Cond(cond, then, otherwise) => DynamicValue::Cond(
+                Box::new(cond.with_runtime_env()),
+                Box::new(then.with_runtime_env()),
+                Box::new(otherwise.with_runtime_env()),
+            ),
+            DynamicValue::Coalesce(value, default) => DynamicValue::Coalesce(
+                Box::new(value.with_runtime_env()),
+                Box::new(default.with_runtime_env()),
+            ),
+        }
+    }
+}
+
+impl<A: Default> DynamicValue<A> {
+    /// Makes the values that are a single path, eg. `{{.user.name}}`, render
+    /// as `null` rather than an empty string when the path is missing, as
+    /// `{{ .user.name ?? null }}` does.
+    pub fn with_null_paths(self) -> Self {
+        match self {
+            DynamicValue::Mustache(mustache)
+                if matches!(mustache.segments().as_slice(), [Segment::Expression(_)]) =>
+            {
+                DynamicValue::Coalesce(
+                    Box::new(DynamicValue::Mustache(mustache)),
+                    Box::new(DynamicValue::Value(A::default())),
+                )
+            }
+            DynamicValue::Object(index_map) => DynamicValue::Object(
+                index_map
+                    .into_iter()
+                    .map(|(key, val)| (key, val.with_null_paths()))
+                    .collect(),
+            ),
+            DynamicValue::Array(vec) => {
+                DynamicValue::Array(vec.into_iter().map(|val| val.with_null_paths()).collect())
+            }
+            // the operands of the operators are already null when missing
+            value => value,
         }
     }
 }
@@ -74,6 +161,9 @@ impl TryFrom<&DynamicValue<ConstValue>> for ConstValue {
                     arr.iter().map(ConstValue::try_from).collect();
                 Ok(ConstValue::List(out?))
             }
+            DynamicValue::Cond(..) | DynamicValue::Coalesce(..) => Err(anyhow::anyhow!(
+                "expression cannot be converted to const value"
+            )),
         }
     }
 }
@@ -85,6 +175,8 @@ impl<A> DynamicValue<A> {
             DynamicValue::Mustache(m) => m.is_const(),
             DynamicValue::Object(obj) => obj.values().all(|v| v.is_const()),
             DynamicValue::Array(arr) => arr.iter().all(|v| v.is_const()),
+            // the operators are evaluated when the value is rendered
+            DynamicValue::Cond(..) | DynamicValue::Coalesce(..) => false,
             _ => true,
         }
     }
@@ -108,6 +200,7 @@ impl TryFrom<&Value> for DynamicValue<ConstValue> {
                     arr.iter().map(DynamicValue::try_from).collect();
                 Ok(DynamicValue::Array(out?))
             }
+            Value::String(s) if is_operator_expression(s) => parse_operators(s),
             Value::String(s) => {
                 let m = Mustache::parse(s.as_str());
                 if m.is_const() {
@@ -121,6 +214,187 @@ impl TryFrom<&Value> for DynamicValue<ConstValue> {
     }
 }
 
+/// Strings that consist of a single mustache expression with the `?` of the
+/// ternary or the `??` operators, eg. `{{ .args.limit ?? 10 }}`.
+fn is_operator_expression(value: &str) -> bool {
+    value
+        .trim()
+        .strip_prefix("{{")
+        .and_then(|value| value.strip_suffix("}}"))
+        .is_some_and(|inner| !inner.contains("{{") && inner.contains('?'))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Path(Vec<String>),
+    Literal(ConstValue),
+    Question,
+    Coalesce,
+    Colon,
+    LeftParen,
+    RightParen,
+}
+
+/// Parses the operators of an `@expr` string, the operands are the paths of
+/// the mustache templates, the literals and the parenthesized expressions.
+/// `??` binds tighter than the ternary, which is right associative.
+fn parse_operators(value: &str) -> anyhow::Result<DynamicValue<ConstValue>> {
+    let inner = value.trim();
+    let inner = &inner[2..inner.len() - 2];
+    let mut parser = OperatorParser { tokens: tokenize(inner)?, position: 0 };
+    let expression = parser.ternary()?;
+
+    match parser.tokens.get(parser.position) {
+        None => Ok(expression),
+        Some(token) => Err(anyhow::anyhow!("Unexpected {:?} in `{}`", token, value)),
+    }
+}
+
+fn tokenize(input: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&char) = chars.peek() {
+        let token = match char {
+            char if char.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '?' => {
+                chars.next();
+                if chars.next_if_eq(&'?').is_some() {
+                    Token::Coalesce
+                } else {
+                    Token::Question
+                }
+            }
+            ':' => {
+                chars.next();
+                Token::Colon
+            }
+            '(' => {
+                chars.next();
+                Token::LeftParen
+            }
+            ')' => {
+                chars.next();
+                Token::RightParen
+            }
+            quote @ ('"' | '\'') => {
+                chars.next();
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => string.extend(chars.next()),
+                        Some(char) if char == quote => break,
+                        Some(char) => string.push(char),
+                        None => return Err(anyhow::anyhow!("Unterminated string in `{input}`")),
+                    }
+                }
+                Token::Literal(ConstValue::String(string))
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(char) =
+                    chars.next_if(|char| char.is_alphanumeric() || "_.-+".contains(*char))
+                {
+                    word.push(char);
+                }
+                match word.as_str() {
+                    "" => return Err(anyhow::anyhow!("Unexpected `{char}` in `{input}`")),
+                    "true" => Token::Literal(ConstValue::Boolean(true)),
+                    "false" => Token::Literal(ConstValue::Boolean(false)),
+                    "null" => Token::Literal(ConstValue::Null),
+                    word => match serde_json::from_str::<serde_json::Number>(word) {
+                        Ok(number) => Token::Literal(ConstValue::Number(number)),
+                        Err(_) => {
+                            let keys: Vec<_> = word.trim_start_matches('.').split('.').collect();
+                            if keys.iter().any(|key| key.is_empty()) {
+                                return Err(anyhow::anyhow!("Invalid path `{word}` in `{input}`"));
+                            }
+                            Token::Path(keys.into_iter().map(String::from).collect())
+                        }
+                    },
+                }
+            }
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+struct OperatorParser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl OperatorParser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn next_if(&mut self, token: &Token) -> bool {
+        let matches = self.tokens.get(self.position) == Some(token);
+        if matches {
+            self.position += 1;
+        }
+        matches
+    }
+
+    fn ternary(&mut self) -> anyhow::Result<DynamicValue<ConstValue>> {
+        let cond = self.coalesce()?;
+        if !self.next_if(&Token::Question) {
+            return Ok(cond);
+        }
+
+        let then = self.ternary()?;
+        if !self.next_if(&Token::Colon) {
+            return Err(anyhow::anyhow!("Expected `:` of the ternary operator"));
+        }
+        let otherwise = self.ternary()?;
+
+        Ok(DynamicValue::Cond(
+            Box::new(cond),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn coalesce(&mut self) -> anyhow::Result<DynamicValue<ConstValue>> {
+        let mut value = self.operand()?;
+        while self.next_if(&Token::Coalesce) {
+            value = DynamicValue::Coalesce(Box::new(value), Box::new(self.operand()?));
+        }
+
+        Ok(value)
+    }
+
+    fn operand(&mut self) -> anyhow::Result<DynamicValue<ConstValue>> {
+        match self.next() {
+            Some(Token::Path(path)) => Ok(DynamicValue::Mustache(Mustache::from(vec![
+                Segment::Expression(path),
+            ]))),
+            Some(Token::Literal(value)) => Ok(DynamicValue::Value(value)),
+            Some(Token::LeftParen) => {
+                let value = self.ternary()?;
+                if !self.next_if(&Token::RightParen) {
+                    return Err(anyhow::anyhow!("Expected `)`"));
+                }
+                Ok(value)
+            }
+            Some(token) => Err(anyhow::anyhow!(
+                "Expected a path or a value, found {:?}",
+                token
+            )),
+            None => Err(anyhow::anyhow!("Unexpected end of the expression")),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -159,4 +433,51 @@ mod test {
         let expected: DynamicValue<ConstValue> = DynamicValue::Value(ConstValue::Null);
         assert_eq!(value, expected);
     }
+
+    #[test]
+    fn test_dynamic_value_with_runtime_env() {
+        let value: DynamicValue<ConstValue> = DynamicValue::Array(vec![DynamicValue::Mustache(
+            Mustache::parse("{{.env.FEATURE_X}}-{{.args.env}}"),
+        )])
+        .with_runtime_env();
+        let expected: DynamicValue<ConstValue> = DynamicValue::Array(vec![DynamicValue::Mustache(
+            Mustache::parse("{{.runtimeEnv.FEATURE_X}}-{{.args.env}}"),
+        )]);
+        assert_eq!(value, expected);
+    }
+
+    fn path(path: &str) -> DynamicValue<ConstValue> {
+        DynamicValue::Mustache(Mustache::parse(&format!("{{{{{path}}}}}")))
+    }
+
+    #[test]
+    fn test_parse_operators() {
+        let value = DynamicValue::try_from(&serde_json::json!(
+            "{{ .args.a ?? .args.b ? 'x' : (.args.c ?? -1) }}"
+        ))
+        .unwrap();
+        let expected = DynamicValue::Cond(
+            Box::new(DynamicValue::Coalesce(
+                Box::new(path(".args.a")),
+                Box::new(path(".args.b")),
+            )),
+            Box::new(DynamicValue::Value(ConstValue::String("x".to_string()))),
+            Box::new(DynamicValue::Coalesce(
+                Box::new(path(".args.c")),
+                Box::new(DynamicValue::Value(ConstValue::from(-1))),
+            )),
+        );
+        assert_eq!(value, expected);
+        assert!(!value.is_const());
+    }
+
+    #[test]
+    fn test_parse_operators_error() {
+        let parse = |value: &str| DynamicValue::try_from(&serde_json::json!(value));
+
+        assert!(parse("{{ .args.a ? 1 }}").is_err());
+        assert!(parse("{{ .args.a ?? }}").is_err());
+        assert!(parse("{{ (.args.a ?? 1 }}").is_err());
+        assert!(parse("{{ .args.a ?? 'b }}").is_err());
+    }
 }
@@ -31,7 +31,13 @@ pub enum BlueprintError {
     #[error("Result resolver can't be empty")]
     ResultResolverCanNotBeEmpty,
 
-    #[error("call must have query or mutation")]
+    #[error("The name `{0}` is already taken by another call or an argument of the field")]
+    ComposeCallNameTaken(String),
+
+    #[error("The composed calls can't be batched")]
+    ComposeCallCanNotBeBatched,
+
+    #[error("call must have query, mutation or subscription")]
     CallMustHaveQueryOrMutation,
 
     #[error("invalid JSON: {0}")]
@@ -43,6 +49,15 @@ pub enum BlueprintError {
     #[error("Invalid method format: {0}. Expected format is <package>.<service>.<method>")]
     InvalidGrpcMethodFormat(String),
 
+    #[error("Field `{0}` of the request is not a `google.protobuf.FieldMask`")]
+    InvalidFieldMask(String),
+
+    #[error("`{0}` is not a valid name of the batch operation")]
+    InvalidBatchName(String),
+
+    #[error("`{0}` is not a valid status code, expected a code like `404`, a class like `4XX` or a range like `500-599`")]
+    InvalidStatusCode(String),
+
     #[error("Protobuf files were not specified in the config")]
     ProtobufFilesNotSpecifiedInConfig,
 
@@ -58,12 +73,71 @@ pub enum BlueprintError {
     #[error("batchKey requires either body or query parameters")]
     BatchKeyRequiresEitherBodyOrQuery,
 
+    #[error("The client-streaming gRPC methods can't be batched")]
+    GrpcClientStreamingCanNotBeBatched,
+
+    #[error(
+        "The server-streaming gRPC methods can only resolve the list fields that aren't batched"
+    )]
+    GrpcServerStreamingRequiresList,
+
+    #[error("The gRPC method `{0}` is rendered at request time, the methods it can call have to be listed in `allow`")]
+    GrpcDynamicMethodNotAllowed(String),
+
+    #[error("The gRPC methods rendered at request time can't be batched, have a field mask or be transcoded")]
+    GrpcDynamicMethodUnsupported,
+
+    #[error("Can't transcode the gRPC method: {0}")]
+    GrpcTranscodingUnsupported(String),
+
+    #[error("Couldn't find the gRPC method or service `{0}`")]
+    GrpcMethodNotFound(String),
+
+    #[error("The `routes` and the `routeBy` of a gRPC call have to be set together")]
+    GrpcRoutesIncomplete,
+
+    #[error("The gRPC calls routed at request time can't be batched")]
+    GrpcRoutesCanNotBeBatched,
+
+    #[error("batchKeys can't be used along with batchKey")]
+    BatchKeysConflict,
+
+    #[error("batchKeys can be used only with the GET method")]
+    BatchKeysRequireGet,
+
+    #[error("batchKeys must lead to the fields of the same items")]
+    BatchKeysDifferentPaths,
+
+    #[error("Query parameter {0} with a value from the parent is required by batchKeys")]
+    BatchKeyNotInQuery(String),
+
+    #[error("streamUpload can't be used along with {0}, the upload can be consumed only once")]
+    StreamUploadConflict(String),
+
+    #[error("streamUpload requires a method that sends a request body, eg. POST or PUT")]
+    StreamUploadRequiresBodyMethod,
+
+    #[error("rawBody requires the field to be of the Bytes scalar, found {0}")]
+    RawBodyRequiresBytes(String),
+
+    #[error("rawBody can't be used along with batchKey")]
+    RawBodyCanNotBeBatched,
+
+    #[error("maxSize can only be used along with rawBody")]
+    MaxSizeRequiresRawBody,
+
     #[error("script is required")]
     ScriptIsRequired,
 
+    #[error("The script needs at least one worker")]
+    ServerScriptPoolSizeZero,
+
     #[error("Field is already implemented from interface")]
     FieldExistsInInterface,
 
+    #[error("Alias {0} conflicts with an existing field")]
+    FieldAliasExists(String),
+
     #[error("Input types can not be protected")]
     InputTypesCannotBeProtected,
 
@@ -73,9 +147,69 @@ pub enum BlueprintError {
     #[error("Auth provider {0} not found")]
     AuthProviderNotFound(String),
 
+    #[error("@mask operator is used but there is no @link definitions for auth providers")]
+    MaskOperatorNoAuthProviders,
+
+    #[error("cacheAdmin is enabled but there is no @link definitions for auth providers")]
+    CacheAdminNoAuthProviders,
+
+    #[error("Masked field {0} must be nullable")]
+    MaskedFieldIsRequired(String),
+
+    #[error("Masked field {0} can't have a resolver")]
+    MaskedFieldHasResolver(String),
+
+    #[error("@compress can only be used on the fields of the String type")]
+    CompressedFieldNotString,
+
+    #[error("{0} can only be used on {1} arguments")]
+    ValidateNotApplicable(String, String),
+
+    #[error("{0} is greater than {1}")]
+    ValidateEmptyRange(String, String),
+
+    #[error("Invalid pattern: {0}")]
+    InvalidValidatePattern(String),
+
+    #[error("@fallback on the field {0} requires at least two resolvers")]
+    FallbackRequiresResolvers(String),
+
+    #[error("@join on the field {0} requires exactly two resolvers")]
+    JoinRequiresResolvers(String),
+
+    #[error("@join can be used only on the fields of a list type")]
+    JoinRequiresList,
+
+    #[error("@partial on the field {0} requires at least two merged resolvers")]
+    PartialRequiresResolvers(String),
+
     #[error("syntax error when parsing `{0}`")]
     SyntaxErrorWhenParsing(String),
 
+    #[error("Invalid filter: {0}")]
+    InvalidFilter(String),
+
+    #[error("filter can be used only on the fields of a list type")]
+    FilterRequiresList,
+
+    #[error("compact can be used only on the fields of a list type")]
+    CompactRequiresList,
+
+    #[error("Invalid skipIf: {0}")]
+    InvalidSkipIf(String),
+
+    #[error("Invalid dynamicHeaders: {0}")]
+    InvalidDynamicHeaders(String),
+
+    #[error("connection requires the `{0}` argument of the field")]
+    ConnectionRequiresArgument(String),
+
+    #[error("connection requires the `edgeCursor` of the items")]
+    ConnectionRequiresEdgeCursor,
+
+    #[error("connection can't be used along with batchKey or on the fields of a list type")]
+    ConnectionCanNotBeList,
+
     #[error("Scalar type {0} is predefined")]
     ScalarTypeIsPredefined(String),
 
@@ -136,6 +270,9 @@ pub enum BlueprintError {
     #[error("unknown template directive '{0}'")]
     UnknownTemplateDirective(String),
 
+    #[error("'{0}' can only be read with @expr(runtimeEnv), use '.env' instead")]
+    RuntimeEnvNotAllowed(String),
+
     #[error("Query root is missing")]
     QueryRootIsMissing,
 
@@ -148,6 +285,18 @@ pub enum BlueprintError {
     #[error("Mutation type is not defined")]
     MutationTypeNotDefined,
 
+    #[error("Subscription type is not defined")]
+    SubscriptionTypeNotDefined,
+
+    #[error("Subscription fields can only be resolved with @graphQL, @http or @call")]
+    InvalidSubscriptionResolver,
+
+    #[error("Subscription fields can only call a single field of the Subscription type")]
+    SubscriptionCallMustBeStreaming,
+
+    #[error("Fields of the Subscription type can only be called from the subscription fields")]
+    SubscriptionCallNotStreaming,
+
     #[error("Certificate is required for HTTP2")]
     CertificateIsRequiredForHTTP2,
 
@@ -163,6 +312,34 @@ pub enum BlueprintError {
     #[error("Invalid CORS configuration: Cannot combine `Access-Control-Allow-Credentials: true` with `{0}: *`")]
     InvalidCORSConfiguration(String),
 
+    #[error("Invalid adaptive timeout: {0}")]
+    InvalidAdaptiveTimeout(String),
+
+    #[error("Invalid bulkhead: {0}")]
+    InvalidBulkhead(String),
+
+    #[error("Invalid canary: {0}")]
+    InvalidCanary(String),
+
+    #[error("http2MaxConcurrentStreams must be greater than 0")]
+    InvalidHttp2MaxConcurrentStreams,
+
+    #[error("Invalid retry: {0}")]
+    InvalidGrpcRetry(String),
+
+    #[error("Invalid token provider: {0}")]
+    InvalidTokenProvider(String),
+
+    #[error("Invalid enum alias: {0}")]
+    InvalidEnumAlias(String),
+
+    #[error("Conflicting resolvers {directives} on the field `{field}`, {reason}")]
+    ConflictingResolvers {
+        field: String,
+        directives: String,
+        reason: String,
+    },
+
     #[error("{0}")]
     Cause(String),
 
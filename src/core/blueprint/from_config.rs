@@ -9,7 +9,7 @@ use super::Server;
 use crate::core::blueprint::compress::compress;
 use crate::core::blueprint::*;
 use crate::core::config::transformer::Required;
-use crate::core::config::{Arg, Batch, Config, ConfigModule};
+use crate::core::config::{Arg, BaseUrls, Batch, Config, ConfigModule};
 use crate::core::ir::model::{IO, IR};
 use crate::core::json::JsonSchema;
 use crate::core::try_fold::TryFold;
@@ -125,6 +125,9 @@ impl TryFrom<&ConfigModule> for Blueprint {
                 &config_module
                     .to_owned()
                     .transform(Required)
+                    .and_then(|module| {
+                        module.transform(BaseUrls(&config_module.extensions().base_url_env))
+                    })
                     .to_result()
                     .map_err(BlueprintError::from_validation_string)?,
                 Blueprint::default(),
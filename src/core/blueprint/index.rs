@@ -5,6 +5,7 @@ use crate::core::blueprint::{
     Blueprint, Definition, FieldDefinition, InputFieldDefinition, SchemaDefinition,
 };
 use crate::core::scalar;
+use crate::core::scalar_codec::ScalarCodecs;
 
 ///
 /// A read optimized index of all the types in the Blueprint. Provide O(1)
@@ -14,6 +15,7 @@ use crate::core::scalar;
 pub struct Index {
     map: IndexMap<String, (Definition, IndexMap<String, QueryField>)>,
     schema: SchemaDefinition,
+    scalar_codecs: ScalarCodecs,
 }
 
 #[derive(Debug)]
@@ -38,6 +40,10 @@ impl Index {
         matches!(def, Some(Definition::Scalar(_))) || scalar::Scalar::is_predefined(type_name)
     }
 
+    pub fn scalar_codecs(&self) -> &ScalarCodecs {
+        &self.scalar_codecs
+    }
+
     pub fn type_is_enum(&self, type_name: &str) -> bool {
         let def = self.map.get(type_name).map(|(def, _)| def);
 
@@ -180,7 +186,11 @@ impl From<&Blueprint> for Index {
             }
         }
 
-        Self { map, schema: blueprint.schema.to_owned() }
+        Self {
+            map,
+            schema: blueprint.schema.to_owned(),
+            scalar_codecs: blueprint.scalar_codecs.clone(),
+        }
     }
 }
 
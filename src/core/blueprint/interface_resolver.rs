@@ -14,15 +14,20 @@ fn compile_interface_resolver(
     interface_types: &BTreeSet<String>,
     discriminate: &Option<Discriminate>,
 ) -> Valid<Discriminator, BlueprintError> {
-    let typename_field = discriminate.as_ref().map(|d| d.get_field());
+    let discriminator = match discriminate {
+        Some(discriminate) if !discriminate.status.is_empty() => Discriminator::with_status(
+            interface_name.to_string(),
+            interface_types.clone(),
+            discriminate.get_status(),
+        ),
+        _ => Discriminator::new(
+            interface_name.to_string(),
+            interface_types.clone(),
+            discriminate.as_ref().map(|d| d.get_field()),
+        ),
+    };
 
-    match Discriminator::new(
-        interface_name.to_string(),
-        interface_types.clone(),
-        typename_field,
-    )
-    .to_result()
-    {
+    match discriminator.to_result() {
         Ok(data) => Valid::succeed(data),
         Err(err) => Valid::from_validation_err(BlueprintError::from_validation_string(err)),
     }
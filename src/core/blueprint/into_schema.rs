@@ -1,15 +1,19 @@
 use std::sync::Arc;
 
-use async_graphql::dynamic::{self, FieldFuture, FieldValue, SchemaBuilder, TypeRef};
+use async_graphql::dynamic::{
+    self, FieldFuture, FieldValue, SchemaBuilder, SubscriptionFieldFuture, TypeRef,
+};
 use async_graphql_value::ConstValue;
-use futures_util::TryFutureExt;
+use futures_util::{StreamExt, TryFutureExt};
 use tracing::Instrument;
 
-use crate::core::blueprint::{Blueprint, Definition};
+use crate::core::blueprint::{Blueprint, Definition, ObjectTypeDefinition};
 use crate::core::http::RequestContext;
 use crate::core::ir::{EvalContext, ResolverContext, TypedValue};
 use crate::core::jit::graphql_error::ErrorExtensions;
+use crate::core::jit::ValidationError;
 use crate::core::scalar::Scalar;
+use crate::core::scalar_codec::ScalarCodecs;
 
 /// We set the default value for an `InputValue` by reading it from the
 /// blueprint and assigning it to the provided `InputValue` during the
@@ -51,7 +55,31 @@ fn to_field_value(value: async_graphql::Value) -> FieldValue<'static> {
     }
 }
 
-fn to_type(def: &Definition) -> dynamic::Type {
+/// The codecs of the custom scalar that a field resolves to, `None` when the
+/// scalar has no codec registered.
+fn scalar_codec(codecs: &ScalarCodecs, type_of: &str) -> Option<(String, ScalarCodecs)> {
+    codecs
+        .get(type_of)
+        .map(|_| (type_of.to_string(), codecs.clone()))
+}
+
+/// Converts the resolved value of a custom scalar with its codec, the same
+/// way the values are converted by the JIT executor.
+fn convert_scalar(
+    codec: &Option<(String, ScalarCodecs)>,
+    value: ConstValue,
+) -> async_graphql::Result<ConstValue> {
+    match codec {
+        Some((type_of, codecs)) => codecs.convert(type_of, value).map_err(|message| {
+            async_graphql::Error::new(
+                ValidationError::ScalarCodec { type_of: type_of.clone(), message }.to_string(),
+            )
+        }),
+        None => Ok(value),
+    }
+}
+
+fn to_type(def: &Definition, codecs: &ScalarCodecs) -> dynamic::Type {
     match def {
         Definition::Object(def) => {
             let mut object = dynamic::Object::new(def.name.clone());
@@ -59,6 +87,7 @@ fn to_type(def: &Definition) -> dynamic::Type {
                 let field = field.clone();
                 let type_ref = TypeRef::from(&field.of_type);
                 let field_name = &field.name.clone();
+                let codec = scalar_codec(codecs, field.of_type.name());
 
                 let mut dyn_schema_field = dynamic::Field::new(
                     field_name,
@@ -79,6 +108,14 @@ fn to_type(def: &Definition) -> dynamic::Type {
 
                                 match ctx.path_value(&[field_name]).map(|a| a.into_owned()) {
                                     Some(ConstValue::Null) => FieldFuture::Value(FieldValue::NONE),
+                                    Some(value) if codec.is_some() => {
+                                        match convert_scalar(&codec, value) {
+                                            Ok(value) => FieldFuture::from_value(Some(value)),
+                                            Err(err) => FieldFuture::new(async move {
+                                                Err::<Option<FieldValue>, _>(err)
+                                            }),
+                                        }
+                                    }
                                     a => FieldFuture::from_value(a),
                                 }
                             }
@@ -89,6 +126,7 @@ fn to_type(def: &Definition) -> dynamic::Type {
                                 );
 
                                 let expr = expr.to_owned();
+                                let codec = codec.clone();
                                 FieldFuture::new(
                                     async move {
                                         let ctx: ResolverContext = ctx.into();
@@ -96,6 +134,7 @@ fn to_type(def: &Definition) -> dynamic::Type {
 
                                         let value =
                                             expr.eval(ctx).await.map_err(|err| err.extend())?;
+                                        let value = convert_scalar(&codec, value)?;
 
                                         if let ConstValue::Null = value {
                                             Ok(FieldValue::NONE)
@@ -192,16 +231,79 @@ fn to_type(def: &Definition) -> dynamic::Type {
     }
 }
 
+/// The fields of the subscription root resolve to the streams of events
+/// received from the upstream subscriptions.
+fn to_subscription(def: &ObjectTypeDefinition, codecs: &ScalarCodecs) -> dynamic::Subscription {
+    let mut subscription = dynamic::Subscription::new(def.name.clone());
+    for field in def.fields.iter() {
+        let field = field.clone();
+        let type_ref = TypeRef::from(&field.of_type);
+        let field_name = field.name.clone();
+        let codec = scalar_codec(codecs, field.of_type.name());
+
+        let mut dyn_schema_field = dynamic::SubscriptionField::new(
+            field_name,
+            type_ref,
+            move |ctx| {
+                let resolver = field.resolver.clone();
+                let codec = codec.clone();
+                SubscriptionFieldFuture::new(async move {
+                    let req_ctx = ctx.ctx.data::<Arc<RequestContext>>()?;
+                    let ctx: ResolverContext = ctx.into();
+                    let ctx = EvalContext::new(req_ctx, &ctx);
+
+                    let Some(resolver) = resolver else {
+                        return Err(async_graphql::Error::new(
+                            "Subscription fields can only be resolved with @graphQL, @http or @call",
+                        ));
+                    };
+                    let events = resolver.subscribe(&ctx).await.map_err(|err| err.extend())?;
+
+                    Ok(events.map(move |event| {
+                        let value = event.map_err(|err| err.extend())?;
+                        Ok(match convert_scalar(&codec, value)? {
+                            ConstValue::Null => FieldValue::NULL,
+                            value => to_field_value(value),
+                        })
+                    }))
+                })
+            },
+        );
+        if let Some(description) = &field.description {
+            dyn_schema_field = dyn_schema_field.description(description);
+        }
+        for arg in field.args.iter() {
+            dyn_schema_field = dyn_schema_field.argument(set_default_value(
+                dynamic::InputValue::new(arg.name.clone(), TypeRef::from(&arg.of_type)),
+                arg.default_value.clone(),
+            ));
+        }
+        subscription = subscription.field(dyn_schema_field);
+    }
+    if let Some(description) = &def.description {
+        subscription = subscription.description(description);
+    }
+
+    subscription
+}
+
 impl From<&Blueprint> for SchemaBuilder {
     fn from(blueprint: &Blueprint) -> Self {
         let query = blueprint.query();
         let mutation = blueprint.mutation();
-        let mut schema = dynamic::Schema::build(query.as_str(), mutation.as_deref(), None);
+        let subscription = blueprint.subscription();
+        let mut schema =
+            dynamic::Schema::build(query.as_str(), mutation.as_deref(), subscription.as_deref());
 
         schema = inject_custom_scalars(schema, blueprint);
 
         for def in blueprint.definitions.iter() {
-            schema = schema.register(to_type(def));
+            schema = match def {
+                Definition::Object(def) if subscription.as_ref() == Some(&def.name) => {
+                    schema.register(to_subscription(def, &blueprint.scalar_codecs))
+                }
+                def => schema.register(to_type(def, &blueprint.scalar_codecs)),
+            };
         }
 
         schema
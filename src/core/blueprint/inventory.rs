@@ -0,0 +1,257 @@
+use serde::Serialize;
+
+use super::{Auth, Blueprint, Definition, Provider};
+use crate::core::ir::model::{Cache, IO, IR};
+use crate::core::ir::IrVisitor;
+
+/// Machine-readable inventory of a blueprint for the security reviews: the
+/// upstream endpoints that its resolvers can call and the fields that require
+/// an authentication. The secrets of the auth providers aren't a part of it.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Inventory {
+    pub endpoints: Vec<UpstreamEndpoint>,
+    pub protected_fields: Vec<ProtectedField>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpstreamEndpoint {
+    /// The field that calls the endpoint eg. `Query.users`
+    pub field: String,
+    /// `http`, `grpc`, `graphQL` or `js`
+    pub kind: &'static str,
+    /// The HTTP method, the full name of the gRPC method, the type and the name
+    /// of the GraphQL operation or the name of the JS function. The gRPC
+    /// methods rendered at request time list the methods they're allowed to
+    /// call.
+    pub method: String,
+    /// Template of the url, absent for the JS functions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// The time in milliseconds the responses are cached for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_ttl: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtectedField {
+    pub field: String,
+    /// The providers that have to authenticate the request eg. `basic and jwt`
+    pub auth: String,
+    /// The field resolves the unauthenticated requests to a fallback
+    pub fallback: bool,
+}
+
+impl From<&Blueprint> for Inventory {
+    fn from(blueprint: &Blueprint) -> Self {
+        let mut inventory = Inventory::default();
+
+        for def in blueprint.definitions.iter() {
+            let Definition::Object(def) = def else {
+                continue;
+            };
+            for field in def.fields.iter() {
+                if let Some(ir) = field.resolver.as_ref() {
+                    let field = format!("{}.{}", def.name, field.name);
+                    ir.accept(&mut FieldInventory {
+                        field,
+                        cache_ttl: None,
+                        inventory: &mut inventory,
+                    });
+                }
+            }
+        }
+
+        inventory
+    }
+}
+
+/// Collects the endpoints and the protection of the resolver of a field.
+struct FieldInventory<'a> {
+    field: String,
+    /// TTL of the cache that wraps the visited IO
+    cache_ttl: Option<u64>,
+    inventory: &'a mut Inventory,
+}
+
+impl IrVisitor for FieldInventory<'_> {
+    fn visit_ir(&mut self, ir: &IR) {
+        match ir {
+            IR::Protect(protect) => {
+                self.inventory.protected_fields.push(ProtectedField {
+                    field: self.field.clone(),
+                    auth: describe(&protect.auth),
+                    fallback: protect.anon.is_some(),
+                });
+            }
+            // the IOs that aren't wrapped by a cache
+            IR::IO(_) => self.cache_ttl = None,
+            _ => {}
+        }
+    }
+
+    fn visit_io(&mut self, io: &IO) {
+        let (kind, method, url) = match io {
+            IO::Http { req_template, .. } => (
+                "http",
+                req_template.method.to_string(),
+                Some(req_template.root_url.to_string()),
+            ),
+            IO::Grpc { req_template, .. } => (
+                "grpc",
+                match &req_template.dynamic_method {
+                    Some(dynamic_method) => dynamic_method.allow.join(", "),
+                    None => req_template.operation.method.full_name().to_string(),
+                },
+                Some(req_template.url.to_string()),
+            ),
+            IO::GraphQL { req_template, field_name, .. } => (
+                "graphQL",
+                format!("{} {}", req_template.operation_type, field_name),
+                Some(req_template.url.clone()),
+            ),
+            IO::Js { name } => ("js", name.clone(), None),
+        };
+
+        self.inventory.endpoints.push(UpstreamEndpoint {
+            field: self.field.clone(),
+            kind,
+            method,
+            url,
+            cache_ttl: self.cache_ttl,
+        });
+    }
+
+    fn visit_cache(&mut self, cache: &Cache) {
+        self.cache_ttl = Some(cache.max_age.get());
+    }
+}
+
+fn describe(auth: &Auth) -> String {
+    let operand = |auth: &Auth| match auth {
+        Auth::Provider(_) => describe(auth),
+        _ => format!("({})", describe(auth)),
+    };
+
+    match auth {
+        Auth::Provider(Provider::Basic(_)) => "basic".to_string(),
+        Auth::Provider(Provider::Jwt(_)) => "jwt".to_string(),
+        Auth::And(left, right) => format!("{} and {}", operand(left), operand(right)),
+        Auth::Or(left, right) => format!("{} or {}", operand(left), operand(right)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU64;
+
+    use serde_json::json;
+    use tailcall_valid::Validator;
+
+    use super::*;
+    use crate::core::config::{Config, ConfigModule, Content, Extensions};
+    use crate::core::http::RequestTemplate;
+    use crate::core::ir::model::CacheOptions;
+
+    const SDL: &str = r#"
+        schema @server @upstream {
+            query: Query
+        }
+
+        type Query {
+            users: [User] @http(url: "http://jsonplaceholder.typicode.com/users") @cache(maxAge: 3000)
+            me: User @http(url: "http://jsonplaceholder.typicode.com/me") @protected
+            posts: [Post] @graphQL(url: "http://upstream/graphql", name: "posts")
+        }
+
+        type User {
+            id: Int
+            name: String
+        }
+
+        type Post {
+            id: Int
+        }
+    "#;
+
+    #[test]
+    fn test_inventory() {
+        let config = Config::from_sdl(SDL).to_result().unwrap();
+        let htpasswd = Content { id: None, content: "user:password".to_string() };
+        let config_module = ConfigModule::from(config)
+            .set_extensions(Extensions { htpasswd: vec![htpasswd], ..Default::default() });
+        let blueprint = Blueprint::try_from(&config_module).unwrap();
+
+        let mut inventory = Inventory::from(&blueprint);
+        inventory.endpoints.sort_by(|a, b| a.field.cmp(&b.field));
+
+        assert_eq!(
+            serde_json::to_value(inventory).unwrap(),
+            json!({
+                "endpoints": [
+                    {
+                        "field": "Query.me",
+                        "kind": "http",
+                        "method": "GET",
+                        "url": "http://jsonplaceholder.typicode.com/me",
+                    },
+                    {
+                        "field": "Query.posts",
+                        "kind": "graphQL",
+                        "method": "query posts",
+                        "url": "http://upstream/graphql",
+                    },
+                    {
+                        "field": "Query.users",
+                        "kind": "http",
+                        "method": "GET",
+                        "url": "http://jsonplaceholder.typicode.com/users",
+                        "cacheTtl": 3000,
+                    },
+                ],
+                "protectedFields": [
+                    {"field": "Query.me", "auth": "basic", "fallback": false},
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_cache_ttl_of_every_io() {
+        let http = |url: &str| {
+            IR::IO(IO::Http {
+                req_template: RequestTemplate::new(url).unwrap(),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: false,
+                hook: None,
+            })
+        };
+        let cached = Cache::wrap(
+            NonZeroU64::new(3000).unwrap(),
+            CacheOptions::default(),
+            IR::Pipe(
+                Box::new(http("http://upstream/users")),
+                Box::new(http("http://upstream/posts")),
+            ),
+        );
+        let ir = IR::Pipe(Box::new(cached), Box::new(http("http://upstream/me")));
+
+        let mut inventory = Inventory::default();
+        ir.accept(&mut FieldInventory {
+            field: "Query.users".to_string(),
+            cache_ttl: None,
+            inventory: &mut inventory,
+        });
+
+        let ttls = inventory
+            .endpoints
+            .iter()
+            .map(|endpoint| endpoint.cache_ttl)
+            .collect::<Vec<_>>();
+        assert_eq!(ttls, vec![Some(3000), Some(3000), None]);
+    }
+}
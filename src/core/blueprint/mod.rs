@@ -1,5 +1,6 @@
 mod auth;
 mod blueprint;
+mod cache;
 mod compress;
 mod cors;
 mod definitions;
@@ -11,6 +12,7 @@ mod index;
 mod interface_resolver;
 mod into_document;
 mod into_schema;
+mod inventory;
 mod links;
 mod mustache;
 mod operators;
@@ -24,12 +26,14 @@ mod upstream;
 
 pub use auth::*;
 pub use blueprint::*;
+pub use cache::BlueprintCache;
 pub use cors::*;
 pub use definitions::*;
 pub use dynamic_value::*;
 pub use error::*;
 pub use from_config::*;
 pub use index::*;
+pub use inventory::*;
 pub use links::*;
 pub use operators::*;
 pub use schema::*;
@@ -1,9 +1,12 @@
 use tailcall_valid::{Valid, Validator};
 
-use super::{BlueprintError, FieldDefinition};
+use super::{BlueprintError, DynamicValue, FieldDefinition};
 use crate::core::config::{self, Config};
 use crate::core::directive::DirectiveCodec;
-use crate::core::ir::model::{IO, IR};
+use crate::core::ir::model::{Fallback, Join, Merge, IO, IR};
+use crate::core::ir::IrVisitor;
+use crate::core::mustache::Mustache;
+use crate::core::path::RUNTIME_ENV;
 use crate::core::scalar;
 
 struct MustachePartsValidator<'a> {
@@ -89,6 +92,19 @@ impl<'a> MustachePartsValidator<'a> {
                     return Valid::fail(BlueprintError::VarNotSetInServerConfig(tail.to_string()));
                 }
             }
+            "operation" => {
+                if !matches!(tail, "name" | "type") {
+                    return Valid::fail(BlueprintError::UnknownTemplateDirective(parts.join(".")));
+                }
+            }
+            "request" => {
+                if tail != "id" {
+                    return Valid::fail(BlueprintError::UnknownTemplateDirective(parts.join(".")));
+                }
+            }
+            RUNTIME_ENV => {
+                // reported for all the request templates by RuntimeEnvRefs
+            }
             "headers" | "env" => {
                 // "headers" and "env" refers to values known at runtime, which
                 // we can't validate here
@@ -103,8 +119,18 @@ impl<'a> MustachePartsValidator<'a> {
 
     fn validate_resolver(&self, resolver: &IR) -> Valid<(), BlueprintError> {
         match resolver {
-            IR::Merge(resolvers) => {
-                Valid::from_iter(resolvers, |resolver| self.validate_resolver(resolver)).unit()
+            IR::Merge(Merge { irs, .. }) | IR::Fallback(Fallback { irs, .. }) => {
+                Valid::from_iter(irs, |resolver| self.validate_resolver(resolver)).unit()
+            }
+            IR::Join(Join { left, right, .. }) => {
+                // the right side is resolved with the elements of the left side as the value
+                let right = match self.config.find_type(self.field.of_type.name()) {
+                    Some(type_of) => MustachePartsValidator::new(type_of, self.config, self.field)
+                        .validate_resolver(right),
+                    None => Valid::succeed(()),
+                };
+
+                self.validate_resolver(left).and(right)
             }
             IR::IO(IO::Http { req_template, .. }) => {
                 Valid::from_iter(req_template.root_url.expression_segments(), |parts| {
@@ -175,6 +201,105 @@ impl<'a> MustachePartsValidator<'a> {
     }
 }
 
+/// The expressions of the template that read the `runtimeEnv` values.
+fn runtime_env_refs(mustache: &Mustache) -> impl Iterator<Item = String> + '_ {
+    mustache
+        .expression_segments()
+        .into_iter()
+        .filter(|parts| parts.first().is_some_and(|head| head == RUNTIME_ENV))
+        .map(|parts| parts.join("."))
+}
+
+/// Fails when the templates of the value read the `runtimeEnv` values.
+/// They're only read by the `@expr(runtimeEnv)` templates, that are
+/// rewritten to read them, and would otherwise be sent to the upstreams or
+/// the clients.
+pub fn validate_runtime_env<A>(value: &DynamicValue<A>) -> Valid<(), BlueprintError> {
+    match value {
+        DynamicValue::Value(_) => Valid::succeed(()),
+        DynamicValue::Mustache(mustache) => Valid::from_iter(runtime_env_refs(mustache), |path| {
+            Valid::<(), BlueprintError>::fail(BlueprintError::RuntimeEnvNotAllowed(path))
+        })
+        .unit(),
+        DynamicValue::Object(obj) => Valid::from_iter(obj.values(), validate_runtime_env).unit(),
+        DynamicValue::Array(arr) => Valid::from_iter(arr, validate_runtime_env).unit(),
+        DynamicValue::Cond(cond, then, otherwise) => validate_runtime_env(cond)
+            .and(validate_runtime_env(then))
+            .and(validate_runtime_env(otherwise)),
+        DynamicValue::Coalesce(value, default) => {
+            validate_runtime_env(value).and(validate_runtime_env(default))
+        }
+    }
+}
+
+/// Collects the expressions of the request templates of the IOs that read
+/// the `runtimeEnv` values, including the ones of the IOs wrapped eg. by
+/// `@cache`.
+#[derive(Default)]
+struct RuntimeEnvRefs {
+    refs: Vec<String>,
+}
+
+impl RuntimeEnvRefs {
+    fn collect(ir: &IR) -> Vec<String> {
+        let mut visitor = Self::default();
+        ir.accept(&mut visitor);
+
+        visitor.refs
+    }
+
+    fn check<'a>(&mut self, templates: impl IntoIterator<Item = &'a Mustache>) {
+        for template in templates {
+            self.refs.extend(runtime_env_refs(template));
+        }
+    }
+}
+
+impl IrVisitor for RuntimeEnvRefs {
+    fn visit_io(&mut self, io: &IO) {
+        match io {
+            IO::Http { req_template, .. } => {
+                self.check([&req_template.root_url]);
+                self.check(req_template.query.iter().map(|query| &query.value));
+                self.check(req_template.headers.iter().map(|(_, value)| value));
+                self.check(&req_template.body_path);
+                self.check(
+                    req_template
+                        .dynamic_headers
+                        .iter()
+                        .map(|headers| &headers.value),
+                );
+            }
+            IO::GraphQL { req_template, .. } => {
+                self.check(req_template.headers.iter().map(|(_, value)| value));
+                self.check(
+                    req_template
+                        .operation_arguments
+                        .iter()
+                        .flatten()
+                        .map(|(_, value)| value),
+                );
+            }
+            IO::Grpc { req_template, .. } => {
+                self.check([&req_template.url]);
+                self.check(req_template.headers.iter().map(|(_, value)| value));
+                self.check(req_template.body.iter().flat_map(|body| &body.mustache));
+                self.check(
+                    req_template
+                        .dynamic_method
+                        .iter()
+                        .map(|method| &method.name),
+                );
+                if let Some(routes) = &req_template.routes {
+                    self.check([&routes.by]);
+                    self.check(routes.urls.values());
+                }
+            }
+            IO::Js { .. } => {}
+        }
+    }
+}
+
 impl FieldDefinition {
     pub fn validate_field(
         &self,
@@ -190,7 +315,12 @@ impl FieldDefinition {
         let parts_validator = MustachePartsValidator::new(type_of, config, self);
 
         match &self.resolver {
-            Some(resolver) => parts_validator.validate_resolver(resolver),
+            Some(resolver) => parts_validator.validate_resolver(resolver).and(
+                Valid::from_iter(RuntimeEnvRefs::collect(resolver), |path| {
+                    Valid::<(), BlueprintError>::fail(BlueprintError::RuntimeEnvNotAllowed(path))
+                })
+                .unit(),
+            ),
             None => Valid::succeed(()),
         }
     }
@@ -198,11 +328,15 @@ impl FieldDefinition {
 
 #[cfg(test)]
 mod test {
+    use http::header::HeaderName;
     use tailcall_valid::Validator;
 
     use super::MustachePartsValidator;
     use crate::core::blueprint::{FieldDefinition, InputFieldDefinition};
     use crate::core::config::{self, Config, Field};
+    use crate::core::http::RequestTemplate;
+    use crate::core::ir::model::{IO, IR};
+    use crate::core::mustache::Mustache;
     use crate::core::Type;
 
     fn initialize_test_config_and_field() -> (Config, FieldDefinition) {
@@ -264,4 +398,51 @@ mod test {
 
         assert!(validation_result.to_result().is_err())
     }
+
+    #[test]
+    fn test_request_id() {
+        let (config, field_def) = initialize_test_config_and_field();
+
+        let parts_validator =
+            MustachePartsValidator::new(config.types.get("T1").unwrap(), &config, &field_def);
+
+        assert!(parts_validator
+            .validate(&["request".to_string(), "id".to_string()], false)
+            .is_succeed());
+        assert!(parts_validator
+            .validate(&["request".to_string(), "method".to_string()], false)
+            .to_result()
+            .is_err());
+    }
+
+    #[test]
+    fn test_runtime_env_in_request_templates() {
+        let (config, mut field_def) = initialize_test_config_and_field();
+        let header = (
+            HeaderName::from_static("authorization"),
+            Mustache::parse("{{.runtimeEnv.TOKEN}}"),
+        );
+        let req_template = RequestTemplate::new("http://localhost:8080/users")
+            .unwrap()
+            .headers(vec![header]);
+        let io = IR::IO(IO::Http {
+            req_template,
+            group_by: None,
+            dl_id: None,
+            is_list: false,
+            dedupe: false,
+            hook: None,
+        });
+        // the IOs wrapped by other nodes are checked too
+        field_def.resolver = Some(IR::Pipe(Box::new(io), Box::new(IR::ContextPath(vec![]))));
+
+        let errors = field_def
+            .validate_field(config.types.get("T1").unwrap(), &config)
+            .to_result()
+            .unwrap_err();
+        assert_eq!(
+            errors.as_vec()[0].message.to_string(),
+            "'runtimeEnv.TOKEN' can only be read with @expr(runtimeEnv), use '.env' instead"
+        );
+    }
 }
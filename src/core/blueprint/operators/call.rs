@@ -12,7 +12,17 @@ pub fn compile_call(
     operation_type: &GraphQLOperationType,
     object_name: &str,
 ) -> Valid<IR, BlueprintError> {
-    Valid::from_iter(call.steps.iter(), |step| {
+    // the streams of the subscription fields can't be piped into the next steps
+    let is_streaming = call.steps.iter().any(|step| step.subscription.is_some());
+    let validate = if *operation_type == GraphQLOperationType::Subscription {
+        Valid::<(), BlueprintError>::fail(BlueprintError::SubscriptionCallMustBeStreaming)
+            .when(|| !is_streaming || call.steps.len() > 1)
+    } else {
+        Valid::<(), BlueprintError>::fail(BlueprintError::SubscriptionCallNotStreaming)
+            .when(|| is_streaming)
+    };
+
+    let steps = Valid::from_iter(call.steps.iter(), |step| {
         get_field_and_field_name(step, config_module).and_then(|(field, field_name, type_of)| {
             let args = step.args.iter();
 
@@ -87,8 +97,9 @@ pub fn compile_call(
             }),
             BlueprintError::StepsCanNotBeEmpty,
         )
-    })
-    .and_then(|field| {
+    });
+
+    validate.and(steps).and_then(|field| {
         Valid::from_option(field.resolver, BlueprintError::ResultResolverCanNotBeEmpty)
     })
 }
@@ -98,10 +109,12 @@ fn get_type_and_field(call: &config::Step) -> Option<(String, String)> {
     // config_module and should not be static values
     if let Some(query) = &call.query {
         Some(("Query".to_string(), query.clone()))
+    } else if let Some(mutation) = &call.mutation {
+        Some(("Mutation".to_string(), mutation.clone()))
     } else {
-        call.mutation
+        call.subscription
             .as_ref()
-            .map(|mutation| ("Mutation".to_string(), mutation.clone()))
+            .map(|subscription| ("Subscription".to_string(), subscription.clone()))
     }
 }
 
@@ -129,3 +142,121 @@ fn get_field_and_field_name<'a>(
         })
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use async_graphql_value::ConstValue;
+    use futures_util::StreamExt;
+    use serde_json::json;
+    use tailcall_valid::Validator;
+
+    use crate::core::app_context::test::TestHttp;
+    use crate::core::blueprint::{Blueprint, Definition};
+    use crate::core::config::{Config, ConfigModule};
+    use crate::core::http::RequestContext;
+    use crate::core::ir::model::IR;
+    use crate::core::ir::{EmptyResolverContext, EvalContext};
+
+    const CONFIG: &str = r#"
+        schema @server @upstream {
+            query: Query
+            subscription: Subscription
+        }
+
+        type Query {
+            news: [News] @http(url: "http://localhost:8080/news")
+        }
+
+        type Subscription {
+            news(since: Int!): News @http(url: "http://localhost:8080/news/events?since={{.args.since}}")
+            latest: News @call(steps: [{subscription: "news", args: {since: 10}}])
+        }
+
+        type News {
+            id: Int
+        }
+    "#;
+
+    fn errors(config: &str) -> Vec<String> {
+        let config = Config::from_sdl(config).to_result().unwrap();
+        Blueprint::try_from(&ConfigModule::from(config))
+            .unwrap_err()
+            .as_vec()
+            .iter()
+            .map(|cause| cause.message.to_string())
+            .collect()
+    }
+
+    fn resolver(blueprint: &Blueprint, field_name: &str) -> IR {
+        blueprint
+            .definitions
+            .iter()
+            .find_map(|def| match def {
+                Definition::Object(def) if def.name == "Subscription" => {
+                    def.fields.iter().find(|field| field.name == field_name)
+                }
+                _ => None,
+            })
+            .and_then(|field| field.resolver.clone())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_subscription_call() {
+        let config = Config::from_sdl(CONFIG).to_result().unwrap();
+        let blueprint = Blueprint::try_from(&ConfigModule::from(config)).unwrap();
+        let http = TestHttp::body(|_| Ok("{\"id\":11}\n{\"id\":12}\n"));
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.http = http.clone();
+
+        let req_ctx = RequestContext::new(runtime);
+        let res_ctx = EmptyResolverContext {};
+        let ctx = EvalContext::new(&req_ctx, &res_ctx);
+        let events = resolver(&blueprint, "latest")
+            .subscribe(&ctx)
+            .await
+            .unwrap()
+            .map(|event| event.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+
+        // the stream of the called field is opened with the arguments of the step
+        assert_eq!(
+            http.urls(),
+            vec!["http://localhost:8080/news/events?since=10"]
+        );
+        assert_eq!(
+            events,
+            vec![
+                ConstValue::from_json(json!({"id": 11})).unwrap(),
+                ConstValue::from_json(json!({"id": 12})).unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_subscription_call_not_streaming() {
+        let config = CONFIG.replace(
+            r#"{subscription: "news", args: {since: 10}}"#,
+            r#"{query: "news"}"#,
+        );
+
+        assert_eq!(
+            errors(&config),
+            vec!["Subscription fields can only call a single field of the Subscription type"]
+        );
+    }
+
+    #[test]
+    fn test_subscription_called_from_query() {
+        let config = CONFIG.replace(
+            r#"news: [News] @http(url: "http://localhost:8080/news")"#,
+            r#"news: News @call(steps: [{subscription: "news", args: {since: 10}}])"#,
+        );
+
+        assert_eq!(
+            errors(&config),
+            vec!["Fields of the Subscription type can only be called from the subscription fields"]
+        );
+    }
+}
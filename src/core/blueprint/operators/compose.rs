@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+use std::convert::identity;
+
+use serde_json::{json, Value};
+use tailcall_valid::{Valid, Validator};
+
+use super::compile_http;
+use crate::core::blueprint::{BlueprintError, DynamicValue};
+use crate::core::config::{self, Arg, ConfigModule, Field};
+use crate::core::ir::model::{Merge, IR};
+
+fn to_dynamic(value: &Value) -> Valid<IR, BlueprintError> {
+    match DynamicValue::try_from(value) {
+        Ok(value) => Valid::succeed(IR::Dynamic(value)),
+        Err(e) => Valid::fail(BlueprintError::Error(e)),
+    }
+}
+
+/// Compiles the calls of a step, that run concurrently with the outputs of
+/// the previous steps as their arguments. The outputs of the calls are merged
+/// into the arguments under the names of the calls, so that the next steps
+/// receive the outputs of all the calls before them.
+fn compile_step(
+    config_module: &ConfigModule,
+    step: &[config::ComposeCall],
+    field: &Field,
+) -> Valid<IR, BlueprintError> {
+    Valid::from_iter(step.iter(), |call| {
+        Valid::<(), BlueprintError>::fail(BlueprintError::ComposeCallCanNotBeBatched)
+            .when(|| call.http.is_batched())
+            .and(compile_http(config_module, &call.http, field))
+            .zip(to_dynamic(&Value::Object(
+                [(call.name.clone(), json!("{{.args}}"))]
+                    .into_iter()
+                    .collect(),
+            )))
+            .map(|(io, output)| io.pipe(output))
+            .trace(&call.name)
+    })
+    .and_then(|calls| {
+        if calls.is_empty() {
+            return Valid::fail(BlueprintError::StepsCanNotBeEmpty);
+        }
+
+        to_dynamic(&json!("{{.args}}"))
+            .map(|args| IR::Merge(Merge::new(std::iter::once(args).chain(calls).collect())))
+    })
+}
+
+pub fn compile_compose(
+    config_module: &ConfigModule,
+    compose: &config::Compose,
+    field: &Field,
+) -> Valid<IR, BlueprintError> {
+    // the calls are type checked against the arguments of the field, along with
+    // the outputs of the calls of the previous steps
+    let mut names = field.args.keys().cloned().collect::<HashSet<_>>();
+    let mut field = Field { args: field.args.clone(), ..Default::default() };
+    let mut steps = Vec::with_capacity(compose.steps.len());
+
+    for step in compose.steps.iter() {
+        let taken = step
+            .iter()
+            .find(|call| !names.insert(call.name.clone()))
+            .map(|call| call.name.clone());
+
+        steps.push(match taken {
+            Some(name) => Valid::fail(BlueprintError::ComposeCallNameTaken(name)),
+            None => compile_step(config_module, step, &field),
+        });
+
+        for call in step {
+            field.args.insert(call.name.clone(), Arg::default());
+        }
+    }
+
+    Valid::from_iter(steps, identity)
+        .and_then(|steps| {
+            Valid::from_option(
+                steps.into_iter().reduce(|ir, step| ir.pipe(step)),
+                BlueprintError::StepsCanNotBeEmpty,
+            )
+        })
+        .trace("steps")
+        .zip(to_dynamic(&compose.result).trace("result"))
+        .map(|(steps, result)| steps.pipe(result))
+}
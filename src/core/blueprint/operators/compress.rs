@@ -0,0 +1,38 @@
+use tailcall_valid::Valid;
+
+use crate::core::blueprint::{BlueprintError, FieldDefinition};
+use crate::core::config::{self, ConfigModule, Field};
+use crate::core::ir::model::{Compress, IR};
+use crate::core::try_fold::TryFold;
+
+pub fn update_compress<'a>() -> TryFold<
+    'a,
+    (&'a ConfigModule, &'a Field, &'a config::Type, &'a str),
+    FieldDefinition,
+    BlueprintError,
+> {
+    TryFold::<(&ConfigModule, &Field, &config::Type, &'a str), FieldDefinition, BlueprintError>::new(
+        |(_, field, _, _), mut b_field| {
+            let Some(compress) = &field.compress else {
+                return Valid::succeed(b_field);
+            };
+
+            // the compressed value is encoded as a string
+            if field.type_of.is_list() || field.type_of.name() != "String" {
+                return Valid::fail(BlueprintError::CompressedFieldNotString);
+            }
+
+            let input = b_field
+                .resolver
+                .take()
+                .unwrap_or_else(|| IR::ContextPath(vec![b_field.name.clone()]));
+
+            b_field.resolver = Some(IR::Compress(Compress {
+                input: Box::new(input),
+                min_size: compress.get_min_size(),
+            }));
+
+            Valid::succeed(b_field)
+        },
+    )
+}
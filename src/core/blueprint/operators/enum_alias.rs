@@ -1,11 +1,12 @@
 use std::collections::HashMap;
 
-use tailcall_valid::Valid;
+use tailcall_valid::{Valid, Validator};
 
 use crate::core::blueprint::*;
 use crate::core::config;
-use crate::core::config::Field;
-use crate::core::ir::model::{Map, IR};
+use crate::core::config::{EnumAlias, Field, OnMissing};
+use crate::core::directive::DirectiveCodec;
+use crate::core::ir::model::{Map, MapMissing, IR};
 use crate::core::try_fold::TryFold;
 
 pub fn update_enum_alias<'a>() -> TryFold<
@@ -19,7 +20,7 @@ pub fn update_enum_alias<'a>() -> TryFold<
             let enum_type = config.enums.get(field.type_of.name());
             if let Some(enum_type) = enum_type {
                 let has_alias = enum_type.variants.iter().any(|v| v.alias.is_some());
-                if !has_alias {
+                if !has_alias && enum_type.enum_alias.is_none() {
                     return Valid::succeed(b_field);
                 }
                 let mut map = HashMap::<String, String>::new();
@@ -31,11 +32,41 @@ pub fn update_enum_alias<'a>() -> TryFold<
                         }
                     }
                 }
-                b_field.resolver = b_field
-                    .resolver
-                    .map(|r| IR::Map(Map { input: Box::new(r), map }));
+                let enum_alias = enum_type.enum_alias.clone().unwrap_or_default();
+
+                return to_map_missing(enum_type, enum_alias)
+                    .trace(EnumAlias::trace_name().as_str())
+                    .map(|on_missing| {
+                        b_field.resolver = b_field
+                            .resolver
+                            .map(|r| IR::Map(Map { input: Box::new(r), map, on_missing }));
+                        b_field
+                    });
             }
             Valid::succeed(b_field)
         },
     )
 }
+
+fn to_map_missing(
+    enum_type: &config::Enum,
+    enum_alias: EnumAlias,
+) -> Valid<MapMissing, BlueprintError> {
+    match (enum_alias.on_missing, enum_alias.default) {
+        (OnMissing::Null, _) => Valid::succeed(MapMissing::Null),
+        (OnMissing::Passthrough, _) => Valid::succeed(MapMissing::Passthrough),
+        (OnMissing::Error, _) => Valid::succeed(MapMissing::Error),
+        (OnMissing::Default, Some(default)) => {
+            if enum_type.variants.iter().any(|v| v.name == default) {
+                Valid::succeed(MapMissing::Default(default))
+            } else {
+                Valid::fail(BlueprintError::InvalidEnumAlias(format!(
+                    "`{default}` is not a variant of the enum"
+                )))
+            }
+        }
+        (OnMissing::Default, None) => Valid::fail(BlueprintError::InvalidEnumAlias(
+            "`default` is required with `onMissing: Default`".to_string(),
+        )),
+    }
+}
@@ -1,6 +1,7 @@
 use async_graphql_value::ConstValue;
 use tailcall_valid::{Valid, Validator};
 
+use crate::core::blueprint::mustache::validate_runtime_env;
 use crate::core::blueprint::*;
 use crate::core::config;
 use crate::core::config::Expr;
@@ -33,11 +34,19 @@ pub fn compile_expr(inputs: CompileExpr) -> Valid<IR, BlueprintError> {
     let field = inputs.field;
     let value = &inputs.expr.body;
     let validate = inputs.validate;
+    let runtime_env = inputs.expr.runtime_env;
 
     match DynamicValue::try_from(&value.clone()) {
-        Ok(data) => Valid::succeed(data),
+        Ok(data) => validate_runtime_env(&data).map(|_| data),
         Err(err) => Valid::fail(BlueprintError::Error(err)),
     }
+    .map(|data| {
+        if runtime_env {
+            data.with_runtime_env()
+        } else {
+            data
+        }
+    })
     .and_then(|value| {
         if !value.is_const() {
             // TODO: Add validation for const with Mustache here
@@ -53,6 +53,23 @@ fn create_related_fields(
     RelatedFields(map)
 }
 
+/// The batches are sent as an operation of the name, so it has to be a valid
+/// GraphQL name.
+fn validate_batch_name(name: Option<&str>) -> Valid<(), BlueprintError> {
+    let Some(name) = name else {
+        return Valid::succeed(());
+    };
+    let mut chars = name.chars();
+    let is_valid = chars
+        .next()
+        .is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+
+    Valid::<(), BlueprintError>::fail(BlueprintError::InvalidBatchName(name.to_string()))
+        .when(|| !is_valid)
+        .trace("batchName")
+}
+
 pub fn compile_graphql(
     config: &ConfigModule,
     operation_type: &GraphQLOperationType,
@@ -66,7 +83,8 @@ pub fn compile_graphql(
         Err(err) => Valid::from_validation_err(BlueprintError::from_validation_string(err)),
     };
 
-    Valid::succeed(graphql.url.as_str())
+    validate_batch_name(graphql.batch_name.as_deref())
+        .and(Valid::succeed(graphql.url.as_str()))
         .zip(mustache)
         .and_then(|(base_url, headers)| {
             match RequestTemplate::new(
@@ -84,7 +102,62 @@ pub fn compile_graphql(
         .map(|req_template| {
             let field_name = graphql.name.clone();
             let batch = graphql.batch;
-            let dedupe = graphql.dedupe;
-            IR::IO(IO::GraphQL { req_template, field_name, batch, dl_id: None, dedupe })
+            let batch_style = graphql.batch_style;
+            let batch_name = graphql.batch_name.clone();
+            let dedupe = graphql.dedupe.unwrap_or_else(|| {
+                config.upstream.get_dedupe() && *operation_type == GraphQLOperationType::Query
+            });
+            let error_policy = graphql.error_policy;
+            IR::IO(IO::GraphQL {
+                req_template,
+                field_name,
+                batch,
+                batch_style,
+                batch_name,
+                dl_id: None,
+                dedupe,
+                error_policy,
+            })
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use tailcall_valid::Validator;
+
+    use super::*;
+    use crate::core::config::Upstream;
+
+    fn dedupe(operation_type: GraphQLOperationType) -> bool {
+        let config = Config {
+            upstream: Upstream { dedupe: Some(true), ..Default::default() },
+            ..Default::default()
+        };
+        let config_module = ConfigModule::from(config);
+        let graphql = GraphQL {
+            url: "http://localhost:3000/graphql".to_string(),
+            name: "users".to_string(),
+            ..Default::default()
+        };
+
+        match compile_graphql(&config_module, &operation_type, "User", &graphql).to_result() {
+            Ok(IR::IO(io)) => io.dedupe(),
+            ir => panic!("unexpected IR: {ir:?}"),
+        }
+    }
+
+    #[test]
+    fn test_dedupe_default() {
+        assert!(dedupe(GraphQLOperationType::Query));
+        assert!(!dedupe(GraphQLOperationType::Mutation));
+    }
+
+    #[test]
+    fn test_batch_name() {
+        assert!(validate_batch_name(None).is_succeed());
+        assert!(validate_batch_name(Some("_UsersBatch2")).is_succeed());
+        assert!(!validate_batch_name(Some("users-batch")).is_succeed());
+        assert!(!validate_batch_name(Some("2users")).is_succeed());
+        assert!(!validate_batch_name(Some("")).is_succeed());
+    }
+}
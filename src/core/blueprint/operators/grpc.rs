@@ -1,23 +1,28 @@
 use std::fmt::Display;
+use std::time::Duration;
 
 use prost_reflect::prost_types::FileDescriptorSet;
-use prost_reflect::FieldDescriptor;
+use prost_reflect::{FieldDescriptor, Kind};
 use tailcall_valid::{Valid, ValidationError, Validator};
 
-use super::apply_select;
+use super::{apply_compact, apply_filter, apply_select};
 use crate::core::blueprint::BlueprintError;
 use crate::core::config::group_by::GroupBy;
 use crate::core::config::{Config, ConfigModule, Field, GraphQLOperationType, Grpc};
-use crate::core::grpc::protobuf::{ProtobufOperation, ProtobufSet};
-use crate::core::grpc::request_template::RequestTemplate;
+use crate::core::grpc::field_mask::FIELD_MASK;
+use crate::core::grpc::protobuf::{EnumAsString, ProtobufOperation, ProtobufSet};
+use crate::core::grpc::request_template::{DynamicMethod, RequestBody, RequestTemplate, Routes};
+use crate::core::grpc::retry::Retry;
+use crate::core::grpc::transcoding::Transcoding;
 use crate::core::helpers;
+use crate::core::helpers::headers::MustacheHeaders;
 use crate::core::ir::model::{IO, IR};
 use crate::core::json::JsonSchema;
 use crate::core::mustache::Mustache;
 use crate::core::worker_hooks::WorkerHooks;
 
-fn to_url(grpc: &Grpc, method: &GrpcMethod) -> Valid<Mustache, String> {
-    Valid::succeed(grpc.url.as_str()).and_then(|base_url| {
+fn to_url(url: &str, method: &GrpcMethod) -> Valid<Mustache, String> {
+    Valid::succeed(url).and_then(|base_url| {
         let mut base_url = base_url.trim_end_matches('/').to_owned();
         base_url.push('/');
         base_url.push_str(format!("{}.{}", method.package, method.service).as_str());
@@ -28,6 +33,70 @@ fn to_url(grpc: &Grpc, method: &GrpcMethod) -> Valid<Mustache, String> {
     })
 }
 
+/// The url of the upstream of a method rendered at request time, the path of
+/// the method is appended to it once it's known.
+fn to_url_base(url: &str) -> Valid<Mustache, String> {
+    helpers::url::to_url(url.trim_end_matches('/'))
+}
+
+fn to_retry(grpc: &Grpc) -> Valid<Option<Retry>, BlueprintError> {
+    let Some(retry) = grpc.retry.as_ref() else {
+        return Valid::succeed(None);
+    };
+
+    let max_attempts = retry.max_attempts.unwrap_or(3);
+    Valid::<(), BlueprintError>::fail(BlueprintError::InvalidGrpcRetry(
+        "`maxAttempts` must be greater than 0".to_string(),
+    ))
+    .when(|| max_attempts == 0)
+    .and(
+        Valid::<(), BlueprintError>::fail(BlueprintError::InvalidGrpcRetry(
+            "`deadline` must be greater than 0".to_string(),
+        ))
+        .when(|| retry.deadline == 0),
+    )
+    .map(|_| {
+        Some(Retry {
+            max_attempts,
+            deadline: Duration::from_millis(retry.deadline),
+        })
+    })
+    .trace("retry")
+}
+
+/// The upstreams of `routes`, their urls are compiled like the `url` of the
+/// call. `method` is the method of the call if it isn't rendered at request
+/// time.
+fn to_routes(grpc: &Grpc, method: Option<&GrpcMethod>) -> Valid<Option<Routes>, BlueprintError> {
+    let route_by = match (&grpc.route_by, grpc.routes.is_empty()) {
+        (None, true) => return Valid::succeed(None),
+        (Some(route_by), false) => route_by,
+        _ => return Valid::fail(BlueprintError::GrpcRoutesIncomplete),
+    };
+
+    Valid::<(), BlueprintError>::fail(BlueprintError::GrpcRoutesCanNotBeBatched)
+        .when(|| !grpc.batch_key.is_empty())
+        .and(Valid::from_iter(grpc.routes.iter(), |route| {
+            let url = match method {
+                Some(method) => to_url(&route.value, method),
+                None => to_url_base(&route.value),
+            };
+            let url = match url.to_result() {
+                Ok(url) => Valid::succeed((route.key.clone(), url)),
+                Err(e) => Valid::from_validation_err(BlueprintError::from_validation_string(e)),
+            };
+
+            url.trace(&route.key)
+        }))
+        .map(|urls| {
+            Some(Routes {
+                by: Mustache::parse(route_by),
+                urls: urls.into_iter().collect(),
+            })
+        })
+        .trace("routes")
+}
+
 fn to_operation(
     method: &GrpcMethod,
     file_descriptor_set: FileDescriptorSet,
@@ -73,7 +142,11 @@ fn validate_schema(
         Err(e) => Valid::from_validation_err(BlueprintError::from_validation_string(e)),
     };
 
+    // the messages of a server stream are resolved to a list
     let output_type = match JsonSchema::try_from(output_type) {
+        Ok(output_type) if operation.method.is_server_streaming() => {
+            Valid::succeed(JsonSchema::Arr(Box::new(output_type)))
+        }
         Ok(output_type) => Valid::succeed(output_type),
         Err(e) => Valid::from_validation_err(BlueprintError::from_validation_string(e)),
     };
@@ -139,6 +212,77 @@ fn validate_group_by(
         })
 }
 
+/// The field mask is a field of the request message of the
+/// `google.protobuf.FieldMask` type.
+fn validate_field_mask(
+    operation: &ProtobufOperation,
+    field_mask: Option<&str>,
+) -> Valid<(), BlueprintError> {
+    let Some(name) = field_mask else {
+        return Valid::succeed(());
+    };
+    let is_field_mask = operation
+        .input_type
+        .get_field_by_name(name)
+        .or_else(|| operation.input_type.get_field_by_json_name(name))
+        .is_some_and(|field| {
+            !field.is_list()
+                && matches!(field.kind(), Kind::Message(message) if message.full_name() == FIELD_MASK)
+        });
+
+    Valid::<(), BlueprintError>::fail(BlueprintError::InvalidFieldMask(name.to_string()))
+        .when(|| !is_field_mask)
+        .trace("fieldMask")
+}
+
+/// The operation whose descriptors the methods of the allowlist are looked up
+/// in, when the method of the `@grpc` is rendered at request time. All the
+/// entries have to be in the descriptors.
+fn to_dynamic_operation(
+    config_module: &ConfigModule,
+    grpc: &Grpc,
+) -> Valid<ProtobufOperation, BlueprintError> {
+    let file_descriptor_set = config_module.extensions().get_file_descriptor_set();
+    if file_descriptor_set.file.is_empty() {
+        return Valid::fail(BlueprintError::ProtobufFilesNotSpecifiedInConfig);
+    }
+    let set = match ProtobufSet::from_proto_file(file_descriptor_set) {
+        Ok(set) => set,
+        Err(e) => return Valid::fail(BlueprintError::Error(e)),
+    };
+
+    Valid::<(), BlueprintError>::fail(BlueprintError::GrpcDynamicMethodNotAllowed(
+        grpc.method.clone(),
+    ))
+    .when(|| grpc.allow.is_empty())
+    .and(
+        Valid::<(), BlueprintError>::fail(BlueprintError::GrpcDynamicMethodUnsupported)
+            .when(|| !grpc.batch_key.is_empty() || grpc.field_mask.is_some() || grpc.transcode),
+    )
+    .and(Valid::from_iter(grpc.allow.iter(), |allowed| {
+        let operation = set
+            .get_service(allowed)
+            .ok()
+            .and_then(|service| service.operations().next())
+            .or_else(|| {
+                let method = GrpcMethod::try_from(allowed.as_str()).ok()?;
+                set.find_service(&method).ok()?.find_operation(&method).ok()
+            });
+
+        match operation {
+            Some(operation) => Valid::succeed(operation),
+            None => Valid::fail(BlueprintError::GrpcMethodNotFound(allowed.clone())),
+        }
+    }))
+    .and_then(|operations| match operations.into_iter().next() {
+        Some(operation) => Valid::succeed(operation),
+        None => Valid::fail(BlueprintError::GrpcDynamicMethodNotAllowed(
+            grpc.method.clone(),
+        )),
+    })
+    .trace("allow")
+}
+
 pub struct CompileGrpc<'a> {
     pub config_module: &'a ConfigModule,
     pub operation_type: &'a GraphQLOperationType,
@@ -179,14 +323,24 @@ impl TryFrom<&str> for GrpcMethod {
     }
 }
 
-pub fn compile_grpc(inputs: CompileGrpc) -> Valid<IR, BlueprintError> {
-    let config_module = inputs.config_module;
-    let operation_type = inputs.operation_type;
-    let field = inputs.field;
-    let grpc = inputs.grpc;
-    let validate_with_schema = inputs.validate_with_schema;
-    let dedupe = grpc.dedupe.unwrap_or_default();
-
+/// Compiles the method of the `@grpc` that's known in advance, the field is
+/// validated against the types of its messages.
+#[allow(clippy::type_complexity)]
+fn compile_static_method(
+    config_module: &ConfigModule,
+    field: &Field,
+    grpc: &Grpc,
+    validate_with_schema: bool,
+) -> Valid<
+    (
+        Mustache,
+        MustacheHeaders,
+        ProtobufOperation,
+        Option<RequestBody>,
+        Option<Transcoding>,
+    ),
+    BlueprintError,
+> {
     Valid::from(GrpcMethod::try_from(grpc.method.as_str()))
         .and_then(|method| {
             let file_descriptor_set = config_module.extensions().get_file_descriptor_set();
@@ -195,8 +349,15 @@ pub fn compile_grpc(inputs: CompileGrpc) -> Valid<IR, BlueprintError> {
                 return Valid::fail(BlueprintError::ProtobufFilesNotSpecifiedInConfig);
             }
 
+            // the path of the transcoded method is the one of its mapping
+            let url = if grpc.transcode {
+                to_url_base(&grpc.url)
+            } else {
+                to_url(&grpc.url, &method)
+            };
+
             match to_operation(&method, file_descriptor_set)
-                .fuse(to_url(grpc, &method))
+                .fuse(url)
                 .fuse(helpers::headers::to_mustache_headers(&grpc.headers))
                 .fuse(helpers::body::to_body(grpc.body.as_ref()))
                 .to_result()
@@ -216,34 +377,144 @@ pub fn compile_grpc(inputs: CompileGrpc) -> Valid<IR, BlueprintError> {
             } else {
                 Valid::succeed(())
             };
-            validation.map(|_| (url, headers, operation, body))
+            validation
+                .and(validate_field_mask(&operation, grpc.field_mask.as_deref()))
+                .and(
+                    Valid::<(), BlueprintError>::fail(
+                        BlueprintError::GrpcClientStreamingCanNotBeBatched,
+                    )
+                    .when(|| operation.method.is_client_streaming() && !grpc.batch_key.is_empty()),
+                )
+                .and(
+                    Valid::<(), BlueprintError>::fail(
+                        BlueprintError::GrpcServerStreamingRequiresList,
+                    )
+                    .when(|| {
+                        operation.method.is_server_streaming()
+                            && (!field.type_of.is_list() || !grpc.batch_key.is_empty())
+                    }),
+                )
+                .and(to_transcoding(grpc, &operation))
+                .map(|transcoding| (url, headers, operation, body, transcoding))
         })
-        .map(|(url, headers, operation, body)| {
-            let req_template = RequestTemplate {
-                url,
-                headers,
-                operation,
-                body,
-                operation_type: operation_type.clone(),
-            };
-            let on_response = grpc.on_response_body.clone();
-            let hook = WorkerHooks::try_new(None, on_response).ok();
-
-            let io = if !grpc.batch_key.is_empty() {
-                IR::IO(IO::Grpc {
-                    req_template,
-                    group_by: Some(GroupBy::new(grpc.batch_key.clone(), None)),
-                    dl_id: None,
-                    dedupe,
-                    hook,
-                })
-            } else {
-                IR::IO(IO::Grpc { req_template, group_by: None, dl_id: None, dedupe, hook })
-            };
+}
 
-            (io, &grpc.select)
-        })
+/// The HTTP mapping of the method that's transcoded. The request and the
+/// response of the mapping are a single message.
+fn to_transcoding(
+    grpc: &Grpc,
+    operation: &ProtobufOperation,
+) -> Valid<Option<Transcoding>, BlueprintError> {
+    if !grpc.transcode {
+        return Valid::succeed(None);
+    }
+
+    let method = &operation.method;
+    let unsupported = if !grpc.batch_key.is_empty() {
+        Some("the transcoded methods can't be batched".to_string())
+    } else if method.is_client_streaming() || method.is_server_streaming() {
+        Some(format!("{} is a streaming method", method.full_name()))
+    } else {
+        None
+    };
+    if let Some(reason) = unsupported {
+        return Valid::fail(BlueprintError::GrpcTranscodingUnsupported(reason)).trace("transcode");
+    }
+
+    match Transcoding::from_method(method) {
+        Ok(Some(transcoding)) => Valid::succeed(Some(transcoding)),
+        Ok(None) => Valid::fail(BlueprintError::GrpcTranscodingUnsupported(format!(
+            "{} has no `google.api.http` annotation",
+            method.full_name()
+        ))),
+        Err(e) => Valid::fail(BlueprintError::GrpcTranscodingUnsupported(e.to_string())),
+    }
+    .trace("transcode")
+}
+
+pub fn compile_grpc(inputs: CompileGrpc) -> Valid<IR, BlueprintError> {
+    let config_module = inputs.config_module;
+    let operation_type = inputs.operation_type;
+    let field = inputs.field;
+    let grpc = inputs.grpc;
+    let validate_with_schema = inputs.validate_with_schema;
+    let dedupe = grpc.dedupe.unwrap_or_else(|| {
+        config_module.upstream.get_dedupe() && *operation_type == GraphQLOperationType::Query
+    });
+
+    let name = Mustache::parse(&grpc.method);
+    // the static methods are a part of the urls of the routes, unless they are
+    // transcoded
+    let method = GrpcMethod::try_from(grpc.method.as_str())
+        .ok()
+        .filter(|_| name.is_const() && !grpc.transcode);
+    let routes = to_routes(grpc, method.as_ref());
+    let compiled = if name.is_const() {
+        compile_static_method(config_module, field, grpc, validate_with_schema).map(
+            |(url, headers, operation, body, transcoding)| {
+                (url, headers, operation, body, None, transcoding)
+            },
+        )
+    } else {
+        let request = match to_url_base(&grpc.url)
+            .fuse(helpers::headers::to_mustache_headers(&grpc.headers))
+            .fuse(helpers::body::to_body(grpc.body.as_ref()))
+            .to_result()
+        {
+            Ok(data) => Valid::succeed(data),
+            Err(e) => Valid::from_validation_err(BlueprintError::from_validation_string(e)),
+        };
+
+        to_dynamic_operation(config_module, grpc).zip(request).map(
+            |(operation, (url, headers, body))| {
+                let dynamic_method = DynamicMethod { name, allow: grpc.allow.clone() };
+                (url, headers, operation, body, Some(dynamic_method), None)
+            },
+        )
+    };
+
+    compiled
+        .zip(routes)
+        .zip(to_retry(grpc))
+        .map(
+            |(((url, headers, operation, body, dynamic_method, transcoding), routes), retry)| {
+                let enum_as_string = grpc
+                    .enum_as_string
+                    .then(|| EnumAsString { unknown: grpc.unknown_enum_value.clone() });
+                let req_template = RequestTemplate {
+                    url,
+                    headers,
+                    operation: operation.with_enum_as_string(enum_as_string),
+                    body,
+                    operation_type: operation_type.clone(),
+                    field_mask: grpc.field_mask.clone(),
+                    dynamic_method,
+                    routes,
+                    priority: grpc.priority,
+                    retry,
+                    transcoding,
+                };
+                let on_response = grpc.on_response_body.clone();
+                let hook = WorkerHooks::try_new(None, on_response).ok();
+
+                let io = if !grpc.batch_key.is_empty() {
+                    IR::IO(IO::Grpc {
+                        req_template,
+                        group_by: Some(GroupBy::new(grpc.batch_key.clone(), None)),
+                        dl_id: None,
+                        dedupe,
+                        hook,
+                    })
+                } else {
+                    IR::IO(IO::Grpc { req_template, group_by: None, dl_id: None, dedupe, hook })
+                };
+
+                (io, &grpc.select)
+            },
+        )
         .and_then(apply_select)
+        .and_then(|ir| apply_filter(ir, &grpc.filter, field))
+        .and_then(|ir| apply_compact(ir, &grpc.compact, field))
 }
 
 #[cfg(test)]
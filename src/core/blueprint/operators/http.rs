@@ -1,3 +1,6 @@
+use std::ops::RangeInclusive;
+
+use http::header::HeaderName;
 use tailcall_valid::{Valid, Validator};
 use template_validation::validate_argument;
 
@@ -5,8 +8,13 @@ use crate::core::blueprint::*;
 use crate::core::config::group_by::GroupBy;
 use crate::core::config::Field;
 use crate::core::endpoint::Endpoint;
-use crate::core::http::{Method, RequestTemplate};
+use crate::core::http::{
+    Connection, DynamicHeaders, Method, RedirectPolicy, RequestTemplate, ResponseSchema,
+    DEFAULT_MAX_RAW_BODY_SIZE,
+};
 use crate::core::ir::model::{IO, IR};
+use crate::core::ir::{parse_status, Predicate};
+use crate::core::json::JsonSchema;
 use crate::core::worker_hooks::WorkerHooks;
 use crate::core::{config, helpers, Mustache};
 
@@ -15,8 +23,10 @@ pub fn compile_http(
     http: &config::Http,
     field: &Field,
 ) -> Valid<IR, BlueprintError> {
-    let is_list = field.type_of.is_list();
-    let dedupe = http.dedupe.unwrap_or_default();
+    let is_list = http.is_list.unwrap_or_else(|| field.type_of.is_list());
+    let dedupe = http
+        .dedupe
+        .unwrap_or_else(|| config_module.upstream.get_dedupe() && http.method.is_safe());
     let mustache_headers = match helpers::headers::to_mustache_headers(&http.headers).to_result() {
         Ok(mustache_headers) => Valid::succeed(mustache_headers),
         Err(e) => Valid::from_validation_err(BlueprintError::from_validation_string(e)),
@@ -25,7 +35,7 @@ pub fn compile_http(
     Valid::<(), BlueprintError>::fail(BlueprintError::IncorrectBatchingUsage)
         .when(|| {
             (config_module.upstream.get_delay() < 1 || config_module.upstream.get_max_size() < 1)
-                && !http.batch_key.is_empty()
+                && http.is_batched()
         })
         .and(
             Valid::from_iter(http.query.iter(), |query| {
@@ -36,39 +46,77 @@ pub fn compile_http(
         )
         .and(
             Valid::<(), BlueprintError>::fail(BlueprintError::BatchKeyRequiresEitherBodyOrQuery)
-                .when(|| {
-                    !http.batch_key.is_empty() && (http.body.is_none() && http.query.is_empty())
-                }),
+                .when(|| http.is_batched() && (http.body.is_none() && http.query.is_empty())),
         )
+        .and(validate_stream_upload(config_module, http))
+        .and(validate_raw_body(http, field))
         .and(Valid::succeed(http.url.as_str()))
         .zip(mustache_headers)
-        .and_then(|(base_url, headers)| {
-            let query = http
-                .query
-                .clone()
-                .iter()
-                .map(|key_value| {
-                    (
-                        key_value.key.clone(),
-                        key_value.value.clone(),
-                        key_value.skip_empty.unwrap_or_default(),
-                    )
-                })
-                .collect();
-
-            match RequestTemplate::try_from(
-                Endpoint::new(base_url.to_string())
-                    .method(http.method.clone())
-                    .query(query)
-                    .body(http.body.clone())
-                    .encoding(http.encoding.clone()),
-            )
-            .map(|req_tmpl| req_tmpl.headers(headers))
-            {
-                Ok(data) => Valid::succeed(data),
-                Err(e) => Valid::fail(BlueprintError::Error(e)),
-            }
-        })
+        .zip(compile_response_schema(http))
+        .zip(compile_success_statuses(http))
+        .zip(compile_skip_if(http))
+        .zip(compile_dynamic_headers(http))
+        .zip(compile_connection(http, field))
+        .and_then(
+            |(
+                (
+                    ((((base_url, headers), response_schema), success_statuses), skip_if),
+                    dynamic_headers,
+                ),
+                connection,
+            )| {
+                // the page is selected by the args of the connection, they are
+                // left out when not given
+                let page = http.connection.iter().flat_map(|connection| {
+                    [
+                        (connection.get_limit_param(), "{{.args.first}}"),
+                        (connection.get_cursor_param(), "{{.args.after}}"),
+                    ]
+                    .map(|(key, value)| (key.to_string(), value.to_string(), true))
+                });
+                let query = http
+                    .query
+                    .clone()
+                    .iter()
+                    .map(|key_value| {
+                        (
+                            key_value.key.clone(),
+                            key_value.value.clone(),
+                            key_value.skip_empty.unwrap_or_default(),
+                        )
+                    })
+                    .chain(page)
+                    .collect();
+
+                match RequestTemplate::try_from(
+                    Endpoint::new(base_url.to_string())
+                        .method(http.method.clone())
+                        .query(query)
+                        .body(http.body.clone())
+                        .encoding(http.encoding.clone()),
+                )
+                .map(|req_tmpl| {
+                    req_tmpl
+                        .headers(headers)
+                        .dynamic_headers(dynamic_headers)
+                        .response_schema(response_schema)
+                        .stream_upload(http.stream_upload)
+                        .redirect(http.redirect.as_ref().map(RedirectPolicy::from))
+                        .query_encoder(http.query_style)
+                        .coerce_list(http.is_list)
+                        .success_statuses(success_statuses)
+                        .key_case(http.key_case)
+                        .priority(http.priority)
+                        .skip_if(skip_if)
+                        .raw_body(http.raw_body)
+                        .max_size(http.max_size.unwrap_or(DEFAULT_MAX_RAW_BODY_SIZE))
+                        .connection(connection)
+                }) {
+                    Ok(data) => Valid::succeed(data),
+                    Err(e) => Valid::fail(BlueprintError::Error(e)),
+                }
+            },
+        )
         .and_then(|request_template| {
             if !http.batch_key.is_empty() && (http.body.is_some() || http.method != Method::GET) {
                 if let Some(body) = http.body.as_ref() {
@@ -85,7 +133,8 @@ pub fn compile_http(
                 Valid::succeed(request_template)
             }
         })
-        .map(|req_template| {
+        .zip(to_composite_group_by(http))
+        .map(|(req_template, composite_group_by)| {
             // marge http and upstream on_request
             let on_request = http
                 .on_request
@@ -94,7 +143,16 @@ pub fn compile_http(
             let on_response_body = http.on_response_body.clone();
             let hook = WorkerHooks::try_new(on_request, on_response_body).ok();
 
-            let io = if !http.batch_key.is_empty() {
+            let io = if let Some(group_by) = composite_group_by {
+                IR::IO(IO::Http {
+                    req_template,
+                    group_by: Some(group_by),
+                    dl_id: None,
+                    is_list,
+                    dedupe,
+                    hook,
+                })
+            } else if !http.batch_key.is_empty() {
                 // Find a query parameter that contains a reference to the {{.value}} key
                 let key = if http.method == Method::GET {
                     http.query.iter().find_map(|q| {
@@ -127,6 +185,189 @@ pub fn compile_http(
             (io, &http.select)
         })
         .and_then(apply_select)
+        .and_then(|ir| apply_filter(ir, &http.filter, field))
+        .and_then(|ir| apply_compact(ir, &http.compact, field))
+}
+
+/// The upload stream is sent as the body of the request as it's received from
+/// the client. It can't be mixed with a body of its own, and as it can be read
+/// only once, the call can't be batched, deduplicated or handed over to the
+/// `onRequest` handler either.
+fn validate_stream_upload(
+    config_module: &config::ConfigModule,
+    http: &config::Http,
+) -> Valid<(), BlueprintError> {
+    if !http.stream_upload {
+        return Valid::succeed(());
+    }
+
+    let conflicts = [
+        ("body", http.body.is_some()),
+        ("batchKey", http.is_batched()),
+        ("dedupe", http.dedupe.unwrap_or_default()),
+        (
+            "onRequest",
+            http.on_request.is_some() || config_module.upstream.on_request.is_some(),
+        ),
+    ];
+
+    Valid::from_iter(conflicts, |(name, is_set)| {
+        Valid::<(), BlueprintError>::fail(BlueprintError::StreamUploadConflict(name.to_string()))
+            .when(|| is_set)
+    })
+    .unit()
+    .and(
+        Valid::<(), BlueprintError>::fail(BlueprintError::StreamUploadRequiresBodyMethod)
+            .when(|| matches!(http.method, Method::GET | Method::HEAD)),
+    )
+    .trace("streamUpload")
+}
+
+/// The paths of the `batchKeys` share the path to the items, the last field of
+/// each path is a part of the composite key. The value of each field is sent
+/// in the query parameter of the same name.
+fn to_composite_group_by(http: &config::Http) -> Valid<Option<GroupBy>, BlueprintError> {
+    let Some(first) = http.batch_keys.first() else {
+        return Valid::succeed(None);
+    };
+    let path = &first[..first.len().saturating_sub(1)];
+
+    Valid::<(), BlueprintError>::fail(BlueprintError::BatchKeysConflict)
+        .when(|| !http.batch_key.is_empty())
+        .and(
+            Valid::<(), BlueprintError>::fail(BlueprintError::BatchKeysRequireGet)
+                .when(|| http.method != Method::GET),
+        )
+        .and(Valid::from_iter(
+            http.batch_keys.iter(),
+            |batch_key| match batch_key.split_last() {
+                Some((key, prefix)) if prefix == path => {
+                    let in_query = http.query.iter().any(|query| {
+                        query.key == *key
+                            && Mustache::parse(&query.value).expression_contains("value")
+                    });
+                    if in_query {
+                        Valid::succeed(key.clone())
+                    } else {
+                        Valid::fail(BlueprintError::BatchKeyNotInQuery(key.clone()))
+                    }
+                }
+                _ => Valid::fail(BlueprintError::BatchKeysDifferentPaths),
+            },
+        ))
+        .map(|keys| Some(GroupBy::composite(path.to_vec(), keys)))
+        .trace("batchKeys")
+}
+
+/// Compiles the `responseSchema` once at build time, so that the responses
+/// can be validated without parsing the schema on every call.
+fn compile_response_schema(http: &config::Http) -> Valid<Option<ResponseSchema>, BlueprintError> {
+    match http.response_schema.as_ref() {
+        Some(schema) => match JsonSchema::from_json_schema(schema).to_result() {
+            Ok(schema) => {
+                Valid::succeed(Some(ResponseSchema::new(schema, http.on_schema_violation)))
+            }
+            Err(e) => Valid::from_validation_err(BlueprintError::from_validation_string(e)),
+        }
+        .trace("responseSchema"),
+        None => Valid::succeed(None),
+    }
+}
+
+fn compile_success_statuses(
+    http: &config::Http,
+) -> Valid<Vec<RangeInclusive<u16>>, BlueprintError> {
+    Valid::from_iter(http.success_statuses.iter(), |code| {
+        Valid::from_option(
+            parse_status(code),
+            BlueprintError::InvalidStatusCode(code.to_string()),
+        )
+    })
+    .trace("successStatuses")
+}
+
+fn validate_raw_body(http: &config::Http, field: &Field) -> Valid<(), BlueprintError> {
+    if !http.raw_body {
+        return Valid::<(), BlueprintError>::fail(BlueprintError::MaxSizeRequiresRawBody)
+            .when(|| http.max_size.is_some())
+            .trace("maxSize");
+    }
+
+    Valid::<(), BlueprintError>::fail(BlueprintError::RawBodyRequiresBytes(
+        field.type_of.name().to_string(),
+    ))
+    .when(|| field.type_of.name() != "Bytes")
+    .and(
+        Valid::<(), BlueprintError>::fail(BlueprintError::RawBodyCanNotBeBatched)
+            .when(|| http.is_batched()),
+    )
+    .trace("rawBody")
+}
+
+fn compile_skip_if(http: &config::Http) -> Valid<Option<Predicate>, BlueprintError> {
+    match http.skip_if.as_deref().map(Predicate::parse) {
+        Some(Ok(predicate)) => Valid::succeed(Some(predicate)),
+        Some(Err(e)) => Valid::fail(BlueprintError::InvalidSkipIf(e)).trace("skipIf"),
+        None => Valid::succeed(None),
+    }
+}
+
+fn compile_dynamic_headers(http: &config::Http) -> Valid<Option<DynamicHeaders>, BlueprintError> {
+    let Some(dynamic_headers) = &http.dynamic_headers else {
+        return Valid::succeed(None);
+    };
+    let value = Mustache::parse(&dynamic_headers.value);
+
+    Valid::<(), BlueprintError>::fail(BlueprintError::InvalidDynamicHeaders(format!(
+        "`{}` must be a single mustache expression",
+        dynamic_headers.value
+    )))
+    .when(|| value.expression_segments().len() != 1 || value.segments().len() != 1)
+    .and(Valid::from_iter(
+        dynamic_headers.allow.iter(),
+        |name| match HeaderName::from_bytes(name.as_bytes()) {
+            Ok(_) => Valid::succeed(()),
+            Err(_) => Valid::fail(BlueprintError::InvalidDynamicHeaders(format!(
+                "`{name}` is not a valid header name"
+            ))),
+        },
+    ))
+    .map(|_| Some(DynamicHeaders::new(value, dynamic_headers.allow.clone())))
+    .trace("dynamicHeaders")
+}
+
+/// The connection is paginated forwards with the `first` and `after` args,
+/// a page of it can't be batched with the pages of the other values. Every
+/// edge has a cursor, so the items need one of their own.
+fn compile_connection(
+    http: &config::Http,
+    field: &Field,
+) -> Valid<Option<Connection>, BlueprintError> {
+    let Some(connection) = &http.connection else {
+        return Valid::succeed(None);
+    };
+
+    Valid::<(), BlueprintError>::fail(BlueprintError::ConnectionCanNotBeList)
+        .when(|| http.is_batched() || field.type_of.is_list())
+        .and(Valid::from_iter(["first", "after"], |arg| {
+            Valid::<(), BlueprintError>::fail(BlueprintError::ConnectionRequiresArgument(
+                arg.to_string(),
+            ))
+            .when(|| !field.args.contains_key(arg))
+        }))
+        .and(Valid::from_option(
+            connection.edge_cursor.clone(),
+            BlueprintError::ConnectionRequiresEdgeCursor,
+        ))
+        .map(|edge_cursor| {
+            Some(Connection {
+                items: connection.get_items(),
+                next_cursor: connection.get_next_cursor(),
+                has_more: connection.get_has_more(),
+                edge_cursor,
+            })
+        })
+        .trace("connection")
 }
 
 /// Count the number of dynamic expressions in the JSON value.
@@ -181,4 +422,71 @@ mod test {
         let keys = count_dynamic_paths(&json);
         assert_eq!(keys, 1);
     }
+
+    #[test]
+    fn test_stream_upload() {
+        let http = config::Http {
+            url: "http://localhost:3000/upload".to_string(),
+            method: Method::POST,
+            stream_upload: true,
+            ..Default::default()
+        };
+        let config_module = config::ConfigModule::default();
+        assert!(validate_stream_upload(&config_module, &http).is_succeed());
+
+        let http = config::Http {
+            method: Method::GET,
+            body: Some(json!({ "id": "{{.args.id}}" })),
+            dedupe: Some(true),
+            on_request: Some("onRequest".to_string()),
+            ..http
+        };
+        let errors = validate_stream_upload(&config_module, &http)
+            .to_result()
+            .unwrap_err();
+        assert_eq!(errors.as_vec().len(), 4);
+    }
+
+    fn dedupe(upstream_dedupe: Option<bool>, http: config::Http) -> bool {
+        let config = config::Config {
+            upstream: config::Upstream { dedupe: upstream_dedupe, ..Default::default() },
+            ..Default::default()
+        };
+        let config_module = config::ConfigModule::from(config);
+
+        match compile_http(&config_module, &http, &Field::default()).to_result() {
+            Ok(IR::IO(io)) => io.dedupe(),
+            ir => panic!("unexpected IR: {ir:?}"),
+        }
+    }
+
+    #[test]
+    fn test_dedupe_default() {
+        let http = config::Http {
+            url: "http://localhost:3000/users".to_string(),
+            ..Default::default()
+        };
+
+        assert!(!dedupe(None, http.clone()));
+        assert!(dedupe(Some(true), http.clone()));
+
+        // the directive overrides the default
+        let http = config::Http { dedupe: Some(false), ..http };
+        assert!(!dedupe(Some(true), http.clone()));
+        let http = config::Http { dedupe: Some(true), ..http };
+        assert!(dedupe(None, http));
+    }
+
+    #[test]
+    fn test_dedupe_default_unsafe_method() {
+        for method in [Method::POST, Method::PUT, Method::PATCH, Method::DELETE] {
+            let http = config::Http {
+                url: "http://localhost:3000/users".to_string(),
+                method,
+                ..Default::default()
+            };
+
+            assert!(!dedupe(Some(true), http));
+        }
+    }
 }
@@ -0,0 +1,58 @@
+use tailcall_valid::{Valid, Validator};
+
+use crate::core::blueprint::{Auth, BlueprintError, FieldDefinition};
+use crate::core::config::{self, ConfigModule, Field};
+use crate::core::ir::model::{Mask, IR};
+use crate::core::try_fold::TryFold;
+
+pub fn update_mask<'a>() -> TryFold<
+    'a,
+    (&'a ConfigModule, &'a Field, &'a config::Type, &'a str),
+    FieldDefinition,
+    BlueprintError,
+> {
+    TryFold::<(&ConfigModule, &Field, &config::Type, &'a str), FieldDefinition, BlueprintError>::new(
+        |(config, field, _, _), mut b_field| {
+            let Some(mask) = &field.mask else {
+                return Valid::succeed(b_field);
+            };
+
+            let Some(auth) = Auth::from_config(config) else {
+                return Valid::fail(BlueprintError::MaskOperatorNoAuthProviders);
+            };
+
+            let type_of = field.type_of.name();
+            let Some(output) = config.find_type(type_of) else {
+                return Valid::fail(BlueprintError::TypeNotFoundInConfig(type_of.to_owned()));
+            };
+
+            // masked fields are replaced with null in the resolved value, so they
+            // have to be nullable and read from the value
+            Valid::from_iter(mask.fields.iter(), |name| match output.fields.get(name) {
+                None => Valid::fail(BlueprintError::FieldNotFoundInType(name.to_owned())),
+                Some(field) if !field.type_of.is_nullable() => {
+                    Valid::fail(BlueprintError::MaskedFieldIsRequired(name.to_owned()))
+                }
+                Some(field) if field.has_resolver() => {
+                    Valid::fail(BlueprintError::MaskedFieldHasResolver(name.to_owned()))
+                }
+                Some(_) => Valid::succeed(()),
+            })
+            .map(|_| {
+                let input = b_field
+                    .resolver
+                    .take()
+                    .unwrap_or_else(|| IR::ContextPath(vec![b_field.name.clone()]));
+
+                b_field.resolver = Some(IR::Mask(Mask {
+                    input: Box::new(input),
+                    auth,
+                    scope: mask.scope.clone(),
+                    fields: mask.fields.clone(),
+                }));
+
+                b_field
+            })
+        },
+    )
+}
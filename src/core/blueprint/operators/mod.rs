@@ -1,25 +1,33 @@
 mod apollo_federation;
 mod call;
+mod compose;
+mod compress;
 mod enum_alias;
 mod expr;
 mod graphql;
 mod grpc;
 mod http;
 mod js;
+mod mask;
 mod modify;
 mod protected;
 mod resolver;
 mod select;
+mod validate;
 
 pub use apollo_federation::*;
 pub use call::*;
+pub use compose::*;
+pub use compress::*;
 pub use enum_alias::*;
 pub use expr::*;
 pub use graphql::*;
 pub use grpc::*;
 pub use http::*;
 pub use js::*;
+pub use mask::*;
 pub use modify::*;
 pub use protected::*;
 pub use resolver::*;
 pub use select::*;
+pub use validate::*;
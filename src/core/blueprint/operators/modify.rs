@@ -36,3 +36,116 @@ pub fn update_modify<'a>() -> TryFold<
         },
     )
 }
+
+/// Builds the field exposed under the `addAlias` name of `@modify`. The alias
+/// shares the resolver of the original field, so both the names resolve to
+/// the same upstream calls and cache entries.
+pub fn to_alias(
+    (config, field, type_of, name): (&ConfigModule, &Field, &config::Type, &str),
+    b_field: &FieldDefinition,
+) -> Valid<Option<FieldDefinition>, BlueprintError> {
+    let Some(alias) = field
+        .modify
+        .as_ref()
+        .and_then(|modify| modify.add_alias.as_ref())
+    else {
+        return Valid::succeed(None);
+    };
+
+    let exists = type_of.fields.iter().any(|(field_name, field)| {
+        let new_name = field
+            .modify
+            .as_ref()
+            .and_then(|modify| modify.name.as_ref());
+        field_name == alias || new_name == Some(alias)
+    });
+    if exists {
+        return Valid::fail(BlueprintError::FieldAliasExists(alias.clone()));
+    }
+
+    for interface in type_of.implements.iter() {
+        if let Some(interface) = config.find_type(interface) {
+            if interface.fields.contains_key(alias) {
+                return Valid::fail(BlueprintError::FieldExistsInInterface);
+            }
+        }
+    }
+
+    let resolver = b_field
+        .resolver
+        .clone()
+        .unwrap_or(IR::ContextPath(vec![name.to_string()]));
+
+    Valid::succeed(Some(
+        b_field.clone().name(alias.clone()).resolver(Some(resolver)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use tailcall_valid::Validator;
+
+    use crate::core::app_context::test::{TestApp, TestHttp};
+    use crate::core::blueprint::Blueprint;
+    use crate::core::config::{Config, ConfigModule};
+
+    const CONFIG: &str = r#"
+        schema @server @upstream {
+            query: Query
+        }
+
+        type Query {
+            users: [User]
+                @http(url: "http://localhost:8080/users")
+                @cache(maxAge: 60000)
+                @modify(addAlias: "members")
+        }
+
+        type User {
+            id: Int
+            username: String @modify(name: "login", addAlias: "handle")
+        }
+    "#;
+
+    #[tokio::test]
+    async fn test_alias_resolves_identically() {
+        let http = TestHttp::body(|_| Ok(r#"[{"id": 1, "username": "foo"}]"#));
+        let app = TestApp::new(CONFIG, http.clone());
+
+        let users = app.execute("query { users { id login handle } }").await;
+        let members = app.execute("query { members { id login handle } }").await;
+
+        let expected = serde_json::json!([{ "id": 1, "login": "foo", "handle": "foo" }]);
+        assert_eq!(users["data"]["users"], expected);
+        assert_eq!(members["data"]["members"], expected);
+
+        // both the names share the resolver and so the cached response
+        assert_eq!(http.hits(), 1);
+    }
+
+    #[test]
+    fn test_alias_conflicts_with_field() {
+        let sdl = r#"
+            schema @server @upstream {
+                query: Query
+            }
+
+            type Query {
+                users: [Int] @http(url: "http://localhost:8080/users") @modify(addAlias: "members")
+                members: [Int] @http(url: "http://localhost:8080/members")
+            }
+        "#;
+        let config = Config::from_sdl(sdl).to_result().unwrap();
+        let errors = Blueprint::try_from(&ConfigModule::from(config))
+            .unwrap_err()
+            .as_vec()
+            .iter()
+            .map(|cause| cause.message.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            errors,
+            vec!["Alias members conflicts with an existing field"]
+        );
+    }
+}
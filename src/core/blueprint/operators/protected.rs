@@ -1,8 +1,8 @@
 use tailcall_valid::{Valid, Validator};
 
-use crate::core::blueprint::{Auth, BlueprintError, FieldDefinition, Provider};
+use crate::core::blueprint::{Auth, BlueprintError, DynamicValue, FieldDefinition, Provider};
 use crate::core::config::{self, ConfigModule, Field};
-use crate::core::ir::model::IR;
+use crate::core::ir::model::{Protect, IR};
 use crate::core::try_fold::TryFold;
 
 pub fn update_protected<'a>(
@@ -56,6 +56,20 @@ pub fn update_protected<'a>(
                         .unwrap_or_default(),
                 );
 
+                // the fallback of the field takes precedence over the one of the type
+                let fallback = field
+                    .protected
+                    .as_ref()
+                    .and_then(|protect| protect.fallback.as_ref())
+                    .or(type_
+                        .protected
+                        .as_ref()
+                        .and_then(|protect| protect.fallback.as_ref()));
+                let anon = match fallback.map(DynamicValue::try_from).transpose() {
+                    Ok(anon) => Valid::succeed(anon.map(|anon| Box::new(IR::Dynamic(anon)))),
+                    Err(e) => Valid::fail(BlueprintError::Error(e)).trace("fallback"),
+                };
+
                 Valid::from_iter(protection.iter(), |id| {
                     if let Some(provider) = providers.get(id) {
                         Valid::succeed(Auth::Provider(provider.clone()))
@@ -63,7 +77,8 @@ pub fn update_protected<'a>(
                         Valid::fail(BlueprintError::AuthProviderNotFound(id.clone()))
                     }
                 })
-                .map(|provider| {
+                .zip(anon)
+                .map(|(provider, anon)| {
                     let mut auth = provider.into_iter().reduce(|left, right| left.and(right));
 
                     // If no protection is defined, use all providers
@@ -72,13 +87,12 @@ pub fn update_protected<'a>(
                     }
 
                     if let Some(auth) = auth {
-                        b_field.resolver = match &b_field.resolver {
-                            None => Some(IR::Protect(
-                                auth,
-                                Box::new(IR::ContextPath(vec![b_field.name.clone()])),
-                            )),
-                            Some(resolver) => Some(IR::Protect(auth, Box::new(resolver.clone()))),
-                        }
+                        let authed = match &b_field.resolver {
+                            None => IR::ContextPath(vec![b_field.name.clone()]),
+                            Some(resolver) => resolver.clone(),
+                        };
+                        b_field.resolver =
+                            Some(IR::Protect(Protect { anon, ..Protect::new(auth, authed) }));
                     }
 
                     b_field
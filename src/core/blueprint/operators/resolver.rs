@@ -1,10 +1,13 @@
 use tailcall_valid::{Valid, Validator};
 
-use super::{compile_call, compile_expr, compile_graphql, compile_grpc, compile_http, compile_js};
+use super::{
+    compile_call, compile_compose, compile_expr, compile_graphql, compile_grpc, compile_http,
+    compile_js,
+};
 use crate::core::blueprint::{BlueprintError, FieldDefinition};
 use crate::core::config::{self, ConfigModule, Field, GraphQLOperationType, Resolver};
 use crate::core::directive::DirectiveCodec;
-use crate::core::ir::model::IR;
+use crate::core::ir::model::{Fallback, Join, Merge, IR};
 use crate::core::try_fold::TryFold;
 
 pub struct CompileResolver<'a> {
@@ -38,6 +41,8 @@ pub fn compile_resolver(
         }
         Resolver::Call(call) => compile_call(config_module, call, operation_type, object_name)
             .trace(config::Call::trace_name().as_str()),
+        Resolver::Compose(compose) => compile_compose(config_module, compose, field)
+            .trace(config::Compose::trace_name().as_str()),
         Resolver::Js(js) => {
             compile_js(super::CompileJs { js, script: &config_module.extensions().script })
                 .trace(config::JS::trace_name().as_str())
@@ -55,6 +60,87 @@ pub fn compile_resolver(
     .map(Some)
 }
 
+/// The resolvers of a field run concurrently and their results are merged, so
+/// they can be combined only when the values of the field can be merged:
+/// - the objects are merged field by field and the lists are concatenated, so
+///   any resolvers eg. `@http` and `@grpc` can be combined on these fields
+/// - only the last of the values of a scalar, an enum or a union is kept, so
+///   the resolvers of these fields are mutually exclusive
+/// - the resolvers of a mutation would run their side effects in no particular
+///   order, so the resolvers of the mutations are mutually exclusive
+///
+/// The directives that modify the resolver eg. `@cache`, `@modify` or
+/// `@protected` are composable with any resolver. With `@fallback` the
+/// resolvers are tried one after another instead of being merged, so any of
+/// them can be combined, but there have to be at least two of them. With
+/// `@join` the elements of the lists of exactly two resolvers are joined.
+fn validate_resolvers(
+    config_module: &ConfigModule,
+    field: &Field,
+    field_name: &str,
+    operation_type: &GraphQLOperationType,
+) -> Valid<(), BlueprintError> {
+    let resolvers = field
+        .resolvers
+        .iter()
+        .filter(|resolver| !matches!(resolver, Resolver::ApolloFederation(_)))
+        .collect::<Vec<_>>();
+
+    if field.partial.is_some()
+        && (resolvers.len() < 2 || field.fallback.is_some() || field.join.is_some())
+    {
+        return Valid::fail(BlueprintError::PartialRequiresResolvers(
+            field_name.to_string(),
+        ));
+    }
+
+    if field.fallback.is_some() {
+        return Valid::<(), BlueprintError>::fail(BlueprintError::FallbackRequiresResolvers(
+            field_name.to_string(),
+        ))
+        .when(|| resolvers.len() < 2);
+    }
+
+    if field.join.is_some() {
+        return Valid::<(), BlueprintError>::fail(BlueprintError::JoinRequiresResolvers(
+            field_name.to_string(),
+        ))
+        .when(|| resolvers.len() != 2)
+        .and(
+            Valid::<(), BlueprintError>::fail(BlueprintError::JoinRequiresList)
+                .when(|| !field.type_of.is_list()),
+        );
+    }
+
+    if resolvers.len() < 2 {
+        return Valid::succeed(());
+    }
+
+    let reason = if matches!(operation_type, GraphQLOperationType::Mutation) {
+        "the resolvers of a mutation are mutually exclusive"
+    } else if field.type_of.is_list()
+        || config_module
+            .find_type(field.type_of.name())
+            .is_some_and(|type_of| !type_of.scalar())
+    {
+        return Valid::succeed(());
+    } else {
+        "only the objects and the lists resolved by multiple resolvers can be merged"
+    };
+
+    let directives = resolvers
+        .iter()
+        .map(|resolver| format!("@{}", resolver.directive_name()))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Valid::fail(BlueprintError::ConflictingResolvers {
+        field: field_name.to_string(),
+        directives,
+        reason: reason.to_string(),
+    })
+}
+
 pub fn update_resolver<'a>(
     operation_type: &'a GraphQLOperationType,
     object_name: &'a str,
@@ -65,25 +151,46 @@ pub fn update_resolver<'a>(
     BlueprintError,
 > {
     TryFold::<(&ConfigModule, &Field, &config::Type, &str), FieldDefinition, BlueprintError>::new(
-        |(config_module, field, type_of, _), b_field| {
+        |(config_module, field, type_of, name), b_field| {
             let inputs = CompileResolver { config_module, field, operation_type, object_name };
 
-            Valid::from_iter(field.resolvers.iter(), |resolver| {
-                compile_resolver(&inputs, resolver)
-            })
-            .map(|mut resolvers| match resolvers.len() {
-                0 => None,
-                1 => resolvers.pop().unwrap(),
-                _ => Some(IR::Merge(resolvers.into_iter().flatten().collect())),
-            })
-            .map(|resolver| b_field.resolver(resolver))
-            .and_then(|b_field| {
-                b_field
-                    // TODO: there are `validate_field` for field, but not for types
-                    // when we use federations's entities
-                    .validate_field(type_of, config_module)
-                    .map_to(b_field)
-            })
+            validate_resolvers(config_module, field, name, operation_type)
+                .and(Valid::from_iter(field.resolvers.iter(), |resolver| {
+                    compile_resolver(&inputs, resolver)
+                }))
+                .map(|mut resolvers| match resolvers.len() {
+                    0 => None,
+                    1 => resolvers.pop().unwrap(),
+                    _ => {
+                        let mut irs: Vec<_> = resolvers.into_iter().flatten().collect();
+                        Some(match (&field.fallback, &field.join) {
+                            (Some(fallback), _) => {
+                                IR::Fallback(Fallback { irs, on_null: fallback.on_null })
+                            }
+                            (None, Some(join)) if irs.len() == 2 => {
+                                let right = irs.pop().unwrap();
+                                let left = irs.pop().unwrap();
+                                IR::Join(Join {
+                                    left: Box::new(left),
+                                    right: Box::new(right),
+                                    on: (join.left.clone(), join.right.clone()),
+                                    how: join.how,
+                                })
+                            }
+                            (None, _) => {
+                                IR::Merge(Merge { irs, tolerate_partial: field.partial.is_some() })
+                            }
+                        })
+                    }
+                })
+                .map(|resolver| b_field.resolver(resolver))
+                .and_then(|b_field| {
+                    b_field
+                        // TODO: there are `validate_field` for field, but not for types
+                        // when we use federations's entities
+                        .validate_field(type_of, config_module)
+                        .map_to(b_field)
+                })
         },
     )
 }
@@ -1,15 +1,18 @@
 use serde_json::Value;
-use tailcall_valid::Valid;
+use tailcall_valid::{Valid, Validator};
 
+use crate::core::blueprint::mustache::validate_runtime_env;
 use crate::core::blueprint::{BlueprintError, DynamicValue};
-use crate::core::ir::model::IR;
+use crate::core::config::{Compact, Field};
+use crate::core::ir::model::{self, Filter, IR};
+use crate::core::ir::Predicate;
 
 pub fn apply_select(input: (IR, &Option<Value>)) -> Valid<IR, BlueprintError> {
-    let (mut ir, select) = input;
+    let (ir, select) = input;
 
     if let Some(select_value) = select {
         let dynamic_value = match DynamicValue::try_from(select_value) {
-            Ok(dynamic_value) => dynamic_value.prepend("args"),
+            Ok(dynamic_value) => dynamic_value,
             Err(e) => {
                 return Valid::fail_with(
                     BlueprintError::SyntaxErrorWhenParsing(format!("{:?}", select)),
@@ -18,9 +21,94 @@ pub fn apply_select(input: (IR, &Option<Value>)) -> Valid<IR, BlueprintError> {
             }
         };
 
-        ir = ir.pipe(IR::Dynamic(dynamic_value));
-        Valid::succeed(ir)
+        validate_runtime_env(&dynamic_value)
+            .map(|_| ir.pipe(IR::Dynamic(dynamic_value.prepend("args").with_null_paths())))
     } else {
         Valid::succeed(ir)
     }
 }
+
+/// Filters the elements of the list resolved by the IR with the predicate.
+pub fn apply_filter(ir: IR, filter: &Option<String>, field: &Field) -> Valid<IR, BlueprintError> {
+    let Some(filter) = filter else {
+        return Valid::succeed(ir);
+    };
+
+    if !field.type_of.is_list() {
+        return Valid::fail(BlueprintError::FilterRequiresList);
+    }
+
+    match Predicate::parse(filter) {
+        Ok(predicate) => Valid::succeed(IR::Filter(Filter { input: Box::new(ir), predicate })),
+        Err(e) => Valid::fail(BlueprintError::InvalidFilter(e)),
+    }
+}
+
+/// Drops the null and the empty elements of the list resolved by the IR.
+pub fn apply_compact(
+    ir: IR,
+    compact: &Option<Compact>,
+    field: &Field,
+) -> Valid<IR, BlueprintError> {
+    let Some(compact) = compact else {
+        return Valid::succeed(ir);
+    };
+
+    if !field.type_of.is_list() {
+        return Valid::fail(BlueprintError::CompactRequiresList);
+    }
+
+    Valid::succeed(IR::Compact(model::Compact {
+        input: Box::new(ir),
+        empty_objects: compact.empty_objects,
+        required: compact.required.clone(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql_value::ConstValue;
+    use serde_json::json;
+
+    use super::*;
+    use crate::core::serde_value_ext::ValueExt;
+
+    fn reshape(select: Value, source: Value) -> ConstValue {
+        let ir = apply_select((IR::ContextPath(vec![]), &Some(select)))
+            .to_result()
+            .unwrap();
+        let IR::Pipe(_, select) = ir else {
+            unreachable!()
+        };
+        let IR::Dynamic(select) = *select else {
+            unreachable!()
+        };
+
+        select.render_value(&json!({"args": source}))
+    }
+
+    #[test]
+    fn test_select_reshape() {
+        let select = json!({
+            "id": "{{.id}}",
+            "address": {"city": "{{.city}}", "zip": "{{.zip}}"}
+        });
+        let source = json!({"id": 1, "city": "Paris"});
+
+        let actual = reshape(select, source);
+        let expected = ConstValue::from_json(json!({
+            "id": 1,
+            "address": {"city": "Paris", "zip": null}
+        }))
+        .unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_select_runtime_env() {
+        let select = json!({"flag": "{{.runtimeEnv.FEATURE_X}}"});
+        let actual = apply_select((IR::ContextPath(vec![]), &Some(select))).to_result();
+
+        assert!(actual.is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use tailcall_valid::{Valid, Validator};
+
+use crate::core::blueprint::{BlueprintError, FieldDefinition};
+use crate::core::config::{self, Arg, ConfigModule, Field};
+use crate::core::ir::model::{Validate, IR};
+use crate::core::ir::{Constraint, Pattern};
+use crate::core::try_fold::TryFold;
+use crate::core::Type;
+
+/// The scalars whose values are numbers that the ranges are checked with.
+const NUMBERS: &[&str] = &[
+    "Int", "Float", "Int8", "Int16", "Int32", "Int64", "Int128", "UInt8", "UInt16", "UInt32",
+    "UInt64", "UInt128",
+];
+
+/// The scalars whose values are strings that the patterns are matched with.
+const STRINGS: &[&str] = &[
+    "String",
+    "ID",
+    "Email",
+    "PhoneNumber",
+    "Date",
+    "DateTime",
+    "Url",
+];
+
+fn is_number(type_of: &Type) -> bool {
+    !type_of.is_list() && NUMBERS.contains(&type_of.name().as_str())
+}
+
+fn is_string(type_of: &Type) -> bool {
+    !type_of.is_list() && STRINGS.contains(&type_of.name().as_str())
+}
+
+/// Whether no value is within the bounds.
+fn is_empty<T: PartialOrd>(min: Option<T>, max: Option<T>) -> bool {
+    matches!((min, max), (Some(min), Some(max)) if min > max)
+}
+
+fn to_constraint(
+    field: &Field,
+    name: &str,
+    arg: &Arg,
+    validate: &config::Validate,
+) -> Valid<Constraint, BlueprintError> {
+    let type_of = &arg.type_of;
+    let range = validate.min.is_some() || validate.max.is_some();
+    let length = validate.min_length.is_some() || validate.max_length.is_some();
+
+    Valid::<(), BlueprintError>::fail(BlueprintError::ValidateNotApplicable(
+        "min and max".to_string(),
+        "number".to_string(),
+    ))
+    .when(|| range && !is_number(type_of))
+    .and(
+        Valid::<(), BlueprintError>::fail(BlueprintError::ValidateNotApplicable(
+            "minLength and maxLength".to_string(),
+            "string or list".to_string(),
+        ))
+        .when(|| length && !is_string(type_of) && !type_of.is_list()),
+    )
+    .and(
+        Valid::<(), BlueprintError>::fail(BlueprintError::ValidateNotApplicable(
+            "pattern".to_string(),
+            "string".to_string(),
+        ))
+        .when(|| validate.pattern.is_some() && !is_string(type_of)),
+    )
+    .and(
+        Valid::<(), BlueprintError>::fail(BlueprintError::ValidateEmptyRange(
+            "min".to_string(),
+            "max".to_string(),
+        ))
+        .when(|| is_empty(validate.min, validate.max)),
+    )
+    .and(
+        Valid::<(), BlueprintError>::fail(BlueprintError::ValidateEmptyRange(
+            "minLength".to_string(),
+            "maxLength".to_string(),
+        ))
+        .when(|| is_empty(validate.min_length, validate.max_length)),
+    )
+    .and(
+        Valid::from_iter(validate.requires.iter(), |required| {
+            Valid::<(), BlueprintError>::fail(BlueprintError::ArgumentNotFound(required.clone()))
+                .when(|| required == name || !field.args.contains_key(required))
+        })
+        .trace("requires"),
+    )
+    .and(match validate.pattern.as_deref().map(Pattern::new) {
+        Some(Ok(pattern)) => Valid::succeed(Some(pattern)),
+        Some(Err(err)) => Valid::fail(BlueprintError::InvalidValidatePattern(err.to_string())),
+        None => Valid::succeed(None),
+    })
+    .map(|pattern| Constraint {
+        arg: name.to_string(),
+        min: validate.min,
+        max: validate.max,
+        min_length: validate.min_length,
+        max_length: validate.max_length,
+        pattern,
+        requires: validate.requires.clone(),
+    })
+}
+
+/// Checks the arguments with their `@validate` constraints before the field
+/// is resolved.
+pub fn update_validate<'a>() -> TryFold<
+    'a,
+    (&'a ConfigModule, &'a Field, &'a config::Type, &'a str),
+    FieldDefinition,
+    BlueprintError,
+> {
+    TryFold::<(&ConfigModule, &Field, &config::Type, &'a str), FieldDefinition, BlueprintError>::new(
+        |(_, field, _, _), mut b_field| {
+            Valid::from_iter(field.args.iter(), |(name, arg)| match &arg.validate {
+                Some(validate) => to_constraint(field, name, arg, validate)
+                    .trace(name)
+                    .map(Some),
+                None => Valid::succeed(None),
+            })
+            .map(move |constraints| {
+                let constraints = constraints.into_iter().flatten().collect::<Vec<_>>();
+                if constraints.is_empty() {
+                    return b_field;
+                }
+
+                let input = b_field
+                    .resolver
+                    .take()
+                    .unwrap_or_else(|| IR::ContextPath(vec![b_field.name.clone()]));
+                b_field.resolver = Some(IR::Validate(Validate {
+                    input: Box::new(input),
+                    constraints,
+                }));
+
+                b_field
+            })
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arg(type_of: &str, validate: config::Validate) -> Arg {
+        Arg {
+            type_of: Type::from(type_of.to_string()),
+            validate: Some(validate),
+            ..Default::default()
+        }
+    }
+
+    fn field(args: Vec<(&str, Arg)>) -> Field {
+        Field {
+            args: args
+                .into_iter()
+                .map(|(name, arg)| (name.to_string(), arg))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn errors(field: &Field) -> Vec<String> {
+        let config_module = ConfigModule::default();
+        let type_of = config::Type::default();
+        let result = update_validate()
+            .try_fold(
+                &(&config_module, field, &type_of, "posts"),
+                FieldDefinition::default(),
+            )
+            .to_result();
+        match result {
+            Ok(_) => vec![],
+            Err(err) => err
+                .as_vec()
+                .iter()
+                .map(|cause| cause.message.to_string())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_update_validate() {
+        let limit = config::Validate { min: Some(1), max: Some(100), ..Default::default() };
+        let field = field(vec![("limit", arg("Int", limit))]);
+        assert!(errors(&field).is_empty());
+
+        let config_module = ConfigModule::default();
+        let type_of = config::Type::default();
+        let b_field = update_validate()
+            .try_fold(
+                &(&config_module, &field, &type_of, "posts"),
+                FieldDefinition::default(),
+            )
+            .to_result()
+            .unwrap();
+        assert!(matches!(
+            b_field.resolver,
+            Some(IR::Validate(Validate { constraints, .. })) if constraints.len() == 1
+        ));
+    }
+
+    #[test]
+    fn test_invalid_constraints() {
+        let field = field(vec![
+            (
+                "limit",
+                arg(
+                    "String",
+                    config::Validate { min: Some(10), max: Some(1), ..Default::default() },
+                ),
+            ),
+            (
+                "code",
+                arg(
+                    "String",
+                    config::Validate {
+                        pattern: Some("(".to_string()),
+                        requires: vec!["missing".to_string()],
+                        ..Default::default()
+                    },
+                ),
+            ),
+        ]);
+
+        let errors = errors(&field);
+        assert_eq!(
+            errors[..3],
+            [
+                "min and max can only be used on number arguments",
+                "min is greater than max",
+                "no argument 'missing' found",
+            ]
+        );
+        assert!(errors[3].starts_with("Invalid pattern: "));
+        assert_eq!(errors.len(), 4);
+    }
+}
@@ -4,7 +4,7 @@ use directive::to_directive;
 use tailcall_valid::{Valid, Validator};
 
 use crate::core::blueprint::*;
-use crate::core::config::{Config, Field, Type};
+use crate::core::config::{Config, Field, Resolver, Type};
 use crate::core::directive::DirectiveCodec;
 
 fn validate_query(config: &Config) -> Valid<(), BlueprintError> {
@@ -79,10 +79,36 @@ fn validate_mutation(config: &Config) -> Valid<(), BlueprintError> {
     }
 }
 
+fn validate_subscription(config: &Config) -> Valid<(), BlueprintError> {
+    let Some(subscription_type_name) = config.schema.subscription.as_ref() else {
+        return Valid::succeed(());
+    };
+    let Some(subscription) = config.find_type(subscription_type_name) else {
+        return Valid::fail(BlueprintError::SubscriptionTypeNotDefined)
+            .trace(subscription_type_name);
+    };
+
+    // the events are streamed from the upstream GraphQL subscriptions, or the
+    // lines of the NDJSON responses, of the field itself or the one it calls
+    Valid::from_iter(subscription.fields.iter(), |(name, field)| {
+        let is_stream = matches!(
+            field.resolvers.as_slice(),
+            [Resolver::Graphql(_) | Resolver::Http(_) | Resolver::Call(_)]
+        );
+
+        Valid::<(), BlueprintError>::fail(BlueprintError::InvalidSubscriptionResolver)
+            .when(|| !field.is_omitted() && !is_stream)
+            .trace(name)
+    })
+    .unit()
+    .trace(subscription_type_name)
+}
+
 pub fn to_schema<'a>() -> TryFoldConfig<'a, SchemaDefinition> {
     TryFoldConfig::new(|config, _| {
         validate_query(config)
             .and(validate_mutation(config))
+            .and(validate_subscription(config))
             .and(Valid::from_option(
                 config.schema.query.as_ref(),
                 BlueprintError::QueryRootIsMissing,
@@ -91,6 +117,7 @@ pub fn to_schema<'a>() -> TryFoldConfig<'a, SchemaDefinition> {
             .map(|(query_type_name, directive)| SchemaDefinition {
                 query: query_type_name.to_owned(),
                 mutation: config.schema.mutation.clone(),
+                subscription: config.schema.subscription.clone(),
                 directives: vec![directive],
             })
     })
@@ -1,15 +1,19 @@
 use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::hash::{Hash, Hasher};
 use std::net::{AddrParseError, IpAddr};
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::Duration;
 
 use derive_setters::Setters;
 use http::header::{HeaderMap, HeaderName, HeaderValue};
 use rustls_pki_types::CertificateDer;
+use tailcall_hasher::TailcallHasher;
 use tailcall_valid::{Valid, ValidationError, Validator};
 
 use super::BlueprintError;
-use crate::core::blueprint::Cors;
+use crate::core::blueprint::{Auth, Cors};
 use crate::core::config::{self, ConfigModule, HttpVersion, PrivateKey, Routes};
 
 #[derive(Clone, Debug, Setters)]
@@ -22,6 +26,8 @@ pub struct Server {
     pub enable_response_validation: bool,
     pub enable_batch_requests: bool,
     pub enable_showcase: bool,
+    pub enable_debug_stats: bool,
+    pub enable_msgpack: bool,
     pub global_response_timeout: i64,
     pub worker: usize,
     pub port: u16,
@@ -34,6 +40,29 @@ pub struct Server {
     pub cors: Option<Cors>,
     pub experimental_headers: HashSet<HeaderName>,
     pub routes: Routes,
+    pub request_id_header: Option<HeaderName>,
+    pub health_check: Option<config::HealthCheck>,
+    pub cache_admin: Option<CacheAdmin>,
+    /// Mixed into the keys of the cached entries, it's stable for a given
+    /// configuration.
+    pub cache_salt: u64,
+    /// File the cached entries are persisted to across restarts.
+    pub cache_snapshot: Option<PathBuf>,
+    /// Number of the cached entries above which the new entries aren't stored.
+    pub cache_max_entries: Option<usize>,
+    pub max_upstream_calls: Option<usize>,
+    pub max_path_depth: Option<usize>,
+    pub max_query_depth: Option<usize>,
+    pub max_query_fields: Option<usize>,
+}
+
+/// Endpoint that inspects and flushes the cache at runtime.
+#[derive(Clone, Debug)]
+pub struct CacheAdmin {
+    pub path: String,
+    /// Scope the requests have to be granted by the `auth` providers
+    pub scope: String,
+    pub auth: Auth,
 }
 
 /// Mimic of mini_v8::Script that's wasm compatible
@@ -41,6 +70,24 @@ pub struct Server {
 pub struct Script {
     pub source: String,
     pub timeout: Option<Duration>,
+    /// Number of the workers that run the script
+    pub pool_size: NonZeroUsize,
+    /// Number of the calls that wait for a free worker, unbounded if not set
+    pub max_queue: Option<usize>,
+    /// Number of the calls after which a worker recreates its JS runtime
+    pub max_requests: Option<NonZeroUsize>,
+}
+
+impl Script {
+    pub fn new(source: String) -> Self {
+        Self {
+            source,
+            timeout: None,
+            pool_size: NonZeroUsize::MIN,
+            max_queue: None,
+            max_requests: None,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -121,8 +168,18 @@ impl TryFrom<crate::core::config::ConfigModule> for Server {
                     .as_ref()
                     .and_then(|headers| headers.get_cors()),
             ))
+            .zip(validate_request_id_header(
+                config_server.get_request_id_header(),
+            ))
+            .zip(to_cache_admin(&config_module))
             .map(
-                |(hostname, http, response_headers, script, experimental_headers, cors)| Server {
+                |(
+                    (
+                        (hostname, http, response_headers, script, experimental_headers, cors),
+                        request_id_header,
+                    ),
+                    cache_admin,
+                )| Server {
                     enable_apollo_tracing: (config_server).enable_apollo_tracing(),
                     enable_cache_control_header: (config_server).enable_cache_control(),
                     enable_set_cookie_header: (config_server).enable_set_cookies(),
@@ -131,6 +188,8 @@ impl TryFrom<crate::core::config::ConfigModule> for Server {
                     enable_response_validation: (config_server).enable_http_validation(),
                     enable_batch_requests: (config_server).enable_batch_requests(),
                     enable_showcase: (config_server).enable_showcase(),
+                    enable_debug_stats: (config_server).enable_debug_stats(),
+                    enable_msgpack: (config_server).enable_msgpack(),
                     experimental_headers,
                     global_response_timeout: (config_server).get_global_response_timeout(),
                     http,
@@ -143,6 +202,16 @@ impl TryFrom<crate::core::config::ConfigModule> for Server {
                     script,
                     cors,
                     routes: config_server.get_routes(),
+                    request_id_header,
+                    health_check: config_server.health_check.clone(),
+                    cache_admin,
+                    cache_salt: to_cache_salt(&config_module),
+                    cache_snapshot: config_server.cache_snapshot.as_ref().map(PathBuf::from),
+                    cache_max_entries: config_server.cache_max_entries,
+                    max_upstream_calls: config_server.get_max_upstream_calls(),
+                    max_path_depth: config_server.get_max_path_depth(),
+                    max_query_depth: config_server.get_max_query_depth(),
+                    max_query_fields: config_server.get_max_query_fields(),
                 },
             )
             .to_result()
@@ -155,19 +224,60 @@ fn to_script(
     config_module.extensions().script.as_ref().map_or_else(
         || Valid::succeed(None),
         |script| {
-            Valid::succeed(Some(Script {
-                source: script.clone(),
-                timeout: config_module
-                    .server
-                    .script
-                    .clone()
-                    .map_or_else(|| None, |script| script.timeout)
-                    .map(Duration::from_millis),
-            }))
+            let options = config_module.server.script.clone().unwrap_or_default();
+
+            Valid::from_option(
+                NonZeroUsize::new(options.pool_size.unwrap_or(1)),
+                BlueprintError::ServerScriptPoolSizeZero,
+            )
+            .trace("poolSize")
+            .trace("script")
+            .map(|pool_size| {
+                Some(Script {
+                    source: script.clone(),
+                    timeout: options.timeout.map(Duration::from_millis),
+                    pool_size,
+                    max_queue: options.max_queue,
+                    max_requests: options.max_requests.and_then(NonZeroUsize::new),
+                })
+            })
         },
     )
 }
 
+/// Hashes the configured cache version, or the whole configuration when the
+/// version isn't set.
+fn to_cache_salt(config_module: &ConfigModule) -> u64 {
+    let mut hasher = TailcallHasher::default();
+    match config_module.server.cache_version.as_ref() {
+        Some(version) => version.hash(&mut hasher),
+        None => config_module.to_sdl().hash(&mut hasher),
+    }
+
+    hasher.finish()
+}
+
+fn to_cache_admin(config_module: &ConfigModule) -> Valid<Option<CacheAdmin>, BlueprintError> {
+    let Some(cache_admin) = config_module.server.cache_admin.as_ref() else {
+        return Valid::succeed(None);
+    };
+
+    match Auth::from_config(config_module) {
+        Some(auth) => Valid::succeed(Some(CacheAdmin {
+            path: cache_admin
+                .path
+                .clone()
+                .unwrap_or_else(|| "/admin/cache".to_string()),
+            scope: cache_admin.scope.clone(),
+            auth,
+        })),
+        None => Valid::fail(BlueprintError::CacheAdminNoAuthProviders),
+    }
+    .trace("cacheAdmin")
+    .trace("@server")
+    .trace("schema")
+}
+
 fn validate_cors(cors: Option<config::cors::Cors>) -> Valid<Option<Cors>, BlueprintError> {
     Valid::from(cors.map(|cors| cors.try_into()).transpose())
         .trace("cors")
@@ -214,6 +324,20 @@ fn handle_response_headers(
     .trace("schema")
 }
 
+fn validate_request_id_header(header: Option<String>) -> Valid<Option<HeaderName>, BlueprintError> {
+    match header {
+        Some(header) => match HeaderName::from_str(&header) {
+            Ok(name) => Valid::succeed(Some(name)),
+            Err(e) => Valid::fail(BlueprintError::InvalidHeaderName(e)),
+        },
+        None => Valid::succeed(None),
+    }
+    .trace("header")
+    .trace("requestId")
+    .trace("@server")
+    .trace("schema")
+}
+
 fn handle_experimental_headers(
     headers: BTreeSet<String>,
 ) -> Valid<HashSet<HeaderName>, BlueprintError> {
@@ -236,11 +360,61 @@ fn handle_experimental_headers(
 
 #[cfg(test)]
 mod tests {
-    use crate::core::config::ConfigModule;
+    use http::header::HeaderName;
+
+    use crate::core::config::{CacheAdmin, Config, ConfigModule, RequestId};
 
     #[test]
     fn test_try_from_default() {
         let actual = super::Server::try_from(ConfigModule::default());
         assert!(actual.is_ok())
     }
+
+    #[test]
+    fn test_cache_salt() {
+        let salt = |version: Option<&str>| {
+            let mut config = Config::default();
+            config.server.cache_version = version.map(str::to_string);
+            super::Server::try_from(ConfigModule::from(config))
+                .unwrap()
+                .cache_salt
+        };
+
+        assert_eq!(salt(Some("v1")), salt(Some("v1")));
+        assert_ne!(salt(Some("v1")), salt(Some("v2")));
+        assert_eq!(salt(None), salt(None));
+        assert_ne!(salt(None), salt(Some("v1")));
+    }
+
+    #[test]
+    fn test_request_id_header() {
+        let mut config = Config::default();
+        config.server.request_id = Some(RequestId::default().with_header("X-Trace-Id"));
+        let config_module = ConfigModule::from(config);
+
+        let actual = super::Server::try_from(config_module)
+            .unwrap()
+            .request_id_header;
+        assert_eq!(actual, Some(HeaderName::from_static("x-trace-id")));
+    }
+
+    #[test]
+    fn test_request_id_header_invalid() {
+        let mut config = Config::default();
+        config.server.request_id = Some(RequestId::default().with_header("x trace"));
+        let config_module = ConfigModule::from(config);
+
+        let actual = super::Server::try_from(config_module);
+        assert!(actual.is_err())
+    }
+
+    #[test]
+    fn test_cache_admin_without_auth() {
+        let mut config = Config::default();
+        config.server.cache_admin = Some(CacheAdmin { path: None, scope: "admin".to_string() });
+        let config_module = ConfigModule::from(config);
+
+        let actual = super::Server::try_from(config_module);
+        assert!(actual.is_err())
+    }
 }
@@ -6,7 +6,7 @@ use url::Url;
 
 use super::{BlueprintError, TryFoldConfig};
 use crate::core::config::{
-    self, Apollo, ConfigModule, KeyValue, PrometheusExporter, StdoutExporter,
+    self, Apollo, ConfigModule, FieldLogs, KeyValue, PrometheusExporter, StdoutExporter,
 };
 use crate::core::directive::DirectiveCodec;
 use crate::core::try_fold::TryFold;
@@ -29,6 +29,7 @@ pub enum TelemetryExporter {
 pub struct Telemetry {
     pub export: Option<TelemetryExporter>,
     pub request_headers: Vec<String>,
+    pub field_logs: Option<FieldLogs>,
 }
 
 fn to_url(url: &str) -> Valid<Url, BlueprintError> {
@@ -80,10 +81,11 @@ pub fn to_opentelemetry<'a>() -> TryFold<'a, ConfigModule, Telemetry, BlueprintE
                 .map(|export| Telemetry {
                     export: Some(export),
                     request_headers: config.telemetry.request_headers.clone(),
+                    field_logs: config.telemetry.field_logs.clone(),
                 })
                 .trace(config::Telemetry::trace_name().as_str())
         } else {
-            Valid::succeed(up)
+            Valid::succeed(Telemetry { field_logs: config.telemetry.field_logs.clone(), ..up })
         }
     })
 }
@@ -12,15 +12,20 @@ fn compile_union_resolver(
     union_definition: &Union,
     discriminate: &Option<Discriminate>,
 ) -> Valid<Discriminator, BlueprintError> {
-    let typename_field = discriminate.as_ref().map(|d| d.get_field());
+    let discriminator = match discriminate {
+        Some(discriminate) if !discriminate.status.is_empty() => Discriminator::with_status(
+            union_name.to_string(),
+            union_definition.types.clone(),
+            discriminate.get_status(),
+        ),
+        _ => Discriminator::new(
+            union_name.to_string(),
+            union_definition.types.clone(),
+            discriminate.as_ref().map(|d| d.get_field()),
+        ),
+    };
 
-    match Discriminator::new(
-        union_name.to_string(),
-        union_definition.types.clone(),
-        typename_field,
-    )
-    .to_result()
-    {
+    match discriminator.to_result() {
         Ok(discriminator) => Valid::succeed(discriminator),
         Err(e) => Valid::from_validation_err(BlueprintError::from_validation_string(e)),
     }
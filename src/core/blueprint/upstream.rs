@@ -1,16 +1,89 @@
 use std::collections::BTreeSet;
+use std::time::Duration;
 
 use derive_setters::Setters;
 use tailcall_valid::{Valid, ValidationError, Validator};
 
 use super::BlueprintError;
-use crate::core::config::{self, Batch, ConfigModule};
+use crate::core::config::{self, Batch, ConfigModule, Http3};
+use crate::core::ir::ValueDiff;
+use crate::core::rng::Rng;
 
 #[derive(PartialEq, Eq, Clone, Debug, schemars::JsonSchema)]
 pub struct Proxy {
     pub url: String,
 }
 
+/// Settings of the timeouts that adapt to the recent latencies of the
+/// upstreams.
+#[derive(PartialEq, Eq, Clone, Debug, schemars::JsonSchema)]
+pub struct AdaptiveTimeout {
+    pub percentile: u8,
+    pub factor: u32,
+    pub half_life: Duration,
+    pub min_samples: u64,
+    pub min: Duration,
+}
+
+/// Limits of the concurrent requests to each upstream, see
+/// `@upstream(bulkhead)`.
+#[derive(PartialEq, Eq, Clone, Debug, schemars::JsonSchema)]
+pub struct Bulkhead {
+    pub max_in_flight: usize,
+    pub max_wait: Duration,
+    pub max_queue: Option<usize>,
+}
+
+/// Shadowing of the `@http` requests to a canary upstream, see
+/// `@upstream(canary)`.
+#[derive(PartialEq, Eq, Clone, Debug, schemars::JsonSchema)]
+pub struct Canary {
+    pub base_url: String,
+    /// Percentage of the requests that are shadowed
+    pub sample: u8,
+    /// The canary calls that take longer are dropped without being compared
+    pub timeout: Duration,
+    pub diff: ValueDiff,
+}
+
+impl Canary {
+    /// The copy of the request that is shadowed to the canary upstream, `None`
+    /// when the request isn't sampled or its body can't be replayed. Only the
+    /// `GET` and `HEAD` requests are shadowed, the others could change the
+    /// state of the canary upstream.
+    pub fn request(&self, request: &reqwest::Request, rng: &Rng) -> Option<reqwest::Request> {
+        if !matches!(
+            *request.method(),
+            reqwest::Method::GET | reqwest::Method::HEAD
+        ) {
+            return None;
+        }
+        if !rng.gen_bool(f64::from(self.sample) / 100.0) {
+            return None;
+        }
+
+        let base_url = url::Url::parse(&self.base_url).ok()?;
+        let mut canary = request.try_clone()?;
+        let url = canary.url_mut();
+        url.set_scheme(base_url.scheme()).ok()?;
+        url.set_host(base_url.host_str()).ok()?;
+        url.set_port(base_url.port()).ok()?;
+
+        Some(canary)
+    }
+}
+
+/// OAuth2 client credentials of an upstream, see `@upstream(tokenProviders)`.
+#[derive(PartialEq, Eq, Clone, Debug, schemars::JsonSchema)]
+pub struct TokenProvider {
+    pub origin: String,
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scope: Option<String>,
+    pub refresh_before: Duration,
+}
+
 #[derive(PartialEq, Eq, Clone, Debug, Setters, schemars::JsonSchema)]
 pub struct Upstream {
     pub pool_idle_timeout: u64,
@@ -20,18 +93,36 @@ pub struct Upstream {
     pub keep_alive_while_idle: bool,
     pub proxy: Option<Proxy>,
     pub connect_timeout: u64,
+    pub dns_ttl: Option<u64>,
     pub timeout: u64,
     pub tcp_keep_alive: u64,
     pub user_agent: String,
     pub allowed_headers: BTreeSet<String>,
     pub http_cache: u64,
+    pub max_decoded_size: usize,
     pub batch: Option<Batch>,
     pub http2_only: bool,
+    pub http2_adaptive_window: bool,
+    pub http2_max_concurrent_streams: Option<usize>,
+    pub http3: Option<Http3>,
     pub on_request: Option<String>,
     pub verify_ssl: bool,
+    pub adaptive_timeout: Option<AdaptiveTimeout>,
+    pub bulkhead: Option<Bulkhead>,
+    pub canary: Option<Canary>,
+    pub danger_accept_invalid_certs: bool,
+    pub token_providers: Vec<TokenProvider>,
+    // Set when tailcall is started with `--insecure`.
+    pub insecure: bool,
 }
 
 impl Upstream {
+    /// Checks if the clients accept invalid certificates of the upstreams.
+    /// `dangerAcceptInvalidCerts` is ignored unless the insecure mode is on.
+    pub fn accept_invalid_certs(&self) -> bool {
+        !self.verify_ssl || (self.danger_accept_invalid_certs && self.insecure)
+    }
+
     /// If the delay is set to 0, then batching is disabled. By default delay is
     /// set to 0.
     pub fn is_batching_enabled(&self) -> bool {
@@ -65,24 +156,53 @@ impl TryFrom<&ConfigModule> for Upstream {
 
         get_batch(&config_upstream)
             .fuse(get_proxy(&config_upstream))
-            .map(|(batch, proxy)| Upstream {
-                pool_idle_timeout: (config_upstream).get_pool_idle_timeout(),
-                pool_max_idle_per_host: (config_upstream).get_pool_max_idle_per_host(),
-                keep_alive_interval: (config_upstream).get_keep_alive_interval(),
-                keep_alive_timeout: (config_upstream).get_keep_alive_timeout(),
-                keep_alive_while_idle: (config_upstream).get_keep_alive_while_idle(),
-                proxy,
-                connect_timeout: (config_upstream).get_connect_timeout(),
-                timeout: (config_upstream).get_timeout(),
-                tcp_keep_alive: (config_upstream).get_tcp_keep_alive(),
-                user_agent: (config_upstream).get_user_agent(),
-                allowed_headers,
-                http_cache: (config_upstream).get_http_cache_size(),
-                batch,
-                http2_only: (config_upstream).get_http_2_only(),
-                on_request: (config_upstream).get_on_request(),
-                verify_ssl: (config_upstream).get_verify_ssl(),
-            })
+            .fuse(get_adaptive_timeout(&config_upstream))
+            .fuse(get_bulkhead(&config_upstream))
+            .fuse(get_token_providers(&config_upstream))
+            .fuse(get_http2_max_concurrent_streams(&config_upstream))
+            .zip(get_canary(&config_upstream))
+            .map(
+                |(
+                    (
+                        batch,
+                        proxy,
+                        adaptive_timeout,
+                        bulkhead,
+                        token_providers,
+                        http2_max_concurrent_streams,
+                    ),
+                    canary,
+                )| Upstream {
+                    pool_idle_timeout: (config_upstream).get_pool_idle_timeout(),
+                    pool_max_idle_per_host: (config_upstream).get_pool_max_idle_per_host(),
+                    keep_alive_interval: (config_upstream).get_keep_alive_interval(),
+                    keep_alive_timeout: (config_upstream).get_keep_alive_timeout(),
+                    keep_alive_while_idle: (config_upstream).get_keep_alive_while_idle(),
+                    proxy,
+                    connect_timeout: (config_upstream).get_connect_timeout(),
+                    dns_ttl: (config_upstream).get_dns_ttl(),
+                    timeout: (config_upstream).get_timeout(),
+                    tcp_keep_alive: (config_upstream).get_tcp_keep_alive(),
+                    user_agent: (config_upstream).get_user_agent(),
+                    allowed_headers,
+                    http_cache: (config_upstream).get_http_cache_size(),
+                    max_decoded_size: (config_upstream).get_max_decoded_size(),
+                    batch,
+                    http2_only: (config_upstream).get_http_2_only(),
+                    http2_adaptive_window: (config_upstream).get_http_2_adaptive_window(),
+                    http2_max_concurrent_streams,
+                    http3: config_upstream.http3,
+                    on_request: (config_upstream).get_on_request(),
+                    verify_ssl: (config_upstream).get_verify_ssl(),
+                    adaptive_timeout,
+                    bulkhead,
+                    canary,
+                    danger_accept_invalid_certs: (config_upstream)
+                        .get_danger_accept_invalid_certs(),
+                    token_providers,
+                    insecure: false,
+                },
+            )
             .to_result()
     }
 }
@@ -107,3 +227,219 @@ fn get_proxy(upstream: &config::Upstream) -> Valid<Option<Proxy>, BlueprintError
         Valid::succeed(None)
     }
 }
+
+fn get_token_providers(upstream: &config::Upstream) -> Valid<Vec<TokenProvider>, BlueprintError> {
+    Valid::from_iter(upstream.token_providers.iter(), |provider| {
+        let origin = Valid::from_option(
+            url::Url::parse(&provider.origin)
+                .ok()
+                .map(|url| url.origin().ascii_serialization()),
+            BlueprintError::InvalidTokenProvider(format!(
+                "`{}` isn't a valid origin",
+                provider.origin
+            )),
+        )
+        .trace("origin");
+        let token_url = Valid::from_option(
+            url::Url::parse(&provider.token_url)
+                .ok()
+                .map(|_| provider.token_url.clone()),
+            BlueprintError::InvalidTokenProvider(format!(
+                "`{}` isn't a valid url",
+                provider.token_url
+            )),
+        )
+        .trace("tokenUrl");
+
+        origin
+            .fuse(token_url)
+            .map(|(origin, token_url)| TokenProvider {
+                origin,
+                token_url,
+                client_id: provider.client_id.clone(),
+                client_secret: provider.client_secret.clone(),
+                scope: provider.scope.clone(),
+                refresh_before: Duration::from_secs(provider.refresh_before.unwrap_or(60)),
+            })
+    })
+    .trace("tokenProviders")
+}
+
+fn get_adaptive_timeout(
+    upstream: &config::Upstream,
+) -> Valid<Option<AdaptiveTimeout>, BlueprintError> {
+    let Some(adaptive_timeout) = upstream.adaptive_timeout.as_ref() else {
+        return Valid::succeed(None);
+    };
+
+    let percentile = adaptive_timeout.percentile.unwrap_or(99);
+    let factor = adaptive_timeout.factor.unwrap_or(2);
+    let half_life = adaptive_timeout.half_life.unwrap_or(60000);
+
+    let percentile = Valid::from_option(
+        Some(percentile).filter(|percentile| (1..=100).contains(percentile)),
+        BlueprintError::InvalidAdaptiveTimeout(format!(
+            "`percentile` must be between 1 and 100, found {percentile}"
+        )),
+    )
+    .trace("percentile");
+    let factor = Valid::from_option(
+        Some(factor).filter(|factor| *factor > 0),
+        BlueprintError::InvalidAdaptiveTimeout("`factor` must be greater than 0".to_string()),
+    )
+    .trace("factor");
+    let half_life = Valid::from_option(
+        Some(half_life).filter(|half_life| *half_life > 0),
+        BlueprintError::InvalidAdaptiveTimeout("`halfLife` must be greater than 0".to_string()),
+    )
+    .trace("halfLife");
+
+    percentile
+        .fuse(factor)
+        .fuse(half_life)
+        .map(|(percentile, factor, half_life)| {
+            Some(AdaptiveTimeout {
+                percentile,
+                factor,
+                half_life: Duration::from_millis(half_life),
+                min_samples: adaptive_timeout.min_samples.unwrap_or(20),
+                min: Duration::from_millis(adaptive_timeout.min.unwrap_or(100)),
+            })
+        })
+        .trace("adaptiveTimeout")
+}
+
+fn get_bulkhead(upstream: &config::Upstream) -> Valid<Option<Bulkhead>, BlueprintError> {
+    let Some(bulkhead) = upstream.bulkhead.as_ref() else {
+        return Valid::succeed(None);
+    };
+
+    let max_in_flight = bulkhead.max_in_flight.unwrap_or(100);
+
+    Valid::from_option(
+        Some(max_in_flight).filter(|max_in_flight| *max_in_flight > 0),
+        BlueprintError::InvalidBulkhead("`maxInFlight` must be greater than 0".to_string()),
+    )
+    .trace("maxInFlight")
+    .map(|max_in_flight| {
+        Some(Bulkhead {
+            max_in_flight,
+            max_wait: Duration::from_millis(bulkhead.max_wait.unwrap_or(1000)),
+            max_queue: bulkhead.max_queue,
+        })
+    })
+    .trace("bulkhead")
+}
+
+fn get_canary(upstream: &config::Upstream) -> Valid<Option<Canary>, BlueprintError> {
+    let Some(canary) = upstream.canary.as_ref() else {
+        return Valid::succeed(None);
+    };
+
+    let base_url = Valid::from_option(
+        url::Url::parse(&canary.base_url)
+            .ok()
+            .filter(|url| url.has_host())
+            .map(|_| canary.base_url.clone()),
+        BlueprintError::InvalidCanary(format!("`{}` isn't a valid url", canary.base_url)),
+    )
+    .trace("baseURL");
+    let sample = canary.sample.unwrap_or(1);
+    let sample = Valid::from_option(
+        Some(sample).filter(|sample| *sample <= 100),
+        BlueprintError::InvalidCanary(format!(
+            "`sample` must be between 0 and 100, found {sample}"
+        )),
+    )
+    .trace("sample");
+
+    base_url
+        .fuse(sample)
+        .map(|(base_url, sample)| {
+            let unordered = canary
+                .unordered
+                .iter()
+                .map(|path| path.split('.').map(String::from).collect())
+                .collect();
+            let max_nodes = canary.max_nodes.unwrap_or(ValueDiff::default().max_nodes);
+
+            Some(Canary {
+                base_url,
+                sample,
+                timeout: Duration::from_millis(canary.timeout.unwrap_or(1000)),
+                diff: ValueDiff { unordered, max_nodes },
+            })
+        })
+        .trace("canary")
+}
+
+fn get_http2_max_concurrent_streams(
+    upstream: &config::Upstream,
+) -> Valid<Option<usize>, BlueprintError> {
+    match upstream.http2_max_concurrent_streams {
+        Some(0) => Valid::fail(BlueprintError::InvalidHttp2MaxConcurrentStreams)
+            .trace("http2MaxConcurrentStreams"),
+        max => Valid::succeed(max),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::Config;
+
+    #[test]
+    fn test_accept_invalid_certs_requires_insecure() {
+        let mut config = Config::default();
+        config.upstream.danger_accept_invalid_certs = Some(true);
+        let upstream = Upstream::try_from(&ConfigModule::from(config)).unwrap();
+
+        assert!(upstream.danger_accept_invalid_certs);
+        assert!(!upstream.accept_invalid_certs());
+        assert!(upstream.insecure(true).accept_invalid_certs());
+        assert!(!Upstream::default().insecure(true).accept_invalid_certs());
+    }
+
+    #[test]
+    fn test_canary_request() {
+        let canary = Canary {
+            base_url: "http://canary:8081".to_string(),
+            sample: 100,
+            timeout: Duration::from_secs(1),
+            diff: ValueDiff::default(),
+        };
+        let url = "https://primary/users/1?active=true".parse().unwrap();
+        let request = reqwest::Request::new(reqwest::Method::GET, url);
+        let rng = Rng::with_seed(0);
+
+        let actual = canary.request(&request, &rng).unwrap();
+        assert_eq!(
+            actual.url().as_str(),
+            "http://canary:8081/users/1?active=true"
+        );
+
+        // the requests that could change the state of the canary aren't shadowed
+        let url = "https://primary/users".parse().unwrap();
+        let post = reqwest::Request::new(reqwest::Method::POST, url);
+        assert!(canary.request(&post, &rng).is_none());
+
+        let canary = Canary { sample: 0, ..canary };
+        assert!(canary.request(&request, &rng).is_none());
+    }
+
+    #[test]
+    fn test_invalid_canary() {
+        let mut config = Config::default();
+        config.upstream.canary = Some(config::Canary {
+            base_url: "canary".to_string(),
+            sample: Some(101),
+            ..Default::default()
+        });
+        let errors = Upstream::try_from(&ConfigModule::from(config))
+            .unwrap_err()
+            .as_vec()
+            .len();
+
+        assert_eq!(errors, 2);
+    }
+}
@@ -1,29 +1,124 @@
+use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 use std::num::NonZeroU64;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock};
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
+use serde::Serialize;
 use ttl_cache::TtlCache;
 
 use super::error::Result;
 
+/// Counters of a cache store, they're reported by the cache admin endpoint.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Stats {
+    /// Number of the entries that haven't expired
+    pub entries: usize,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+/// An entry of a cache store along with when it expires, the entries are
+/// listed to persist the store across restarts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry<K, V> {
+    pub key: K,
+    pub value: V,
+    pub expires_at: SystemTime,
+    pub tags: Vec<String>,
+}
+
+/// The keys of the entries tagged with each tag, and the tags of each key so
+/// that the key is removed from its tags once the entry is replaced or
+/// removed.
+struct TagIndex<K> {
+    keys: HashMap<String, HashSet<K>>,
+    tags: HashMap<K, Vec<String>>,
+}
+
+impl<K> Default for TagIndex<K> {
+    fn default() -> Self {
+        Self { keys: HashMap::new(), tags: HashMap::new() }
+    }
+}
+
+impl<K: Hash + Eq + Clone> TagIndex<K> {
+    fn tag(&mut self, key: K, tags: Vec<String>) {
+        self.untag(&key);
+        if tags.is_empty() {
+            return;
+        }
+
+        for tag in &tags {
+            self.keys
+                .entry(tag.clone())
+                .or_default()
+                .insert(key.clone());
+        }
+        self.tags.insert(key, tags);
+    }
+
+    fn untag(&mut self, key: &K) {
+        for tag in self.tags.remove(key).unwrap_or_default() {
+            if let Some(keys) = self.keys.get_mut(&tag) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.keys.remove(&tag);
+                }
+            }
+        }
+    }
+
+    /// Removes the tag and returns the keys that were tagged with it.
+    fn take(&mut self, tag: &str) -> HashSet<K> {
+        let keys = self.keys.remove(tag).unwrap_or_default();
+        for key in &keys {
+            self.untag(key);
+        }
+
+        keys
+    }
+
+    /// Drops the keys of the entries that are gone, eg. that have expired.
+    fn retain(&mut self, is_present: impl Fn(&K) -> bool) {
+        let gone = self
+            .tags
+            .keys()
+            .filter(|key| !is_present(key))
+            .cloned()
+            .collect::<Vec<_>>();
+        for key in &gone {
+            self.untag(key);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.keys.clear();
+        self.tags.clear();
+    }
+}
+
 pub struct InMemoryCache<K: Hash + Eq, V> {
-    data: Arc<RwLock<TtlCache<K, V>>>,
+    /// The values are stored along with their expiry, which the TTL cache
+    /// doesn't expose.
+    data: Arc<RwLock<TtlCache<K, (V, SystemTime)>>>,
+    tags: Arc<RwLock<TagIndex<K>>>,
     hits: AtomicUsize,
     miss: AtomicUsize,
 }
 
-impl<K: Hash + Eq, V: Clone> Default for InMemoryCache<K, V> {
+impl<K: Hash + Eq + Clone, V: Clone> Default for InMemoryCache<K, V> {
     fn default() -> Self {
         Self::new(100000)
     }
 }
 
-impl<K: Hash + Eq, V: Clone> InMemoryCache<K, V> {
+impl<K: Hash + Eq + Clone, V: Clone> InMemoryCache<K, V> {
     pub fn new(capacity: usize) -> Self {
         InMemoryCache {
             data: Arc::new(RwLock::new(TtlCache::new(capacity))),
+            tags: Arc::new(RwLock::new(TagIndex::default())),
             hits: AtomicUsize::new(0),
             miss: AtomicUsize::new(0),
         }
@@ -31,7 +126,7 @@ impl<K: Hash + Eq, V: Clone> InMemoryCache<K, V> {
 }
 
 #[async_trait::async_trait]
-impl<K: Hash + Eq + Send + Sync, V: Clone + Send + Sync> crate::core::Cache
+impl<K: Hash + Eq + Clone + Send + Sync, V: Clone + Send + Sync> crate::core::Cache
     for InMemoryCache<K, V>
 {
     type Key = K;
@@ -39,12 +134,62 @@ impl<K: Hash + Eq + Send + Sync, V: Clone + Send + Sync> crate::core::Cache
     #[allow(clippy::too_many_arguments)]
     async fn set<'a>(&'a self, key: K, value: V, ttl: NonZeroU64) -> Result<()> {
         let ttl = Duration::from_millis(ttl.get());
-        self.data.write().unwrap().insert(key, value, ttl);
+        let mut data = self.data.write().unwrap();
+        // the entry that is replaced loses its tags
+        self.tags.write().unwrap().untag(&key);
+        data.insert(key, (value, SystemTime::now() + ttl), ttl);
+        Ok(())
+    }
+
+    async fn set_with_tags<'a>(
+        &'a self,
+        key: K,
+        value: V,
+        ttl: NonZeroU64,
+        tags: Vec<String>,
+    ) -> Result<()> {
+        let mut data = self.data.write().unwrap();
+        self.tags.write().unwrap().tag(key.clone(), tags);
+
+        let ttl = Duration::from_millis(ttl.get());
+        data.insert(key, (value, SystemTime::now() + ttl), ttl);
         Ok(())
     }
 
+    async fn invalidate<'a>(&'a self, tag: &'a str) -> Result<usize> {
+        let mut data = self.data.write().unwrap();
+        let mut index = self.tags.write().unwrap();
+        let keys = index.take(tag);
+        let count = keys.iter().filter(|key| data.remove(key).is_some()).count();
+        // drop the keys of the entries that have expired since they were tagged
+        index.retain(|key| data.contains_key(key));
+
+        Ok(count)
+    }
+
+    async fn evict<'a>(&'a self, key: &'a K) -> Result<bool> {
+        let mut data = self.data.write().unwrap();
+        self.tags.write().unwrap().untag(key);
+
+        Ok(data.remove(key).is_some())
+    }
+
+    async fn clear<'a>(&'a self) -> Result<usize> {
+        let mut data = self.data.write().unwrap();
+        let count = data.iter().count();
+        data.clear();
+        self.tags.write().unwrap().clear();
+
+        Ok(count)
+    }
+
     async fn get<'a>(&'a self, key: &'a K) -> Result<Option<Self::Value>> {
-        let val = self.data.read().unwrap().get(key).cloned();
+        let val = self
+            .data
+            .read()
+            .unwrap()
+            .get(key)
+            .map(|(value, _)| value.clone());
         if val.is_some() {
             self.hits.fetch_add(1, Ordering::Relaxed);
         } else {
@@ -66,6 +211,85 @@ impl<K: Hash + Eq + Send + Sync, V: Clone + Send + Sync> crate::core::Cache
 
         None
     }
+
+    fn stats(&self) -> Option<Stats> {
+        Some(Stats {
+            entries: self.data.write().unwrap().iter().count(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.miss.load(Ordering::Relaxed),
+        })
+    }
+
+    fn entries(&self) -> Option<Vec<Entry<K, V>>> {
+        let mut data = self.data.write().unwrap();
+        let index = self.tags.read().unwrap();
+
+        let entries = data
+            .iter()
+            .map(|(key, (value, expires_at))| Entry {
+                key: key.clone(),
+                value: value.clone(),
+                expires_at: *expires_at,
+                tags: index.tags.get(key).cloned().unwrap_or_default(),
+            })
+            .collect();
+
+        Some(entries)
+    }
+}
+
+/// A shared store, eg. one whose entries are also counted by the source of the
+/// memory pressure of a [super::PressureAwareCache].
+#[async_trait::async_trait]
+impl<C: crate::core::Cache + ?Sized> crate::core::Cache for Arc<C>
+where
+    C::Key: Send + Sync,
+    C::Value: Send,
+{
+    type Key = C::Key;
+    type Value = C::Value;
+
+    async fn set<'a>(&'a self, key: Self::Key, value: Self::Value, ttl: NonZeroU64) -> Result<()> {
+        self.as_ref().set(key, value, ttl).await
+    }
+
+    async fn set_with_tags<'a>(
+        &'a self,
+        key: Self::Key,
+        value: Self::Value,
+        ttl: NonZeroU64,
+        tags: Vec<String>,
+    ) -> Result<()> {
+        self.as_ref().set_with_tags(key, value, ttl, tags).await
+    }
+
+    async fn get<'a>(&'a self, key: &'a Self::Key) -> Result<Option<Self::Value>> {
+        self.as_ref().get(key).await
+    }
+
+    async fn invalidate<'a>(&'a self, tag: &'a str) -> Result<usize> {
+        self.as_ref().invalidate(tag).await
+    }
+
+    async fn evict<'a>(&'a self, key: &'a Self::Key) -> Result<bool> {
+        self.as_ref().evict(key).await
+    }
+
+    async fn clear<'a>(&'a self) -> Result<usize> {
+        self.as_ref().clear().await
+    }
+
+    fn hit_rate(&self) -> Option<f64> {
+        self.as_ref().hit_rate()
+    }
+
+    fn stats(&self) -> Option<Stats> {
+        self.as_ref().stats()
+    }
+
+    fn entries(&self) -> Option<Vec<Entry<Self::Key, Self::Value>>> {
+        self.as_ref().entries()
+    }
 }
 
 #[cfg(test)]
@@ -73,6 +297,7 @@ mod tests {
     use std::num::NonZeroU64;
     use std::time::Duration;
 
+    use super::Stats;
     use crate::core::Cache;
 
     #[tokio::test]
@@ -89,4 +314,102 @@ mod tests {
         tokio::time::sleep(Duration::from_millis(ttl.get())).await;
         assert_eq!(cache.get(&10).await.ok(), Some(None));
     }
+
+    #[tokio::test]
+    async fn test_invalidate_tag() {
+        let cache: crate::core::cache::InMemoryCache<u64, String> =
+            crate::core::cache::InMemoryCache::default();
+        let ttl = NonZeroU64::new(10000).unwrap();
+        let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect();
+
+        cache
+            .set_with_tags(1, "user".into(), ttl, tags(&["user:1"]))
+            .await
+            .unwrap();
+        cache
+            .set_with_tags(2, "posts".into(), ttl, tags(&["user:1", "posts"]))
+            .await
+            .unwrap();
+        cache
+            .set_with_tags(3, "other user".into(), ttl, tags(&["user:2"]))
+            .await
+            .unwrap();
+
+        assert_eq!(cache.invalidate("user:1").await.unwrap(), 2);
+        assert_eq!(cache.get(&1).await.ok(), Some(None));
+        assert_eq!(cache.get(&2).await.ok(), Some(None));
+        assert_eq!(cache.get(&3).await.ok(), Some(Some("other user".into())));
+
+        assert_eq!(cache.invalidate("user:1").await.unwrap(), 0);
+        assert_eq!(cache.invalidate("posts").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_tag_index_pruning() {
+        let cache: crate::core::cache::InMemoryCache<u64, String> =
+            crate::core::cache::InMemoryCache::default();
+        let ttl = NonZeroU64::new(10000).unwrap();
+        let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect();
+        let indexed = |cache: &crate::core::cache::InMemoryCache<u64, String>| {
+            let index = cache.tags.read().unwrap();
+            (index.keys.len(), index.tags.len())
+        };
+
+        cache
+            .set_with_tags(1, "user".into(), ttl, tags(&["user:1"]))
+            .await
+            .unwrap();
+        cache
+            .set_with_tags(2, "posts".into(), ttl, tags(&["posts"]))
+            .await
+            .unwrap();
+        cache
+            .set_with_tags(
+                3,
+                "short".into(),
+                NonZeroU64::new(100).unwrap(),
+                tags(&["short"]),
+            )
+            .await
+            .unwrap();
+        assert_eq!(indexed(&cache), (3, 3));
+
+        // the overwritten entry without tags is no longer tagged
+        cache.set(1, "user".into(), ttl).await.unwrap();
+        assert_eq!(cache.invalidate("user:1").await.unwrap(), 0);
+        assert_eq!(cache.get(&1).await.ok(), Some(Some("user".into())));
+
+        // the evicted entry is dropped from its tags
+        assert!(cache.evict(&2).await.unwrap());
+        assert_eq!(indexed(&cache), (1, 1));
+
+        // the expired entries are swept on invalidate
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(cache.invalidate("other").await.unwrap(), 0);
+        assert_eq!(indexed(&cache), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_evict_and_clear() {
+        let cache: crate::core::cache::InMemoryCache<u64, String> =
+            crate::core::cache::InMemoryCache::default();
+        let ttl = NonZeroU64::new(10000).unwrap();
+
+        for key in 1..=3 {
+            cache.set(key, key.to_string(), ttl).await.unwrap();
+        }
+        assert_eq!(cache.get(&4).await.ok(), Some(None));
+
+        assert!(cache.evict(&1).await.unwrap());
+        assert!(!cache.evict(&1).await.unwrap());
+        assert_eq!(cache.get(&1).await.ok(), Some(None));
+        assert_eq!(cache.get(&2).await.ok(), Some(Some("2".into())));
+
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats, Stats { entries: 2, hits: 1, misses: 2 });
+
+        assert_eq!(cache.clear().await.unwrap(), 2);
+        assert_eq!(cache.get(&3).await.ok(), Some(None));
+        assert_eq!(cache.stats().unwrap().entries, 0);
+    }
 }
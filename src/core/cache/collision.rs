@@ -0,0 +1,174 @@
+use std::num::NonZeroU64;
+#[cfg(debug_assertions)]
+use std::sync::RwLock;
+#[cfg(debug_assertions)]
+use std::time::Duration;
+
+#[cfg(debug_assertions)]
+use ttl_cache::TtlCache;
+
+use super::error::Result;
+use super::{Entry, Stats};
+use crate::core::ir::model::IoId;
+use crate::core::Cache;
+
+#[cfg(debug_assertions)]
+const CAPACITY: usize = 100000;
+
+/// Cache that detects the collisions of the ids of the IOs in the debug
+/// builds, ie. the distinct requests that share an id and so would be served
+/// each other's values, which points to a bug in the derivation of the keys.
+/// The fingerprints of the requests are stored alongside the entries and it
+/// panics when a request with the id of an entry has a different fingerprint.
+/// In the release builds it only forwards to the wrapped cache.
+pub struct CollisionCheck<C> {
+    cache: C,
+    #[cfg(debug_assertions)]
+    fingerprints: RwLock<TtlCache<u64, u64>>,
+}
+
+impl<C> CollisionCheck<C> {
+    pub fn new(cache: C) -> Self {
+        Self {
+            cache,
+            #[cfg(debug_assertions)]
+            fingerprints: RwLock::new(TtlCache::new(CAPACITY)),
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    fn check(&self, key: &IoId) {
+        let fingerprints = self.fingerprints.read().unwrap();
+        if let Some(fingerprint) = fingerprints.get(&key.as_u64()) {
+            assert_eq!(
+                *fingerprint,
+                key.fingerprint(),
+                "Collision of the IO id {}, it's shared by distinct requests",
+                key.as_u64()
+            );
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    fn record(&self, key: &IoId, ttl: NonZeroU64) {
+        self.check(key);
+        self.fingerprints.write().unwrap().insert(
+            key.as_u64(),
+            key.fingerprint(),
+            Duration::from_millis(ttl.get()),
+        );
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: Cache<Key = IoId>> Cache for CollisionCheck<C>
+where
+    C::Value: Send,
+{
+    type Key = IoId;
+    type Value = C::Value;
+
+    async fn set<'a>(&'a self, key: IoId, value: Self::Value, ttl: NonZeroU64) -> Result<()> {
+        #[cfg(debug_assertions)]
+        self.record(&key, ttl);
+
+        self.cache.set(key, value, ttl).await
+    }
+
+    async fn set_with_tags<'a>(
+        &'a self,
+        key: IoId,
+        value: Self::Value,
+        ttl: NonZeroU64,
+        tags: Vec<String>,
+    ) -> Result<()> {
+        #[cfg(debug_assertions)]
+        self.record(&key, ttl);
+
+        self.cache.set_with_tags(key, value, ttl, tags).await
+    }
+
+    async fn get<'a>(&'a self, key: &'a IoId) -> Result<Option<Self::Value>> {
+        #[cfg(debug_assertions)]
+        self.check(key);
+
+        self.cache.get(key).await
+    }
+
+    async fn invalidate<'a>(&'a self, tag: &'a str) -> Result<usize> {
+        self.cache.invalidate(tag).await
+    }
+
+    async fn evict<'a>(&'a self, key: &'a IoId) -> Result<bool> {
+        #[cfg(debug_assertions)]
+        self.fingerprints.write().unwrap().remove(&key.as_u64());
+
+        self.cache.evict(key).await
+    }
+
+    async fn clear<'a>(&'a self) -> Result<usize> {
+        #[cfg(debug_assertions)]
+        self.fingerprints.write().unwrap().clear();
+
+        self.cache.clear().await
+    }
+
+    fn hit_rate(&self) -> Option<f64> {
+        self.cache.hit_rate()
+    }
+
+    fn stats(&self) -> Option<Stats> {
+        self.cache.stats()
+    }
+
+    fn entries(&self) -> Option<Vec<Entry<IoId, Self::Value>>> {
+        self.cache.entries()
+    }
+
+    async fn restore<'a>(&'a self, entries: Vec<Entry<IoId, Self::Value>>) -> Result<usize> {
+        // the fingerprints aren't persisted, the restored entries are only
+        // checked once they're set again
+        self.cache.restore(entries).await
+    }
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use std::hash::Hash;
+
+    use super::*;
+    use crate::core::cache::InMemoryCache;
+    use crate::core::ir::model::IoIdHasher;
+
+    fn io_id(url: &str) -> IoId {
+        let mut hasher = IoIdHasher::default();
+        url.hash(&mut hasher);
+        hasher.finish_id()
+    }
+
+    #[tokio::test]
+    async fn test_no_collision() {
+        let cache = CollisionCheck::new(InMemoryCache::<IoId, String>::default());
+        let ttl = NonZeroU64::new(1000).unwrap();
+
+        cache.set(io_id("/users/1"), "1".into(), ttl).await.unwrap();
+        cache.set(io_id("/users/2"), "2".into(), ttl).await.unwrap();
+        cache.set(io_id("/users/1"), "1".into(), ttl).await.unwrap();
+
+        let value = cache.get(&io_id("/users/1")).await.unwrap();
+        assert_eq!(value.as_deref(), Some("1"));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Collision of the IO id 1")]
+    async fn test_collision() {
+        let cache = CollisionCheck::new(InMemoryCache::<IoId, String>::default());
+        let ttl = NonZeroU64::new(1000).unwrap();
+
+        cache
+            .set(IoId::with_fingerprint(1, 10), "1".into(), ttl)
+            .await
+            .unwrap();
+        let _ = cache.get(&IoId::with_fingerprint(1, 20)).await;
+    }
+}
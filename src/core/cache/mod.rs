@@ -1,4 +1,10 @@
 pub mod cache;
+pub mod collision;
 pub mod error;
+pub mod pressure;
+pub mod snapshot;
 pub use cache::*;
+pub use collision::*;
 pub use error::Error;
+pub use pressure::*;
+pub use snapshot::Snapshot;
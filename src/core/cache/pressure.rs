@@ -0,0 +1,201 @@
+use std::num::NonZeroU64;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::error::Result;
+use super::{Entry, Stats};
+use crate::core::Cache;
+
+/// Level of the memory pressure of the process.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Pressure {
+    #[default]
+    Normal,
+    /// The new entries aren't stored, the stored ones are still served.
+    High,
+    /// The cache is bypassed altogether, the values are served straight from
+    /// the upstreams.
+    Critical,
+}
+
+/// Source of the memory pressure signal, eg. the memory used by the process
+/// or the size of the cache store. It's consulted on every access to the
+/// cache, so it should be cheap to check.
+pub trait MemoryPressure: Send + Sync {
+    fn pressure(&self) -> Pressure;
+}
+
+impl<F: Fn() -> Pressure + Send + Sync> MemoryPressure for F {
+    fn pressure(&self) -> Pressure {
+        self()
+    }
+}
+
+/// Memory pressure measured as the number of the entries of a cache store,
+/// it's high once the store holds `max` entries. Counting the entries walks
+/// the store, so the count is refreshed at most once per `interval`.
+pub struct MaxEntries<C: ?Sized> {
+    cache: Arc<C>,
+    max: usize,
+    interval: Duration,
+    measured: Mutex<Option<(Instant, Pressure)>>,
+}
+
+impl<C: Cache + ?Sized> MaxEntries<C> {
+    pub fn new(cache: Arc<C>, max: usize, interval: Duration) -> Self {
+        Self { cache, max, interval, measured: Mutex::new(None) }
+    }
+}
+
+impl<C: Cache + ?Sized> MemoryPressure for MaxEntries<C> {
+    fn pressure(&self) -> Pressure {
+        let mut measured = self.measured.lock().unwrap();
+        match *measured {
+            Some((at, pressure)) if at.elapsed() < self.interval => pressure,
+            _ => {
+                let entries = self.cache.stats().map(|stats| stats.entries);
+                let pressure = match entries {
+                    Some(entries) if entries >= self.max => Pressure::High,
+                    _ => Pressure::Normal,
+                };
+                *measured = Some((Instant::now(), pressure));
+                pressure
+            }
+        }
+    }
+}
+
+/// Cache that bypasses the wrapped cache while the memory is under pressure,
+/// until the pressure subsides. The values are then resolved as if they
+/// weren't cached, rather than the growth of the cache running the process
+/// out of memory.
+pub struct PressureAwareCache<C> {
+    cache: C,
+    pressure: Arc<dyn MemoryPressure>,
+}
+
+impl<C> PressureAwareCache<C> {
+    pub fn new(cache: C, pressure: Arc<dyn MemoryPressure>) -> Self {
+        Self { cache, pressure }
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: Cache> Cache for PressureAwareCache<C>
+where
+    C::Key: Send + Sync,
+    C::Value: Send,
+{
+    type Key = C::Key;
+    type Value = C::Value;
+
+    async fn set<'a>(&'a self, key: Self::Key, value: Self::Value, ttl: NonZeroU64) -> Result<()> {
+        if self.pressure.pressure() >= Pressure::High {
+            return Ok(());
+        }
+
+        self.cache.set(key, value, ttl).await
+    }
+
+    async fn set_with_tags<'a>(
+        &'a self,
+        key: Self::Key,
+        value: Self::Value,
+        ttl: NonZeroU64,
+        tags: Vec<String>,
+    ) -> Result<()> {
+        if self.pressure.pressure() >= Pressure::High {
+            return Ok(());
+        }
+
+        self.cache.set_with_tags(key, value, ttl, tags).await
+    }
+
+    async fn get<'a>(&'a self, key: &'a Self::Key) -> Result<Option<Self::Value>> {
+        if self.pressure.pressure() >= Pressure::Critical {
+            return Ok(None);
+        }
+
+        self.cache.get(key).await
+    }
+
+    async fn invalidate<'a>(&'a self, tag: &'a str) -> Result<usize> {
+        self.cache.invalidate(tag).await
+    }
+
+    async fn evict<'a>(&'a self, key: &'a Self::Key) -> Result<bool> {
+        self.cache.evict(key).await
+    }
+
+    async fn clear<'a>(&'a self) -> Result<usize> {
+        self.cache.clear().await
+    }
+
+    fn hit_rate(&self) -> Option<f64> {
+        self.cache.hit_rate()
+    }
+
+    fn stats(&self) -> Option<Stats> {
+        self.cache.stats()
+    }
+
+    fn entries(&self) -> Option<Vec<Entry<Self::Key, Self::Value>>> {
+        self.cache.entries()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::cache::InMemoryCache;
+
+    fn cache() -> (
+        PressureAwareCache<InMemoryCache<u64, String>>,
+        Arc<Mutex<Pressure>>,
+    ) {
+        let pressure = Arc::new(Mutex::new(Pressure::Normal));
+        let source = pressure.clone();
+        let cache = PressureAwareCache::new(
+            InMemoryCache::default(),
+            Arc::new(move || *source.lock().unwrap()),
+        );
+
+        (cache, pressure)
+    }
+
+    #[tokio::test]
+    async fn test_high_pressure() {
+        let (cache, pressure) = cache();
+        let ttl = NonZeroU64::new(1000).unwrap();
+        cache.set(1, "one".into(), ttl).await.unwrap();
+
+        // the writes are skipped, the stored entries are still served
+        *pressure.lock().unwrap() = Pressure::High;
+        cache.set(2, "two".into(), ttl).await.unwrap();
+        cache
+            .set_with_tags(3, "three".into(), ttl, vec!["tag".into()])
+            .await
+            .unwrap();
+        assert_eq!(cache.get(&1).await.unwrap(), Some("one".into()));
+        assert_eq!(cache.get(&2).await.unwrap(), None);
+        assert_eq!(cache.get(&3).await.unwrap(), None);
+
+        // the writes resume once the pressure subsides
+        *pressure.lock().unwrap() = Pressure::Normal;
+        cache.set(2, "two".into(), ttl).await.unwrap();
+        assert_eq!(cache.get(&2).await.unwrap(), Some("two".into()));
+    }
+
+    #[tokio::test]
+    async fn test_critical_pressure() {
+        let (cache, pressure) = cache();
+        let ttl = NonZeroU64::new(1000).unwrap();
+        cache.set(1, "one".into(), ttl).await.unwrap();
+
+        *pressure.lock().unwrap() = Pressure::Critical;
+        assert_eq!(cache.get(&1).await.unwrap(), None);
+
+        *pressure.lock().unwrap() = Pressure::Normal;
+        assert_eq!(cache.get(&1).await.unwrap(), Some("one".into()));
+    }
+}
@@ -0,0 +1,163 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use async_graphql_value::ConstValue;
+use serde::{Deserialize, Serialize};
+
+use super::error::Result;
+use super::Entry;
+use crate::core::ir::model::IoId;
+use crate::core::EntityCache;
+
+/// The entries of the cache written to disk on a graceful shutdown, so that
+/// the cache is warm again on the next start. It's tied to the salt of the
+/// keys, a snapshot taken with a different configuration is discarded.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    salt: u64,
+    entries: Vec<SnapshotEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotEntry {
+    key: u64,
+    value: ConstValue,
+    /// Milliseconds since the unix epoch.
+    expires_at: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+impl Snapshot {
+    /// Takes the snapshot of the entries that haven't expired, `None` when
+    /// the store can't list its entries.
+    pub fn take(cache: &EntityCache, salt: u64) -> Option<Self> {
+        let entries = cache
+            .entries()?
+            .into_iter()
+            .map(|entry| SnapshotEntry {
+                key: entry.key.as_u64(),
+                value: entry.value,
+                expires_at: entry
+                    .expires_at
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64,
+                tags: entry.tags,
+            })
+            .collect();
+
+        Some(Self { salt, entries })
+    }
+
+    pub fn to_bytes(&self) -> serde_json::Result<Vec<u8>> {
+        serde_json::to_vec(self)
+    }
+
+    /// Reads a snapshot, `None` when it can't be read.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        serde_json::from_slice(bytes).ok()
+    }
+
+    /// Stores the entries that are still valid into the cache and returns
+    /// their number. The entries that expired since the snapshot was taken
+    /// are skipped, and all of them are when it was taken with another salt.
+    pub async fn restore(self, cache: &EntityCache, salt: u64) -> Result<usize> {
+        if self.salt != salt {
+            return Ok(0);
+        }
+
+        let entries = self
+            .entries
+            .into_iter()
+            .map(|entry| Entry {
+                key: IoId::new(entry.key),
+                value: entry.value,
+                expires_at: UNIX_EPOCH + Duration::from_millis(entry.expires_at),
+                tags: entry.tags,
+            })
+            .collect();
+
+        cache.restore(entries).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU64;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::core::cache::InMemoryCache;
+    use crate::core::Cache;
+
+    const SALT: u64 = 42;
+
+    fn in_memory() -> InMemoryCache<IoId, ConstValue> {
+        InMemoryCache::default()
+    }
+
+    fn persist(cache: &EntityCache) -> Vec<u8> {
+        Snapshot::take(cache, SALT).unwrap().to_bytes().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_persist_and_reload() {
+        let cache = in_memory();
+        cache
+            .set_with_tags(
+                IoId::new(1),
+                ConstValue::from("valid"),
+                NonZeroU64::new(60_000).unwrap(),
+                vec!["user:1".to_string()],
+            )
+            .await
+            .unwrap();
+        cache
+            .set(
+                IoId::new(2),
+                ConstValue::from("expired"),
+                NonZeroU64::new(50).unwrap(),
+            )
+            .await
+            .unwrap();
+        let bytes = persist(&cache);
+
+        // the second entry expires while the server is down
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let reloaded = in_memory();
+        let snapshot = Snapshot::from_bytes(&bytes).unwrap();
+        assert_eq!(snapshot.restore(&reloaded, SALT).await.unwrap(), 1);
+        assert_eq!(
+            reloaded.get(&IoId::new(1)).await.unwrap(),
+            Some(ConstValue::from("valid"))
+        );
+        assert_eq!(reloaded.get(&IoId::new(2)).await.unwrap(), None);
+
+        // the tags are kept
+        assert_eq!(reloaded.invalidate("user:1").await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_salt_mismatch() {
+        let cache = in_memory();
+        cache
+            .set(
+                IoId::new(1),
+                ConstValue::from("valid"),
+                NonZeroU64::new(60_000).unwrap(),
+            )
+            .await
+            .unwrap();
+        let bytes = persist(&cache);
+
+        let reloaded = in_memory();
+        let snapshot = Snapshot::from_bytes(&bytes).unwrap();
+        assert_eq!(snapshot.restore(&reloaded, SALT + 1).await.unwrap(), 0);
+        assert_eq!(reloaded.get(&IoId::new(1)).await.unwrap(), None);
+
+        assert!(Snapshot::from_bytes(b"not a snapshot").is_none());
+    }
+}
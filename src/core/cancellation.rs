@@ -0,0 +1,108 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use futures_util::future::{select, Either};
+use tokio::sync::Notify;
+
+/// Signals that the work started for a request is no longer needed, eg.
+/// because the client has disconnected. Cancellation is cooperative, the
+/// running futures observe the token and stop at their next await point.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the token is cancelled.
+    pub async fn cancelled(&self) {
+        let mut notified = pin!(self.inner.notify.notified());
+        notified.as_mut().enable();
+
+        if !self.is_cancelled() {
+            notified.await;
+        }
+    }
+
+    /// Runs the future to completion unless the token is cancelled first, in
+    /// which case the future is dropped and `None` is returned.
+    pub async fn run_until_cancelled<F: Future>(&self, future: F) -> Option<F::Output> {
+        if self.is_cancelled() {
+            return None;
+        }
+
+        match select(pin!(self.cancelled()), pin!(future)).await {
+            Either::Left(_) => None,
+            Either::Right((output, _)) => Some(output),
+        }
+    }
+
+    /// Returns a guard that cancels the token when it's dropped. Holding the
+    /// guard in the future that serves a request ties the token to the
+    /// lifecycle of the request.
+    pub fn drop_guard(&self) -> DropGuard {
+        DropGuard(self.clone())
+    }
+}
+
+/// Cancels the [CancellationToken] when dropped.
+pub struct DropGuard(CancellationToken);
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_until_cancelled() {
+        let token = CancellationToken::default();
+
+        let actual = token.run_until_cancelled(async { 1 }).await;
+        assert_eq!(actual, Some(1));
+
+        let cancel = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            cancel.cancel();
+        });
+
+        let actual = token
+            .run_until_cancelled(tokio::time::sleep(Duration::from_secs(10)))
+            .await;
+        assert_eq!(actual, None);
+        assert!(token.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_drop_guard() {
+        let token = CancellationToken::default();
+
+        drop(token.drop_guard());
+
+        assert!(token.is_cancelled());
+        assert_eq!(token.run_until_cancelled(async { 1 }).await, None);
+    }
+}
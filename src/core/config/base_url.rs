@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+
+use tailcall_valid::{Valid, Validator};
+
+use super::{Compose, Config, ConfigReaderContext, GraphQL, Grpc, Http, Resolver, ResolverSet};
+use crate::core::directive::DirectiveCodec;
+use crate::core::mustache::{Mustache, Segment};
+use crate::core::path::PathString;
+use crate::core::transform::Transform;
+
+impl Config {
+    /// Reads the env variables referenced in the base urls (scheme, host and
+    /// port) of the `@http`, `@grpc` and `@graphQL` resolvers, so that the
+    /// blueprint could resolve them without the runtime. The variables that
+    /// aren't set are left out.
+    pub fn base_url_env(&self, reader_ctx: &ConfigReaderContext) -> BTreeMap<String, String> {
+        let mut env = BTreeMap::new();
+        let _ = self.clone().visit_base_urls(&mut |url| {
+            let mustache = Mustache::parse(url);
+            for (_, parts) in env_segments(&mustache) {
+                if let Some(value) = reader_ctx.path_string(parts) {
+                    env.insert(parts[1..].join("."), value.to_string());
+                }
+            }
+            Valid::succeed(())
+        });
+
+        env
+    }
+
+    fn visit_base_urls(
+        &mut self,
+        f: &mut impl FnMut(&mut String) -> Valid<(), String>,
+    ) -> Valid<(), String> {
+        Valid::from_iter(self.types.iter_mut(), |(type_name, type_of)| {
+            visit_resolvers(&mut type_of.resolvers, f)
+                .and(
+                    Valid::from_iter(type_of.fields.iter_mut(), |(field_name, field)| {
+                        visit_resolvers(&mut field.resolvers, f).trace(field_name)
+                    })
+                    .unit(),
+                )
+                .trace(type_name)
+        })
+        .unit()
+    }
+}
+
+/// Resolves the env variables referenced in the base urls with the values
+/// read along with the config, so that the same config could be used with
+/// different upstream hosts across environments.
+///
+/// Fails with the name of the variable, if any of the referenced env variables
+/// isn't set, instead of producing a malformed url at runtime.
+pub struct BaseUrls<'a>(pub &'a BTreeMap<String, String>);
+
+impl Transform for BaseUrls<'_> {
+    type Value = Config;
+    type Error = String;
+
+    fn transform(&self, mut config: Self::Value) -> Valid<Self::Value, Self::Error> {
+        config
+            .visit_base_urls(&mut |url| render_base_url(url, self.0))
+            .map(|_| config)
+    }
+}
+
+fn visit_resolvers(
+    resolvers: &mut ResolverSet,
+    f: &mut impl FnMut(&mut String) -> Valid<(), String>,
+) -> Valid<(), String> {
+    Valid::from_iter(resolvers.0.iter_mut(), |resolver| match resolver {
+        Resolver::Http(http) => f(&mut http.url)
+            .trace("url")
+            .trace(Http::trace_name().as_str()),
+        Resolver::Grpc(grpc) => f(&mut grpc.url)
+            .trace("url")
+            .and(
+                Valid::from_iter(grpc.routes.iter_mut(), |route| {
+                    f(&mut route.value).trace(&route.key)
+                })
+                .unit()
+                .trace("routes"),
+            )
+            .trace(Grpc::trace_name().as_str()),
+        Resolver::Graphql(graphql) => f(&mut graphql.url)
+            .trace("url")
+            .trace(GraphQL::trace_name().as_str()),
+        Resolver::Compose(compose) => {
+            Valid::from_iter(compose.steps.iter_mut().flatten(), |call| {
+                f(&mut call.http.url).trace("url").trace(&call.name)
+            })
+            .unit()
+            .trace(Compose::trace_name().as_str())
+        }
+        _ => Valid::succeed(()),
+    })
+    .unit()
+}
+
+/// The env expressions of the base url, along with the index of their segment.
+fn env_segments(mustache: &Mustache) -> Vec<(usize, &[String])> {
+    let mut literal = String::new();
+    let mut segments = vec![];
+
+    for (index, segment) in mustache.segments().iter().enumerate() {
+        match segment {
+            Segment::Expression(parts) if is_env(parts) => segments.push((index, parts.as_slice())),
+            Segment::Expression(_) => {}
+            Segment::Literal(text) => literal.push_str(text),
+        }
+
+        // the base url ends with the first `/` that follows the scheme
+        let rest = literal
+            .split_once("://")
+            .map_or(literal.as_str(), |(_, rest)| rest);
+        if rest.contains('/') {
+            break;
+        }
+    }
+
+    segments
+}
+
+fn render_base_url(url: &mut String, env: &BTreeMap<String, String>) -> Valid<(), String> {
+    let mustache = Mustache::parse(url);
+    let mut segments = mustache.segments().clone();
+    let mut missing = Vec::new();
+
+    for (index, parts) in env_segments(&mustache) {
+        let name = parts[1..].join(".");
+        match env.get(&name) {
+            Some(value) => segments[index] = Segment::Literal(value.clone()),
+            None => missing.push(name),
+        }
+    }
+
+    Valid::from_iter(missing, |name| {
+        Valid::<(), String>::fail(format!(
+            "env var '{}' referenced in the base url is not set",
+            name
+        ))
+    })
+    .map(|_| *url = Mustache::from(segments).to_string())
+}
+
+fn is_env(parts: &[String]) -> bool {
+    parts.len() > 1 && parts[0] == "env"
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tailcall_valid::Validator;
+
+    use super::*;
+    use crate::core::tests::TestEnvIO;
+
+    fn render(url: &str) -> Result<String, String> {
+        let env = BTreeMap::from([("HOST".to_owned(), "api.foo.com".to_owned())]);
+
+        let mut url = url.to_string();
+        render_base_url(&mut url, &env)
+            .to_result()
+            .map(|_| url)
+            .map_err(|e| e.as_vec().first().unwrap().message.clone())
+    }
+
+    #[test]
+    fn test_render_base_url() {
+        assert_eq!(
+            render("http://{{.env.HOST}}:8080/users/{{.args.id}}").unwrap(),
+            "http://api.foo.com:8080/users/{{.args.id}}"
+        );
+    }
+
+    #[test]
+    fn test_render_base_url_keeps_env_in_path() {
+        assert_eq!(
+            render("http://{{.env.HOST}}/users/{{.env.ID}}").unwrap(),
+            "http://api.foo.com/users/{{.env.ID}}"
+        );
+    }
+
+    #[test]
+    fn test_render_base_url_missing_env() {
+        assert_eq!(
+            render("http://{{.env.UPSTREAM_HOST}}/users").unwrap_err(),
+            "env var 'UPSTREAM_HOST' referenced in the base url is not set"
+        );
+    }
+
+    #[test]
+    fn test_base_url_env() {
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.env = Arc::new(TestEnvIO::from_iter([
+            ("HOST".to_owned(), "api.foo.com".to_owned()),
+            ("ID".to_owned(), "1".to_owned()),
+        ]));
+        let reader_ctx = ConfigReaderContext::new(&runtime);
+        let config = Config::from_sdl(
+            r#"
+            schema { query: Query }
+            type Query {
+                user: String @http(url: "http://{{.env.HOST}}/users/{{.env.ID}}")
+                post: String @http(url: "http://{{.env.POSTS_HOST}}/posts")
+            }
+            "#,
+        )
+        .to_result()
+        .unwrap();
+
+        // only the variables of the base urls that are set are read
+        assert_eq!(
+            config.base_url_env(&reader_ctx),
+            BTreeMap::from([("HOST".to_owned(), "api.foo.com".to_owned())])
+        );
+    }
+}
@@ -16,8 +16,9 @@ use tailcall_valid::{Valid, Validator};
 use super::directive::Directive;
 use super::from_document::from_document;
 use super::{
-    AddField, Alias, Cache, Call, Discriminate, Expr, GraphQL, Grpc, Http, Link, Modify, Omit,
-    Protected, ResolverSet, Server, Telemetry, Upstream, JS,
+    AddField, Alias, Cache, Call, Compose, Compress, Discriminate, EnumAlias, Expr, Fallback,
+    GraphQL, Grpc, Http, Join, Link, Mask, Modify, Omit, Partial, Protected, ResolverSet, Server,
+    Telemetry, Upstream, Validate, JS,
 };
 use crate::core::config::npo::QueryPath;
 use crate::core::config::source::Source;
@@ -212,6 +213,28 @@ pub struct Field {
     /// Marks field as protected by auth provider
     pub protected: Option<Protected>,
 
+    ///
+    /// Masks the fields of the resolved value based on the auth scopes
+    pub mask: Option<Mask>,
+
+    ///
+    /// Compresses the resolved value for the clients that accept it
+    pub compress: Option<Compress>,
+
+    ///
+    /// Resolves the field with the first resolver that succeeds
+    pub fallback: Option<Fallback>,
+
+    ///
+    /// Joins the elements of the first resolver with the ones of the second
+    /// resolver by a key
+    pub join: Option<Join>,
+
+    ///
+    /// Merges the results of the resolvers that succeed, skipping the failed
+    /// ones
+    pub partial: Option<Partial>,
+
     ///
     /// Used to overwrite the default discrimination strategy
     pub discriminate: Option<Discriminate>,
@@ -282,6 +305,8 @@ pub struct Arg {
     pub doc: Option<String>,
     pub modify: Option<Modify>,
     pub default_value: Option<Value>,
+    /// Checks the value of the argument before the field is resolved.
+    pub validate: Option<Validate>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, MergeRight)]
@@ -295,6 +320,8 @@ pub struct Union {
 pub struct Enum {
     pub variants: BTreeSet<Variant>,
     pub doc: Option<String>,
+    // directive: enumAlias
+    pub enum_alias: Option<EnumAlias>,
 }
 
 /// Definition of GraphQL value
@@ -305,11 +332,12 @@ pub struct Variant {
     pub alias: Option<Alias>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GraphQLOperationType {
     #[default]
     Query,
     Mutation,
+    Subscription,
 }
 
 impl Display for GraphQLOperationType {
@@ -317,6 +345,7 @@ impl Display for GraphQLOperationType {
         f.write_str(match self {
             Self::Query => "query",
             Self::Mutation => "mutation",
+            Self::Subscription => "subscription",
         })
     }
 }
@@ -633,9 +662,10 @@ impl Config {
         let generated_types = &mut generated_types;
 
         let builder = ServiceDocumentBuilder::new();
-        let mut builder = builder
+        let builder = builder
             .add_directive(AddField::directive_definition(generated_types))
             .add_directive(Alias::directive_definition(generated_types))
+            .add_directive(EnumAlias::directive_definition(generated_types))
             .add_directive(Cache::directive_definition(generated_types))
             .add_directive(Call::directive_definition(generated_types))
             .add_directive(Expr::directive_definition(generated_types))
@@ -647,6 +677,17 @@ impl Config {
             .add_directive(Omit::directive_definition(generated_types))
             .add_directive(Protected::directive_definition(generated_types))
             .add_directive(Discriminate::directive_definition(generated_types))
+            .add_directive(Mask::directive_definition(generated_types))
+            .add_directive(Compress::directive_definition(generated_types))
+            .add_directive(Fallback::directive_definition(generated_types))
+            .add_directive(Join::directive_definition(generated_types))
+            .add_directive(Partial::directive_definition(generated_types))
+            .add_directive(Validate::directive_definition(generated_types));
+
+        // the calls of `@compose` refer to the input of `@http` that is added below
+        generated_types.insert("Http".to_string());
+        let mut builder = builder
+            .add_directive(Compose::directive_definition(generated_types))
             .add_input(GraphQL::input_definition())
             .add_input(Grpc::input_definition())
             .add_input(Http::input_definition())
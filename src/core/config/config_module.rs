@@ -147,6 +147,9 @@ pub struct Extensions {
     pub htpasswd: Vec<Content<String>>,
 
     pub jwks: Vec<Content<JwkSet>>,
+
+    /// Contains the env variables referenced in the base urls of the upstreams
+    pub base_url_env: BTreeMap<String, String>,
 }
 
 impl Extensions {
@@ -82,6 +82,7 @@ impl Contravariant for Arg {
             doc: self.doc.merge_right(other.doc),
             modify: self.modify.merge_right(other.modify),
             default_value: self.default_value.or(other.default_value),
+            validate: self.validate.merge_right(other.validate),
         })
     }
 }
@@ -100,6 +101,11 @@ impl Contravariant for Field {
                 cache: self.cache.merge_right(other.cache),
                 default_value: self.default_value.or(other.default_value),
                 protected: self.protected.merge_right(other.protected),
+                mask: self.mask.merge_right(other.mask),
+                compress: self.compress.merge_right(other.compress),
+                fallback: self.fallback.merge_right(other.fallback),
+                join: self.join.merge_right(other.join),
+                partial: self.partial.merge_right(other.partial),
                 discriminate: self.discriminate.merge_right(other.discriminate),
                 resolvers: self.resolvers.merge_right(other.resolvers),
                 directives: self.directives.merge_right(other.directives),
@@ -122,6 +128,11 @@ impl Covariant for Field {
                 cache: self.cache.merge_right(other.cache),
                 default_value: self.default_value.or(other.default_value),
                 protected: self.protected.merge_right(other.protected),
+                mask: self.mask.merge_right(other.mask),
+                compress: self.compress.merge_right(other.compress),
+                fallback: self.fallback.merge_right(other.fallback),
+                join: self.join.merge_right(other.join),
+                partial: self.partial.merge_right(other.partial),
                 discriminate: self.discriminate.merge_right(other.discriminate),
                 resolvers: self.resolvers.merge_right(other.resolvers),
                 directives: self.directives.merge_right(other.directives),
@@ -168,6 +179,7 @@ impl Contravariant for Enum {
         Valid::succeed(Self {
             variants: self.variants,
             doc: self.doc.merge_right(other.doc),
+            enum_alias: self.enum_alias.merge_right(other.enum_alias),
         })
     }
 }
@@ -179,6 +191,7 @@ impl Covariant for Enum {
         Valid::succeed(Self {
             variants: self.variants,
             doc: self.doc.merge_right(other.doc),
+            enum_alias: self.enum_alias.merge_right(other.enum_alias),
         })
     }
 }
@@ -3,6 +3,8 @@ use std::collections::BTreeSet;
 use serde::{Deserialize, Serialize};
 use tailcall_macros::{DirectiveDefinition, MergeRight};
 
+use crate::core::is_default;
+
 /// The @alias directive indicates that aliases of one enum value.
 #[derive(
     Default,
@@ -22,3 +24,56 @@ use tailcall_macros::{DirectiveDefinition, MergeRight};
 pub struct Alias {
     pub options: BTreeSet<String>,
 }
+
+/// The @enumAlias directive sets how the values that are neither the variants
+/// of the enum nor their aliases are resolved.
+#[derive(
+    Default,
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    schemars::JsonSchema,
+    MergeRight,
+    DirectiveDefinition,
+)]
+#[directive_definition(locations = "Enum")]
+#[serde(rename_all = "camelCase")]
+pub struct EnumAlias {
+    /// `Null` resolves the unknown values to null, `Passthrough` resolves them
+    /// as they are and `Default` resolves them to the `default` variant,
+    /// whereas `Error` fails the field. @default `Error`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub on_missing: OnMissing,
+
+    /// The variant that the unknown values resolve to with `onMissing:
+    /// Default`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub default: Option<String>,
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    schemars::JsonSchema,
+    MergeRight,
+)]
+pub enum OnMissing {
+    Null,
+    Passthrough,
+    #[default]
+    Error,
+    Default,
+}
@@ -3,6 +3,8 @@ use std::num::NonZeroU64;
 use serde::{Deserialize, Serialize};
 use tailcall_macros::{DirectiveDefinition, InputDefinition, MergeRight};
 
+use crate::core::is_default;
+
 #[derive(
     Clone,
     Debug,
@@ -24,4 +26,107 @@ pub struct Cache {
     /// Specifies the duration, in milliseconds, of how long the value has to be
     /// stored in the cache.
     pub max_age: NonZeroU64,
+
+    /// Invalidation tags of the cached entries, eg. `user:{{.value.id}}`. Tags
+    /// are rendered against the resolved value when it's stored, so that all
+    /// the entries of an entity could be invalidated at once with `DELETE
+    /// <path>/tags/<tag>` of `@server(cacheAdmin)`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub tags: Vec<String>,
+
+    /// Isolates the cached entries by the rendered value, eg.
+    /// `{{.headers.tenant}}`. Requests that are identical but render a
+    /// different namespace never share an entry, so that the entries don't
+    /// leak across the tenants of a multi-tenant deployment.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub namespace: Option<String>,
+
+    /// Serves the last resolved value in place of an error when the value has
+    /// to be resolved again after it has expired, eg. when the upstream is
+    /// down. The last value is kept for a day, or for `maxAge` if it's longer.
+    /// If there's no last value the error is returned as usual.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub serve_stale_on_error: bool,
+
+    /// Request headers whose values isolate the cached entries, in addition
+    /// to the headers listed in the `Vary` header of the upstream response.
+    /// The responses with `Vary: *` aren't cached.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub vary: Vec<String>,
+
+    /// Arguments of the field that the cached entries are keyed by, eg.
+    /// `["query"]`. The other arguments, like the ids of the requests, are
+    /// left out of the key, so that the calls that differ only in them share
+    /// an entry. When not set, all the arguments are a part of the key.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub key_args: Option<Vec<String>>,
+
+    /// Stores the negative results, like the missing entities, for a different
+    /// duration than `maxAge`, usually a shorter one, so that the entities
+    /// created in the meantime are seen sooner.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub negative: Option<NegativeCache>,
+
+    /// Stores the large entries for a shorter duration than `maxAge`, so that
+    /// they don't occupy the cache for as long as the small ones.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub size: Option<SizeCache>,
+
+    /// Adapts the duration the entries are stored for to how often their
+    /// values change. Each time an entry is refreshed, its duration is doubled
+    /// if the value is the same as the last time and halved if it isn't,
+    /// starting from `maxAge` and within the bounds.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub adaptive: Option<AdaptiveCache>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Eq, schemars::JsonSchema, MergeRight)]
+#[serde(rename_all = "camelCase")]
+/// The results that are cached as negative and how long they are stored for.
+pub struct NegativeCache {
+    /// Specifies the duration, in milliseconds, of how long the negative
+    /// results have to be stored in the cache.
+    pub max_age: NonZeroU64,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Treats the `null` results as negative. @default `true`.
+    pub null: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Treats the empty lists as negative. @default `true`.
+    pub empty: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Status codes of the upstream responses whose results are negative, eg.
+    /// `404`, `4XX` or `500-599`. The failed responses with any of these
+    /// statuses are resolved as `null` instead of failing the field.
+    pub status: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Eq, schemars::JsonSchema, MergeRight)]
+#[serde(rename_all = "camelCase")]
+/// The entries that are cached as large and how long they are stored for.
+pub struct SizeCache {
+    /// Size, in bytes, of the serialized value above which the entry is stored
+    /// for a duration inversely proportional to its size, eg. an entry twice
+    /// as large as the threshold is stored for half of `maxAge`.
+    pub threshold: u64,
+
+    /// Specifies the shortest duration, in milliseconds, the large entries
+    /// are stored for.
+    pub min_max_age: NonZeroU64,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Eq, schemars::JsonSchema, MergeRight)]
+#[serde(rename_all = "camelCase")]
+/// The bounds of the duration the entries are stored for when it's adapted to
+/// how often their values change.
+pub struct AdaptiveCache {
+    /// Specifies the shortest duration, in milliseconds, the entries whose
+    /// values keep changing are stored for.
+    pub min_max_age: NonZeroU64,
+
+    /// Specifies the longest duration, in milliseconds, the entries whose
+    /// values don't change are stored for.
+    pub max_max_age: NonZeroU64,
 }
@@ -7,8 +7,8 @@ use tailcall_macros::DirectiveDefinition;
 use crate::core::is_default;
 
 ///
-/// Provides the ability to refer to a field defined in the root Query or
-/// Mutation.
+/// Provides the ability to refer to a field defined in the root Query,
+/// Mutation or Subscription.
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, schemars::JsonSchema)]
 pub struct Step {
     #[serde(default, skip_serializing_if = "is_default")]
@@ -19,6 +19,11 @@ pub struct Step {
     /// The name of the field on the `Mutation` type that you want to call.
     pub mutation: Option<String>,
 
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The name of the field on the `Subscription` type that you want to call.
+    /// It can only be called from the fields of the `Subscription` type.
+    pub subscription: Option<String>,
+
     /// The arguments that will override the actual arguments of the field.
     #[serde(default, skip_serializing_if = "is_default")]
     pub args: BTreeMap<String, Value>,
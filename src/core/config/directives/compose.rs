@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tailcall_macros::DirectiveDefinition;
+
+use super::Http;
+
+/// A named HTTP call of a composition, its output is available to the calls
+/// of the next steps and to the result as `{{.args.<name>}}`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ComposeCall {
+    /// The name the output of the call is referred to by.
+    pub name: String,
+
+    /// The HTTP call, with the same options as the `@http` directive.
+    pub http: Http,
+}
+
+///
+/// The `@compose` operator resolves a field with a sequence of HTTP calls and
+/// shapes their outputs into the value of the field. For example, the user and
+/// then the posts of the user are fetched with `steps: [[{name: "user", http:
+/// {url: "/users/{{.args.id}}"}}], [{name: "posts", http: {url:
+/// "/users/{{.args.user.id}}/posts"}}]]` and combined with `result: {name:
+/// "{{.args.user.name}}", posts: "{{.args.posts}}"}`.
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    schemars::JsonSchema,
+    DirectiveDefinition,
+)]
+#[directive_definition(locations = "FieldDefinition")]
+#[serde(deny_unknown_fields)]
+pub struct Compose {
+    /// The steps run one after the other, while the calls of a step run
+    /// concurrently. The calls can refer to the arguments of the field and to
+    /// the outputs of the calls of the previous steps.
+    pub steps: Vec<Vec<ComposeCall>>,
+
+    /// The value of the field, a Mustache template that refers to the
+    /// arguments of the field and the outputs of the calls.
+    pub result: Value,
+}
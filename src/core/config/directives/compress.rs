@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use tailcall_macros::{DirectiveDefinition, MergeRight};
+
+use crate::core::is_default;
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    schemars::JsonSchema,
+    DirectiveDefinition,
+    MergeRight,
+)]
+#[directive_definition(locations = "FieldDefinition")]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+///
+/// The `@compress` directive returns the value of a `String` field compressed
+/// with gzip and encoded as base64, prefixed with `gzip:`, to the clients that
+/// send the `x-compressed-fields: gzip` header. It reduces the size of the
+/// responses with large text fields, the other clients get the raw values.
+pub struct Compress {
+    /// The minimum size in bytes of the values that are compressed, the shorter
+    /// ones are returned raw. Defaults to `1024`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub min_size: Option<usize>,
+}
+
+impl Compress {
+    pub fn get_min_size(&self) -> usize {
+        self.min_size.unwrap_or(1024)
+    }
+}
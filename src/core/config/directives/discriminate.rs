@@ -25,14 +25,43 @@ use crate::core::is_default;
 /// be `Buzz`. If `field` is not applied it defaults to "type". The `field` does
 /// not have to be part of the GraphQL Schema, but it is required to be part of
 /// the JSON response. In case this field is missing from the response an
-/// appropriate error message will be displayed.
+/// appropriate error message will be displayed. Alternatively, with `status`
+/// the type is resolved by the HTTP status code of the upstream response, eg.
+/// `@discriminate(status: [{code: "2XX", type: "Success"}, {code: "404", type:
+/// "NotFound"}])`.
 pub struct Discriminate {
     #[serde(default, skip_serializing_if = "is_default")]
     pub field: Option<String>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Maps the status codes of the upstream response to the types. The first
+    /// matching entry is used, and failed responses whose status is mapped
+    /// to a type are resolved as an object of that type. When set, `field`
+    /// is ignored.
+    pub status: Vec<DiscriminateStatus>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, schemars::JsonSchema)]
+/// Maps a range of the HTTP status codes to the type they resolve to.
+pub struct DiscriminateStatus {
+    /// The status code (`404`), the class of the status codes (`4XX`) or an
+    /// inclusive range of the status codes (`500-599`).
+    pub code: String,
+
+    #[serde(rename = "type")]
+    /// The name of the type the status codes resolve to.
+    pub type_of: String,
 }
 
 impl Discriminate {
     pub fn get_field(&self) -> String {
         self.field.clone().unwrap_or("type".to_string())
     }
+
+    pub fn get_status(&self) -> Vec<(String, String)> {
+        self.status
+            .iter()
+            .map(|status| (status.code.clone(), status.type_of.clone()))
+            .collect()
+    }
 }
@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tailcall_macros::{DirectiveDefinition, InputDefinition};
 
+use crate::core::is_default;
+
 #[derive(
     Serialize,
     Deserialize,
@@ -17,7 +19,16 @@ use tailcall_macros::{DirectiveDefinition, InputDefinition};
 #[serde(deny_unknown_fields)]
 /// The `@expr` operators allows you to specify an expression that can evaluate
 /// to a value. The expression can be a static value or built form a Mustache
-/// template. schema.
+/// template. schema. A string that is a single template can use the ternary
+/// and the null-coalescing operators, eg. `{{ .args.admin ? 'all' : 'own' }}`
+/// or `{{ .args.limit ?? 10 }}`, only the branch that is taken is evaluated.
 pub struct Expr {
     pub body: Value,
+
+    #[serde(rename = "runtimeEnv", default, skip_serializing_if = "is_default")]
+    /// Reads the `{{.env.*}}` variables referenced in the body from the
+    /// environment on every request, instead of the values captured when the
+    /// server started. Fields that depend on such values are never treated as
+    /// constants, so their responses aren't cached between requests.
+    pub runtime_env: bool,
 }
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use tailcall_macros::{DirectiveDefinition, MergeRight};
+
+use crate::core::is_default;
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    schemars::JsonSchema,
+    DirectiveDefinition,
+    MergeRight,
+)]
+#[directive_definition(locations = "FieldDefinition")]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+///
+/// The `@fallback` directive resolves a field with the first of its resolvers
+/// that succeeds instead of merging their results, eg. to fail over from a
+/// primary upstream to the secondary ones. The resolvers are tried in the
+/// order they are declared in, and the error of the last one is returned when
+/// all of them fail.
+pub struct Fallback {
+    /// Falls back to the next resolver when a resolver resolves to `null` too,
+    /// not only when it fails. @default `false`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub on_null: bool,
+}
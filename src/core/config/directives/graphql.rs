@@ -37,6 +37,17 @@ pub struct GraphQL {
     /// to the `@graphQL` operator.
     pub batch: bool,
 
+    #[serde(rename = "batchName", default, skip_serializing_if = "is_default")]
+    /// The name of the operation the batches are sent as, so that the upstream
+    /// can tell them apart in its logs and traces.
+    pub batch_name: Option<String>,
+
+    #[serde(rename = "batchStyle", default, skip_serializing_if = "is_default")]
+    /// How the batched requests are composed. `Array` sends them as a JSON
+    /// array of operations, `Alias` merges them into a single operation that
+    /// selects each of the fields under an alias of its own. @default `Array`.
+    pub batch_style: GraphQLBatchStyle,
+
     #[serde(default, skip_serializing_if = "is_default")]
     /// The headers parameter allows you to customize the headers of the GraphQL
     /// request made by the `@graphQL` operator. It is used by specifying a
@@ -55,5 +66,32 @@ pub struct GraphQL {
     /// concurrently, reducing resource load. Caution: May lead to issues
     /// with APIs that expect unique results for identical inputs, such as
     /// nonce-based APIs.
-    pub dedupe: bool,
+    pub dedupe: Option<bool>,
+
+    #[serde(rename = "errorPolicy", default, skip_serializing_if = "is_default")]
+    /// The handling of the `errors` that the upstream responds with.
+    /// `Propagate` adds them to the errors of the response, with the path
+    /// remapped to the field. `Ignore` drops them when the upstream
+    /// responds with the data of the field and fails the field otherwise,
+    /// whereas `Fail` always fails the field. @default `Propagate`.
+    pub error_policy: ErrorPolicy,
+}
+
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, schemars::JsonSchema,
+)]
+pub enum ErrorPolicy {
+    #[default]
+    Propagate,
+    Ignore,
+    Fail,
+}
+
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, schemars::JsonSchema,
+)]
+pub enum GraphQLBatchStyle {
+    #[default]
+    Array,
+    Alias,
 }
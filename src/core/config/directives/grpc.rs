@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tailcall_macros::{DirectiveDefinition, InputDefinition};
 
-use crate::core::config::KeyValue;
+use crate::core::config::{Compact, KeyValue, Priority};
 use crate::core::is_default;
 
 #[derive(
@@ -47,9 +47,18 @@ pub struct Grpc {
     /// automatically set to application/grpc
     pub headers: Vec<KeyValue>,
     /// This refers to the gRPC method you're going to call. For instance
-    /// `GetAllNews`.
+    /// `GetAllNews`. It can be a Mustache template eg. `{{.args.method}}`,
+    /// that is rendered to the full name of the method at request time. The
+    /// method then has to be in `allow`.
     pub method: String,
     #[serde(default, skip_serializing_if = "is_default")]
+    /// The methods that a `method` rendered at request time can call. The
+    /// entries are the full names of the methods eg. `news.NewsService.GetNews`
+    /// or of the services whose methods can all be called eg.
+    /// `news.NewsService`. The responses aren't validated against the type of
+    /// the field, that is usually `JSON`.
+    pub allow: Vec<String>,
+    #[serde(default, skip_serializing_if = "is_default")]
     /// Enables deduplication of IO operations to enhance performance.
     ///
     /// This flag prevents duplicate IO requests from being executed
@@ -57,6 +66,50 @@ pub struct Grpc {
     /// with APIs that expect unique results for identical inputs, such as
     /// nonce-based APIs.
     pub dedupe: Option<bool>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The name of the `google.protobuf.FieldMask` field of the request
+    /// message, that is set to the paths of the fields selected by the query.
+    /// The upstream can then compute only the requested fields.
+    pub field_mask: Option<String>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Decodes the enum values that have no name in the descriptor, eg. the
+    /// values added to the upstream after the schema, as strings instead of
+    /// numbers, so they can be resolved as GraphQL enums. They're decoded to
+    /// `unknownEnumValue` if it's set, to their number otherwise. The known
+    /// values are decoded to their names in any case.
+    pub enum_as_string: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The name that the enum values with no name in the descriptor are
+    /// decoded to with `enumAsString`, eg. `UNKNOWN`.
+    pub unknown_enum_value: Option<String>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The priority of the API calls in the `@upstream(bulkhead)` of the API.
+    /// When the bulkhead is full, the calls waiting for a free slot are
+    /// admitted by their priority, the calls of the same priority in the order
+    /// they arrived. @default `Normal`.
+    pub priority: Priority,
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The upstreams that the calls can be routed to by `routeBy` instead of
+    /// `url`, eg. the regional deployments of the API. The `key` is the name
+    /// of the upstream and the `value` is its url.
+    pub routes: Vec<KeyValue>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Selects the upstream of `routes` that the call is sent to by the
+    /// rendered name, eg. `{{.headers.region}}`. The calls whose name is
+    /// absent or isn't one of the `routes` are sent to `url`. Only the url of
+    /// the call is selected, never its method.
+    pub route_by: Option<String>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Retries the calls to the unavailable upstreams, within a `deadline`
+    /// shared by all the attempts of a call.
+    pub retry: Option<GrpcRetry>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Calls the method on its HTTP mapping of the `google.api.http`
+    /// annotation, eg. through a gRPC-JSON transcoding proxy at `url`. The
+    /// fields of the message bound by the path of the mapping are sent in the
+    /// path, the others in the body or in the query parameters. The method
+    /// can't be batched or streamed.
+    pub transcode: bool,
 
     /// You can use `select` with mustache syntax to re-construct the directives
     /// response to the desired format. This is useful when data are deeply
@@ -67,11 +120,45 @@ pub struct Grpc {
     /// * EXAMPLE 2: if we have a call that returns `{ "foo": "bar", "fizz": {
     ///   "buzz": "eggs", ... }, ... }` we can use { foo: "{{.foo}}", buzz:
     ///   "{{.fizz.buzz}}" }`
+    /// * EXAMPLE 3: the objects can be nested to reshape the response, eg. `{
+    ///   user: { name: "{{.name}}", city: "{{.address.city}}" } }`, the paths
+    ///   that are missing from the response are `null`.
     pub select: Option<Value>,
 
+    /// Keeps only the elements of the list that satisfy the expression, eg.
+    /// `active == true && age >= 18`. It's applied to the response after
+    /// `select`. The fields that are missing from an element are `null` and
+    /// `null` is falsy. An absent list is filtered to an empty list.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub filter: Option<String>,
+
+    /// Drops the `null` elements of the list, and the empty ones as
+    /// configured, keeping the order of the others. It's applied to the
+    /// response after `filter`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub compact: Option<Compact>,
+
     /// Specifies a JavaScript function to be executed after receiving the
     /// response body. This function can modify or transform the response
     /// body before it's sent back to the client.
     #[serde(rename = "onResponseBody", default, skip_serializing_if = "is_default")]
     pub on_response_body: Option<String>,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+/// Retries of the failed calls of a `@grpc`, the calls are retried when the
+/// upstream is unavailable. A retry is skipped when the median latency of the
+/// previous attempts exceeds the remaining time before the deadline, the call
+/// then fails with the last error.
+pub struct GrpcRetry {
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Maximum number of the attempts of a call, the first one included.
+    /// @default `3`.
+    pub max_attempts: Option<usize>,
+
+    /// The time in milliseconds that all the attempts of a call have to
+    /// complete within, the call fails with `DEADLINE_EXCEEDED` once it
+    /// elapses.
+    pub deadline: u64,
+}
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tailcall_macros::{DirectiveDefinition, InputDefinition};
 
-use crate::core::config::{Encoding, KeyValue, URLQuery};
+use crate::core::config::{Encoding, KeyValue, Priority, URLQuery};
 use crate::core::http::Method;
 use crate::core::is_default;
 use crate::core::json::JsonSchema;
@@ -50,21 +50,60 @@ pub struct Http {
     /// `ApplicationJson`.
     pub encoding: Encoding,
 
+    #[serde(rename = "keyCase", default, skip_serializing_if = "is_default")]
+    /// Renames the keys of the JSON request body to the case of the API, and
+    /// the keys of its response back, in the nested objects too.
+    /// `CamelToSnake` sends `userName` as `user_name` and resolves `user_name`
+    /// as `userName`, `SnakeToCamel` does the opposite. The keys already in
+    /// the target case are kept as is, and an acronym is a single word, eg.
+    /// `userID` is sent as `user_id`. The `batchKey` and the `responseSchema`
+    /// use the keys of the API.
+    pub key_case: Option<KeyCase>,
+
     #[serde(rename = "batchKey", default, skip_serializing_if = "is_default")]
     /// The `batchKey` dictates the path Tailcall will follow to group the returned items from the batch request. For more details please refer out [n + 1 guide](https://tailcall.run/docs/guides/n+1#solving-using-batching).
     pub batch_key: Vec<String>,
 
+    #[serde(rename = "batchKeys", default, skip_serializing_if = "is_default")]
+    /// The `batchKeys` group the returned items by a composite key, eg.
+    /// `[["tenant"], ["id"]]`, for the APIs that identify the items by more
+    /// than one field. Each path ends with a field that's sent in the query
+    /// parameter of the same name, and all the paths must lead to the same
+    /// items. Only `GET` requests can be batched by a composite key.
+    pub batch_keys: Vec<Vec<String>>,
+
     #[serde(default, skip_serializing_if = "is_default")]
     /// The `headers` parameter allows you to customize the headers of the HTTP
     /// request made by the `@http` operator. It is used by specifying a
     /// key-value map of header names and their values.
     pub headers: Vec<KeyValue>,
 
+    #[serde(rename = "dynamicHeaders", default, skip_serializing_if = "is_default")]
+    /// The `dynamicHeaders` send the headers of an object resolved from the
+    /// args or the context, eg. `{{.args.headers}}`, whose keys are the names
+    /// of the headers. The `headers` take precedence over them.
+    pub dynamic_headers: Option<DynamicHeaders>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Resolves the field to a Relay connection, ie. `{ edges { node cursor }
+    /// pageInfo { hasNextPage hasPreviousPage endCursor } }`, from a page of
+    /// a cursor-paginated API, eg. `{ items, nextCursor, hasMore }`. The
+    /// `first` and `after` args of the field are sent in the query parameters
+    /// of the limit and the cursor of the page.
+    pub connection: Option<Connection>,
+
     #[serde(default, skip_serializing_if = "is_default")]
     /// Schema of the input of the API call. It is automatically inferred in
     /// most cases.
     pub input: Option<JsonSchema>,
 
+    #[serde(rename = "isList", default, skip_serializing_if = "is_default")]
+    /// Whether the response of the API call is a list, inferred from the type
+    /// of the field when not set. When set, the response is coerced into the
+    /// shape, a single object is wrapped into a list and only the first element
+    /// of a list is kept otherwise.
+    pub is_list: Option<bool>,
+
     #[serde(default, skip_serializing_if = "is_default")]
     /// This refers to the HTTP method of the API call. Commonly used methods
     /// include `GET`, `POST`, `PUT`, `DELETE` etc. @default `GET`.
@@ -83,6 +122,13 @@ pub struct Http {
     /// first parameter referencing a field in the current value using mustache
     /// syntax is automatically selected as the batching parameter.
     pub query: Vec<URLQuery>,
+
+    #[serde(rename = "queryStyle", default, skip_serializing_if = "is_default")]
+    /// The serialization style of the array valued query parameters.
+    /// `RepeatedKey` encodes them as `id=1&id=2`, `CommaSeparated` as
+    /// `id=1,2` and `Brackets` as `id[]=1&id[]=2`. @default `RepeatedKey`.
+    pub query_style: QueryArrayStyle,
+
     #[serde(default, skip_serializing_if = "is_default")]
     /// Enables deduplication of IO operations to enhance performance.
     ///
@@ -92,6 +138,27 @@ pub struct Http {
     /// nonce-based APIs.
     pub dedupe: Option<bool>,
 
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The priority of the API calls in the `@upstream(bulkhead)` of the API.
+    /// When the bulkhead is full, the calls waiting for a free slot are
+    /// admitted by their priority, the calls of the same priority in the order
+    /// they arrived. @default `Normal`.
+    pub priority: Priority,
+
+    #[serde(rename = "skipIf", default, skip_serializing_if = "is_default")]
+    /// Skips the API call when the expression is true, eg. `value.managerId
+    /// == null`, the field then resolves to `null` without a request. The
+    /// expression is the one of `filter`, evaluated against the `value` and
+    /// the `args` of the field before the call is batched, deduplicated or
+    /// cached.
+    pub skip_if: Option<String>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Controls how the redirects of the API are followed. When not set, the
+    /// redirects are followed by the HTTP client, dropping the credentials
+    /// when redirected to a different host.
+    pub redirect: Option<Redirect>,
+
     /// You can use `select` with mustache syntax to re-construct the directives
     /// response to the desired format. This is useful when data are deeply
     /// nested or want to keep specific fields only from the response.
@@ -101,11 +168,215 @@ pub struct Http {
     /// * EXAMPLE 2: if we have a call that returns `{ "foo": "bar", "fizz": {
     ///   "buzz": "eggs", ... }, ... }` we can use { foo: "{{.foo}}", buzz:
     ///   "{{.fizz.buzz}}" }`
+    /// * EXAMPLE 3: the objects can be nested to reshape the response, eg. `{
+    ///   user: { name: "{{.name}}", city: "{{.address.city}}" } }`, the paths
+    ///   that are missing from the response are `null`.
     pub select: Option<Value>,
 
+    /// Keeps only the elements of the list that satisfy the expression, eg.
+    /// `active == true && age >= 18`. It's applied to the response after
+    /// `select`. The fields that are missing from an element are `null` and
+    /// `null` is falsy. An absent list is filtered to an empty list.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub filter: Option<String>,
+
+    /// Drops the `null` elements of the list, and the empty ones as
+    /// configured, keeping the order of the others. It's applied to the
+    /// response after `filter`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub compact: Option<Compact>,
+
     /// Specifies a JavaScript function to be executed after receiving the
     /// response body. This function can modify or transform the response
     /// body before it's sent back to the client.
     #[serde(rename = "onResponseBody", default, skip_serializing_if = "is_default")]
     pub on_response_body: Option<String>,
+
+    #[serde(rename = "responseSchema", default, skip_serializing_if = "is_default")]
+    /// A JSON Schema that the response of the API call is validated against.
+    /// It's used to detect drifts in the upstream contract early, without
+    /// relying on the coercion of the GraphQL types.
+    pub response_schema: Option<Value>,
+
+    #[serde(
+        rename = "onSchemaViolation",
+        default,
+        skip_serializing_if = "is_default"
+    )]
+    /// The action taken when the response doesn't conform to the
+    /// `responseSchema`. `Error` fails the API call, whereas `Warn` only logs
+    /// the violation and increments a metric. @default `Error`.
+    pub on_schema_violation: SchemaViolation,
+
+    #[serde(
+        rename = "successStatuses",
+        default,
+        skip_serializing_if = "is_default"
+    )]
+    /// The statuses of the responses that resolve to `null` instead of
+    /// failing the API call, eg. `["404"]` for the APIs that respond with
+    /// `404` when there's no result. The statuses can be a code (`404`), a
+    /// class of codes (`4XX`) or a range of codes (`400-404`). A `null`
+    /// result of a non-nullable field is still an error.
+    pub success_statuses: Vec<String>,
+
+    #[serde(rename = "streamUpload", default, skip_serializing_if = "is_default")]
+    /// Forwards the body of the client's request to the API as is, using the
+    /// chunked transfer encoding instead of buffering it. Only the requests to
+    /// the `@rest` endpoints that don't map the `body` to a variable have a
+    /// body to forward. The body can be consumed only once, thus the API call
+    /// is never retried, batched, deduplicated or passed to `onRequest`.
+    pub stream_upload: bool,
+
+    #[serde(rename = "rawBody", default, skip_serializing_if = "is_default")]
+    /// Resolves the field to the body of the response as is, base64 encoded,
+    /// instead of decoding it as JSON, eg. for the thumbnails of an API. The
+    /// field must be of the `Bytes` scalar, the API call can't be batched and
+    /// the response must have a binary content type.
+    pub raw_body: bool,
+
+    #[serde(rename = "maxSize", default, skip_serializing_if = "is_default")]
+    /// Maximum size in bytes of the body of a `rawBody` response, the larger
+    /// bodies fail the field instead of being encoded. @default `10485760`.
+    pub max_size: Option<usize>,
+}
+
+impl Http {
+    pub fn is_batched(&self) -> bool {
+        !self.batch_key.is_empty() || !self.batch_keys.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+/// The policy for following the redirects of an API.
+pub struct Redirect {
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The maximum number of redirects followed before failing the API call.
+    /// @default `10`.
+    pub max_redirects: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Follows the redirects to a different host. When disabled, such redirects
+    /// fail the API call. @default `true`.
+    pub cross_host: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Forwards the `Authorization`, `Proxy-Authorization` and `Cookie` headers
+    /// when redirected to a different host. Enable it only if all the hosts
+    /// the API redirects to are trusted. @default `false`.
+    pub preserve_auth: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+/// The shape of the pages of a cursor-paginated API.
+pub struct Connection {
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The path of the items of the page. @default `["items"]`.
+    pub items: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The path of the cursor of the next page. @default `["nextCursor"]`.
+    pub next_cursor: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The path of the flag whether there's a next page. When the page doesn't
+    /// have it, there's a next page as long as there's a cursor of it.
+    /// @default `["hasMore"]`.
+    pub has_more: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The path of the cursor of an item, relative to the item. It's required,
+    /// so that every edge has a cursor to paginate from.
+    pub edge_cursor: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The query parameter that the `first` arg is sent in. @default `limit`.
+    pub limit_param: Option<String>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The query parameter that the `after` arg is sent in. @default
+    /// `cursor`.
+    pub cursor_param: Option<String>,
+}
+
+impl Connection {
+    pub fn get_items(&self) -> Vec<String> {
+        self.items
+            .clone()
+            .unwrap_or_else(|| vec!["items".to_string()])
+    }
+
+    pub fn get_next_cursor(&self) -> Vec<String> {
+        self.next_cursor
+            .clone()
+            .unwrap_or_else(|| vec!["nextCursor".to_string()])
+    }
+
+    pub fn get_has_more(&self) -> Vec<String> {
+        self.has_more
+            .clone()
+            .unwrap_or_else(|| vec!["hasMore".to_string()])
+    }
+
+    pub fn get_limit_param(&self) -> &str {
+        self.limit_param.as_deref().unwrap_or("limit")
+    }
+
+    pub fn get_cursor_param(&self) -> &str {
+        self.cursor_param.as_deref().unwrap_or("cursor")
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+/// The elements of a list that are dropped along with the `null` ones.
+pub struct Compact {
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Drops the objects without any key.
+    pub empty_objects: bool,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Drops the objects that miss any of the keys, or where it is `null`.
+    pub required: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, schemars::JsonSchema)]
+/// The headers of an API call resolved from an object.
+pub struct DynamicHeaders {
+    /// A mustache expression of the object of the headers, eg.
+    /// `{{.args.headers}}`.
+    pub value: String,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The `Host`, `Authorization`, `Proxy-Authorization`, `Cookie` and the
+    /// hop-by-hop headers can't be set by the dynamic headers unless they are
+    /// allowed here.
+    pub allow: Vec<String>,
+}
+
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, schemars::JsonSchema,
+)]
+pub enum SchemaViolation {
+    #[default]
+    Error,
+    Warn,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, schemars::JsonSchema)]
+pub enum KeyCase {
+    CamelToSnake,
+    SnakeToCamel,
+}
+
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, schemars::JsonSchema,
+)]
+pub enum QueryArrayStyle {
+    #[default]
+    RepeatedKey,
+    CommaSeparated,
+    Brackets,
 }
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use tailcall_macros::{DirectiveDefinition, MergeRight};
+
+use crate::core::is_default;
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    schemars::JsonSchema,
+    DirectiveDefinition,
+    MergeRight,
+)]
+#[directive_definition(locations = "FieldDefinition")]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+///
+/// The `@join` directive resolves a list field by joining the elements of its
+/// first resolver with the elements of its second resolver by a key, like a SQL
+/// join, eg. the users of a service with their profiles from another one. The
+/// second resolver is resolved once for every distinct key with the element of
+/// the first one as the `value`, so a batched `@http` loads all of the keys
+/// with a single request.
+pub struct Join {
+    /// Path to the key in the elements of the first resolver, eg. `["id"]`.
+    pub left: Vec<String>,
+
+    /// Path to the key in the elements of the second resolver, eg.
+    /// `["userId"]`.
+    pub right: Vec<String>,
+
+    /// `Inner` drops the elements of the first resolver without a matching
+    /// element, `Left` keeps them without the fields of the second resolver.
+    /// @default `Inner`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub how: JoinKind,
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    schemars::JsonSchema,
+    MergeRight,
+)]
+pub enum JoinKind {
+    #[default]
+    Inner,
+    Left,
+}
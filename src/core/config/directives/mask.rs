@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use tailcall_macros::{DirectiveDefinition, MergeRight};
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    schemars::JsonSchema,
+    DirectiveDefinition,
+    MergeRight,
+)]
+#[directive_definition(locations = "FieldDefinition")]
+#[serde(deny_unknown_fields)]
+///
+/// The `@mask` directive hides sensitive fields of the resolved value from the
+/// callers that aren't granted a scope. For example with `@mask(scope: "admin",
+/// fields: ["ssn"])` applied on a field that returns a `User`, the `ssn` of the
+/// user resolves to `null` unless the `scope` claim of the JWT of the request
+/// contains `admin`. Unlike `@protected`, the request doesn't fail when the
+/// scope is missing.
+pub struct Mask {
+    /// The scope that is required to see the masked fields.
+    pub scope: String,
+
+    /// The names of the fields of the resolved value that are masked. Lists
+    /// are masked item by item.
+    pub fields: Vec<String>,
+}
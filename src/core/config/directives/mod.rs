@@ -2,36 +2,50 @@ mod add_field;
 mod alias;
 mod cache;
 mod call;
+mod compose;
+mod compress;
 mod discriminate;
 mod expr;
+mod fallback;
 mod federation;
 mod graphql;
 mod grpc;
 mod http;
+mod join;
 mod js;
 mod link;
+mod mask;
 mod modify;
 mod omit;
+mod partial;
 mod protected;
 mod server;
 mod telemetry;
 mod upstream;
+mod validate;
 
 pub use add_field::*;
 pub use alias::*;
 pub use cache::*;
 pub use call::*;
+pub use compose::*;
+pub use compress::*;
 pub use discriminate::*;
 pub use expr::*;
+pub use fallback::*;
 pub use federation::*;
 pub use graphql::*;
 pub use grpc::*;
 pub use http::*;
+pub use join::*;
 pub use js::*;
 pub use link::*;
+pub use mask::*;
 pub use modify::*;
 pub use omit::*;
+pub use partial::*;
 pub use protected::*;
 pub use server::*;
 pub use telemetry::*;
 pub use upstream::*;
+pub use validate::*;
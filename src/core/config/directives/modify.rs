@@ -22,4 +22,9 @@ pub struct Modify {
     pub name: Option<String>,
     #[serde(default, skip_serializing_if = "is_default")]
     pub omit: Option<bool>,
+    /// Exposes the field under an additional name, without removing the
+    /// original one. Both the names share the same resolver, which is useful
+    /// while clients migrate from a deprecated name.
+    #[serde(default, rename = "addAlias", skip_serializing_if = "is_default")]
+    pub add_alias: Option<String>,
 }
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use tailcall_macros::{DirectiveDefinition, MergeRight};
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    schemars::JsonSchema,
+    DirectiveDefinition,
+    MergeRight,
+)]
+#[directive_definition(locations = "FieldDefinition")]
+#[serde(deny_unknown_fields)]
+///
+/// The `@partial` directive merges the results of the resolvers of a field that
+/// succeed, skipping the ones that fail instead of failing the field, eg. for
+/// a dashboard that aggregates many independent upstreams. The errors of the
+/// skipped resolvers are added to the response, and the field fails when all
+/// of them fail.
+pub struct Partial {}
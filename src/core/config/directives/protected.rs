@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tailcall_macros::{DirectiveDefinition, MergeRight};
 
 /// Specifies the authentication requirements for accessing a field or type.
@@ -33,4 +34,16 @@ pub struct Protected {
     /// - Include multiple IDs to require authorization from each one.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<Vec<String>>,
+
+    /// The value of the field for the requests without credentials, instead of
+    /// failing them. It's a Mustache template that can refer to the parent
+    /// value and to the arguments, eg. `{{.value.name}}`. The requests with
+    /// invalid credentials still fail.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[merge_right(merge_right_fn = "merge_right_fallback")]
+    pub fallback: Option<Value>,
+}
+
+fn merge_right_fallback(left: Option<Value>, right: Option<Value>) -> Option<Value> {
+    right.or(left)
 }
@@ -51,6 +51,12 @@ pub struct Server {
     /// all responses.
     pub headers: Option<Headers>,
 
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `healthCheck` makes the status endpoint probe the upstreams of the
+    /// `@http` and `@grpc` resolvers and report their status along with the
+    /// status of the server.
+    pub health_check: Option<HealthCheck>,
+
     #[serde(default, skip_serializing_if = "is_default")]
     /// `globalResponseTimeout` sets the maximum query duration before
     /// termination, acting as a safeguard against long-running queries.
@@ -66,11 +72,77 @@ pub struct Server {
     /// and operations. @default `true`.
     pub introspection: Option<bool>,
 
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `cacheAdmin` enables the endpoint to inspect and flush the cache of the
+    /// `@cache` fields at runtime. It's only served to the requests whose JWT
+    /// is granted the `scope`.
+    pub cache_admin: Option<CacheAdmin>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `cacheMaxEntries` is the number of the `@cache` entries above which the
+    /// cache is under memory pressure. The new entries then aren't stored, and
+    /// the values are resolved from the upstreams until some of the stored
+    /// entries expire or are evicted.
+    pub cache_max_entries: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `cacheSnapshot` is the path of the file the `@cache` entries are
+    /// written to on a graceful shutdown, and read back from on the next
+    /// start. The entries that expired in the meantime are discarded, and so
+    /// is the whole snapshot when it was written with another `cacheVersion`.
+    pub cache_snapshot: Option<String>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `cacheVersion` is mixed into the keys of the `@cache` entries, so that
+    /// changing it on a deploy invalidates all the entries cached with the
+    /// previous shape of the schema. When it's not set, the keys are salted
+    /// with a hash of the configuration instead.
+    pub cache_version: Option<String>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `debugStats` reports in the `debug` extension of the responses whether
+    /// every field was a cache hit, deduped or batched, and how many upstream
+    /// calls it made. It's meant for debugging the configuration and must not
+    /// be enabled in production. @default `false`.
+    pub debug_stats: Option<bool>,
+
     /// `enableFederation` enables functionality to Tailcall server to act
     /// as a federation subgraph.
     #[serde(default, skip_serializing_if = "is_default")]
     pub enable_federation: Option<bool>,
 
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `maxPathDepth` limits the number of keys of the paths that navigate into
+    /// the resolved values. Evaluating a longer path fails with a
+    /// `PATH_TOO_DEEP` error. @default unlimited.
+    pub max_path_depth: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `maxQueryDepth` limits the nesting of the selections of the queries.
+    /// The deeper queries are rejected before any of their fields is resolved.
+    /// The introspection fields aren't counted. @default unlimited.
+    pub max_query_depth: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `maxQueryFields` limits the total number of the fields selected by the
+    /// queries, including the nested ones. The queries selecting more fields
+    /// are rejected before any of their fields is resolved. The introspection
+    /// fields aren't counted. @default unlimited.
+    pub max_query_fields: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `maxUpstreamCalls` limits the number of upstream calls a single request
+    /// can make, including the calls of batched and fanned out resolvers. Once
+    /// the limit is reached, the remaining calls fail with a `BUDGET_EXCEEDED`
+    /// error instead of being sent to the upstream. @default unlimited.
+    pub max_upstream_calls: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `msgpack` serves the GraphQL responses as MessagePack to the clients
+    /// that send `Accept: application/msgpack`, the others get JSON.
+    /// @default `false`.
+    pub msgpack: Option<bool>,
+
     #[serde(default, skip_serializing_if = "is_default")]
     /// `pipelineFlush` allows to control flushing behavior of the server
     /// pipeline.
@@ -86,6 +158,14 @@ pub struct Server {
     /// @default `false`.
     pub query_validation: Option<bool>,
 
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `requestId` makes sure that every request has an id that can be used to
+    /// correlate logs across services. A client supplied id is preserved,
+    /// otherwise a new UUID is generated. The id is forwarded to all the
+    /// upstream calls and returned in the response headers, and the templates
+    /// read it as `{{.request.id}}`.
+    pub request_id: Option<RequestId>,
+
     #[serde(default, skip_serializing_if = "is_default")]
     /// `responseValidation` Tailcall automatically validates responses from
     /// upstream services using inferred schema. @default `false`.
@@ -158,15 +238,94 @@ impl Routes {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, MergeRight, JsonSchema, Getters)]
+pub struct RequestId {
+    /// `header` is the name of the header that carries the request id.
+    /// @default `x-request-id`.
+    #[serde(default = "default_request_id_header")]
+    header: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, MergeRight, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheck {
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `upstreams` are the hosts that are probed, eg. `api.foo.com`. When
+    /// empty, all the upstreams are probed.
+    pub upstreams: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `timeout` is the maximum time in milliseconds to wait for an upstream
+    /// to respond to the probe. @default `1000`.
+    pub timeout: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `interval` is the time in seconds for which the results of the probes
+    /// are reused, so that frequent status checks don't flood the upstreams.
+    /// @default `10`.
+    pub interval: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, MergeRight, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheAdmin {
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `path` of the endpoint. `GET` reports the number of the entries and the
+    /// hit rate, `GET <path>/keys` lists the keys, `DELETE` flushes the cache,
+    /// `DELETE <path>/<id>` evicts the entries of an IO id and `DELETE
+    /// <path>/tags/<tag>` evicts the entries of an invalidation tag. @default
+    /// `/admin/cache`.
+    pub path: Option<String>,
+
+    /// `scope` that the JWT of the request has to be granted to use the
+    /// endpoint.
+    pub scope: String,
+}
+
+fn default_request_id_header() -> String {
+    "x-request-id".into()
+}
+
+impl Default for RequestId {
+    fn default() -> Self {
+        Self { header: default_request_id_header() }
+    }
+}
+
+impl RequestId {
+    pub fn with_header<T: Into<String>>(self, header: T) -> Self {
+        Self { header: header.into() }
+    }
+}
+
 fn merge_right_vars(mut left: Vec<KeyValue>, right: Vec<KeyValue>) -> Vec<KeyValue> {
     left = merge_key_value_vecs(&left, &right);
     left
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, schemars::JsonSchema, MergeRight)]
+#[derive(
+    Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, schemars::JsonSchema, MergeRight,
+)]
 #[serde(rename_all = "camelCase")]
 pub struct ScriptOptions {
     pub timeout: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `maxQueue` is the number of calls that wait for a free worker once all
+    /// of them are busy. The calls beyond it fail right away instead of
+    /// waiting. @default unbounded.
+    pub max_queue: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `maxRequests` is the number of calls a worker handles before its JS
+    /// runtime is recreated, which releases the memory the script leaked.
+    /// @default never recreated.
+    pub max_requests: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// `poolSize` is the number of workers that run the script, each one on
+    /// its own thread with its own JS runtime. @default `1`.
+    pub pool_size: Option<usize>,
 }
 
 #[derive(
@@ -221,6 +380,12 @@ impl Server {
     pub fn enable_showcase(&self) -> bool {
         self.showcase.unwrap_or(false)
     }
+    pub fn enable_debug_stats(&self) -> bool {
+        self.debug_stats.unwrap_or(false)
+    }
+    pub fn enable_msgpack(&self) -> bool {
+        self.msgpack.unwrap_or(false)
+    }
 
     pub fn get_hostname(&self) -> String {
         self.hostname.clone().unwrap_or("127.0.0.1".to_string())
@@ -268,6 +433,28 @@ impl Server {
     pub fn get_enable_federation(&self) -> bool {
         self.enable_federation.unwrap_or(false)
     }
+
+    pub fn get_max_path_depth(&self) -> Option<usize> {
+        self.max_path_depth
+    }
+
+    pub fn get_max_query_depth(&self) -> Option<usize> {
+        self.max_query_depth
+    }
+
+    pub fn get_max_query_fields(&self) -> Option<usize> {
+        self.max_query_fields
+    }
+
+    pub fn get_max_upstream_calls(&self) -> Option<usize> {
+        self.max_upstream_calls
+    }
+
+    pub fn get_request_id_header(&self) -> Option<String> {
+        self.request_id
+            .as_ref()
+            .map(|request_id| request_id.header().to_owned())
+    }
 }
 
 #[cfg(test)]
@@ -282,46 +469,52 @@ mod tests {
 
     #[test]
     fn script_options_merge_both() {
-        let a = server_with_script_options(ScriptOptions { timeout: Some(100) });
-        let b = server_with_script_options(ScriptOptions { timeout: Some(200) });
+        let a =
+            server_with_script_options(ScriptOptions { timeout: Some(100), ..Default::default() });
+        let b =
+            server_with_script_options(ScriptOptions { timeout: Some(200), ..Default::default() });
         let merged = a.merge_right(b);
-        let expected = ScriptOptions { timeout: Some(200) };
+        let expected = ScriptOptions { timeout: Some(200), ..Default::default() };
         assert_eq!(merged.script, Some(expected));
     }
 
     #[test]
     fn script_options_merge_first() {
-        let a = server_with_script_options(ScriptOptions { timeout: Some(100) });
-        let b = server_with_script_options(ScriptOptions { timeout: None });
+        let a =
+            server_with_script_options(ScriptOptions { timeout: Some(100), ..Default::default() });
+        let b = server_with_script_options(ScriptOptions { timeout: None, ..Default::default() });
         let merged = a.merge_right(b);
-        let expected = ScriptOptions { timeout: Some(100) };
+        let expected = ScriptOptions { timeout: Some(100), ..Default::default() };
         assert_eq!(merged.script, Some(expected));
     }
 
     #[test]
     fn script_options_merge_second() {
-        let a = server_with_script_options(ScriptOptions { timeout: None });
-        let b = server_with_script_options(ScriptOptions { timeout: Some(100) });
+        let a = server_with_script_options(ScriptOptions { timeout: None, ..Default::default() });
+        let b =
+            server_with_script_options(ScriptOptions { timeout: Some(100), ..Default::default() });
         let merged = a.merge_right(b);
-        let expected = ScriptOptions { timeout: Some(100) };
+        let expected = ScriptOptions { timeout: Some(100), ..Default::default() };
         assert_eq!(merged.script, Some(expected));
     }
 
     #[test]
     fn script_options_merge_second_default() {
-        let a = server_with_script_options(ScriptOptions { timeout: Some(100) });
+        let a =
+            server_with_script_options(ScriptOptions { timeout: Some(100), ..Default::default() });
         let b = Server::default();
         let merged = a.merge_right(b);
-        let expected = ScriptOptions { timeout: Some(100) };
+        let expected = ScriptOptions { timeout: Some(100), ..Default::default() };
         assert_eq!(merged.script, Some(expected));
     }
 
     #[test]
     fn script_options_merge_first_default() {
         let a = Server::default();
-        let b = server_with_script_options(ScriptOptions { timeout: Some(100) });
+        let b =
+            server_with_script_options(ScriptOptions { timeout: Some(100), ..Default::default() });
         let merged = a.merge_right(b);
-        let expected = ScriptOptions { timeout: Some(100) };
+        let expected = ScriptOptions { timeout: Some(100), ..Default::default() };
         assert_eq!(merged.script, Some(expected));
     }
 
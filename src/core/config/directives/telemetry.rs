@@ -58,6 +58,36 @@ pub struct PrometheusExporter {
     pub format: PrometheusFormat,
 }
 
+/// Level of the structured field logs
+#[derive(
+    Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, schemars::JsonSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+/// Local structured logs that open a span for every resolved field with the
+/// path of the field, the name of the operation and the request id, and log
+/// the outcome of the field's IO within that span.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, schemars::JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldLogs {
+    /// Level of the spans and the logs. @default `info`.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub level: LogLevel,
+    /// Adds the arguments of the field to the span. Be careful about **leaking
+    /// sensitive information** when the arguments could contain sensitive
+    /// data.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub include_args: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, schemars::JsonSchema, MergeRight)]
 #[serde(rename_all = "camelCase")]
 pub enum TelemetryExporter {
@@ -94,6 +124,9 @@ pub struct Telemetry {
     /// contain sensitive data
     #[serde(default, skip_serializing_if = "is_default")]
     pub request_headers: Vec<String>,
+    /// Enables the structured logs with a span per resolved field
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub field_logs: Option<FieldLogs>,
 }
 
 impl Telemetry {
@@ -105,6 +138,7 @@ impl Telemetry {
             (Some(left), Some(right)) => Some(left.clone().merge_right(right.clone())),
         };
         self.request_headers.extend(other.request_headers);
+        self.field_logs = other.field_logs.or(self.field_logs);
 
         self
     }
@@ -147,6 +181,7 @@ mod tests {
                 headers: vec![KeyValue { key: "header_a".to_owned(), value: "a".to_owned() }],
             })),
             request_headers: vec!["Api-Key-A".to_owned()],
+            ..Default::default()
         };
         let exporter_otlp_2 = Telemetry {
             export: Some(TelemetryExporter::Otlp(OtlpExporter {
@@ -154,6 +189,7 @@ mod tests {
                 headers: vec![KeyValue { key: "header_b".to_owned(), value: "b".to_owned() }],
             })),
             request_headers: vec!["Api-Key-B".to_owned()],
+            ..Default::default()
         };
         let exporter_prometheus_1 = Telemetry {
             export: Some(TelemetryExporter::Prometheus(PrometheusExporter {
@@ -202,7 +238,8 @@ mod tests {
                     url: "test-url-2".to_owned(),
                     headers: vec![KeyValue { key: "header_b".to_owned(), value: "b".to_owned() }]
                 })),
-                request_headers: vec!["Api-Key-A".to_string(), "Api-Key-B".to_string(),]
+                request_headers: vec!["Api-Key-A".to_string(), "Api-Key-B".to_string(),],
+                ..Default::default()
             }
         );
 
@@ -213,4 +250,19 @@ mod tests {
             exporter_prometheus_2
         );
     }
+
+    #[test]
+    fn merge_right_field_logs() {
+        let field_logs = FieldLogs { level: LogLevel::Debug, include_args: true };
+        let telemetry = Telemetry { field_logs: Some(field_logs.clone()), ..Default::default() };
+
+        assert_eq!(
+            telemetry.clone().merge_right(Telemetry::default()),
+            telemetry
+        );
+        assert_eq!(
+            Telemetry::default().merge_right(telemetry.clone()),
+            telemetry
+        );
+    }
 }
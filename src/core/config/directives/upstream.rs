@@ -34,6 +34,198 @@ pub struct Proxy {
     pub url: String,
 }
 
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Clone,
+    Debug,
+    Default,
+    Setters,
+    schemars::JsonSchema,
+    MergeRight,
+)]
+#[serde(rename_all = "camelCase", default)]
+/// Derives the timeout of the requests to an upstream from the latencies it
+/// has recently responded with. The timeout is the `percentile` of the
+/// recent latencies multiplied by the `factor`, and it never exceeds the
+/// static `timeout`.
+pub struct AdaptiveTimeout {
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Percentile of the recent latencies that the timeout is derived from,
+    /// between `1` and `100`. Defaults to `99`.
+    pub percentile: Option<u8>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Multiplier of the percentile. Defaults to `2`.
+    pub factor: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The time in milliseconds after which the weight of a latency is
+    /// halved, so that the timeout follows the recent latencies of the
+    /// upstream. Defaults to `60000`.
+    pub half_life: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Number of recent latencies required to adapt the timeout, until then
+    /// the static `timeout` is used. Defaults to `20`.
+    pub min_samples: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The minimum timeout in milliseconds, so that the fast upstreams don't
+    /// fail on small hiccups. Defaults to `100`.
+    pub min: Option<u64>,
+}
+
+#[derive(
+    Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug, schemars::JsonSchema, MergeRight,
+)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+/// When the `@http` requests are sent over HTTP/3 (QUIC), see
+/// `@upstream(http3)`.
+pub enum Http3 {
+    /// Only to the upstreams that advertise HTTP/3 with the `Alt-Svc` header of
+    /// their responses.
+    AltSvc,
+    /// To all the upstreams, without checking if they support it.
+    Force,
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Clone,
+    Debug,
+    Default,
+    Setters,
+    schemars::JsonSchema,
+    MergeRight,
+)]
+#[serde(rename_all = "camelCase", default)]
+/// Isolates the upstreams from each other, so that a saturated upstream can't
+/// starve the requests to the others. Each upstream, identified by the origin
+/// of its requests eg. `http://foo.com:8080`, has a bulkhead of its own that
+/// admits up to `maxInFlight` concurrent requests, the others wait for a free
+/// slot for up to `maxWait` and fail with `SERVICE_OVERLOADED` once it
+/// elapses.
+pub struct Bulkhead {
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Maximum number of the concurrent requests to each upstream. Defaults to
+    /// `100`.
+    pub max_in_flight: Option<usize>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The time in milliseconds that a request waits for a free slot in the
+    /// bulkhead of its upstream before it fails. Defaults to `1000`.
+    pub max_wait: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Maximum number of the requests waiting for a free slot in the bulkhead
+    /// of each upstream. The excess requests fail at once with
+    /// `SERVICE_OVERLOADED` instead of waiting. Unbounded if not set.
+    pub max_queue: Option<usize>,
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Clone,
+    Debug,
+    Default,
+    Setters,
+    schemars::JsonSchema,
+    MergeRight,
+)]
+#[serde(rename_all = "camelCase", default)]
+/// Shadows a sample of the `@http` requests to a canary upstream, eg. the new
+/// deployment of a backend that is migrated to, and compares its results with
+/// the ones of the primary upstream. The differences are logged along with
+/// their paths and counted by the `canary.mismatch.count` metric, the clients
+/// are always served the primary results.
+pub struct Canary {
+    #[serde(rename = "baseURL")]
+    /// The url of the canary upstream, its scheme, host and port replace the
+    /// ones of the shadowed requests.
+    pub base_url: String,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Percentage of the requests that are shadowed. Defaults to `1`.
+    pub sample: Option<u8>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The time in milliseconds that the canary upstream is waited for, its
+    /// slower results are dropped without being compared. Defaults to `1000`.
+    pub timeout: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Paths of the lists that are compared ignoring the order of their
+    /// elements, eg. `user.tags`. The paths leave out the indexes of the lists.
+    pub unordered: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Maximum number of the values that are compared in a result, the
+    /// comparison of the larger results stops once it's reached. Defaults to
+    /// `10000`.
+    pub max_nodes: Option<usize>,
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    schemars::JsonSchema,
+)]
+/// The priority of the API calls waiting for a free slot in a bulkhead, the
+/// `High` ones are admitted first.
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+#[derive(
+    Serialize, Deserialize, PartialEq, Eq, Clone, Debug, Setters, schemars::JsonSchema, MergeRight,
+)]
+#[serde(rename_all = "camelCase")]
+/// Fetches the OAuth2 access tokens of an upstream with the client
+/// credentials grant. The tokens are cached until shortly before they expire
+/// and are sent to the upstream in the `Authorization` header of the requests.
+pub struct TokenProvider {
+    /// Origin of the upstream the tokens are sent to, eg.
+    /// `https://api.foo.com`.
+    pub origin: String,
+
+    /// Url of the token endpoint of the authorization server.
+    pub token_url: String,
+
+    pub client_id: String,
+
+    pub client_secret: String,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Space separated scopes that are requested for the tokens.
+    pub scope: Option<String>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Time in seconds before the expiry of a token it's refreshed at, while
+    /// the requests still use the current token. @default `60`.
+    pub refresh_before: Option<u64>,
+}
+
 #[derive(
     Serialize,
     Deserialize,
@@ -60,6 +252,12 @@ pub struct Upstream {
     /// interception handler.
     pub on_request: Option<String>,
 
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Adapts the timeout of the requests to each upstream to its recent
+    /// latencies, see `AdaptiveTimeout`. If not set, the static `timeout` is
+    /// used for all the requests.
+    pub adaptive_timeout: Option<AdaptiveTimeout>,
+
     #[serde(default, skip_serializing_if = "is_default")]
     /// `allowedHeaders` defines the HTTP headers allowed to be forwarded to
     /// upstream services. If not set, no headers are forwarded, enhancing
@@ -73,15 +271,72 @@ pub struct Upstream {
     /// the batch).
     pub batch: Option<Batch>,
 
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Limits the concurrent requests to each upstream separately, see
+    /// `Bulkhead`. If not set, the requests to the upstreams aren't limited.
+    pub bulkhead: Option<Bulkhead>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Shadows a sample of the `@http` requests to a canary upstream to compare
+    /// the results, see `Canary`. If not set, no request is shadowed.
+    pub canary: Option<Canary>,
+
     #[serde(default, skip_serializing_if = "is_default")]
     /// The time in seconds that the connection will wait for a response before
     /// timing out.
     pub connect_timeout: Option<u64>,
 
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Accepts invalid certificates of the upstreams, eg. self-signed ones, for
+    /// local development. It's only honored when tailcall is started with
+    /// `--insecure`, so that it can't be enabled in production by accident.
+    pub danger_accept_invalid_certs: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Deduplicates the IOs that read from the upstreams, ie. the `@http`
+    /// requests of the safe methods eg. `GET`, and the `@grpc` and `@graphQL`
+    /// queries, unless their own `dedupe` is set. The requests that could
+    /// have side effects, eg. `POST` or the mutations, are never deduplicated
+    /// by default. @default `false`.
+    pub dedupe: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The time in seconds for which the resolved addresses of an upstream
+    /// host are reused by new connections. Idle connections are closed once
+    /// they outlive the TTL, and the busy ones are replaced once the
+    /// addresses change, so changes to the DNS records are picked up
+    /// periodically. Setting it to `0` resolves the host for every request
+    /// without reusing connections, which is useful for canary deployments.
+    /// If not set, the system resolver is used for every new connection.
+    pub dns_ttl: Option<u64>,
+
     #[serde(default, skip_serializing_if = "is_default")]
     /// Providing httpCache size enables Tailcall's HTTP caching, adhering to the [HTTP Caching RFC](https://tools.ietf.org/html/rfc7234), to enhance performance by minimizing redundant data fetches. Defaults to `0` if unspecified.
     pub http_cache: Option<u64>,
 
+    #[serde(
+        rename = "http2AdaptiveWindow",
+        default,
+        skip_serializing_if = "is_default"
+    )]
+    /// Adapts the flow-control windows of the HTTP/2 connections to the
+    /// measured bandwidth and latency of the upstream, so that the responses
+    /// of the requests multiplexed over a connection aren't throttled by the
+    /// default window. @default `false`.
+    pub http2_adaptive_window: Option<bool>,
+
+    #[serde(
+        rename = "http2MaxConcurrentStreams",
+        default,
+        skip_serializing_if = "is_default"
+    )]
+    /// The most requests in flight at once over the HTTP/2 connection to an
+    /// upstream, the others wait for one of them to complete instead of
+    /// opening another connection. It applies to the gRPC requests, and to
+    /// all of them with `http2Only`. If not set, the limit advertised by the
+    /// upstream is used.
+    pub http2_max_concurrent_streams: Option<usize>,
+
     #[setters(strip_option)]
     #[serde(rename = "http2Only", default, skip_serializing_if = "is_default")]
     /// The `http2Only` setting allows you to specify whether the client should
@@ -90,6 +345,15 @@ pub struct Upstream {
     /// the server, but is automatically set to true for GRPC.
     pub http2_only: Option<bool>,
 
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Sends the `@http` requests over HTTP/3 (QUIC), either to the upstreams
+    /// that advertise it with `Alt-Svc` (`ALT_SVC`) or to all of them
+    /// (`FORCE`). The requests fall back to HTTP/2 when the QUIC connection
+    /// can't be established or breaks, eg. when the network changes. It
+    /// requires tailcall to be built with the `http3` feature and
+    /// `--cfg reqwest_unstable`, it's ignored otherwise.
+    pub http3: Option<Http3>,
+
     #[serde(default, skip_serializing_if = "is_default")]
     /// The time in seconds between each keep-alive message sent to maintain the
     /// connection.
@@ -105,6 +369,12 @@ pub struct Upstream {
     /// sent while the connection is idle.
     pub keep_alive_while_idle: Option<bool>,
 
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// The maximum size in bytes that the compressed upstream responses are
+    /// decoded to, the responses that exceed it fail. @default `104857600`
+    /// (100 MiB).
+    pub max_decoded_size: Option<usize>,
+
     #[serde(default, skip_serializing_if = "is_default")]
     /// The maximum number of idle connections that will be maintained per host.
     pub pool_max_idle_per_host: Option<usize>,
@@ -131,6 +401,11 @@ pub struct Upstream {
     /// response.
     pub timeout: Option<u64>,
 
+    #[serde(default, skip_serializing_if = "is_default")]
+    /// Fetches the OAuth2 access tokens of the upstreams, see
+    /// `TokenProvider`.
+    pub token_providers: Vec<TokenProvider>,
+
     #[serde(default, skip_serializing_if = "is_default")]
     /// The User-Agent header value to be used in HTTP requests. @default
     /// `Tailcall/1.0`
@@ -168,6 +443,12 @@ impl Upstream {
     pub fn get_connect_timeout(&self) -> u64 {
         self.connect_timeout.unwrap_or(60)
     }
+    pub fn get_danger_accept_invalid_certs(&self) -> bool {
+        self.danger_accept_invalid_certs.unwrap_or(false)
+    }
+    pub fn get_dedupe(&self) -> bool {
+        self.dedupe.unwrap_or(false)
+    }
     pub fn get_timeout(&self) -> u64 {
         self.timeout.unwrap_or(60)
     }
@@ -179,9 +460,16 @@ impl Upstream {
             .clone()
             .unwrap_or("Tailcall/1.0".to_string())
     }
+    pub fn get_dns_ttl(&self) -> Option<u64> {
+        self.dns_ttl
+    }
     pub fn get_http_cache_size(&self) -> u64 {
         self.http_cache.unwrap_or(0)
     }
+    pub fn get_max_decoded_size(&self) -> usize {
+        self.max_decoded_size
+            .unwrap_or(crate::core::http::content_encoding::DEFAULT_MAX_DECODED_SIZE)
+    }
     pub fn get_allowed_headers(&self) -> BTreeSet<String> {
         self.allowed_headers.clone().unwrap_or_default()
     }
@@ -198,6 +486,10 @@ impl Upstream {
         self.http2_only.unwrap_or(false)
     }
 
+    pub fn get_http_2_adaptive_window(&self) -> bool {
+        self.http2_adaptive_window.unwrap_or(false)
+    }
+
     pub fn get_on_request(&self) -> Option<String> {
         self.on_request.clone()
     }
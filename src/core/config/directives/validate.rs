@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use tailcall_macros::{DirectiveDefinition, MergeRight};
+
+use crate::core::is_default;
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    schemars::JsonSchema,
+    DirectiveDefinition,
+    MergeRight,
+)]
+#[directive_definition(locations = "ArgumentDefinition")]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+///
+/// The `@validate` directive checks the value of an argument before the field
+/// is resolved. A field whose arguments violate the constraints fails with
+/// the violations and the `BAD_USER_INPUT` code, without calling its
+/// upstream. An argument that isn't set, or is `null`, satisfies all the
+/// constraints but the ones of the arguments that require it.
+pub struct Validate {
+    /// The smallest value of a number.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub min: Option<i64>,
+    /// The largest value of a number.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub max: Option<i64>,
+    /// The fewest characters of a string, or elements of a list.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub min_length: Option<usize>,
+    /// The most characters of a string, or elements of a list.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub max_length: Option<usize>,
+    /// A regular expression that a string has to match, anchor it with `^`
+    /// and `$` to match the whole string.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub pattern: Option<String>,
+    /// The other arguments of the field that have to be set along with the
+    /// argument.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub requires: Vec<String>,
+}
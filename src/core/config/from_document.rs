@@ -12,10 +12,12 @@ use indexmap::IndexMap;
 use tailcall_valid::{Valid, ValidationError, Validator};
 
 use super::directive::{to_directive, Directive};
-use super::{Alias, Discriminate, Resolver, RuntimeConfig, Telemetry, FEDERATION_DIRECTIVES};
+use super::{
+    Alias, Discriminate, EnumAlias, Resolver, RuntimeConfig, Telemetry, FEDERATION_DIRECTIVES,
+};
 use crate::core::config::{
-    self, Cache, Config, Enum, Link, Modify, Omit, Protected, RootSchema, Server, Union, Upstream,
-    Variant,
+    self, Cache, Compress, Config, Enum, Fallback, Join, Link, Mask, Modify, Omit, Partial,
+    Protected, RootSchema, Server, Union, Upstream, Validate, Variant,
 };
 use crate::core::directive::DirectiveCodec;
 
@@ -215,6 +217,7 @@ fn to_enum_types(
                     .description
                     .to_owned()
                     .map(|pos| pos.node),
+                &type_definition.node.directives,
             ),
             _ => return Valid::succeed(None),
         };
@@ -329,7 +332,14 @@ where
         .fuse(Cache::from_directives(directives.iter()))
         .fuse(Omit::from_directives(directives.iter()))
         .fuse(Modify::from_directives(directives.iter()))
-        .fuse(Protected::from_directives(directives.iter()))
+        .fuse(
+            Protected::from_directives(directives.iter())
+                .zip(Mask::from_directives(directives.iter()))
+                .zip(Compress::from_directives(directives.iter()))
+                .zip(Fallback::from_directives(directives.iter()))
+                .zip(Join::from_directives(directives.iter()))
+                .zip(Partial::from_directives(directives.iter())),
+        )
         .fuse(Discriminate::from_directives(directives.iter()))
         .fuse(default_value)
         .fuse(to_federation_directives(directives))
@@ -339,7 +349,7 @@ where
                 cache,
                 omit,
                 modify,
-                protected,
+                (((((protected, mask), compress), fallback), join), partial),
                 discriminate,
                 default_value,
                 directives,
@@ -351,6 +361,11 @@ where
                 omit,
                 cache,
                 protected,
+                mask,
+                compress,
+                fallback,
+                join,
+                partial,
                 discriminate,
                 default_value,
                 resolvers,
@@ -387,7 +402,17 @@ fn to_arg(input_value_definition: &InputValueDefinition) -> config::Arg {
     } else {
         None
     };
-    config::Arg { type_of: type_of.into(), doc, modify, default_value }
+    let validate = Validate::from_directives(input_value_definition.directives.iter())
+        .to_result()
+        .ok()
+        .flatten();
+    config::Arg {
+        type_of: type_of.into(),
+        doc,
+        modify,
+        default_value,
+        validate,
+    }
 }
 
 fn to_union(union_type: UnionType, doc: &Option<String>) -> Valid<Union, String> {
@@ -400,7 +425,11 @@ fn to_union(union_type: UnionType, doc: &Option<String>) -> Valid<Union, String>
     Valid::succeed(Union { types, doc: doc.clone() })
 }
 
-fn to_enum(enum_type: EnumType, doc: Option<String>) -> Valid<Enum, String> {
+fn to_enum(
+    enum_type: EnumType,
+    doc: Option<String>,
+    directives: &[Positioned<ConstDirective>],
+) -> Valid<Enum, String> {
     let variants = Valid::from_iter(enum_type.values.iter(), |member| {
         let name = member.node.value.node.as_str().to_owned();
         let alias = member
@@ -414,7 +443,13 @@ fn to_enum(enum_type: EnumType, doc: Option<String>) -> Valid<Enum, String> {
             Valid::succeed(Variant { name, alias: None })
         }
     });
-    variants.map(|v| Enum { variants: v.into_iter().collect::<BTreeSet<Variant>>(), doc })
+    variants
+        .fuse(EnumAlias::from_directives(directives.iter()))
+        .map(|(v, enum_alias)| Enum {
+            variants: v.into_iter().collect::<BTreeSet<Variant>>(),
+            doc,
+            enum_alias,
+        })
 }
 
 fn to_add_fields_from_directives(
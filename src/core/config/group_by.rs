@@ -9,15 +9,37 @@ pub struct GroupBy {
     path: Vec<String>,
     #[serde(default, skip_serializing_if = "is_default")]
     key: Option<String>,
+    /// The fields of the items at the `path` that form a composite key. Each
+    /// field is sent in the query parameter of the same name.
+    #[serde(default, skip_serializing_if = "is_default")]
+    keys: Vec<String>,
 }
 
 impl GroupBy {
     pub fn new(path: Vec<String>, key: Option<String>) -> Self {
-        Self { path, key }
+        Self { path, key, keys: vec![] }
+    }
+
+    /// Groups the items found at the `path` by the tuple of their `keys`.
+    pub fn composite(path: Vec<String>, keys: Vec<String>) -> Self {
+        Self { path, key: None, keys }
+    }
+
+    pub fn is_composite(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// The fields forming the key, a single one unless the key is composite.
+    pub fn keys(&self) -> Vec<&str> {
+        if self.is_composite() {
+            self.keys.iter().map(String::as_str).collect()
+        } else {
+            vec![self.key()]
+        }
     }
 
     pub fn path(&self) -> Vec<String> {
-        if self.path.is_empty() {
+        if self.path.is_empty() && !self.is_composite() {
             return vec![String::from(ID)];
         }
         self.path.clone()
@@ -40,6 +62,6 @@ const ID: &str = "id";
 
 impl Default for GroupBy {
     fn default() -> Self {
-        Self { path: vec![ID.to_string()], key: None }
+        Self { path: vec![ID.to_string()], key: None, keys: vec![] }
     }
 }
@@ -132,7 +132,11 @@ fn config_document(config: &Config) -> ServiceDocument {
                                         arg.default_value.clone(),
                                     )
                                     .map(pos),
-                                    directives: Vec::new(),
+                                    directives: arg
+                                        .validate
+                                        .iter()
+                                        .map(|validate| pos(validate.to_directive()))
+                                        .collect(),
                                 })
                             })
                             .collect::<Vec<Positioned<InputValueDefinition>>>();
@@ -180,7 +184,10 @@ fn config_document(config: &Config) -> ServiceDocument {
             extend: false,
             description: values.doc.clone().map(pos),
             name: pos(Name::new(name)),
-            directives: Vec::new(),
+            directives: values
+                .enum_alias
+                .as_ref()
+                .map_or(vec![], |enum_alias| vec![pos(enum_alias.to_directive())]),
             kind: TypeKind::Enum(EnumType {
                 values: values
                     .variants
@@ -221,6 +228,11 @@ fn field_directives(field: &crate::core::config::Field) -> Vec<Positioned<ConstD
         .chain(field.omit.as_ref().map(|d| pos(d.to_directive())))
         .chain(field.cache.as_ref().map(|d| pos(d.to_directive())))
         .chain(field.protected.as_ref().map(|d| pos(d.to_directive())))
+        .chain(field.mask.as_ref().map(|d| pos(d.to_directive())))
+        .chain(field.compress.as_ref().map(|d| pos(d.to_directive())))
+        .chain(field.fallback.as_ref().map(|d| pos(d.to_directive())))
+        .chain(field.join.as_ref().map(|d| pos(d.to_directive())))
+        .chain(field.partial.as_ref().map(|d| pos(d.to_directive())))
         .chain(into_directives(&field.directives))
         .collect()
 }
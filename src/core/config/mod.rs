@@ -1,4 +1,5 @@
 pub use apollo::*;
+pub use base_url::BaseUrls;
 pub use config::*;
 pub use config_module::*;
 pub use directive::Directive;
@@ -10,6 +11,7 @@ pub use resolver::*;
 pub use source::*;
 pub use url_query::*;
 mod apollo;
+mod base_url;
 mod config;
 mod config_module;
 pub mod cors;
@@ -8,7 +8,7 @@ use rustls_pki_types::{
 use tailcall_valid::{Valid, ValidationError, Validator};
 use url::Url;
 
-use super::{ConfigModule, Content, Link, LinkType, PrivateKey};
+use super::{ConfigModule, Content, Extensions, Link, LinkType, PrivateKey};
 use crate::core::config::{Config, ConfigReaderContext, Source};
 use crate::core::proto_reader::ProtoReader;
 use crate::core::resource_reader::{Cached, Resource, ResourceReader};
@@ -243,9 +243,12 @@ impl ConfigReader {
             .collect();
         let reader_ctx = ConfigReaderContext::new(&self.runtime).vars(vars);
         config.telemetry.render_mustache(&reader_ctx)?;
+        let base_url_env = config.base_url_env(&reader_ctx);
 
         // Create initial config set & extend it with the links
-        self.ext_links(ConfigModule::from(config), parent_dir).await
+        let config_module = ConfigModule::from(config)
+            .merge_extensions(Extensions { base_url_env, ..Default::default() });
+        self.ext_links(config_module, parent_dir).await
     }
 
     /// Checks if path is a URL or absolute path, returns directly if so.
@@ -274,9 +277,11 @@ mod reader_tests {
     use std::path::{Path, PathBuf};
 
     use pretty_assertions::assert_eq;
+    use tailcall_valid::Validator;
 
+    use crate::core::blueprint::Blueprint;
     use crate::core::config::reader::ConfigReader;
-    use crate::core::config::{Config, Type};
+    use crate::core::config::{Config, Resolver, Type};
 
     fn start_mock_server() -> httpmock::MockServer {
         httpmock::MockServer::start()
@@ -372,6 +377,48 @@ mod reader_tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_missing_env_in_base_url() {
+        let runtime = crate::core::runtime::test::init(None);
+        let reader = ConfigReader::init(runtime);
+
+        let config = Config::from_sdl(
+            r#"
+            schema { query: Query }
+            type Query {
+                users: [String] @http(url: "http://{{.env.UPSTREAM_HOST}}/users")
+            }
+            "#,
+        )
+        .to_result()
+        .unwrap();
+
+        // the config is read as it is, its base urls are resolved by the
+        // blueprint
+        let config_module = reader.resolve(config, None).await.unwrap();
+        let resolvers = &config_module.types["Query"].fields["users"].resolvers;
+        assert!(matches!(
+            resolvers.0.first(),
+            Some(Resolver::Http(http)) if http.url == "http://{{.env.UPSTREAM_HOST}}/users"
+        ));
+
+        let error = Blueprint::try_from(&config_module).unwrap_err();
+        let cause = error.as_vec().first().unwrap();
+
+        assert_eq!(
+            cause.message.to_string(),
+            "env var 'UPSTREAM_HOST' referenced in the base url is not set"
+        );
+        assert_eq!(
+            cause
+                .trace
+                .iter()
+                .map(|trace| trace.to_string())
+                .collect::<Vec<_>>(),
+            vec!["Query", "users", "@http", "url"]
+        );
+    }
+
     #[test]
     fn test_relative_path() {
         let path_dir = Path::new("abc/xyz");
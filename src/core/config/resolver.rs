@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use tailcall_macros::{CustomResolver, MergeRight};
 use tailcall_valid::{Valid, Validator};
 
-use super::{Call, EntityResolver, Expr, GraphQL, Grpc, Http, JS};
+use super::{Call, Compose, EntityResolver, Expr, GraphQL, Grpc, Http, JS};
 use crate::core::directive::DirectiveCodec;
 use crate::core::merge_right::MergeRight;
 
@@ -33,6 +33,7 @@ pub enum Resolver {
     Grpc(Grpc),
     Graphql(GraphQL),
     Call(Call),
+    Compose(Compose),
     Js(JS),
     Expr(Expr),
     #[serde(skip)]
@@ -43,7 +44,7 @@ pub enum Resolver {
 impl Resolver {
     pub fn is_batched(&self) -> bool {
         match self {
-            Resolver::Http(http) => !http.batch_key.is_empty(),
+            Resolver::Http(http) => http.is_batched(),
             Resolver::Grpc(grpc) => !grpc.batch_key.is_empty(),
             Resolver::Graphql(graphql) => graphql.batch,
             Resolver::ApolloFederation(ApolloFederation::EntityResolver(entity_resolver)) => {
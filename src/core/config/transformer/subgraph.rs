@@ -575,6 +575,7 @@ mod tests {
         fn test_extract_expr() {
             let expr = Expr {
                 body: json!({ "a": "{{.value.body.a}}", "b": "{{.value.body.b}}"}),
+                runtime_env: false,
             };
 
             let resolver = Resolver::Expr(expr);
@@ -113,8 +113,34 @@ where
         Ok(values.remove(&key))
     }
 
+    /// Use this `DataLoader` load a data, along with the number of the keys
+    /// of the batch it was loaded in, `0` when it was in the cache.
+    pub async fn load_one_batched(&self, key: K) -> Result<(Option<T::Value>, usize), T::Error>
+    where
+        K: Send + Sync + Hash + Eq + Clone + 'static,
+        T: Loader<K>,
+    {
+        let (mut values, batch_size) = self.load_many_batched(std::iter::once(key.clone())).await?;
+        Ok((values.remove(&key), batch_size))
+    }
+
     /// Use this `DataLoader` to load some data.
     pub async fn load_many<I>(&self, keys: I) -> Result<HashMap<K, T::Value>, T::Error>
+    where
+        K: Send + Sync + Hash + Eq + Clone + 'static,
+        I: IntoIterator<Item = K>,
+        T: Loader<K>,
+    {
+        Ok(self.load_many_batched(keys).await?.0)
+    }
+
+    /// Use this `DataLoader` to load some data, along with the number of the
+    /// keys of the batch they were loaded in, `0` when they were all in the
+    /// cache.
+    pub async fn load_many_batched<I>(
+        &self,
+        keys: I,
+    ) -> Result<(HashMap<K, T::Value>, usize), T::Error>
     where
         K: Send + Sync + Hash + Eq + Clone + 'static,
         I: IntoIterator<Item = K>,
@@ -146,7 +172,7 @@ where
             }
 
             if !use_cache_values.is_empty() && keys_set.is_empty() {
-                return Ok(use_cache_values);
+                return Ok((use_cache_values, 0));
             } else if use_cache_values.is_empty() && keys_set.is_empty() {
                 return Ok(Default::default());
             }
@@ -273,7 +299,7 @@ where
 #[allow(clippy::type_complexity)]
 struct ResSender<K: Send + Sync + Hash + Eq + Clone + 'static, T: Loader<K>> {
     use_cache_values: HashMap<K, T::Value>,
-    tx: oneshot::Sender<Result<HashMap<K, T::Value>, T::Error>>,
+    tx: oneshot::Sender<Result<(HashMap<K, T::Value>, usize), T::Error>>,
 }
 
 struct Requests<
@@ -330,6 +356,7 @@ where
         T: Loader<K>,
     {
         let keys = keys.into_iter().collect::<Vec<_>>();
+        let keys_count = keys.len();
 
         match self.loader.load(&keys).await {
             Ok(values) => {
@@ -351,7 +378,7 @@ where
                     for key in &keys {
                         res.extend(values.get(key).map(|value| (key.clone(), value.clone())));
                     }
-                    sender.tx.send(Ok(res)).ok();
+                    sender.tx.send(Ok((res, keys_count))).ok();
                 }
             }
             Err(err) => {
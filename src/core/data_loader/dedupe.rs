@@ -2,14 +2,15 @@ use std::collections::HashMap;
 use std::hash::Hash;
 use std::sync::{Arc, Mutex, Weak};
 
-use futures_util::Future;
+use futures_util::future::{BoxFuture, Shared, WeakShared};
+use futures_util::{Future, FutureExt};
 use tokio::sync::broadcast;
 
-pub trait Key: Send + Sync + Eq + Hash + Clone {}
-impl<A: Send + Sync + Eq + Hash + Clone> Key for A {}
+pub trait Key: Send + Sync + Eq + Hash + Clone + 'static {}
+impl<A: Send + Sync + Eq + Hash + Clone + 'static> Key for A {}
 
-pub trait Value: Send + Sync + Clone {}
-impl<A: Send + Sync + Clone> Value for A {}
+pub trait Value: Send + Sync + Clone + 'static {}
+impl<A: Send + Sync + Clone + 'static> Value for A {}
 
 ///
 /// Allows deduplication of async operations based on a key.
@@ -30,6 +31,10 @@ enum State<Value> {
     /// Means that the operation is in progress and the result can be sent via
     /// the stored sender whenever it's available in the future.
     Pending(Weak<broadcast::Sender<Value>>),
+
+    /// Means that the owned operation is in progress and is polled by all of
+    /// its waiters. The operation is dropped along with the last of them.
+    Shared(WeakShared<BoxFuture<'static, Value>>),
 }
 
 /// Represents the next steps
@@ -41,16 +46,70 @@ enum Step<Value> {
     /// receiver.
     Await(broadcast::Receiver<Value>),
 
+    /// The owned operation is in progress and must be polled along with the
+    /// other waiters.
+    Join(Shared<BoxFuture<'static, Value>>),
+
     /// The operation needs to be executed and the result needs to be sent to
     /// the provided sender.
     Init(Arc<broadcast::Sender<Value>>),
 }
 
+/// Removes the pending entry of an operation that is dropped before it
+/// completes, so that the entry doesn't outlive it. The waiters of a borrowed
+/// operation then take over its execution.
+struct PendingGuard<'a, K: Key, V: Value> {
+    cache: &'a Mutex<HashMap<K, State<V>>>,
+    key: &'a K,
+    tx: Weak<broadcast::Sender<V>>,
+    completed: bool,
+}
+
+impl<K: Key, V: Value> Drop for PendingGuard<'_, K, V> {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+        let mut cache = self.cache.lock().unwrap();
+        if let Some(State::Pending(tx)) = cache.get(self.key) {
+            if Weak::ptr_eq(tx, &self.tx) {
+                cache.remove(self.key);
+            }
+        }
+    }
+}
+
+/// Removes the entry of an owned operation once it's dropped by its last
+/// waiter before it completes.
+struct SharedGuard<K: Key, V: Value> {
+    cache: Arc<Mutex<HashMap<K, State<V>>>>,
+    key: K,
+    completed: bool,
+}
+
+impl<K: Key, V: Value> Drop for SharedGuard<K, V> {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+        let mut cache = self.cache.lock().unwrap();
+        // the entry of a newer operation with the same key can still be upgraded
+        if let Some(State::Shared(operation)) = cache.get(&self.key) {
+            if operation.upgrade().is_none() {
+                cache.remove(&self.key);
+            }
+        }
+    }
+}
+
 impl<K: Key, V: Value> Dedupe<K, V> {
     pub fn new(size: usize, persist: bool) -> Self {
         Self { cache: Arc::new(Mutex::new(HashMap::new())), size, persist }
     }
 
+    /// Deduplicates an operation that is run by the caller that started it.
+    /// The operation may borrow from the caller, so it's dropped along with
+    /// it and one of the other waiters takes over its execution.
     pub async fn dedupe<'a, Fn, Fut>(&'a self, key: &'a K, or_else: Fn) -> V
     where
         Fn: FnOnce() -> Fut,
@@ -69,14 +128,17 @@ impl<K: Key, V: Value> Dedupe<K, V> {
                         continue;
                     }
                 },
+                Step::Join(operation) => operation.await,
                 Step::Init(tx) => {
+                    let mut pending = PendingGuard {
+                        cache: &self.cache,
+                        key,
+                        tx: Arc::downgrade(&tx),
+                        completed: false,
+                    };
                     let value = or_else().await;
-                    let mut guard = self.cache.lock().unwrap();
-                    if self.persist {
-                        guard.insert(key.to_owned(), State::Ready(value.clone()));
-                    } else {
-                        guard.remove(key);
-                    }
+                    pending.completed = true;
+                    self.complete(key, &value);
                     let _ = tx.send(value.clone());
                     value
                 }
@@ -86,6 +148,60 @@ impl<K: Key, V: Value> Dedupe<K, V> {
         }
     }
 
+    /// Deduplicates an owned operation. Unlike with `dedupe` the operation
+    /// isn't tied to the caller that started it: it's polled by all of its
+    /// waiters, so it keeps running while any of them is waiting on it, and
+    /// it's cancelled once the last of them is dropped.
+    pub async fn dedupe_shared<Fn, Fut>(&self, key: &K, or_else: Fn) -> V
+    where
+        Fn: FnOnce() -> Fut,
+        Fut: Future<Output = V> + Send + 'static,
+    {
+        loop {
+            let operation = match self.step(key) {
+                Step::Return(value) => return value,
+                Step::Await(mut rx) => match rx.recv().await {
+                    Ok(value) => return value,
+                    Err(_) => continue,
+                },
+                Step::Join(operation) => operation,
+                Step::Init(tx) => {
+                    let mut guard = SharedGuard {
+                        cache: self.cache.clone(),
+                        key: key.to_owned(),
+                        completed: false,
+                    };
+                    let persist = self.persist;
+                    let future = or_else();
+                    let operation = async move {
+                        let value = future.await;
+                        guard.completed = true;
+                        complete(&guard.cache, &guard.key, &value, persist);
+                        value
+                    }
+                    .boxed()
+                    .shared();
+
+                    if let Some(weak) = operation.downgrade() {
+                        let mut cache = self.cache.lock().unwrap();
+                        cache.insert(key.to_owned(), State::Shared(weak));
+                    }
+                    // the waiters subscribed in the meantime get the result
+                    // of the operation
+                    let value = operation.await;
+                    let _ = tx.send(value.clone());
+                    return value;
+                }
+            };
+
+            return operation.await;
+        }
+    }
+
+    fn complete(&self, key: &K, value: &V) {
+        complete(&self.cache, key, value, self.persist)
+    }
+
     fn step(&self, key: &K) -> Step<V> {
         let mut this = self.cache.lock().unwrap();
 
@@ -100,6 +216,11 @@ impl<K: Key, V: Value> Dedupe<K, V> {
                         return Step::Await(tx.subscribe());
                     }
                 }
+                State::Shared(operation) => {
+                    if let Some(operation) = operation.upgrade() {
+                        return Step::Join(operation);
+                    }
+                }
             }
         }
 
@@ -114,6 +235,22 @@ impl<K: Key, V: Value> Dedupe<K, V> {
     }
 }
 
+/// Stores the result of a completed operation, or forgets the operation if the
+/// results aren't persisted.
+fn complete<K: Key, V: Value>(
+    cache: &Mutex<HashMap<K, State<V>>>,
+    key: &K,
+    value: &V,
+    persist: bool,
+) {
+    let mut cache = cache.lock().unwrap();
+    if persist {
+        cache.insert(key.to_owned(), State::Ready(value.clone()));
+    } else {
+        cache.remove(key);
+    }
+}
+
 pub struct DedupeResult<K, V, E>(Dedupe<K, Result<V, E>>);
 
 impl<K: Key, V: Value, E: Value> DedupeResult<K, V, E> {
@@ -130,6 +267,14 @@ impl<K: Key, V: Value, E: Value> DedupeResult<K, V, E> {
     {
         self.0.dedupe(key, or_else).await
     }
+
+    pub async fn dedupe_shared<Fn, Fut>(&self, key: &K, or_else: Fn) -> Result<V, E>
+    where
+        Fn: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V, E>> + Send + 'static,
+    {
+        self.0.dedupe_shared(key, or_else).await
+    }
 }
 
 #[cfg(test)]
@@ -286,9 +431,7 @@ mod tests {
         assert_eq!(actual, 200)
     }
 
-    // TODO: This is a failing test
     #[tokio::test]
-    #[ignore]
     async fn test_should_not_abort_call_1() {
         #[derive(Debug, PartialEq, Clone)]
         struct Status {
@@ -310,7 +453,7 @@ mod tests {
         // Task 1 completed in 100ms
         let task_1 = tokio::spawn(async move {
             cache_1
-                .dedupe(&1, move || async move {
+                .dedupe_shared(&1, move || async move {
                     sleep(Duration::from_millis(100)).await;
                     status_1.lock().unwrap().call_1 = true;
                 })
@@ -323,7 +466,7 @@ mod tests {
         // Task 2 completed in 200ms
         tokio::spawn(async move {
             cache_2
-                .dedupe(&1, move || async move {
+                .dedupe_shared(&1, move || async move {
                     sleep(Duration::from_millis(120)).await;
                     status_2.lock().unwrap().call_2 = true;
                 })
@@ -395,4 +538,65 @@ mod tests {
         let actual = status.lock().unwrap().deref().to_owned();
         assert_eq!(actual, Status { call_1: false, call_2: false })
     }
+
+    #[tokio::test]
+    async fn test_shared_should_abort_all() {
+        let called = Arc::new(AtomicUsize::new(0));
+        let cache = Arc::new(Dedupe::<u64, ()>::new(100, true));
+
+        let tasks = (0..2)
+            .map(|_| {
+                let cache = cache.clone();
+                let called = called.clone();
+                tokio::spawn(async move {
+                    cache
+                        .dedupe_shared(&1, move || async move {
+                            sleep(Duration::from_millis(100)).await;
+                            called.fetch_add(1, Ordering::SeqCst);
+                        })
+                        .await
+                })
+            })
+            .collect::<Vec<_>>();
+
+        sleep(Duration::from_millis(50)).await;
+        for task in tasks {
+            task.abort();
+        }
+
+        sleep(Duration::from_millis(300)).await;
+
+        // the operation is cancelled along with its last waiter
+        assert_eq!(called.load(Ordering::SeqCst), 0);
+        assert!(cache.cache.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_shared_with_borrowed() {
+        let cache = Arc::new(Dedupe::<u64, u64>::new(100, false));
+
+        let shared = cache.dedupe_shared(&1, || async {
+            sleep(Duration::from_millis(10)).await;
+            1
+        });
+        let borrowed = cache.dedupe(&1, || async { 2 });
+        let (shared, borrowed) = join!(shared, borrowed);
+
+        // the borrowed call waits on the owned operation in progress
+        assert_eq!((shared, borrowed), (1, 1));
+        assert!(cache.cache.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_entry_removed() {
+        let cache = Arc::new(Dedupe::<u64, ()>::new(100, false));
+
+        let task = cache.dedupe(&1, || sleep(Duration::from_millis(100)));
+        timeout_at(Instant::now() + Duration::from_millis(10), task)
+            .await
+            .expect_err("Should throw timeout error");
+
+        // the entry of the dropped operation doesn't outlive it
+        assert!(cache.cache.lock().unwrap().is_empty());
+    }
 }
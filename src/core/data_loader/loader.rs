@@ -12,4 +12,10 @@ pub trait Loader<K: Send + Sync + Hash + Eq + Clone + 'static>: Send + Sync + 's
 
     /// Load the data set specified by the `keys`.
     async fn load(&self, keys: &[K]) -> Result<HashMap<K, Self::Value>, Self::Error>;
+
+    /// Whether the keys are loaded with a call for all of them, rather than
+    /// with a call each.
+    fn is_batched(&self) -> bool {
+        true
+    }
 }
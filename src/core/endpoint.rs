@@ -1,17 +1,19 @@
 use derive_setters::Setters;
 use http::header::HeaderMap;
+use serde::{Deserialize, Serialize};
 
 use crate::core::config::Encoding;
 use crate::core::http::Method;
 use crate::core::json::JsonSchema;
 
-#[derive(Clone, Debug, Setters)]
+#[derive(Clone, Debug, Setters, Serialize, Deserialize)]
 pub struct Endpoint {
     pub path: String,
     pub query: Vec<(String, String, bool)>,
     pub method: Method,
     pub input: JsonSchema,
     pub output: JsonSchema,
+    #[serde(with = "crate::core::helpers::serde::header_map")]
     pub headers: HeaderMap,
     pub body: Option<serde_json::Value>,
     pub description: Option<String>,
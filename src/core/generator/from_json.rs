@@ -106,6 +106,12 @@ impl Transform for FromJsonGenerator<'_> {
                         .to_case(Case::Pascal),
                     self.mutation_name.clone().unwrap_or("Mutation".to_owned()),
                 ),
+                GraphQLOperationType::Subscription => {
+                    let name = GraphQLOperationType::Subscription
+                        .to_string()
+                        .to_case(Case::Pascal);
+                    (name.clone(), name)
+                }
             };
 
             // collect the required header keys
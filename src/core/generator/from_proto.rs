@@ -210,9 +210,10 @@ impl Context {
                 .map(|v| Variant { name: v, alias: None })
                 .collect();
 
-            self.config
-                .enums
-                .insert(type_name, Enum { variants: variants_with_comments, doc });
+            self.config.enums.insert(
+                type_name,
+                Enum { variants: variants_with_comments, doc, enum_alias: None },
+            );
         }
         self
     }
@@ -356,6 +357,7 @@ impl Context {
                         doc: None,
                         modify: None,
                         default_value: None,
+                        validate: None,
                     };
 
                     body = Some(Value::String(format!("{{{{.args.{key}}}}}")));
@@ -375,7 +377,9 @@ impl Context {
                     method: field_name.id(),
                     dedupe: None,
                     select: None,
+                    filter: None,
                     on_response_body: None,
+                    ..Default::default()
                 })
                 .into();
 
@@ -51,7 +51,10 @@ impl From<Grpc> for Http {
         let batch_key = grpc.batch_key;
         let dedupe = grpc.dedupe;
         let select = grpc.select;
+        let filter = grpc.filter;
+        let compact = grpc.compact;
         let on_response_body = grpc.on_response_body;
+        let priority = grpc.priority;
 
         Self {
             url: new_url,
@@ -61,7 +64,10 @@ impl From<Grpc> for Http {
             batch_key,
             dedupe,
             select,
+            filter,
+            compact,
             on_response_body,
+            priority,
             ..Default::default()
         }
     }
@@ -72,19 +78,30 @@ mod tests {
     use serde_json::{json, Value};
 
     use super::*;
-    use crate::core::config::KeyValue;
+    use crate::core::config::{KeyValue, Priority};
 
     #[test]
     fn test_grpc_to_http_basic_conversion() {
         let grpc = Grpc {
             url: "http://localhost:8080".to_string(),
             method: "package.service.method".to_string(),
+            allow: Default::default(),
             body: Some(json!({"key": "value"})),
             headers: Default::default(),
             batch_key: Default::default(),
             dedupe: Default::default(),
+            field_mask: Default::default(),
+            enum_as_string: Default::default(),
+            unknown_enum_value: Default::default(),
             select: Default::default(),
+            filter: Default::default(),
             on_response_body: Default::default(),
+            priority: Default::default(),
+            routes: Default::default(),
+            route_by: Default::default(),
+            retry: Default::default(),
+            transcode: Default::default(),
+            compact: Default::default(),
         };
 
         let http = Http::from(grpc);
@@ -99,12 +116,23 @@ mod tests {
         let grpc = Grpc {
             url: "http://localhost:8080".to_string(),
             method: "package.service.method".to_string(),
+            allow: Default::default(),
             body: Default::default(),
             headers: Default::default(),
             batch_key: Default::default(),
             dedupe: Default::default(),
+            field_mask: Default::default(),
+            enum_as_string: Default::default(),
+            unknown_enum_value: Default::default(),
             select: Default::default(),
+            filter: Default::default(),
             on_response_body: Default::default(),
+            priority: Default::default(),
+            routes: Default::default(),
+            route_by: Default::default(),
+            retry: Default::default(),
+            transcode: Default::default(),
+            compact: Default::default(),
         };
 
         let http = Http::from(grpc);
@@ -117,12 +145,23 @@ mod tests {
         let grpc = Grpc {
             url: "http://localhost:8080".to_string(),
             method: "a.b.c".to_string(),
+            allow: Default::default(),
             body: None,
             headers: vec![KeyValue { key: "X-Foo".to_string(), value: "bar".to_string() }],
             batch_key: Default::default(),
             dedupe: Default::default(),
+            field_mask: Default::default(),
+            enum_as_string: Default::default(),
+            unknown_enum_value: Default::default(),
             select: Default::default(),
+            filter: Default::default(),
             on_response_body: Default::default(),
+            priority: Default::default(),
+            routes: Default::default(),
+            route_by: Default::default(),
+            retry: Default::default(),
+            transcode: Default::default(),
+            compact: Default::default(),
         };
 
         let http = Http::from(grpc);
@@ -144,12 +183,23 @@ mod tests {
         let grpc = Grpc {
             url: "http://localhost:8080".to_string(),
             method: "package.service.method".to_string(),
+            allow: Default::default(),
             body: Some(json!({"key": "value"})),
             headers: vec![KeyValue { key: "X-Foo".to_string(), value: "bar".to_string() }],
             batch_key: vec!["batch_key_value".to_string()],
             dedupe: Some(true),
+            field_mask: Some("mask".to_string()),
+            enum_as_string: true,
+            unknown_enum_value: Some("UNKNOWN".to_string()),
             select: Some(Value::String("select_value".to_string())),
+            filter: Some("active == true".to_string()),
             on_response_body: Some("on_response_body_value".to_string()),
+            priority: Priority::High,
+            routes: Default::default(),
+            route_by: Default::default(),
+            retry: Default::default(),
+            transcode: Default::default(),
+            compact: Default::default(),
         };
 
         let http = Http::from(grpc);
@@ -167,7 +217,9 @@ mod tests {
         );
         assert_eq!(http.batch_key, vec!["batch_key_value".to_string()]);
         assert_eq!(http.dedupe, Some(true));
+        assert_eq!(http.priority, Priority::High);
         assert_eq!(http.select, Some(Value::String("select_value".to_string())));
+        assert_eq!(http.filter, Some("active == true".to_string()));
         assert_eq!(
             http.on_response_body,
             Some("on_response_body_value".to_string())
@@ -5,20 +5,34 @@ use std::time::Duration;
 
 use async_graphql::async_trait;
 use async_graphql::futures_util::future::join_all;
+use async_graphql_value::{ConstValue, Name};
+use indexmap::IndexMap;
 
-use crate::core::config::Batch;
+use crate::core::config::{Batch, GraphQLBatchStyle};
 use crate::core::data_loader::{DataLoader, Loader};
-use crate::core::http::{DataLoaderRequest, Response};
+use crate::core::http::{DataLoaderRequest, Response, UpstreamBudget};
 use crate::core::runtime::TargetRuntime;
 
 pub struct GraphqlDataLoader {
     pub runtime: TargetRuntime,
     pub batch: bool,
+    pub style: GraphQLBatchStyle,
+    /// Name of the operation the batches are sent as
+    pub name: Option<String>,
 }
 
 impl GraphqlDataLoader {
     pub fn new(runtime: TargetRuntime, batch: bool) -> Self {
-        GraphqlDataLoader { runtime, batch }
+        GraphqlDataLoader {
+            runtime,
+            batch,
+            style: GraphQLBatchStyle::default(),
+            name: None,
+        }
+    }
+
+    pub fn style(self, style: GraphQLBatchStyle, name: Option<String>) -> Self {
+        GraphqlDataLoader { style, name, ..self }
     }
 
     pub fn into_data_loader(
@@ -42,15 +56,38 @@ impl Loader<DataLoaderRequest> for GraphqlDataLoader {
         keys: &[DataLoaderRequest],
     ) -> async_graphql::Result<HashMap<DataLoaderRequest, Self::Value>, Self::Error> {
         if self.batch {
-            let batched_req = create_batched_request(keys);
-            let result = self.runtime.http.execute(batched_req).await?.to_json();
-            let hashmap = extract_responses(result, keys);
-            Ok(hashmap)
+            // the keys of the requests that are out of budget are left out of the batch
+            let keys = &UpstreamBudget::charge_batch(keys, DataLoaderRequest::budget);
+            if keys.is_empty() {
+                return Ok(HashMap::new());
+            }
+            let name = self.name.as_deref();
+            // the requests whose query can't be merged are batched as an array
+            let fields: Option<Vec<_>> = match self.style {
+                GraphQLBatchStyle::Alias => keys.iter().map(top_level_field).collect(),
+                GraphQLBatchStyle::Array => None,
+            };
+
+            if let Some(fields) = fields {
+                let batched_req = create_aliased_request(keys, &fields, name);
+                let result = self.runtime.http.execute(batched_req).await?.to_json();
+                Ok(scatter_aliased_responses(result, keys, &fields))
+            } else {
+                let batched_req = create_batched_request(keys, name);
+                let result = self.runtime.http.execute(batched_req).await?.to_json();
+                let hashmap = extract_responses(result, keys);
+                Ok(hashmap)
+            }
         } else {
-            let results = keys.iter().map(|key| async {
-                let result = self.runtime.http.execute(key.to_request()).await;
-                (key.clone(), result)
-            });
+            // the keys are dispatched one by one, so each of them is charged and the
+            // ones that are out of budget are left out
+            let results =
+                keys.iter()
+                    .filter(|key| key.budget().charge().is_ok())
+                    .map(|key| async {
+                        let result = self.runtime.http.execute(key.to_request()).await;
+                        (key.clone(), result)
+                    });
             let results = join_all(results).await;
             #[allow(clippy::mutable_key_type)]
             let mut hashmap = HashMap::new();
@@ -63,7 +100,7 @@ impl Loader<DataLoaderRequest> for GraphqlDataLoader {
     }
 }
 
-fn collect_request_bodies(dataloader_requests: &[DataLoaderRequest]) -> String {
+fn collect_request_bodies(dataloader_requests: &[DataLoaderRequest], name: Option<&str>) -> String {
     let batched_query = dataloader_requests
         .iter()
         .filter_map(|dataloader_req| {
@@ -74,13 +111,22 @@ fn collect_request_bodies(dataloader_requests: &[DataLoaderRequest]) -> String {
                 .and_then(|body| from_utf8(body).ok())
                 .or(Some(""))
         })
+        .map(
+            |body| match name.and_then(|name| name_operation(body, name)) {
+                Some(body) => body,
+                None => body.to_string(),
+            },
+        )
         .collect::<Vec<_>>()
         .join(",");
     format!("[{}]", batched_query)
 }
 
-fn create_batched_request(dataloader_requests: &[DataLoaderRequest]) -> reqwest::Request {
-    let batched_query = collect_request_bodies(dataloader_requests);
+fn create_batched_request(
+    dataloader_requests: &[DataLoaderRequest],
+    name: Option<&str>,
+) -> reqwest::Request {
+    let batched_query = collect_request_bodies(dataloader_requests, name);
 
     let first_req = dataloader_requests.first().unwrap();
     let mut batched_req = first_req.to_request();
@@ -116,14 +162,329 @@ fn extract_responses(
     hashmap
 }
 
+/// The query of the body of a request, without its `query` keyword eg.
+/// `{ users { name } }` of `query { users { name } }`.
+fn query_selection(body: &serde_json::Value) -> Option<&str> {
+    let query = body.get("query")?.as_str()?.trim();
+    let selection = query.strip_prefix("query")?.trim_start();
+
+    selection.starts_with('{').then_some(selection)
+}
+
+/// Names the operation of the body of a request, it's kept as it is if its
+/// query isn't an anonymous query.
+fn name_operation(body: &str, name: &str) -> Option<String> {
+    let mut body: serde_json::Value = serde_json::from_str(body).ok()?;
+    let query = format!("query {name} {}", query_selection(&body)?);
+    body["query"] = serde_json::Value::String(query);
+    body["operationName"] = serde_json::Value::String(name.to_string());
+
+    Some(body.to_string())
+}
+
+/// The field the request selects along with its arguments and selection,
+/// eg. `users(id: 1) { name }` of `query { users(id: 1) { name } }`. `None`
+/// if the query of the request isn't a single field of an anonymous query.
+fn top_level_field(request: &DataLoaderRequest) -> Option<String> {
+    let body = request.body()?.as_bytes()?;
+    let body: serde_json::Value = serde_json::from_slice(body).ok()?;
+    let selection = query_selection(&body)?;
+    let field = selection.strip_prefix('{')?.strip_suffix('}')?.trim();
+
+    (!field.is_empty()).then(|| field.to_string())
+}
+
+/// Name of the field selected by the output of `top_level_field`.
+fn field_name(field: &str) -> &str {
+    let end = field
+        .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_'))
+        .unwrap_or(field.len());
+
+    &field[..end]
+}
+
+/// Alias of the field of the request at the index, the aliases are unique
+/// within the batch and can't collide with the fields, that are never
+/// selected without an alias.
+fn alias(index: usize) -> String {
+    format!("batch{index}")
+}
+
+/// Merges the fields of the requests into a single operation that selects each
+/// of them under an alias, eg. `query { batch0: user(id: 1) { name } batch1:
+/// user(id: 2) { name } }`.
+fn create_aliased_request(
+    dataloader_requests: &[DataLoaderRequest],
+    fields: &[String],
+    name: Option<&str>,
+) -> reqwest::Request {
+    let selections = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| format!("{}: {field}", alias(i)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let query = match name {
+        Some(name) => format!("query {name} {{ {selections} }}"),
+        None => format!("query {{ {selections} }}"),
+    };
+    let mut body = serde_json::json!({ "query": query });
+    if let Some(name) = name {
+        body["operationName"] = serde_json::Value::String(name.to_string());
+    }
+
+    let first_req = dataloader_requests.first().unwrap();
+    let mut batched_req = first_req.to_request();
+    batched_req
+        .body_mut()
+        .replace(reqwest::Body::from(body.to_string()));
+    batched_req
+}
+
+/// Splits the response of the merged operation into the responses of each of
+/// the requests. Each request gets the data of its alias under the name of its
+/// field, along with the errors of its alias, and the errors without a path
+/// that are shared by all of them.
+#[allow(clippy::mutable_key_type)]
+fn scatter_aliased_responses(
+    result: Result<Response<async_graphql::Value>, anyhow::Error>,
+    keys: &[DataLoaderRequest],
+    fields: &[String],
+) -> HashMap<DataLoaderRequest, Response<async_graphql::Value>> {
+    let mut hashmap = HashMap::new();
+    let Ok(res) = result else {
+        return hashmap;
+    };
+    let ConstValue::Object(mut body) = res.body else {
+        return hashmap;
+    };
+    let mut data = match body.swap_remove("data") {
+        Some(ConstValue::Object(data)) => data,
+        _ => IndexMap::new(),
+    };
+    let errors = match body.swap_remove("errors") {
+        Some(ConstValue::List(errors)) => errors,
+        _ => vec![],
+    };
+
+    for (i, (request, field)) in keys.iter().zip(fields).enumerate() {
+        let alias = alias(i);
+        let name = Name::new(field_name(field));
+        let value = data.swap_remove(alias.as_str()).unwrap_or_default();
+        let errors = errors
+            .iter()
+            .filter_map(|error| remap_error(error, &alias, &name))
+            .collect::<Vec<_>>();
+
+        let mut body = IndexMap::new();
+        body.insert(
+            Name::new("data"),
+            ConstValue::Object(IndexMap::from([(name, value)])),
+        );
+        if !errors.is_empty() {
+            body.insert(Name::new("errors"), ConstValue::List(errors));
+        }
+
+        hashmap.insert(
+            request.clone(),
+            Response {
+                status: res.status,
+                headers: res.headers.clone(),
+                body: ConstValue::Object(body),
+            },
+        );
+    }
+
+    hashmap
+}
+
+/// The error of the merged operation as an error of the request of the alias,
+/// with the alias at the start of its path replaced by the name of the field.
+/// `None` if it's an error of another request.
+fn remap_error(error: &ConstValue, alias: &str, name: &Name) -> Option<ConstValue> {
+    let ConstValue::Object(error) = error else {
+        return None;
+    };
+    let mut error = error.clone();
+
+    match error.get_mut("path") {
+        Some(ConstValue::List(path)) => match path.first_mut() {
+            Some(ConstValue::String(segment)) if segment.as_str() == alias => {
+                *segment = name.to_string();
+            }
+            Some(_) => return None,
+            None => {}
+        },
+        Some(_) => return None,
+        None => {}
+    }
+
+    Some(ConstValue::Object(error))
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeSet;
+    use std::sync::Mutex;
 
+    use hyper::body::Bytes;
     use reqwest::Url;
+    use serde_json::json;
 
     use super::*;
     use crate::core::http::DataLoaderRequest;
+    use crate::core::HttpIO;
+
+    /// Records the bodies of the requests and responds with the given body.
+    struct TestHttp {
+        response: serde_json::Value,
+        bodies: Mutex<Vec<serde_json::Value>>,
+    }
+
+    #[async_trait::async_trait]
+    impl HttpIO for TestHttp {
+        async fn execute(&self, request: reqwest::Request) -> anyhow::Result<Response<Bytes>> {
+            let body = request.body().and_then(|body| body.as_bytes()).unwrap();
+            self.bodies
+                .lock()
+                .unwrap()
+                .push(serde_json::from_slice(body)?);
+
+            Ok(Response {
+                status: reqwest::StatusCode::OK,
+                headers: Default::default(),
+                body: Bytes::from(self.response.to_string()),
+            })
+        }
+    }
+
+    fn graphql_request(query: &str) -> DataLoaderRequest {
+        let url = Url::parse("http://upstream/graphql").unwrap();
+        let mut request = reqwest::Request::new(reqwest::Method::POST, url);
+        let body = json!({ "query": query }).to_string();
+        request.body_mut().replace(reqwest::Body::from(body));
+
+        DataLoaderRequest::new(request, BTreeSet::new())
+    }
+
+    #[allow(clippy::mutable_key_type)]
+    async fn load(
+        style: GraphQLBatchStyle,
+        keys: &[DataLoaderRequest],
+        response: serde_json::Value,
+    ) -> (Vec<serde_json::Value>, Vec<serde_json::Value>) {
+        let http = Arc::new(TestHttp { response, bodies: Mutex::new(vec![]) });
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.http = http.clone();
+        let loader = GraphqlDataLoader::new(runtime, true).style(style, Some("UsersBatch".into()));
+
+        let mut responses = loader.load(keys).await.unwrap();
+        let responses = keys
+            .iter()
+            .map(|key| responses.remove(key).unwrap().body.into_json().unwrap())
+            .collect();
+        let bodies = http.bodies.lock().unwrap().clone();
+
+        (bodies, responses)
+    }
+
+    #[tokio::test]
+    async fn test_array_batch() {
+        let keys = [
+            graphql_request("query { user(id: 1) { name } }"),
+            graphql_request("query { user(id: 2) { name } }"),
+        ];
+        let response = json!([
+            { "data": { "user": { "name": "foo" } } },
+            { "data": { "user": { "name": "bar" } } },
+        ]);
+
+        let (bodies, responses) = load(GraphQLBatchStyle::Array, &keys, response).await;
+
+        assert_eq!(
+            bodies,
+            vec![json!([
+                {
+                    "query": "query UsersBatch { user(id: 1) { name } }",
+                    "operationName": "UsersBatch",
+                },
+                {
+                    "query": "query UsersBatch { user(id: 2) { name } }",
+                    "operationName": "UsersBatch",
+                },
+            ])]
+        );
+        assert_eq!(
+            responses,
+            vec![
+                json!({ "data": { "user": { "name": "foo" } } }),
+                json!({ "data": { "user": { "name": "bar" } } }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_alias_batch() {
+        let keys = [
+            graphql_request("query { user(id: 1) { name } }"),
+            graphql_request("query { user(id: 2) { name } }"),
+            graphql_request("query { posts { title } }"),
+        ];
+        let response = json!({
+            "data": {
+                "batch0": { "name": "foo" },
+                "batch1": null,
+                "batch2": [{ "title": "baz" }],
+            },
+            "errors": [
+                { "message": "User not found", "path": ["batch1"] },
+                { "message": "Slow query" },
+            ],
+        });
+
+        let (bodies, responses) = load(GraphQLBatchStyle::Alias, &keys, response).await;
+
+        assert_eq!(
+            bodies,
+            vec![json!({
+                "query": "query UsersBatch { batch0: user(id: 1) { name } batch1: user(id: 2) { name } batch2: posts { title } }",
+                "operationName": "UsersBatch",
+            })]
+        );
+        assert_eq!(
+            responses,
+            vec![
+                json!({
+                    "data": { "user": { "name": "foo" } },
+                    "errors": [{ "message": "Slow query" }],
+                }),
+                json!({
+                    "data": { "user": null },
+                    "errors": [
+                        { "message": "User not found", "path": ["user"] },
+                        { "message": "Slow query" },
+                    ],
+                }),
+                json!({
+                    "data": { "posts": [{ "title": "baz" }] },
+                    "errors": [{ "message": "Slow query" }],
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aliased_fallback() {
+        // a named query can't be merged, so the batch falls back to an array
+        let named = graphql_request("query Me { me { name } }");
+        assert_eq!(top_level_field(&named), None);
+        assert_eq!(
+            top_level_field(&graphql_request("query { user(id: 1) { name } }")).as_deref(),
+            Some("user(id: 1) { name }")
+        );
+        assert_eq!(field_name("user(id: 1) { name }"), "user");
+        assert_eq!(field_name("posts @skip(if: false) { title }"), "posts");
+    }
 
     #[test]
     fn test_collect_request_bodies() {
@@ -145,7 +506,7 @@ mod tests {
         let dl_req2 = DataLoaderRequest::new(request2, BTreeSet::new());
         let dl_req3 = DataLoaderRequest::new(request3, BTreeSet::new());
 
-        let body = collect_request_bodies(&[dl_req1, dl_req2, dl_req3]);
+        let body = collect_request_bodies(&[dl_req1, dl_req2, dl_req3], None);
         assert_eq!(body, "[a,b,c]");
     }
 }
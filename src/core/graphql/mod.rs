@@ -1,5 +1,7 @@
 mod data_loader;
 mod request_template;
+mod subscription;
 
 pub use data_loader::*;
 pub use request_template::*;
+pub use subscription::subscribe;
@@ -1,24 +1,24 @@
 #![allow(clippy::too_many_arguments)]
 
 use std::borrow::Cow;
-use std::hash::{Hash, Hasher};
+use std::hash::Hash;
 
 use derive_setters::Setters;
 use http::header::{HeaderMap, HeaderValue};
-use tailcall_hasher::TailcallHasher;
+use serde::{Deserialize, Serialize};
 use tracing::info;
 
 use crate::core::config::{GraphQLOperationType, KeyValue};
 use crate::core::has_headers::HasHeaders;
 use crate::core::helpers::headers::MustacheHeaders;
 use crate::core::http::Method::POST;
-use crate::core::ir::model::{CacheKey, IoId};
+use crate::core::ir::model::{CacheKey, IoId, IoIdHasher};
 use crate::core::ir::{GraphQLOperationContext, RelatedFields};
 use crate::core::mustache::Mustache;
 use crate::core::path::{PathGraphql, PathString};
 
 /// Represents a GraphQL selection that can either be resolved or unresolved.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Selection {
     /// A selection with a resolved string value.
     Resolved(String),
@@ -46,13 +46,14 @@ impl From<Mustache> for Selection {
 }
 
 /// RequestTemplate for GraphQL requests (See RequestTemplate documentation)
-#[derive(Setters, Debug, Clone)]
+#[derive(Setters, Debug, Clone, Serialize, Deserialize)]
 pub struct RequestTemplate {
     // TODO: should be Mustache as for other templates
     pub url: String,
     pub operation_type: GraphQLOperationType,
     pub operation_name: String,
     pub operation_arguments: Option<Vec<(String, Mustache)>>,
+    #[serde(with = "crate::core::helpers::serde::mustache_headers")]
     pub headers: MustacheHeaders,
     pub related_fields: RelatedFields,
     pub selection: Option<Selection>,
@@ -194,10 +195,15 @@ impl RequestTemplate {
 
 impl<Ctx: PathGraphql + HasHeaders + GraphQLOperationContext> CacheKey<Ctx> for RequestTemplate {
     fn cache_key(&self, ctx: &Ctx) -> Option<IoId> {
-        let mut hasher = TailcallHasher::default();
+        // every subscription is a stream of its own
+        if self.operation_type == GraphQLOperationType::Subscription {
+            return None;
+        }
+
+        let mut hasher = IoIdHasher::default();
         let graphql_query = self.render_graphql_query(ctx);
         graphql_query.hash(&mut hasher);
-        Some(IoId::new(hasher.finish()))
+        Some(hasher.finish_id())
     }
 }
 
@@ -377,4 +383,78 @@ mod tests {
 
         assert_eq!(arr.len(), cache_key_set.len());
     }
+
+    #[tokio::test]
+    async fn test_pruned_selection() {
+        use std::sync::{Arc, Mutex};
+
+        use crate::core::app_context::test::{TestApp, TestHttp};
+
+        let sdl = r#"
+            schema @server @upstream { query: Query }
+            type Query {
+              user: User
+                @graphQL(url: "http://upstream/graphql", name: "user", args: [{key: "id", value: "1"}])
+                @cache(maxAge: 60000)
+            }
+            type User {
+              id: Int
+              name: String
+              email: String
+            }
+        "#;
+        let queries = Arc::new(Mutex::new(vec![]));
+        let http = TestHttp::body({
+            let queries = queries.clone();
+            move |request| {
+                let body = request.body().and_then(|body| body.as_bytes()).unwrap();
+                queries
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(body).to_string());
+                Ok(r#"{"data": {"user": {"id": 1, "name": "Leanne", "email": "a@b.c"}}}"#)
+            }
+        });
+        let app = TestApp::new(sdl, http.clone());
+
+        // the upstream query selects only the fields of the client query
+        app.execute("query { user { id } }").await;
+        app.execute("query { user { id name email } }").await;
+        let actual = queries.lock().unwrap().clone();
+        assert_eq!(
+            actual,
+            [
+                r#"{ "query": "query { user(id: 1) { id } }" }"#,
+                r#"{ "query": "query { user(id: 1) { id name email } }" }"#,
+            ]
+        );
+
+        // the selections are cached under keys of their own
+        let response = app.execute("query { user { id } }").await;
+        assert_eq!(response["data"]["user"], json!({ "id": 1 }));
+        assert_eq!(http.hits(), 2);
+    }
+
+    #[test]
+    fn test_subscription() {
+        let tmpl = RequestTemplate::new(
+            "http://localhost:3000".to_string(),
+            &GraphQLOperationType::Subscription,
+            "onCreate",
+            None,
+            vec![],
+            RelatedFields::default(),
+        )
+        .unwrap();
+        let ctx = Context { value: Value::Null, headers: Default::default() };
+
+        let req = tmpl.to_request(&ctx).unwrap();
+        let body = req.body().unwrap().as_bytes().unwrap().to_owned();
+
+        assert_eq!(
+            std::str::from_utf8(&body).unwrap(),
+            r#"{ "query": "subscription { onCreate { a,b,c } }" }"#
+        );
+        assert_eq!(tmpl.cache_key(&ctx), None);
+    }
 }
@@ -0,0 +1,480 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_graphql_value::ConstValue;
+use futures_timer::Delay;
+use futures_util::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::core::ir::Error;
+use crate::core::json::JsonLike;
+use crate::core::{WebSocketConnection, WebSocketIO};
+
+/// Subprotocol of the `graphql-ws` library.
+const PROTOCOL: &str = "graphql-transport-ws";
+
+/// Id of the operation, only one operation is sent per connection.
+const OPERATION_ID: &str = "1";
+
+/// Number of consecutive attempts to reconnect after the connection is lost.
+const MAX_RECONNECTS: u32 = 5;
+
+/// Delay before the first reconnect, doubled with every attempt.
+const RECONNECT_DELAY: Duration = Duration::from_millis(100);
+
+/// Messages of the `graphql-transport-ws` protocol.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    ConnectionInit {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        payload: Option<Value>,
+    },
+    ConnectionAck {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        payload: Option<Value>,
+    },
+    Ping {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        payload: Option<Value>,
+    },
+    Pong {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        payload: Option<Value>,
+    },
+    Subscribe {
+        id: String,
+        payload: Value,
+    },
+    Next {
+        id: String,
+        payload: Value,
+    },
+    Error {
+        id: String,
+        payload: Value,
+    },
+    Complete {
+        id: String,
+    },
+}
+
+impl Message {
+    fn to_text(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+/// Opens a connection and starts the operation on it.
+async fn connect(
+    websocket: &Arc<dyn WebSocketIO>,
+    request: reqwest::Request,
+    payload: &Value,
+) -> anyhow::Result<Box<dyn WebSocketConnection>> {
+    let mut connection = websocket.connect(request, PROTOCOL).await?;
+
+    connection
+        .send(Message::ConnectionInit { payload: None }.to_text()?)
+        .await?;
+
+    loop {
+        let Some(text) = connection.recv().await? else {
+            anyhow::bail!("The connection was closed before it was acknowledged");
+        };
+
+        match serde_json::from_str(&text)? {
+            Message::ConnectionAck { .. } => break,
+            Message::Ping { .. } => {
+                connection
+                    .send(Message::Pong { payload: None }.to_text()?)
+                    .await?
+            }
+            message => anyhow::bail!("Expected connection_ack, received {:?}", message),
+        }
+    }
+
+    let subscribe = Message::Subscribe { id: OPERATION_ID.to_string(), payload: payload.clone() };
+    connection.send(subscribe.to_text()?).await?;
+
+    Ok(connection)
+}
+
+/// Subscription to an upstream GraphQL server over the `graphql-ws` protocol.
+/// The connection is re-established if it's lost, whereas the subscription
+/// ends once the upstream completes it or closes the connection.
+struct Subscription {
+    websocket: Arc<dyn WebSocketIO>,
+    request: reqwest::Request,
+    payload: Value,
+    field_name: String,
+    connection: Option<Box<dyn WebSocketConnection>>,
+    reconnects: u32,
+    reconnect_delay: Duration,
+    is_done: bool,
+}
+
+impl Subscription {
+    fn new(
+        websocket: Arc<dyn WebSocketIO>,
+        request: reqwest::Request,
+        field_name: String,
+    ) -> Result<Self, Error> {
+        let body = request.body().and_then(|body| body.as_bytes());
+        let payload = body
+            .map(serde_json::from_slice)
+            .transpose()
+            .map_err(|err| Error::Deserialize(err.to_string()))?
+            .unwrap_or_default();
+
+        Ok(Self {
+            websocket,
+            request,
+            payload,
+            field_name,
+            connection: None,
+            reconnects: 0,
+            reconnect_delay: RECONNECT_DELAY,
+            is_done: false,
+        })
+    }
+
+    fn into_stream(self) -> impl Stream<Item = Result<ConstValue, Error>> + Send + 'static {
+        stream::unfold(self, |mut subscription| async move {
+            let event = subscription.next().await?;
+            Some((event, subscription))
+        })
+    }
+
+    /// Waits for the next event of the subscription, reconnecting if needed.
+    /// Returns `None` once the subscription has ended.
+    async fn next(&mut self) -> Option<Result<ConstValue, Error>> {
+        while !self.is_done {
+            let Some(connection) = self.connection.as_mut() else {
+                let request = self
+                    .request
+                    .try_clone()
+                    .ok_or(anyhow::anyhow!("Unable to clone the subscription request"));
+                let connection = match request {
+                    Ok(request) => connect(&self.websocket, request, &self.payload).await,
+                    Err(err) => Err(err),
+                };
+                match connection {
+                    Ok(connection) => self.connection = Some(connection),
+                    Err(err) => {
+                        if let Err(err) = self.reconnect(err).await {
+                            return Some(Err(err));
+                        }
+                    }
+                }
+                continue;
+            };
+
+            let text = match connection.recv().await {
+                Ok(Some(text)) => text,
+                Ok(None) => {
+                    tracing::debug!("Subscription closed by the upstream");
+                    self.is_done = true;
+                    return None;
+                }
+                Err(err) => {
+                    self.connection = None;
+                    if let Err(err) = self.reconnect(err).await {
+                        return Some(Err(err));
+                    }
+                    continue;
+                }
+            };
+
+            match serde_json::from_str(&text) {
+                Ok(Message::Next { id, payload }) if id == OPERATION_ID => {
+                    self.reconnects = 0;
+                    return Some(self.to_value(payload));
+                }
+                Ok(Message::Error { id, payload }) if id == OPERATION_ID => {
+                    self.is_done = true;
+                    return Some(Err(Error::IO(format!(
+                        "Subscription failed in the upstream: {}",
+                        payload
+                    ))));
+                }
+                Ok(Message::Complete { id }) if id == OPERATION_ID => {
+                    self.is_done = true;
+                    return None;
+                }
+                Ok(Message::Ping { .. }) => {
+                    let sent = match (Message::Pong { payload: None }).to_text() {
+                        Ok(pong) => connection.send(pong).await,
+                        Err(err) => Err(err),
+                    };
+                    if let Err(err) = sent {
+                        self.connection = None;
+                        if let Err(err) = self.reconnect(err).await {
+                            return Some(Err(err));
+                        }
+                    }
+                }
+                Ok(message) => tracing::debug!("Ignoring subscription message: {:?}", message),
+                Err(err) => tracing::warn!("Invalid subscription message: {}", err),
+            }
+        }
+
+        None
+    }
+
+    /// Backs off before the next attempt to connect, unless the attempts are
+    /// exhausted in which case the subscription ends with the error.
+    async fn reconnect(&mut self, err: anyhow::Error) -> Result<(), Error> {
+        if self.reconnects >= MAX_RECONNECTS {
+            self.is_done = true;
+            return Err(Error::IO(format!(
+                "Subscription to {} failed after {} reconnects: {}",
+                self.request.url(),
+                self.reconnects,
+                err
+            )));
+        }
+
+        tracing::warn!("Subscription connection lost, reconnecting: {}", err);
+        Delay::new(self.reconnect_delay * 2u32.pow(self.reconnects)).await;
+        self.reconnects += 1;
+
+        Ok(())
+    }
+
+    /// Picks the subscribed field from the execution result of the event.
+    fn to_value(&self, payload: Value) -> Result<ConstValue, Error> {
+        let response: async_graphql::Response =
+            serde_json::from_value(payload).map_err(|err| Error::Deserialize(err.to_string()))?;
+
+        if let Some(error) = response.errors.first() {
+            return Err(Error::IO(error.message.clone()));
+        }
+
+        Ok(response
+            .data
+            .get_key(&self.field_name)
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// Subscribes to the upstream with the GraphQL request, yielding the values of
+/// the field for every event.
+pub fn subscribe(
+    websocket: Arc<dyn WebSocketIO>,
+    request: reqwest::Request,
+    field_name: String,
+) -> Result<impl Stream<Item = Result<ConstValue, Error>> + Send + 'static, Error> {
+    Ok(Subscription::new(websocket, request, field_name)?.into_stream())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    use futures_util::StreamExt;
+    use serde_json::json;
+
+    use super::*;
+
+    /// What the upstream does when the client reads from the connection.
+    #[derive(Clone)]
+    enum Step {
+        Send(Value),
+        Close,
+        Drop,
+    }
+
+    #[derive(Default)]
+    struct TestWebSocket {
+        connections: Mutex<VecDeque<Vec<Step>>>,
+        sent: Arc<Mutex<Vec<Value>>>,
+    }
+
+    impl TestWebSocket {
+        fn new(connections: Vec<Vec<Step>>) -> Self {
+            Self {
+                connections: Mutex::new(connections.into()),
+                ..Default::default()
+            }
+        }
+
+        fn sent(&self) -> Vec<String> {
+            self.sent
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|message| message["type"].as_str().unwrap().to_string())
+                .collect()
+        }
+    }
+
+    struct TestConnection {
+        steps: VecDeque<Step>,
+        sent: Arc<Mutex<Vec<Value>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl WebSocketConnection for TestConnection {
+        async fn send(&mut self, message: String) -> anyhow::Result<()> {
+            let message = serde_json::from_str(&message)?;
+            self.sent.lock().unwrap().push(message);
+            Ok(())
+        }
+
+        async fn recv(&mut self) -> anyhow::Result<Option<String>> {
+            match self.steps.pop_front() {
+                Some(Step::Send(message)) => Ok(Some(message.to_string())),
+                Some(Step::Close) => Ok(None),
+                Some(Step::Drop) | None => anyhow::bail!("connection lost"),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl WebSocketIO for TestWebSocket {
+        async fn connect(
+            &self,
+            request: reqwest::Request,
+            protocol: &str,
+        ) -> anyhow::Result<Box<dyn WebSocketConnection>> {
+            assert_eq!(protocol, PROTOCOL);
+            assert_eq!(request.url().as_str(), "http://upstream.com/graphql");
+
+            let steps = self
+                .connections
+                .lock()
+                .unwrap()
+                .pop_front()
+                .ok_or(anyhow::anyhow!("connection refused"))?;
+
+            Ok(Box::new(TestConnection {
+                steps: steps.into(),
+                sent: self.sent.clone(),
+            }))
+        }
+    }
+
+    fn ack() -> Step {
+        Step::Send(json!({ "type": "connection_ack" }))
+    }
+
+    fn next(value: i32) -> Step {
+        Step::Send(
+            json!({ "type": "next", "id": "1", "payload": { "data": { "counter": value } } }),
+        )
+    }
+
+    fn request() -> reqwest::Request {
+        let mut request = reqwest::Request::new(
+            reqwest::Method::POST,
+            "http://upstream.com/graphql".parse().unwrap(),
+        );
+        request
+            .body_mut()
+            .replace(r#"{ "query": "subscription { counter }" }"#.into());
+        request
+    }
+
+    async fn events(websocket: Arc<TestWebSocket>) -> Vec<Result<ConstValue, String>> {
+        let mut subscription =
+            Subscription::new(websocket, request(), "counter".to_string()).unwrap();
+        subscription.reconnect_delay = Duration::from_millis(1);
+
+        subscription
+            .into_stream()
+            .map(|event| event.map_err(|err| err.to_string()))
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_subscribe() {
+        let websocket = Arc::new(TestWebSocket::new(vec![vec![
+            ack(),
+            Step::Send(json!({ "type": "ping" })),
+            next(1),
+            next(2),
+            Step::Send(json!({ "type": "complete", "id": "1" })),
+        ]]));
+
+        let actual = events(websocket.clone()).await;
+
+        assert_eq!(
+            actual,
+            vec![Ok(ConstValue::from(1)), Ok(ConstValue::from(2))]
+        );
+        assert_eq!(
+            websocket.sent(),
+            vec!["connection_init", "subscribe", "pong"]
+        );
+        assert_eq!(
+            websocket.sent.lock().unwrap()[1]["payload"],
+            json!({ "query": "subscription { counter }" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_error() {
+        let websocket = Arc::new(TestWebSocket::new(vec![vec![
+            ack(),
+            next(1),
+            Step::Send(json!({ "type": "error", "id": "1", "payload": [{ "message": "boom" }] })),
+            next(2),
+        ]]));
+
+        let actual = events(websocket).await;
+
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0], Ok(ConstValue::from(1)));
+        assert!(actual[1].as_ref().unwrap_err().contains("boom"));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_closed_by_upstream() {
+        let websocket = Arc::new(TestWebSocket::new(vec![vec![ack(), next(1), Step::Close]]));
+
+        let actual = events(websocket).await;
+
+        assert_eq!(actual, vec![Ok(ConstValue::from(1))]);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_reconnect() {
+        let websocket = Arc::new(TestWebSocket::new(vec![
+            vec![ack(), next(1), Step::Drop],
+            vec![ack(), next(2), Step::Close],
+        ]));
+
+        let actual = events(websocket.clone()).await;
+
+        assert_eq!(
+            actual,
+            vec![Ok(ConstValue::from(1)), Ok(ConstValue::from(2))]
+        );
+        assert_eq!(
+            websocket.sent(),
+            vec![
+                "connection_init",
+                "subscribe",
+                "connection_init",
+                "subscribe"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_reconnects_exhausted() {
+        let websocket = Arc::new(TestWebSocket::new(vec![vec![ack(), next(1), Step::Drop]]));
+
+        let actual = events(websocket).await;
+
+        assert_eq!(actual.len(), 2);
+        assert!(actual[1]
+            .as_ref()
+            .unwrap_err()
+            .contains("failed after 5 reconnects"));
+    }
+}
@@ -14,7 +14,7 @@ use crate::core::config::group_by::GroupBy;
 use crate::core::config::Batch;
 use crate::core::data_loader::{DataLoader, Loader};
 use crate::core::grpc::request::create_grpc_request;
-use crate::core::http::Response;
+use crate::core::http::{Response, UpstreamBudget};
 use crate::core::json::JsonLike;
 use crate::core::runtime::TargetRuntime;
 
@@ -36,16 +36,21 @@ impl GrpcDataLoader {
         &self,
         keys: &[DataLoaderRequest],
     ) -> anyhow::Result<HashMap<DataLoaderRequest, Response<async_graphql::Value>>> {
-        let results = keys.iter().map(|key| async {
-            let result = match key.to_request() {
-                Ok(req) => execute_grpc_request(&self.runtime, &self.operation, req).await,
-                Err(error) => Err(error),
-            };
-
-            // TODO: do we have to clone keys here? join_all seems like returns the results
-            // in passed order
-            (key.clone(), result)
-        });
+        // the keys are dispatched one by one, so each of them is charged and the
+        // ones that are out of budget are left out
+        let results = keys
+            .iter()
+            .filter(|key| key.budget().charge().is_ok())
+            .map(|key| async {
+                let result = match key.to_request() {
+                    Ok(req) => execute_grpc_request(&self.runtime, &self.operation, req).await,
+                    Err(error) => Err(error),
+                };
+
+                // TODO: do we have to clone keys here? join_all seems like returns the results
+                // in passed order
+                (key.clone(), result)
+            });
 
         let results = join_all(results).await;
 
@@ -63,6 +68,11 @@ impl GrpcDataLoader {
         group_by: &GroupBy,
         keys: &[DataLoaderRequest],
     ) -> Result<HashMap<DataLoaderRequest, Response<async_graphql::Value>>> {
+        // the keys of the requests that are out of budget are left out of the batch
+        let keys = &UpstreamBudget::charge_batch(keys, DataLoaderRequest::budget);
+        if keys.is_empty() {
+            return Ok(HashMap::new());
+        }
         let inputs = keys.iter().map(|key| key.template.body.as_str());
         let (multiple_body, grouped_keys) = self
             .operation
@@ -115,4 +125,8 @@ impl Loader<DataLoaderRequest> for GrpcDataLoader {
             self.load_dedupe_only(keys).await.map_err(Arc::new)
         }
     }
+
+    fn is_batched(&self) -> bool {
+        self.group_by.is_some()
+    }
 }
@@ -5,11 +5,14 @@ use anyhow::Result;
 use tailcall_hasher::TailcallHasher;
 
 use super::request_template::RenderedRequestTemplate;
+use crate::core::http::UpstreamBudget;
 
-#[derive(Debug, Clone, Eq)]
+#[derive(Debug, Clone)]
 pub struct DataLoaderRequest {
     pub template: RenderedRequestTemplate,
     batch_headers: BTreeSet<String>,
+    /// charged by the data loader for the requests that it dispatches.
+    budget: UpstreamBudget,
 }
 
 impl Hash for DataLoaderRequest {
@@ -40,9 +43,19 @@ impl PartialEq for DataLoaderRequest {
     }
 }
 
+impl Eq for DataLoaderRequest {}
+
 impl DataLoaderRequest {
     pub fn new(template: RenderedRequestTemplate, batch_headers: BTreeSet<String>) -> Self {
-        Self { template, batch_headers }
+        Self { template, batch_headers, budget: UpstreamBudget::default() }
+    }
+
+    pub fn with_budget(self, budget: UpstreamBudget) -> Self {
+        Self { budget, ..self }
+    }
+
+    pub fn budget(&self) -> &UpstreamBudget {
+        &self.budget
     }
 
     pub fn to_request(&self) -> Result<reqwest::Request> {
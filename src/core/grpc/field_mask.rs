@@ -0,0 +1,139 @@
+use prost_reflect::{Kind, MessageDescriptor};
+
+use crate::core::ir::SelectionField;
+
+pub const FIELD_MASK: &str = "google.protobuf.FieldMask";
+
+/// Paths of the fields of the message selected by the query, in the JSON
+/// format of the `google.protobuf.FieldMask` eg. `id,author.name`. The nested
+/// selections of a message produce the dotted paths, except for the lists of
+/// messages that can only end a path. The fields that aren't a part of the
+/// message, like the ones resolved by the other resolvers, are skipped.
+pub fn field_mask<'a>(
+    message: &MessageDescriptor,
+    selection: impl Iterator<Item = &'a SelectionField>,
+) -> String {
+    let mut paths = Vec::new();
+    collect_paths(message, selection, "", &mut paths);
+    paths.join(",")
+}
+
+fn collect_paths<'a>(
+    message: &MessageDescriptor,
+    selection: impl Iterator<Item = &'a SelectionField>,
+    prefix: &str,
+    paths: &mut Vec<String>,
+) {
+    for selected in selection {
+        let Some(field) = message.get_field_by_json_name(selected.field_name()) else {
+            continue;
+        };
+        let path = format!("{prefix}{}", field.json_name());
+        if paths.contains(&path) {
+            continue;
+        }
+
+        match field.kind() {
+            Kind::Message(nested) if !field.is_list() && !field.is_map() => {
+                let len = paths.len();
+                collect_paths(
+                    &nested,
+                    selected.selection_set(),
+                    &format!("{path}."),
+                    paths,
+                );
+
+                // none of the selected fields is a part of the nested message
+                if paths.len() == len {
+                    paths.push(path);
+                }
+            }
+            _ => paths.push(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tailcall_fixtures::protobuf;
+
+    use super::*;
+    use crate::core::blueprint::GrpcMethod;
+    use crate::core::grpc::protobuf::ProtobufSet;
+
+    fn output_type(files: &[&str], method: &str) -> MessageDescriptor {
+        let file_descriptor_set = protox::compile(files, [protobuf::SELF]).unwrap();
+        let protobuf_set = ProtobufSet::from_proto_file(file_descriptor_set).unwrap();
+        let method = GrpcMethod::try_from(method).unwrap();
+        let service = protobuf_set.find_service(&method).unwrap();
+
+        service.find_operation(&method).unwrap().output_type
+    }
+
+    fn field(name: &str) -> SelectionField {
+        SelectionField::new(name, vec![])
+    }
+
+    #[test]
+    fn test_field_mask() {
+        let message = output_type(&[protobuf::NESTED_TYPES], "nested.types.Example.Get");
+        let selection = [
+            field("__typename"),
+            field("value"),
+            SelectionField::new(
+                "nestedUsage",
+                vec![
+                    field("movie"),
+                    SelectionField::new("info", vec![field("description")]),
+                ],
+            ),
+            field("description"),
+        ];
+
+        assert_eq!(
+            field_mask(&message, selection.iter()),
+            "value,nestedUsage.movie,nestedUsage.info.description"
+        );
+    }
+
+    #[test]
+    fn test_field_mask_without_nested_fields() {
+        let message = output_type(&[protobuf::NESTED_TYPES], "nested.types.Example.Get");
+        let selection = [
+            SelectionField::new("nestedUsage", vec![field("__typename")]),
+            field("nestedEnum"),
+            field("nestedEnum"),
+        ];
+
+        assert_eq!(
+            field_mask(&message, selection.iter()),
+            "nestedUsage,nestedEnum"
+        );
+    }
+
+    #[test]
+    fn test_field_mask_list() {
+        let message = output_type(&[protobuf::NEWS], "news.NewsService.GetAllNews");
+        let selection = [SelectionField::new(
+            "news",
+            vec![field("id"), field("title")],
+        )];
+
+        assert_eq!(field_mask(&message, selection.iter()), "news");
+    }
+
+    #[test]
+    fn test_field_mask_alias() {
+        let message = output_type(&[protobuf::NESTED_TYPES], "nested.types.Example.Get");
+        let selection = [
+            field("value").with_alias("other"),
+            SelectionField::new("nestedUsage", vec![field("movie").with_alias("film")])
+                .with_alias("usage"),
+        ];
+
+        assert_eq!(
+            field_mask(&message, selection.iter()),
+            "value,nestedUsage.movie"
+        );
+    }
+}
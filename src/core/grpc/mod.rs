@@ -1,8 +1,12 @@
 pub mod data_loader;
 pub mod data_loader_request;
+pub mod field_mask;
 pub mod protobuf;
 pub mod request;
 pub mod request_template;
+pub mod retry;
+pub mod stream;
+pub mod transcoding;
 
 pub use data_loader_request::DataLoaderRequest;
 pub use request_template::RequestTemplate;
@@ -1,19 +1,23 @@
+use std::collections::HashSet;
 use std::fmt::Debug;
 
 use anyhow::{anyhow, bail, Context, Result};
 use async_graphql::Value;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
 use prost::bytes::BufMut;
 use prost::Message;
 use prost_reflect::prost_types::FileDescriptorSet;
 use prost_reflect::{
-    DescriptorPool, DynamicMessage, MessageDescriptor, MethodDescriptor, SerializeOptions,
-    ServiceDescriptor,
+    DescriptorPool, DeserializeOptions, DynamicMessage, EnumDescriptor, Kind, MessageDescriptor,
+    MethodDescriptor, ReflectMessage, SerializeOptions, ServiceDescriptor,
 };
+use serde::{Deserialize, Serialize};
 use serde_json::Deserializer;
 
 use crate::core::blueprint::GrpcMethod;
 
-fn to_message(descriptor: &MessageDescriptor, input: &str) -> Result<DynamicMessage> {
+pub(super) fn to_message(descriptor: &MessageDescriptor, input: &str) -> Result<DynamicMessage> {
     let mut deserializer = Deserializer::from_str(input);
     let message =
         DynamicMessage::deserialize(descriptor.clone(), &mut deserializer).with_context(|| {
@@ -39,6 +43,197 @@ fn message_to_bytes(message: DynamicMessage) -> Result<Vec<u8>> {
     Ok(buf)
 }
 
+const ANY: &str = "google.protobuf.Any";
+
+/// Converts the message to JSON with the serde implementation of
+/// prost-reflect, except for the `google.protobuf.Any` fields. The embedded
+/// message of an `Any` is unpacked with the descriptor of its type url, and an
+/// `Any` of a type that isn't in the descriptor set is converted to
+/// `{ "@type": url, "value": base64 }` instead of failing.
+pub(super) fn to_json(
+    message: &DynamicMessage,
+    options: &SerializeOptions,
+) -> Result<serde_json::Value> {
+    let descriptor = message.descriptor();
+    if descriptor.full_name() == ANY {
+        return any_to_json(message, options);
+    }
+
+    let fields = descriptor
+        .fields()
+        .filter(|field| has_any(&field.kind(), &mut HashSet::new()))
+        .collect::<Vec<_>>();
+    if fields.is_empty() {
+        return Ok(message.serialize_with_options(serde_json::value::Serializer, options)?);
+    }
+
+    // the fields with an `Any` are converted separately, the unset fields of a
+    // oneof are left out as in the rest of the message
+    let mut rest = message.clone();
+    fields.iter().for_each(|field| rest.clear_field(field));
+    let mut json = rest.serialize_with_options(serde_json::value::Serializer, options)?;
+
+    if let serde_json::Value::Object(map) = &mut json {
+        for field in fields.iter().filter(|field| message.has_field(field)) {
+            let value = value_to_json(&message.get_field(field), options)?;
+            map.insert(field.json_name().to_string(), value);
+        }
+    }
+
+    Ok(json)
+}
+
+fn value_to_json(
+    value: &prost_reflect::Value,
+    options: &SerializeOptions,
+) -> Result<serde_json::Value> {
+    use prost_reflect::{MapKey, Value};
+
+    match value {
+        Value::Message(message) => to_json(message, options),
+        Value::List(values) => values
+            .iter()
+            .map(|value| value_to_json(value, options))
+            .collect(),
+        Value::Map(values) => values
+            .iter()
+            .map(|(key, value)| {
+                let key = match key {
+                    MapKey::Bool(key) => key.to_string(),
+                    MapKey::I32(key) => key.to_string(),
+                    MapKey::I64(key) => key.to_string(),
+                    MapKey::U32(key) => key.to_string(),
+                    MapKey::U64(key) => key.to_string(),
+                    MapKey::String(key) => key.clone(),
+                };
+                Ok((key, value_to_json(value, options)?))
+            })
+            .collect(),
+        _ => bail!("Expected a message, a list or a map"),
+    }
+}
+
+fn any_to_json(message: &DynamicMessage, options: &SerializeOptions) -> Result<serde_json::Value> {
+    let type_url = message
+        .get_field_by_name("type_url")
+        .and_then(|value| value.as_str().map(String::from))
+        .unwrap_or_default();
+    let value = message
+        .get_field_by_name("value")
+        .and_then(|value| value.as_bytes().cloned())
+        .unwrap_or_default();
+
+    // type urls are of the form `type.googleapis.com/package.Message`
+    let name = type_url.rsplit('/').next().unwrap_or_default();
+    let Some(descriptor) = message.descriptor().parent_pool().get_message_by_name(name) else {
+        return Ok(
+            serde_json::json!({ "@type": type_url, "value": BASE64_STANDARD.encode(value) }),
+        );
+    };
+
+    let embedded = DynamicMessage::decode(descriptor, value)
+        .with_context(|| format!("Failed to parse the embedded message of type {type_url}"))?;
+    let mut map = match to_json(&embedded, options)? {
+        serde_json::Value::Object(map) => map,
+        // the well-known types that aren't converted to objects eg.
+        // `google.protobuf.Timestamp` are held in `value`
+        value => serde_json::Map::from_iter([("value".to_string(), value)]),
+    };
+    map.insert("@type".to_string(), serde_json::Value::String(type_url));
+
+    Ok(serde_json::Value::Object(map))
+}
+
+/// Decoding of the enum values that have no name in the descriptor, set with
+/// `@grpc(enumAsString)`. The serializer of prost-reflect keeps their numbers,
+/// that aren't valid values of the GraphQL enums.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnumAsString {
+    /// The name of the unknown values, their number as a string if not set.
+    pub unknown: Option<String>,
+}
+
+impl EnumAsString {
+    fn name(&self, value: &mut serde_json::Value, descriptor: &EnumDescriptor) {
+        let Some(number) = value.as_i64() else {
+            return;
+        };
+        let name = descriptor
+            .get_value(number as i32)
+            .map(|value| value.name().to_string())
+            .or_else(|| self.unknown.clone())
+            .unwrap_or_else(|| number.to_string());
+        *value = serde_json::Value::String(name);
+    }
+
+    /// Replaces the numbers of the enum values in the JSON of the message
+    /// with strings.
+    fn message(&self, json: &mut serde_json::Value, descriptor: &MessageDescriptor) {
+        let serde_json::Value::Object(map) = json else {
+            return;
+        };
+
+        if descriptor.full_name() == ANY {
+            // the embedded message is unpacked next to its `@type`
+            let embedded = map
+                .get("@type")
+                .and_then(|type_url| type_url.as_str()?.rsplit('/').next())
+                .and_then(|name| descriptor.parent_pool().get_message_by_name(name));
+            if let Some(embedded) = embedded {
+                self.message(json, &embedded);
+            }
+            return;
+        }
+
+        for field in descriptor.fields() {
+            let Some(value) = map.get_mut(field.json_name()) else {
+                continue;
+            };
+            let kind = field.kind();
+
+            if field.is_map() {
+                let Some(entry) = kind.as_message() else {
+                    continue;
+                };
+                let kind = entry.map_entry_value_field().kind();
+                if let serde_json::Value::Object(values) = value {
+                    values
+                        .values_mut()
+                        .for_each(|value| self.value(value, &kind));
+                }
+            } else if field.is_list() {
+                if let serde_json::Value::Array(values) = value {
+                    values.iter_mut().for_each(|value| self.value(value, &kind));
+                }
+            } else {
+                self.value(value, &kind);
+            }
+        }
+    }
+
+    fn value(&self, value: &mut serde_json::Value, kind: &Kind) {
+        match kind {
+            Kind::Enum(descriptor) => self.name(value, descriptor),
+            Kind::Message(descriptor) => self.message(value, descriptor),
+            _ => {}
+        }
+    }
+}
+
+/// Checks if the values of the kind can hold an `Any`.
+fn has_any(kind: &Kind, visited: &mut HashSet<String>) -> bool {
+    match kind.as_message() {
+        Some(message) if message.full_name() == ANY => true,
+        Some(message) => {
+            visited.insert(message.full_name().to_string())
+                && message
+                    .fields()
+                    .any(|field| has_any(&field.kind(), visited))
+        }
+        None => false,
+    }
+}
+
 pub fn protobuf_value_as_str(value: &prost_reflect::Value) -> String {
     use prost_reflect::Value;
 
@@ -81,9 +276,14 @@ impl ProtobufSet {
     pub fn find_service(&self, grpc_method: &GrpcMethod) -> Result<ProtobufService> {
         let service_name = format!("{}.{}", grpc_method.package, grpc_method.service);
 
+        self.get_service(&service_name)
+    }
+
+    /// Finds the service by its full name eg. `news.NewsService`.
+    pub fn get_service(&self, service_name: &str) -> Result<ProtobufService> {
         let service_descriptor = self
             .descriptor_pool
-            .get_service_by_name(&service_name)
+            .get_service_by_name(service_name)
             .with_context(|| format!("Couldn't find definitions for service {service_name}"))?;
 
         Ok(ProtobufService { service_descriptor })
@@ -99,7 +299,7 @@ impl ProtobufMessage {
     pub fn decode(&self, bytes: &[u8]) -> Result<Value> {
         let message = DynamicMessage::decode(self.message_descriptor.clone(), bytes)?;
 
-        let json = serde_json::to_value(message)?;
+        let json = to_json(&message, &SerializeOptions::default())?;
 
         Ok(async_graphql::Value::from_json(json)?)
     }
@@ -123,6 +323,15 @@ impl ProtobufService {
 
         Ok(ProtobufOperation::new(method, input_type, output_type))
     }
+
+    pub fn operations(&self) -> impl Iterator<Item = ProtobufOperation> + '_ {
+        self.service_descriptor.methods().map(|method| {
+            let input_type = method.input();
+            let output_type = method.output();
+
+            ProtobufOperation::new(method, input_type, output_type)
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -131,6 +340,7 @@ pub struct ProtobufOperation {
     pub input_type: MessageDescriptor,
     pub output_type: MessageDescriptor,
     serialize_options: SerializeOptions,
+    enum_as_string: Option<EnumAsString>,
 }
 
 impl Eq for ProtobufOperation {}
@@ -140,6 +350,59 @@ impl PartialEq for ProtobufOperation {
         self.method.eq(&other.method)
             && self.input_type.eq(&other.input_type)
             && self.output_type.eq(&other.output_type)
+            && self.enum_as_string.eq(&other.enum_as_string)
+    }
+}
+
+/// Serialized form of [ProtobufOperation]. The descriptors can't be serialized
+/// directly, so the operation is stored as the encoded files of its descriptor
+/// pool and the full name of the method, that is looked up again when the
+/// operation is loaded.
+#[derive(Serialize, Deserialize)]
+struct SerializedOperation {
+    descriptors: String,
+    method: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    enum_as_string: Option<EnumAsString>,
+}
+
+impl Serialize for ProtobufOperation {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedOperation {
+            descriptors: BASE64_STANDARD.encode(self.method.parent_pool().encode_to_vec()),
+            method: self.method.full_name().to_string(),
+            enum_as_string: self.enum_as_string.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProtobufOperation {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let operation = SerializedOperation::deserialize(deserializer)?;
+        let descriptors = BASE64_STANDARD
+            .decode(operation.descriptors)
+            .map_err(serde::de::Error::custom)?;
+        let pool =
+            DescriptorPool::decode(descriptors.as_slice()).map_err(serde::de::Error::custom)?;
+
+        let method = operation
+            .method
+            .rsplit_once('.')
+            .and_then(|(service, method)| {
+                pool.get_service_by_name(service)?
+                    .methods()
+                    .find(|m| m.name() == method)
+            })
+            .ok_or_else(|| {
+                serde::de::Error::custom(format!("Couldn't find method {}", operation.method))
+            })?;
+
+        let input_type = method.input();
+        let output_type = method.output();
+
+        Ok(ProtobufOperation::new(method, input_type, output_type)
+            .with_enum_as_string(operation.enum_as_string))
     }
 }
 
@@ -155,8 +418,13 @@ impl ProtobufOperation {
             input_type,
             output_type,
             serialize_options: SerializeOptions::default().skip_default_fields(false),
+            enum_as_string: None,
         }
     }
+
+    pub fn with_enum_as_string(self, enum_as_string: Option<EnumAsString>) -> Self {
+        Self { enum_as_string, ..self }
+    }
     pub fn name(&self) -> &str {
         self.method.name()
     }
@@ -166,11 +434,38 @@ impl ProtobufOperation {
     }
 
     pub fn convert_input(&self, input: &str) -> Result<Vec<u8>> {
+        if self.method.is_client_streaming() {
+            return self.convert_stream_input(input);
+        }
+
         let message = to_message(&self.input_type, input)?;
 
         message_to_bytes(message)
     }
 
+    /// Encodes the input of a client-streaming method, that is a list of the
+    /// messages sent one after the other as length-prefixed messages. The
+    /// messages are encoded before the upload starts, so a message that
+    /// doesn't match the type aborts the whole upload.
+    fn convert_stream_input(&self, input: &str) -> Result<Vec<u8>> {
+        let messages =
+            serde_json::from_str::<Vec<serde_json::Value>>(input).with_context(|| {
+                format!(
+                    "Expected a list of the messages of type {} to stream",
+                    self.input_type.full_name()
+                )
+            })?;
+
+        let mut buf = Vec::new();
+        for (i, message) in messages.iter().enumerate() {
+            let message = to_message(&self.input_type, &message.to_string())
+                .with_context(|| format!("Failed to encode the message {i} of the stream"))?;
+            buf.extend(message_to_bytes(message)?);
+        }
+
+        Ok(buf)
+    }
+
     pub fn convert_multiple_inputs<'a>(
         &self,
         child_inputs: impl Iterator<Item = &'a str>,
@@ -210,7 +505,50 @@ impl ProtobufOperation {
         message_to_bytes(message).map(|result| (result, ids))
     }
 
+    /// Decodes the response of the method, the messages of a server stream
+    /// are decoded to a list of them.
     pub fn convert_output<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<T> {
+        if self.method.is_server_streaming() {
+            let messages = super::stream::split(bytes)?
+                .iter()
+                .map(|message| self.message_to_json(message))
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(serde_json::from_value::<T>(serde_json::Value::Array(
+                messages,
+            ))?);
+        }
+
+        self.convert_message(bytes)
+    }
+
+    /// Decodes a single length-prefixed message of the output type.
+    pub fn convert_message<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<T> {
+        Ok(serde_json::from_value::<T>(self.message_to_json(bytes)?)?)
+    }
+
+    /// Decodes the JSON response of a gRPC-JSON transcoding proxy as a message
+    /// of the output type, so that it's serialized like the gRPC responses.
+    /// The fields that the proxy knows of and the descriptors don't are
+    /// ignored.
+    pub fn convert_json_output<T: serde::de::DeserializeOwned>(&self, bytes: &[u8]) -> Result<T> {
+        let mut deserializer = Deserializer::from_slice(bytes);
+        let message = DynamicMessage::deserialize_with_options(
+            self.output_type.clone(),
+            &mut deserializer,
+            &DeserializeOptions::new().deny_unknown_fields(false),
+        )
+        .with_context(|| {
+            format!(
+                "Failed to parse response for type {}",
+                self.output_type.full_name()
+            )
+        })?;
+        deserializer.end()?;
+
+        Ok(serde_json::from_value::<T>(self.serialize(&message)?)?)
+    }
+
+    fn message_to_json(&self, bytes: &[u8]) -> Result<serde_json::Value> {
         if bytes.len() < 5 {
             bail!("Empty response");
         }
@@ -226,12 +564,26 @@ impl ProtobufOperation {
                 )
             })?;
 
-        let mut serializer = serde_json::Serializer::new(vec![]);
-        message.serialize_with_options(&mut serializer, &self.serialize_options)?;
-        let json = serde_json::from_slice::<T>(serializer.into_inner().as_ref())?;
+        self.serialize(&message)
+    }
+
+    fn serialize(&self, message: &DynamicMessage) -> Result<serde_json::Value> {
+        let mut json = to_json(message, &self.serialize_options)?;
+        if let Some(enum_as_string) = &self.enum_as_string {
+            enum_as_string.message(&mut json, &self.output_type);
+        }
         Ok(json)
     }
 
+    /// Finds the operation of another method in the descriptors of this one,
+    /// it decodes the enums the same way.
+    pub fn find_operation(&self, grpc_method: &GrpcMethod) -> Result<ProtobufOperation> {
+        let set = ProtobufSet { descriptor_pool: self.method.parent_pool().clone() };
+        let operation = set.find_service(grpc_method)?.find_operation(grpc_method)?;
+
+        Ok(operation.with_enum_as_string(self.enum_as_string.clone()))
+    }
+
     pub fn find_message(&self, name: &str) -> Option<ProtobufMessage> {
         let message_descriptor = self.method.parent_pool().get_message_by_name(name)?;
 
@@ -408,6 +760,65 @@ pub mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn enum_as_string() -> Result<()> {
+        let grpc_method = GrpcMethod::try_from("news.NewsService.GetAllNews").unwrap();
+
+        let file = ProtobufSet::from_proto_file(get_proto_file(protobuf::NEWS).await?)?;
+        let service = file.find_service(&grpc_method)?;
+        let operation = service.find_operation(&grpc_method)?;
+
+        // the news with the statuses DRAFT and 7, that isn't a value of the enum
+        let output = b"\0\0\0\0\x0c\x0a\x04\x08\x01\x28\x01\x0a\x04\x08\x02\x28\x07";
+        let statuses = |operation: &ProtobufOperation| -> Result<Vec<serde_json::Value>> {
+            let parsed = operation.convert_output::<serde_json::Value>(output)?;
+            Ok(parsed["news"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|news| news["status"].clone())
+                .collect())
+        };
+
+        assert_eq!(statuses(&operation)?, vec![json!("DRAFT"), json!(7)]);
+
+        let operation = operation.with_enum_as_string(Some(EnumAsString::default()));
+        assert_eq!(statuses(&operation)?, vec![json!("DRAFT"), json!("7")]);
+
+        let operation = operation
+            .with_enum_as_string(Some(EnumAsString { unknown: Some("UNKNOWN".to_string()) }));
+        assert_eq!(
+            statuses(&operation)?,
+            vec![json!("DRAFT"), json!("UNKNOWN")]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn serialize_operation() -> Result<()> {
+        let grpc_method = GrpcMethod::try_from("news.NewsService.GetNews").unwrap();
+
+        let file = ProtobufSet::from_proto_file(get_proto_file(protobuf::NEWS).await?)?;
+        let service = file.find_service(&grpc_method)?;
+        let operation = service.find_operation(&grpc_method)?;
+
+        let serialized = serde_json::to_string(&operation)?;
+        let actual: ProtobufOperation = serde_json::from_str(&serialized)?;
+
+        assert_eq!(actual.method.full_name(), "news.NewsService.GetNews");
+        assert_eq!(
+            actual.output_type.full_name(),
+            operation.output_type.full_name()
+        );
+        assert_eq!(
+            actual.convert_input(r#"{ "id": 1 }"#)?,
+            operation.convert_input(r#"{ "id": 1 }"#)?
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn news_proto_file_with_proto_paths() -> Result<()> {
         let grpc_method = GrpcMethod::try_from("news.NewsService.GetNews").unwrap();
@@ -498,6 +909,70 @@ pub mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn any_proto_file() -> Result<()> {
+        let grpc_method = GrpcMethod::try_from("any.AnyService.GetEvent").unwrap();
+
+        let file = ProtobufSet::from_proto_file(get_proto_file(protobuf::ANY).await?)?;
+        let service = file.find_service(&grpc_method)?;
+        let operation = service.find_operation(&grpc_method)?;
+        let pool = operation.method.parent_pool();
+
+        let message = |name: &str, fields: Vec<(&str, Value)>| {
+            let mut message = DynamicMessage::new(pool.get_message_by_name(name).unwrap());
+            for (name, value) in fields {
+                message.set_field_by_name(name, value);
+            }
+            message
+        };
+        let note = message("any.Note", vec![("text", Value::String("hello".into()))]);
+        let detail = message(
+            "google.protobuf.Any",
+            vec![
+                (
+                    "type_url",
+                    Value::String("type.googleapis.com/any.Note".into()),
+                ),
+                ("value", Value::Bytes(note.encode_to_vec().into())),
+            ],
+        );
+        // the type of the attachment isn't in the descriptor set
+        let attachment = message(
+            "google.protobuf.Any",
+            vec![
+                (
+                    "type_url",
+                    Value::String("type.googleapis.com/any.Unknown".into()),
+                ),
+                ("value", Value::Bytes(b"\x08\x01".to_vec().into())),
+            ],
+        );
+        let event = message(
+            "any.Event",
+            vec![
+                ("id", Value::String("1".into())),
+                ("detail", Value::Message(detail)),
+                ("attachments", Value::List(vec![Value::Message(attachment)])),
+                ("note", Value::Message(note)),
+            ],
+        );
+
+        let output = message_to_bytes(event)?;
+        let parsed = operation.convert_output::<serde_json::Value>(&output)?;
+
+        assert_eq!(
+            parsed,
+            json!({
+                "id": "1",
+                "detail": { "@type": "type.googleapis.com/any.Note", "text": "hello" },
+                "attachments": [{ "@type": "type.googleapis.com/any.Unknown", "value": "CAE=" }],
+                "note": { "text": "hello" }
+            })
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn news_proto_file_multiple_messages() -> Result<()> {
         let grpc_method = GrpcMethod::try_from("news.NewsService.GetMultipleNews").unwrap();
@@ -701,4 +1176,38 @@ pub mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn upload_proto_file() -> Result<()> {
+        let grpc_method = GrpcMethod::try_from("upload.Uploader.Ingest").unwrap();
+        let file = ProtobufSet::from_proto_file(get_proto_file(protobuf::UPLOAD).await?)?;
+        let service = file.find_service(&grpc_method)?;
+        let operation = service.find_operation(&grpc_method)?;
+
+        let input =
+            operation.convert_input(r#"[{ "value": 1 }, { "value": 2 }, { "value": 3 }]"#)?;
+
+        assert_eq!(
+            input,
+            b"\0\0\0\0\x02\x08\x01\0\0\0\0\x02\x08\x02\0\0\0\0\x02\x08\x03"
+        );
+        assert_eq!(operation.convert_input("[]")?, b"");
+
+        // a message that doesn't match the type aborts the upload
+        let error = operation
+            .convert_input(r#"[{ "value": 1 }, { "unknown": 2 }]"#)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to encode the message 1 of the stream"
+        );
+
+        let error = operation.convert_input(r#"{ "value": 1 }"#).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Expected a list of the messages of type upload.Record to stream"
+        );
+
+        Ok(())
+    }
 }
@@ -55,7 +55,7 @@ mod tests {
 
     use crate::core::blueprint::GrpcMethod;
     use crate::core::grpc::protobuf::{ProtobufOperation, ProtobufSet};
-    use crate::core::grpc::request::execute_grpc_request;
+    use crate::core::grpc::request::{create_grpc_request, execute_grpc_request};
     use crate::core::http::Response;
     use crate::core::ir::Error;
     use crate::core::runtime::TargetRuntime;
@@ -200,4 +200,86 @@ mod tests {
 
         Ok(())
     }
+
+    /// Sums the values of the streamed records, rejecting the negative ones
+    struct UploadHttp {
+        record: prost_reflect::MessageDescriptor,
+    }
+
+    #[async_trait]
+    impl HttpIO for UploadHttp {
+        async fn execute(&self, request: Request) -> Result<Response<Bytes>> {
+            let mut body = request.body().and_then(|body| body.as_bytes()).unwrap();
+            let (mut count, mut total) = (0u8, 0i64);
+            while !body.is_empty() {
+                let len = u32::from_be_bytes(body[1..5].try_into()?) as usize;
+                let record =
+                    prost_reflect::DynamicMessage::decode(self.record.clone(), &body[5..5 + len])?;
+                let value = record.get_field_by_name("value").unwrap().as_i32().unwrap();
+                if value < 0 {
+                    let mut headers = HeaderMap::new();
+                    Status::invalid_argument("negative value").add_header(&mut headers)?;
+                    return Ok(Response { status: StatusCode::OK, headers, body: Bytes::new() });
+                }
+
+                count += 1;
+                total += value as i64;
+                body = &body[5 + len..];
+            }
+
+            Ok(Response {
+                status: StatusCode::OK,
+                headers: HeaderMap::new(),
+                body: Bytes::from(vec![0, 0, 0, 0, 4, 0x08, count, 0x10, total as u8]),
+            })
+        }
+    }
+
+    async fn upload(input: &str) -> Result<Response<async_graphql::Value>> {
+        let file_descriptor_set = protox::compile([protobuf::UPLOAD], [protobuf::SELF])?;
+        let grpc_method = GrpcMethod::try_from("upload.Uploader.Ingest").unwrap();
+        let file = ProtobufSet::from_proto_file(file_descriptor_set)?;
+        let operation = file
+            .find_service(&grpc_method)?
+            .find_operation(&grpc_method)?;
+
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.http2_only = Arc::new(UploadHttp { record: operation.input_type.clone() });
+
+        let request = create_grpc_request(
+            "http://example.com".parse().unwrap(),
+            HeaderMap::new(),
+            operation.convert_input(input)?,
+        );
+        execute_grpc_request(&runtime, &operation, request).await
+    }
+
+    #[tokio::test]
+    async fn test_grpc_client_streaming() -> Result<()> {
+        let response = upload(r#"[{"value": 1}, {"value": 2}, {"value": 3}]"#).await?;
+
+        assert_eq!(
+            serde_json::to_value(response.body)?,
+            json!({ "count": 3, "total": 6 })
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grpc_client_streaming_error() -> Result<()> {
+        let error = upload(r#"[{"value": 1}, {"value": -2}, {"value": 3}]"#)
+            .await
+            .unwrap_err();
+
+        match error.downcast_ref::<Error>() {
+            Some(Error::GRPC { grpc_code, grpc_status_message, .. }) => {
+                assert_eq!(*grpc_code, Code::InvalidArgument as i32);
+                assert_eq!(grpc_status_message, "negative value");
+            }
+            _ => panic!("Expected GRPCError"),
+        }
+
+        Ok(())
+    }
 }
@@ -1,32 +1,94 @@
+use std::collections::BTreeMap;
 use std::hash::{Hash, Hasher};
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use derive_setters::Setters;
 use http::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
-use tailcall_hasher::TailcallHasher;
+use serde::{Deserialize, Serialize};
 use url::Url;
 
+use super::field_mask::field_mask;
 use super::request::create_grpc_request;
-use crate::core::config::GraphQLOperationType;
+use super::retry::Retry;
+use super::transcoding::Transcoding;
+use crate::core::blueprint::GrpcMethod;
+use crate::core::config::{GraphQLOperationType, Priority};
 use crate::core::grpc::protobuf::ProtobufOperation;
 use crate::core::has_headers::HasHeaders;
 use crate::core::helpers::headers::MustacheHeaders;
-use crate::core::ir::model::{CacheKey, IoId};
+use crate::core::ir::model::{CacheKey, IoId, IoIdHasher};
+use crate::core::ir::SelectionField;
 use crate::core::mustache::Mustache;
 use crate::core::path::PathString;
 
 static GRPC_MIME_TYPE: HeaderValue = HeaderValue::from_static("application/grpc");
 
-#[derive(Setters, Debug, Clone)]
+#[derive(Setters, Debug, Clone, Serialize, Deserialize)]
 pub struct RequestTemplate {
     pub url: Mustache,
+    #[serde(with = "crate::core::helpers::serde::mustache_headers")]
     pub headers: MustacheHeaders,
     pub body: Option<RequestBody>,
     pub operation: ProtobufOperation,
     pub operation_type: GraphQLOperationType,
+    /// Name of the `google.protobuf.FieldMask` field of the request that is set
+    /// to the selection of the field.
+    pub field_mask: Option<String>,
+    /// The method rendered from the request, `operation` is then only used
+    /// for its descriptors and `url` is the url of the upstream.
+    pub dynamic_method: Option<DynamicMethod>,
+    /// The upstreams the request is routed to in place of `url`.
+    pub routes: Option<Routes>,
+    /// Admits the requests waiting on a full bulkhead in the order of their
+    /// priority.
+    pub priority: Priority,
+    /// Retries the calls to the unavailable upstream within a deadline.
+    pub retry: Option<Retry>,
+    /// Calls the method on its HTTP mapping instead, `url` is then the base
+    /// url of the mapping.
+    pub transcoding: Option<Transcoding>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Setters)]
+/// The method of a `@grpc` whose name is rendered at request time, eg. from an
+/// argument of the field. Only the methods of the allowlist can be called.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DynamicMethod {
+    pub name: Mustache,
+    /// The full names of the methods eg. `news.NewsService.GetNews`, or of the
+    /// services whose methods can all be called eg. `news.NewsService`.
+    pub allow: Vec<String>,
+}
+
+impl DynamicMethod {
+    fn is_allowed(&self, method: &str) -> bool {
+        self.allow.iter().any(|allowed| {
+            method == allowed
+                || method
+                    .strip_prefix(allowed.as_str())
+                    .and_then(|name| name.strip_prefix('.'))
+                    .is_some_and(|name| !name.is_empty() && !name.contains('.'))
+        })
+    }
+}
+
+/// The upstreams that the requests are routed to at request time, eg. by the
+/// region of the request. The requests that render the name of none of them
+/// are sent to the `url` of the template.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Routes {
+    /// Renders the name of the upstream of the request.
+    pub by: Mustache,
+    /// The urls of the upstreams by their names.
+    pub urls: BTreeMap<String, Mustache>,
+}
+
+impl Routes {
+    fn select<C: PathString>(&self, ctx: &C) -> Option<&Mustache> {
+        self.urls.get(&self.by.render(ctx))
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Setters, Serialize, Deserialize)]
 pub struct RequestBody {
     pub mustache: Option<Mustache>,
     pub value: String,
@@ -58,12 +120,44 @@ impl Hash for RenderedRequestTemplate {
 }
 
 impl RequestTemplate {
-    fn create_url<C: PathString>(&self, ctx: &C) -> Result<Url> {
-        let url = url::Url::parse(self.url.render(ctx).as_str())?;
+    fn create_url<C: PathString>(&self, ctx: &C, operation: &ProtobufOperation) -> Result<Url> {
+        let url = self
+            .routes
+            .as_ref()
+            .and_then(|routes| routes.select(ctx))
+            .unwrap_or(&self.url);
+        let mut url = url.render(ctx);
+        if self.dynamic_method.is_some() {
+            let service = operation.method.parent_service().full_name();
+            url = format!(
+                "{}/{}/{}",
+                url.trim_end_matches('/'),
+                service,
+                operation.name()
+            );
+        }
+        let url = url::Url::parse(url.as_str())?;
 
         Ok(url)
     }
 
+    /// The operation of the called method, that is looked up by the rendered
+    /// name for the dynamic methods.
+    fn create_operation<C: PathString>(&self, ctx: &C) -> Result<ProtobufOperation> {
+        let Some(dynamic_method) = &self.dynamic_method else {
+            return Ok(self.operation.clone());
+        };
+
+        let name = dynamic_method.name.render(ctx);
+        if !dynamic_method.is_allowed(&name) {
+            bail!("The gRPC method `{name}` isn't allowed");
+        }
+        let method = GrpcMethod::try_from(name.as_str())
+            .map_err(|_| anyhow!("`{name}` isn't a valid gRPC method"))?;
+
+        self.operation.find_operation(&method)
+    }
+
     fn create_headers<C: PathString>(&self, ctx: &C) -> HeaderMap {
         let mut header_map = HeaderMap::new();
 
@@ -79,10 +173,31 @@ impl RequestTemplate {
     }
 
     pub fn render<C: PathString + HasHeaders>(&self, ctx: &C) -> Result<RenderedRequestTemplate> {
-        let url = self.create_url(ctx)?;
+        let operation = self.create_operation(ctx)?;
+        let url = self.create_url(ctx, &operation)?;
         let headers = self.render_headers(ctx);
         let body = self.render_body(ctx);
-        Ok(RenderedRequestTemplate { url, headers, body, operation: self.operation.clone() })
+        Ok(RenderedRequestTemplate { url, headers, body, operation })
+    }
+
+    /// Renders the request with the paths of the selected fields in the field
+    /// mask, the selection is only required when the field mask is set.
+    pub fn render_with_selection<C: PathString + HasHeaders>(
+        &self,
+        ctx: &C,
+        selection: Option<&SelectionField>,
+    ) -> Result<RenderedRequestTemplate> {
+        let mut rendered = self.render(ctx)?;
+        if let (Some(name), Some(selection)) = (&self.field_mask, selection) {
+            let mut body: serde_json::Value = serde_json::from_str(&rendered.body)?;
+            if let Some(body) = body.as_object_mut() {
+                let mask = field_mask(&rendered.operation.output_type, selection.selection_set());
+                body.insert(name.clone(), mask.into());
+            }
+            rendered.body = body.to_string();
+        }
+
+        Ok(rendered)
     }
 
     fn render_body<C: PathString + HasHeaders>(&self, ctx: &C) -> String {
@@ -108,6 +223,12 @@ impl RequestTemplate {
 }
 
 impl RenderedRequestTemplate {
+    pub fn io_id(&self) -> IoId {
+        let mut hasher = IoIdHasher::default();
+        self.hash(&mut hasher);
+        hasher.finish_id()
+    }
+
     pub fn to_request(&self) -> Result<reqwest::Request> {
         Ok(create_grpc_request(
             self.url.clone(),
@@ -119,10 +240,7 @@ impl RenderedRequestTemplate {
 
 impl<Ctx: PathString + HasHeaders> CacheKey<Ctx> for RequestTemplate {
     fn cache_key(&self, ctx: &Ctx) -> Option<IoId> {
-        let mut hasher = TailcallHasher::default();
-        let rendered_req = self.render(ctx).unwrap();
-        rendered_req.hash(&mut hasher);
-        Some(IoId::new(hasher.finish()))
+        self.render(ctx).ok().map(|rendered| rendered.io_id())
     }
 }
 
@@ -137,11 +255,11 @@ mod tests {
     use pretty_assertions::assert_eq;
     use tailcall_fixtures::protobuf;
 
-    use super::{RequestBody, RequestTemplate};
+    use super::{DynamicMethod, RequestBody, RequestTemplate, Routes};
     use crate::core::blueprint::GrpcMethod;
     use crate::core::config::reader::ConfigReader;
     use crate::core::config::{
-        Config, Field, GraphQLOperationType, Grpc, Link, LinkType, Resolver, Type,
+        Config, Field, GraphQLOperationType, Grpc, Link, LinkType, Priority, Resolver, Type,
     };
     use crate::core::grpc::protobuf::{ProtobufOperation, ProtobufSet};
     use crate::core::ir::model::CacheKey;
@@ -227,6 +345,12 @@ mod tests {
             operation: get_protobuf_op().await,
             body: None,
             operation_type: GraphQLOperationType::Query,
+            field_mask: None,
+            dynamic_method: None,
+            routes: None,
+            priority: Priority::default(),
+            retry: None,
+            transcoding: None,
         };
         let ctx = Context::default();
         let rendered = tmpl.render(&ctx).unwrap();
@@ -264,6 +388,12 @@ mod tests {
                 value: Default::default(),
             }),
             operation_type: GraphQLOperationType::Query,
+            field_mask: None,
+            dynamic_method: None,
+            routes: None,
+            priority: Priority::default(),
+            retry: None,
+            transcoding: None,
         };
         let ctx = Context::default();
         let rendered = tmpl.render(&ctx).unwrap();
@@ -284,6 +414,12 @@ mod tests {
                 value: Default::default(),
             }),
             operation_type: GraphQLOperationType::Query,
+            field_mask: None,
+            dynamic_method: None,
+            routes: None,
+            priority: Priority::default(),
+            retry: None,
+            transcoding: None,
         }
     }
 
@@ -310,4 +446,137 @@ mod tests {
 
         assert_eq!(arr.len(), tmpl_set.len());
     }
+
+    async fn dynamic_request_template(allow: &[&str]) -> RequestTemplate {
+        let file = ProtobufSet::from_proto_file(
+            crate::core::grpc::protobuf::tests::get_proto_file(protobuf::NEWS)
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        let method = GrpcMethod::try_from("news.NewsService.GetAllNews").unwrap();
+        let operation = file
+            .find_service(&method)
+            .unwrap()
+            .find_operation(&method)
+            .unwrap();
+
+        RequestTemplate {
+            url: Mustache::parse("http://localhost:50051/"),
+            headers: vec![],
+            operation,
+            body: Some(RequestBody {
+                mustache: Some(Mustache::parse(r#"{ "id": 1 }"#)),
+                value: Default::default(),
+            }),
+            operation_type: GraphQLOperationType::Query,
+            field_mask: None,
+            dynamic_method: Some(DynamicMethod {
+                name: Mustache::parse("{{.args.method}}"),
+                allow: allow.iter().map(|allowed| allowed.to_string()).collect(),
+            }),
+            routes: None,
+            priority: Priority::default(),
+            retry: None,
+            transcoding: None,
+        }
+    }
+
+    fn method_ctx(method: &str) -> Context {
+        Context::default().value(serde_json::json!({ "args": { "method": method } }))
+    }
+
+    #[tokio::test]
+    async fn test_dynamic_method() {
+        let tmpl = dynamic_request_template(&["news.NewsService.GetNews"]).await;
+        let rendered = tmpl
+            .render(&method_ctx("news.NewsService.GetNews"))
+            .unwrap();
+
+        assert_eq!(
+            rendered.url.as_str(),
+            "http://localhost:50051/news.NewsService/GetNews"
+        );
+        assert_eq!(rendered.operation.name(), "GetNews");
+        assert_eq!(rendered.operation.input_type.full_name(), "news.NewsId");
+
+        let req = rendered.to_request().unwrap();
+        if let Some(body) = req.body() {
+            assert_eq!(body.as_bytes(), Some(b"\0\0\0\0\x02\x08\x01".as_ref()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dynamic_method_not_allowed() {
+        let tmpl = dynamic_request_template(&["news.NewsService.GetNews"]).await;
+        let error = tmpl
+            .render(&method_ctx("news.NewsService.DeleteNews"))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The gRPC method `news.NewsService.DeleteNews` isn't allowed"
+        );
+
+        // a service allows its own methods only
+        let tmpl = dynamic_request_template(&["news.NewsService"]).await;
+        assert!(tmpl
+            .render(&method_ctx("news.NewsService.DeleteNews"))
+            .is_ok());
+        assert!(tmpl
+            .render(&method_ctx("news.NewsServiceV2.GetNews"))
+            .is_err());
+        assert!(tmpl.render(&method_ctx("news.NewsService")).is_err());
+        assert!(tmpl.cache_key(&method_ctx("news.Other.GetNews")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_routes() {
+        let tmpl = RequestTemplate {
+            url: Mustache::parse("http://localhost:50051/greetings.Greeter/SayHello"),
+            headers: vec![],
+            operation: get_protobuf_op().await,
+            body: None,
+            operation_type: GraphQLOperationType::Query,
+            field_mask: None,
+            dynamic_method: None,
+            routes: Some(Routes {
+                by: Mustache::parse("{{.headers.region}}"),
+                urls: [("eu", "http://eu.localhost:50051/greetings.Greeter/SayHello")]
+                    .into_iter()
+                    .map(|(name, url)| (name.to_string(), Mustache::parse(url)))
+                    .collect(),
+            }),
+            priority: Priority::default(),
+            retry: None,
+            transcoding: None,
+        };
+        let region_ctx = |region: serde_json::Value| {
+            Context::default().value(serde_json::json!({ "headers": { "region": region } }))
+        };
+
+        let rendered = tmpl.render(&region_ctx("eu".into())).unwrap();
+        assert_eq!(
+            rendered.url.as_str(),
+            "http://eu.localhost:50051/greetings.Greeter/SayHello"
+        );
+
+        // the unknown and the absent regions fall back to the url
+        for ctx in [region_ctx("us".into()), Context::default()] {
+            let rendered = tmpl.render(&ctx).unwrap();
+            assert_eq!(
+                rendered.url.as_str(),
+                "http://localhost:50051/greetings.Greeter/SayHello"
+            );
+        }
+
+        // the routed requests are cached apart
+        assert_ne!(
+            tmpl.cache_key(&region_ctx("eu".into())),
+            tmpl.cache_key(&region_ctx("us".into()))
+        );
+        assert_eq!(
+            tmpl.cache_key(&region_ctx("us".into())),
+            tmpl.cache_key(&Context::default())
+        );
+    }
 }
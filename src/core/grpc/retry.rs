@@ -0,0 +1,192 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use async_graphql_value::ConstValue;
+use serde::{Deserialize, Serialize};
+
+use crate::core::ir::Error;
+
+/// Retries of the failed gRPC calls, configured with `@grpc(retry)`. All the
+/// attempts of a call share the `deadline`, so a retry is only attempted if
+/// it's expected to complete before the deadline: the median latency of the
+/// previous attempts has to fit in the remaining time, otherwise the last
+/// error is returned instead of a doomed `DEADLINE_EXCEEDED`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Retry {
+    /// The attempts of a call, the first one included.
+    pub max_attempts: usize,
+    pub deadline: Duration,
+}
+
+impl Retry {
+    /// Runs the attempts until one succeeds or fails with an error that isn't
+    /// retried. Only the unavailable upstreams are retried, eg. the failed
+    /// connections or the `UNAVAILABLE` status.
+    pub async fn run<T, F, Fut>(&self, mut attempt: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let started_at = Instant::now();
+        let mut latencies = Vec::with_capacity(self.max_attempts);
+
+        loop {
+            let remaining = self.deadline.saturating_sub(started_at.elapsed());
+            let attempted_at = Instant::now();
+            let Ok(result) = tokio::time::timeout(remaining, attempt()).await else {
+                return Err(deadline_exceeded(self.deadline));
+            };
+            latencies.push(attempted_at.elapsed());
+
+            let error = match result {
+                Err(error) if is_retryable(&error) => error,
+                result => return result,
+            };
+            let remaining = self.deadline.saturating_sub(started_at.elapsed());
+            if latencies.len() >= self.max_attempts || remaining < median(&latencies) {
+                return Err(error);
+            }
+        }
+    }
+}
+
+fn is_retryable(error: &Error) -> bool {
+    match error {
+        Error::IO(_) => true,
+        Error::GRPC { grpc_code, .. } => {
+            tonic::Code::from_i32(*grpc_code) == tonic::Code::Unavailable
+        }
+        _ => false,
+    }
+}
+
+/// The lower median, it's the only latency after the first attempt.
+fn median(latencies: &[Duration]) -> Duration {
+    let mut latencies = latencies.to_vec();
+    latencies.sort();
+
+    latencies
+        .get(latencies.len().saturating_sub(1) / 2)
+        .copied()
+        .unwrap_or_default()
+}
+
+fn deadline_exceeded(deadline: Duration) -> Error {
+    Error::GRPC {
+        grpc_code: tonic::Code::DeadlineExceeded as i32,
+        grpc_description: tonic::Code::DeadlineExceeded.description().to_string(),
+        grpc_status_message: format!("Deadline of {}ms exceeded", deadline.as_millis()),
+        grpc_status_details: ConstValue::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    fn unavailable() -> Error {
+        Error::GRPC {
+            grpc_code: tonic::Code::Unavailable as i32,
+            grpc_description: tonic::Code::Unavailable.description().to_string(),
+            grpc_status_message: "try again".to_string(),
+            grpc_status_details: ConstValue::Null,
+        }
+    }
+
+    fn code(error: &Error) -> Option<tonic::Code> {
+        match error {
+            Error::GRPC { grpc_code, .. } => Some(tonic::Code::from_i32(*grpc_code)),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_until_success() {
+        let retry = Retry { max_attempts: 3, deadline: Duration::from_secs(5) };
+        let attempts = AtomicUsize::new(0);
+
+        let actual = retry
+            .run(|| async {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(unavailable()),
+                    _ => Ok(1),
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(actual, 1);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_max_attempts() {
+        let retry = Retry { max_attempts: 3, deadline: Duration::from_secs(5) };
+        let attempts = AtomicUsize::new(0);
+
+        let actual = retry
+            .run(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err::<(), _>(unavailable())
+            })
+            .await
+            .unwrap_err();
+
+        assert_eq!(code(&actual), Some(tonic::Code::Unavailable));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_not_retryable() {
+        let retry = Retry { max_attempts: 3, deadline: Duration::from_secs(5) };
+        let attempts = AtomicUsize::new(0);
+
+        let actual = retry
+            .run(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err::<(), _>(Error::HTTP { status: 400, message: "bad request".to_string() })
+            })
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual, Error::HTTP { status: 400, .. }));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_deadline_budget() {
+        // every attempt takes 100ms, the third one can't complete within 250ms
+        let retry = Retry { max_attempts: 5, deadline: Duration::from_millis(250) };
+        let attempts = AtomicUsize::new(0);
+
+        let actual = retry
+            .run(|| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Err::<(), _>(unavailable())
+            })
+            .await
+            .unwrap_err();
+
+        // the last error is returned instead of DEADLINE_EXCEEDED
+        assert_eq!(code(&actual), Some(tonic::Code::Unavailable));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_deadline_exceeded() {
+        let retry = Retry { max_attempts: 3, deadline: Duration::from_millis(100) };
+
+        let actual = retry
+            .run(|| async {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Ok(())
+            })
+            .await
+            .unwrap_err();
+
+        assert_eq!(code(&actual), Some(tonic::Code::DeadlineExceeded));
+    }
+}
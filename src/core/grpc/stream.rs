@@ -0,0 +1,304 @@
+use anyhow::{bail, Result};
+use async_graphql_value::ConstValue;
+use futures_util::stream::{self, BoxStream};
+use futures_util::StreamExt;
+use http::{HeaderMap, StatusCode};
+use hyper::body::Bytes;
+
+use super::protobuf::ProtobufOperation;
+use super::request::GRPC_STATUS;
+use crate::core::http::{BodyStream, Response, Trailers};
+
+/// Size of the prefix of a message, ie. the compression flag and the length.
+const PREFIX: usize = 5;
+
+/// Decodes the length-prefixed messages of a server stream that is received
+/// in chunks. A chunk can end in the middle of a message, so the incomplete
+/// message is held until the rest of it is received.
+#[derive(Default)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+}
+
+impl Decoder {
+    /// The messages that are completed by the chunk, along with their prefix.
+    pub fn decode(&mut self, chunk: &[u8]) -> Result<Vec<Vec<u8>>> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut messages = vec![];
+        while self.buffer.len() >= PREFIX {
+            if self.buffer[0] != 0 {
+                bail!("Compressed messages are not supported");
+            }
+            let len = u32::from_be_bytes([
+                self.buffer[1],
+                self.buffer[2],
+                self.buffer[3],
+                self.buffer[4],
+            ]) as usize;
+            if self.buffer.len() < PREFIX + len {
+                break;
+            }
+
+            let rest = self.buffer.split_off(PREFIX + len);
+            messages.push(std::mem::replace(&mut self.buffer, rest));
+        }
+
+        Ok(messages)
+    }
+
+    /// Fails when the stream ended in the middle of a message.
+    pub fn finish(self) -> Result<()> {
+        if !self.buffer.is_empty() {
+            bail!("The stream ended in the middle of a message");
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits the body of a response received in full into its messages.
+pub fn split(body: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut decoder = Decoder::default();
+    let messages = decoder.decode(body)?;
+    decoder.finish()?;
+
+    Ok(messages)
+}
+
+/// Decodes the messages of the server stream of the operation as they are
+/// received. The stream ends after the first error, the messages received
+/// before it are kept. A failed status that is sent in the trailers once the
+/// messages are sent ends the stream with its error.
+pub fn decode(
+    operation: ProtobufOperation,
+    response: Response<BodyStream>,
+    trailers: Trailers,
+) -> BoxStream<'static, Result<ConstValue>> {
+    if let Some(err) = status_error(&operation, response.status, &response.headers) {
+        return stream::iter([Err(err)]).boxed();
+    }
+    if !response.status.is_success() {
+        return stream::iter([Err(anyhow::anyhow!("Failed to execute request"))]).boxed();
+    }
+
+    let status = response.status;
+    stream::unfold(
+        Some((response.body, Decoder::default(), operation, trailers)),
+        move |state| async move {
+            let (mut body, mut decoder, operation, trailers) = state?;
+
+            match body.next().await {
+                Some(Ok(chunk)) => {
+                    let mut values = match decoder.decode(&chunk) {
+                        Ok(messages) => messages
+                            .iter()
+                            .map(|message| operation.convert_message::<ConstValue>(message))
+                            .collect(),
+                        Err(err) => vec![Err(err)],
+                    };
+                    match values.iter().position(Result::is_err) {
+                        Some(index) => {
+                            values.truncate(index + 1);
+                            Some((values, None))
+                        }
+                        None => Some((values, Some((body, decoder, operation, trailers)))),
+                    }
+                }
+                Some(Err(err)) => Some((vec![Err(err)], None)),
+                None => {
+                    let err = match decoder.finish() {
+                        Ok(()) => trailers
+                            .await
+                            .and_then(|trailers| status_error(&operation, status, &trailers)),
+                        Err(err) => Some(err),
+                    };
+                    Some((err.map(Err).into_iter().collect(), None))
+                }
+            }
+        },
+    )
+    .flat_map(stream::iter)
+    .boxed()
+}
+
+/// The error of the status in the headers or in the trailers of the
+/// response, when it isn't `0`.
+fn status_error(
+    operation: &ProtobufOperation,
+    status: StatusCode,
+    headers: &HeaderMap,
+) -> Option<anyhow::Error> {
+    let failed = headers.get(GRPC_STATUS).is_some_and(|status| status != "0");
+    failed.then(|| {
+        Response { status, headers: headers.clone(), body: Bytes::default() }
+            .to_grpc_error(operation)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{HeaderName, HeaderValue};
+    use prost::Message;
+    use serde_json::json;
+    use tailcall_fixtures::protobuf;
+
+    use super::*;
+    use crate::core::blueprint::GrpcMethod;
+    use crate::core::grpc::protobuf::{to_message, ProtobufSet};
+
+    async fn operation() -> ProtobufOperation {
+        let file = crate::core::grpc::protobuf::tests::get_proto_file(protobuf::FEED)
+            .await
+            .unwrap();
+        let method = GrpcMethod::try_from("feed.FeedService.ListPosts").unwrap();
+
+        ProtobufSet::from_proto_file(file)
+            .unwrap()
+            .find_service(&method)
+            .unwrap()
+            .find_operation(&method)
+            .unwrap()
+    }
+
+    fn message(operation: &ProtobufOperation, post: serde_json::Value) -> Vec<u8> {
+        let message = to_message(&operation.output_type, &post.to_string()).unwrap();
+        let mut bytes = vec![0];
+        bytes.extend((message.encoded_len() as u32).to_be_bytes());
+        bytes.extend(message.encode_to_vec());
+
+        bytes
+    }
+
+    fn response(chunks: Vec<Result<Vec<u8>>>) -> Response<BodyStream> {
+        Response {
+            status: StatusCode::OK,
+            headers: Default::default(),
+            body: stream::iter(chunks.into_iter().map(|chunk| chunk.map(Bytes::from))).boxed(),
+        }
+    }
+
+    async fn collect(
+        operation: ProtobufOperation,
+        response: Response<BodyStream>,
+        trailers: Option<HeaderMap>,
+    ) -> Vec<Result<serde_json::Value, String>> {
+        decode(operation, response, Box::pin(async { trailers }))
+            .map(|value| {
+                value
+                    .map(|value| value.into_json().unwrap())
+                    .map_err(|err| err.to_string())
+            })
+            .collect()
+            .await
+    }
+
+    #[test]
+    fn test_decoder() {
+        let mut decoder = Decoder::default();
+        let body = [0, 0, 0, 0, 2, 8, 1, 0, 0, 0, 0, 2, 8, 2];
+
+        // the chunks end in the middle of the messages
+        assert_eq!(decoder.decode(&body[..3]).unwrap(), Vec::<Vec<u8>>::new());
+        assert_eq!(
+            decoder.decode(&body[3..9]).unwrap(),
+            vec![body[..7].to_vec()]
+        );
+        assert_eq!(
+            decoder.decode(&body[9..]).unwrap(),
+            vec![body[7..].to_vec()]
+        );
+        assert!(decoder.finish().is_ok());
+
+        assert!(split(&body[..10]).is_err());
+        assert!(split(&[1, 0, 0, 0, 0]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_decode() {
+        let operation = operation().await;
+        let first = message(&operation, json!({"id": 1, "title": "a"}));
+        let second = message(&operation, json!({"id": 2, "title": "b"}));
+        let chunks = [&first[..], &second[..]].concat();
+        let (head, tail) = chunks.split_at(first.len() + 3);
+
+        let actual = collect(
+            operation,
+            response(vec![Ok(head.to_vec()), Ok(tail.to_vec())]),
+            None,
+        )
+        .await;
+
+        assert_eq!(
+            actual,
+            vec![
+                Ok(json!({"id": 1, "title": "a"})),
+                Ok(json!({"id": 2, "title": "b"}))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_decode_error() {
+        let operation = operation().await;
+        let first = message(&operation, json!({"id": 1, "title": "a"}));
+        let second = message(&operation, json!({"id": 2, "title": "b"}));
+
+        // the stream ends after the error, the messages before it are kept
+        let chunks = vec![
+            Ok(first),
+            Err(anyhow::anyhow!("connection reset")),
+            Ok(second),
+        ];
+        let actual = collect(operation.clone(), response(chunks), None).await;
+        assert_eq!(
+            actual,
+            vec![
+                Ok(json!({"id": 1, "title": "a"})),
+                Err("connection reset".to_string())
+            ]
+        );
+
+        let actual = collect(operation, response(vec![Ok(vec![0, 0, 0, 0, 2, 8])]), None).await;
+        assert_eq!(
+            actual,
+            vec![Err(
+                "The stream ended in the middle of a message".to_string()
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_decode_trailers() {
+        let operation = operation().await;
+        let first = message(&operation, json!({"id": 1, "title": "a"}));
+
+        // the failed status of the trailers ends the stream with its error
+        let trailers = HeaderMap::from_iter([
+            (
+                HeaderName::from_static(GRPC_STATUS),
+                HeaderValue::from_static("14"),
+            ),
+            (
+                HeaderName::from_static("grpc-message"),
+                HeaderValue::from_static("unavailable"),
+            ),
+        ]);
+        let actual = collect(
+            operation.clone(),
+            response(vec![Ok(first.clone())]),
+            Some(trailers),
+        )
+        .await;
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0], Ok(json!({"id": 1, "title": "a"})));
+        assert!(actual[1].is_err());
+
+        let trailers = HeaderMap::from_iter([(
+            HeaderName::from_static(GRPC_STATUS),
+            HeaderValue::from_static("0"),
+        )]);
+        let actual = collect(operation, response(vec![Ok(first)]), Some(trailers)).await;
+        assert_eq!(actual, vec![Ok(json!({"id": 1, "title": "a"}))]);
+    }
+}
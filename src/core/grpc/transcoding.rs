@@ -0,0 +1,435 @@
+use anyhow::{anyhow, bail, Result};
+use http::header::{HeaderValue, CONTENT_TYPE};
+use prost_reflect::{DynamicMessage, MessageDescriptor, MethodDescriptor, SerializeOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::protobuf::{protobuf_value_as_str, to_json, to_message};
+use super::request_template::RenderedRequestTemplate;
+
+const HTTP_RULE: &str = "google.api.http";
+
+/// The HTTP mapping of a gRPC method from its `google.api.http` annotation, to
+/// call the method on its REST form exposed by a gRPC-JSON transcoding proxy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transcoding {
+    #[serde(with = "crate::core::helpers::serde::method")]
+    pub method: reqwest::Method,
+    pub path: Vec<Segment>,
+    /// The field of the message that's sent as the body, or `*` for all the
+    /// fields that aren't bound by the path. The other fields are sent as the
+    /// query parameters.
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Segment {
+    Literal(String),
+    /// The path of a field of the message, eg. `book.id` of `{book.id}`.
+    Field(Vec<String>),
+}
+
+impl Transcoding {
+    /// The mapping of the annotation of the method, `None` when the method
+    /// isn't annotated.
+    pub fn from_method(method: &MethodDescriptor) -> Result<Option<Self>> {
+        let Some(rule) = http_rule(method) else {
+            return Ok(None);
+        };
+        let get = |name: &str| {
+            rule.iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let patterns = [
+            ("get", reqwest::Method::GET),
+            ("put", reqwest::Method::PUT),
+            ("post", reqwest::Method::POST),
+            ("delete", reqwest::Method::DELETE),
+            ("patch", reqwest::Method::PATCH),
+        ];
+        let (http_method, template) = patterns
+            .into_iter()
+            .find_map(|(name, http_method)| Some((http_method, get(name)?)))
+            .ok_or_else(|| {
+                anyhow!(
+                    "The `{HTTP_RULE}` annotation of {} has no supported pattern",
+                    method.full_name()
+                )
+            })?;
+        let path = parse_path(template, &method.input())?;
+        let body = get("body").filter(|body| !body.is_empty());
+        if let Some(body) = body.filter(|body| *body != "*") {
+            if method.input().get_field_by_name(body).is_none() {
+                bail!(
+                    "The body `{body}` isn't a field of {}",
+                    method.input().full_name()
+                );
+            }
+        }
+
+        Ok(Some(Self {
+            method: http_method,
+            path,
+            body: body.map(str::to_string),
+        }))
+    }
+
+    /// The request of the rendered call, the fields of the message that are
+    /// bound by the path are left out of the body and of the query.
+    pub fn to_request(&self, rendered: &RenderedRequestTemplate) -> Result<reqwest::Request> {
+        let message = to_message(&rendered.operation.input_type, &rendered.body)?;
+        let options = SerializeOptions::new().use_proto_field_name(true);
+        let mut fields = to_json(&message, &options)?;
+
+        let mut url = rendered.url.clone();
+        {
+            let mut segments = url
+                .path_segments_mut()
+                .map_err(|_| anyhow!("{} can't be the base of a path", rendered.url))?;
+            segments.pop_if_empty();
+            for segment in &self.path {
+                match segment {
+                    Segment::Literal(literal) => {
+                        segments.push(literal);
+                    }
+                    Segment::Field(path) => {
+                        let value = field_value(&message, path).ok_or_else(|| {
+                            anyhow!("The field `{}` of the path isn't set", path.join("."))
+                        })?;
+                        remove_field(&mut fields, path);
+                        segments.push(&value);
+                    }
+                }
+            }
+        }
+
+        let body = match self.body.as_deref() {
+            Some("*") => Some(std::mem::take(&mut fields)),
+            Some(name) => fields
+                .as_object_mut()
+                .and_then(|fields| fields.remove(name)),
+            None => None,
+        };
+
+        let mut query = vec![];
+        to_query(String::new(), &fields, &mut query);
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+
+        let mut request = reqwest::Request::new(self.method.clone(), url);
+        let headers = request.headers_mut();
+        headers.extend(rendered.headers.clone());
+        headers.remove(CONTENT_TYPE);
+        if let Some(body) = body {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            request.body_mut().replace(body.to_string().into());
+        }
+
+        Ok(request)
+    }
+}
+
+/// The top-level string fields of the `HttpRule` of the method, eg. `get` and
+/// `body`. The rule is read from the interpreted options of the method, or
+/// from its uninterpreted ones when the descriptors were parsed without
+/// resolving the custom options.
+fn http_rule(method: &MethodDescriptor) -> Option<Vec<(String, String)>> {
+    if let Some(extension) = method.parent_pool().get_extension_by_name(HTTP_RULE) {
+        let options = method.options();
+        if options.has_extension(&extension) {
+            if let prost_reflect::Value::Message(rule) = options.get_extension(&extension).as_ref()
+            {
+                let fields = rule
+                    .fields()
+                    .filter_map(|(field, value)| {
+                        Some((field.name().to_string(), value.as_str()?.to_string()))
+                    })
+                    .collect();
+                return Some(fields);
+            }
+        }
+    }
+
+    let options = method.method_descriptor_proto().options.as_ref()?;
+    let mut rule = vec![];
+    for option in &options.uninterpreted_option {
+        let names = option
+            .name
+            .iter()
+            .map(|name| name.name_part.trim_matches(['(', ')', '.']))
+            .collect::<Vec<_>>();
+        match names.as_slice() {
+            [HTTP_RULE] => rule.extend(parse_aggregate(option.aggregate_value())),
+            [HTTP_RULE, field] => rule.push((
+                field.to_string(),
+                String::from_utf8_lossy(option.string_value()).into_owned(),
+            )),
+            _ => {}
+        }
+    }
+
+    (!rule.is_empty()).then_some(rule)
+}
+
+/// The top-level string fields of the text format of a message, eg. `get:
+/// "/v1/news/{id}" body: "*"`. The nested messages are skipped.
+fn parse_aggregate(text: &str) -> Vec<(String, String)> {
+    let mut fields = vec![];
+    let mut chars = text.chars().peekable();
+    let mut depth = 0usize;
+    let mut key = None;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '<' => depth += 1,
+            '}' | '>' => depth = depth.saturating_sub(1),
+            '"' | '\'' => {
+                let mut value = String::new();
+                while let Some(next) = chars.next() {
+                    match next {
+                        next if next == c => break,
+                        '\\' => value.extend(chars.next()),
+                        next => value.push(next),
+                    }
+                }
+                if let Some(key) = key.take().filter(|_| depth == 0) {
+                    fields.push((key, value));
+                }
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = String::from(c);
+                while let Some(next) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    ident.push(next);
+                }
+                key = Some(ident);
+            }
+            _ => {}
+        }
+    }
+
+    fields
+}
+
+/// Parses the path template of the rule, eg. `/v1/shelves/{shelf}/books`. A
+/// variable binds a single segment of the path to a field of the request
+/// message.
+fn parse_path(template: &str, input: &MessageDescriptor) -> Result<Vec<Segment>> {
+    template
+        .trim_start_matches('/')
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            let Some(variable) = segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) else {
+                if segment.contains(['{', '}']) {
+                    bail!("The variable of `{segment}` has to be a whole segment of the path");
+                }
+                return Ok(Segment::Literal(segment.to_string()));
+            };
+
+            let (name, pattern) = variable.split_once('=').unwrap_or((variable, "*"));
+            if pattern != "*" {
+                bail!("The variable `{variable}` binds more than a segment of the path");
+            }
+            let path = name.split('.').map(str::to_string).collect::<Vec<_>>();
+            if !has_field(input, &path) {
+                bail!("`{name}` isn't a field of {}", input.full_name());
+            }
+
+            Ok(Segment::Field(path))
+        })
+        .collect()
+}
+
+fn has_field(message: &MessageDescriptor, path: &[String]) -> bool {
+    let Some((name, rest)) = path.split_first() else {
+        return true;
+    };
+    let Some(field) = message.get_field_by_name(name) else {
+        return false;
+    };
+
+    match field.kind() {
+        prost_reflect::Kind::Message(message) => has_field(&message, rest),
+        _ => rest.is_empty(),
+    }
+}
+
+/// The value of the field as a segment of the path, the unset fields have
+/// their default value.
+fn field_value(message: &DynamicMessage, path: &[String]) -> Option<String> {
+    let (name, rest) = path.split_first()?;
+    let value = message.get_field_by_name(name)?;
+
+    match rest {
+        [] => Some(protobuf_value_as_str(&value)),
+        rest => field_value(value.as_message()?, rest),
+    }
+}
+
+fn remove_field(fields: &mut Value, path: &[String]) {
+    match path {
+        [] => {}
+        [name] => {
+            if let Some(fields) = fields.as_object_mut() {
+                fields.remove(name);
+            }
+        }
+        [name, rest @ ..] => {
+            if let Some(fields) = fields.get_mut(name) {
+                remove_field(fields, rest);
+            }
+        }
+    }
+}
+
+/// The query parameters of the fields, the nested fields are named by their
+/// path eg. `book.id` and the repeated fields are repeated.
+fn to_query(name: String, value: &Value, query: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                let name = if name.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{name}.{key}")
+                };
+                to_query(name, value, query);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                to_query(name.clone(), value, query);
+            }
+        }
+        Value::Null => {}
+        Value::String(value) => query.push((name, value.clone())),
+        value => query.push((name, value.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::header::HeaderMap;
+    use tailcall_fixtures::protobuf;
+    use url::Url;
+
+    use super::*;
+    use crate::core::blueprint::GrpcMethod;
+    use crate::core::grpc::protobuf::tests::get_proto_file;
+    use crate::core::grpc::protobuf::{ProtobufOperation, ProtobufSet};
+
+    async fn operation(name: &str) -> ProtobufOperation {
+        let file_descriptor_set = get_proto_file(protobuf::TRANSCODING).await.unwrap();
+        let method = GrpcMethod::try_from(name).unwrap();
+
+        ProtobufSet::from_proto_file(file_descriptor_set)
+            .unwrap()
+            .find_service(&method)
+            .unwrap()
+            .find_operation(&method)
+            .unwrap()
+    }
+
+    fn rendered(operation: ProtobufOperation, body: &str) -> RenderedRequestTemplate {
+        RenderedRequestTemplate {
+            url: Url::parse("http://localhost:8080/").unwrap(),
+            headers: HeaderMap::from_iter([(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/grpc"),
+            )]),
+            body: body.to_string(),
+            operation,
+        }
+    }
+
+    #[test]
+    fn test_parse_aggregate() {
+        let actual = parse_aggregate(
+            r#"get: "/v1/news/{id}" additional_bindings { get: "/v2/news/{id}" } body: "*""#,
+        );
+        assert_eq!(
+            actual,
+            vec![
+                ("get".to_string(), "/v1/news/{id}".to_string()),
+                ("body".to_string(), "*".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_with_path_param() {
+        let operation = operation("transcoding.NewsService.GetNews").await;
+        let transcoding = Transcoding::from_method(&operation.method)
+            .unwrap()
+            .unwrap();
+        assert_eq!(transcoding.method, reqwest::Method::GET);
+        assert_eq!(
+            transcoding.path,
+            vec![
+                Segment::Literal("v1".to_string()),
+                Segment::Literal("news".to_string()),
+                Segment::Field(vec!["news_id".to_string()]),
+            ]
+        );
+
+        // the path param is bound from the field, the others are in the query
+        let request = transcoding
+            .to_request(&rendered(operation, r#"{"newsId": 2, "lang": "en"}"#))
+            .unwrap();
+        assert_eq!(request.method(), reqwest::Method::GET);
+        assert_eq!(
+            request.url().as_str(),
+            "http://localhost:8080/v1/news/2?lang=en"
+        );
+        assert!(request.headers().get(CONTENT_TYPE).is_none());
+        assert!(request.body().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_post_with_body() {
+        let operation = operation("transcoding.NewsService.AddNews").await;
+        let transcoding = Transcoding::from_method(&operation.method)
+            .unwrap()
+            .unwrap();
+
+        let request = transcoding
+            .to_request(&rendered(operation, r#"{"id": 1, "title": "foo"}"#))
+            .unwrap();
+        assert_eq!(request.method(), reqwest::Method::POST);
+        assert_eq!(request.url().as_str(), "http://localhost:8080/v1/news");
+        assert_eq!(
+            request.headers().get(CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        let body: Value =
+            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(body, serde_json::json!({ "id": 1, "title": "foo" }));
+    }
+
+    #[tokio::test]
+    async fn test_convert_json_output() {
+        let operation = operation("transcoding.NewsService.GetNews").await;
+
+        // serialized like the gRPC responses, with the default fields and
+        // without the fields the descriptors don't know of
+        let actual: Value = operation
+            .convert_json_output(br#"{"id": 1, "author": "foo"}"#)
+            .unwrap();
+        assert_eq!(actual, serde_json::json!({ "id": 1, "title": "" }));
+
+        assert!(operation
+            .convert_json_output::<Value>(br#"{"id": "foo"}"#)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_not_annotated() {
+        let operation = operation("transcoding.NewsService.GetAllNews").await;
+        assert!(Transcoding::from_method(&operation.method)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -1,5 +1,6 @@
 pub mod body;
 pub mod gql_type;
 pub mod headers;
+pub mod serde;
 pub mod url;
 pub mod value;
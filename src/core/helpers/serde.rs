@@ -0,0 +1,80 @@
+//! Serde adapters for the HTTP types that are used by the blueprint but don't
+//! implement `Serialize` and `Deserialize` themselves. They are used with
+//! `#[serde(with = "...")]` on the fields.
+
+pub mod method {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        method: &reqwest::Method,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(method.as_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<reqwest::Method, D::Error> {
+        let method = String::deserialize(deserializer)?;
+        reqwest::Method::from_bytes(method.as_bytes()).map_err(serde::de::Error::custom)
+    }
+}
+
+pub mod header_map {
+    use http::header::{HeaderMap, HeaderName, HeaderValue};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(headers: &HeaderMap, serializer: S) -> Result<S::Ok, S::Error> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = value.to_str().map_err(serde::ser::Error::custom)?;
+                Ok((name.as_str(), value))
+            })
+            .collect::<Result<Vec<_>, S::Error>>()?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HeaderMap, D::Error> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in Vec::<(String, String)>::deserialize(deserializer)? {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(serde::de::Error::custom)?;
+            let value = HeaderValue::from_str(&value).map_err(serde::de::Error::custom)?;
+            headers.append(name, value);
+        }
+
+        Ok(headers)
+    }
+}
+
+pub mod mustache_headers {
+    use http::header::HeaderName;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::core::helpers::headers::MustacheHeaders;
+    use crate::core::mustache::Mustache;
+
+    pub fn serialize<S: Serializer>(
+        headers: &MustacheHeaders,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<MustacheHeaders, D::Error> {
+        Vec::<(String, Mustache)>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(name, value)| {
+                let name =
+                    HeaderName::from_bytes(name.as_bytes()).map_err(serde::de::Error::custom)?;
+                Ok((name, value))
+            })
+            .collect()
+    }
+}
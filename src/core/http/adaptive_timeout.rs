@@ -0,0 +1,265 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use opentelemetry::metrics::Counter;
+use opentelemetry::KeyValue;
+
+use crate::core::blueprint;
+use crate::core::ir::Error;
+
+static UPSTREAM_TIMEOUTS: Lazy<Counter<u64>> = Lazy::new(|| {
+    let meter = opentelemetry::global::meter("upstream");
+
+    meter
+        .u64_counter("upstream.timeout")
+        .with_description("Number of the requests that timed out waiting for their upstream")
+        .init()
+});
+
+/// The latencies are counted in buckets whose upper bounds grow exponentially
+/// from 1ms. Each bucket is 10% wider than the previous one, which keeps the
+/// error of the percentiles under 10%, the last bucket holds the latencies
+/// above ~25 minutes.
+const BUCKETS: usize = 150;
+const GROWTH: f64 = 1.1;
+
+/// Histogram of the recent latencies of an upstream, the weight of the
+/// latencies decays exponentially with time.
+struct Latencies {
+    buckets: Vec<f64>,
+    weight: f64,
+    updated_at: Instant,
+}
+
+impl Latencies {
+    fn new(now: Instant) -> Self {
+        Self { buckets: vec![0.0; BUCKETS], weight: 0.0, updated_at: now }
+    }
+
+    fn decay(&mut self, half_life: Duration, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at);
+        let decay = 0.5f64.powf(elapsed.as_secs_f64() / half_life.as_secs_f64());
+
+        self.buckets.iter_mut().for_each(|weight| *weight *= decay);
+        self.weight *= decay;
+        self.updated_at = now;
+    }
+
+    fn record(&mut self, latency: Duration) {
+        let millis = latency.as_secs_f64() * 1000.0;
+        let bucket = if millis <= 1.0 {
+            0
+        } else {
+            (millis.ln() / GROWTH.ln()).ceil() as usize
+        };
+
+        self.buckets[bucket.min(BUCKETS - 1)] += 1.0;
+        self.weight += 1.0;
+    }
+
+    /// Upper bound of the bucket that holds the percentile.
+    fn percentile(&self, percentile: u8) -> Duration {
+        // searching from the slowest bucket is exact for the 100th percentile
+        let limit = self.weight * f64::from(100 - percentile) / 100.0;
+        let mut weight = 0.0;
+        let bucket = (0..BUCKETS)
+            .rev()
+            .find(|bucket| {
+                weight += self.buckets[*bucket];
+                weight > limit
+            })
+            .unwrap_or(0);
+
+        Duration::from_secs_f64(GROWTH.powi(bucket as i32) / 1000.0)
+    }
+}
+
+/// Timeouts of the requests to the upstreams that follow their recent
+/// latencies, configured with `@upstream(adaptiveTimeout)`. The latencies of
+/// each upstream are tracked separately, identified by the origin of its
+/// requests eg. `http://foo.com:8080`.
+pub struct AdaptiveTimeouts {
+    config: blueprint::AdaptiveTimeout,
+    default: Duration,
+    upstreams: Mutex<HashMap<String, Latencies>>,
+}
+
+impl AdaptiveTimeouts {
+    /// The default timeout is used until enough latencies of an upstream are
+    /// recorded, it's also the maximum of the adapted timeouts.
+    pub fn new(config: blueprint::AdaptiveTimeout, default: Duration) -> Self {
+        Self { config, default, upstreams: Mutex::new(HashMap::new()) }
+    }
+
+    /// Timeout of the next request to the upstream.
+    pub fn timeout(&self, origin: &str) -> Duration {
+        let mut upstreams = self.upstreams.lock().unwrap();
+
+        match upstreams.get_mut(origin) {
+            Some(latencies) => self.adapt(latencies, Instant::now()),
+            None => self.default,
+        }
+    }
+
+    pub fn record(&self, origin: &str, latency: Duration) {
+        let now = Instant::now();
+        let mut upstreams = self.upstreams.lock().unwrap();
+        let latencies = upstreams
+            .entry(origin.to_string())
+            .or_insert_with(|| Latencies::new(now));
+
+        latencies.decay(self.config.half_life, now);
+        latencies.record(latency);
+    }
+
+    /// Runs the request to the upstream within its timeout and records the
+    /// latency. The requests that time out are recorded with the timeout as
+    /// their latency, so that the timeout grows when the upstream slows down.
+    pub async fn run<T>(
+        &self,
+        origin: &str,
+        request: impl Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        let timeout = self.timeout(origin);
+        let started_at = Instant::now();
+
+        match tokio::time::timeout(timeout, request).await {
+            Ok(result) => {
+                self.record(origin, started_at.elapsed());
+                result
+            }
+            Err(_) => {
+                self.record(origin, timeout);
+                UPSTREAM_TIMEOUTS.add(1, &[KeyValue::new("origin", origin.to_string())]);
+                Err(Error::IO(format!(
+                    "Timed out after {}ms waiting for {origin}",
+                    timeout.as_millis()
+                )))
+            }
+        }
+    }
+
+    /// Current timeouts of the upstreams that have been called.
+    pub fn timeouts(&self) -> Vec<(String, Duration)> {
+        let now = Instant::now();
+        let mut upstreams = self.upstreams.lock().unwrap();
+
+        upstreams
+            .iter_mut()
+            .map(|(origin, latencies)| (origin.clone(), self.adapt(latencies, now)))
+            .collect()
+    }
+
+    fn adapt(&self, latencies: &mut Latencies, now: Instant) -> Duration {
+        latencies.decay(self.config.half_life, now);
+
+        // the latencies of a cold start, or of an upstream that hasn't been
+        // called for a while, are too few to rely on
+        if latencies.weight.round() < self.config.min_samples as f64 {
+            return self.default;
+        }
+
+        (latencies.percentile(self.config.percentile) * self.config.factor)
+            .max(self.config.min)
+            .min(self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGIN: &str = "http://localhost:8080";
+
+    fn timeouts() -> AdaptiveTimeouts {
+        let config = blueprint::AdaptiveTimeout {
+            percentile: 99,
+            factor: 2,
+            half_life: Duration::from_secs(60),
+            min_samples: 10,
+            min: Duration::from_millis(10),
+        };
+
+        AdaptiveTimeouts::new(config, Duration::from_secs(5))
+    }
+
+    #[test]
+    fn test_cold_start() {
+        let timeouts = timeouts();
+        assert_eq!(timeouts.timeout(ORIGIN), Duration::from_secs(5));
+
+        for _ in 0..9 {
+            timeouts.record(ORIGIN, Duration::from_millis(100));
+        }
+        assert_eq!(timeouts.timeout(ORIGIN), Duration::from_secs(5));
+
+        timeouts.record(ORIGIN, Duration::from_millis(100));
+        let timeout = timeouts.timeout(ORIGIN);
+        assert!(timeout >= Duration::from_millis(200), "{timeout:?}");
+        assert!(timeout <= Duration::from_millis(220), "{timeout:?}");
+    }
+
+    #[test]
+    fn test_percentile() {
+        let timeouts = timeouts();
+        for _ in 0..199 {
+            timeouts.record(ORIGIN, Duration::from_millis(20));
+        }
+        timeouts.record(ORIGIN, Duration::from_millis(1000));
+
+        // the slowest 1% of the latencies are ignored
+        let timeout = timeouts.timeout(ORIGIN);
+        assert!(timeout >= Duration::from_millis(40), "{timeout:?}");
+        assert!(timeout <= Duration::from_millis(44), "{timeout:?}");
+
+        // the timeout never exceeds the default
+        for _ in 0..1000 {
+            timeouts.record(ORIGIN, Duration::from_secs(10));
+        }
+        assert_eq!(timeouts.timeout(ORIGIN), Duration::from_secs(5));
+        assert_eq!(
+            timeouts.timeout("http://localhost:8081"),
+            Duration::from_secs(5)
+        );
+    }
+
+    #[test]
+    fn test_decay() {
+        let timeouts = timeouts();
+        let now = Instant::now();
+        let mut latencies = Latencies::new(now);
+        for _ in 0..100 {
+            latencies.record(Duration::from_secs(1));
+        }
+
+        // the old latencies are outweighed by the recent ones
+        latencies.decay(Duration::from_secs(60), now + Duration::from_secs(600));
+        for _ in 0..10 {
+            latencies.record(Duration::from_millis(50));
+        }
+
+        let timeout = timeouts.adapt(&mut latencies, now + Duration::from_secs(600));
+        assert!(timeout <= Duration::from_millis(120), "{timeout:?}");
+    }
+
+    #[tokio::test]
+    async fn test_run_timeout() {
+        let timeouts = timeouts();
+        for _ in 0..10 {
+            timeouts.record(ORIGIN, Duration::from_millis(5));
+        }
+
+        let slow = async {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Ok(())
+        };
+        let result = timeouts.run(ORIGIN, slow).await;
+        assert!(matches!(result, Err(Error::IO(_))));
+
+        let result = timeouts.run(ORIGIN, async { Ok(1) }).await;
+        assert_eq!(result.unwrap(), 1);
+    }
+}
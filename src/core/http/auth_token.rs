@@ -0,0 +1,354 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use http::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use hyper::body::Bytes;
+use serde::Deserialize;
+use url::form_urlencoded;
+
+use super::{BodyStream, Response, Trailers};
+use crate::core::blueprint::TokenProvider;
+use crate::core::runtime::TargetRuntime;
+use crate::core::HttpIO;
+
+/// Tokens that are issued without `expires_in` are fetched again after an
+/// hour.
+const DEFAULT_EXPIRES_IN: u64 = 3600;
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+#[derive(Clone)]
+struct Token {
+    value: HeaderValue,
+    expires_at: Instant,
+    refresh_at: Instant,
+}
+
+/// Fetches the access tokens of an upstream with the OAuth2 client
+/// credentials grant and caches them until they are due for refresh.
+pub struct AuthTokenProvider {
+    config: TokenProvider,
+    token: Mutex<Option<Token>>,
+    // held while a token is fetched, so that only one fetch is in flight
+    refresh: tokio::sync::Mutex<()>,
+}
+
+impl AuthTokenProvider {
+    pub fn new(config: TokenProvider) -> Self {
+        Self {
+            config,
+            token: Mutex::new(None),
+            refresh: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Returns the `Authorization` header with the cached token, or with a
+    /// new one once the cached token is due for refresh. A single caller
+    /// fetches the new token, while the rest keep using the current token
+    /// until it expires, or wait for the new one if there's none. When the
+    /// refresh fails, the current token is used until it expires as well.
+    pub async fn token(&self, http: &dyn HttpIO) -> Result<HeaderValue> {
+        let now = Instant::now();
+        let current = self.token.lock().unwrap().clone();
+        if let Some(token) = current.as_ref().filter(|token| now < token.refresh_at) {
+            return Ok(token.value.clone());
+        }
+
+        let _refresh = match self.refresh.try_lock() {
+            Ok(refresh) => refresh,
+            Err(_) => match current {
+                Some(token) if now < token.expires_at => return Ok(token.value),
+                _ => self.refresh.lock().await,
+            },
+        };
+
+        // the token could have been fetched while waiting for the lock
+        let current = self.token.lock().unwrap().clone();
+        if let Some(token) = current
+            .as_ref()
+            .filter(|token| Instant::now() < token.refresh_at)
+        {
+            return Ok(token.value.clone());
+        }
+
+        let token = match self.fetch(http).await {
+            Ok(token) => token,
+            Err(err) => match current {
+                Some(token) if Instant::now() < token.expires_at => {
+                    tracing::warn!(
+                        "Failed to refresh the token of {}: {}",
+                        self.config.origin,
+                        err
+                    );
+                    return Ok(token.value);
+                }
+                _ => return Err(err),
+            },
+        };
+        let value = token.value.clone();
+        *self.token.lock().unwrap() = Some(token);
+
+        Ok(value)
+    }
+
+    async fn fetch(&self, http: &dyn HttpIO) -> Result<Token> {
+        let body = {
+            let mut form = form_urlencoded::Serializer::new(String::new());
+            form.append_pair("grant_type", "client_credentials")
+                .append_pair("client_id", &self.config.client_id)
+                .append_pair("client_secret", &self.config.client_secret);
+            if let Some(scope) = self.config.scope.as_ref() {
+                form.append_pair("scope", scope);
+            }
+            form.finish()
+        };
+
+        let mut request =
+            reqwest::Request::new(reqwest::Method::POST, self.config.token_url.parse()?);
+        request.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded"),
+        );
+        *request.body_mut() = Some(body.into());
+
+        let response = http.execute(request).await?;
+        let token: TokenResponse = serde_json::from_slice(&response.body).with_context(|| {
+            format!(
+                "Failed to parse the token response of {}",
+                self.config.token_url
+            )
+        })?;
+
+        let now = Instant::now();
+        let expires_at = now + Duration::from_secs(token.expires_in.unwrap_or(DEFAULT_EXPIRES_IN));
+        let refresh_at = expires_at
+            .checked_sub(self.config.refresh_before)
+            .map_or(now, |refresh_at| refresh_at.max(now));
+
+        Ok(Token {
+            value: HeaderValue::from_str(&format!("Bearer {}", token.access_token))?,
+            expires_at,
+            refresh_at,
+        })
+    }
+}
+
+/// Sends the tokens of the providers to their upstreams, configured with
+/// `@upstream(tokenProviders)`. The requests to the rest of the upstreams are
+/// sent as they are.
+pub struct AuthTokenHttp {
+    inner: Arc<dyn HttpIO>,
+    // client the tokens are fetched with
+    client: Arc<dyn HttpIO>,
+    providers: Arc<Vec<AuthTokenProvider>>,
+}
+
+impl AuthTokenHttp {
+    /// Wraps the HTTP clients of the runtime, so that the requests of all the
+    /// resolvers carry the tokens.
+    pub fn wrap(mut runtime: TargetRuntime, providers: &[TokenProvider]) -> TargetRuntime {
+        if providers.is_empty() {
+            return runtime;
+        }
+
+        let providers = Arc::new(
+            providers
+                .iter()
+                .cloned()
+                .map(AuthTokenProvider::new)
+                .collect::<Vec<_>>(),
+        );
+        let client = runtime.http.clone();
+
+        runtime.http = Arc::new(Self {
+            inner: runtime.http,
+            client: client.clone(),
+            providers: providers.clone(),
+        });
+        runtime.http2_only = Arc::new(Self { inner: runtime.http2_only, client, providers });
+
+        runtime
+    }
+
+    async fn authorize(&self, mut request: reqwest::Request) -> Result<reqwest::Request> {
+        let origin = request.url().origin().ascii_serialization();
+        let provider = self
+            .providers
+            .iter()
+            .find(|provider| provider.config.origin == origin);
+
+        if let Some(provider) = provider {
+            let token = provider.token(self.client.as_ref()).await?;
+            request.headers_mut().insert(AUTHORIZATION, token);
+        }
+
+        Ok(request)
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpIO for AuthTokenHttp {
+    async fn execute(&self, request: reqwest::Request) -> Result<Response<Bytes>> {
+        self.inner.execute(self.authorize(request).await?).await
+    }
+
+    async fn execute_without_redirects(
+        &self,
+        request: reqwest::Request,
+    ) -> Result<Response<Bytes>> {
+        self.inner
+            .execute_without_redirects(self.authorize(request).await?)
+            .await
+    }
+
+    async fn execute_stream(&self, request: reqwest::Request) -> Result<Response<BodyStream>> {
+        self.inner
+            .execute_stream(self.authorize(request).await?)
+            .await
+    }
+
+    async fn execute_stream_with_trailers(
+        &self,
+        request: reqwest::Request,
+    ) -> Result<(Response<BodyStream>, Trailers)> {
+        self.inner
+            .execute_stream_with_trailers(self.authorize(request).await?)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures_util::future::join_all;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct TestHttp {
+        token_requests: AtomicUsize,
+        // fails the token requests after the first one
+        fail_refresh: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl HttpIO for TestHttp {
+        async fn execute(&self, request: reqwest::Request) -> Result<Response<Bytes>> {
+            let body = if request.url().path() == "/token" {
+                let requests = self.token_requests.fetch_add(1, Ordering::SeqCst);
+                if self.fail_refresh && requests > 0 {
+                    anyhow::bail!("Token server is unavailable");
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                Bytes::from_static(br#"{"access_token": "foo", "expires_in": 3600}"#)
+            } else {
+                // echoes the token the request was sent with
+                let token = request
+                    .headers()
+                    .get(AUTHORIZATION)
+                    .map(|token| token.as_bytes().to_vec())
+                    .unwrap_or_default();
+                Bytes::from(token)
+            };
+
+            Ok(Response { body, ..Default::default() })
+        }
+    }
+
+    fn provider() -> TokenProvider {
+        TokenProvider {
+            origin: "http://api.foo.com".to_string(),
+            token_url: "http://auth.foo.com/token".to_string(),
+            client_id: "id".to_string(),
+            client_secret: "secret".to_string(),
+            scope: None,
+            refresh_before: Duration::from_secs(60),
+        }
+    }
+
+    fn request(url: &str) -> reqwest::Request {
+        reqwest::Request::new(reqwest::Method::GET, url.parse().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_single_fetch_under_load() {
+        let http = Arc::new(TestHttp::default());
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.http = http.clone();
+        let runtime = AuthTokenHttp::wrap(runtime, &[provider()]);
+
+        let responses = join_all((0..100).map(|id| {
+            runtime
+                .http
+                .execute(request(&format!("http://api.foo.com/{id}")))
+        }))
+        .await;
+
+        assert_eq!(http.token_requests.load(Ordering::SeqCst), 1);
+        for response in responses {
+            assert_eq!(response.unwrap().body, Bytes::from("Bearer foo"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_other_upstreams() {
+        let http = Arc::new(TestHttp::default());
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.http = http.clone();
+        let runtime = AuthTokenHttp::wrap(runtime, &[provider()]);
+
+        let response = runtime
+            .http
+            .execute(request("http://api.bar.com/users"))
+            .await
+            .unwrap();
+
+        assert!(response.body.is_empty());
+        assert_eq!(http.token_requests.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_before_expiry() {
+        let http = TestHttp::default();
+        // the tokens are due for refresh as soon as they are fetched
+        let provider = AuthTokenProvider::new(TokenProvider {
+            refresh_before: Duration::from_secs(3600),
+            ..provider()
+        });
+
+        provider.token(&http).await.unwrap();
+        provider.token(&http).await.unwrap();
+
+        assert_eq!(http.token_requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_refresh() {
+        let http = TestHttp { fail_refresh: true, ..Default::default() };
+        let provider = AuthTokenProvider::new(TokenProvider {
+            refresh_before: Duration::from_secs(3600),
+            ..provider()
+        });
+
+        // the current token is used until it expires
+        provider.token(&http).await.unwrap();
+        let token = provider.token(&http).await.unwrap();
+        assert_eq!(token, HeaderValue::from_static("Bearer foo"));
+        assert_eq!(http.token_requests.load(Ordering::SeqCst), 2);
+
+        // and there's none to fall back to once it expired
+        let provider = AuthTokenProvider::new(provider.config.clone());
+        provider.token.lock().unwrap().replace(Token {
+            value: HeaderValue::from_static("Bearer foo"),
+            expires_at: Instant::now(),
+            refresh_at: Instant::now(),
+        });
+        assert!(provider.token(&http).await.is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use serde_json::Value;
+
+use super::RequestContext;
+
+/// Mutates the JSON body of the HTTP requests to the upstreams, eg. to sign
+/// it, once the body template is rendered and before the request is sent. It's
+/// a native alternative to modifying the requests with a JS worker. The
+/// batched requests are transformed once, as the request of the whole batch.
+pub trait BodyTransformer: Send + Sync {
+    fn transform(&self, body: &mut Value, request: &reqwest::Request, ctx: &RequestContext);
+}
+
+/// Runs the body of the request through the chain of the transformers of the
+/// runtime, in order. The bodies that aren't JSON, like the forms and the
+/// streamed uploads, are sent as they are.
+pub fn transform_body(request: &mut reqwest::Request, ctx: &RequestContext) -> anyhow::Result<()> {
+    let transformers = &ctx.runtime.body_transformers;
+    if transformers.is_empty() {
+        return Ok(());
+    }
+
+    let Some(bytes) = request.body().and_then(|body| body.as_bytes()) else {
+        return Ok(());
+    };
+    let Ok(mut body) = serde_json::from_slice::<Value>(bytes) else {
+        return Ok(());
+    };
+
+    for transformer in transformers.iter() {
+        transformer.transform(&mut body, request, ctx);
+    }
+
+    request
+        .body_mut()
+        .replace(reqwest::Body::from(serde_json::to_vec(&body)?));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+
+    /// Adds the sum of the bytes of the body as a checksum
+    struct Checksum;
+
+    impl BodyTransformer for Checksum {
+        fn transform(&self, body: &mut Value, _: &reqwest::Request, _: &RequestContext) {
+            let checksum: u64 = serde_json::to_vec(body)
+                .unwrap()
+                .into_iter()
+                .map(u64::from)
+                .sum();
+            body["checksum"] = json!(checksum);
+        }
+    }
+
+    /// Adds the method and the path of the request
+    struct Target;
+
+    impl BodyTransformer for Target {
+        fn transform(&self, body: &mut Value, request: &reqwest::Request, _: &RequestContext) {
+            body["target"] = json!(format!("{} {}", request.method(), request.url().path()));
+        }
+    }
+
+    fn request(body: &'static str) -> reqwest::Request {
+        reqwest::Client::new()
+            .post("http://localhost:8080/users")
+            .body(body)
+            .build()
+            .unwrap()
+    }
+
+    fn body(request: &reqwest::Request) -> Value {
+        serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_transform_body() {
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.add_body_transformer(Arc::new(Target));
+        runtime.add_body_transformer(Arc::new(Checksum));
+        let ctx = RequestContext::new(runtime);
+
+        let mut req = request(r#"{"id":1}"#);
+        transform_body(&mut req, &ctx).unwrap();
+
+        // the checksum covers the fields added by the transformers before it
+        let checksum: u64 =
+            br#"{"id":1,"target":"POST /users"}"#.iter().copied().map(u64::from).sum();
+        assert_eq!(
+            body(&req),
+            json!({"id": 1, "target": "POST /users", "checksum": checksum})
+        );
+    }
+
+    #[test]
+    fn test_transform_body_not_json() {
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.add_body_transformer(Arc::new(Checksum));
+        let ctx = RequestContext::new(runtime);
+
+        let mut req = request("id=1");
+        transform_body(&mut req, &ctx).unwrap();
+
+        assert_eq!(req.body().unwrap().as_bytes().unwrap(), b"id=1");
+    }
+}
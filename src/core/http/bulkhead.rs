@@ -0,0 +1,618 @@
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use hyper::body::Bytes;
+use once_cell::sync::Lazy;
+use opentelemetry::metrics::Counter;
+use opentelemetry::KeyValue;
+use tokio::sync::oneshot;
+
+use super::{AdaptiveTimeouts, BodyStream, Response, Trailers};
+use crate::core::config::Priority;
+use crate::core::ir::Error;
+use crate::core::runtime::TargetRuntime;
+use crate::core::{blueprint, HttpIO};
+
+tokio::task_local! {
+    // Priority of the upstream calls made while the IO of a field is
+    // evaluated, they wait for a free slot of the bulkhead by it.
+    static PRIORITY: Priority;
+}
+
+static BULKHEAD_REJECTED: Lazy<Counter<u64>> = Lazy::new(|| {
+    let meter = opentelemetry::global::meter("upstream");
+
+    meter
+        .u64_counter("upstream.bulkhead.rejected")
+        .with_description(
+            "Number of the requests that failed without being sent, because the bulkhead of \
+             their upstream had no free slot",
+        )
+        .init()
+});
+
+/// Bulkheads of the upstreams, configured with `@upstream(bulkhead)`. Each
+/// upstream, identified by the origin of its requests eg.
+/// `http://foo.com:8080`, gets its own limit of the requests in flight, so the
+/// requests waiting on a saturated upstream don't hold up the others. The
+/// slots are taken by the requests actually sent, see `BulkheadHttp`, so a
+/// batch of a data loader takes a single slot for all of its fields.
+pub struct Bulkheads {
+    config: blueprint::Bulkhead,
+    upstreams: Mutex<HashMap<String, Arc<Mutex<Slots>>>>,
+}
+
+/// The free slots of the bulkhead of an upstream and the requests waiting
+/// for one, by priority.
+struct Slots {
+    available: usize,
+    waiting: [VecDeque<oneshot::Sender<Slot>>; 3],
+}
+
+impl Slots {
+    fn queue(&mut self, priority: Priority) -> &mut VecDeque<oneshot::Sender<Slot>> {
+        let index = match priority {
+            Priority::High => 0,
+            Priority::Normal => 1,
+            Priority::Low => 2,
+        };
+
+        &mut self.waiting[index]
+    }
+
+    /// Drops the waiters that gave up and counts the others.
+    fn live_waiting(&mut self) -> usize {
+        self.waiting
+            .iter_mut()
+            .map(|queue| {
+                queue.retain(|waiter| !waiter.is_closed());
+                queue.len()
+            })
+            .sum()
+    }
+}
+
+/// How a request is admitted by the bulkhead of its upstream.
+enum Admission {
+    Admitted(Slot),
+    Queued(oneshot::Receiver<Slot>),
+    /// The queue of the waiting requests is full.
+    Rejected,
+}
+
+/// A slot of the bulkhead taken by a request in flight. When it's dropped the
+/// slot is handed over to the waiting request of the highest priority.
+struct Slot {
+    slots: Option<Arc<Mutex<Slots>>>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let Some(slots) = self.slots.take() else {
+            return;
+        };
+        let mut guard = slots.lock().unwrap();
+
+        // the waiters that gave up on the slot are skipped
+        while let Some(waiter) = guard.waiting.iter_mut().find_map(VecDeque::pop_front) {
+            match waiter.send(Slot { slots: Some(slots.clone()) }) {
+                Ok(()) => return,
+                Err(mut slot) => {
+                    slot.slots = None;
+                }
+            }
+        }
+
+        guard.available += 1;
+    }
+}
+
+impl Bulkheads {
+    pub fn new(config: blueprint::Bulkhead) -> Self {
+        Self { config, upstreams: Mutex::new(HashMap::new()) }
+    }
+
+    fn slots(&self, origin: &str) -> Arc<Mutex<Slots>> {
+        self.upstreams
+            .lock()
+            .unwrap()
+            .entry(origin.to_string())
+            .or_insert_with(|| {
+                Arc::new(Mutex::new(Slots {
+                    available: self.config.max_in_flight,
+                    waiting: Default::default(),
+                }))
+            })
+            .clone()
+    }
+
+    /// Takes a free slot, or queues the request by its priority until one is
+    /// freed. The request is rejected if the queue is full.
+    fn acquire(&self, origin: &str, priority: Priority) -> Admission {
+        let slots = self.slots(origin);
+        let mut guard = slots.lock().unwrap();
+        if guard.available > 0 {
+            guard.available -= 1;
+            drop(guard);
+            return Admission::Admitted(Slot { slots: Some(slots) });
+        }
+
+        if let Some(max_queue) = self.config.max_queue {
+            if guard.live_waiting() >= max_queue {
+                return Admission::Rejected;
+            }
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        let queue = guard.queue(priority);
+        queue.retain(|waiter| !waiter.is_closed());
+        queue.push_back(sender);
+
+        Admission::Queued(receiver)
+    }
+
+    /// Runs the request once there's a free slot in the bulkhead of the
+    /// upstream, it fails without being sent if there's none within the
+    /// `maxWait` or if the queue of the waiting requests is full. The waiting
+    /// requests are admitted by their priority.
+    pub async fn run<T>(
+        &self,
+        origin: &str,
+        priority: Priority,
+        request: impl Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        let waiting = match self.acquire(origin, priority) {
+            Admission::Admitted(_slot) => return request.await,
+            Admission::Queued(waiting) => waiting,
+            Admission::Rejected => {
+                reject(origin, "queue_full");
+                return Err(Error::Overloaded(format!(
+                    "Too many requests waiting for {origin}"
+                )));
+            }
+        };
+
+        match tokio::time::timeout(self.config.max_wait, waiting).await {
+            Ok(Ok(_slot)) => request.await,
+            _ => {
+                reject(origin, "queue_timeout");
+                Err(Error::Overloaded(format!(
+                    "Too many requests in flight to {origin}, waited for {}ms",
+                    self.config.max_wait.as_millis()
+                )))
+            }
+        }
+    }
+
+    /// Number of the requests in flight to the upstream.
+    pub fn in_flight(&self, origin: &str) -> usize {
+        let available = self
+            .upstreams
+            .lock()
+            .unwrap()
+            .get(origin)
+            .map_or(self.config.max_in_flight, |slots| {
+                slots.lock().unwrap().available
+            });
+
+        self.config.max_in_flight - available
+    }
+
+    /// Number of the requests waiting for a free slot of the upstream.
+    pub fn waiting(&self, origin: &str) -> usize {
+        self.upstreams
+            .lock()
+            .unwrap()
+            .get(origin)
+            .map_or(0, |slots| {
+                let guard = slots.lock().unwrap();
+                guard
+                    .waiting
+                    .iter()
+                    .flatten()
+                    .filter(|waiter| !waiter.is_closed())
+                    .count()
+            })
+    }
+}
+
+/// Sends the upstream calls made by the future with the priority, in the
+/// bulkheads of their upstreams. The calls of the batches are made by the data
+/// loaders on behalf of multiple fields, so they're sent with the `Normal`
+/// priority.
+pub async fn with_priority<F: Future>(priority: Priority, future: F) -> F::Output {
+    PRIORITY.scope(priority, future).await
+}
+
+/// Limits the requests sent by the HTTP clients to the bulkheads and to the
+/// adaptive timeouts of their upstreams, configured with `@upstream(bulkhead)`
+/// and `@upstream(adaptiveTimeout)`. The time spent waiting for a free slot in
+/// the bulkhead doesn't count towards the timeout. The streamed responses
+/// hold their slot until the head of the response is received.
+pub struct BulkheadHttp {
+    inner: Arc<dyn HttpIO>,
+    bulkheads: Option<Arc<Bulkheads>>,
+    timeouts: Option<Arc<AdaptiveTimeouts>>,
+}
+
+impl BulkheadHttp {
+    /// Wraps the HTTP clients of the runtime, so that the requests of all the
+    /// resolvers and of the data loaders are limited.
+    pub fn wrap(
+        mut runtime: TargetRuntime,
+        bulkheads: Option<Arc<Bulkheads>>,
+        timeouts: Option<Arc<AdaptiveTimeouts>>,
+    ) -> TargetRuntime {
+        if bulkheads.is_none() && timeouts.is_none() {
+            return runtime;
+        }
+
+        runtime.http = Arc::new(Self {
+            inner: runtime.http,
+            bulkheads: bulkheads.clone(),
+            timeouts: timeouts.clone(),
+        });
+        runtime.http2_only = Arc::new(Self { inner: runtime.http2_only, bulkheads, timeouts });
+
+        runtime
+    }
+
+    async fn limit<T>(
+        &self,
+        origin: &str,
+        execute: impl Future<Output = anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
+        // the errors of the upstream are passed through as they are, only the
+        // bulkhead and the timeout fail on their own
+        let execute = async {
+            match self.timeouts.as_ref() {
+                Some(timeouts) => timeouts.run(origin, async { Ok(execute.await) }).await,
+                None => Ok(execute.await),
+            }
+        };
+        let result = match self.bulkheads.as_ref() {
+            Some(bulkheads) => {
+                let priority = PRIORITY.try_with(|priority| *priority).unwrap_or_default();
+                bulkheads.run(origin, priority, execute).await
+            }
+            None => execute.await,
+        };
+
+        result?
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpIO for BulkheadHttp {
+    async fn execute(&self, request: reqwest::Request) -> anyhow::Result<Response<Bytes>> {
+        let origin = origin(&request);
+        self.limit(&origin, self.inner.execute(request)).await
+    }
+
+    async fn execute_without_redirects(
+        &self,
+        request: reqwest::Request,
+    ) -> anyhow::Result<Response<Bytes>> {
+        let origin = origin(&request);
+        self.limit(&origin, self.inner.execute_without_redirects(request))
+            .await
+    }
+
+    async fn execute_stream(
+        &self,
+        request: reqwest::Request,
+    ) -> anyhow::Result<Response<BodyStream>> {
+        let origin = origin(&request);
+        self.limit(&origin, self.inner.execute_stream(request))
+            .await
+    }
+
+    async fn execute_stream_with_trailers(
+        &self,
+        request: reqwest::Request,
+    ) -> anyhow::Result<(Response<BodyStream>, Trailers)> {
+        let origin = origin(&request);
+        self.limit(&origin, self.inner.execute_stream_with_trailers(request))
+            .await
+    }
+}
+
+/// Identifies the upstream of the request, eg. `http://foo.com:8080`.
+fn origin(request: &reqwest::Request) -> String {
+    request.url().origin().ascii_serialization()
+}
+
+/// Counts the rejected requests apart from the ones that time out in the
+/// upstream, by the `reason` they weren't admitted for.
+fn reject(origin: &str, reason: &'static str) {
+    BULKHEAD_REJECTED.add(
+        1,
+        &[
+            KeyValue::new("origin", origin.to_string()),
+            KeyValue::new("reason", reason),
+        ],
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::core::app_context::test::{TestApp, TestHttp};
+
+    const A: &str = "http://a.com";
+    const B: &str = "http://b.com";
+
+    fn bulkheads() -> Arc<Bulkheads> {
+        Arc::new(Bulkheads::new(blueprint::Bulkhead {
+            max_in_flight: 2,
+            max_wait: Duration::from_millis(50),
+            max_queue: None,
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_isolation() {
+        let bulkheads = bulkheads();
+
+        // saturate the bulkhead of A with the requests that never complete
+        let mut releases = vec![];
+        for _ in 0..2 {
+            let (release, released) = oneshot::channel::<()>();
+            releases.push(release);
+            let bulkheads = bulkheads.clone();
+            tokio::spawn(async move {
+                bulkheads
+                    .run(A, Priority::Normal, async move {
+                        let _ = released.await;
+                        Ok(())
+                    })
+                    .await
+            });
+        }
+        while bulkheads.in_flight(A) < 2 {
+            tokio::task::yield_now().await;
+        }
+
+        let error = bulkheads
+            .run(A, Priority::Normal, async { Ok(1) })
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&error, Error::Overloaded(message) if message == "Too many requests in flight to http://a.com, waited for 50ms"),
+            "{error:?}"
+        );
+
+        // the requests to B are admitted while A is saturated
+        let value = bulkheads
+            .run(B, Priority::Normal, async { Ok(2) })
+            .await
+            .unwrap();
+        assert_eq!(value, 2);
+        assert_eq!(bulkheads.in_flight(B), 0);
+
+        // A admits the requests again once its requests complete
+        drop(releases);
+        let value = bulkheads
+            .run(A, Priority::Normal, async { Ok(3) })
+            .await
+            .unwrap();
+        assert_eq!(value, 3);
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_slot() {
+        let bulkheads = bulkheads();
+        let (release, released) = oneshot::channel::<()>();
+
+        let first = tokio::spawn({
+            let bulkheads = bulkheads.clone();
+            async move {
+                bulkheads
+                    .run(A, Priority::Normal, async move {
+                        let _ = released.await;
+                        Ok(1)
+                    })
+                    .await
+            }
+        });
+        let second = tokio::spawn({
+            let bulkheads = bulkheads.clone();
+            async move {
+                bulkheads
+                    .run(
+                        A,
+                        Priority::Normal,
+                        std::future::pending::<Result<i32, _>>(),
+                    )
+                    .await
+            }
+        });
+        while bulkheads.in_flight(A) < 2 {
+            tokio::task::yield_now().await;
+        }
+
+        // the waiting request gets the slot freed within the `maxWait`
+        let third = tokio::spawn({
+            let bulkheads = bulkheads.clone();
+            async move { bulkheads.run(A, Priority::Normal, async { Ok(3) }).await }
+        });
+        release.send(()).unwrap();
+
+        assert_eq!(first.await.unwrap().unwrap(), 1);
+        assert_eq!(third.await.unwrap().unwrap(), 3);
+        second.abort();
+    }
+
+    #[tokio::test]
+    async fn test_priority() {
+        let bulkheads = Arc::new(Bulkheads::new(blueprint::Bulkhead {
+            max_in_flight: 1,
+            max_wait: Duration::from_secs(5),
+            max_queue: None,
+        }));
+        let (release, released) = oneshot::channel::<()>();
+        let (admitted, mut admissions) = mpsc::unbounded_channel();
+
+        let first = tokio::spawn({
+            let bulkheads = bulkheads.clone();
+            async move {
+                bulkheads
+                    .run(A, Priority::Normal, async move {
+                        let _ = released.await;
+                        Ok(())
+                    })
+                    .await
+            }
+        });
+        while bulkheads.in_flight(A) < 1 {
+            tokio::task::yield_now().await;
+        }
+
+        let queue = |priority: Priority| {
+            let bulkheads = bulkheads.clone();
+            let admitted = admitted.clone();
+            tokio::spawn(async move {
+                bulkheads
+                    .run(A, priority, async move {
+                        admitted.send(priority).unwrap();
+                        Ok(())
+                    })
+                    .await
+            })
+        };
+
+        // the low priority request is queued before the high priority one
+        let low = queue(Priority::Low);
+        while bulkheads.waiting(A) < 1 {
+            tokio::task::yield_now().await;
+        }
+        let high = queue(Priority::High);
+        while bulkheads.waiting(A) < 2 {
+            tokio::task::yield_now().await;
+        }
+
+        release.send(()).unwrap();
+        first.await.unwrap().unwrap();
+        high.await.unwrap().unwrap();
+        low.await.unwrap().unwrap();
+
+        assert_eq!(admissions.recv().await, Some(Priority::High));
+        assert_eq!(admissions.recv().await, Some(Priority::Low));
+        assert_eq!(bulkheads.in_flight(A), 0);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue() {
+        let bulkheads = Arc::new(Bulkheads::new(blueprint::Bulkhead {
+            max_in_flight: 1,
+            max_wait: Duration::from_millis(50),
+            max_queue: Some(1),
+        }));
+        let (release, released) = oneshot::channel::<()>();
+
+        let first = tokio::spawn({
+            let bulkheads = bulkheads.clone();
+            async move {
+                bulkheads
+                    .run(A, Priority::Normal, async move {
+                        let _ = released.await;
+                        Ok(1)
+                    })
+                    .await
+            }
+        });
+        while bulkheads.in_flight(A) < 1 {
+            tokio::task::yield_now().await;
+        }
+        let second = tokio::spawn({
+            let bulkheads = bulkheads.clone();
+            async move { bulkheads.run(A, Priority::Normal, async { Ok(2) }).await }
+        });
+        while bulkheads.waiting(A) < 1 {
+            tokio::task::yield_now().await;
+        }
+
+        // the excess request is rejected at once, without waiting
+        let error = bulkheads
+            .run(A, Priority::High, async { Ok(3) })
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&error, Error::Overloaded(message) if message == "Too many requests waiting for http://a.com"),
+            "{error:?}"
+        );
+
+        // the queued request times out while the slot is still taken
+        let error = second.await.unwrap().unwrap_err();
+        assert!(
+            matches!(&error, Error::Overloaded(message) if message == "Too many requests in flight to http://a.com, waited for 50ms"),
+            "{error:?}"
+        );
+
+        // the queue has room again once the waiter gave up
+        let third = tokio::spawn({
+            let bulkheads = bulkheads.clone();
+            async move { bulkheads.run(A, Priority::Normal, async { Ok(3) }).await }
+        });
+        while bulkheads.waiting(A) < 1 {
+            tokio::task::yield_now().await;
+        }
+        release.send(()).unwrap();
+        assert_eq!(first.await.unwrap().unwrap(), 1);
+        assert_eq!(third.await.unwrap().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_batch_takes_a_slot() {
+        let sdl = r#"
+            schema
+                @server
+                @upstream(batch: {delay: 1}, bulkhead: {maxInFlight: 1, maxWait: 50}) {
+                query: Query
+            }
+
+            type Query {
+                posts: [Post] @http(url: "http://a.com/posts")
+            }
+
+            type Post {
+                id: Int
+                userId: Int
+                user: User
+                    @http(
+                        url: "http://a.com/users"
+                        query: [{key: "id", value: "{{.value.userId}}"}]
+                        batchKey: ["id"]
+                    )
+            }
+
+            type User {
+                id: Int
+            }
+        "#;
+        let http = TestHttp::delayed(Duration::from_millis(100), |request| {
+            let body = match request.url().path() {
+                "/posts" => r#"[{"id": 1, "userId": 1}, {"id": 2, "userId": 2}]"#,
+                _ => r#"[{"id": 1}, {"id": 2}]"#,
+            };
+            Ok(Response { body: Bytes::from(body), ..Default::default() })
+        });
+        let response = TestApp::new(sdl, http.clone())
+            .execute("query { posts { id user { id } } }")
+            .await;
+
+        // the fields of the batch wait on a single request, that fits the
+        // bulkhead of its upstream
+        assert!(response.get("errors").is_none(), "{response}");
+        assert_eq!(
+            response["data"]["posts"],
+            serde_json::json!([{"id": 1, "user": {"id": 1}}, {"id": 2, "user": {"id": 2}}])
+        );
+        assert_eq!(http.hits(), 2);
+    }
+}
@@ -1,4 +1,5 @@
 use cache_control::CacheControl;
+use http::header::{HeaderMap, VARY};
 
 use super::Response;
 
@@ -9,6 +10,36 @@ pub fn cache_policy(res: &Response<async_graphql::Value>) -> Option<CacheControl
     CacheControl::from_value(value)
 }
 
+/// Request headers a response varies by, listed in its `Vary` header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Vary {
+    /// `Vary: *`, the response can't be reused for any other request.
+    Any,
+    /// Lowercase names of the headers, sorted and without duplicates.
+    Headers(Vec<String>),
+}
+
+impl Vary {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let mut names = headers
+            .get_all(VARY)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|name| name.trim().to_ascii_lowercase())
+            .filter(|name| !name.is_empty())
+            .collect::<Vec<_>>();
+
+        if names.iter().any(|name| name == "*") {
+            return Vary::Any;
+        }
+
+        names.sort();
+        names.dedup();
+        Vary::Headers(names)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -67,6 +98,25 @@ mod tests {
         headers
     }
 
+    #[test]
+    fn test_vary() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            super::Vary::from_headers(&headers),
+            super::Vary::Headers(vec![])
+        );
+
+        headers.append("Vary", "Accept-Language, accept".parse().unwrap());
+        headers.append("Vary", "Accept-Language".parse().unwrap());
+        assert_eq!(
+            super::Vary::from_headers(&headers),
+            super::Vary::Headers(vec!["accept".to_string(), "accept-language".to_string()])
+        );
+
+        headers.append("Vary", "*".parse().unwrap());
+        assert_eq!(super::Vary::from_headers(&headers), super::Vary::Any);
+    }
+
     #[test]
     fn test_max_age_none() {
         let response = Response::default();
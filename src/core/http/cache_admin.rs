@@ -0,0 +1,429 @@
+use std::borrow::Cow;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Result;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::http::Method;
+use hyper::{Body, HeaderMap, Request, Response, StatusCode};
+use serde::Serialize;
+
+use super::RequestContext;
+use crate::core::app_context::AppContext;
+use crate::core::auth::verify::{AuthVerifier, Verify};
+use crate::core::blueprint::CacheAdmin;
+use crate::core::cache::{self, Stats};
+use crate::core::ir::model::{Cache, IoId};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    #[serde(flatten)]
+    stats: Option<Stats>,
+    hit_rate: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct Evicted {
+    evicted: usize,
+}
+
+/// A key of the cache, without its value.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Key {
+    key: u64,
+    /// Milliseconds since the epoch
+    expires_at: u128,
+    tags: Vec<String>,
+}
+
+/// The request is for the cache admin endpoint or one of its entries.
+pub fn is_route(cache_admin: &CacheAdmin, path: &str) -> bool {
+    path.strip_prefix(cache_admin.path.as_str())
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Serves the cache admin endpoint, configured with `@server(cacheAdmin)`.
+/// `GET` reports the entries and the lookups of the cache, `GET <path>/keys`
+/// lists its keys, `DELETE` flushes it along with the cached responses of the
+/// constant operations, `DELETE <path>/<id>` evicts everything stored for an
+/// IO id and `DELETE <path>/tags/<tag>` evicts the entries tagged by
+/// `@cache(tags)`, so that the next request re-fetches them from the upstream.
+/// The requests whose JWT isn't granted the scope of the endpoint are
+/// forbidden.
+pub async fn handle(
+    req: &Request<Body>,
+    app_ctx: &AppContext,
+    cache_admin: &CacheAdmin,
+) -> Result<Response<Body>> {
+    if !is_authorized(req, app_ctx, cache_admin).await {
+        return status(StatusCode::FORBIDDEN);
+    }
+
+    let cache = &app_ctx.runtime.cache;
+    let id = req.uri().path()[cache_admin.path.len()..].trim_start_matches('/');
+
+    match (req.method(), id) {
+        (&Method::GET, "") => json(&Report { stats: cache.stats(), hit_rate: cache.hit_rate() }),
+        (&Method::GET, "keys") => {
+            let Some(entries) = cache.entries() else {
+                return status(StatusCode::NOT_IMPLEMENTED);
+            };
+            let keys = entries
+                .into_iter()
+                .map(|entry| Key {
+                    key: entry.key.as_u64(),
+                    expires_at: entry
+                        .expires_at
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_millis(),
+                    tags: entry.tags,
+                })
+                .collect::<Vec<_>>();
+
+            json(&keys)
+        }
+        (&Method::DELETE, "") => match cache.clear().await {
+            Ok(evicted) => {
+                // the responses of the constant operations are resolved from the cache
+                app_ctx.const_execution_cache.clear();
+                json(&Evicted { evicted })
+            }
+            Err(err) => failed(err),
+        },
+        (&Method::DELETE, id) => {
+            let tag = match id.strip_prefix("tags/") {
+                Some(tag) => urlencoding::decode(tag).ok().map(Cow::into_owned),
+                None => id.parse().ok().map(|id| Cache::entry_tag(&IoId::new(id))),
+            };
+            let Some(tag) = tag else {
+                return status(StatusCode::BAD_REQUEST);
+            };
+
+            match cache.invalidate(&tag).await {
+                Ok(evicted) => json(&Evicted { evicted }),
+                Err(err) => failed(err),
+            }
+        }
+        _ => status(StatusCode::METHOD_NOT_ALLOWED),
+    }
+}
+
+async fn is_authorized(
+    req: &Request<Body>,
+    app_ctx: &AppContext,
+    cache_admin: &CacheAdmin,
+) -> bool {
+    // only the credentials are read from the request, none of its headers is
+    // forwarded anywhere
+    let mut headers = HeaderMap::new();
+    if let Some(authorization) = req.headers().get(AUTHORIZATION) {
+        headers.insert(AUTHORIZATION, authorization.clone());
+    }
+    let req_ctx = RequestContext::from(app_ctx).allowed_headers(headers);
+
+    AuthVerifier::from(cache_admin.auth.clone())
+        .scopes(&req_ctx)
+        .await
+        .contains(&cache_admin.scope)
+}
+
+fn json<T: Serialize>(body: &T) -> Result<Response<Body>> {
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(body)?))?)
+}
+
+fn failed(err: cache::Error) -> Result<Response<Body>> {
+    let body = serde_json::json!({ "message": err.to_string() });
+
+    Ok(Response::builder()
+        .status(StatusCode::INTERNAL_SERVER_ERROR)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&body)?))?)
+}
+
+fn status(status: StatusCode) -> Result<Response<Body>> {
+    Ok(Response::builder().status(status).body(Body::empty())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU64;
+    use std::sync::Arc;
+
+    use jsonwebtoken::jwk::JwkSet;
+    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+    use serde_json::json;
+    use tailcall_valid::Validator;
+
+    use super::*;
+    use crate::core::app_context::test::{TestApp, TestHttp};
+    use crate::core::async_graphql_hyper::GraphQLRequest;
+    use crate::core::blueprint::{Auth, Blueprint, Jwt, Provider};
+    use crate::core::config::{Config, ConfigModule};
+    use crate::core::http::{handle_request, RequestTemplate};
+    use crate::core::ir::model::{CacheKey, CacheOptions, IO, IR};
+    use crate::core::ir::{EmptyResolverContext, EvalContext};
+    use crate::core::jit::OPHash;
+    use crate::core::runtime::test::init;
+    use crate::core::Mustache;
+
+    fn token(scope: &str) -> String {
+        let header = Header {
+            kid: Some("test".to_string()),
+            ..Header::new(Algorithm::HS256)
+        };
+        let claims = json!({ "exp": 2019056441, "scope": scope });
+
+        encode(&header, &claims, &EncodingKey::from_secret(b"secret")).unwrap()
+    }
+
+    fn cached(url: &str) -> IR {
+        let io = IR::IO(IO::Http {
+            req_template: RequestTemplate::new(url).unwrap(),
+            group_by: None,
+            dl_id: None,
+            is_list: false,
+            dedupe: false,
+            hook: None,
+        });
+
+        Cache::wrap(NonZeroU64::new(60000).unwrap(), CacheOptions::default(), io)
+    }
+
+    struct Setup {
+        app_ctx: Arc<AppContext>,
+        http: Arc<TestHttp>,
+    }
+
+    impl Setup {
+        async fn new() -> anyhow::Result<Self> {
+            let sdl =
+                tokio::fs::read_to_string(tailcall_fixtures::configs::JSONPLACEHOLDER).await?;
+            let config = Config::from_sdl(&sdl).to_result()?;
+            let mut blueprint = Blueprint::try_from(&ConfigModule::from(config))?;
+
+            // the key is the base64 encoded `secret`
+            let jwks: JwkSet = serde_json::from_value(json!({
+                "keys": [{ "kty": "oct", "kid": "test", "alg": "HS256", "k": "c2VjcmV0" }]
+            }))?;
+            blueprint.server.cache_admin = Some(CacheAdmin {
+                path: "/admin/cache".to_string(),
+                scope: "admin".to_string(),
+                auth: Auth::Provider(Provider::Jwt(Jwt {
+                    issuer: None,
+                    audiences: Default::default(),
+                    optional_kid: false,
+                    jwks,
+                })),
+            });
+
+            let http = TestHttp::body(|_| Ok(r#"{"id": 1}"#));
+            let mut runtime = init(None);
+            runtime.http = http.clone();
+            let app_ctx = TestApp::from_blueprint(blueprint, runtime).app_ctx;
+
+            Ok(Self { app_ctx, http })
+        }
+
+        /// Resolves the cached IR and returns its key.
+        async fn resolve(&self, ir: &IR) -> IoId {
+            let req_ctx = RequestContext::new(self.app_ctx.runtime.clone());
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+            ir.eval(&mut eval_ctx).await.unwrap();
+
+            let IR::Cache(cache) = ir else { unreachable!() };
+            cache.cache_key(&eval_ctx).unwrap()
+        }
+
+        fn requests(&self) -> usize {
+            self.http.hits()
+        }
+
+        async fn admin(
+            &self,
+            method: Method,
+            path: &str,
+            scope: &str,
+        ) -> anyhow::Result<(StatusCode, serde_json::Value)> {
+            let req = Request::builder()
+                .method(method)
+                .uri(format!("http://localhost:8000{path}"))
+                .header(AUTHORIZATION, format!("Bearer {}", token(scope)))
+                .body(Body::empty())?;
+
+            let resp = handle_request::<GraphQLRequest>(req, self.app_ctx.clone()).await?;
+            let status = resp.status();
+            let body = hyper::body::to_bytes(resp.into_body()).await?;
+            let body = serde_json::from_slice(&body).unwrap_or_default();
+
+            Ok((status, body))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evict_entry() -> anyhow::Result<()> {
+        let setup = Setup::new().await?;
+        let first = cached("http://localhost:8080/users/1");
+        let second = cached("http://localhost:8080/users/2");
+
+        let key = setup.resolve(&first).await;
+        setup.resolve(&second).await;
+        setup.resolve(&first).await;
+        assert_eq!(setup.requests(), 2);
+
+        let path = format!("/admin/cache/{}", key.as_u64());
+        let (status, body) = setup.admin(Method::DELETE, &path, "admin").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "evicted": 1 }));
+
+        // only the evicted entry is re-fetched
+        setup.resolve(&first).await;
+        setup.resolve(&second).await;
+        assert_eq!(setup.requests(), 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_evict_varied_entry() -> anyhow::Result<()> {
+        let setup = Setup::new().await?;
+        let io = IR::IO(IO::Http {
+            req_template: RequestTemplate::new("http://localhost:8080/users/1")?,
+            group_by: None,
+            dl_id: None,
+            is_list: false,
+            dedupe: false,
+            hook: None,
+        });
+        let options = CacheOptions::default()
+            .vary(vec!["x-tenant".to_string()])
+            .serve_stale_on_error(true);
+        let ir = Cache::wrap(NonZeroU64::new(60000).unwrap(), options, io);
+
+        let key = setup.resolve(&ir).await;
+        setup.resolve(&ir).await;
+        assert_eq!(setup.requests(), 1);
+
+        // the entry of the headers and its stale copy are stored under their own keys
+        let path = format!("/admin/cache/{}", key.as_u64());
+        let (_, body) = setup.admin(Method::DELETE, &path, "admin").await?;
+        assert_eq!(body, json!({ "evicted": 2 }));
+
+        setup.resolve(&ir).await;
+        assert_eq!(setup.requests(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_tag() -> anyhow::Result<()> {
+        let setup = Setup::new().await?;
+        let tagged = |url| {
+            let io = IR::IO(IO::Http {
+                req_template: RequestTemplate::new(url).unwrap(),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: false,
+                hook: None,
+            });
+            let options = CacheOptions::default().tags(vec![Mustache::parse("user:{{.value.id}}")]);
+
+            Cache::wrap(NonZeroU64::new(60000).unwrap(), options, io)
+        };
+        let (first, second) = (
+            tagged("http://localhost:8080/users/1"),
+            cached("http://localhost:8080/users/2"),
+        );
+
+        setup.resolve(&first).await;
+        setup.resolve(&second).await;
+        assert_eq!(setup.requests(), 2);
+
+        let (status, body) = setup
+            .admin(Method::DELETE, "/admin/cache/tags/user%3A1", "admin")
+            .await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "evicted": 1 }));
+
+        // only the tagged entry is re-fetched
+        setup.resolve(&first).await;
+        setup.resolve(&second).await;
+        assert_eq!(setup.requests(), 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_keys() -> anyhow::Result<()> {
+        let setup = Setup::new().await?;
+        let key = setup
+            .resolve(&cached("http://localhost:8080/users/1"))
+            .await;
+
+        let (status, body) = setup
+            .admin(Method::GET, "/admin/cache/keys", "admin")
+            .await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["key"], json!(key.as_u64()));
+        assert_eq!(body[0]["tags"], json!([Cache::entry_tag(&key)]));
+        assert_eq!(body.as_array().map(Vec::len), Some(1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_flush() -> anyhow::Result<()> {
+        let setup = Setup::new().await?;
+        let first = cached("http://localhost:8080/users/1");
+        let second = cached("http://localhost:8080/users/2");
+
+        setup.resolve(&first).await;
+        setup.resolve(&second).await;
+        setup.resolve(&second).await;
+
+        // each resolution also looks up the headers the entry varies by
+        let (status, body) = setup.admin(Method::GET, "/admin/cache", "admin").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!({ "entries": 2, "hits": 1, "misses": 5, "hitRate": 1.0 / 6.0 })
+        );
+
+        setup
+            .app_ctx
+            .const_execution_cache
+            .insert(OPHash::new(1), Default::default());
+
+        let (status, body) = setup.admin(Method::DELETE, "/admin/cache", "admin").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "evicted": 2 }));
+        assert!(setup.app_ctx.const_execution_cache.is_empty());
+
+        setup.resolve(&first).await;
+        setup.resolve(&second).await;
+        assert_eq!(setup.requests(), 4);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_forbidden() -> anyhow::Result<()> {
+        let setup = Setup::new().await?;
+        let ir = cached("http://localhost:8080/users/1");
+        setup.resolve(&ir).await;
+
+        let (status, _) = setup.admin(Method::DELETE, "/admin/cache", "read").await?;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        setup.resolve(&ir).await;
+        assert_eq!(setup.requests(), 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use async_graphql::{Name, Value as ConstValue};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::core::json::JsonLike;
+
+/// Maps a page of a cursor-paginated API, eg. `{ items, nextCursor, hasMore }`,
+/// to a Relay connection, ie. `{ edges { node cursor } pageInfo { hasNextPage
+/// hasPreviousPage endCursor } }`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Connection {
+    /// The path of the items of the page.
+    pub items: Vec<String>,
+    /// The path of the cursor of the next page.
+    pub next_cursor: Vec<String>,
+    /// The path of the flag whether there's a next page.
+    pub has_more: Vec<String>,
+    /// The path of the cursor of an item, relative to the item.
+    pub edge_cursor: Vec<String>,
+}
+
+impl Connection {
+    /// Without a flag in the page there's a next page as long as there's a
+    /// cursor of it. The API is paginated forwards only, so
+    /// `hasPreviousPage` is always `false`.
+    pub fn to_connection(&self, page: ConstValue) -> ConstValue {
+        let end_cursor = page
+            .get_path(&self.next_cursor)
+            .cloned()
+            .unwrap_or_default();
+        let has_next_page = match page.get_path(&self.has_more) {
+            Some(ConstValue::Boolean(has_more)) => *has_more,
+            _ => !end_cursor.is_null(),
+        };
+        let items = match page.get_path(&self.items) {
+            Some(ConstValue::List(items)) => items.clone(),
+            _ => vec![],
+        };
+
+        let edges = items
+            .into_iter()
+            .map(|node| {
+                let cursor = node
+                    .get_path(&self.edge_cursor)
+                    .cloned()
+                    .unwrap_or_default();
+                ConstValue::Object(IndexMap::from([
+                    (Name::new("node"), node),
+                    (Name::new("cursor"), cursor),
+                ]))
+            })
+            .collect();
+
+        let page_info = IndexMap::from([
+            (Name::new("hasNextPage"), ConstValue::from(has_next_page)),
+            (Name::new("hasPreviousPage"), ConstValue::from(false)),
+            (Name::new("endCursor"), end_cursor),
+        ]);
+
+        ConstValue::Object(IndexMap::from([
+            (Name::new("edges"), ConstValue::List(edges)),
+            (Name::new("pageInfo"), ConstValue::Object(page_info)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn connection() -> Connection {
+        Connection {
+            items: vec!["items".to_string()],
+            next_cursor: vec!["nextCursor".to_string()],
+            has_more: vec!["hasMore".to_string()],
+            edge_cursor: vec!["id".to_string()],
+        }
+    }
+
+    fn value(json: serde_json::Value) -> ConstValue {
+        ConstValue::from_json(json).unwrap()
+    }
+
+    #[test]
+    fn test_to_connection() {
+        let page = value(json!({
+            "items": [{ "id": 1 }, { "id": 2 }],
+            "nextCursor": "abc",
+            "hasMore": true,
+        }));
+
+        let actual = connection().to_connection(page);
+        let expected = json!({
+            "edges": [
+                { "node": { "id": 1 }, "cursor": 1 },
+                { "node": { "id": 2 }, "cursor": 2 },
+            ],
+            "pageInfo": { "hasNextPage": true, "hasPreviousPage": false, "endCursor": "abc" },
+        });
+        assert_eq!(actual.into_json().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_last_page() {
+        // without the flag the next page is inferred from the cursor
+        let page = value(json!({ "items": [{ "id": 3 }], "nextCursor": null }));
+        let actual = connection().to_connection(page).into_json().unwrap();
+        assert_eq!(actual["pageInfo"]["hasNextPage"], json!(false));
+        assert_eq!(actual["edges"][0]["cursor"], json!(3));
+
+        let page = value(json!({ "nextCursor": "abc", "hasMore": false }));
+        let actual = connection().to_connection(page).into_json().unwrap();
+        assert_eq!(actual["edges"], json!([]));
+        assert_eq!(actual["pageInfo"]["hasNextPage"], json!(false));
+    }
+}
@@ -0,0 +1,235 @@
+use std::io::Read;
+
+use anyhow::{anyhow, Context, Result};
+use http::header::{HeaderMap, HeaderValue, CONTENT_ENCODING, CONTENT_LENGTH};
+use hyper::body::Bytes;
+
+/// Encodings of the response bodies that can be decoded, sent to the
+/// upstreams in the `Accept-Encoding` header.
+pub static ACCEPT_ENCODING: HeaderValue = HeaderValue::from_static("gzip, deflate, br, zstd");
+
+/// Size in bytes that the bodies are decoded to at most, unless
+/// `@upstream(maxDecodedSize)` is set.
+pub const DEFAULT_MAX_DECODED_SIZE: usize = 100 * 1024 * 1024;
+
+/// Size in bytes of the encoded bodies above which the runtimes decode them
+/// off the async threads. The smaller ones decode to at most a few megabytes
+/// and are quicker to decode in place.
+pub const BLOCKING_DECODE_SIZE: usize = 8 * 1024;
+
+/// Decodes the body with the encodings listed in the `Content-Encoding`
+/// header, in the reverse of the order they were applied in. The header is
+/// removed once the body is decoded, along with the `Content-Length` of the
+/// encoded body. The bodies that decode to more than `max_size` bytes fail,
+/// so that a small compressed response can't exhaust the memory.
+pub fn decode(headers: &mut HeaderMap, body: Bytes, max_size: usize) -> Result<Bytes> {
+    let encodings = headers
+        .get_all(CONTENT_ENCODING)
+        .iter()
+        .map(|value| {
+            value
+                .to_str()
+                .map_err(|_| anyhow!("Invalid Content-Encoding header {:?}", value))
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flat_map(|value| value.split(','))
+        .map(|encoding| encoding.trim().to_ascii_lowercase())
+        .filter(|encoding| !encoding.is_empty() && encoding != "identity")
+        .collect::<Vec<_>>();
+
+    if encodings.is_empty() {
+        return Ok(body);
+    }
+
+    let body = encodings.iter().rev().try_fold(body, |body, encoding| {
+        decode_with(encoding, &body, max_size)
+    })?;
+
+    headers.remove(CONTENT_ENCODING);
+    headers.remove(CONTENT_LENGTH);
+
+    Ok(body)
+}
+
+fn decode_with(encoding: &str, body: &[u8], max_size: usize) -> Result<Bytes> {
+    let mut decoded = Vec::new();
+    // one byte past the limit is read to tell the bodies that exceed it apart
+    let limit = max_size as u64 + 1;
+    let result = match encoding {
+        "gzip" | "x-gzip" => flate2::read::MultiGzDecoder::new(body)
+            .take(limit)
+            .read_to_end(&mut decoded),
+        "deflate" => flate2::read::ZlibDecoder::new(body)
+            .take(limit)
+            .read_to_end(&mut decoded),
+        "br" => brotli::Decompressor::new(body, 4096)
+            .take(limit)
+            .read_to_end(&mut decoded),
+        "zstd" => ruzstd::StreamingDecoder::new(body)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string()))
+            .and_then(|decoder| decoder.take(limit).read_to_end(&mut decoded)),
+        encoding => return Err(anyhow!("Unsupported Content-Encoding `{encoding}`")),
+    };
+
+    result.with_context(|| format!("Failed to decode the `{encoding}` encoded response body"))?;
+    if decoded.len() > max_size {
+        return Err(anyhow!(
+            "The `{encoding}` encoded response body exceeds the limit of {max_size} bytes once decoded"
+        ));
+    }
+
+    Ok(decoded.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    const BODY: &[u8] = br#"{"id":1,"name":"Leanne Graham"}"#;
+
+    fn headers(encoding: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, encoding.parse().unwrap());
+        headers.insert(CONTENT_LENGTH, "10".parse().unwrap());
+        headers
+    }
+
+    fn assert_decoded(encoding: &str, body: Vec<u8>) {
+        let mut headers = headers(encoding);
+        let actual = decode(&mut headers, body.into(), DEFAULT_MAX_DECODED_SIZE).unwrap();
+
+        assert_eq!(actual, Bytes::from_static(BODY));
+        assert!(headers.get(CONTENT_ENCODING).is_none());
+        assert!(headers.get(CONTENT_LENGTH).is_none());
+    }
+
+    fn gzip(body: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(body).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_gzip() {
+        assert_decoded("gzip", gzip(BODY));
+    }
+
+    #[test]
+    fn test_deflate() {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(BODY).unwrap();
+
+        assert_decoded("deflate", encoder.finish().unwrap());
+    }
+
+    #[test]
+    fn test_brotli() {
+        let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+        encoder.write_all(BODY).unwrap();
+
+        assert_decoded("br", encoder.into_inner());
+    }
+
+    #[test]
+    fn test_zstd() {
+        // BODY compressed with `zstd --no-check`, as ruzstd only decodes
+        let body = [
+            0x28, 0xb5, 0x2f, 0xfd, 0x00, 0x58, 0xf9, 0x00, 0x00, 0x7b, 0x22, 0x69, 0x64, 0x22,
+            0x3a, 0x31, 0x2c, 0x22, 0x6e, 0x61, 0x6d, 0x65, 0x22, 0x3a, 0x22, 0x4c, 0x65, 0x61,
+            0x6e, 0x6e, 0x65, 0x20, 0x47, 0x72, 0x61, 0x68, 0x61, 0x6d, 0x22, 0x7d,
+        ];
+
+        assert_decoded("zstd", body.to_vec());
+    }
+
+    #[test]
+    fn test_multiple_encodings() {
+        let body = gzip(&gzip(BODY));
+
+        assert_decoded("gzip, GZIP", body.clone());
+
+        let mut headers = HeaderMap::new();
+        headers.append(CONTENT_ENCODING, "gzip".parse().unwrap());
+        headers.append(CONTENT_ENCODING, "identity, gzip".parse().unwrap());
+        let actual = decode(&mut headers, body.into(), DEFAULT_MAX_DECODED_SIZE).unwrap();
+        assert_eq!(actual, Bytes::from_static(BODY));
+    }
+
+    #[test]
+    fn test_uncompressed() {
+        let mut uncompressed = HeaderMap::new();
+        uncompressed.insert(CONTENT_LENGTH, "31".parse().unwrap());
+        let actual = decode(
+            &mut uncompressed,
+            Bytes::from_static(BODY),
+            DEFAULT_MAX_DECODED_SIZE,
+        )
+        .unwrap();
+
+        assert_eq!(actual, Bytes::from_static(BODY));
+        assert!(uncompressed.get(CONTENT_LENGTH).is_some());
+
+        let actual = decode(
+            &mut headers("identity"),
+            Bytes::from_static(BODY),
+            DEFAULT_MAX_DECODED_SIZE,
+        )
+        .unwrap();
+        assert_eq!(actual, Bytes::from_static(BODY));
+    }
+
+    #[test]
+    fn test_unsupported_encoding() {
+        let error = decode(
+            &mut headers("compress"),
+            Bytes::from_static(BODY),
+            DEFAULT_MAX_DECODED_SIZE,
+        )
+        .unwrap_err();
+
+        assert_eq!(error.to_string(), "Unsupported Content-Encoding `compress`");
+    }
+
+    #[test]
+    fn test_mismatched_encoding() {
+        let error = decode(
+            &mut headers("zstd"),
+            gzip(BODY).into(),
+            DEFAULT_MAX_DECODED_SIZE,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to decode the `zstd` encoded response body"
+        );
+
+        let error = decode(
+            &mut headers("gzip"),
+            Bytes::from_static(BODY),
+            DEFAULT_MAX_DECODED_SIZE,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to decode the `gzip` encoded response body"
+        );
+    }
+
+    #[test]
+    fn test_max_decoded_size() {
+        let body = gzip(&[b' '; 1024]);
+
+        let actual = decode(&mut headers("gzip"), body.clone().into(), 1024).unwrap();
+        assert_eq!(actual.len(), 1024);
+
+        let error = decode(&mut headers("gzip"), body.into(), 1023).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The `gzip` encoded response body exceeds the limit of 1023 bytes once decoded"
+        );
+    }
+}
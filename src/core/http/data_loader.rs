@@ -11,8 +11,10 @@ use super::transformations::{BodyBatching, QueryBatching};
 use crate::core::config::group_by::GroupBy;
 use crate::core::config::Batch;
 use crate::core::data_loader::{DataLoader, Loader};
-use crate::core::http::{DataLoaderRequest, Response};
-use crate::core::json::JsonLike;
+use crate::core::http::{
+    transform_body, DataLoaderRequest, RequestContext, Response, UpstreamBudget,
+};
+use crate::core::json::{group_by_keys, JsonLike};
 use crate::core::runtime::TargetRuntime;
 use crate::core::transform::TransformerOps;
 use crate::core::Transform;
@@ -51,6 +53,82 @@ impl HttpDataLoader {
             .delay(Duration::from_millis(batch.delay as u64))
             .max_batch_size(batch.max_size.unwrap_or_default())
     }
+
+    /// Runs the body of the request that's sent for the keys through the
+    /// transformers of the runtime, once the bodies of the batch are merged.
+    /// The batch is shared by the requests, so the transformers get a context
+    /// that isn't bound to any of them.
+    fn transform(&self, mut request: reqwest::Request) -> anyhow::Result<reqwest::Request> {
+        if !self.runtime.body_transformers.is_empty() {
+            transform_body(&mut request, &RequestContext::new(self.runtime.clone()))?;
+        }
+
+        Ok(request)
+    }
+
+    /// Batches the GET requests whose items are identified by a composite key,
+    /// eg. a tenant and an id. The items of the response are matched back to
+    /// the requests by the full tuple of the key, so the requests that share
+    /// only a part of the key get the items of their own.
+    async fn load_with_composite_key(
+        &self,
+        group_by: &GroupBy,
+        dl_requests: &[DataLoaderRequest],
+    ) -> async_graphql::Result<HashMap<DataLoaderRequest, Response<ConstValue>>, Arc<anyhow::Error>>
+    {
+        let Some(base_dl_request) = dl_requests.first() else {
+            return Ok(HashMap::new());
+        };
+        let keys = group_by.keys();
+
+        let base_request = QueryBatching::with_keys(
+            &dl_requests.iter().skip(1).collect::<Vec<_>>(),
+            keys.clone(),
+        )
+        .transform(base_dl_request.to_request())
+        .to_result()
+        .map_err(|e| anyhow::anyhow!(e))?;
+        let base_request = self.transform(base_request)?;
+
+        let res = self
+            .runtime
+            .http
+            .execute(base_request)
+            .await?
+            .to_json::<ConstValue>()?;
+        let response_map = group_by_keys(&res.body, &group_by.path(), &keys);
+
+        #[allow(clippy::mutable_key_type)]
+        let mut hashmap = HashMap::with_capacity(dl_requests.len());
+        for dl_req in dl_requests.iter() {
+            let url = dl_req.url();
+            let query_set: HashMap<_, _> = url.query_pairs().collect();
+            let id = keys
+                .iter()
+                .map(|key| {
+                    query_set
+                        .get(*key)
+                        .map(|value| value.to_string())
+                        .ok_or(anyhow::anyhow!(
+                            "Unable to find key {} in query params",
+                            key
+                        ))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            let items = response_map.get(&id);
+            let body = if self.is_list {
+                ConstValue::List(items.into_iter().flatten().map(|&o| o.to_owned()).collect())
+            } else {
+                items
+                    .and_then(|items| items.first().cloned().cloned())
+                    .unwrap_or(ConstValue::Null)
+            };
+            hashmap.insert(dl_req.clone(), res.clone().body(body));
+        }
+
+        Ok(hashmap)
+    }
 }
 
 #[async_trait::async_trait]
@@ -64,12 +142,20 @@ impl Loader<DataLoaderRequest> for HttpDataLoader {
     ) -> async_graphql::Result<HashMap<DataLoaderRequest, Self::Value>, Self::Error> {
         if let Some(group_by) = &self.group_by {
             let query_name = group_by.key();
-            let mut dl_requests = keys.to_vec();
+            // the keys of the requests that are out of budget are left out of the batch
+            let mut dl_requests = UpstreamBudget::charge_batch(keys, DataLoaderRequest::budget);
+            if dl_requests.is_empty() {
+                return Ok(HashMap::new());
+            }
             if cfg!(debug_assertions) {
                 // Sort keys to build consistent URLs only in Testing environment.
                 dl_requests.sort_by(|a, b| a.to_request().url().cmp(b.to_request().url()));
             }
 
+            if group_by.is_composite() {
+                return self.load_with_composite_key(group_by, &dl_requests).await;
+            }
+
             if let Some(base_dl_request) = dl_requests.first().as_mut() {
                 let base_request = if base_dl_request.method() == http::Method::GET {
                     QueryBatching::new(
@@ -86,6 +172,7 @@ impl Loader<DataLoaderRequest> for HttpDataLoader {
                         .to_result()
                         .map_err(|e| anyhow::anyhow!(e))?
                 };
+                let base_request = self.transform(base_request)?;
 
                 // Dispatch request
                 let res = self
@@ -146,10 +233,18 @@ impl Loader<DataLoaderRequest> for HttpDataLoader {
                 Err(anyhow::anyhow!(error_message).into())
             }
         } else {
-            let results = keys.iter().map(|key| async {
-                let result = self.runtime.http.execute(key.to_request()).await;
-                (key.clone(), result)
-            });
+            // the keys are dispatched one by one, so each of them is charged and the
+            // ones that are out of budget are left out
+            let results =
+                keys.iter()
+                    .filter(|key| key.budget().charge().is_ok())
+                    .map(|key| async {
+                        let result = match self.transform(key.to_request()) {
+                            Ok(request) => self.runtime.http.execute(request).await,
+                            Err(err) => Err(err),
+                        };
+                        (key.clone(), result)
+                    });
 
             let results = join_all(results).await;
 
@@ -162,4 +257,151 @@ impl Loader<DataLoaderRequest> for HttpDataLoader {
             Ok(hashmap)
         }
     }
+
+    fn is_batched(&self) -> bool {
+        self.group_by.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use hyper::body::Bytes;
+    use serde_json::json;
+
+    use super::*;
+    use crate::core::http::BodyTransformer;
+    use crate::core::HttpIO;
+
+    /// Responds with an item for each pair of `tenant` and `id` in the query.
+    #[derive(Default)]
+    struct TestHttp {
+        hits: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl HttpIO for TestHttp {
+        async fn execute(&self, request: reqwest::Request) -> anyhow::Result<Response<Bytes>> {
+            self.hits.fetch_add(1, Ordering::SeqCst);
+
+            let values = request
+                .url()
+                .query_pairs()
+                .map(|(_, value)| value.to_string())
+                .collect::<Vec<_>>();
+            let items = values
+                .chunks(2)
+                .map(|pair| {
+                    let (tenant, id) = (&pair[0], &pair[1]);
+                    json!({ "tenant": tenant, "id": id.parse::<i64>().unwrap(), "name": format!("{tenant}-{id}") })
+                })
+                .collect::<Vec<_>>();
+
+            Ok(Response {
+                body: Bytes::from(serde_json::to_vec(&items)?),
+                ..Default::default()
+            })
+        }
+    }
+
+    fn request(tenant: &str, id: i64) -> DataLoaderRequest {
+        let url = format!("http://localhost:8080/users?tenant={tenant}&id={id}");
+        let request = reqwest::Request::new(reqwest::Method::GET, url.parse().unwrap());
+        DataLoaderRequest::new(request, Default::default())
+    }
+
+    /// Responds with the body of the request.
+    struct EchoHttp;
+
+    #[async_trait::async_trait]
+    impl HttpIO for EchoHttp {
+        async fn execute(&self, request: reqwest::Request) -> anyhow::Result<Response<Bytes>> {
+            let body = request.body().and_then(|body| body.as_bytes()).unwrap();
+            Ok(Response { body: Bytes::from(body.to_vec()), ..Default::default() })
+        }
+    }
+
+    /// Records the bodies that it transforms.
+    #[derive(Default)]
+    struct Record(std::sync::Mutex<Vec<serde_json::Value>>);
+
+    impl BodyTransformer for Record {
+        fn transform(
+            &self,
+            body: &mut serde_json::Value,
+            _: &reqwest::Request,
+            _: &RequestContext,
+        ) {
+            self.0.lock().unwrap().push(body.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transform_batched_body() {
+        let record = Arc::new(Record::default());
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.http = Arc::new(EchoHttp);
+        runtime.add_body_transformer(record.clone());
+
+        let group_by = GroupBy::new(vec!["id".to_string()], Some("id".to_string()));
+        let loader = HttpDataLoader::new(runtime, Some(group_by), false);
+        let keys = [1, 2].map(|id| {
+            let request = reqwest::Client::new()
+                .post("http://localhost:8080/users")
+                .body(format!(r#"{{"id":{id}}}"#))
+                .build()
+                .unwrap();
+            DataLoaderRequest::new(request, Default::default())
+                .with_batching_value(Some(id.to_string()))
+        });
+
+        loader.load(&keys).await.unwrap();
+
+        // the merged body is transformed once
+        assert_eq!(
+            *record.0.lock().unwrap(),
+            [json!([{ "id": 1 }, { "id": 2 }])]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_composite_key() {
+        let http = Arc::new(TestHttp::default());
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.http = http.clone();
+
+        let group_by = GroupBy::composite(vec![], vec!["tenant".to_string(), "id".to_string()]);
+        let loader = HttpDataLoader::new(runtime, Some(group_by), false);
+        let keys = vec![request("a", 1), request("b", 1), request("a", 2)];
+
+        #[allow(clippy::mutable_key_type)]
+        let actual = loader.load(&keys).await.unwrap();
+
+        assert_eq!(http.hits.load(Ordering::SeqCst), 1);
+        for (key, expected) in keys.iter().zip(["a-1", "b-1", "a-2"]) {
+            let name = actual[key].body.get_key("name").cloned();
+            assert_eq!(name, Some(ConstValue::String(expected.to_string())));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_budget_leaves_the_batch() {
+        let http = Arc::new(TestHttp::default());
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.http = http.clone();
+
+        let group_by = GroupBy::composite(vec![], vec!["tenant".to_string(), "id".to_string()]);
+        let loader = HttpDataLoader::new(runtime, Some(group_by), false);
+        let exhausted = UpstreamBudget::new(Arc::new(AtomicUsize::new(1)), Some(1));
+        let keys = vec![request("a", 1).with_budget(exhausted), request("b", 1)];
+
+        #[allow(clippy::mutable_key_type)]
+        let actual = loader.load(&keys).await.unwrap();
+
+        assert_eq!(http.hits.load(Ordering::SeqCst), 1);
+        assert!(!actual.contains_key(&keys[0]));
+        let name = actual[&keys[1]].body.get_key("name").cloned();
+        assert_eq!(name, Some(ConstValue::String("b-1".to_string())));
+    }
 }
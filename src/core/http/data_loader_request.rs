@@ -4,24 +4,41 @@ use std::ops::Deref;
 
 use tailcall_hasher::TailcallHasher;
 
+use super::UpstreamBudget;
+
 #[derive(Debug)]
 pub struct DataLoaderRequest {
     request: reqwest::Request,
     headers: BTreeSet<String>,
     /// used for request body batching.
     batching_value: Option<String>,
+    /// charged by the data loader for the requests that it dispatches.
+    budget: UpstreamBudget,
 }
 
 impl DataLoaderRequest {
     pub fn new(req: reqwest::Request, headers: BTreeSet<String>) -> Self {
         // TODO: req should already have headers builtin, no?
-        Self { request: req, headers, batching_value: None }
+        Self {
+            request: req,
+            headers,
+            batching_value: None,
+            budget: UpstreamBudget::default(),
+        }
     }
 
     pub fn with_batching_value(self, body: Option<String>) -> Self {
         Self { batching_value: body, ..self }
     }
 
+    pub fn with_budget(self, budget: UpstreamBudget) -> Self {
+        Self { budget, ..self }
+    }
+
+    pub fn budget(&self) -> &UpstreamBudget {
+        &self.budget
+    }
+
     pub fn batching_value(&self) -> Option<&String> {
         self.batching_value.as_ref()
     }
@@ -81,6 +98,7 @@ impl Clone for DataLoaderRequest {
 
         DataLoaderRequest::new(req, self.headers.clone())
             .with_batching_value(self.batching_value.clone())
+            .with_budget(self.budget.clone())
     }
 }
 
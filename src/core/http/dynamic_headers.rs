@@ -0,0 +1,148 @@
+use async_graphql::Value as ConstValue;
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+
+use crate::core::mustache::{Mustache, Segment};
+use crate::core::path::{PathValue, ValueString};
+
+/// The headers that can only be set by the dynamic headers when they are
+/// explicitly allowed, they identify the client or the connection to the
+/// upstream.
+const DENIED: &[&str] = &[
+    "authorization",
+    "connection",
+    "content-length",
+    "cookie",
+    "host",
+    "keep-alive",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// A map of headers resolved from an object, eg. an argument of the field,
+/// whose keys are the names of the headers.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DynamicHeaders {
+    /// An expression of the object, eg. `{{.args.headers}}`.
+    pub value: Mustache,
+    /// The lowercase names of the denied headers that can be set anyway.
+    pub allow: Vec<String>,
+}
+
+impl DynamicHeaders {
+    pub fn new(value: Mustache, allow: Vec<String>) -> Self {
+        Self {
+            value,
+            allow: allow.iter().map(|name| name.to_lowercase()).collect(),
+        }
+    }
+
+    fn is_denied(&self, name: &HeaderName) -> bool {
+        DENIED.contains(&name.as_str()) && !self.allow.iter().any(|allow| allow == name.as_str())
+    }
+
+    /// The headers of the object, the denied ones and the ones that aren't
+    /// valid headers are left out. The values that aren't strings are sent
+    /// as JSON, and the `null` ones are left out.
+    pub fn render<C: PathValue>(&self, ctx: &C) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let [Segment::Expression(path)] = self.value.segments().as_slice() else {
+            return headers;
+        };
+        let object = match ctx.raw_value(path) {
+            Some(ValueString::Value(value)) => value.into_owned(),
+            Some(ValueString::String(value)) => {
+                serde_json::from_str(&value).unwrap_or(ConstValue::Null)
+            }
+            None => ConstValue::Null,
+        };
+        let ConstValue::Object(object) = object else {
+            return headers;
+        };
+
+        for (name, value) in object {
+            let value = match value {
+                ConstValue::Null => continue,
+                ConstValue::String(value) => value,
+                value => value.to_string(),
+            };
+            let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(&value),
+            ) else {
+                continue;
+            };
+            if self.is_denied(&name) {
+                tracing::warn!("The dynamic header {} is denied", name);
+                continue;
+            }
+            headers.append(name, value);
+        }
+
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use serde_json::json;
+
+    use super::*;
+
+    struct Context(ConstValue);
+
+    impl PathValue for Context {
+        fn raw_value<'a, T: AsRef<str>>(&'a self, _: &[T]) -> Option<ValueString<'a>> {
+            Some(ValueString::Value(Cow::Borrowed(&self.0)))
+        }
+    }
+
+    fn context(value: serde_json::Value) -> Context {
+        Context(ConstValue::from_json(value).unwrap())
+    }
+
+    #[test]
+    fn test_render() {
+        let headers = DynamicHeaders::new(Mustache::parse("{{.args.headers}}"), vec![]);
+        let ctx = context(json!({
+            "x-tenant": "acme",
+            "x-retries": 3,
+            "x-missing": null,
+            "invalid name": "foo",
+        }));
+
+        let actual = headers.render(&ctx);
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual.get("x-tenant").unwrap(), "acme");
+        assert_eq!(actual.get("x-retries").unwrap(), "3");
+    }
+
+    #[test]
+    fn test_denied() {
+        let ctx = context(json!({
+            "x-tenant": "acme",
+            "Host": "evil.com",
+            "authorization": "Bearer foo",
+        }));
+
+        let headers = DynamicHeaders::new(Mustache::parse("{{.args.headers}}"), vec![]);
+        let actual = headers.render(&ctx);
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual.get("x-tenant").unwrap(), "acme");
+
+        // only the allowed header can be set
+        let headers = DynamicHeaders::new(
+            Mustache::parse("{{.args.headers}}"),
+            vec!["Authorization".to_string()],
+        );
+        let actual = headers.render(&ctx);
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual.get("authorization").unwrap(), "Bearer foo");
+        assert!(actual.get("host").is_none());
+    }
+}
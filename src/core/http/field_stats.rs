@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use async_graphql::{Name, Value as ConstValue};
+use indexmap::IndexMap;
+
+/// How the fields of a request were resolved, reported in the `debug`
+/// extension of the response when `@server(debugStats)` is enabled. The
+/// fields are identified by their response path, eg. `users.posts`, so the
+/// elements of a list share the stats of their field.
+#[derive(Default, Debug)]
+pub struct FieldStats {
+    fields: Mutex<BTreeMap<String, Stats>>,
+}
+
+#[derive(Default, Debug)]
+struct Stats {
+    /// The value was read from the cache of `@cache`.
+    cache_hit: bool,
+    /// The value was shared with an identical upstream call in flight.
+    deduped: bool,
+    /// The largest batch of the data loader the field was loaded in.
+    batch_size: Option<usize>,
+    /// Number of the upstream calls that were made to resolve the field, the
+    /// calls of a batch are counted one by one like by
+    /// `@server(maxUpstreamCalls)`.
+    upstream_calls: usize,
+}
+
+impl FieldStats {
+    fn update(&self, path: String, update: impl FnOnce(&mut Stats)) {
+        update(self.fields.lock().unwrap().entry(path).or_default())
+    }
+
+    pub fn cache_hit(&self, path: String) {
+        self.update(path, |stats| stats.cache_hit = true)
+    }
+
+    pub fn deduped(&self, path: String) {
+        self.update(path, |stats| stats.deduped = true)
+    }
+
+    /// The values that the data loader had in its cache aren't batched, their
+    /// `batch_size` is `0` and isn't reported.
+    pub fn batched(&self, path: String, batch_size: usize) {
+        if batch_size > 0 {
+            self.update(path, |stats| {
+                stats.batch_size = stats.batch_size.max(Some(batch_size))
+            })
+        }
+    }
+
+    pub fn upstream_call(&self, path: String) {
+        self.update(path, |stats| stats.upstream_calls += 1)
+    }
+
+    /// The stats as the value of the `debug` extension, ie.
+    /// `{ fields: { <path>: { cacheHit, deduped, batchSize, upstreamCalls } }
+    /// }`
+    pub fn to_value(&self) -> ConstValue {
+        let fields = self
+            .fields
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(path, stats)| {
+                let mut value = IndexMap::from([
+                    (Name::new("cacheHit"), ConstValue::from(stats.cache_hit)),
+                    (Name::new("deduped"), ConstValue::from(stats.deduped)),
+                    (
+                        Name::new("upstreamCalls"),
+                        ConstValue::from(stats.upstream_calls as u64),
+                    ),
+                ]);
+                if let Some(batch_size) = stats.batch_size {
+                    value.insert(Name::new("batchSize"), ConstValue::from(batch_size as u64));
+                }
+                (Name::new(path), ConstValue::Object(value))
+            })
+            .collect();
+
+        ConstValue::Object(IndexMap::from([(
+            Name::new("fields"),
+            ConstValue::Object(fields),
+        )]))
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::{bail, Result};
+use serde_json::Value;
+use url::form_urlencoded::byte_serialize;
+
+/// Encodes the JSON body as `application/x-www-form-urlencoded`.
+///
+/// The keys of the nested objects and the indices of the arrays are appended
+/// to the key of their parent in brackets, eg. `{"filter": {"status":
+/// "active", "tags": ["a", "b"]}}` is encoded as
+/// `filter[status]=active&filter[tags][0]=a&filter[tags][1]=b`. The arrays of
+/// objects are encoded the same way, eg. `items[0][id]=1`. The brackets are
+/// left unescaped, while the keys and the values in them are escaped. `null`
+/// is encoded as an empty value, and the empty objects and arrays are left
+/// out. A list of key and value pairs is encoded in its order, so that a key
+/// can be repeated, eg. `[["tag", "a"], ["tag", "b"]]` is encoded as
+/// `tag=a&tag=b`.
+pub fn encode(value: &Value) -> Result<String> {
+    let mut pairs = vec![];
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                flatten(escape(key), value, &mut pairs);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                let [Value::String(key), value] =
+                    item.as_array().map(Vec::as_slice).unwrap_or_default()
+                else {
+                    bail!("Only the pairs of a key and a value can be encoded as form data");
+                };
+                flatten(escape(key), value, &mut pairs);
+            }
+        }
+        _ => bail!("Only objects and lists of pairs can be encoded as form data"),
+    }
+
+    Ok(pairs
+        .into_iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join("&"))
+}
+
+fn flatten(key: String, value: &Value, pairs: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) => {
+            for (name, value) in map {
+                flatten(format!("{key}[{}]", escape(name)), value, pairs);
+            }
+        }
+        Value::Array(values) => {
+            for (index, value) in values.iter().enumerate() {
+                flatten(format!("{key}[{index}]"), value, pairs);
+            }
+        }
+        Value::Null => pairs.push((key, String::new())),
+        Value::String(value) => pairs.push((key, escape(value))),
+        value => pairs.push((key, value.to_string())),
+    }
+}
+
+fn escape(input: &str) -> String {
+    byte_serialize(input.as_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_flat() {
+        let actual = encode(&json!({"name": "foo bar", "age": 20, "active": true, "bio": null}));
+
+        assert_eq!(actual.unwrap(), "name=foo+bar&age=20&active=true&bio=");
+    }
+
+    #[test]
+    fn test_nested() {
+        let body = json!({
+            "filter": {
+                "status": "active",
+                "tags": ["a", "b & c"],
+                "owner": {"id": 1}
+            },
+            "items": [
+                {"id": 1, "options": {"gift": true}},
+                {"id": 2, "options": {}}
+            ],
+            "sort": []
+        });
+
+        assert_eq!(
+            encode(&body).unwrap(),
+            [
+                "filter[status]=active",
+                "filter[tags][0]=a",
+                "filter[tags][1]=b+%26+c",
+                "filter[owner][id]=1",
+                "items[0][id]=1",
+                "items[0][options][gift]=true",
+                "items[1][id]=2",
+            ]
+            .join("&")
+        );
+    }
+
+    #[test]
+    fn test_escaped_keys() {
+        let actual = encode(&json!({"a b": {"c[d]": "e"}}));
+
+        assert_eq!(actual.unwrap(), "a+b[c%5Bd%5D]=e");
+    }
+
+    #[test]
+    fn test_pairs() {
+        let actual = encode(&json!([["tag", "a"], ["tag", "b c"], ["filter", {"id": 1}]]));
+
+        assert_eq!(actual.unwrap(), "tag=a&tag=b+c&filter[id]=1");
+    }
+
+    #[test]
+    fn test_not_an_object() {
+        assert!(encode(&json!(["a"])).is_err());
+        assert!(encode(&json!([["a", "b", "c"]])).is_err());
+        assert!(encode(&json!([[1, "a"]])).is_err());
+        assert!(encode(&json!("a")).is_err());
+    }
+}
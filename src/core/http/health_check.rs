@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures_util::future::join_all;
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use crate::core::blueprint::{Blueprint, Definition};
+use crate::core::config;
+use crate::core::ir::model::IO;
+use crate::core::ir::IrVisitor;
+use crate::core::runtime::TargetRuntime;
+
+/// Status of an upstream as observed by the last probe.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum UpstreamHealth {
+    Up,
+    Down { error: String },
+}
+
+/// Response of the status endpoint when the health check is enabled.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HealthReport {
+    pub message: &'static str,
+    pub upstreams: BTreeMap<String, UpstreamHealth>,
+}
+
+impl HealthReport {
+    fn new(upstreams: BTreeMap<String, UpstreamHealth>) -> Self {
+        let is_ready = upstreams
+            .values()
+            .all(|health| matches!(health, UpstreamHealth::Up));
+        let message = if is_ready { "ready" } else { "degraded" };
+
+        Self { message, upstreams }
+    }
+}
+
+/// An upstream that can be probed, identified by its origin eg.
+/// `http://foo.com:8080`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Upstream {
+    origin: String,
+    is_grpc: bool,
+}
+
+/// Collects the upstreams of the `@http` and `@grpc` resolvers. Upstreams
+/// whose host is only known at runtime are skipped.
+#[derive(Default)]
+struct Upstreams<'a> {
+    hosts: Option<&'a BTreeSet<String>>,
+    upstreams: BTreeSet<Upstream>,
+}
+
+impl Upstreams<'_> {
+    fn insert(&mut self, url: &str, is_grpc: bool) {
+        let Ok(url) = url::Url::parse(url) else {
+            return;
+        };
+        let Some(host) = url.host_str().filter(|host| !host.contains('{')) else {
+            return;
+        };
+
+        if self.hosts.map_or(true, |hosts| hosts.contains(host)) {
+            let origin = url.origin().ascii_serialization();
+            self.upstreams.insert(Upstream { origin, is_grpc });
+        }
+    }
+}
+
+impl IrVisitor for Upstreams<'_> {
+    fn visit_io(&mut self, io: &IO) {
+        match io {
+            IO::Http { req_template, .. } => self.insert(&req_template.root_url.to_string(), false),
+            IO::Grpc { req_template, .. } => self.insert(&req_template.url.to_string(), true),
+            IO::GraphQL { .. } | IO::Js { .. } => {}
+        }
+    }
+}
+
+/// Registry of the upstreams that are probed by the status endpoint. The
+/// results of the probes are cached for the configured interval, concurrent
+/// checks wait for the running probes instead of starting new ones.
+pub struct HealthCheck {
+    upstreams: BTreeSet<Upstream>,
+    timeout: Duration,
+    interval: Duration,
+    last_report: Mutex<Option<(Instant, Arc<HealthReport>)>>,
+}
+
+impl HealthCheck {
+    pub fn new(blueprint: &Blueprint, config: &config::HealthCheck) -> Self {
+        let hosts: BTreeSet<String> = config.upstreams.iter().cloned().collect();
+        let mut visitor = Upstreams {
+            hosts: (!hosts.is_empty()).then_some(&hosts),
+            ..Default::default()
+        };
+
+        for def in blueprint.definitions.iter() {
+            if let Definition::Object(def) = def {
+                for ir in def
+                    .fields
+                    .iter()
+                    .filter_map(|field| field.resolver.as_ref())
+                {
+                    ir.accept(&mut visitor);
+                }
+            }
+        }
+
+        Self::with_upstreams(visitor.upstreams, config)
+    }
+
+    fn with_upstreams(upstreams: BTreeSet<Upstream>, config: &config::HealthCheck) -> Self {
+        Self {
+            upstreams,
+            timeout: Duration::from_millis(config.timeout.unwrap_or(1000)),
+            interval: Duration::from_secs(config.interval.unwrap_or(10)),
+            last_report: Mutex::new(None),
+        }
+    }
+
+    /// Returns the status of all the upstreams, probing them if the last report
+    /// is older than the interval.
+    pub async fn check(&self, runtime: &TargetRuntime) -> Arc<HealthReport> {
+        let mut last_report = self.last_report.lock().await;
+
+        if let Some((checked_at, report)) = last_report.as_ref() {
+            if checked_at.elapsed() < self.interval {
+                return report.clone();
+            }
+        }
+
+        let probes = self
+            .upstreams
+            .iter()
+            .map(|upstream| self.probe(runtime, upstream));
+        let upstreams = join_all(probes).await.into_iter().collect();
+        let report = Arc::new(HealthReport::new(upstreams));
+
+        *last_report = Some((Instant::now(), report.clone()));
+        report
+    }
+
+    async fn probe(
+        &self,
+        runtime: &TargetRuntime,
+        upstream: &Upstream,
+    ) -> (String, UpstreamHealth) {
+        let health = match probe(runtime, upstream, self.timeout).await {
+            Ok(()) => UpstreamHealth::Up,
+            Err(error) => UpstreamHealth::Down { error },
+        };
+
+        (upstream.origin.clone(), health)
+    }
+}
+
+/// Sends a request to the origin of the upstream. Any response that isn't a
+/// server error means that the upstream is reachable.
+async fn probe(
+    runtime: &TargetRuntime,
+    upstream: &Upstream,
+    timeout: Duration,
+) -> Result<(), String> {
+    let url = reqwest::Url::parse(&upstream.origin).map_err(|e| e.to_string())?;
+    let request = reqwest::Request::new(reqwest::Method::GET, url);
+    let http = if upstream.is_grpc {
+        &runtime.http2_only
+    } else {
+        &runtime.http
+    };
+
+    let Ok(result) = tokio::time::timeout(timeout, http.execute(request)).await else {
+        return Err(format!("Timed out after {}ms", timeout.as_millis()));
+    };
+
+    match result {
+        Ok(response) if response.status.is_server_error() => {
+            Err(format!("Responded with {}", response.status))
+        }
+        Ok(_) => Ok(()),
+        Err(error) => match error.downcast_ref::<reqwest::Error>() {
+            Some(error)
+                if error
+                    .status()
+                    .is_some_and(|status| !status.is_server_error()) =>
+            {
+                Ok(())
+            }
+            _ => Err(error.to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use hyper::body::Bytes;
+    use serde_json::json;
+
+    use super::*;
+    use crate::core::http::Response;
+    use crate::core::runtime::test::init;
+    use crate::core::HttpIO;
+
+    #[derive(Default)]
+    struct TestHttp {
+        hits: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl HttpIO for TestHttp {
+        async fn execute(&self, request: reqwest::Request) -> anyhow::Result<Response<Bytes>> {
+            self.hits.fetch_add(1, Ordering::SeqCst);
+
+            let mut response = Response::empty();
+            match request.url().host_str() {
+                Some("up.com") => {}
+                Some("missing.com") => response.status = reqwest::StatusCode::NOT_FOUND,
+                Some("slow.com") => tokio::time::sleep(Duration::from_secs(10)).await,
+                _ => response.status = reqwest::StatusCode::SERVICE_UNAVAILABLE,
+            }
+
+            Ok(response)
+        }
+    }
+
+    fn health_check(origins: &[&str]) -> HealthCheck {
+        let upstreams = origins
+            .iter()
+            .map(|origin| Upstream { origin: origin.to_string(), is_grpc: false })
+            .collect();
+        let config =
+            config::HealthCheck { upstreams: vec![], timeout: Some(50), interval: Some(60) };
+
+        HealthCheck::with_upstreams(upstreams, &config)
+    }
+
+    fn runtime(http: Arc<TestHttp>) -> TargetRuntime {
+        let mut runtime = init(None);
+        runtime.http = http;
+        runtime
+    }
+
+    #[test]
+    fn test_upstreams() {
+        let hosts = BTreeSet::from(["foo.com".to_string(), "grpc.com".to_string()]);
+        let mut visitor = Upstreams { hosts: Some(&hosts), ..Default::default() };
+
+        visitor.insert("http://foo.com:8080/users", false);
+        visitor.insert("http://foo.com:8080/posts", false);
+        visitor.insert("http://bar.com/users", false);
+        visitor.insert("http://{{.env.HOST}}/users", false);
+        visitor.insert("https://grpc.com/news.NewsService/GetNews", true);
+
+        let actual: Vec<_> = visitor
+            .upstreams
+            .into_iter()
+            .map(|upstream| (upstream.origin, upstream.is_grpc))
+            .collect();
+        let expected = vec![
+            ("http://foo.com:8080".to_string(), false),
+            ("https://grpc.com".to_string(), true),
+        ];
+
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_check() {
+        let http = Arc::new(TestHttp::default());
+        let health_check = health_check(&[
+            "http://up.com",
+            "http://missing.com",
+            "http://down.com",
+            "http://slow.com",
+        ]);
+
+        let report = health_check.check(&runtime(http)).await;
+
+        assert_eq!(
+            serde_json::to_value(report.as_ref()).unwrap(),
+            json!({
+                "message": "degraded",
+                "upstreams": {
+                    "http://down.com": { "status": "down", "error": "Responded with 503 Service Unavailable" },
+                    "http://missing.com": { "status": "up" },
+                    "http://slow.com": { "status": "down", "error": "Timed out after 50ms" },
+                    "http://up.com": { "status": "up" },
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_cached() {
+        let http = Arc::new(TestHttp::default());
+        let runtime = runtime(http.clone());
+        let health_check = health_check(&["http://up.com", "http://missing.com"]);
+
+        let first = health_check.check(&runtime).await;
+        let second = health_check.check(&runtime).await;
+
+        assert_eq!(first.message, "ready");
+        assert_eq!(first, second);
+        assert_eq!(http.hits.load(Ordering::SeqCst), 2);
+    }
+}
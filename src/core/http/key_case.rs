@@ -0,0 +1,162 @@
+use async_graphql::{Name, Value as ConstValue};
+
+use crate::core::config::KeyCase;
+
+/// Renames the keys of the request body to the case of the API.
+pub fn encode_keys(key_case: KeyCase, value: serde_json::Value) -> serde_json::Value {
+    match key_case {
+        KeyCase::CamelToSnake => rename_json(value, &to_snake_case),
+        KeyCase::SnakeToCamel => rename_json(value, &to_camel_case),
+    }
+}
+
+/// Renames the keys of the response of the API back to the case of the
+/// schema.
+pub fn decode_keys(key_case: KeyCase, value: ConstValue) -> ConstValue {
+    match key_case {
+        KeyCase::CamelToSnake => rename_const(value, &to_camel_case),
+        KeyCase::SnakeToCamel => rename_const(value, &to_snake_case),
+    }
+}
+
+fn rename_json(value: serde_json::Value, rename: &impl Fn(&str) -> String) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => map
+            .into_iter()
+            .map(|(key, value)| (rename(&key), rename_json(value, rename)))
+            .collect(),
+        serde_json::Value::Array(list) => list
+            .into_iter()
+            .map(|value| rename_json(value, rename))
+            .collect(),
+        value => value,
+    }
+}
+
+fn rename_const(value: ConstValue, rename: &impl Fn(&str) -> String) -> ConstValue {
+    match value {
+        ConstValue::Object(map) => ConstValue::Object(
+            map.into_iter()
+                .map(|(key, value)| (Name::new(rename(&key)), rename_const(value, rename)))
+                .collect(),
+        ),
+        ConstValue::List(list) => ConstValue::List(
+            list.into_iter()
+                .map(|value| rename_const(value, rename))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// `userName` to `user_name`. A word starts at an uppercase letter that
+/// follows a lowercase letter or a digit, or that ends an acronym eg.
+/// `HTTPServer` is `http_server`. The digits don't start a word.
+fn to_snake_case(key: &str) -> String {
+    let chars = key.chars().collect::<Vec<_>>();
+    let mut snake = String::with_capacity(key.len() + 4);
+
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next = chars.get(i + 1);
+            let starts_word = prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next.is_some_and(|next| next.is_lowercase()));
+            if starts_word {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+
+    snake
+}
+
+/// `user_name` to `userName`. The leading underscores are kept eg. `_id`.
+fn to_camel_case(key: &str) -> String {
+    let name = key.trim_start_matches('_');
+    let mut camel = key[..key.len() - name.len()].to_string();
+
+    for (i, word) in name.split('_').filter(|word| !word.is_empty()).enumerate() {
+        let mut chars = word.chars();
+        match chars.next() {
+            Some(first) if i > 0 => {
+                camel.extend(first.to_uppercase());
+                camel.push_str(chars.as_str());
+            }
+            _ => camel.push_str(word),
+        }
+    }
+
+    camel
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_to_snake_case() {
+        assert_eq!(to_snake_case("userName"), "user_name");
+        assert_eq!(to_snake_case("user_name"), "user_name");
+        assert_eq!(to_snake_case("userID"), "user_id");
+        assert_eq!(to_snake_case("HTTPServer"), "http_server");
+        assert_eq!(to_snake_case("addressLine1"), "address_line1");
+        assert_eq!(to_snake_case("id"), "id");
+    }
+
+    #[test]
+    fn test_to_camel_case() {
+        assert_eq!(to_camel_case("user_name"), "userName");
+        assert_eq!(to_camel_case("userName"), "userName");
+        assert_eq!(to_camel_case("user_id"), "userId");
+        assert_eq!(to_camel_case("address_line1"), "addressLine1");
+        assert_eq!(to_camel_case("_id"), "_id");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let body = json!({
+            "userName": "foo",
+            "homeAddress": { "zipCode": "123", "addressLine1": "bar" },
+            "tags": [{ "tagName": "baz" }],
+            "already_snake": true,
+        });
+
+        let upstream = encode_keys(KeyCase::CamelToSnake, body);
+        assert_eq!(
+            upstream,
+            json!({
+                "user_name": "foo",
+                "home_address": { "zip_code": "123", "address_line1": "bar" },
+                "tags": [{ "tag_name": "baz" }],
+                "already_snake": true,
+            })
+        );
+
+        let response = ConstValue::from_json(upstream).unwrap();
+        let response = decode_keys(KeyCase::CamelToSnake, response)
+            .into_json()
+            .unwrap();
+        assert_eq!(
+            response,
+            json!({
+                "userName": "foo",
+                "homeAddress": { "zipCode": "123", "addressLine1": "bar" },
+                "tags": [{ "tagName": "baz" }],
+                "alreadySnake": true,
+            })
+        );
+
+        // the acronyms are a word of their own
+        let response = decode_keys(
+            KeyCase::CamelToSnake,
+            ConstValue::from_json(encode_keys(KeyCase::CamelToSnake, json!({ "userID": 1 })))
+                .unwrap(),
+        );
+        assert_eq!(response.into_json().unwrap(), json!({ "userId": 1 }));
+    }
+}
@@ -27,6 +27,15 @@ pub enum Method {
 }
 
 impl Method {
+    /// Whether the method only reads from the upstream, so the concurrent
+    /// requests can share a response.
+    pub fn is_safe(&self) -> bool {
+        matches!(
+            self,
+            Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+        )
+    }
+
     pub fn to_hyper(self) -> http::Method {
         match self {
             Method::GET => http::Method::GET,
@@ -1,26 +1,58 @@
+pub use adaptive_timeout::AdaptiveTimeouts;
+pub use auth_token::{AuthTokenHttp, AuthTokenProvider};
+pub use body_transformer::{transform_body, BodyTransformer};
+pub use bulkhead::{with_priority, BulkheadHttp, Bulkheads};
 pub use cache::*;
+pub use connection::Connection;
 pub use data_loader::*;
 pub use data_loader_request::*;
+pub use dynamic_headers::DynamicHeaders;
+pub use field_stats::FieldStats;
+pub use health_check::{HealthCheck, HealthReport, UpstreamHealth};
 use http::HeaderValue;
+pub use key_case::{decode_keys, encode_keys};
 pub use method::Method;
-pub use query_encoder::QueryEncoder;
+pub use msgpack::ResponseEncoding;
+pub use redirect::RedirectPolicy;
 pub use request_context::RequestContext;
-pub use request_handler::{handle_request, API_URL_PREFIX};
-pub use request_template::RequestTemplate;
+pub use request_handler::{create_request_context, handle_request, API_URL_PREFIX};
+pub use request_template::{RequestTemplate, DEFAULT_MAX_RAW_BODY_SIZE};
 pub use response::*;
+pub use response_schema::ResponseSchema;
+pub use upload::UploadStream;
+pub use upstream_budget::UpstreamBudget;
 
+mod adaptive_timeout;
+mod auth_token;
+mod body_transformer;
+mod bulkhead;
 mod cache;
+mod cache_admin;
+mod connection;
+pub mod content_encoding;
 mod data_loader;
 mod data_loader_request;
+mod dynamic_headers;
+mod field_stats;
+mod form_encoder;
+mod health_check;
+mod key_case;
 mod method;
+mod msgpack;
+pub mod ndjson;
 mod query_encoder;
+mod redirect;
 mod request_context;
 mod request_handler;
 mod request_template;
 mod response;
+mod response_schema;
 pub mod showcase;
 mod telemetry;
 mod transformations;
+mod upload;
+mod upstream_budget;
+pub mod upstream_size;
 
 pub static TAILCALL_HTTPS_ORIGIN: HeaderValue = HeaderValue::from_static("https://tailcall.run");
 pub static TAILCALL_HTTP_ORIGIN: HeaderValue = HeaderValue::from_static("http://tailcall.run");
@@ -0,0 +1,197 @@
+use http::header::{self, HeaderMap};
+use serde::Serialize;
+
+pub const APPLICATION_MSGPACK: &str = "application/msgpack";
+
+/// Format of the body of the GraphQL responses, negotiated with the `Accept`
+/// header of the request. The first of the supported media types listed in the
+/// header is used, the responses are JSON for any other ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ResponseEncoding {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl ResponseEncoding {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|accept| accept.to_str().ok())
+            .flat_map(|accept| accept.split(','))
+            .filter_map(|media_type| media_type.split(';').next())
+            .find_map(|media_type| match media_type.trim() {
+                "application/json" => Some(Self::Json),
+                "application/msgpack" | "application/x-msgpack" => Some(Self::MessagePack),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::MessagePack => APPLICATION_MSGPACK,
+        }
+    }
+
+    /// Serializes the value in the format, straight from the value.
+    pub fn to_vec<T: Serialize + ?Sized>(self, value: &T) -> anyhow::Result<Vec<u8>> {
+        Ok(match self {
+            Self::Json => serde_json::to_vec(value)?,
+            // the structs are written as maps, the same as the objects of JSON
+            Self::MessagePack => rmp_serde::to_vec_named(value)?,
+        })
+    }
+
+    /// Joins the serialized values into a list of the format.
+    pub fn join(self, items: &[&[u8]]) -> Vec<u8> {
+        let mut buf =
+            Vec::with_capacity(items.iter().map(|item| item.len() + 1).sum::<usize>() + 5);
+        match self {
+            Self::Json => {
+                buf.push(b'[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        buf.push(b',');
+                    }
+                    buf.extend_from_slice(item);
+                }
+                buf.push(b']');
+            }
+            Self::MessagePack => {
+                // writing to a vector can't fail
+                let _ = rmp::encode::write_array_len(&mut buf, items.len() as u32);
+                items.iter().for_each(|item| buf.extend_from_slice(item));
+            }
+        }
+        buf
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Value;
+
+    use super::ResponseEncoding;
+
+    fn from_slice(bytes: &[u8]) -> Value {
+        rmp_serde::from_slice(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = serde_json::json!({
+            "data": {
+                "users": [
+                    {"id": 1, "name": "Leanne", "score": 0.5, "active": true},
+                    {"id": 300, "name": "a".repeat(40), "score": -1.25, "active": false},
+                ],
+                "ints": [0, 127, 128, 255, 256, 65535, 65536, u32::MAX, u64::MAX],
+                "negative": [-1, -32, -33, -128, -129, -32768, -32769, i32::MIN, i64::MIN],
+                "long": (0..20).collect::<Vec<_>>(),
+                "empty": {},
+                "missing": null,
+            },
+            "errors": [{"message": "Failed", "path": ["users", 1]}],
+        });
+
+        assert_eq!(
+            from_slice(&ResponseEncoding::MessagePack.to_vec(&value).unwrap()),
+            value
+        );
+    }
+
+    #[test]
+    fn test_compact() {
+        let to_vec = |value| ResponseEncoding::MessagePack.to_vec(&value).unwrap();
+        assert_eq!(
+            to_vec(serde_json::json!({"a": 1})),
+            [0x81, 0xa1, b'a', 0x01]
+        );
+        assert_eq!(to_vec(serde_json::json!(-1)), [0xff]);
+        assert_eq!(to_vec(serde_json::json!(200)), [0xcc, 200]);
+    }
+
+    #[test]
+    fn test_const_value() {
+        // the values are serialized straight from the responses
+        use async_graphql_value::{ConstValue, Name};
+        use indexmap::IndexMap;
+
+        let value = ConstValue::Object(IndexMap::from([
+            (Name::new("role"), ConstValue::Enum(Name::new("ADMIN"))),
+            (Name::new("ids"), ConstValue::List(vec![1.into(), 2.into()])),
+        ]));
+
+        assert_eq!(
+            from_slice(&ResponseEncoding::MessagePack.to_vec(&value).unwrap()),
+            serde_json::json!({"role": "ADMIN", "ids": [1, 2]})
+        );
+    }
+
+    #[test]
+    fn test_unknown_len() {
+        /// Serializes its entries as a map of an unknown length.
+        struct Entries(Vec<(&'static str, u8)>);
+
+        impl serde::Serialize for Entries {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeMap;
+
+                let mut map = serializer.serialize_map(None)?;
+                for (key, value) in &self.0 {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+
+        let entries = Entries((0..20).map(|i| ("k", i)).collect());
+        let bytes = ResponseEncoding::MessagePack.to_vec(&[entries]).unwrap();
+        assert_eq!(&bytes[..4], [0x91, 0xde, 0x00, 20]);
+    }
+
+    #[test]
+    fn test_join() {
+        let items = [br#"{"a":1}"#.as_slice(), br#"{"a":2}"#.as_slice()];
+        assert_eq!(ResponseEncoding::Json.join(&items), br#"[{"a":1},{"a":2}]"#);
+
+        let items = [[0x01].as_slice(), [0x02].as_slice()];
+        assert_eq!(
+            ResponseEncoding::MessagePack.join(&items),
+            [0x92, 0x01, 0x02]
+        );
+    }
+
+    #[test]
+    fn test_response_encoding() {
+        use http::header::{HeaderMap, HeaderValue, ACCEPT};
+
+        let encoding = |accept: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(ACCEPT, HeaderValue::from_str(accept).unwrap());
+            ResponseEncoding::from_headers(&headers)
+        };
+
+        assert_eq!(
+            encoding("application/msgpack"),
+            ResponseEncoding::MessagePack
+        );
+        assert_eq!(
+            encoding("text/html, application/x-msgpack;q=0.9"),
+            ResponseEncoding::MessagePack
+        );
+        assert_eq!(
+            encoding("application/json, application/msgpack"),
+            ResponseEncoding::Json
+        );
+        assert_eq!(encoding("application/xml"), ResponseEncoding::Json);
+        assert_eq!(encoding("*/*"), ResponseEncoding::Json);
+        assert_eq!(
+            ResponseEncoding::from_headers(&HeaderMap::new()),
+            ResponseEncoding::Json
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use anyhow::Result;
+use async_graphql_value::ConstValue;
+use futures_util::stream::{self, BoxStream};
+use futures_util::StreamExt;
+use http::header::{HeaderMap, CONTENT_TYPE};
+
+use super::{BodyStream, FromValue};
+
+/// Checks if the body of the response is newline delimited JSON.
+pub fn is_ndjson(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| {
+            content_type.starts_with("application/x-ndjson")
+                || content_type.starts_with("application/ndjson")
+        })
+}
+
+/// Parses the lines of the body, the blank lines are skipped.
+pub fn parse(body: &[u8]) -> Result<Vec<serde_json_borrow::Value<'_>>> {
+    body.split(|byte| *byte == b'\n')
+        .filter(|line| !line.trim_ascii().is_empty())
+        .map(|line| Ok(serde_json::from_slice(line)?))
+        .collect()
+}
+
+/// Decodes the newline delimited JSON that is received in chunks. A chunk
+/// can end in the middle of a line, so the incomplete line is held until the
+/// rest of it is received.
+#[derive(Default)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+}
+
+impl Decoder {
+    /// Decodes the lines that are completed by the chunk.
+    pub fn decode(&mut self, chunk: &[u8]) -> Result<Vec<ConstValue>> {
+        self.buffer.extend_from_slice(chunk);
+
+        let Some(end) = self.buffer.iter().rposition(|byte| *byte == b'\n') else {
+            return Ok(vec![]);
+        };
+        let incomplete = self.buffer.split_off(end + 1);
+        let lines = std::mem::replace(&mut self.buffer, incomplete);
+
+        Ok(parse(&lines)?
+            .into_iter()
+            .map(ConstValue::from_value)
+            .collect())
+    }
+
+    /// Decodes the last line, that isn't terminated with a newline.
+    pub fn finish(self) -> Result<Option<ConstValue>> {
+        Ok(parse(&self.buffer)?
+            .into_iter()
+            .map(ConstValue::from_value)
+            .next())
+    }
+}
+
+/// Decodes the lines of the body as they are received. The stream ends after
+/// the first error.
+pub fn decode(body: BodyStream) -> BoxStream<'static, Result<ConstValue>> {
+    stream::unfold(Some((body, Decoder::default())), |state| async move {
+        let (mut body, mut decoder) = state?;
+
+        match body.next().await {
+            Some(Ok(chunk)) => match decoder.decode(&chunk) {
+                Ok(values) => Some((Ok(values), Some((body, decoder)))),
+                Err(err) => Some((Err(err), None)),
+            },
+            Some(Err(err)) => Some((Err(err), None)),
+            None => Some((decoder.finish().map(Vec::from_iter), None)),
+        }
+    })
+    .flat_map(|values| match values {
+        Ok(values) => stream::iter(values.into_iter().map(Ok).collect::<Vec<_>>()),
+        Err(err) => stream::iter(vec![Err(err)]),
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::body::Bytes;
+    use serde_json::json;
+
+    use super::*;
+    use crate::core::http::Response;
+
+    fn value(json: serde_json::Value) -> ConstValue {
+        ConstValue::from_json(json).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let body = b"{\"id\":1}\n\n  \r\n{\"id\":2}\r\n{\"id\":3}";
+        let actual = parse(body)
+            .unwrap()
+            .into_iter()
+            .map(ConstValue::from_value)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            actual,
+            vec![
+                value(json!({"id": 1})),
+                value(json!({"id": 2})),
+                value(json!({"id": 3}))
+            ]
+        );
+        assert!(parse(b"{\"id\":1}\n{\"id\":").is_err());
+    }
+
+    #[test]
+    fn test_response_to_list() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, "application/x-ndjson".parse().unwrap());
+        let response = Response {
+            headers,
+            body: Bytes::from("{\"id\":1}\n{\"id\":2}\n"),
+            ..Default::default()
+        };
+
+        let actual = response.to_json::<ConstValue>().unwrap().body;
+        assert_eq!(actual, value(json!([{"id": 1}, {"id": 2}])));
+    }
+
+    #[tokio::test]
+    async fn test_decode_chunks() {
+        // the second object spans the chunks, the third one isn't terminated
+        let chunks = [
+            "{\"level\":\"info\",\"message\":\"started\"}\n{\"level\":\"wa",
+            "rn\",",
+            "\"message\":\"slow\"}",
+            "\n\n{\"level\":\"error\"}",
+        ];
+        let body = stream::iter(chunks.map(|chunk| Ok(Bytes::from(chunk)))).boxed();
+
+        let actual = decode(body)
+            .map(|event| event.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(
+            actual,
+            vec![
+                value(json!({"level": "info", "message": "started"})),
+                value(json!({"level": "warn", "message": "slow"})),
+                value(json!({"level": "error"})),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_decode_error() {
+        let chunks = ["{\"id\":1}\n", "{\"id\"\n", "{\"id\":3}\n"];
+        let body = stream::iter(chunks.map(|chunk| Ok(Bytes::from(chunk)))).boxed();
+
+        let actual = decode(body).collect::<Vec<_>>().await;
+
+        assert_eq!(actual.len(), 2);
+        assert_eq!(actual[0].as_ref().unwrap(), &value(json!({"id": 1})));
+        assert!(actual[1].is_err());
+    }
+}
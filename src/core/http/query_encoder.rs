@@ -1,17 +1,14 @@
+use crate::core::config::QueryArrayStyle;
 use crate::core::path::ValueString;
 
-/// Defines different strategies for encoding query parameters.
-#[derive(Default, Debug, Clone)]
-pub enum QueryEncoder {
-    /// Encodes the query list as key=value1,value2,value3,...
-    CommaSeparated,
-    /// Encodes the query list by repeating the key for each value:
-    /// key=value1&key=value2&key=value3&...
-    #[default]
-    RepeatedKey,
-}
-
-impl QueryEncoder {
+/// Encodes the array valued query parameters in the style of the
+/// [QueryArrayStyle]:
+/// * `CommaSeparated` as `key=value1,value2,value3,...`
+/// * `RepeatedKey` by repeating the key for each value,
+///   `key=value1&key=value2&...`
+/// * `Brackets` by repeating the key suffixed with brackets,
+///   `key[]=value1&key[]=value2&...`
+impl QueryArrayStyle {
     pub fn encode(&self, key: &str, raw_value: Option<ValueString>) -> String {
         if let Some(value) = raw_value {
             match &value {
@@ -24,7 +21,7 @@ impl QueryEncoder {
     }
     fn encode_const_value(&self, key: &str, value: &async_graphql::Value) -> String {
         match self {
-            QueryEncoder::CommaSeparated => match value {
+            QueryArrayStyle::CommaSeparated => match value {
                 async_graphql::Value::List(list) if !list.is_empty() => {
                     let encoded_values: Vec<String> =
                         list.iter().filter_map(convert_value).collect();
@@ -39,7 +36,7 @@ impl QueryEncoder {
                     .map(|val| format!("{}={}", key, val))
                     .unwrap_or(key.to_string()),
             },
-            QueryEncoder::RepeatedKey => match value {
+            QueryArrayStyle::RepeatedKey => match value {
                 async_graphql::Value::List(list) if !list.is_empty() => {
                     let encoded_values: Vec<String> = list
                         .iter()
@@ -55,6 +52,23 @@ impl QueryEncoder {
                     .map(|val| format!("{}={}", key, val))
                     .unwrap_or(key.to_string()),
             },
+            QueryArrayStyle::Brackets => match value {
+                async_graphql::Value::List(list) if !list.is_empty() => {
+                    let encoded_values: Vec<String> = list
+                        .iter()
+                        .filter_map(convert_value)
+                        .map(|val| format!("{}[]={}", key, val))
+                        .collect();
+                    if encoded_values.is_empty() {
+                        key.to_string()
+                    } else {
+                        encoded_values.join("&")
+                    }
+                }
+                _ => convert_value(value)
+                    .map(|val| format!("{}={}", key, val))
+                    .unwrap_or(key.to_string()),
+            },
         }
     }
 }
@@ -79,7 +93,7 @@ mod tests {
 
     #[test]
     fn test_encode_comma_separated_arg() {
-        let encoder = QueryEncoder::CommaSeparated;
+        let encoder = QueryArrayStyle::CommaSeparated;
         let values = Value::List(vec![
             Value::Number(12.into()),
             Value::Number(42.into()),
@@ -95,7 +109,7 @@ mod tests {
 
     #[test]
     fn test_encode_repeated_key_value_arg() {
-        let encoder = QueryEncoder::RepeatedKey;
+        let encoder = QueryArrayStyle::RepeatedKey;
         let values = Value::List(vec![
             Value::Number(12.into()),
             Value::Number(42.into()),
@@ -109,9 +123,25 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_encode_brackets_arg() {
+        let encoder = QueryArrayStyle::Brackets;
+        let values = Value::List(vec![
+            Value::Number(12.into()),
+            Value::Number(42.into()),
+            Value::Number(13.into()),
+        ]);
+        let arg_raw_value = Some(ValueString::Value(Cow::Borrowed(&values)));
+
+        let actual = encoder.encode("key", arg_raw_value);
+        let expected = "key[]=12&key[]=42&key[]=13".to_string();
+
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn test_encode_env_var() {
-        let encoder = QueryEncoder::default();
+        let encoder = QueryArrayStyle::default();
         let raw_value = Some(ValueString::String("env_value".into()));
 
         let actual = encoder.encode("key", raw_value);
@@ -122,7 +152,7 @@ mod tests {
 
     #[test]
     fn test_encode_var() {
-        let encoder = QueryEncoder::default();
+        let encoder = QueryArrayStyle::default();
         let raw_value = Some(ValueString::String("var_value".into()));
 
         let actual = encoder.encode("key", raw_value);
@@ -133,7 +163,7 @@ mod tests {
 
     #[test]
     fn test_encode_none() {
-        let encoder = QueryEncoder::default();
+        let encoder = QueryArrayStyle::default();
         let raw_value: Option<ValueString> = None;
 
         let actual = encoder.encode("key", raw_value);
@@ -150,7 +180,7 @@ mod tests {
             Value::String("2".to_string()),
             Value::String("3".to_string()),
         ]);
-        let strategy = QueryEncoder::CommaSeparated;
+        let strategy = QueryArrayStyle::CommaSeparated;
 
         let actual = strategy.encode_const_value(key, &values);
         let expected = "ids=1,2,3".to_string();
@@ -166,7 +196,7 @@ mod tests {
             Value::String("2".to_string()),
             Value::String("3".to_string()),
         ]);
-        let strategy = QueryEncoder::RepeatedKey;
+        let strategy = QueryArrayStyle::RepeatedKey;
 
         let actual = strategy.encode_const_value(key, &values);
         let expected = "ids=1&ids=2&ids=3".to_string();
@@ -182,7 +212,7 @@ mod tests {
             Value::Number(42.into()),
             Value::Boolean(true),
         ]);
-        let strategy = QueryEncoder::CommaSeparated;
+        let strategy = QueryArrayStyle::CommaSeparated;
 
         let actual = strategy.encode_const_value(key, &values);
         let expected = "values=string,42,true".to_string();
@@ -198,7 +228,7 @@ mod tests {
             Value::Number(42.into()),
             Value::Boolean(true),
         ]);
-        let strategy = QueryEncoder::RepeatedKey;
+        let strategy = QueryArrayStyle::RepeatedKey;
 
         let actual = strategy.encode_const_value(key, &values);
         let expected = "values=string&values=42&values=true".to_string();
@@ -210,7 +240,7 @@ mod tests {
     fn test_encode_empty_list_comma_separated() {
         let key = "empty";
         let values = Value::List(vec![]);
-        let strategy = QueryEncoder::CommaSeparated;
+        let strategy = QueryArrayStyle::CommaSeparated;
 
         let actual = strategy.encode_const_value(key, &values);
         let expected = "empty".to_string();
@@ -222,7 +252,7 @@ mod tests {
     fn test_encode_empty_list_repeated_key() {
         let key = "empty";
         let values = Value::List(vec![]);
-        let strategy = QueryEncoder::RepeatedKey;
+        let strategy = QueryArrayStyle::RepeatedKey;
 
         let actual = strategy.encode_const_value(key, &values);
         let expected = "empty".to_string();
@@ -234,7 +264,7 @@ mod tests {
     fn test_encode_single_value_comma_separated() {
         let key = "single";
         let values = Value::List(vec![Value::String("value".to_string())]);
-        let strategy = QueryEncoder::CommaSeparated;
+        let strategy = QueryArrayStyle::CommaSeparated;
 
         let actual = strategy.encode_const_value(key, &values);
         let expected = "single=value".to_string();
@@ -246,7 +276,31 @@ mod tests {
     fn test_encode_single_value_repeated_key() {
         let key = "single";
         let values = Value::List(vec![Value::String("value".to_string())]);
-        let strategy = QueryEncoder::RepeatedKey;
+        let strategy = QueryArrayStyle::RepeatedKey;
+
+        let actual = strategy.encode_const_value(key, &values);
+        let expected = "single=value".to_string();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_encode_empty_list_brackets() {
+        let key = "empty";
+        let values = Value::List(vec![]);
+        let strategy = QueryArrayStyle::Brackets;
+
+        let actual = strategy.encode_const_value(key, &values);
+        let expected = "empty".to_string();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_encode_single_value_brackets() {
+        let key = "single";
+        let values = Value::String("value".to_string());
+        let strategy = QueryArrayStyle::Brackets;
 
         let actual = strategy.encode_const_value(key, &values);
         let expected = "single=value".to_string();
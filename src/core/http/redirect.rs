@@ -0,0 +1,245 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail};
+use hyper::body::Bytes;
+use reqwest::header::{
+    HeaderMap, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, LOCATION, PROXY_AUTHORIZATION,
+};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use super::Response;
+use crate::core::{config, HttpIO};
+
+const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// The policy for following the redirects of an `@http` upstream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedirectPolicy {
+    pub max_redirects: usize,
+    pub cross_host: bool,
+    pub preserve_auth: bool,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        Self {
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            cross_host: true,
+            preserve_auth: false,
+        }
+    }
+}
+
+impl From<&config::Redirect> for RedirectPolicy {
+    fn from(redirect: &config::Redirect) -> Self {
+        Self {
+            max_redirects: redirect.max_redirects.unwrap_or(DEFAULT_MAX_REDIRECTS),
+            cross_host: redirect.cross_host.unwrap_or(true),
+            preserve_auth: redirect.preserve_auth,
+        }
+    }
+}
+
+impl RedirectPolicy {
+    /// Executes the request, following the redirects of the responses as
+    /// allowed by the policy.
+    pub async fn execute(
+        &self,
+        http: &Arc<dyn HttpIO>,
+        request: reqwest::Request,
+    ) -> anyhow::Result<Response<Bytes>> {
+        self.follow(request, |request| http.execute_without_redirects(request))
+            .await
+    }
+
+    /// Sends the request with `send`, which doesn't follow the redirects
+    /// itself, and the requests the redirects of its responses lead to.
+    pub async fn follow<Body, F, Fut>(
+        &self,
+        mut request: reqwest::Request,
+        send: F,
+    ) -> anyhow::Result<Response<Body>>
+    where
+        F: Fn(reqwest::Request) -> Fut,
+        Fut: Future<Output = anyhow::Result<Response<Body>>>,
+    {
+        let mut redirects = 0;
+
+        loop {
+            let next = request.try_clone();
+            let response = send(request).await?;
+            let Some(location) = location(&response) else {
+                return Ok(response);
+            };
+
+            if redirects >= self.max_redirects {
+                bail!("Too many redirects, the limit is {}", self.max_redirects);
+            }
+            let mut next = next.ok_or_else(|| {
+                anyhow!("Can't follow the redirect, the request body can't be replayed")
+            })?;
+
+            let url = next.url().join(location)?;
+            if url.origin() != next.url().origin() {
+                if !self.cross_host {
+                    bail!("Redirect to a different host {} is not allowed", url);
+                }
+                if !self.preserve_auth {
+                    remove_credentials(next.headers_mut());
+                }
+            }
+
+            // The same as the browsers, the body is dropped for 303 and the POST
+            // requests redirected with 301 and 302.
+            let status = response.status;
+            if status == StatusCode::SEE_OTHER
+                || (next.method() == Method::POST
+                    && (status == StatusCode::MOVED_PERMANENTLY || status == StatusCode::FOUND))
+            {
+                *next.method_mut() = Method::GET;
+                *next.body_mut() = None;
+                next.headers_mut().remove(CONTENT_TYPE);
+                next.headers_mut().remove(CONTENT_LENGTH);
+            }
+
+            *next.url_mut() = url;
+            request = next;
+            redirects += 1;
+        }
+    }
+}
+
+fn location<Body>(response: &Response<Body>) -> Option<&str> {
+    if !response.status.is_redirection() || response.status == StatusCode::NOT_MODIFIED {
+        return None;
+    }
+
+    response.headers.get(LOCATION)?.to_str().ok()
+}
+
+fn remove_credentials(headers: &mut HeaderMap) {
+    headers.remove(AUTHORIZATION);
+    headers.remove(PROXY_AUTHORIZATION);
+    headers.remove(COOKIE);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use reqwest::header::HeaderValue;
+
+    use super::*;
+
+    /// Redirects `/<n>` to `/<n + 1>` and everything on `foo.com` to `bar.com`.
+    #[derive(Default)]
+    struct TestHttp {
+        requests: Mutex<Vec<reqwest::Request>>,
+    }
+
+    #[async_trait::async_trait]
+    impl HttpIO for TestHttp {
+        async fn execute(&self, request: reqwest::Request) -> anyhow::Result<Response<Bytes>> {
+            let url = request.url().clone();
+            self.requests.lock().unwrap().push(request);
+
+            let location = match (url.host_str(), url.path()) {
+                (Some("foo.com"), path) => format!("http://bar.com{path}"),
+                (_, "/done") => return Ok(Response::default()),
+                (_, path) => match path.trim_start_matches('/').parse::<usize>() {
+                    Ok(n) => format!("/{}", n + 1),
+                    Err(_) => "/done".to_string(),
+                },
+            };
+
+            let mut response =
+                Response::<Bytes> { status: StatusCode::FOUND, ..Default::default() };
+            response
+                .headers
+                .insert(LOCATION, HeaderValue::from_str(&location).unwrap());
+            Ok(response)
+        }
+    }
+
+    fn request(url: &str) -> reqwest::Request {
+        let mut request = reqwest::Request::new(Method::GET, url.parse().unwrap());
+        let headers = request.headers_mut();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+        headers.insert("x-request-id", HeaderValue::from_static("1"));
+        request
+    }
+
+    #[tokio::test]
+    async fn test_max_redirects() {
+        let test_http = Arc::new(TestHttp::default());
+        let http: Arc<dyn HttpIO> = test_http.clone();
+        let policy = RedirectPolicy { max_redirects: 3, ..Default::default() };
+
+        let actual = policy.execute(&http, request("http://localhost/1")).await;
+
+        assert_eq!(
+            actual.unwrap_err().to_string(),
+            "Too many redirects, the limit is 3"
+        );
+        let paths = test_http
+            .requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|request| request.url().path().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["/1", "/2", "/3", "/4"]);
+    }
+
+    #[tokio::test]
+    async fn test_cross_host_strips_auth() {
+        let test_http = Arc::new(TestHttp::default());
+        let http: Arc<dyn HttpIO> = test_http.clone();
+
+        let actual = RedirectPolicy::default()
+            .execute(&http, request("http://foo.com/users"))
+            .await;
+
+        assert!(actual.unwrap().status.is_success());
+        let requests = test_http.requests.lock().unwrap();
+        let redirected = requests
+            .iter()
+            .find(|request| request.url().host_str() == Some("bar.com"))
+            .unwrap();
+        assert!(requests[0].headers().contains_key(AUTHORIZATION));
+        assert!(!redirected.headers().contains_key(AUTHORIZATION));
+        assert!(redirected.headers().contains_key("x-request-id"));
+    }
+
+    #[tokio::test]
+    async fn test_cross_host_preserve_auth() {
+        let test_http = Arc::new(TestHttp::default());
+        let http: Arc<dyn HttpIO> = test_http.clone();
+        let policy = RedirectPolicy { preserve_auth: true, ..Default::default() };
+
+        policy
+            .execute(&http, request("http://foo.com/users"))
+            .await
+            .unwrap();
+
+        let requests = test_http.requests.lock().unwrap();
+        assert!(requests
+            .iter()
+            .all(|request| request.headers().contains_key(AUTHORIZATION)));
+    }
+
+    #[tokio::test]
+    async fn test_cross_host_not_allowed() {
+        let http: Arc<dyn HttpIO> = Arc::new(TestHttp::default());
+        let policy = RedirectPolicy { cross_host: false, ..Default::default() };
+
+        let actual = policy.execute(&http, request("http://foo.com/users")).await;
+
+        assert_eq!(
+            actual.unwrap_err().to_string(),
+            "Redirect to a different host http://bar.com/users is not allowed"
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::num::NonZeroU64;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
 use async_graphql_value::ConstValue;
@@ -9,12 +10,15 @@ use http::header::{HeaderMap, HeaderName, HeaderValue};
 
 use crate::core::app_context::AppContext;
 use crate::core::blueprint::{Server, Upstream};
+use crate::core::cancellation::CancellationToken;
 use crate::core::data_loader::{DataLoader, DedupeResult};
 use crate::core::graphql::GraphqlDataLoader;
 use crate::core::grpc::data_loader::GrpcDataLoader;
-use crate::core::http::{DataLoaderRequest, HttpDataLoader};
+use crate::core::http::{
+    DataLoaderRequest, FieldStats, HttpDataLoader, ResponseEncoding, UploadStream, UpstreamBudget,
+};
 use crate::core::ir::model::IoId;
-use crate::core::ir::Error;
+use crate::core::ir::{DedupedValue, Error};
 use crate::core::runtime::TargetRuntime;
 use crate::core::{cache, grpc};
 
@@ -27,6 +31,9 @@ pub struct RequestContext {
     // A subset of all the headers received in the GraphQL Request that will be sent to the
     // upstream.
     pub allowed_headers: HeaderMap,
+    // Id used to correlate the request across services, set only when
+    // `@server(requestId)` is configured.
+    pub request_id: Option<HeaderValue>,
     pub http_data_loaders: Arc<Vec<DataLoader<DataLoaderRequest, HttpDataLoader>>>,
     pub gql_data_loaders: Arc<Vec<DataLoader<DataLoaderRequest, GraphqlDataLoader>>>,
     pub grpc_data_loaders: Arc<Vec<DataLoader<grpc::DataLoaderRequest, GrpcDataLoader>>>,
@@ -34,7 +41,34 @@ pub struct RequestContext {
     pub cache_public: Arc<Mutex<Option<bool>>>,
     pub runtime: TargetRuntime,
     pub cache: DedupeResult<IoId, ConstValue, Error>,
-    pub dedupe_handler: Arc<DedupeResult<IoId, ConstValue, Error>>,
+    pub dedupe_handler: Arc<DedupeResult<IoId, DedupedValue, Error>>,
+    // Coalesces the refreshes of the cached IOs that miss the cache at the
+    // same time across the requests, regardless of their `dedupe`.
+    pub cache_refreshes: Arc<DedupeResult<IoId, ConstValue, Error>>,
+    // Cancelled when the GraphQL request is aborted, eg. the client disconnects,
+    // so that the in-flight upstream calls could be stopped.
+    pub cancellation: CancellationToken,
+    // Body of the client's request that is streamed to the upstreams of the
+    // `@http` resolvers with `streamUpload`.
+    pub upload: UploadStream,
+    // Number of the upstream calls made so far, limited by
+    // `@server(maxUpstreamCalls)`.
+    pub upstream_calls: Arc<AtomicUsize>,
+    // Attributes the sizes of the upstream calls to the fields, set only when
+    // the telemetry is exported.
+    pub upstream_size_metrics: bool,
+    // The client accepts the `@compress` fields gzipped, it sent the
+    // `x-compressed-fields: gzip` header.
+    pub compressed_fields: bool,
+    // Format of the body of the response, negotiated with the `Accept` header.
+    pub response_encoding: ResponseEncoding,
+    // The operation selects the `@compress` fields, so the response varies on
+    // the `x-compressed-fields` header.
+    #[setters(skip)]
+    pub selects_compressed_fields: Arc<AtomicBool>,
+    // How the fields were resolved, set only when `@server(debugStats)` is
+    // enabled.
+    pub field_stats: Option<Arc<FieldStats>>,
 }
 
 impl RequestContext {
@@ -52,9 +86,50 @@ impl RequestContext {
             runtime: target_runtime,
             cache: DedupeResult::new(true),
             dedupe_handler: Arc::new(DedupeResult::new(false)),
+            cache_refreshes: Arc::new(DedupeResult::new(false)),
             allowed_headers: HeaderMap::new(),
+            request_id: None,
+            cancellation: CancellationToken::default(),
+            upload: UploadStream::default(),
+            upstream_calls: Arc::new(AtomicUsize::new(0)),
+            upstream_size_metrics: false,
+            compressed_fields: false,
+            response_encoding: ResponseEncoding::default(),
+            selects_compressed_fields: Arc::new(AtomicBool::new(false)),
+            field_stats: None,
         }
     }
+    /// Copies the context for an evaluation that outlives the request, eg. an
+    /// IO shared by the requests. It isn't cancelled along with the request and
+    /// it has a cache of its own, the rest of the state is shared.
+    pub fn detached(&self) -> RequestContext {
+        RequestContext {
+            server: self.server.clone(),
+            upstream: self.upstream.clone(),
+            x_response_headers: self.x_response_headers.clone(),
+            cookie_headers: self.cookie_headers.clone(),
+            allowed_headers: self.allowed_headers.clone(),
+            request_id: self.request_id.clone(),
+            http_data_loaders: self.http_data_loaders.clone(),
+            gql_data_loaders: self.gql_data_loaders.clone(),
+            grpc_data_loaders: self.grpc_data_loaders.clone(),
+            min_max_age: self.min_max_age.clone(),
+            cache_public: self.cache_public.clone(),
+            runtime: self.runtime.clone(),
+            cache: DedupeResult::new(true),
+            dedupe_handler: self.dedupe_handler.clone(),
+            cache_refreshes: self.cache_refreshes.clone(),
+            cancellation: CancellationToken::default(),
+            upload: self.upload.clone(),
+            upstream_calls: self.upstream_calls.clone(),
+            upstream_size_metrics: self.upstream_size_metrics,
+            compressed_fields: self.compressed_fields,
+            response_encoding: self.response_encoding,
+            selects_compressed_fields: self.selects_compressed_fields.clone(),
+            field_stats: self.field_stats.clone(),
+        }
+    }
+
     fn set_min_max_age_conc(&self, min_max_age: i32) {
         *self.min_max_age.lock().unwrap() = Some(min_max_age);
     }
@@ -70,6 +145,15 @@ impl RequestContext {
         *self.cache_public.lock().unwrap()
     }
 
+    pub fn set_selects_compressed_fields(&self) {
+        self.selects_compressed_fields
+            .store(true, Ordering::Relaxed);
+    }
+
+    pub fn selects_compressed_fields(&self) -> bool {
+        self.selects_compressed_fields.load(Ordering::Relaxed)
+    }
+
     pub fn set_min_max_age(&self, max_age: i32) {
         let min_max_age_lock = self.get_min_max_age();
         match min_max_age_lock {
@@ -148,6 +232,23 @@ impl RequestContext {
         self.runtime.cache.set(key, value, ttl).await
     }
 
+    /// Removes all the cached entries that are tagged with the given tag.
+    pub async fn cache_invalidate(&self, tag: &str) -> Result<usize, cache::Error> {
+        self.runtime.cache.invalidate(tag).await
+    }
+
+    /// Counts an upstream call against the budget of the request, failing once
+    /// the budget is used up.
+    pub fn charge_upstream_call(&self) -> Result<(), Error> {
+        self.upstream_budget().charge()
+    }
+
+    /// Budget of the upstream calls of the request, to be charged by the data
+    /// loaders for the batches they dispatch.
+    pub fn upstream_budget(&self) -> UpstreamBudget {
+        UpstreamBudget::new(self.upstream_calls.clone(), self.server.max_upstream_calls)
+    }
+
     pub fn is_batching_enabled(&self) -> bool {
         self.upstream.is_batching_enabled()
     }
@@ -193,6 +294,7 @@ impl From<&AppContext> for RequestContext {
             x_response_headers: Arc::new(Mutex::new(HeaderMap::new())),
             cookie_headers,
             allowed_headers: HeaderMap::new(),
+            request_id: None,
             http_data_loaders: app_ctx.http_data_loaders.clone(),
             gql_data_loaders: app_ctx.gql_data_loaders.clone(),
             grpc_data_loaders: app_ctx.grpc_data_loaders.clone(),
@@ -201,6 +303,19 @@ impl From<&AppContext> for RequestContext {
             runtime: app_ctx.runtime.clone(),
             cache: DedupeResult::new(true),
             dedupe_handler: app_ctx.dedupe_handler.clone(),
+            cache_refreshes: app_ctx.cache_refreshes.clone(),
+            cancellation: CancellationToken::default(),
+            upload: UploadStream::default(),
+            upstream_calls: Arc::new(AtomicUsize::new(0)),
+            upstream_size_metrics: app_ctx.blueprint.telemetry.export.is_some(),
+            compressed_fields: false,
+            response_encoding: ResponseEncoding::default(),
+            selects_compressed_fields: Arc::new(AtomicBool::new(false)),
+            field_stats: app_ctx
+                .blueprint
+                .server
+                .enable_debug_stats
+                .then(|| Arc::new(FieldStats::default())),
         }
     }
 }
@@ -225,6 +340,17 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_charge_upstream_call() {
+        let mut req_ctx = RequestContext::default();
+        req_ctx.server.max_upstream_calls = Some(2);
+
+        assert!(req_ctx.charge_upstream_call().is_ok());
+        assert!(req_ctx.charge_upstream_call().is_ok());
+        assert!(req_ctx.charge_upstream_call().is_err());
+        assert!(req_ctx.charge_upstream_call().is_err());
+    }
+
     #[test]
     fn test_update_max_age_less_than_existing() {
         let req_ctx = RequestContext::default();
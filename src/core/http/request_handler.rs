@@ -1,4 +1,5 @@
 use std::collections::BTreeSet;
+use std::fmt::Write;
 use std::ops::Deref;
 use std::sync::Arc;
 
@@ -15,16 +16,22 @@ use serde::de::DeserializeOwned;
 use tracing::Instrument;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
+use super::msgpack::ResponseEncoding;
 use super::request_context::RequestContext;
 use super::telemetry::{get_response_status_code, RequestCounter};
-use super::{showcase, telemetry, TAILCALL_HTTPS_ORIGIN, TAILCALL_HTTP_ORIGIN};
+use super::{cache_admin, showcase, telemetry, TAILCALL_HTTPS_ORIGIN, TAILCALL_HTTP_ORIGIN};
 use crate::core::app_context::AppContext;
-use crate::core::async_graphql_hyper::{GraphQLRequestLike, GraphQLResponse};
+use crate::core::async_graphql_hyper::{
+    GraphQLIncrementalResponse, GraphQLRequestLike, GraphQLResponse,
+};
 use crate::core::blueprint::telemetry::TelemetryExporter;
+use crate::core::cancellation::DropGuard;
 use crate::core::config::{PrometheusExporter, PrometheusFormat};
 use crate::core::jit::JITExecutor;
+use crate::core::rng::Rng;
 
 pub const API_URL_PREFIX: &str = "/api";
+const COMPRESSED_FIELDS: &str = "x-compressed-fields";
 
 fn prometheus_metrics(prometheus_exporter: &PrometheusExporter) -> Result<Response<Body>> {
     let metric_families = prometheus::default_registry().gather();
@@ -54,10 +61,73 @@ fn not_found() -> Result<Response<Body>> {
         .body(Body::empty())?)
 }
 
-fn create_request_context(req: &Request<Body>, app_ctx: &AppContext) -> RequestContext {
-    let allowed_headers =
+pub fn create_request_context(req: &Request<Body>, app_ctx: &AppContext) -> RequestContext {
+    let mut allowed_headers =
         create_allowed_headers(req.headers(), &app_ctx.blueprint.upstream.allowed_headers);
-    RequestContext::from(app_ctx).allowed_headers(allowed_headers)
+
+    // Preserve the id supplied by the client and forward it to the upstream
+    // along with the allowed headers.
+    let request_id = app_ctx
+        .blueprint
+        .server
+        .request_id_header
+        .as_ref()
+        .map(|name| {
+            let request_id = req
+                .headers()
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| create_request_id(&app_ctx.runtime.rng));
+            allowed_headers.insert(name.clone(), request_id.clone());
+            request_id
+        });
+
+    let response_encoding = if app_ctx.blueprint.server.enable_msgpack {
+        ResponseEncoding::from_headers(req.headers())
+    } else {
+        ResponseEncoding::Json
+    };
+
+    RequestContext::from(app_ctx)
+        .allowed_headers(allowed_headers)
+        .request_id(request_id)
+        .compressed_fields(accepts_compressed_fields(req.headers()))
+        .response_encoding(response_encoding)
+}
+
+/// The client opts into the gzipped `@compress` fields with the
+/// `x-compressed-fields: gzip` header, the others get the raw values.
+fn accepts_compressed_fields(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(COMPRESSED_FIELDS)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|encoding| encoding.trim().eq_ignore_ascii_case("gzip"))
+}
+
+/// Generates a random (v4) UUID to be used as request id.
+fn create_request_id(rng: &Rng) -> HeaderValue {
+    let mut bytes = [0u8; 16];
+    rng.fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex = bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    });
+    let uuid = format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    );
+
+    // The uuid contains only hex digits and dashes, so it's always a valid value
+    HeaderValue::from_str(&uuid).unwrap()
 }
 
 pub fn update_response_headers(
@@ -79,6 +149,17 @@ pub fn update_response_headers(
 
     // Insert Experimental Headers
     req_ctx.extend_x_headers(resp.headers_mut());
+
+    // Insert Request Id Header
+    if let Some((name, request_id)) = app_ctx
+        .blueprint
+        .server
+        .request_id_header
+        .as_ref()
+        .zip(req_ctx.request_id.as_ref())
+    {
+        resp.headers_mut().insert(name, request_id.clone());
+    }
 }
 
 #[tracing::instrument(skip_all, fields(otel.name = "graphQL", otel.kind = ?SpanKind::Server))]
@@ -89,14 +170,14 @@ pub async fn graphql_request<T: DeserializeOwned + GraphQLRequestLike>(
 ) -> Result<Response<Body>> {
     req_counter.set_http_route("/graphql");
     let req_ctx = Arc::new(create_request_context(&req, app_ctx));
+    // hyper drops the future when the client disconnects, which cancels the
+    // upstream calls that are still in-flight
+    let cancel_on_drop = req_ctx.cancellation.drop_guard();
     let (req, body) = req.into_parts();
     let bytes = hyper::body::to_bytes(body).await?;
     let graphql_request = serde_json::from_slice::<T>(&bytes);
-    match graphql_request {
-        Ok(request) => {
-            let resp = execute_query(app_ctx, &req_ctx, request, req).await?;
-            Ok(resp)
-        }
+    let mut response = match graphql_request {
+        Ok(request) => execute_query(app_ctx, &req_ctx, request, req, cancel_on_drop).await?,
         Err(err) => {
             tracing::error!(
                 "Failed to parse request: {}",
@@ -108,9 +189,17 @@ pub async fn graphql_request<T: DeserializeOwned + GraphQLRequestLike>(
                 ServerError::new(format!("Unexpected GraphQL Request: {}", err), None);
             response.errors = vec![server_error];
 
-            Ok(GraphQLResponse::from(response).into_response()?)
+            GraphQLResponse::from(response).into_encoded_response(req_ctx.response_encoding)?
         }
+    };
+
+    // the body is encoded according to the `Accept` header
+    if app_ctx.blueprint.server.enable_msgpack {
+        response
+            .headers_mut()
+            .append(header::VARY, HeaderValue::from_static("accept"));
     }
+    Ok(response)
 }
 
 async fn execute_query<T: DeserializeOwned + GraphQLRequestLike>(
@@ -118,23 +207,42 @@ async fn execute_query<T: DeserializeOwned + GraphQLRequestLike>(
     req_ctx: &Arc<RequestContext>,
     request: T,
     req: Parts,
+    cancel_on_drop: DropGuard,
 ) -> anyhow::Result<Response<Body>> {
     let operation_id = request.operation_id(&req.headers);
     let exec = JITExecutor::new(app_ctx.clone(), req_ctx.clone(), operation_id);
-    let mut response = request
-        .execute_with_jit(exec)
-        .await
+    let response = if accepts_multipart(&req.headers) {
+        request.execute_incremental_with_jit(exec).await
+    } else {
+        GraphQLIncrementalResponse::Single(request.execute_with_jit(exec).await)
+    };
+    let mut response = response
         .set_cache_control(
             app_ctx.blueprint.server.enable_cache_control_header,
             req_ctx.get_min_max_age().unwrap_or(0),
             req_ctx.is_cache_public().unwrap_or(true),
         )
-        .into_response()?;
+        .into_response(cancel_on_drop)?;
 
     update_response_headers(&mut response, req_ctx, app_ctx);
+    if req_ctx.selects_compressed_fields() {
+        response
+            .headers_mut()
+            .append(header::VARY, HeaderValue::from_static(COMPRESSED_FIELDS));
+    }
     Ok(response)
 }
 
+/// Checks if the client accepts the incremental delivery of the `@stream`
+/// fields.
+fn accepts_multipart(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|accept| accept.to_str().ok())
+        .any(|accept| accept.contains("multipart/mixed"))
+}
+
 fn create_allowed_headers(headers: &HeaderMap, allowed: &BTreeSet<String>) -> HeaderMap {
     let mut new_headers = HeaderMap::with_capacity(allowed.len());
     for (k, v) in headers.iter() {
@@ -240,6 +348,7 @@ async fn handle_rest_apis(
 ) -> Result<Response<Body>> {
     *request.uri_mut() = request.uri().path().replace(API_URL_PREFIX, "").parse()?;
     let req_ctx = Arc::new(create_request_context(&request, app_ctx.as_ref()));
+    let _cancel_on_drop = req_ctx.cancellation.drop_guard();
     if let Some(p_request) = app_ctx.endpoints.matches(&request) {
         let http_route = format!("{API_URL_PREFIX}{}", p_request.path.as_str());
         req_counter.set_http_route(&http_route);
@@ -251,6 +360,15 @@ async fn handle_rest_apis(
             { HTTP_ROUTE } = http_route
         );
         return async {
+            // The body that isn't mapped to a variable is left for the
+            // resolvers that stream it to the upstream.
+            let request = if p_request.body.is_none() {
+                let (parts, body) = request.into_parts();
+                req_ctx.upload.put(body);
+                Request::from_parts(parts, Body::empty())
+            } else {
+                request
+            };
             let graphql_request = p_request.into_request(request).await?;
             let mut response = graphql_request
                 .data(req_ctx.clone())
@@ -281,6 +399,12 @@ async fn handle_request_inner<T: DeserializeOwned + GraphQLRequestLike>(
         return handle_rest_apis(req, app_ctx, req_counter).await;
     }
 
+    if let Some(cache_admin) = app_ctx.blueprint.server.cache_admin.as_ref() {
+        if cache_admin::is_route(cache_admin, req.uri().path()) {
+            return cache_admin::handle(&req, &app_ctx, cache_admin).await;
+        }
+    }
+
     let health_check_endpoint = app_ctx.blueprint.server.routes.status();
     let graphql_endpoint = app_ctx.blueprint.server.routes.graphql();
 
@@ -304,10 +428,17 @@ async fn handle_request_inner<T: DeserializeOwned + GraphQLRequestLike>(
             graphql_request::<T>(req, &Arc::new(app_ctx), req_counter).await
         }
         Method::GET if req.uri().path() == health_check_endpoint => {
+            let body = match app_ctx.health_check.as_ref() {
+                Some(health_check) => {
+                    let report = health_check.check(&app_ctx.runtime).await;
+                    Body::from(serde_json::to_vec(report.as_ref())?)
+                }
+                None => Body::from(r#"{"message": "ready"}"#),
+            };
             let status_response = Response::builder()
                 .status(StatusCode::OK)
                 .header(CONTENT_TYPE, "application/json")
-                .body(Body::from(r#"{"message": "ready"}"#))?;
+                .body(body)?;
             Ok(status_response)
         }
         Method::GET => {
@@ -364,12 +495,14 @@ pub async fn handle_request<T: DeserializeOwned + GraphQLRequestLike>(
 
 #[cfg(test)]
 mod test {
+    use hyper::header::HeaderName;
     use tailcall_valid::Validator;
 
     use super::*;
     use crate::core::async_graphql_hyper::GraphQLRequest;
     use crate::core::blueprint::Blueprint;
     use crate::core::config::{Config, ConfigModule, Routes};
+    use crate::core::http::msgpack::APPLICATION_MSGPACK;
     use crate::core::rest::EndpointSet;
     use crate::core::runtime::test::init;
 
@@ -429,6 +562,201 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_create_request_id() {
+        use super::create_request_id;
+
+        let rng = Rng::default();
+        let request_id = create_request_id(&rng);
+        let request_id = request_id.to_str().unwrap();
+
+        assert_eq!(request_id.len(), 36);
+        assert_eq!(&request_id[14..15], "4");
+        assert_ne!(create_request_id(&rng), create_request_id(&rng));
+
+        // the ids are reproducible with a fixed seed
+        assert_eq!(
+            create_request_id(&Rng::with_seed(42)),
+            create_request_id(&Rng::with_seed(42))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_id() -> anyhow::Result<()> {
+        let sdl = tokio::fs::read_to_string(tailcall_fixtures::configs::JSONPLACEHOLDER).await?;
+        let config = Config::from_sdl(&sdl).to_result()?;
+        let mut blueprint = Blueprint::try_from(&ConfigModule::from(config))?;
+        blueprint.server.request_id_header = Some(HeaderName::from_static("x-request-id"));
+        let app_ctx = Arc::new(AppContext::new(
+            blueprint,
+            init(None),
+            EndpointSet::default(),
+        ));
+
+        let query = r#"{"query": "{ __typename }"}"#;
+
+        // generates a new id when the client doesn't supply one
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("http://localhost:8000/graphql".to_string())
+            .header("Content-Type", "application/json")
+            .body(Body::from(query))?;
+        let resp = handle_request::<GraphQLRequest>(req, app_ctx.clone()).await?;
+        assert_eq!(resp.headers().get("x-request-id").unwrap().len(), 36);
+
+        // preserves the id supplied by the client
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("http://localhost:8000/graphql".to_string())
+            .header("Content-Type", "application/json")
+            .header("x-request-id", "client-id")
+            .body(Body::from(query))?;
+        let resp = handle_request::<GraphQLRequest>(req, app_ctx).await?;
+        assert_eq!(resp.headers().get("x-request-id").unwrap(), "client-id");
+
+        Ok(())
+    }
+
+    async fn graphql(
+        msgpack: bool,
+        accept: &str,
+        body: &'static str,
+    ) -> anyhow::Result<Response<Body>> {
+        let sdl = tokio::fs::read_to_string(tailcall_fixtures::configs::JSONPLACEHOLDER).await?;
+        let mut config = Config::from_sdl(&sdl).to_result()?;
+        config.server.msgpack = Some(msgpack);
+        let blueprint = Blueprint::try_from(&ConfigModule::from(config))?;
+        let app_ctx = Arc::new(AppContext::new(
+            blueprint,
+            init(None),
+            EndpointSet::default(),
+        ));
+
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("http://localhost:8000/graphql".to_string())
+            .header("Content-Type", "application/json")
+            .header("Accept", accept)
+            .body(Body::from(body))?;
+
+        handle_request::<GraphQLRequest>(req, app_ctx).await
+    }
+
+    async fn decode(response: Response<Body>) -> anyhow::Result<serde_json::Value> {
+        let is_msgpack = response.headers()[CONTENT_TYPE] == APPLICATION_MSGPACK;
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+
+        Ok(if is_msgpack {
+            rmp_serde::from_slice(&body)?
+        } else {
+            serde_json::from_slice(&body)?
+        })
+    }
+
+    #[tokio::test]
+    async fn test_msgpack_response() -> anyhow::Result<()> {
+        let query = r#"{"query": "{ __typename __schema { queryType { name } } }"}"#;
+
+        let json = graphql(true, "application/json", query).await?;
+        assert_eq!(json.headers()[CONTENT_TYPE], "application/json");
+        let msgpack = graphql(true, "application/msgpack", query).await?;
+        assert_eq!(msgpack.headers()[CONTENT_TYPE], APPLICATION_MSGPACK);
+        assert_eq!(msgpack.headers()[header::VARY], "accept");
+
+        let json = decode(json).await?;
+        assert_eq!(json["data"]["__schema"]["queryType"]["name"], "Query");
+        assert_eq!(decode(msgpack).await?, json);
+
+        // the clients get JSON unless the negotiation is enabled
+        let response = graphql(false, "application/msgpack", query).await?;
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+        assert!(!response.headers().contains_key(header::VARY));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_msgpack_response_errors() -> anyhow::Result<()> {
+        let query = r#"{"query": "{ users {"}"#;
+        let json = decode(graphql(true, "application/json", query).await?).await?;
+        let msgpack = decode(graphql(true, "application/msgpack", query).await?).await?;
+        assert!(json["errors"].is_array());
+        assert_eq!(msgpack, json);
+
+        // the requests that can't be parsed
+        let json = decode(graphql(true, "application/json", "{").await?).await?;
+        let msgpack = decode(graphql(true, "application/msgpack", "{").await?).await?;
+        assert!(json["errors"].is_array());
+        assert_eq!(msgpack, json);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_accepts_compressed_fields() {
+        let accepts = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(COMPRESSED_FIELDS, HeaderValue::from_str(value).unwrap());
+            accepts_compressed_fields(&headers)
+        };
+
+        assert!(accepts("gzip"));
+        assert!(accepts("br, GZIP"));
+        assert!(!accepts("br"));
+        assert!(!accepts_compressed_fields(&HeaderMap::new()));
+    }
+
+    #[tokio::test]
+    async fn test_vary_compressed_fields() -> anyhow::Result<()> {
+        let sdl = r#"
+            schema @server {
+              query: Query
+            }
+
+            type Query {
+              text: String @expr(body: "text") @compress(minSize: 1)
+              name: String @expr(body: "name")
+            }
+        "#;
+        let config = Config::from_sdl(sdl).to_result()?;
+        let blueprint = Blueprint::try_from(&ConfigModule::from(config))?;
+        let app_ctx = Arc::new(AppContext::new(
+            blueprint,
+            init(None),
+            EndpointSet::default(),
+        ));
+
+        let vary = |body: &'static str| {
+            let app_ctx = app_ctx.clone();
+            async move {
+                let req = Request::builder()
+                    .method(Method::POST)
+                    .uri("http://localhost:8000/graphql")
+                    .body(Body::from(body))?;
+                let response = handle_request::<GraphQLRequest>(req, app_ctx).await?;
+
+                anyhow::Ok(
+                    response
+                        .headers()
+                        .get_all(header::VARY)
+                        .iter()
+                        .map(|value| value.to_str().unwrap().to_string())
+                        .collect::<Vec<_>>(),
+                )
+            }
+        };
+
+        // the responses of the operations with the `@compress` fields depend on
+        // the header, even when they're sent raw
+        assert_eq!(
+            vary(r#"{"query": "{ text name }"}"#).await?,
+            [COMPRESSED_FIELDS]
+        );
+        assert!(vary(r#"{"query": "{ name }"}"#).await?.is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn test_create_allowed_headers() {
         use std::collections::BTreeSet;
@@ -1,38 +1,78 @@
 use std::borrow::Cow;
-use std::hash::{Hash, Hasher};
+use std::hash::Hash;
+use std::ops::RangeInclusive;
 
 use derive_setters::Setters;
 use http::header::{HeaderMap, HeaderValue};
-use tailcall_hasher::TailcallHasher;
+use serde::{Deserialize, Serialize};
 use url::Url;
 
-use super::query_encoder::QueryEncoder;
-use crate::core::config::Encoding;
+use super::{
+    encode_keys, form_encoder, Connection, DynamicHeaders, RedirectPolicy, ResponseSchema,
+};
+use crate::core::config::{Encoding, KeyCase, Priority, QueryArrayStyle};
 use crate::core::endpoint::Endpoint;
 use crate::core::has_headers::HasHeaders;
 use crate::core::helpers::headers::MustacheHeaders;
-use crate::core::ir::model::{CacheKey, IoId};
-use crate::core::ir::DynamicRequest;
+use crate::core::ir::model::{CacheKey, IoId, IoIdHasher};
+use crate::core::ir::{DynamicRequest, Predicate};
 use crate::core::mustache::{Eval, Mustache, Segment};
 use crate::core::path::{PathString, PathValue, ValueString};
 
+/// Default maximum size in bytes of the body of a `raw_body` response.
+pub const DEFAULT_MAX_RAW_BODY_SIZE: usize = 10 * 1024 * 1024;
+
 /// RequestTemplate is an extension of a Mustache template.
 /// Various parts of the template can be written as a mustache template.
 /// When `to_request` is called, all mustache templates are evaluated.
 /// To call `to_request` we need to provide a context.
-#[derive(Setters, Debug, Clone)]
+#[derive(Setters, Debug, Clone, Serialize, Deserialize)]
 pub struct RequestTemplate {
     pub root_url: Mustache,
     pub query: Vec<Query>,
+    #[serde(with = "crate::core::helpers::serde::method")]
     pub method: reqwest::Method,
+    #[serde(with = "crate::core::helpers::serde::mustache_headers")]
     pub headers: MustacheHeaders,
+    /// Headers resolved from an object, the `headers` take precedence over
+    /// them.
+    pub dynamic_headers: Option<DynamicHeaders>,
     pub body_path: Option<Mustache>,
     pub endpoint: Endpoint,
     pub encoding: Encoding,
-    pub query_encoder: QueryEncoder,
+    pub query_encoder: QueryArrayStyle,
+    pub response_schema: Option<ResponseSchema>,
+    /// Sends the client's upload stream as the body. Such requests can't be
+    /// replayed, so they are never cached, deduplicated or batched.
+    pub stream_upload: bool,
+    /// Follows the redirects of the responses with the policy instead of the
+    /// HTTP client.
+    pub redirect: Option<RedirectPolicy>,
+    /// Coerces the responses into a list when `true` and into a single value
+    /// when `false`, regardless of the shape returned by the upstream.
+    pub coerce_list: Option<bool>,
+    /// The statuses of the failed responses that are resolved to `null`
+    /// instead.
+    pub success_statuses: Vec<RangeInclusive<u16>>,
+    /// Renames the keys of the body to the case of the API, and the keys of
+    /// the response back.
+    pub key_case: Option<KeyCase>,
+    /// Admits the requests waiting on a full bulkhead in the order of their
+    /// priority.
+    pub priority: Priority,
+    /// Skips the request when the predicate holds for the value and the args
+    /// of the field.
+    pub skip_if: Option<Predicate>,
+    /// Resolves to the base64 encoded body of the response instead of
+    /// decoding it as JSON.
+    pub raw_body: bool,
+    /// Maximum size in bytes of the body of a `raw_body` response.
+    pub max_size: usize,
+    /// Maps the page of a cursor-paginated API to a Relay connection.
+    pub connection: Option<Connection>,
 }
 
-#[derive(Setters, Debug, Clone)]
+#[derive(Setters, Debug, Clone, Serialize, Deserialize)]
 pub struct Query {
     pub key: String,
     pub value: Mustache,
@@ -95,11 +135,16 @@ impl RequestTemplate {
             && self.body_path.as_ref().map_or(true, |b| b.is_const())
             && self.query.iter().all(|query| query.value.is_const())
             && self.headers.iter().all(|(_, v)| v.is_const())
+            && self.dynamic_headers.is_none()
     }
 
     /// Creates a HeaderMap for the context
-    fn create_headers<C: PathString>(&self, ctx: &C) -> HeaderMap {
-        let mut header_map = HeaderMap::new();
+    fn create_headers<C: PathString + PathValue>(&self, ctx: &C) -> HeaderMap {
+        let mut header_map = self
+            .dynamic_headers
+            .as_ref()
+            .map(|dynamic_headers| dynamic_headers.render(ctx))
+            .unwrap_or_default();
 
         for (k, v) in &self.headers {
             if let Ok(header_value) = HeaderValue::from_str(&v.render(ctx)) {
@@ -133,15 +178,15 @@ impl RequestTemplate {
                 Encoding::ApplicationJson => {
                     let (body, batching_value) =
                         ExpressionValueEval::default().eval(body_path, ctx);
-                    req.body_mut().replace(body.into());
+                    req.body_mut().replace(self.encode_keys(body).into());
                     batching_value
                 }
                 Encoding::ApplicationXWwwFormUrlencoded => {
                     // TODO: this is a performance bottleneck
                     // We first encode everything to string and then back to form-urlencoded
-                    let body = body_path.render(ctx);
+                    let body = self.encode_keys(body_path.render(ctx));
                     let form_data = match serde_json::from_str::<serde_json::Value>(&body) {
-                        Ok(deserialized_data) => serde_urlencoded::to_string(deserialized_data)?,
+                        Ok(deserialized_data) => form_encoder::encode(&deserialized_data)?,
                         Err(_) => body,
                     };
 
@@ -155,8 +200,21 @@ impl RequestTemplate {
         Ok(DynamicRequest::new(req).with_batching_value(batching_value))
     }
 
+    /// Renames the keys of the JSON body with the `key_case`, the other bodies
+    /// are sent as is.
+    fn encode_keys(&self, body: String) -> String {
+        let Some(key_case) = self.key_case else {
+            return body;
+        };
+
+        match serde_json::from_str(&body) {
+            Ok(value) => encode_keys(key_case, value).to_string(),
+            Err(_) => body,
+        }
+    }
+
     /// Sets the headers for the request
-    fn set_headers<C: PathString + HasHeaders>(
+    fn set_headers<C: PathString + HasHeaders + PathValue>(
         &self,
         mut req: reqwest::Request,
         ctx: &C,
@@ -192,10 +250,22 @@ impl RequestTemplate {
             query: Default::default(),
             method: reqwest::Method::GET,
             headers: Default::default(),
+            dynamic_headers: None,
             body_path: Default::default(),
             endpoint: Endpoint::new(root_url.to_string()),
             encoding: Default::default(),
             query_encoder: Default::default(),
+            response_schema: None,
+            stream_upload: false,
+            redirect: None,
+            coerce_list: None,
+            success_statuses: vec![],
+            key_case: None,
+            priority: Priority::default(),
+            skip_if: None,
+            raw_body: false,
+            max_size: DEFAULT_MAX_RAW_BODY_SIZE,
+            connection: None,
         })
     }
 
@@ -243,17 +313,33 @@ impl TryFrom<Endpoint> for RequestTemplate {
             query,
             method,
             headers,
+            dynamic_headers: None,
             body_path: body,
             endpoint,
             encoding,
             query_encoder: Default::default(),
+            response_schema: None,
+            stream_upload: false,
+            redirect: None,
+            coerce_list: None,
+            success_statuses: vec![],
+            key_case: None,
+            priority: Priority::default(),
+            skip_if: None,
+            raw_body: false,
+            max_size: DEFAULT_MAX_RAW_BODY_SIZE,
+            connection: None,
         })
     }
 }
 
 impl<Ctx: PathString + HasHeaders + PathValue> CacheKey<Ctx> for RequestTemplate {
     fn cache_key(&self, ctx: &Ctx) -> Option<IoId> {
-        let mut hasher = TailcallHasher::default();
+        if self.stream_upload {
+            return None;
+        }
+
+        let mut hasher = IoIdHasher::default();
         let state = &mut hasher;
 
         self.method.hash(state);
@@ -263,6 +349,13 @@ impl<Ctx: PathString + HasHeaders + PathValue> CacheKey<Ctx> for RequestTemplate
             mustache.render(ctx).hash(state);
         }
 
+        if let Some(dynamic_headers) = &self.dynamic_headers {
+            for (name, value) in dynamic_headers.render(ctx).iter() {
+                name.hash(state);
+                value.hash(state);
+            }
+        }
+
         for (name, value) in ctx.headers().iter() {
             name.hash(state);
             value.hash(state);
@@ -275,7 +368,7 @@ impl<Ctx: PathString + HasHeaders + PathValue> CacheKey<Ctx> for RequestTemplate
         let url = self.create_url(ctx).unwrap();
         url.hash(state);
 
-        Some(IoId::new(hasher.finish()))
+        Some(hasher.finish_id())
     }
 }
 
@@ -359,7 +452,7 @@ mod tests {
     use pretty_assertions::assert_eq;
     use serde_json::json;
 
-    use super::{Query, RequestTemplate};
+    use super::{DynamicHeaders, Query, QueryArrayStyle, RequestTemplate};
     use crate::core::has_headers::HasHeaders;
     use crate::core::json::JsonLike;
     use crate::core::mustache::Mustache;
@@ -455,6 +548,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_query_list_args_styles() {
+        let query = vec![Query {
+            key: "id".to_string(),
+            value: Mustache::parse("{{args.id}}"),
+            skip_empty: false,
+        }];
+        let ctx = Context::default().value(json!({ "args": { "id": [1, 2] } }));
+
+        let styles = [
+            (
+                QueryArrayStyle::RepeatedKey,
+                "http://localhost:3000/?id=1&id=2",
+            ),
+            (
+                QueryArrayStyle::CommaSeparated,
+                "http://localhost:3000/?id=1,2",
+            ),
+            (
+                QueryArrayStyle::Brackets,
+                "http://localhost:3000/?id[]=1&id[]=2",
+            ),
+        ];
+
+        for (query_encoder, expected) in styles {
+            let tmpl = RequestTemplate::new("http://localhost:3000/")
+                .unwrap()
+                .query(query.clone())
+                .query_encoder(query_encoder);
+
+            let request_wrapper = tmpl.to_request(&ctx).unwrap();
+            let req = request_wrapper.request();
+            assert_eq!(req.url().to_string(), expected);
+        }
+    }
+
     #[test]
     fn test_url() {
         let tmpl = RequestTemplate::new("http://localhost:3000/").unwrap();
@@ -628,6 +757,32 @@ mod tests {
         assert_eq!(req.headers().get("baz").unwrap(), "2");
     }
 
+    #[test]
+    fn test_dynamic_headers() {
+        let headers = vec![(HeaderName::from_static("x-tenant"), Mustache::parse("acme"))];
+        let tmpl = RequestTemplate::new("http://localhost:3000")
+            .unwrap()
+            .headers(headers)
+            .dynamic_headers(Some(DynamicHeaders::new(
+                Mustache::parse("{{headers}}"),
+                vec![],
+            )));
+        let ctx = Context::default().value(json!({
+          "headers": {
+            "x-trace": "abc",
+            "x-tenant": "evil",
+            "host": "evil.com"
+          }
+        }));
+        let request_wrapper = tmpl.to_request(&ctx).unwrap();
+        let req = request_wrapper.request();
+        assert_eq!(req.headers().get("x-trace").unwrap(), "abc");
+        // the configured headers take precedence and the denied ones are dropped
+        assert_eq!(req.headers().get("x-tenant").unwrap(), "acme");
+        assert!(req.headers().get("host").is_none());
+        assert!(!tmpl.is_const());
+    }
+
     #[test]
     fn test_header_encoding_application_json() {
         let tmpl = RequestTemplate::new("http://localhost:3000")
@@ -900,6 +1055,22 @@ mod tests {
             assert_eq!(a, e);
         }
 
+        #[test]
+        fn test_with_json_body_two_levels() {
+            let tmpl = RequestTemplate::form_encoded_url("http://localhost:3000")
+                .unwrap()
+                .body_path(Some(Mustache::parse("{{a}}")));
+            let ctx = Context::default().value(json!({"a": {
+                "filter": {"status": "active", "owner": {"id": 1}},
+                "ids": [1, 2]
+            }}));
+            let body = tmpl.to_body(&ctx).unwrap();
+            assert_eq!(
+                body,
+                "filter[status]=active&filter[owner][id]=1&ids[0]=1&ids[1]=2"
+            );
+        }
+
         #[test]
         fn test_with_mustache_literal() {
             let tmpl = RequestTemplate::form_encoded_url("http://localhost:3000")
@@ -1000,5 +1171,16 @@ mod tests {
             assert_eq!(key_123_1, key_123_2);
             assert_eq!(key_234_1, key_234_2);
         }
+
+        #[test]
+        fn test_stream_upload() {
+            let ctx = Context::default().value(json!({}));
+            let key = RequestTemplate::new("http://localhost:3000/upload")
+                .unwrap()
+                .stream_upload(true)
+                .cache_key(&ctx);
+
+            assert_eq!(key, None);
+        }
     }
 }
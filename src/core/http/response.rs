@@ -1,16 +1,30 @@
 use anyhow::Result;
 use async_graphql_value::{ConstValue, Name};
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
 use derive_setters::Setters;
-use hyper::body::Bytes;
+use futures_util::future::BoxFuture;
+use futures_util::stream::{self, BoxStream};
+use futures_util::{FutureExt, StreamExt};
+use http::header::CONTENT_TYPE;
+use hyper::body::{Bytes, HttpBody};
 use hyper::Body;
 use indexmap::IndexMap;
 use prost::Message;
+use tokio::sync::oneshot;
 use tonic::Status;
 use tonic_types::Status as GrpcStatus;
 
+use super::{content_encoding, ndjson};
 use crate::core::grpc::protobuf::ProtobufOperation;
 use crate::core::ir::Error;
 
+/// Body of a response that is streamed as it's received.
+pub type BodyStream = BoxStream<'static, Result<Bytes>>;
+
+/// The trailers of a streamed response, they are received once its body is.
+pub type Trailers = BoxFuture<'static, Option<headers::HeaderMap>>;
+
 #[derive(Clone, Debug, Default, Setters)]
 pub struct Response<Body> {
     pub status: reqwest::StatusCode,
@@ -48,11 +62,67 @@ impl FromValue for ConstValue {
     }
 }
 
+impl Response<BodyStream> {
+    /// Streams the body of the response instead of buffering it.
+    pub fn from_reqwest_stream(resp: reqwest::Response) -> Self {
+        let status = resp.status();
+        let headers = resp.headers().to_owned();
+        let body = resp
+            .bytes_stream()
+            .map(|chunk| chunk.map_err(anyhow::Error::from))
+            .boxed();
+        Response { status, headers, body }
+    }
+
+    /// Streams the body of the response along with its trailers, that are
+    /// received once the body is.
+    pub fn from_hyper_stream(resp: hyper::Response<Body>) -> (Self, Trailers) {
+        let (parts, body) = resp.into_parts();
+        let (sender, receiver) = oneshot::channel();
+        let body = stream::unfold(Some((body, sender)), |state| async move {
+            let (mut body, sender) = state?;
+            match body.data().await {
+                Some(chunk) => Some((chunk.map_err(anyhow::Error::from), Some((body, sender)))),
+                None => {
+                    let _ = sender.send(body.trailers().await.ok().flatten());
+                    None
+                }
+            }
+        })
+        .boxed();
+        // the trailers resolve to none if the body is dropped before its end
+        let trailers = async move { receiver.await.ok().flatten() }.boxed();
+
+        (
+            Response { status: parts.status, headers: parts.headers, body },
+            trailers,
+        )
+    }
+}
+
 impl Response<Bytes> {
+    /// Decodes the body with its `Content-Encoding`.
     pub async fn from_reqwest(resp: reqwest::Response) -> Result<Self> {
         let status = resp.status();
         let headers = resp.headers().to_owned();
         let body = resp.bytes().await?;
+        Self::decoded(
+            status,
+            headers,
+            body,
+            content_encoding::DEFAULT_MAX_DECODED_SIZE,
+        )
+    }
+
+    /// Decodes the body that is received with its `Content-Encoding`, to at
+    /// most `max_size` bytes.
+    pub fn decoded(
+        status: reqwest::StatusCode,
+        mut headers: headers::HeaderMap,
+        body: Bytes,
+        max_size: usize,
+    ) -> Result<Self> {
+        let body = content_encoding::decode(&mut headers, body, max_size)?;
         Ok(Response { status, headers, body })
     }
 
@@ -82,11 +152,44 @@ impl Response<Bytes> {
         // Note: We convert the body to a serde_json_borrow::Value for better
         // performance. Warning: Do not change this to direct conversion to `T`
         // without benchmarking the performance impact.
-        let body: serde_json_borrow::Value = serde_json::from_slice(&self.body)?;
+        let body: serde_json_borrow::Value = if ndjson::is_ndjson(&self.headers) {
+            // every line of the body is an element of the list
+            serde_json_borrow::Value::Array(ndjson::parse(&self.body)?)
+        } else {
+            serde_json::from_slice(&self.body)?
+        };
         let body = T::from_value(body);
         Ok(Response { status: self.status, headers: self.headers, body })
     }
 
+    /// Encodes the body as is in base64, for the `Bytes` scalar. The body must
+    /// have a binary content type, so that the errors or the JSON of a
+    /// misconfigured API aren't passed off as an opaque value, and it must not
+    /// be larger than `max_size` bytes.
+    pub fn to_base64(self, max_size: usize) -> Result<Response<ConstValue>> {
+        let content_type = self
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .unwrap_or_default();
+        if !is_binary(content_type) {
+            anyhow::bail!("Expected a binary response, received `{}`", content_type);
+        }
+        if self.body.len() > max_size {
+            anyhow::bail!(
+                "The response of {} bytes exceeds the maximum size of {} bytes",
+                self.body.len(),
+                max_size
+            );
+        }
+
+        Ok(Response {
+            status: self.status,
+            headers: self.headers,
+            body: ConstValue::String(BASE64_STANDARD.encode(&self.body)),
+        })
+    }
+
     pub fn to_grpc_value(
         self,
         operation: &ProtobufOperation,
@@ -99,6 +202,16 @@ impl Response<Bytes> {
         Ok(resp)
     }
 
+    /// Decodes the JSON response of a gRPC-JSON transcoding proxy as the
+    /// output of the operation.
+    pub fn to_transcoded_value(
+        self,
+        operation: &ProtobufOperation,
+    ) -> Result<Response<async_graphql::Value>> {
+        let body = operation.convert_json_output::<async_graphql::Value>(&self.body)?;
+        Ok(Response { status: self.status, headers: self.headers, body })
+    }
+
     pub fn to_grpc_error(&self, operation: &ProtobufOperation) -> anyhow::Error {
         let grpc_status = match Status::from_header_map(&self.headers) {
             Some(status) => status,
@@ -156,6 +269,30 @@ impl Response<Bytes> {
     }
 }
 
+/// Any content type but the textual ones is binary, the ones that are missing
+/// aren't.
+fn is_binary(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let textual = mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || mime.ends_with("+xml")
+        || matches!(
+            mime.as_str(),
+            "application/json"
+                | "application/xml"
+                | "application/x-ndjson"
+                | "application/ndjson"
+                | "application/x-www-form-urlencoded"
+        );
+
+    !mime.is_empty() && !textual
+}
+
 impl From<Response<Bytes>> for http::Response<Body> {
     fn from(resp: Response<Bytes>) -> Self {
         let mut response = http::Response::new(Body::from(resp.body));
@@ -164,3 +301,55 @@ impl From<Response<Bytes>> for http::Response<Body> {
         response
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use http::header::HeaderValue;
+
+    use super::*;
+
+    fn response(content_type: Option<&'static str>) -> Response<Bytes> {
+        let mut headers = headers::HeaderMap::new();
+        if let Some(content_type) = content_type {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        }
+
+        Response {
+            status: reqwest::StatusCode::OK,
+            headers,
+            body: Bytes::from_static(&[0x89, b'P', b'N', b'G', 0x00, 0xff]),
+        }
+    }
+
+    #[test]
+    fn test_to_base64() {
+        let actual = response(Some("image/png")).to_base64(usize::MAX).unwrap();
+        assert_eq!(actual.body, ConstValue::String("iVBORwD/".to_string()));
+
+        let actual = response(Some("application/octet-stream; charset=binary"))
+            .to_base64(usize::MAX)
+            .unwrap();
+        assert_eq!(actual.body, ConstValue::String("iVBORwD/".to_string()));
+    }
+
+    #[test]
+    fn test_to_base64_max_size() {
+        assert!(response(Some("image/png")).to_base64(6).is_ok());
+        assert!(response(Some("image/png")).to_base64(5).is_err());
+    }
+
+    #[test]
+    fn test_to_base64_textual() {
+        for content_type in [
+            Some("application/json"),
+            Some("application/problem+json"),
+            Some("text/html; charset=utf-8"),
+            None,
+        ] {
+            assert!(
+                response(content_type).to_base64(usize::MAX).is_err(),
+                "{content_type:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use once_cell::sync::Lazy;
+use opentelemetry::metrics::Counter;
+use opentelemetry::KeyValue;
+use serde::{Deserialize, Serialize};
+use tailcall_valid::Validator;
+
+use crate::core::config::SchemaViolation;
+use crate::core::ir::Error;
+use crate::core::json::JsonSchema;
+
+static HTTP_CLIENT_SCHEMA_VIOLATION_COUNT: Lazy<Counter<u64>> = Lazy::new(|| {
+    let meter = opentelemetry::global::meter("http_request");
+
+    meter
+        .u64_counter("http.client.response.schema_violation.count")
+        .with_description("Number of upstream responses that don't conform to the responseSchema")
+        .init()
+});
+
+/// Upstream contract that is compiled from the `responseSchema` of `@http`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponseSchema {
+    pub schema: JsonSchema,
+    pub on_violation: SchemaViolation,
+}
+
+impl ResponseSchema {
+    pub fn new(schema: JsonSchema, on_violation: SchemaViolation) -> Self {
+        Self { schema, on_violation }
+    }
+
+    /// Validates the decoded response of the upstream. Depending on the
+    /// configured action a violation either fails the call or is only reported.
+    pub fn validate(&self, url: &str, value: &async_graphql::Value) -> Result<(), Error> {
+        let Err(err) = self.schema.validate(value).to_result() else {
+            return Ok(());
+        };
+
+        match self.on_violation {
+            SchemaViolation::Error => Err(Error::from(err)),
+            SchemaViolation::Warn => {
+                tracing::warn!(
+                    "Response from {} violates the responseSchema: {:?}",
+                    url,
+                    err
+                );
+                HTTP_CLIENT_SCHEMA_VIOLATION_COUNT.add(1, &[KeyValue::new("url", url.to_string())]);
+
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Value;
+    use serde_json::json;
+    use tailcall_valid::Validator;
+
+    use super::*;
+
+    fn response_schema(on_violation: SchemaViolation) -> ResponseSchema {
+        let schema = json!({
+            "type": "object",
+            "required": ["id"],
+            "properties": { "id": { "type": "integer" }, "name": { "type": "string" } }
+        });
+        let schema = JsonSchema::from_json_schema(&schema).to_result().unwrap();
+
+        ResponseSchema::new(schema, on_violation)
+    }
+
+    #[test]
+    fn test_conforming_response() {
+        let value = Value::from_json(json!({ "id": 1, "name": "foo" })).unwrap();
+
+        for on_violation in [SchemaViolation::Error, SchemaViolation::Warn] {
+            let actual = response_schema(on_violation).validate("http://foo.com", &value);
+            assert!(actual.is_ok());
+        }
+    }
+
+    #[test]
+    fn test_non_conforming_response_error() {
+        let value = Value::from_json(json!({ "name": 1 })).unwrap();
+
+        let actual = response_schema(SchemaViolation::Error).validate("http://foo.com", &value);
+        assert!(matches!(actual, Err(Error::APIValidation(errors)) if errors.len() == 2));
+    }
+
+    #[test]
+    fn test_non_conforming_response_warn() {
+        let value = Value::from_json(json!({ "name": 1 })).unwrap();
+
+        let actual = response_schema(SchemaViolation::Warn).validate("http://foo.com", &value);
+        assert!(actual.is_ok());
+    }
+}
@@ -8,12 +8,18 @@ use crate::core::Transform;
 
 pub struct QueryBatching<'a> {
     dl_requests: &'a [&'a DataLoaderRequest],
-    group_by: Option<&'a str>,
+    group_by: Vec<&'a str>,
 }
 
 impl<'a> QueryBatching<'a> {
     pub fn new(dl_requests: &'a [&'a DataLoaderRequest], group_by: Option<&'a str>) -> Self {
-        QueryBatching { dl_requests, group_by }
+        QueryBatching { dl_requests, group_by: group_by.into_iter().collect() }
+    }
+
+    /// Merges only the query params of the keys, that together form a composite
+    /// key.
+    pub fn with_keys(dl_requests: &'a [&'a DataLoaderRequest], keys: Vec<&'a str>) -> Self {
+        QueryBatching { dl_requests, group_by: keys }
     }
 }
 
@@ -25,12 +31,12 @@ impl Transform for QueryBatching<'_> {
         for key in self.dl_requests.iter() {
             let request = key.to_request();
             let url = request.url();
-            let pairs: Vec<_> = if let Some(group_by_key) = self.group_by {
+            let pairs: Vec<_> = if self.group_by.is_empty() {
+                url.query_pairs().collect()
+            } else {
                 url.query_pairs()
-                    .filter(|(key, _)| group_by_key.eq(&key.to_string()))
+                    .filter(|(key, _)| self.group_by.contains(&key.as_ref()))
                     .collect()
-            } else {
-                url.query_pairs().collect()
             };
 
             if !pairs.is_empty() {
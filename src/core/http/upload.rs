@@ -0,0 +1,83 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+
+use futures_util::stream;
+use hyper::body::HttpBody;
+
+/// Body of the client's request that is forwarded to an upstream by the
+/// `@http` resolvers with `streamUpload`. The body is never buffered, it's
+/// read from the client as the upstream consumes it. Being a stream it can be
+/// consumed only once, the first call that takes it owns it and the requests
+/// built from it can't be retried or replayed.
+#[derive(Clone, Default)]
+pub struct UploadStream(Arc<Mutex<Option<hyper::Body>>>);
+
+impl UploadStream {
+    pub fn new(body: hyper::Body) -> Self {
+        Self(Arc::new(Mutex::new(Some(body))))
+    }
+
+    pub fn put(&self, body: hyper::Body) {
+        *self.0.lock().unwrap() = Some(body);
+    }
+
+    /// Takes the upload out, leaving nothing for the later calls.
+    pub fn take(&self) -> Option<hyper::Body> {
+        self.0.lock().unwrap().take()
+    }
+
+    /// Takes the upload out as a request body. The length of the body isn't
+    /// known upfront, so it's sent with the chunked transfer encoding.
+    pub fn take_body(&self) -> Option<reqwest::Body> {
+        let body = self.take()?;
+        let chunks = stream::unfold(body, |mut body| async move {
+            body.data().await.map(|chunk| (chunk, body))
+        });
+
+        Some(reqwest::Body::wrap_stream(chunks))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().unwrap().is_none()
+    }
+}
+
+impl Debug for UploadStream {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UploadStream")
+            .field("is_empty", &self.is_empty())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_take_once() {
+        let upload = UploadStream::new(hyper::Body::from("foo"));
+        let clone = upload.clone();
+
+        let body = clone.take().unwrap();
+        let bytes = hyper::body::to_bytes(body).await.unwrap();
+
+        assert_eq!(bytes, "foo");
+        assert!(upload.is_empty());
+        assert!(upload.take().is_none());
+    }
+
+    #[test]
+    fn test_streamed_body_is_not_replayable() {
+        let upload = UploadStream::new(hyper::Body::from("foo"));
+
+        let mut request = reqwest::Request::new(
+            reqwest::Method::POST,
+            "http://localhost:8080/upload".parse().unwrap(),
+        );
+        request.body_mut().replace(upload.take_body().unwrap());
+
+        assert!(request.body().unwrap().as_bytes().is_none());
+        assert!(request.try_clone().is_none());
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::core::ir::Error;
+
+/// Number of the upstream calls a request is allowed to make, shared by all
+/// the resolvers of the request. The data loaders are shared by the requests,
+/// so the budget is carried by the keys of the batches and it's charged once
+/// for every request that they dispatch.
+#[derive(Clone, Debug, Default)]
+pub struct UpstreamBudget {
+    calls: Arc<AtomicUsize>,
+    max: Option<usize>,
+}
+
+impl UpstreamBudget {
+    pub fn new(calls: Arc<AtomicUsize>, max: Option<usize>) -> Self {
+        Self { calls, max }
+    }
+
+    /// Counts an upstream call, failing once the budget is used up.
+    pub fn charge(&self) -> Result<(), Error> {
+        let calls = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        match self.max {
+            Some(max) if calls > max => Err(Error::BudgetExceeded(max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails when the budget was used up by the calls charged so far, eg. to
+    /// tell why a key was left out of the batch of a data loader.
+    pub fn check(&self) -> Result<(), Error> {
+        match self.max {
+            Some(max) if self.calls.load(Ordering::SeqCst) > max => Err(Error::BudgetExceeded(max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts a single upstream call that resolves the keys of a batch against
+    /// the budget of every request that the keys belong to. The batch is
+    /// shared by the requests, so the keys of the requests that are out of
+    /// budget are left out of it instead of failing the others, and only the
+    /// remaining keys are returned.
+    pub fn charge_batch<K: Clone>(keys: &[K], budget: impl Fn(&K) -> &UpstreamBudget) -> Vec<K> {
+        let mut charged: Vec<(&UpstreamBudget, bool)> = vec![];
+        let mut allowed_keys = Vec::with_capacity(keys.len());
+        for key in keys {
+            let budget = budget(key);
+            let allowed = match charged
+                .iter()
+                .find(|(other, _)| Arc::ptr_eq(&other.calls, &budget.calls))
+            {
+                Some((_, allowed)) => *allowed,
+                None => {
+                    let allowed = budget.charge().is_ok();
+                    charged.push((budget, allowed));
+                    allowed
+                }
+            };
+            if allowed {
+                allowed_keys.push(key.clone());
+            }
+        }
+
+        allowed_keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_charge_batch_once_per_request() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let budget = UpstreamBudget::new(calls.clone(), Some(2));
+        let other = UpstreamBudget::new(Arc::new(AtomicUsize::new(0)), Some(2));
+
+        let keys = [&budget, &budget, &other, &budget];
+        assert_eq!(UpstreamBudget::charge_batch(&keys, |key| key).len(), 4);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        assert_eq!(
+            UpstreamBudget::charge_batch(&[&budget, &budget], |key| key).len(),
+            2
+        );
+        assert!(UpstreamBudget::charge_batch(&[&budget], |key| key).is_empty());
+        assert!(budget.check().is_err());
+    }
+
+    #[test]
+    fn test_charge_batch_drops_exhausted_keys() {
+        let exhausted = UpstreamBudget::new(Arc::new(AtomicUsize::new(1)), Some(1));
+        let budget = UpstreamBudget::new(Arc::new(AtomicUsize::new(0)), Some(1));
+
+        let keys = [(1, &exhausted), (2, &budget), (3, &exhausted), (4, &budget)];
+        let charged = UpstreamBudget::charge_batch(&keys, |(_, budget)| budget);
+
+        assert_eq!(
+            charged.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            [2, 4]
+        );
+        assert!(exhausted.check().is_err());
+        assert!(budget.check().is_ok());
+    }
+}
@@ -0,0 +1,165 @@
+use std::future::Future;
+
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use opentelemetry::metrics::{Histogram, Unit};
+use opentelemetry::KeyValue;
+
+use super::BodyStream;
+
+tokio::task_local! {
+    // Name of the field whose IO is evaluated, the upstream calls made while
+    // it's evaluated are attributed to it.
+    static FIELD: String;
+}
+
+static UPSTREAM_REQUEST_SIZE: Lazy<Histogram<u64>> = Lazy::new(|| {
+    let meter = opentelemetry::global::meter("upstream");
+
+    meter
+        .u64_histogram("upstream.request.size")
+        .with_description("Size of the bodies of the requests sent to the upstreams")
+        .with_unit(Unit::new("By"))
+        .init()
+});
+
+static UPSTREAM_RESPONSE_SIZE: Lazy<Histogram<u64>> = Lazy::new(|| {
+    let meter = opentelemetry::global::meter("upstream");
+
+    meter
+        .u64_histogram("upstream.response.size")
+        .with_description("Size of the bodies of the responses received from the upstreams")
+        .with_unit(Unit::new("By"))
+        .init()
+});
+
+/// Attributes the upstream calls made by the future to the field. The calls
+/// of the batches are made by the data loaders on behalf of multiple fields,
+/// so they aren't attributed to any field.
+pub async fn with_field<F: Future>(field: Option<String>, future: F) -> F::Output {
+    match field {
+        Some(field) => FIELD.scope(field, future).await,
+        None => future.await,
+    }
+}
+
+/// Records the sizes of the body of a request and of its response, labeled by
+/// the origin of the upstream and the field the call is attributed to.
+#[derive(Default)]
+pub struct UpstreamSize {
+    attributes: Option<Vec<KeyValue>>,
+}
+
+impl UpstreamSize {
+    pub fn new(enable_telemetry: bool, request: &reqwest::Request) -> Self {
+        if !enable_telemetry {
+            return Self::default();
+        }
+
+        let mut attributes = vec![KeyValue::new(
+            "upstream",
+            request.url().origin().ascii_serialization(),
+        )];
+        if let Ok(field) = FIELD.try_with(|field| field.clone()) {
+            attributes.push(KeyValue::new("field", field));
+        }
+
+        // the streamed uploads are of an unknown size
+        if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+            UPSTREAM_REQUEST_SIZE.record(body.len() as u64, &attributes);
+        }
+
+        Self { attributes: Some(attributes) }
+    }
+
+    pub fn record_response(&self, size: usize) {
+        if let Some(attributes) = &self.attributes {
+            UPSTREAM_RESPONSE_SIZE.record(size as u64, attributes);
+        }
+    }
+
+    /// Counts the bytes of the body as they are received, the size is
+    /// recorded once the body is received or dropped.
+    pub fn record_stream(self, body: BodyStream) -> BodyStream {
+        if self.attributes.is_none() {
+            return body;
+        }
+
+        count_bytes(body, move |size| self.record_response(size))
+    }
+}
+
+struct OnDrop<F: FnOnce(usize)> {
+    size: usize,
+    callback: Option<F>,
+}
+
+impl<F: FnOnce(usize)> Drop for OnDrop<F> {
+    fn drop(&mut self) {
+        if let Some(callback) = self.callback.take() {
+            callback(self.size)
+        }
+    }
+}
+
+fn count_bytes(body: BodyStream, callback: impl FnOnce(usize) + Send + 'static) -> BodyStream {
+    let mut counter = OnDrop { size: 0, callback: Some(callback) };
+
+    body.inspect(move |chunk| {
+        // the counter is moved as a whole, so that it's dropped with the body
+        // rather than the copy of its size
+        let counter = &mut counter;
+        if let Ok(chunk) = chunk {
+            counter.size += chunk.len();
+        }
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use futures_util::stream;
+    use hyper::body::Bytes;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_count_bytes() {
+        let size = Arc::new(Mutex::new(None));
+        let chunks = ["{\"id\":1}\n", "{\"id\":", "2}\n"];
+        let body = stream::iter(chunks.map(|chunk| Ok(Bytes::from(chunk)))).boxed();
+
+        let mut body = count_bytes(body, {
+            let size = size.clone();
+            move |bytes| *size.lock().unwrap() = Some(bytes)
+        });
+        body.next().await.unwrap().unwrap();
+        assert_eq!(*size.lock().unwrap(), None);
+
+        // the size so far is recorded when the body is dropped half way
+        drop(body);
+        assert_eq!(*size.lock().unwrap(), Some(9));
+
+        let body = stream::iter(chunks.map(|chunk| Ok(Bytes::from(chunk)))).boxed();
+        let body = count_bytes(body, {
+            let size = size.clone();
+            move |bytes| *size.lock().unwrap() = Some(bytes)
+        });
+        body.collect::<Vec<_>>().await;
+        assert_eq!(*size.lock().unwrap(), Some(18));
+    }
+
+    #[tokio::test]
+    async fn test_with_field() {
+        let field = with_field(Some("posts".to_string()), async {
+            FIELD.try_with(|field| field.clone()).ok()
+        })
+        .await;
+        assert_eq!(field.as_deref(), Some("posts"));
+
+        let field = with_field(None, async { FIELD.try_with(|field| field.clone()).ok() }).await;
+        assert_eq!(field, None);
+    }
+}
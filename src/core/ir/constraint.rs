@@ -0,0 +1,196 @@
+use std::borrow::Cow;
+use std::fmt::{Debug, Formatter};
+
+use async_graphql_value::ConstValue;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Constraints on the value of an argument that are checked before the field
+/// is resolved, see `@validate`. An argument that isn't set, or is `null`,
+/// satisfies all of them.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Constraint {
+    pub arg: String,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+    /// Bounds of the number of the characters of a string, or of the elements
+    /// of a list.
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    pub pattern: Option<Pattern>,
+    /// The other arguments that have to be set along with the argument.
+    pub requires: Vec<String>,
+}
+
+impl Constraint {
+    /// The messages of the constraints that the arguments violate, the
+    /// arguments are looked up by their name.
+    pub fn check<'a>(&self, arg: impl Fn(&str) -> Option<Cow<'a, ConstValue>>) -> Vec<String> {
+        let mut violations = vec![];
+        let Some(value) = arg(&self.arg).filter(|value| is_set(value)) else {
+            return violations;
+        };
+        let name = &self.arg;
+
+        if let ConstValue::Number(number) = value.as_ref() {
+            let number = number.as_f64().unwrap_or_default();
+            if let Some(min) = self.min.filter(|min| number < *min as f64) {
+                violations.push(format!("`{name}` must be at least {min}"));
+            }
+            if let Some(max) = self.max.filter(|max| number > *max as f64) {
+                violations.push(format!("`{name}` must be at most {max}"));
+            }
+        }
+
+        let length = match value.as_ref() {
+            ConstValue::String(value) => Some((value.chars().count(), "characters")),
+            ConstValue::List(list) => Some((list.len(), "elements")),
+            _ => None,
+        };
+        if let Some((length, unit)) = length {
+            if let Some(min) = self.min_length.filter(|min| length < *min) {
+                violations.push(format!("`{name}` must have at least {min} {unit}"));
+            }
+            if let Some(max) = self.max_length.filter(|max| length > *max) {
+                violations.push(format!("`{name}` must have at most {max} {unit}"));
+            }
+        }
+
+        if let (Some(pattern), ConstValue::String(value)) = (&self.pattern, value.as_ref()) {
+            if !pattern.0.is_match(value) {
+                violations.push(format!("`{name}` must match `{}`", pattern.0.as_str()));
+            }
+        }
+
+        for required in &self.requires {
+            if arg(required).filter(|value| is_set(value)).is_none() {
+                violations.push(format!("`{name}` requires `{required}` to be set"));
+            }
+        }
+
+        violations
+    }
+}
+
+/// Whether the argument is set to a value other than `null`.
+fn is_set(value: &ConstValue) -> bool {
+    !matches!(value, ConstValue::Null)
+}
+
+/// A compiled regular expression, it's serialized as its source.
+#[derive(Clone)]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self(Regex::new(pattern)?))
+    }
+}
+
+impl Debug for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self.0.as_str(), f)
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Pattern::new(&pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn check(constraint: &Constraint, args: serde_json::Value) -> Vec<String> {
+        let args = ConstValue::from_json(args).unwrap();
+        let ConstValue::Object(args) = args else {
+            unreachable!()
+        };
+
+        constraint.check(|name| args.get(name).cloned().map(Cow::Owned))
+    }
+
+    #[test]
+    fn test_range() {
+        let constraint = Constraint {
+            arg: "limit".to_string(),
+            min: Some(1),
+            max: Some(100),
+            ..Default::default()
+        };
+
+        assert!(check(&constraint, json!({"limit": 10})).is_empty());
+        assert!(check(&constraint, json!({"limit": null})).is_empty());
+        assert!(check(&constraint, json!({})).is_empty());
+        assert_eq!(
+            check(&constraint, json!({"limit": 0})),
+            vec!["`limit` must be at least 1"]
+        );
+        assert_eq!(
+            check(&constraint, json!({"limit": 100.5})),
+            vec!["`limit` must be at most 100"]
+        );
+    }
+
+    #[test]
+    fn test_length_and_pattern() {
+        let constraint = Constraint {
+            arg: "code".to_string(),
+            min_length: Some(2),
+            max_length: Some(3),
+            pattern: Some(Pattern::new("^[A-Z]+$").unwrap()),
+            ..Default::default()
+        };
+
+        assert!(check(&constraint, json!({"code": "ABC"})).is_empty());
+        assert_eq!(
+            check(&constraint, json!({"code": "abcd"})),
+            vec![
+                "`code` must have at most 3 characters",
+                "`code` must match `^[A-Z]+$`"
+            ]
+        );
+        assert_eq!(
+            check(&constraint, json!({"code": ["A"]})),
+            vec!["`code` must have at least 2 elements"]
+        );
+    }
+
+    #[test]
+    fn test_requires() {
+        let constraint = Constraint {
+            arg: "from".to_string(),
+            requires: vec!["to".to_string()],
+            ..Default::default()
+        };
+
+        assert!(check(&constraint, json!({"from": 1, "to": 2})).is_empty());
+        assert!(check(&constraint, json!({"to": 2})).is_empty());
+        assert_eq!(
+            check(&constraint, json!({"from": 1, "to": null})),
+            vec!["`from` requires `to` to be set"]
+        );
+    }
+
+    #[test]
+    fn test_pattern_serde() {
+        let pattern = Pattern::new("^\\d+$").unwrap();
+        let json = serde_json::to_value(&pattern).unwrap();
+        assert_eq!(json, json!("^\\d+$"));
+
+        let pattern: Pattern = serde_json::from_value(json).unwrap();
+        assert!(pattern.0.is_match("123"));
+        assert!(serde_json::from_value::<Pattern>(json!("(")).is_err());
+    }
+}
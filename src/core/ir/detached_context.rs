@@ -0,0 +1,67 @@
+use std::sync::{Arc, Mutex};
+
+use async_graphql::parser::types::OperationType;
+use async_graphql::{Name, PathSegment, ServerError, Value};
+use indexmap::IndexMap;
+
+use super::{ResolverContextLike, SelectionField};
+
+/// An owned copy of the resolver context of a field, so that an IO of the
+/// field could be evaluated detached from the caller, eg. when it's shared
+/// by the callers of several requests. The errors that the IO adds are
+/// collected, for the caller to forward them.
+#[derive(Clone)]
+pub struct DetachedContext {
+    pub(super) value: Option<Value>,
+    pub(super) args: Option<IndexMap<Name, Value>>,
+    pub(super) field: Option<SelectionField>,
+    pub(super) is_query: bool,
+    pub(super) operation_name: Option<String>,
+    pub(super) operation_type: Option<OperationType>,
+    pub(super) response_path: Vec<PathSegment>,
+    pub(super) errors: Arc<Mutex<Vec<ServerError>>>,
+    // Overrides of the value and of the args of the evaluation context
+    pub(super) value_override: Option<Arc<Value>>,
+    pub(super) args_override: Option<Arc<Value>>,
+}
+
+impl DetachedContext {
+    /// The errors added by the IO.
+    pub fn errors(&self) -> Vec<ServerError> {
+        self.errors.lock().unwrap().clone()
+    }
+}
+
+impl ResolverContextLike for DetachedContext {
+    fn value(&self) -> Option<&Value> {
+        self.value.as_ref()
+    }
+
+    fn args(&self) -> Option<&IndexMap<Name, Value>> {
+        self.args.as_ref()
+    }
+
+    fn field(&self) -> Option<SelectionField> {
+        self.field.clone()
+    }
+
+    fn is_query(&self) -> bool {
+        self.is_query
+    }
+
+    fn operation_name(&self) -> Option<&str> {
+        self.operation_name.as_deref()
+    }
+
+    fn operation_type(&self) -> Option<OperationType> {
+        self.operation_type
+    }
+
+    fn add_error(&self, error: ServerError) {
+        self.errors.lock().unwrap().push(error);
+    }
+
+    fn response_path(&self) -> Vec<PathSegment> {
+        self.response_path.clone()
+    }
+}
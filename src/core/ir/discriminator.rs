@@ -1,4 +1,5 @@
 mod keyed_discriminator;
+mod status_discriminator;
 mod type_field_discriminator;
 
 use std::collections::BTreeSet;
@@ -6,6 +7,8 @@ use std::collections::BTreeSet;
 use anyhow::{bail, Result};
 use async_graphql::Value;
 use keyed_discriminator::KeyedDiscriminator;
+use serde::{Deserialize, Serialize};
+pub use status_discriminator::{parse_status, StatusDiscriminator};
 use tailcall_valid::{Valid, Validator};
 use type_field_discriminator::TypeFieldDiscriminator;
 
@@ -21,16 +24,26 @@ use crate::core::json::{JsonLike, JsonObjectLike};
 /// * [KeyedDiscriminator]: Uses the keys of an object to determine its type.
 /// * [TypeFieldDiscriminator]: Uses a specific field of an object to determine
 ///   its type.
+/// * [StatusDiscriminator]: Uses the HTTP status code of the upstream response
+///   to determine the type of the object.
 ///
 /// The [Discriminator] enum provides a way to construct and use these
 /// discriminators.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// None of them infers the type from the shape of the value, so a value can't
+/// match several types: the keyed and the type field discriminators read the
+/// type name from the value, and the status discriminator picks the first
+/// configured entry that matches the status code.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Discriminator {
     /// A discriminator that uses the keys of an object to determine its type.
     Keyed(KeyedDiscriminator),
     /// A discriminator that uses a specific field of an object to determine its
     /// type.
     TypeField(TypeFieldDiscriminator),
+    /// A discriminator that uses the status code of the upstream response to
+    /// determine the type.
+    Status(StatusDiscriminator),
 }
 
 impl Discriminator {
@@ -64,9 +77,23 @@ impl Discriminator {
         }
     }
 
+    /// Constructs a new discriminator that resolves the types by the status
+    /// code of the upstream response.
+    ///
+    /// `statuses`: Pairs of a status code pattern and the type it resolves to,
+    /// see [StatusDiscriminator::new].
+    pub fn with_status(
+        type_name: String,
+        types: BTreeSet<String>,
+        statuses: Vec<(String, String)>,
+    ) -> Valid<Self, String> {
+        StatusDiscriminator::new(type_name, types, statuses).map(Self::Status)
+    }
+
     /// Resolves the `__typename` for an object and inserts the value into the
-    /// object.
-    pub fn resolve_type(&self, value: Value) -> Result<Value> {
+    /// object. `status` is the status code of the upstream response the value
+    /// was received with, it's only used by [StatusDiscriminator].
+    pub fn resolve_type(&self, value: Value, status: Option<u16>) -> Result<Value> {
         // if typename is already present we return it
         if value.get_type_name().is_some() {
             return Ok(value);
@@ -75,7 +102,7 @@ impl Discriminator {
         match value {
             Value::Null => Ok(value),
             Value::List(arr) => {
-                let arr = arr.into_iter().map(|i| self.resolve_type(i)).collect::<Result<Vec<_>>>()?;
+                let arr = arr.into_iter().map(|i| self.resolve_type(i, status)).collect::<Result<Vec<_>>>()?;
                 Ok(Value::array(arr))
             },
             Value::Object(_) => {
@@ -86,6 +113,9 @@ impl Discriminator {
                     Discriminator::TypeField(type_field_discriminator) => {
                         type_field_discriminator.resolve_and_set_type(value)
                     }
+                    Discriminator::Status(status_discriminator) => {
+                        status_discriminator.resolve_and_set_type(value, status)
+                    }
                 }
             },
             _ => bail!("Discriminator can only determine the types of arrays or objects but a different type.")
@@ -2,6 +2,7 @@ use std::collections::BTreeSet;
 
 use anyhow::{bail, Result};
 use async_graphql::Value;
+use serde::{Deserialize, Serialize};
 use tailcall_valid::Valid;
 
 use super::TypedValue;
@@ -14,7 +15,7 @@ use super::TypedValue;
 ///
 /// This discriminator is used when the type of an object can be determined by
 /// its keys.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct KeyedDiscriminator {
     /// List of all types that are members of the union or interface.
     types: BTreeSet<String>,
@@ -0,0 +1,218 @@
+use std::collections::BTreeSet;
+use std::ops::RangeInclusive;
+
+use anyhow::{bail, Result};
+use async_graphql::Value;
+use serde::{Deserialize, Serialize};
+use tailcall_valid::{Valid, Validator};
+
+use super::TypedValue;
+
+/// Resolver for `__typename` of Union and Interface types.
+///
+/// The [StatusDiscriminator] uses the HTTP status code of the upstream response
+/// to determine the type. For example with `404 → NotFound` the `__typename`
+/// of any value that is received with the `404` status will resolve to
+/// "NotFound".
+///
+/// This discriminator is used when the shape of the response isn't enough to
+/// tell the types apart, eg. when the errors returned by an API share the
+/// fields of the successful responses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusDiscriminator {
+    /// Status code ranges along with the type they resolve to, in the order of
+    /// precedence
+    statuses: Vec<(RangeInclusive<u16>, String)>,
+    /// The name of StatusDiscriminator is used for error reporting
+    type_name: String,
+}
+
+impl StatusDiscriminator {
+    /// Constructs a new [StatusDiscriminator] resolver.
+    ///
+    /// `type_name`: The name of the type that this discriminator is applied at.
+    /// `types`: The possible types that this discriminator can resolve.
+    /// `statuses`: Pairs of a status code pattern and the type it resolves to.
+    /// A pattern is either a status code (`404`), a class of status codes
+    /// (`4XX`) or an inclusive range of status codes (`500-599`).
+    pub fn new(
+        type_name: String,
+        types: BTreeSet<String>,
+        statuses: Vec<(String, String)>,
+    ) -> Valid<Self, String> {
+        Valid::from_iter(statuses, |(code, type_of)| {
+            let range = match parse_status(&code) {
+                Some(range) => Valid::succeed(range),
+                None => Valid::fail(format!(
+                    "`{}` is not a valid status code, expected a code like `404`, a class like `4XX` or a range like `500-599`",
+                    code
+                )),
+            };
+            let type_of = if types.contains(&type_of) {
+                Valid::succeed(type_of)
+            } else {
+                let types: Vec<_> = types.iter().collect();
+                Valid::fail(format!(
+                    "The type `{}` is not in the list of acceptable types {:?} of StatusDiscriminator(type=\"{}\")",
+                    type_of, types, type_name
+                ))
+            };
+
+            range.zip(type_of).trace(&code)
+        })
+        .map(|statuses| Self { statuses, type_name })
+    }
+
+    /// Checks if the status code is mapped to any of the types.
+    pub fn matches(&self, status: u16) -> bool {
+        self.find(status).is_some()
+    }
+
+    fn find(&self, status: u16) -> Option<&str> {
+        self.statuses
+            .iter()
+            .find(|(range, _)| range.contains(&status))
+            .map(|(_, type_of)| type_of.as_str())
+    }
+
+    /// Resolves the `__typename` for the object received with the given status
+    /// code and inserts it into the object. Values that weren't received from
+    /// an upstream in the current evaluation (eg. served from the cache) are
+    /// treated as `200` responses.
+    pub fn resolve_and_set_type(&self, mut value: Value, status: Option<u16>) -> Result<Value> {
+        let status = status.unwrap_or(200);
+        let Some(type_name) = self.find(status) else {
+            bail!(
+                "The StatusDiscriminator(type=\"{}\") cannot discriminate the Value because the status code `{}` is not mapped to any type",
+                self.type_name,
+                status
+            )
+        };
+
+        value.set_type_name(type_name.to_string())?;
+        Ok(value)
+    }
+}
+
+/// Parses a status code (`404`), a class of status codes (`4XX`) or an
+/// inclusive range of status codes (`500-599`) into the range of the codes.
+pub fn parse_status(code: &str) -> Option<RangeInclusive<u16>> {
+    let code = code.trim();
+    let range = if let Some((start, end)) = code.split_once('-') {
+        start.trim().parse().ok()?..=end.trim().parse().ok()?
+    } else if let Some(class) = code.strip_suffix("XX").or_else(|| code.strip_suffix("xx")) {
+        let class: u16 = class.parse().ok().filter(|class| *class < 10)?;
+        class * 100..=class * 100 + 99
+    } else {
+        let code = code.parse().ok()?;
+        code..=code
+    };
+
+    let is_valid = (100..=599).contains(range.start())
+        && (100..=599).contains(range.end())
+        && range.start() <= range.end();
+
+    is_valid.then_some(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Value;
+    use serde_json::json;
+    use tailcall_valid::Validator;
+    use test_log::test;
+
+    use super::*;
+
+    fn discriminator() -> StatusDiscriminator {
+        let types = ["Success", "NotFound", "Failure"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let statuses = [
+            ("2XX", "Success"),
+            ("404", "NotFound"),
+            ("400-599", "Failure"),
+        ]
+        .into_iter()
+        .map(|(code, type_of)| (code.to_string(), type_of.to_string()))
+        .collect();
+
+        StatusDiscriminator::new("Result".to_string(), types, statuses)
+            .to_result()
+            .unwrap()
+    }
+
+    fn resolve(status: u16) -> Value {
+        let value = Value::from_json(json!({ "id": 1 })).unwrap();
+        discriminator()
+            .resolve_and_set_type(value, Some(status))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_status_discrimination() {
+        assert_eq!(
+            resolve(200),
+            Value::from_json(json!({ "id": 1, "__typename": "Success" })).unwrap()
+        );
+        assert_eq!(
+            resolve(404),
+            Value::from_json(json!({ "id": 1, "__typename": "NotFound" })).unwrap()
+        );
+        assert_eq!(
+            resolve(500),
+            Value::from_json(json!({ "id": 1, "__typename": "Failure" })).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_status_cached() {
+        let value = Value::from_json(json!({ "id": 1 })).unwrap();
+
+        let actual = discriminator().resolve_and_set_type(value, None).unwrap();
+        let expected = Value::from_json(json!({ "id": 1, "__typename": "Success" })).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_status_not_mapped() {
+        let value = Value::from_json(json!({ "id": 1 })).unwrap();
+
+        let error = discriminator()
+            .resolve_and_set_type(value, Some(301))
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "The StatusDiscriminator(type=\"Result\") cannot discriminate the Value because the status code `301` is not mapped to any type"
+        );
+    }
+
+    #[test]
+    fn test_parse_status() {
+        assert_eq!(parse_status("404"), Some(404..=404));
+        assert_eq!(parse_status("5XX"), Some(500..=599));
+        assert_eq!(parse_status("4xx"), Some(400..=499));
+        assert_eq!(parse_status("200-299"), Some(200..=299));
+        assert_eq!(parse_status("299-200"), None);
+        assert_eq!(parse_status("9XX"), None);
+        assert_eq!(parse_status("ok"), None);
+    }
+
+    #[test]
+    fn test_invalid_statuses() {
+        let types = ["Success"].into_iter().map(String::from).collect();
+        let statuses = vec![
+            ("ok".to_string(), "Success".to_string()),
+            ("404".to_string(), "NotFound".to_string()),
+        ];
+
+        let errors = StatusDiscriminator::new("Result".to_string(), types, statuses)
+            .to_result()
+            .unwrap_err();
+
+        assert_eq!(errors.as_vec().len(), 2);
+    }
+}
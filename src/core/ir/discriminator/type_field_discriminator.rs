@@ -2,6 +2,7 @@ use std::collections::BTreeSet;
 
 use anyhow::{bail, Result};
 use async_graphql::Value;
+use serde::{Deserialize, Serialize};
 use tailcall_valid::Valid;
 
 use super::TypedValue;
@@ -15,7 +16,7 @@ use crate::core::json::JsonLike;
 ///
 /// This discriminator is used when the type of an object can be determined by
 /// a specific field.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TypeFieldDiscriminator {
     /// The field name that contains the type
     typename_field: String,
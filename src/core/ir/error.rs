@@ -12,6 +12,13 @@ use crate::core::{auth, cache, worker, Errata};
 pub enum Error {
     IO(String),
 
+    /// Upstream responded with a non-success HTTP status.
+    #[from(ignore)]
+    HTTP {
+        status: u16,
+        message: String,
+    },
+
     GRPC {
         grpc_code: i32,
         grpc_description: String,
@@ -35,6 +42,33 @@ pub enum Error {
 
     #[from(ignore)]
     Entity(String),
+
+    /// Upstream GraphQL server responded with errors that fail the field.
+    #[from(ignore)]
+    GraphQL(Vec<String>),
+
+    /// The request was aborted before the evaluation completed.
+    #[from(ignore)]
+    Cancelled,
+
+    /// The request made more upstream calls than allowed by
+    /// `@server(maxUpstreamCalls)`.
+    #[from(ignore)]
+    BudgetExceeded(usize),
+
+    /// A path into the resolved value is longer than allowed by
+    /// `@server(maxPathDepth)`.
+    #[from(ignore)]
+    PathTooDeep(usize),
+
+    /// The bulkhead of the upstream had no free slot for the request, see
+    /// `@upstream(bulkhead)`.
+    #[from(ignore)]
+    Overloaded(String),
+
+    /// The arguments of the field violate their `@validate` constraints.
+    #[from(ignore)]
+    InvalidArgument(Vec<String>),
 }
 
 impl Display for Error {
@@ -46,7 +80,9 @@ impl Display for Error {
 impl From<Error> for Errata {
     fn from(value: Error) -> Self {
         match value {
-            Error::IO(message) => Errata::new("IOException").description(message),
+            Error::IO(message) | Error::HTTP { message, .. } => {
+                Errata::new("IOException").description(message)
+            }
             Error::GRPC {
                 grpc_code,
                 grpc_description,
@@ -67,27 +103,85 @@ impl From<Error> for Errata {
             }
             Error::Worker(err) => Errata::new("Worker Error").description(err.to_string()),
             Error::Cache(err) => Errata::new("Cache Error").description(err.to_string()),
-            Error::Entity(message) => Errata::new("Entity Resolver Error").description(message)
+            Error::Entity(message) => Errata::new("Entity Resolver Error").description(message),
+            Error::GraphQL(errors) => Errata::new("GraphQL Error")
+                .caused_by(errors.iter().map(|e| Errata::new(e)).collect::<Vec<_>>()),
+            Error::Cancelled => Errata::new("Cancelled")
+                .description("The request was aborted before the upstream call completed".to_string()),
+            Error::BudgetExceeded(max) => Errata::new("Budget Exceeded")
+                .description(format!("The request exceeded the limit of {max} upstream calls")),
+            Error::PathTooDeep(max) => Errata::new("Path Too Deep")
+                .description(format!("The path exceeds the limit of {max} keys")),
+            Error::Overloaded(message) => Errata::new("Service Overloaded").description(message),
+            Error::InvalidArgument(errors) => Errata::new("Invalid Argument")
+                .caused_by(errors.iter().map(|e| Errata::new(e)).collect::<Vec<_>>()),
+        }
+    }
+}
+
+impl Error {
+    /// Status code of the response of a REST endpoint that fails with the
+    /// error. The client errors of the upstreams are passed through, while
+    /// the other failures of the upstreams are reported as a bad gateway.
+    pub fn rest_status(&self) -> Option<u16> {
+        match self {
+            Error::HTTP { status, .. } if (400..500).contains(status) => Some(*status),
+            Error::HTTP { .. }
+            | Error::IO(_)
+            | Error::APIValidation(_)
+            | Error::Deserialize(_)
+            | Error::GraphQL(_) => Some(502),
+            Error::GRPC { grpc_code, .. } => Some(match tonic::Code::from_i32(*grpc_code) {
+                tonic::Code::InvalidArgument | tonic::Code::OutOfRange => 400,
+                tonic::Code::Unauthenticated => 401,
+                tonic::Code::PermissionDenied => 403,
+                tonic::Code::NotFound => 404,
+                tonic::Code::AlreadyExists | tonic::Code::Aborted => 409,
+                tonic::Code::FailedPrecondition => 412,
+                tonic::Code::ResourceExhausted => 429,
+                tonic::Code::Unavailable => 503,
+                tonic::Code::DeadlineExceeded => 504,
+                _ => 502,
+            }),
+            Error::Auth(_) => Some(401),
+            Error::BudgetExceeded(_) | Error::PathTooDeep(_) => Some(422),
+            Error::InvalidArgument(_) => Some(400),
+            Error::Overloaded(_) => Some(503),
+            _ => None,
         }
     }
 }
 
 impl ErrorExtensions for Error {
     fn extend(&self) -> ExtensionError {
-        ExtensionError::new(format!("{}", self)).extend_with(|_err, e| {
-            if let Error::GRPC {
-                grpc_code,
-                grpc_description,
-                grpc_status_message,
-                grpc_status_details,
-            } = self
-            {
-                e.set("grpcCode", *grpc_code);
-                e.set("grpcDescription", grpc_description);
-                e.set("grpcStatusMessage", grpc_status_message);
-                e.set("grpcStatusDetails", grpc_status_details.clone());
-            }
-        })
+        ExtensionError::new(format!("{}", self))
+            .status(self.rest_status())
+            .extend_with(|_err, e| {
+                if let Error::GRPC {
+                    grpc_code,
+                    grpc_description,
+                    grpc_status_message,
+                    grpc_status_details,
+                } = self
+                {
+                    e.set("grpcCode", *grpc_code);
+                    e.set("grpcDescription", grpc_description);
+                    e.set("grpcStatusMessage", grpc_status_message);
+                    e.set("grpcStatusDetails", grpc_status_details.clone());
+                }
+                if let Error::BudgetExceeded(_) = self {
+                    e.set("code", "BUDGET_EXCEEDED");
+                }
+                if let Error::PathTooDeep(_) = self {
+                    e.set("code", "PATH_TOO_DEEP");
+                }
+                if let Error::Overloaded(_) = self {
+                    e.set("code", "SERVICE_OVERLOADED");
+                }
+                if let Error::InvalidArgument(_) = self {
+                    e.set("code", "BAD_USER_INPUT");
+                }
+            })
     }
 }
 
@@ -107,11 +201,23 @@ impl From<Arc<anyhow::Error>> for Error {
     fn from(error: Arc<anyhow::Error>) -> Self {
         match error.downcast_ref::<Error>() {
             Some(err) => err.clone(),
-            None => Error::IO(error.to_string()),
+            None => from_untyped(&error),
         }
     }
 }
 
+/// Keeps the status code of the failed upstream responses, so that it could be
+/// later used eg. to discriminate the type of the result.
+fn from_untyped(error: &anyhow::Error) -> Error {
+    match error
+        .downcast_ref::<reqwest::Error>()
+        .and_then(|err| err.status())
+    {
+        Some(status) => Error::HTTP { status: status.as_u16(), message: error.to_string() },
+        None => Error::IO(error.to_string()),
+    }
+}
+
 // TODO: remove conversion from anyhow and don't use anyhow to pass errors
 // since it loses potentially valuable information that could be later provided
 // in the error extensions
@@ -119,7 +225,7 @@ impl From<anyhow::Error> for Error {
     fn from(value: anyhow::Error) -> Self {
         match value.downcast::<Error>() {
             Ok(err) => err,
-            Err(err) => Error::IO(err.to_string()),
+            Err(err) => from_untyped(&err),
         }
     }
 }
@@ -1,19 +1,44 @@
 use std::collections::HashMap;
 use std::future::Future;
+use std::io::Write;
 use std::ops::Deref;
 
+use async_graphql::ServerError;
 use async_graphql_value::ConstValue;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use futures_util::future::join_all;
+use futures_util::stream::{self, BoxStream};
+use futures_util::StreamExt;
 use indexmap::IndexMap;
+use once_cell::sync::Lazy;
+use opentelemetry::metrics::Counter;
 
-use super::eval_io::eval_io;
-use super::model::{Cache, CacheKey, Map, IR};
-use super::{Error, EvalContext, ResolverContextLike, TypedValue};
+use super::eval_io::{eval_io, eval_stream, eval_subscription};
+use super::model::{
+    Cache, CacheKey, Compress, Fallback, Filter, Join, Map, MapMissing, Mask, Merge, Protect, IO,
+    IR,
+};
+use super::{Discriminator, Error, EvalContext, Next, ResolverContextLike, TypedValue};
+use crate::core::auth::error::Error as AuthError;
 use crate::core::auth::verify::{AuthVerifier, Verify};
-use crate::core::json::{JsonLike, JsonObjectLike};
+use crate::core::config::JoinKind;
+use crate::core::http::Vary;
+use crate::core::json::{key_to_string, JsonLike, JsonObjectLike};
 use crate::core::merge_right::MergeRight;
 use crate::core::serde_value_ext::ValueExt;
 
+static CACHE_STALE_SERVED_COUNT: Lazy<Counter<u64>> = Lazy::new(|| {
+    let meter = opentelemetry::global::meter("cache");
+
+    meter
+        .u64_counter("cache.stale.served")
+        .with_description("Number of stale cached values served in place of errors")
+        .init()
+});
+
 impl IR {
     #[tracing::instrument(skip_all, fields(otel.name = %self), err)]
     pub fn eval<'a, 'b, Ctx>(
@@ -23,64 +48,241 @@ impl IR {
     where
         Ctx: ResolverContextLike + Sync,
     {
-        Box::pin(async move {
+        async move {
+            let req_ctx = ctx.request_ctx;
+            let middlewares = &req_ctx.runtime.eval_middlewares;
+            if middlewares.is_empty() {
+                return self.eval_node(ctx).await;
+            }
+
+            // the first middleware is the outermost one
+            let mut next = Next::new(Box::pin(self.eval_node(ctx)));
+            for middleware in middlewares.iter().rev() {
+                next = Next::new(middleware.around(self, req_ctx, next));
+            }
+
+            next.run().await
+        }
+    }
+
+    fn eval_node<'a, 'b, Ctx>(
+        &'a self,
+        ctx: &'b mut EvalContext<'a, Ctx>,
+    ) -> impl Future<Output = Result<ConstValue, Error>> + Send + use<'a, 'b, Ctx>
+    where
+        Ctx: ResolverContextLike + Sync,
+    {
+        // only the nested evaluations are boxed, so that the recursive future
+        // is sized, and the IOs, whose futures are too large to be inlined in
+        // the ones of the fields, the other leaves are evaluated without an
+        // allocation
+        async move {
             match self {
                 IR::ContextPath(path) => Ok(ctx
                     .path_value(path)
                     .map(|a| a.into_owned())
                     .unwrap_or(async_graphql::Value::Null)),
                 IR::Path(input, path) => {
-                    let inp = input.eval(ctx).await?;
+                    if let Some(max) = ctx.request_ctx.server.max_path_depth {
+                        if path.len() > max {
+                            return Err(Error::PathTooDeep(max));
+                        }
+                    }
+
+                    let inp = Box::pin(input.eval(ctx)).await?;
                     Ok(inp
                         .get_path(path)
                         .unwrap_or(&async_graphql::Value::Null)
                         .clone())
                 }
                 IR::Dynamic(value) => Ok(value.render_value(ctx)),
-                IR::Protect(auth, expr) => {
+                IR::Protect(protect) => match verify(protect, ctx).await? {
+                    Some(anon) => Box::pin(anon.eval(ctx)).await,
+                    None => Box::pin(protect.authed.eval(ctx)).await,
+                },
+                IR::Mask(Mask { input, auth, scope, fields }) => {
+                    let value = Box::pin(input.eval(ctx)).await?;
                     let verifier = AuthVerifier::from(auth.clone());
-                    verifier.verify(ctx.request_ctx).await.to_result()?;
 
-                    expr.eval(ctx).await
+                    if verifier.scopes(ctx.request_ctx).await.contains(scope) {
+                        Ok(value)
+                    } else {
+                        Ok(mask(value, fields))
+                    }
+                }
+                IR::Compress(Compress { input, min_size }) => {
+                    let value = Box::pin(input.eval(ctx)).await?;
+
+                    match value {
+                        ConstValue::String(value)
+                            if ctx.request_ctx.compressed_fields && value.len() >= *min_size =>
+                        {
+                            Ok(ConstValue::String(compress(&value)?))
+                        }
+                        value => Ok(value),
+                    }
                 }
-                IR::IO(io) => eval_io(io, ctx).await,
-                IR::Cache(Cache { max_age, io }) => {
+                IR::IO(io) => Box::pin(eval_io(io, ctx)).await,
+                IR::Cache(cache) => {
+                    let Cache { tags, io, serve_stale_on_error, .. } = cache;
                     let io = io.deref();
-                    let key = io.cache_key(ctx);
+                    let key = cache.cache_key(ctx);
                     if let Some(key) = key {
-                        if let Some(val) = ctx.request_ctx.runtime.cache.get(&key).await? {
+                        let runtime_cache = ctx.request_ctx.runtime.cache.clone();
+                        // the headers the last response varied by, the key of the
+                        // entry can only be known once they are
+                        let vary_key = Cache::vary_key(&key);
+                        let entry_tag = Cache::entry_tag(&key);
+                        let varied = match runtime_cache.get(&vary_key).await? {
+                            Some(ConstValue::List(names)) => names
+                                .into_iter()
+                                .filter_map(|name| match name {
+                                    ConstValue::String(name) => Some(name),
+                                    _ => None,
+                                })
+                                .collect(),
+                            _ => vec![],
+                        };
+                        let names = cache.vary_headers(&varied);
+                        let mut entry_key = Cache::varied_key(key.clone(), &names, ctx);
+
+                        if let Some(val) = runtime_cache.get(&entry_key).await? {
+                            ctx.record_stats(|stats, path| stats.cache_hit(path));
                             Ok(val)
                         } else {
-                            let val = eval_io(io, ctx).await?;
-                            ctx.request_ctx
-                                .runtime
-                                .cache
-                                .set(key, val.clone(), max_age.to_owned())
-                                .await?;
-                            Ok(val)
+                            // the concurrent misses of the requests are coalesced into
+                            // a single refresh, whose value is shared with all of them
+                            let refreshes = ctx.request_ctx.cache_refreshes.clone();
+                            let refresh_key = entry_key.clone();
+                            refreshes
+                                .dedupe(&refresh_key, || async move {
+                                    ctx.set_upstream_vary(None);
+                                    let val = match Box::pin(eval_io(io, ctx)).await {
+                                        // the failures with a negative status are the
+                                        // negative results that are cached as null
+                                        Err(Error::HTTP { status, .. })
+                                            if cache.negative.as_ref().is_some_and(
+                                                |negative| negative.matches_status(status),
+                                            ) =>
+                                        {
+                                            ctx.set_upstream_status(status);
+                                            ConstValue::Null
+                                        }
+                                        Err(err) if *serve_stale_on_error => {
+                                            let stale_key = Cache::stale_key(&entry_key);
+                                            let Some(stale) = runtime_cache.get(&stale_key).await?
+                                            else {
+                                                return Err(err);
+                                            };
+
+                                            tracing::warn!("Serving a stale cached value: {}", err);
+                                            CACHE_STALE_SERVED_COUNT.add(1, &[]);
+                                            return Ok(stale);
+                                        }
+                                        val => val?,
+                                    };
+                                    match ctx.upstream_vary() {
+                                        Some(Vary::Any) => return Ok(val),
+                                        Some(Vary::Headers(upstream)) if upstream != &varied => {
+                                            let names =
+                                                upstream.iter().cloned().map(ConstValue::String);
+                                            runtime_cache
+                                                .set_with_tags(
+                                                    vary_key,
+                                                    ConstValue::List(names.collect()),
+                                                    cache.stale_max_age(),
+                                                    vec![entry_tag.clone()],
+                                                )
+                                                .await?;
+                                            entry_key = Cache::varied_key(
+                                                key,
+                                                &cache.vary_headers(upstream),
+                                                ctx,
+                                            );
+                                        }
+                                        _ => {}
+                                    }
+                                    // tags are rendered against the resolved value, so that they
+                                    // refer to the entity that is actually stored
+                                    let tag_ctx = ctx.with_value(val.clone());
+                                    let tags: Vec<_> = tags
+                                        .iter()
+                                        .map(|tag| tag.render(&tag_ctx))
+                                        .chain([entry_tag.clone()])
+                                        .collect();
+                                    // the stale copy is tagged too, so that an invalidated
+                                    // entity isn't served on errors either
+                                    if *serve_stale_on_error {
+                                        runtime_cache
+                                            .set_with_tags(
+                                                Cache::stale_key(&entry_key),
+                                                val.clone(),
+                                                cache.stale_max_age(),
+                                                tags.clone(),
+                                            )
+                                            .await?;
+                                    }
+                                    let negative =
+                                        cache.negative.as_ref().is_some_and(|negative| {
+                                            negative.matches(&val, ctx.upstream_status())
+                                        });
+                                    // the duration is adapted to whether the value changed
+                                    // since the last refresh, the negative ones aren't
+                                    let max_age = match &cache.adaptive {
+                                        Some(adaptive) if !negative => {
+                                            let adaptive_key = Cache::adaptive_key(&entry_key);
+                                            let record = runtime_cache.get(&adaptive_key).await?;
+                                            let (max_age, record) = adaptive.max_age(
+                                                cache.max_age,
+                                                record.as_ref(),
+                                                &val,
+                                            );
+                                            runtime_cache
+                                                .set_with_tags(
+                                                    adaptive_key,
+                                                    record,
+                                                    cache.stale_max_age().max(adaptive.max_max_age),
+                                                    vec![entry_tag],
+                                                )
+                                                .await?;
+                                            cache.adapted_max_age(max_age, &val, negative)
+                                        }
+                                        _ => cache.entry_max_age(&val, negative),
+                                    };
+                                    runtime_cache
+                                        .set_with_tags(entry_key, val.clone(), max_age, tags)
+                                        .await?;
+                                    Ok::<_, Error>(val)
+                                })
+                                .await
                         }
                     } else {
-                        eval_io(io, ctx).await
+                        Box::pin(eval_io(io, ctx)).await
                     }
                 }
-                IR::Map(Map { input, map }) => {
+                IR::Map(Map { input, map, on_missing }) => {
                     fn recursive_map_enum(
                         val: Result<ConstValue, Error>,
                         map: &HashMap<String, String>,
+                        on_missing: &MapMissing,
                     ) -> Result<ConstValue, Error> {
                         match val? {
                             ConstValue::Null => Ok(ConstValue::Null),
-                            ConstValue::String(key) => {
-                                if let Some(value) = map.get(&key) {
+                            ConstValue::String(key) => match (map.get(&key), on_missing) {
+                                (Some(value), _) => Ok(ConstValue::String(value.to_owned())),
+                                (None, MapMissing::Null) => Ok(ConstValue::Null),
+                                (None, MapMissing::Passthrough) => Ok(ConstValue::String(key)),
+                                (None, MapMissing::Default(value)) => {
                                     Ok(ConstValue::String(value.to_owned()))
-                                } else {
+                                }
+                                (None, MapMissing::Error) => {
                                     Err(Error::ExprEval(format!("Can't find mapped key: {}.", key)))
                                 }
-                            }
+                            },
                             ConstValue::List(vec) => {
                                 let vec = vec
                                     .into_iter()
-                                    .map(|value| recursive_map_enum(Ok(value), map))
+                                    .map(|value| recursive_map_enum(Ok(value), map, on_missing))
                                     .collect::<Result<Vec<_>, _>>()?;
                                 Ok(ConstValue::List(vec))
                             }
@@ -89,22 +291,61 @@ impl IR {
                             )),
                         }
                     }
-                    recursive_map_enum(input.eval(ctx).await, map)
+                    recursive_map_enum(Box::pin(input.eval(ctx)).await, map, on_missing)
+                }
+                IR::Filter(Filter { input, predicate }) => {
+                    // an absent list is filtered as an empty one
+                    let items = match Box::pin(input.eval(ctx)).await? {
+                        ConstValue::Null => vec![],
+                        ConstValue::List(items) => items,
+                        value => vec![value],
+                    };
+
+                    Ok(ConstValue::List(
+                        items
+                            .into_iter()
+                            .filter(|item| predicate.test(item))
+                            .collect(),
+                    ))
+                }
+                IR::Compact(compact) => match Box::pin(compact.input.eval(ctx)).await? {
+                    ConstValue::List(items) => Ok(ConstValue::List(
+                        items
+                            .into_iter()
+                            .filter(|item| compact.keeps(item))
+                            .collect(),
+                    )),
+                    value => Ok(value),
+                },
+                IR::Validate(validate) => {
+                    let violations = validate
+                        .constraints
+                        .iter()
+                        .flat_map(|constraint| constraint.check(|name| ctx.path_arg(&[name])))
+                        .collect::<Vec<_>>();
+                    if !violations.is_empty() {
+                        return Err(Error::InvalidArgument(violations));
+                    }
+
+                    Box::pin(validate.input.eval(ctx)).await
                 }
                 IR::Pipe(first, second) => {
-                    let args = first.eval(&mut ctx.clone()).await?;
+                    let args = Box::pin(first.eval(&mut ctx.clone())).await?;
                     let ctx = &mut ctx.with_args(args);
-                    second.eval(ctx).await
+                    Box::pin(second.eval(ctx)).await
                 }
-                IR::Merge(vec) => {
-                    let results: Vec<_> = join_all(vec.iter().map(|ir| {
+                IR::Merge(Merge { irs, tolerate_partial }) => {
+                    let results = join_all(irs.iter().map(|ir| {
                         let mut ctx = ctx.clone();
 
-                        async move { ir.eval(&mut ctx).await }
+                        async move { Box::pin(ir.eval(&mut ctx)).await }
                     }))
-                    .await
-                    .into_iter()
-                    .collect::<Result<_, _>>()?;
+                    .await;
+                    let results = if *tolerate_partial {
+                        partial(ctx, results)?
+                    } else {
+                        results.into_iter().collect::<Result<_, _>>()?
+                    };
 
                     // TODO: This is a very opinionated merge. We should allow users to customize
                     // how they would like to merge the values. In future we should support more
@@ -114,10 +355,88 @@ impl IR {
                         .reduce(|acc, result| acc.merge_right(result))
                         .unwrap_or_default())
                 }
-                IR::Discriminate(discriminator, expr) => expr
-                    .eval(ctx)
-                    .await
-                    .and_then(|value| Ok(discriminator.resolve_type(value)?)),
+                IR::Fallback(Fallback { irs, on_null }) => {
+                    let mut result = Ok(ConstValue::Null);
+                    for ir in irs {
+                        result = Box::pin(ir.eval(&mut ctx.clone())).await;
+                        match &result {
+                            Ok(ConstValue::Null) if *on_null => {}
+                            // the request is aborted, there's no one to fall back for
+                            Ok(_) | Err(Error::Cancelled) => break,
+                            Err(err) => tracing::debug!("Falling back after: {}", err),
+                        }
+                    }
+
+                    result
+                }
+                IR::Join(Join { left, right, on: (left_on, right_on), how }) => {
+                    let rows = match Box::pin(left.eval(ctx)).await? {
+                        ConstValue::Null => vec![],
+                        ConstValue::List(rows) => rows,
+                        value => vec![value],
+                    };
+
+                    // the right side is resolved once for every distinct key, with
+                    // the first left element of the key as the value
+                    let mut keys = HashMap::new();
+                    for row in rows.iter() {
+                        if let Some(key) = row.get_path(left_on).and_then(key_to_string) {
+                            keys.entry(key).or_insert(row);
+                        }
+                    }
+                    let matches =
+                        join_all(
+                            keys.into_iter().map(|(key, row)| {
+                                let mut ctx = ctx.with_value(row.clone());
+
+                                async move {
+                                    Ok::<_, Error>((key, Box::pin(right.eval(&mut ctx)).await?))
+                                }
+                            }),
+                        )
+                        .await
+                        .into_iter()
+                        .collect::<Result<HashMap<_, _>, _>>()?;
+
+                    let find_match = |row: &ConstValue| {
+                        let key = key_to_string(row.get_path(left_on)?)?;
+                        let candidates = match matches.get(&key)? {
+                            ConstValue::List(candidates) => candidates.iter().collect(),
+                            candidate => vec![candidate],
+                        };
+
+                        candidates.into_iter().find(|candidate| {
+                            candidate
+                                .get_path(right_on)
+                                .and_then(key_to_string)
+                                .is_some_and(|right_key| right_key == key)
+                        })
+                    };
+
+                    Ok(ConstValue::List(
+                        rows.into_iter()
+                            .filter_map(|row| match (find_match(&row), how) {
+                                (Some(found), _) => Some(row.merge_right(found.clone())),
+                                (None, JoinKind::Left) => Some(row),
+                                (None, JoinKind::Inner) => None,
+                            })
+                            .collect(),
+                    ))
+                }
+                IR::Discriminate(discriminator, expr) => {
+                    let value = match Box::pin(expr.eval(ctx)).await {
+                        // failed responses are still discriminated by their status, while
+                        // their body isn't kept
+                        Err(Error::HTTP { status, .. }) if matches!(discriminator, Discriminator::Status(d) if d.matches(status)) =>
+                        {
+                            ctx.set_upstream_status(status);
+                            ConstValue::Object(Default::default())
+                        }
+                        value => value?,
+                    };
+
+                    Ok(discriminator.resolve_type(value, ctx.upstream_status())?)
+                }
                 IR::Entity(map) => {
                     let representations = ctx.path_arg(&["representations"]);
 
@@ -144,7 +463,7 @@ impl IR {
                         let mut ctx = ctx.with_value(repr.clone());
 
                         tasks.push(async move {
-                            ir.eval(&mut ctx).await.and_then(|mut value| {
+                            Box::pin(ir.eval(&mut ctx)).await.and_then(|mut value| {
                                 // set typename explicitly to reuse it if needed
                                 value.set_type_name(type_name.to_owned())?;
                                 Ok(value)
@@ -166,7 +485,152 @@ impl IR {
                     Ok(ConstValue::object(obj))
                 }
             }
-        })
+        }
+    }
+
+    /// Opens the stream of events for a field of the subscription root. The
+    /// events are received from the upstream GraphQL subscriptions, the
+    /// outer operators other than `@protected` and the arguments of `@call`
+    /// aren't applied to them.
+    pub async fn subscribe<Ctx>(
+        &self,
+        ctx: &EvalContext<'_, Ctx>,
+    ) -> Result<BoxStream<'static, Result<ConstValue, Error>>, Error>
+    where
+        Ctx: ResolverContextLike + Sync,
+    {
+        match self {
+            IR::Protect(protect) => match verify(protect, ctx).await? {
+                Some(anon) => Box::pin(anon.subscribe(ctx)).await,
+                None => Box::pin(protect.authed.subscribe(ctx)).await,
+            },
+            // the arguments of the `@call` steps are resolved before the
+            // stream of the called field is opened
+            IR::Pipe(first, second) => {
+                let args = Box::pin(first.eval(&mut ctx.clone())).await?;
+                Box::pin(second.subscribe(&ctx.with_args(args))).await
+            }
+            IR::IO(io) => eval_subscription(io, ctx),
+            _ => Err(Error::IO(
+                "Subscription fields can only be resolved with @graphQL, @http or @call"
+                    .to_string(),
+            )),
+        }
+    }
+
+    /// Opens the stream of the elements of a `@stream` list field. The lists
+    /// of `@http` and of the server-streaming `@grpc` methods that aren't
+    /// batched or transformed by the hooks are streamed as they're received
+    /// from the upstream, any other list is resolved in full before its
+    /// elements are streamed. So are the `@http` lists whose response is
+    /// transformed or validated as a whole. It's `None` when the list resolves
+    /// to null.
+    pub async fn stream<'a, Ctx>(
+        &'a self,
+        ctx: &mut EvalContext<'a, Ctx>,
+    ) -> Result<Option<BoxStream<'static, Result<ConstValue, Error>>>, Error>
+    where
+        Ctx: ResolverContextLike + Sync,
+    {
+        match self {
+            IR::IO(
+                io @ IO::Http { req_template, is_list: true, group_by: None, hook: None, .. },
+            ) if req_template.coerce_list.is_none()
+                && req_template.response_schema.is_none()
+                && req_template.connection.is_none()
+                && req_template.redirect.is_none()
+                && !req_template.raw_body =>
+            {
+                eval_stream(io, ctx).await
+            }
+            IR::IO(io @ IO::Grpc { req_template, group_by: None, hook: None, .. })
+                if req_template.operation.method.is_server_streaming()
+                    && req_template.dynamic_method.is_none() =>
+            {
+                eval_stream(io, ctx).await
+            }
+            ir => match ir.eval(ctx).await? {
+                ConstValue::List(items) => {
+                    Ok(Some(stream::iter(items.into_iter().map(Ok)).boxed()))
+                }
+                ConstValue::Null => Ok(None),
+                _ => Err(Error::IO("Only the lists can be streamed".to_string())),
+            },
+        }
+    }
+}
+
+/// Verifies the credentials of the request, returns the IR that resolves the
+/// anonymous requests when they're allowed to fall back to it.
+async fn verify<'a, Ctx: ResolverContextLike>(
+    protect: &'a Protect,
+    ctx: &EvalContext<'_, Ctx>,
+) -> Result<Option<&'a IR>, Error> {
+    let verifier = AuthVerifier::from(protect.auth.clone());
+    match (
+        verifier.verify(ctx.request_ctx).await.to_result(),
+        &protect.anon,
+    ) {
+        (Ok(()), _) => Ok(None),
+        (Err(AuthError::Missing), Some(anon)) => Ok(Some(anon)),
+        (Err(err), _) => Err(err.into()),
+    }
+}
+
+/// Keeps the results of the branches that succeeded, the errors of the others
+/// are added to the response at the path of the field. It fails with the first
+/// error if none succeeded.
+fn partial<Ctx: ResolverContextLike>(
+    ctx: &EvalContext<'_, Ctx>,
+    results: Vec<Result<ConstValue, Error>>,
+) -> Result<Vec<ConstValue>, Error> {
+    let (values, errors): (Vec<_>, Vec<_>) = results.into_iter().partition(Result::is_ok);
+    let mut errors = errors.into_iter().filter_map(Result::err);
+
+    if values.is_empty() {
+        if let Some(error) = errors.next() {
+            return Err(error);
+        }
+    }
+    let path = ctx.response_path();
+    for error in errors {
+        tracing::warn!("Skipping a failed branch of the merge: {}", error);
+        ctx.add_error(ServerError {
+            path: path.clone(),
+            ..ServerError::new(error.to_string(), None)
+        });
+    }
+
+    Ok(values.into_iter().filter_map(Result::ok).collect())
+}
+
+/// Gzips the value, it's base64 encoded and prefixed with `gzip:` to be sent
+/// as a string.
+fn compress(value: &str) -> Result<String, Error> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(value.as_bytes())
+        .map_err(|e| Error::IO(e.to_string()))?;
+    let bytes = encoder.finish().map_err(|e| Error::IO(e.to_string()))?;
+
+    Ok(format!("gzip:{}", BASE64_STANDARD.encode(bytes)))
+}
+
+/// Replaces the masked fields of the objects with null.
+fn mask(value: ConstValue, fields: &[String]) -> ConstValue {
+    match value {
+        ConstValue::Object(mut object) => {
+            for field in fields {
+                if let Some(value) = object.get_mut(field.as_str()) {
+                    *value = ConstValue::Null;
+                }
+            }
+            ConstValue::Object(object)
+        }
+        ConstValue::List(list) => {
+            ConstValue::List(list.into_iter().map(|value| mask(value, fields)).collect())
+        }
+        value => value,
     }
 }
 
@@ -215,7 +679,7 @@ mod tests {
                 .unwrap(),
             );
 
-            let ir = IR::Merge([a, b, c].into_iter().map(IR::Dynamic).collect());
+            let ir = IR::Merge(Merge::new([a, b, c].into_iter().map(IR::Dynamic).collect()));
             let runtime = crate::cli::runtime::init(&Blueprint::default());
             let req_ctx = RequestContext::new(runtime);
             let res_ctx = EmptyResolverContext {};
@@ -236,5 +700,2042 @@ mod tests {
 
             assert_eq!(actual, expected);
         }
+
+        fn value(value: serde_json::Value) -> IR {
+            IR::Dynamic(DynamicValue::Value(ConstValue::from_json(value).unwrap()))
+        }
+
+        /// Fails to map the missing key
+        fn failing() -> IR {
+            IR::Map(Map {
+                input: Box::new(value(json!("paused"))),
+                map: HashMap::new(),
+                on_missing: MapMissing::Error,
+            })
+        }
+
+        async fn eval_merge(irs: Vec<IR>, tolerate_partial: bool) -> Result<ConstValue, Error> {
+            let ir = IR::Merge(Merge { irs, tolerate_partial });
+            let req_ctx = RequestContext::default();
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+
+            ir.eval(&mut eval_ctx).await
+        }
+
+        #[tokio::test]
+        async fn test_partial() {
+            let irs = vec![value(json!({"a": 1})), failing(), value(json!({"c": 3}))];
+
+            let error = eval_merge(irs.clone(), false).await.unwrap_err();
+            assert!(matches!(error, Error::ExprEval(_)), "{error:?}");
+
+            let actual = eval_merge(irs, true).await.unwrap();
+            assert_eq!(actual.into_json().unwrap(), json!({"a": 1, "c": 3}));
+        }
+
+        #[tokio::test]
+        async fn test_partial_all_failed() {
+            let actual = eval_merge(vec![failing(), failing()], true).await;
+
+            assert!(matches!(
+                actual,
+                Err(Error::ExprEval(message)) if message == "Can't find mapped key: paused."
+            ));
+        }
+    }
+
+    mod fallback {
+        use serde_json::json;
+
+        use super::*;
+        use crate::core::app_context::test::{TestApp, TestHttp};
+        use crate::core::blueprint::DynamicValue;
+        use crate::core::http::RequestContext;
+        use crate::core::ir::EmptyResolverContext;
+
+        fn value(value: serde_json::Value) -> IR {
+            IR::Dynamic(DynamicValue::Value(ConstValue::from_json(value).unwrap()))
+        }
+
+        /// Fails to map the missing key
+        fn failing(key: &str) -> IR {
+            IR::Map(Map {
+                input: Box::new(value(json!(key))),
+                map: HashMap::new(),
+                on_missing: MapMissing::Error,
+            })
+        }
+
+        async fn eval_fallback(irs: Vec<IR>, on_null: bool) -> Result<ConstValue, Error> {
+            let ir = IR::Fallback(Fallback { irs, on_null });
+            let req_ctx = RequestContext::default();
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+
+            ir.eval(&mut eval_ctx).await
+        }
+
+        #[tokio::test]
+        async fn test_first_success() {
+            let irs = vec![failing("a"), value(json!(null)), value(json!({"c": 3}))];
+
+            let actual = eval_fallback(irs.clone(), false).await.unwrap();
+            assert_eq!(actual, ConstValue::Null);
+
+            let actual = eval_fallback(irs, true).await.unwrap();
+            assert_eq!(actual.into_json().unwrap(), json!({"c": 3}));
+        }
+
+        #[tokio::test]
+        async fn test_all_failed() {
+            let actual = eval_fallback(vec![failing("a"), failing("b")], false).await;
+
+            assert!(matches!(
+                actual,
+                Err(Error::ExprEval(message)) if message == "Can't find mapped key: b."
+            ));
+        }
+
+        const CONFIG: &str = r#"
+            schema @server @upstream {
+                query: Query
+            }
+
+            type Query {
+                user: User
+                    @http(url: "http://primary.com/user")
+                    @http(url: "http://secondary.com/user")
+                    @http(url: "http://tertiary.com/user")
+                    @fallback(onNull: true)
+            }
+
+            type User {
+                id: Int
+                name: String
+            }
+        "#;
+
+        #[tokio::test]
+        async fn test_failover() {
+            let http = TestHttp::body(|request| {
+                Ok(match request.url().host_str() {
+                    Some("primary.com") => anyhow::bail!("Connection refused"),
+                    Some("secondary.com") => "null",
+                    _ => r#"{"id": 1, "name": "foo"}"#,
+                })
+            });
+            let response = TestApp::new(CONFIG, http.clone())
+                .execute("query { user { id name } }")
+                .await;
+
+            assert_eq!(
+                response["data"],
+                json!({ "user": { "id": 1, "name": "foo" } })
+            );
+            assert!(response.get("errors").is_none(), "{response}");
+            // the upstreams are tried in order
+            assert_eq!(
+                http.urls(),
+                vec![
+                    "http://primary.com/user",
+                    "http://secondary.com/user",
+                    "http://tertiary.com/user",
+                ]
+            );
+        }
+    }
+
+    mod canary {
+        use serde_json::json;
+
+        use crate::core::app_context::test::{TestApp, TestHttp};
+
+        const CONFIG: &str = r#"
+            schema @server @upstream(canary: {baseURL: "http://canary.com", sample: 100}) {
+                query: Query
+                mutation: Mutation
+            }
+
+            type Query {
+                user: User @http(url: "http://primary.com/user")
+            }
+
+            type Mutation {
+                createUser: User @http(url: "http://primary.com/user", method: POST)
+            }
+
+            type User {
+                id: Int
+                name: String
+            }
+        "#;
+
+        #[tokio::test]
+        async fn test_shadow_to_canary() {
+            let http = TestHttp::body(|request| {
+                Ok(match request.url().host_str() {
+                    Some("canary.com") => r#"{"id": 1, "name": "bar"}"#,
+                    _ => r#"{"id": 1, "name": "foo"}"#,
+                })
+            });
+            let response = TestApp::new(CONFIG, http.clone())
+                .execute("query { user { id name } }")
+                .await;
+
+            // the clients are served the primary result, without waiting for
+            // the canary
+            assert_eq!(
+                response["data"],
+                json!({ "user": { "id": 1, "name": "foo" } })
+            );
+            while http.hits() < 2 {
+                tokio::task::yield_now().await;
+            }
+            assert_eq!(
+                http.urls(),
+                vec!["http://primary.com/user", "http://canary.com/user"]
+            );
+        }
+
+        #[tokio::test]
+        async fn test_mutation_isnt_shadowed() {
+            let http = TestHttp::body(|_| Ok(r#"{"id": 1, "name": "foo"}"#));
+            let response = TestApp::new(CONFIG, http.clone())
+                .execute("mutation { createUser { id } }")
+                .await;
+
+            assert_eq!(response["data"], json!({ "createUser": { "id": 1 } }));
+            tokio::task::yield_now().await;
+            assert_eq!(http.urls(), vec!["http://primary.com/user"]);
+        }
+    }
+
+    mod connection {
+        use std::sync::Arc;
+
+        use serde_json::json;
+
+        use crate::core::app_context::test::{TestApp, TestHttp};
+
+        const CONFIG: &str = r#"
+            schema @server @upstream {
+                query: Query
+            }
+
+            type Query {
+                users(first: Int, after: String): UserConnection
+                    @http(
+                        url: "http://jsonplaceholder.typicode.com/users"
+                        connection: {edgeCursor: ["cursor"]}
+                    )
+            }
+
+            type UserConnection {
+                edges: [UserEdge]
+                pageInfo: PageInfo
+            }
+
+            type UserEdge {
+                node: User
+                cursor: String
+            }
+
+            type PageInfo {
+                hasNextPage: Boolean!
+                hasPreviousPage: Boolean!
+                endCursor: String
+            }
+
+            type User {
+                id: Int
+            }
+        "#;
+
+        /// Pages the users two at a time by the id of the last one
+        fn pages() -> Arc<TestHttp> {
+            TestHttp::body(|request| {
+                let query = request
+                    .url()
+                    .query_pairs()
+                    .into_owned()
+                    .collect::<std::collections::HashMap<_, _>>();
+                let limit = query.get("limit").map_or(Ok(10), |limit| limit.parse())?;
+                let after = query.get("cursor").map_or(Ok(0), |cursor| cursor.parse())?;
+                let items = (after + 1..=3).take(limit).collect::<Vec<usize>>();
+                let last = items.last().copied();
+                let has_more = last.is_some_and(|last| last < 3);
+                let body = json!({
+                    "items": items
+                        .iter()
+                        .map(|id| json!({ "id": id, "cursor": id.to_string() }))
+                        .collect::<Vec<_>>(),
+                    "nextCursor": last.filter(|_| has_more).map(|last| last.to_string()),
+                    "hasMore": has_more,
+                });
+
+                Ok(body.to_string())
+            })
+        }
+
+        #[tokio::test]
+        async fn test_pages() {
+            let http = pages();
+            let app = TestApp::new(CONFIG, http.clone());
+            let query = r#"query {
+                users(first: 2) {
+                    edges { node { id } cursor }
+                    pageInfo { hasNextPage hasPreviousPage endCursor }
+                }
+            }"#;
+
+            let response = app.execute(query).await;
+            assert_eq!(
+                response["data"],
+                json!({
+                    "users": {
+                        "edges": [
+                            { "node": { "id": 1 }, "cursor": "1" },
+                            { "node": { "id": 2 }, "cursor": "2" },
+                        ],
+                        "pageInfo": {
+                            "hasNextPage": true,
+                            "hasPreviousPage": false,
+                            "endCursor": "2",
+                        },
+                    }
+                })
+            );
+
+            // the end cursor selects the next page, the last one
+            let query = r#"query {
+                users(first: 2, after: "2") {
+                    edges { node { id } }
+                    pageInfo { hasNextPage endCursor }
+                }
+            }"#;
+            let response = app.execute(query).await;
+            assert_eq!(
+                response["data"],
+                json!({
+                    "users": {
+                        "edges": [{ "node": { "id": 3 } }],
+                        "pageInfo": { "hasNextPage": false, "endCursor": null },
+                    }
+                })
+            );
+
+            assert_eq!(
+                http.urls(),
+                vec![
+                    "http://jsonplaceholder.typicode.com/users?limit=2",
+                    "http://jsonplaceholder.typicode.com/users?limit=2&cursor=2",
+                ]
+            );
+        }
+    }
+
+    mod filter {
+        use serde_json::json;
+
+        use super::*;
+        use crate::core::blueprint::{Blueprint, DynamicValue};
+        use crate::core::http::RequestContext;
+        use crate::core::ir::{EmptyResolverContext, Predicate};
+
+        async fn filter(value: serde_json::Value, predicate: &str) -> serde_json::Value {
+            let value = DynamicValue::Value(ConstValue::from_json(value).unwrap());
+            let ir = IR::Filter(Filter {
+                input: Box::new(IR::Dynamic(value)),
+                predicate: Predicate::parse(predicate).unwrap(),
+            });
+            let runtime = crate::cli::runtime::init(&Blueprint::default());
+            let req_ctx = RequestContext::new(runtime);
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+
+            ir.eval(&mut eval_ctx).await.unwrap().into_json().unwrap()
+        }
+
+        #[tokio::test]
+        async fn test_filter() {
+            let users = json!([
+                {"id": 1, "active": true},
+                {"id": 2, "active": false},
+                {"id": 3},
+                {"id": 4, "active": true}
+            ]);
+
+            let actual = filter(users, "active == true").await;
+
+            assert_eq!(
+                actual,
+                json!([{"id": 1, "active": true}, {"id": 4, "active": true}])
+            );
+        }
+
+        #[tokio::test]
+        async fn test_filter_absent_list() {
+            assert_eq!(filter(json!([]), "active").await, json!([]));
+            assert_eq!(filter(json!(null), "active").await, json!([]));
+        }
+    }
+
+    mod compact {
+        use serde_json::json;
+
+        use super::*;
+        use crate::core::blueprint::{Blueprint, DynamicValue};
+        use crate::core::http::RequestContext;
+        use crate::core::ir::model::Compact;
+        use crate::core::ir::EmptyResolverContext;
+
+        async fn compact(
+            value: serde_json::Value,
+            empty_objects: bool,
+            required: &[&str],
+        ) -> serde_json::Value {
+            let value = DynamicValue::Value(ConstValue::from_json(value).unwrap());
+            let ir = IR::Compact(Compact {
+                input: Box::new(IR::Dynamic(value)),
+                empty_objects,
+                required: required.iter().map(|key| key.to_string()).collect(),
+            });
+            let runtime = crate::cli::runtime::init(&Blueprint::default());
+            let req_ctx = RequestContext::new(runtime);
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+
+            ir.eval(&mut eval_ctx).await.unwrap().into_json().unwrap()
+        }
+
+        #[tokio::test]
+        async fn test_compact() {
+            let users = json!([
+                {"id": 1},
+                null,
+                {},
+                {"id": null, "name": "foo"},
+                {"name": "bar"},
+                {"id": 2, "name": "baz"},
+                null
+            ]);
+
+            let actual = compact(users.clone(), false, &[]).await;
+            assert_eq!(
+                actual,
+                json!([
+                    {"id": 1},
+                    {},
+                    {"id": null, "name": "foo"},
+                    {"name": "bar"},
+                    {"id": 2, "name": "baz"}
+                ])
+            );
+
+            let actual = compact(users.clone(), true, &[]).await;
+            assert_eq!(
+                actual,
+                json!([
+                    {"id": 1},
+                    {"id": null, "name": "foo"},
+                    {"name": "bar"},
+                    {"id": 2, "name": "baz"}
+                ])
+            );
+
+            // the remaining elements keep their order
+            let actual = compact(users, false, &["id"]).await;
+            assert_eq!(actual, json!([{"id": 1}, {"id": 2, "name": "baz"}]));
+        }
+
+        #[tokio::test]
+        async fn test_compact_scalars() {
+            let actual = compact(json!([1, null, "", 0, false]), true, &["id"]).await;
+            assert_eq!(actual, json!([1, "", 0, false]));
+
+            // an absent list stays absent
+            assert_eq!(compact(json!(null), true, &[]).await, json!(null));
+        }
+    }
+
+    mod map {
+        use serde_json::json;
+
+        use super::*;
+        use crate::core::blueprint::DynamicValue;
+        use crate::core::http::RequestContext;
+        use crate::core::ir::EmptyResolverContext;
+
+        async fn map(
+            value: serde_json::Value,
+            on_missing: MapMissing,
+        ) -> Result<serde_json::Value, Error> {
+            let value = DynamicValue::Value(ConstValue::from_json(value).unwrap());
+            let ir = IR::Map(Map {
+                input: Box::new(IR::Dynamic(value)),
+                map: HashMap::from([
+                    ("ACTIVE".to_string(), "ACTIVE".to_string()),
+                    ("enabled".to_string(), "ACTIVE".to_string()),
+                ]),
+                on_missing,
+            });
+            let req_ctx = RequestContext::default();
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+
+            Ok(ir.eval(&mut eval_ctx).await?.into_json().unwrap())
+        }
+
+        #[tokio::test]
+        async fn test_mapped_key() {
+            let actual = map(json!(["enabled", "ACTIVE", null]), MapMissing::Error).await;
+
+            assert_eq!(actual.unwrap(), json!(["ACTIVE", "ACTIVE", null]));
+        }
+
+        #[tokio::test]
+        async fn test_missing_key_null() {
+            let actual = map(json!(["enabled", "paused"]), MapMissing::Null).await;
+
+            assert_eq!(actual.unwrap(), json!(["ACTIVE", null]));
+        }
+
+        #[tokio::test]
+        async fn test_missing_key_passthrough() {
+            let actual = map(json!(["enabled", "paused"]), MapMissing::Passthrough).await;
+
+            assert_eq!(actual.unwrap(), json!(["ACTIVE", "paused"]));
+        }
+
+        #[tokio::test]
+        async fn test_missing_key_default() {
+            let on_missing = MapMissing::Default("UNKNOWN".to_string());
+            let actual = map(json!("paused"), on_missing).await;
+
+            assert_eq!(actual.unwrap(), json!("UNKNOWN"));
+        }
+
+        #[tokio::test]
+        async fn test_missing_key_error() {
+            let actual = map(json!(["enabled", "paused"]), MapMissing::Error).await;
+
+            assert!(matches!(
+                actual,
+                Err(Error::ExprEval(message)) if message == "Can't find mapped key: paused."
+            ));
+        }
+    }
+
+    mod join {
+        use serde_json::json;
+
+        use super::*;
+        use crate::core::blueprint::DynamicValue;
+        use crate::core::http::RequestContext;
+        use crate::core::ir::EmptyResolverContext;
+
+        async fn join(how: JoinKind) -> serde_json::Value {
+            let users = json!([
+                {"id": 1, "name": "foo"},
+                {"id": 2, "name": "bar"},
+                {"id": 3, "name": "baz"}
+            ]);
+            let profiles = json!([
+                {"userId": 1, "bio": "foo's bio"},
+                {"userId": 3, "bio": "baz's bio"}
+            ]);
+            let dynamic =
+                |value| IR::Dynamic(DynamicValue::Value(ConstValue::from_json(value).unwrap()));
+            let ir = IR::Join(Join {
+                left: Box::new(dynamic(users)),
+                right: Box::new(dynamic(profiles)),
+                on: (vec!["id".to_string()], vec!["userId".to_string()]),
+                how,
+            });
+            let req_ctx = RequestContext::default();
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+
+            ir.eval(&mut eval_ctx).await.unwrap().into_json().unwrap()
+        }
+
+        #[tokio::test]
+        async fn test_inner_join() {
+            assert_eq!(
+                join(JoinKind::Inner).await,
+                json!([
+                    {"id": 1, "name": "foo", "userId": 1, "bio": "foo's bio"},
+                    {"id": 3, "name": "baz", "userId": 3, "bio": "baz's bio"}
+                ])
+            );
+        }
+
+        #[tokio::test]
+        async fn test_left_join() {
+            assert_eq!(
+                join(JoinKind::Left).await,
+                json!([
+                    {"id": 1, "name": "foo", "userId": 1, "bio": "foo's bio"},
+                    {"id": 2, "name": "bar"},
+                    {"id": 3, "name": "baz", "userId": 3, "bio": "baz's bio"}
+                ])
+            );
+        }
+
+        #[tokio::test]
+        async fn test_join_mixed_scalar_keys() {
+            // the ids are numbers on the left and strings on the right
+            let dynamic =
+                |value| IR::Dynamic(DynamicValue::Value(ConstValue::from_json(value).unwrap()));
+            let ir = IR::Join(Join {
+                left: Box::new(dynamic(json!([{"id": 1}, {"id": 2}]))),
+                right: Box::new(dynamic(json!([{"userId": "1", "bio": "foo's bio"}]))),
+                on: (vec!["id".to_string()], vec!["userId".to_string()]),
+                how: JoinKind::Inner,
+            });
+            let req_ctx = RequestContext::default();
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+
+            assert_eq!(
+                ir.eval(&mut eval_ctx).await.unwrap().into_json().unwrap(),
+                json!([{"id": 1, "userId": "1", "bio": "foo's bio"}])
+            );
+        }
+
+        #[tokio::test]
+        async fn test_join_directive() {
+            use crate::core::app_context::test::{TestApp, TestHttp};
+
+            let sdl = r#"
+                schema @server @upstream(batch: {delay: 1}) {
+                    query: Query
+                }
+
+                type Query {
+                    users: [User]
+                        @http(url: "http://localhost:8080/users")
+                        @http(
+                            url: "http://localhost:8080/profiles"
+                            query: [{key: "userId", value: "{{.value.id}}"}]
+                            batchKey: ["userId"]
+                        )
+                        @join(left: ["id"], right: ["userId"], how: Left)
+                }
+
+                type User {
+                    id: Int
+                    name: String
+                    bio: String
+                }
+            "#;
+            let http = TestHttp::body(|request| {
+                Ok(match request.url().path() {
+                    "/users" => json!([
+                        {"id": 1, "name": "foo"},
+                        {"id": 2, "name": "bar"},
+                        {"id": 3, "name": "baz"}
+                    ]),
+                    _ => json!([
+                        {"userId": 1, "bio": "foo's bio"},
+                        {"userId": 3, "bio": "baz's bio"}
+                    ]),
+                }
+                .to_string())
+            });
+            let query = "query { users { id bio } }";
+
+            let response = TestApp::new(sdl, http.clone()).execute(query).await;
+            assert_eq!(
+                response,
+                json!({"data": {"users": [
+                    {"id": 1, "bio": "foo's bio"},
+                    {"id": 2, "bio": null},
+                    {"id": 3, "bio": "baz's bio"}
+                ]}})
+            );
+            // the profiles of all the users are fetched with a single request
+            assert_eq!(http.paths(), vec!["/users", "/profiles"]);
+
+            let inner = sdl.replace("how: Left", "how: Inner");
+            let response = TestApp::new(&inner, http).execute(query).await;
+            assert_eq!(
+                response,
+                json!({"data": {"users": [
+                    {"id": 1, "bio": "foo's bio"},
+                    {"id": 3, "bio": "baz's bio"}
+                ]}})
+            );
+        }
+    }
+
+    mod path {
+        use serde_json::json;
+
+        use super::*;
+        use crate::core::blueprint::DynamicValue;
+        use crate::core::http::RequestContext;
+        use crate::core::ir::EmptyResolverContext;
+        use crate::core::jit::graphql_error::ErrorExtensions;
+
+        async fn eval_path(
+            path: &[&str],
+            max_path_depth: Option<usize>,
+        ) -> Result<ConstValue, Error> {
+            let value = json!({"a": {"b": {"c": {"d": 1}}}});
+            let value = DynamicValue::Value(ConstValue::from_json(value).unwrap());
+            let ir = IR::Path(
+                Box::new(IR::Dynamic(value)),
+                path.iter().map(|key| key.to_string()).collect(),
+            );
+            let mut req_ctx = RequestContext::default();
+            req_ctx.server.max_path_depth = max_path_depth;
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+
+            ir.eval(&mut eval_ctx).await
+        }
+
+        #[tokio::test]
+        async fn test_path_within_depth() {
+            let path = ["a", "b", "c", "d"];
+
+            assert_eq!(eval_path(&path, None).await.unwrap(), ConstValue::from(1));
+            assert_eq!(
+                eval_path(&path, Some(4)).await.unwrap(),
+                ConstValue::from(1)
+            );
+        }
+
+        #[tokio::test]
+        async fn test_path_too_deep() {
+            let error = eval_path(&["a", "b", "c", "d"], Some(3)).await.unwrap_err();
+
+            assert!(matches!(error, Error::PathTooDeep(3)));
+            assert_eq!(
+                error.extend().extensions.unwrap().get("code"),
+                Some(&async_graphql::Value::from("PATH_TOO_DEEP"))
+            );
+        }
+    }
+
+    mod cache {
+        use std::num::NonZeroU64;
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        use http::header::{HeaderMap, HeaderValue};
+        use hyper::body::Bytes;
+        use reqwest::StatusCode;
+
+        use super::*;
+        use crate::core::app_context::test::TestHttp;
+        use crate::core::blueprint::Server;
+        use crate::core::config::GraphQLOperationType;
+        use crate::core::data_loader::DedupeResult;
+        use crate::core::http::{RequestContext, RequestTemplate, Response};
+        use crate::core::ir::model::{Adaptive, CacheOptions, IoId, Negative, Size, IO};
+        use crate::core::ir::{EmptyResolverContext, TestContext};
+        use crate::core::{graphql, Mustache};
+
+        const USER: &str = r#"{"id": 1, "name": "foo"}"#;
+
+        fn user_http() -> Arc<TestHttp> {
+            TestHttp::body(|_| Ok(USER))
+        }
+
+        /// Fails the requests once the upstream is down
+        fn flaky_http(down: Arc<AtomicBool>) -> Arc<TestHttp> {
+            TestHttp::body(move |_| {
+                if down.load(Ordering::SeqCst) {
+                    anyhow::bail!("Upstream is down");
+                }
+                Ok(USER)
+            })
+        }
+
+        fn flaky_cache(down: Arc<AtomicBool>) -> (IR, crate::core::runtime::TargetRuntime) {
+            let tags = vec![Mustache::parse("user:{{.value.id}}")];
+            let io = IR::IO(IO::Http {
+                req_template: RequestTemplate::new("http://localhost:8080/me").unwrap(),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: false,
+                hook: None,
+            });
+            let ir = Cache::wrap(
+                NonZeroU64::new(1).unwrap(),
+                CacheOptions::default()
+                    .tags(tags)
+                    .serve_stale_on_error(true),
+                io,
+            );
+            let mut runtime = crate::core::runtime::test::init(None);
+            runtime.http = flaky_http(down);
+
+            (ir, runtime)
+        }
+
+        #[tokio::test]
+        async fn test_serve_stale_on_error() {
+            let down = Arc::new(AtomicBool::new(false));
+            let (ir, runtime) = flaky_cache(down.clone());
+            let res_ctx = EmptyResolverContext {};
+
+            let req_ctx = RequestContext::new(runtime.clone());
+            let expected = ir
+                .eval(&mut EvalContext::new(&req_ctx, &res_ctx))
+                .await
+                .unwrap();
+
+            // the fresh value expires and the upstream fails to resolve it again
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            down.store(true, Ordering::SeqCst);
+
+            let req_ctx = RequestContext::new(runtime);
+            let actual = ir
+                .eval(&mut EvalContext::new(&req_ctx, &res_ctx))
+                .await
+                .unwrap();
+
+            assert_eq!(actual, expected);
+        }
+
+        #[tokio::test]
+        async fn test_invalidated_stale_value() {
+            let down = Arc::new(AtomicBool::new(false));
+            let (ir, runtime) = flaky_cache(down.clone());
+            let res_ctx = EmptyResolverContext {};
+
+            let req_ctx = RequestContext::new(runtime.clone());
+            ir.eval(&mut EvalContext::new(&req_ctx, &res_ctx))
+                .await
+                .unwrap();
+
+            // the stale copy is invalidated along with the entry
+            assert_eq!(req_ctx.cache_invalidate("user:1").await.unwrap(), 2);
+            down.store(true, Ordering::SeqCst);
+
+            let req_ctx = RequestContext::new(runtime);
+            let actual = ir.eval(&mut EvalContext::new(&req_ctx, &res_ctx)).await;
+
+            assert!(actual.is_err());
+        }
+
+        #[tokio::test]
+        async fn test_error_without_stale_value() {
+            let (ir, runtime) = flaky_cache(Arc::new(AtomicBool::new(true)));
+            let res_ctx = EmptyResolverContext {};
+
+            let req_ctx = RequestContext::new(runtime);
+            let actual = ir.eval(&mut EvalContext::new(&req_ctx, &res_ctx)).await;
+
+            assert!(actual.is_err());
+        }
+
+        /// Takes a while to respond
+        fn slow_http() -> Arc<TestHttp> {
+            TestHttp::delayed(Duration::from_millis(50), |_| {
+                Ok(Response {
+                    body: Bytes::from_static(USER.as_bytes()),
+                    ..Default::default()
+                })
+            })
+        }
+
+        #[tokio::test]
+        async fn test_coalesce_cache_misses() {
+            let io = IR::IO(IO::Http {
+                req_template: RequestTemplate::new("http://localhost:8080/me").unwrap(),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: false,
+                hook: None,
+            });
+            let ir = Cache::wrap(NonZeroU64::new(10000).unwrap(), CacheOptions::default(), io);
+            let http = slow_http();
+            let mut runtime = crate::core::runtime::test::init(None);
+            runtime.http = http.clone();
+            let refreshes = Arc::new(DedupeResult::new(false));
+
+            // the requests share the refreshes as they do within an app context
+            let values = join_all((0..50).map(|_| {
+                let req_ctx =
+                    RequestContext::new(runtime.clone()).cache_refreshes(refreshes.clone());
+                let ir = &ir;
+                async move {
+                    let res_ctx = EmptyResolverContext {};
+                    ir.eval(&mut EvalContext::new(&req_ctx, &res_ctx)).await
+                }
+            }))
+            .await;
+
+            assert_eq!(http.hits(), 1);
+            for value in values {
+                assert_eq!(
+                    value.unwrap().into_json().unwrap(),
+                    serde_json::json!({"id": 1, "name": "foo"})
+                );
+            }
+        }
+
+        #[tokio::test]
+        async fn test_dedupe_outlives_cancelled_caller() {
+            let ir = IR::IO(IO::Http {
+                req_template: RequestTemplate::new("http://localhost:8080/me").unwrap(),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: true,
+                hook: None,
+            });
+            let http = slow_http();
+            let mut runtime = crate::core::runtime::test::init(None);
+            runtime.http = http.clone();
+            let dedupe_handler = Arc::new(DedupeResult::new(false));
+            let res_ctx = TestContext::new(runtime.clone());
+
+            let first = RequestContext::new(runtime.clone()).dedupe_handler(dedupe_handler.clone());
+            let second = RequestContext::new(runtime).dedupe_handler(dedupe_handler);
+            let cancellation = first.cancellation.clone();
+
+            // the first caller is cancelled while the second one waits on its call
+            let mut first_ctx = EvalContext::new(&first, &res_ctx);
+            let (first, second, _) = tokio::join!(
+                ir.eval(&mut first_ctx),
+                async {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    ir.eval(&mut EvalContext::new(&second, &res_ctx)).await
+                },
+                async {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    cancellation.cancel();
+                }
+            );
+
+            assert!(matches!(first, Err(Error::Cancelled)));
+            assert_eq!(
+                second.unwrap().into_json().unwrap(),
+                serde_json::json!({"id": 1, "name": "foo"})
+            );
+            assert_eq!(http.hits(), 1);
+        }
+
+        #[tokio::test]
+        async fn test_invalidate_tag() {
+            let io = IR::IO(IO::Http {
+                req_template: RequestTemplate::new("http://localhost:8080/me").unwrap(),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: false,
+                hook: None,
+            });
+            let tags = vec![Mustache::parse("user:{{.value.id}}")];
+            let ir = Cache::wrap(
+                NonZeroU64::new(10000).unwrap(),
+                CacheOptions::default().tags(tags),
+                io,
+            );
+
+            let mut runtime = crate::core::runtime::test::init(None);
+            runtime.http = user_http();
+            let req_ctx = RequestContext::new(runtime);
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+
+            let expected = ir.eval(&mut eval_ctx).await.unwrap();
+
+            let IR::Cache(cache) = &ir else {
+                unreachable!()
+            };
+            let key = cache.cache_key(&eval_ctx).unwrap();
+            assert_eq!(req_ctx.cache_get(&key).await.unwrap(), Some(expected));
+
+            // the tag is rendered from the stored value
+            assert_eq!(
+                req_ctx
+                    .cache_invalidate("user:{{.value.id}}")
+                    .await
+                    .unwrap(),
+                0
+            );
+            assert_eq!(req_ctx.cache_invalidate("user:1").await.unwrap(), 1);
+            assert_eq!(req_ctx.cache_get(&key).await.unwrap(), None);
+        }
+
+        #[tokio::test]
+        async fn test_namespace_isolation() {
+            let io = IR::IO(IO::Http {
+                req_template: RequestTemplate::new("http://localhost:8080/me").unwrap(),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: false,
+                hook: None,
+            });
+            let namespace = Mustache::parse("{{.args.tenant}}");
+            let ir = Cache::wrap(
+                NonZeroU64::new(10000).unwrap(),
+                CacheOptions::default().namespace(namespace),
+                io.clone(),
+            );
+            let IR::Cache(cache) = &ir else {
+                unreachable!()
+            };
+            let shared = Cache::wrap(NonZeroU64::new(10000).unwrap(), CacheOptions::default(), io);
+            let IR::Cache(shared) = &shared else {
+                unreachable!()
+            };
+
+            let mut runtime = crate::core::runtime::test::init(None);
+            runtime.http = user_http();
+            let req_ctx = RequestContext::new(runtime);
+            let res_ctx = EmptyResolverContext {};
+            let ctx = EvalContext::new(&req_ctx, &res_ctx);
+            // the tenant isn't a part of the request to the upstream
+            let tenant = |name: &str| {
+                ctx.with_args(ConstValue::from_json(serde_json::json!({"tenant": name})).unwrap())
+            };
+            let (foo, bar) = (tenant("foo"), tenant("bar"));
+
+            // the same request collides without the namespace
+            assert_eq!(shared.cache_key(&foo), shared.cache_key(&bar));
+
+            let foo_key = cache.cache_key(&foo).unwrap();
+            let bar_key = cache.cache_key(&bar).unwrap();
+            assert_ne!(foo_key, bar_key);
+
+            let expected = ir.eval(&mut foo.clone()).await.unwrap();
+
+            assert_eq!(req_ctx.cache_get(&foo_key).await.unwrap(), Some(expected));
+            assert_eq!(req_ctx.cache_get(&bar_key).await.unwrap(), None);
+        }
+
+        #[test]
+        fn test_cache_salt() {
+            let io = IR::IO(IO::Http {
+                req_template: RequestTemplate::new("http://localhost:8080/me").unwrap(),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: false,
+                hook: None,
+            });
+            let ir = Cache::wrap(NonZeroU64::new(10000).unwrap(), CacheOptions::default(), io);
+            let IR::Cache(cache) = &ir else {
+                unreachable!()
+            };
+
+            let runtime = crate::core::runtime::test::init(None);
+            let deploy = |cache_salt: u64| {
+                let server = Server { cache_salt, ..Default::default() };
+                RequestContext::new(runtime.clone()).server(server)
+            };
+            let (v1, v1_again, v2) = (deploy(1), deploy(1), deploy(2));
+            let res_ctx = EmptyResolverContext {};
+            let key = |req_ctx: &RequestContext| {
+                cache
+                    .cache_key(&EvalContext::new(req_ctx, &res_ctx))
+                    .unwrap()
+            };
+
+            assert_eq!(key(&v1), key(&v1_again));
+            assert_ne!(key(&v1), key(&v2));
+        }
+
+        /// GraphQL upstream that greets in the language of the request
+        fn vary_http(vary: &'static str) -> Arc<TestHttp> {
+            TestHttp::new(move |request| {
+                let language = request
+                    .headers()
+                    .get("accept-language")
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default();
+                let mut headers = HeaderMap::new();
+                headers.insert("vary", HeaderValue::from_static(vary));
+
+                Ok(Response {
+                    status: StatusCode::OK,
+                    headers,
+                    body: Bytes::from(format!(r#"{{"data": {{"greeting": "{language}"}}}}"#)),
+                })
+            })
+        }
+
+        async fn eval_vary(vary: &'static str) -> (Vec<ConstValue>, usize) {
+            let req_template = graphql::RequestTemplate::new(
+                "http://localhost:8080/graphql".to_string(),
+                &GraphQLOperationType::Query,
+                "greeting",
+                None,
+                vec![],
+                Default::default(),
+            )
+            .unwrap();
+            let io = IR::IO(IO::GraphQL {
+                req_template,
+                field_name: "greeting".to_string(),
+                batch: false,
+                batch_style: Default::default(),
+                batch_name: None,
+                dl_id: None,
+                dedupe: false,
+                error_policy: Default::default(),
+            });
+            let ir = Cache::wrap(NonZeroU64::new(10000).unwrap(), CacheOptions::default(), io);
+
+            let http = vary_http(vary);
+            let mut runtime = crate::core::runtime::test::init(None);
+            runtime.http = http.clone();
+            let res_ctx = EmptyResolverContext {};
+            let mut values = vec![];
+            for language in ["en", "fr", "en", "fr"] {
+                let mut headers = HeaderMap::new();
+                headers.insert("accept-language", HeaderValue::from_static(language));
+                let req_ctx = RequestContext::new(runtime.clone()).allowed_headers(headers);
+                let value = ir.eval(&mut EvalContext::new(&req_ctx, &res_ctx)).await;
+                values.push(value.unwrap());
+            }
+
+            (values, http.hits())
+        }
+
+        #[tokio::test]
+        async fn test_vary_entries() {
+            let (values, calls) = eval_vary("Accept-Language").await;
+
+            // the requests that differ only in the language never share an entry
+            assert_eq!(values, ["en", "fr", "en", "fr"].map(ConstValue::from));
+            assert_eq!(calls, 2);
+        }
+
+        #[tokio::test]
+        async fn test_vary_any() {
+            let (values, calls) = eval_vary("*").await;
+
+            assert_eq!(values, ["en", "fr", "en", "fr"].map(ConstValue::from));
+            assert_eq!(calls, 4);
+        }
+
+        #[tokio::test]
+        async fn test_key_args() {
+            let url = "http://localhost:8080/search?q={{.args.query}}&id={{.args.requestId}}";
+            let io = IR::IO(IO::Http {
+                req_template: RequestTemplate::new(url).unwrap(),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: false,
+                hook: None,
+            });
+            let ir = Cache::wrap(
+                NonZeroU64::new(10000).unwrap(),
+                CacheOptions::default().key_args(vec!["query".to_string()]),
+                io,
+            );
+            let IR::Cache(cache) = &ir else {
+                unreachable!()
+            };
+
+            let mut runtime = crate::core::runtime::test::init(None);
+            runtime.http = user_http();
+            let req_ctx = RequestContext::new(runtime);
+            let res_ctx = EmptyResolverContext {};
+            let args = |query: &str, request_id: &str| {
+                ConstValue::from_json(serde_json::json!({"query": query, "requestId": request_id}))
+                    .unwrap()
+            };
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx).with_args(args("foo", "1"));
+            let expected = ir.eval(&mut eval_ctx).await.unwrap();
+
+            // the calls that differ only in the request id share the entry
+            let eval_ctx = EvalContext::new(&req_ctx, &res_ctx).with_args(args("foo", "2"));
+            let key = cache.cache_key(&eval_ctx).unwrap();
+            assert_eq!(req_ctx.cache_get(&key).await.unwrap(), Some(expected));
+
+            let eval_ctx = EvalContext::new(&req_ctx, &res_ctx).with_args(args("bar", "1"));
+            let key = cache.cache_key(&eval_ctx).unwrap();
+            assert_eq!(req_ctx.cache_get(&key).await.unwrap(), None);
+        }
+
+        /// Responds with the status code that is the last segment of the path,
+        /// the successful responses have a null body
+        fn negative_http() -> Arc<TestHttp> {
+            TestHttp::new(|request| {
+                let status = request
+                    .url()
+                    .path()
+                    .trim_start_matches('/')
+                    .parse::<u16>()?;
+                if status >= 400 {
+                    return Err(
+                        Error::HTTP { status, message: "Status code error".to_string() }.into(),
+                    );
+                }
+
+                Ok(Response {
+                    status: StatusCode::from_u16(status)?,
+                    headers: Default::default(),
+                    body: Bytes::from_static(b"null"),
+                })
+            })
+        }
+
+        async fn eval_negative(status: u16) -> (Result<ConstValue, Error>, RequestContext, IoId) {
+            let url = format!("http://localhost:8080/{}", status);
+            let io = IR::IO(IO::Http {
+                req_template: RequestTemplate::new(&url).unwrap(),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: false,
+                hook: None,
+            });
+            let negative = Negative {
+                max_age: NonZeroU64::new(1).unwrap(),
+                null: true,
+                empty: true,
+                status: vec![404..=404],
+            };
+            let ir = Cache::wrap(
+                NonZeroU64::new(10000).unwrap(),
+                CacheOptions::default().negative(negative),
+                io,
+            );
+            let IR::Cache(cache) = &ir else {
+                unreachable!()
+            };
+
+            let mut runtime = crate::core::runtime::test::init(None);
+            runtime.http = negative_http();
+            let req_ctx = RequestContext::new(runtime);
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+            let key = cache.cache_key(&eval_ctx).unwrap();
+            let value = ir.eval(&mut eval_ctx).await;
+
+            (value, req_ctx, key)
+        }
+
+        #[tokio::test]
+        async fn test_negative_max_age() {
+            let (value, req_ctx, key) = eval_negative(200).await;
+            assert_eq!(value.unwrap(), ConstValue::Null);
+            assert_eq!(
+                req_ctx.cache_get(&key).await.unwrap(),
+                Some(ConstValue::Null)
+            );
+
+            // the null value expires long before `maxAge`
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            assert_eq!(req_ctx.cache_get(&key).await.unwrap(), None);
+        }
+
+        #[tokio::test]
+        async fn test_negative_status() {
+            let (value, req_ctx, key) = eval_negative(404).await;
+            assert_eq!(value.unwrap(), ConstValue::Null);
+            assert_eq!(
+                req_ctx.cache_get(&key).await.unwrap(),
+                Some(ConstValue::Null)
+            );
+
+            let (value, req_ctx, key) = eval_negative(500).await;
+            assert!(matches!(value, Err(Error::HTTP { status: 500, .. })));
+            assert_eq!(req_ctx.cache_get(&key).await.unwrap(), None);
+        }
+
+        #[test]
+        fn test_size_max_age() {
+            let io = IR::IO(IO::Http {
+                req_template: RequestTemplate::new("http://localhost:8080/users").unwrap(),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: false,
+                hook: None,
+            });
+            let size = Size { threshold: 100, min_max_age: NonZeroU64::new(1000).unwrap() };
+            let ir = Cache::wrap(
+                NonZeroU64::new(10000).unwrap(),
+                CacheOptions::default().size(size),
+                io,
+            );
+            let IR::Cache(cache) = &ir else {
+                unreachable!()
+            };
+            let value = |len: usize| ConstValue::String("a".repeat(len));
+
+            // the size is the one of the serialized value, quotes included
+            assert_eq!(cache.entry_max_age(&value(98), false).get(), 10000);
+            assert_eq!(cache.entry_max_age(&value(198), false).get(), 5000);
+            assert_eq!(cache.entry_max_age(&value(10000), false).get(), 1000);
+            assert_eq!(cache.min_max_age().get(), 1000);
+        }
+
+        /// Responds with the current version of the value
+        fn versioned_http(version: Arc<AtomicUsize>) -> Arc<TestHttp> {
+            TestHttp::body(move |_| {
+                let version = version.load(Ordering::SeqCst);
+                Ok(format!(r#"{{"version": {}}}"#, version))
+            })
+        }
+
+        /// Refreshes the value as if it expired and returns the duration it's
+        /// stored for.
+        async fn refresh_max_age(ir: &IR, runtime: &crate::core::runtime::TargetRuntime) -> u64 {
+            let IR::Cache(cache) = ir else { unreachable!() };
+            let req_ctx = RequestContext::new(runtime.clone());
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+            let key = cache.cache_key(&eval_ctx).unwrap();
+            runtime.cache.evict(&key).await.unwrap();
+            ir.eval(&mut eval_ctx).await.unwrap();
+
+            let record = req_ctx
+                .cache_get(&Cache::adaptive_key(&key))
+                .await
+                .unwrap()
+                .unwrap();
+            record.into_json().unwrap()["maxAge"].as_u64().unwrap()
+        }
+
+        #[tokio::test]
+        async fn test_adaptive_max_age() {
+            let io = IR::IO(IO::Http {
+                req_template: RequestTemplate::new("http://localhost:8080/me").unwrap(),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: false,
+                hook: None,
+            });
+            let adaptive = Adaptive {
+                min_max_age: NonZeroU64::new(500).unwrap(),
+                max_max_age: NonZeroU64::new(4000).unwrap(),
+            };
+            let ir = Cache::wrap(
+                NonZeroU64::new(1000).unwrap(),
+                CacheOptions::default().adaptive(adaptive),
+                io,
+            );
+            let IR::Cache(cache) = &ir else {
+                unreachable!()
+            };
+            assert_eq!(cache.min_max_age().get(), 500);
+
+            let version = Arc::new(AtomicUsize::new(0));
+            let mut runtime = crate::core::runtime::test::init(None);
+            runtime.http = versioned_http(version.clone());
+            let refresh = || refresh_max_age(&ir, &runtime);
+
+            // the duration grows while the value stays the same, up to the bound
+            assert_eq!(refresh().await, 1000);
+            assert_eq!(refresh().await, 2000);
+            assert_eq!(refresh().await, 4000);
+            assert_eq!(refresh().await, 4000);
+
+            // and shrinks once it changes, down to the bound
+            version.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(refresh().await, 2000);
+            version.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(refresh().await, 1000);
+            version.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(refresh().await, 500);
+            version.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(refresh().await, 500);
+
+            // the first refresh of the same value starts growing it again
+            assert_eq!(refresh().await, 1000);
+        }
+    }
+
+    mod discriminate {
+        use std::collections::BTreeSet;
+        use std::sync::Arc;
+
+        use hyper::body::Bytes;
+        use reqwest::StatusCode;
+        use serde_json::json;
+        use tailcall_valid::Validator;
+
+        use super::*;
+        use crate::core::app_context::test::TestHttp;
+        use crate::core::http::{RequestContext, RequestTemplate, Response};
+        use crate::core::ir::model::IO;
+        use crate::core::ir::EmptyResolverContext;
+
+        /// Responds with the status code that is the last segment of the path
+        fn status_http() -> Arc<TestHttp> {
+            TestHttp::new(|request| {
+                let status = request
+                    .url()
+                    .path()
+                    .trim_start_matches('/')
+                    .parse::<u16>()?;
+                if status >= 400 {
+                    return Err(
+                        Error::HTTP { status, message: "Status code error".to_string() }.into(),
+                    );
+                }
+
+                Ok(Response {
+                    status: StatusCode::from_u16(status)?,
+                    headers: Default::default(),
+                    body: Bytes::from_static(br#"{"id": 1}"#),
+                })
+            })
+        }
+
+        async fn eval(status: u16) -> Result<ConstValue, Error> {
+            let types = BTreeSet::from([
+                "Found".to_string(),
+                "NotFound".to_string(),
+                "Failure".to_string(),
+            ]);
+            let statuses = vec![
+                ("2XX".to_string(), "Found".to_string()),
+                ("404".to_string(), "NotFound".to_string()),
+                ("500-599".to_string(), "Failure".to_string()),
+            ];
+            let discriminator = Discriminator::with_status("Result".to_string(), types, statuses)
+                .to_result()
+                .unwrap();
+            let url = format!("http://localhost:8080/{}", status);
+            let io = IR::IO(IO::Http {
+                req_template: RequestTemplate::new(&url).unwrap(),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: false,
+                hook: None,
+            });
+            let ir = IR::Discriminate(discriminator, Box::new(io));
+
+            let mut runtime = crate::core::runtime::test::init(None);
+            runtime.http = status_http();
+            let req_ctx = RequestContext::new(runtime);
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+
+            ir.eval(&mut eval_ctx).await
+        }
+
+        #[tokio::test]
+        async fn test_discriminate_status() {
+            let actual = eval(200).await.unwrap();
+            let expected = ConstValue::from_json(json!({"id": 1, "__typename": "Found"})).unwrap();
+            assert_eq!(actual, expected);
+
+            let actual = eval(404).await.unwrap();
+            let expected = ConstValue::from_json(json!({"__typename": "NotFound"})).unwrap();
+            assert_eq!(actual, expected);
+
+            let actual = eval(500).await.unwrap();
+            let expected = ConstValue::from_json(json!({"__typename": "Failure"})).unwrap();
+            assert_eq!(actual, expected);
+        }
+
+        #[tokio::test]
+        async fn test_discriminate_status_not_mapped() {
+            let actual = eval(401).await;
+            assert!(matches!(actual, Err(Error::HTTP { status: 401, .. })));
+        }
+    }
+
+    mod cancellation {
+        use std::time::Duration;
+
+        use super::*;
+        use crate::core::app_context::test::TestHttp;
+        use crate::core::http::{RequestContext, RequestTemplate, Response};
+        use crate::core::ir::model::IO;
+        use crate::core::ir::EmptyResolverContext;
+
+        #[tokio::test]
+        async fn test_cancel_slow_upstream() {
+            let ir = IR::IO(IO::Http {
+                req_template: RequestTemplate::new("http://localhost:8080/slow").unwrap(),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: false,
+                hook: None,
+            });
+
+            let mut runtime = crate::core::runtime::test::init(None);
+            runtime.http = TestHttp::delayed(Duration::from_secs(30), |_| Ok(Response::default()));
+            let req_ctx = RequestContext::new(runtime);
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+
+            // the guard is dropped along with the request, eg. on disconnect
+            let guard = req_ctx.cancellation.drop_guard();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                drop(guard);
+            });
+
+            let actual = tokio::time::timeout(Duration::from_secs(5), ir.eval(&mut eval_ctx))
+                .await
+                .expect("upstream call should be cancelled");
+
+            assert!(matches!(actual, Err(Error::Cancelled)));
+        }
+    }
+
+    mod runtime_env {
+        use std::borrow::Cow;
+        use std::collections::HashMap;
+        use std::sync::{Arc, Mutex};
+
+        use serde_json::json;
+
+        use super::*;
+        use crate::core::blueprint::DynamicValue;
+        use crate::core::http::RequestContext;
+        use crate::core::ir::EmptyResolverContext;
+        use crate::core::EnvIO;
+
+        /// Serves `get` from the snapshot taken on creation, like the native
+        /// env
+        #[derive(Default)]
+        struct TestEnv {
+            snapshot: HashMap<String, String>,
+            live: Mutex<HashMap<String, String>>,
+        }
+
+        impl EnvIO for TestEnv {
+            fn get(&self, key: &str) -> Option<Cow<'_, str>> {
+                self.snapshot.get(key).map(Cow::from)
+            }
+
+            fn get_live(&self, key: &str) -> Option<Cow<'_, str>> {
+                self.live.lock().unwrap().get(key).cloned().map(Cow::from)
+            }
+        }
+
+        #[tokio::test]
+        async fn test_runtime_env() {
+            let snapshot = HashMap::from([("FEATURE_X".to_string(), "off".to_string())]);
+            let env = Arc::new(TestEnv { live: Mutex::new(snapshot.clone()), snapshot });
+
+            let value = DynamicValue::try_from(&json!("{{.env.FEATURE_X}}")).unwrap();
+            let startup = IR::Dynamic(value.clone());
+            let live = value.with_runtime_env();
+            // env dependent values are never constant, so the responses that
+            // include them are not cached
+            assert!(!live.is_const());
+            let live = IR::Dynamic(live);
+
+            let mut runtime = crate::core::runtime::test::init(None);
+            runtime.env = env.clone();
+            let req_ctx = RequestContext::new(runtime);
+            let res_ctx = EmptyResolverContext {};
+
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+            assert_eq!(
+                live.eval(&mut eval_ctx).await.unwrap(),
+                ConstValue::from("off")
+            );
+
+            env.live
+                .lock()
+                .unwrap()
+                .insert("FEATURE_X".to_string(), "on".to_string());
+
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+            assert_eq!(
+                live.eval(&mut eval_ctx).await.unwrap(),
+                ConstValue::from("on")
+            );
+            assert_eq!(
+                startup.eval(&mut eval_ctx).await.unwrap(),
+                ConstValue::from("off")
+            );
+        }
+    }
+
+    mod mask {
+        use jsonwebtoken::jwk::JwkSet;
+        use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+        use serde_json::json;
+
+        use super::*;
+        use crate::core::auth::jwt::jwt_verify::tests::create_jwt_auth_request;
+        use crate::core::blueprint::{Auth, DynamicValue, Jwt, Provider};
+        use crate::core::http::RequestContext;
+        use crate::core::ir::model::Mask;
+        use crate::core::ir::EmptyResolverContext;
+
+        fn token(scope: &str) -> String {
+            let header = Header {
+                kid: Some("test".to_string()),
+                ..Header::new(Algorithm::HS256)
+            };
+            let claims = json!({ "exp": 2019056441, "scope": scope });
+
+            encode(&header, &claims, &EncodingKey::from_secret(b"secret")).unwrap()
+        }
+
+        fn ir() -> IR {
+            // the key is the base64 encoded `secret`
+            let jwks: JwkSet = serde_json::from_value(json!({
+                "keys": [{ "kty": "oct", "kid": "test", "alg": "HS256", "k": "c2VjcmV0" }]
+            }))
+            .unwrap();
+            let auth = Auth::Provider(Provider::Jwt(Jwt {
+                issuer: None,
+                audiences: Default::default(),
+                optional_kid: false,
+                jwks,
+            }));
+            let users = json!([
+                { "name": "foo", "ssn": "123-45-6789" },
+                { "name": "bar", "ssn": "987-65-4321" }
+            ]);
+
+            IR::Mask(Mask {
+                input: Box::new(IR::Dynamic(DynamicValue::try_from(&users).unwrap())),
+                auth,
+                scope: "admin".to_string(),
+                fields: vec!["ssn".to_string()],
+            })
+        }
+
+        async fn eval(req_ctx: &RequestContext) -> serde_json::Value {
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(req_ctx, &res_ctx);
+
+            ir().eval(&mut eval_ctx).await.unwrap().into_json().unwrap()
+        }
+
+        #[tokio::test]
+        async fn test_mask_applied() {
+            let masked = json!([
+                { "name": "foo", "ssn": null },
+                { "name": "bar", "ssn": null }
+            ]);
+
+            assert_eq!(eval(&RequestContext::default()).await, masked);
+            assert_eq!(
+                eval(&create_jwt_auth_request(&token("read write"))).await,
+                masked
+            );
+        }
+
+        #[tokio::test]
+        async fn test_mask_not_applied() {
+            let actual = eval(&create_jwt_auth_request(&token("read admin"))).await;
+            let expected = json!([
+                { "name": "foo", "ssn": "123-45-6789" },
+                { "name": "bar", "ssn": "987-65-4321" }
+            ]);
+
+            assert_eq!(actual, expected);
+        }
+    }
+
+    mod compress {
+        use std::io::Read;
+
+        use flate2::read::GzDecoder;
+
+        use super::*;
+        use crate::core::blueprint::DynamicValue;
+        use crate::core::http::RequestContext;
+        use crate::core::ir::EmptyResolverContext;
+
+        async fn eval(value: &str, compressed_fields: bool) -> ConstValue {
+            let ir = IR::Compress(Compress {
+                input: Box::new(IR::Dynamic(DynamicValue::Value(ConstValue::String(
+                    value.to_string(),
+                )))),
+                min_size: 16,
+            });
+            let req_ctx = RequestContext::default().compressed_fields(compressed_fields);
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+
+            ir.eval(&mut eval_ctx).await.unwrap()
+        }
+
+        fn decompress(value: &str) -> String {
+            let bytes = BASE64_STANDARD
+                .decode(value.strip_prefix("gzip:").unwrap())
+                .unwrap();
+            let mut decoded = String::new();
+            GzDecoder::new(bytes.as_slice())
+                .read_to_string(&mut decoded)
+                .unwrap();
+            decoded
+        }
+
+        #[tokio::test]
+        async fn test_compressed() {
+            let value = "lorem ipsum ".repeat(100);
+
+            let ConstValue::String(actual) = eval(&value, true).await else {
+                panic!("expected a string");
+            };
+            assert!(actual.len() < value.len());
+            assert_eq!(decompress(&actual), value);
+        }
+
+        #[tokio::test]
+        async fn test_raw() {
+            let value = "lorem ipsum ".repeat(100);
+
+            // the client didn't opt in
+            let actual = eval(&value, false).await;
+            assert_eq!(actual, ConstValue::String(value));
+
+            // the value is below the threshold
+            let actual = eval("lorem", true).await;
+            assert_eq!(actual, ConstValue::String("lorem".to_string()));
+        }
+    }
+
+    mod budget {
+        use crate::core::app_context::test::{TestApp, TestHttp};
+
+        const CONFIG: &str = r#"
+            schema @server(maxUpstreamCalls: 3) @upstream {
+                query: Query
+            }
+
+            type Query {
+                users: [User] @http(url: "http://localhost:8080/users")
+            }
+
+            type User {
+                id: Int!
+                posts: [Post] @http(url: "http://localhost:8080/users/{{.value.id}}/posts")
+            }
+
+            type Post {
+                id: Int
+            }
+        "#;
+
+        fn error_codes(response: &serde_json::Value) -> Vec<&str> {
+            response["errors"]
+                .as_array()
+                .map(|errors| {
+                    errors
+                        .iter()
+                        .map(|error| error["extensions"]["code"].as_str().unwrap())
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        fn users_http() -> std::sync::Arc<TestHttp> {
+            TestHttp::body(|request| {
+                Ok(match request.url().path() {
+                    "/users" => r#"[{"id": 1}, {"id": 2}, {"id": 3}, {"id": 4}, {"id": 5}]"#,
+                    "/posts" => r#"[{"id": 1, "userId": 1}, {"id": 2, "userId": 2}]"#,
+                    _ => r#"[{"id": 1}]"#,
+                })
+            })
+        }
+
+        #[tokio::test]
+        async fn test_fan_out_stops_after_budget() {
+            let http = users_http();
+            let response = TestApp::new(CONFIG, http.clone())
+                .execute("query { users { id posts { id } } }")
+                .await;
+
+            // the response fails with the first of the calls out of the budget
+            assert_eq!(http.hits(), 3);
+            assert_eq!(error_codes(&response), vec!["BUDGET_EXCEEDED"]);
+            assert_eq!(
+                response["errors"][0]["path"],
+                serde_json::json!(["users", 2, "posts"])
+            );
+        }
+
+        #[tokio::test]
+        async fn test_batch_is_charged_once() {
+            let sdl = r#"
+                schema @server(maxUpstreamCalls: 2) @upstream(batch: {delay: 1}) {
+                    query: Query
+                }
+
+                type Query {
+                    users: [User] @http(url: "http://localhost:8080/users")
+                }
+
+                type User {
+                    id: Int
+                    posts: [Post]
+                        @http(
+                            url: "http://localhost:8080/posts"
+                            query: [{key: "userId", value: "{{.value.id}}"}]
+                            batchKey: ["userId"]
+                        )
+                }
+
+                type Post {
+                    id: Int
+                    userId: Int
+                }
+            "#;
+            let http = users_http();
+            let app = TestApp::new(sdl, http.clone());
+
+            // the posts of all the users are fetched with a single request
+            let response = app.execute("query { users { id posts { id } } }").await;
+            assert!(response.get("errors").is_none(), "{response}");
+            assert_eq!(http.hits(), 2);
+
+            // the budget is charged for the batch of each request
+            let budget = sdl.replace("maxUpstreamCalls: 2", "maxUpstreamCalls: 1");
+            let response = TestApp::new(&budget, http.clone())
+                .execute("query { users { id posts { id } } }")
+                .await;
+            assert_eq!(error_codes(&response), vec!["BUDGET_EXCEEDED"]);
+            assert_eq!(http.hits(), 3);
+        }
+    }
+
+    mod skip_if {
+        use serde_json::json;
+
+        use crate::core::app_context::test::{TestApp, TestHttp};
+
+        const CONFIG: &str = r#"
+            schema @server @upstream(batch: {delay: 1}) {
+                query: Query
+            }
+
+            type Query {
+                users: [User] @http(url: "http://localhost:8080/users")
+            }
+
+            type User {
+                id: Int
+                managerId: Int
+                manager: User
+                    @http(
+                        url: "http://localhost:8080/managers"
+                        query: [{key: "id", value: "{{.value.managerId}}"}]
+                        batchKey: ["id"]
+                        skipIf: "value.managerId == null"
+                    )
+            }
+        "#;
+
+        #[tokio::test]
+        async fn test_skip_and_execute() {
+            let http = TestHttp::body(|request| {
+                Ok(match request.url().path() {
+                    "/users" => {
+                        r#"[{"id": 1, "managerId": 3}, {"id": 2}, {"id": 3, "managerId": null}]"#
+                    }
+                    _ => r#"[{"id": 3}]"#,
+                })
+            });
+            let response = TestApp::new(CONFIG, http.clone())
+                .execute("query { users { id manager { id } } }")
+                .await;
+
+            assert_eq!(
+                response["data"],
+                json!({
+                    "users": [
+                        { "id": 1, "manager": { "id": 3 } },
+                        { "id": 2, "manager": null },
+                        { "id": 3, "manager": null },
+                    ]
+                })
+            );
+            // the skipped users don't add an `id` to the batch
+            assert_eq!(
+                http.urls(),
+                vec![
+                    "http://localhost:8080/users",
+                    "http://localhost:8080/managers?id=3",
+                ]
+            );
+        }
+    }
+
+    mod raw_body {
+        use std::sync::Arc;
+
+        use http::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+        use hyper::body::Bytes;
+
+        use super::*;
+        use crate::core::app_context::test::{TestApp, TestHttp};
+        use crate::core::http::{RequestContext, RequestTemplate, Response};
+        use crate::core::ir::model::IO;
+        use crate::core::ir::EmptyResolverContext;
+
+        /// Responds with a thumbnail
+        fn thumbnail_http() -> Arc<TestHttp> {
+            TestHttp::new(|_| {
+                let mut headers = HeaderMap::new();
+                headers.insert(CONTENT_TYPE, HeaderValue::from_static("image/png"));
+
+                Ok(Response {
+                    headers,
+                    body: Bytes::from_static(&[0x89, b'P', b'N', b'G']),
+                    ..Default::default()
+                })
+            })
+        }
+
+        #[tokio::test]
+        async fn test_raw_body() {
+            let ir = IR::IO(IO::Http {
+                req_template: RequestTemplate::new("http://localhost:8080/thumbnail")
+                    .unwrap()
+                    .raw_body(true),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: false,
+                hook: None,
+            });
+
+            let mut runtime = crate::core::runtime::test::init(None);
+            runtime.http = thumbnail_http();
+            let req_ctx = RequestContext::new(runtime);
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+
+            let actual = ir.eval(&mut eval_ctx).await.unwrap();
+            assert_eq!(actual, ConstValue::String("iVBORw==".to_string()));
+        }
+
+        #[tokio::test]
+        async fn test_raw_body_max_size() {
+            let ir = IR::IO(IO::Http {
+                req_template: RequestTemplate::new("http://localhost:8080/thumbnail")
+                    .unwrap()
+                    .raw_body(true)
+                    .max_size(3),
+                group_by: None,
+                dl_id: None,
+                is_list: false,
+                dedupe: false,
+                hook: None,
+            });
+
+            let mut runtime = crate::core::runtime::test::init(None);
+            runtime.http = thumbnail_http();
+            let req_ctx = RequestContext::new(runtime);
+            let res_ctx = EmptyResolverContext {};
+            let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+
+            assert!(ir.eval(&mut eval_ctx).await.is_err());
+        }
+
+        #[tokio::test]
+        async fn test_raw_body_batching_enabled() {
+            let app = TestApp::new(
+                r#"
+                schema @upstream(batch: {delay: 1}) {
+                    query: Query
+                }
+
+                type Query {
+                    thumbnail: Bytes @http(url: "http://localhost:8080/thumbnail", rawBody: true)
+                }
+                "#,
+                thumbnail_http(),
+            );
+
+            let actual = app.execute("query { thumbnail }").await;
+            assert_eq!(
+                actual,
+                serde_json::json!({"data": {"thumbnail": "iVBORw=="}})
+            );
+        }
+    }
+
+    mod debug_stats {
+        use serde_json::json;
+
+        use crate::core::app_context::test::{TestApp, TestHttp};
+
+        const CONFIG: &str = r#"
+            schema @server(debugStats: true) @upstream(batch: {delay: 1}) {
+                query: Query
+            }
+
+            type Query {
+                users: [User] @http(url: "http://localhost:8080/users") @cache(maxAge: 60000)
+            }
+
+            type User {
+                id: Int
+                posts: [Post]
+                    @http(
+                        url: "http://localhost:8080/posts"
+                        query: [{key: "userId", value: "{{.value.id}}"}]
+                        batchKey: ["userId"]
+                    )
+            }
+
+            type Post {
+                id: Int
+                userId: Int
+            }
+        "#;
+
+        const QUERY: &str = "query { users { id posts { id } } }";
+
+        fn app(sdl: &str) -> TestApp {
+            let http = TestHttp::body(|request| {
+                Ok(match request.url().path() {
+                    "/users" => r#"[{"id": 1}, {"id": 2}]"#,
+                    _ => r#"[{"id": 1, "userId": 1}, {"id": 2, "userId": 2}]"#,
+                })
+            });
+
+            TestApp::new(sdl, http)
+        }
+
+        #[tokio::test]
+        async fn test_debug_stats() {
+            let app = app(CONFIG);
+
+            let response = app.execute(QUERY).await;
+            let fields = &response["extensions"]["debug"]["fields"];
+            assert_eq!(
+                fields["users"],
+                json!({ "cacheHit": false, "deduped": false, "upstreamCalls": 1 })
+            );
+            assert_eq!(
+                fields["users.posts"],
+                json!({ "cacheHit": false, "deduped": false, "upstreamCalls": 2, "batchSize": 2 })
+            );
+
+            // the users are read from the cache by the next request
+            let response = app.execute(QUERY).await;
+            let fields = &response["extensions"]["debug"]["fields"];
+            assert_eq!(
+                fields["users"],
+                json!({ "cacheHit": true, "deduped": false, "upstreamCalls": 0 })
+            );
+        }
+
+        #[tokio::test]
+        async fn test_disabled_by_default() {
+            let app = app(&CONFIG.replace("@server(debugStats: true)", "@server"));
+
+            let response = app.execute(QUERY).await;
+            assert!(response.get("extensions").is_none());
+            assert_eq!(response["data"]["users"][1]["posts"], json!([{ "id": 2 }]));
+        }
     }
 }
@@ -2,12 +2,15 @@ use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::sync::Arc;
 
-use async_graphql::{ServerError, Value};
+use async_graphql::parser::types::OperationType;
+use async_graphql::{PathSegment, ServerError, Value};
 use http::header::HeaderMap;
 
-use super::{GraphQLOperationContext, RelatedFields, ResolverContextLike, SelectionField};
+use super::{
+    DetachedContext, GraphQLOperationContext, RelatedFields, ResolverContextLike, SelectionField,
+};
 use crate::core::document::print_directives;
-use crate::core::http::RequestContext;
+use crate::core::http::{FieldStats, RequestContext, Vary};
 
 // TODO: rename to ResolverContext
 #[derive(Clone)]
@@ -24,6 +27,12 @@ pub struct EvalContext<'a, Ctx: ResolverContextLike> {
 
     // Overridden Arguments for Async GraphQL Context
     graphql_ctx_args: Option<Arc<Value>>,
+
+    // Status code of the last upstream HTTP response received in this context
+    upstream_status: Option<u16>,
+
+    // Headers the last upstream response received in this context varies by
+    upstream_vary: Option<Vary>,
 }
 
 impl<'a, Ctx: ResolverContextLike> EvalContext<'a, Ctx> {
@@ -43,15 +52,59 @@ impl<'a, Ctx: ResolverContextLike> EvalContext<'a, Ctx> {
         self.graphql_ctx.is_query()
     }
 
+    /// Name of the operation, `None` when it's anonymous.
+    pub fn operation_name(&self) -> Option<&'a str> {
+        self.graphql_ctx.operation_name()
+    }
+
+    pub fn operation_type(&self) -> Option<OperationType> {
+        self.graphql_ctx.operation_type()
+    }
+
     pub fn new(req_ctx: &'a RequestContext, graphql_ctx: &'a Ctx) -> EvalContext<'a, Ctx> {
         Self {
             request_ctx: req_ctx,
             graphql_ctx,
             graphql_ctx_value: None,
             graphql_ctx_args: None,
+            upstream_status: None,
+            upstream_vary: None,
+        }
+    }
+
+    /// Copies the context of the field into an owned one, see
+    /// [DetachedContext].
+    pub fn detach(&self) -> DetachedContext {
+        DetachedContext {
+            value: self.graphql_ctx.value().cloned(),
+            args: self.graphql_ctx.args().cloned(),
+            field: self.graphql_ctx.field(),
+            is_query: self.graphql_ctx.is_query(),
+            operation_name: self.graphql_ctx.operation_name().map(str::to_string),
+            operation_type: self.graphql_ctx.operation_type(),
+            response_path: self.graphql_ctx.response_path(),
+            errors: Default::default(),
+            value_override: self.graphql_ctx_value.clone(),
+            args_override: self.graphql_ctx_args.clone(),
         }
     }
 
+    pub fn upstream_status(&self) -> Option<u16> {
+        self.upstream_status
+    }
+
+    pub fn set_upstream_status(&mut self, status: u16) {
+        self.upstream_status = Some(status);
+    }
+
+    pub fn upstream_vary(&self) -> Option<&Vary> {
+        self.upstream_vary.as_ref()
+    }
+
+    pub fn set_upstream_vary(&mut self, vary: Option<Vary>) {
+        self.upstream_vary = vary;
+    }
+
     pub fn value(&self) -> Option<&Value> {
         self.graphql_ctx.value()
     }
@@ -89,10 +142,18 @@ impl<'a, Ctx: ResolverContextLike> EvalContext<'a, Ctx> {
         value.to_str().ok()
     }
 
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_ctx.request_id.as_ref()?.to_str().ok()
+    }
+
     pub fn env_var(&self, key: &str) -> Option<Cow<'_, str>> {
         self.request_ctx.runtime.env.get(key)
     }
 
+    pub fn runtime_env_var(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.request_ctx.runtime.env.get_live(key)
+    }
+
     pub fn var(&self, key: &str) -> Option<&str> {
         let vars = &self.request_ctx.server.vars;
 
@@ -106,6 +167,46 @@ impl<'a, Ctx: ResolverContextLike> EvalContext<'a, Ctx> {
     pub fn add_error(&self, error: ServerError) {
         self.graphql_ctx.add_error(error)
     }
+
+    pub fn response_path(&self) -> Vec<PathSegment> {
+        self.graphql_ctx.response_path()
+    }
+
+    pub fn field(&self) -> Option<SelectionField> {
+        self.graphql_ctx.field()
+    }
+
+    /// Records how the field was resolved with `@server(debugStats)`, it's a
+    /// no-op otherwise.
+    pub fn record_stats(&self, record: impl FnOnce(&FieldStats, String)) {
+        if let Some(field_stats) = &self.request_ctx.field_stats {
+            let path = self
+                .response_path()
+                .into_iter()
+                .filter_map(|segment| match segment {
+                    PathSegment::Field(name) => Some(name),
+                    PathSegment::Index(_) => None,
+                })
+                .collect::<Vec<_>>()
+                .join(".");
+            record(field_stats, path)
+        }
+    }
+}
+
+impl<'a> EvalContext<'a, DetachedContext> {
+    /// Evaluation context of a field that's detached from its caller, see
+    /// [EvalContext::detach].
+    pub fn attach(req_ctx: &'a RequestContext, graphql_ctx: &'a DetachedContext) -> Self {
+        Self {
+            request_ctx: req_ctx,
+            graphql_ctx,
+            graphql_ctx_value: graphql_ctx.value_override.clone(),
+            graphql_ctx_args: graphql_ctx.args_override.clone(),
+            upstream_status: None,
+            upstream_vary: None,
+        }
+    }
 }
 
 impl<Ctx: ResolverContextLike> GraphQLOperationContext for EvalContext<'_, Ctx> {
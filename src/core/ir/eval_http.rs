@@ -1,18 +1,21 @@
+use std::future::Future;
 use std::sync::Arc;
 
-use async_graphql::from_value;
-use reqwest::Request;
+use async_graphql::{from_value, PathSegment, ServerError};
+use reqwest::{Request, StatusCode};
 use tailcall_valid::Validator;
 
 use super::model::DataLoaderId;
 use super::request::DynamicRequest;
 use super::{EvalContext, ResolverContextLike};
+use crate::core::config::ErrorPolicy;
 use crate::core::data_loader::{DataLoader, Loader};
 use crate::core::grpc::protobuf::ProtobufOperation;
 use crate::core::grpc::request::execute_grpc_request;
 use crate::core::grpc::request_template::RenderedRequestTemplate;
 use crate::core::http::{
-    cache_policy, DataLoaderRequest, HttpDataLoader, RequestTemplate, Response,
+    cache_policy, decode_keys, transform_body, DataLoaderRequest, HttpDataLoader, RequestTemplate,
+    Response,
 };
 use crate::core::ir::Error;
 use crate::core::json::JsonLike;
@@ -55,7 +58,15 @@ impl<'a, 'ctx, Context: ResolverContextLike + Sync> EvalHttp<'a, 'ctx, Context>
         request_template: &'a RequestTemplate,
         id: &Option<DataLoaderId>,
     ) -> Self {
-        let data_loader = if evaluation_ctx.request_ctx.is_batching_enabled() {
+        // The upload stream can't be cloned into a batch, so streamed requests
+        // bypass the data loader. So do the requests whose redirects are followed
+        // with a custom policy, and the ones whose raw body the data loader would
+        // decode as JSON.
+        let data_loader = if evaluation_ctx.request_ctx.is_batching_enabled()
+            && !request_template.stream_upload
+            && request_template.redirect.is_none()
+            && !request_template.raw_body
+        {
             id.and_then(|id| {
                 evaluation_ctx
                     .request_ctx
@@ -70,7 +81,19 @@ impl<'a, 'ctx, Context: ResolverContextLike + Sync> EvalHttp<'a, 'ctx, Context>
     }
 
     pub fn init_request(&self) -> Result<DynamicRequest<String>, Error> {
-        let inner = self.request_template.to_request(self.evaluation_ctx)?;
+        let mut inner = self.request_template.to_request(self.evaluation_ctx)?;
+        if self.request_template.stream_upload {
+            let body = self.evaluation_ctx.request_ctx.upload.take_body();
+            let body = body.ok_or_else(|| {
+                Error::IO("The request body is not available for streaming, it's either not provided or already consumed by another call".to_string())
+            })?;
+            inner.request_mut().body_mut().replace(body);
+        } else if self.data_loader.is_none() {
+            // the batched requests are transformed by the data loader, once
+            // their bodies are merged
+            transform_body(inner.request_mut(), self.evaluation_ctx.request_ctx)?;
+        }
+
         Ok(inner)
     }
 
@@ -79,25 +102,104 @@ impl<'a, 'ctx, Context: ResolverContextLike + Sync> EvalHttp<'a, 'ctx, Context>
         req: DynamicRequest<String>,
     ) -> Result<Response<async_graphql::Value>, Error> {
         let ctx = &self.evaluation_ctx;
-        let dl = &self.data_loader;
-        let response = if dl.is_some() {
-            execute_request_with_dl(ctx, req, self.data_loader).await?
-        } else {
-            execute_raw_request(ctx, req).await?
+        let response = match self.send(req).await {
+            // the statuses configured as successful resolve to null, the
+            // non-nullable fields fail when the value is completed
+            Err(Error::HTTP { status, .. })
+                if self
+                    .request_template
+                    .success_statuses
+                    .iter()
+                    .any(|range| range.contains(&status)) =>
+            {
+                return Ok(Response {
+                    status: StatusCode::from_u16(status).map_err(|e| Error::IO(e.to_string()))?,
+                    headers: Default::default(),
+                    body: async_graphql::Value::Null,
+                });
+            }
+            response => response?,
         };
+        let response = self.reshape(response)?;
+        set_headers(ctx, &response);
 
-        if ctx.request_ctx.server.get_enable_http_validation() {
-            self.request_template
-                .endpoint
-                .output
-                .validate(&response.body)
-                .to_result()
-                .map_err(Error::from)?;
+        Ok(response)
+    }
+
+    /// Sends the request shadowed to the canary upstream and shapes its
+    /// response like the primary one, without reporting it to the context. The
+    /// call doesn't borrow the context, so that it could outlive the request.
+    pub fn execute_canary(
+        &self,
+        req: reqwest::Request,
+    ) -> impl Future<Output = Result<async_graphql::Value, Error>> + Send + 'static {
+        let http = self.evaluation_ctx.request_ctx.runtime.http.clone();
+        let request_template = self.request_template.clone();
+        let http_validation = self
+            .evaluation_ctx
+            .request_ctx
+            .server
+            .get_enable_http_validation();
+
+        async move {
+            let response = http.execute(req).await.map_err(Error::from)?;
+            let response = if request_template.raw_body {
+                response.to_base64(request_template.max_size)?
+            } else {
+                response.to_json()?
+            };
+
+            Ok(reshape(&request_template, http_validation, response)?.body)
         }
+    }
 
-        set_headers(ctx, &response);
+    fn reshape(
+        &self,
+        response: Response<async_graphql::Value>,
+    ) -> Result<Response<async_graphql::Value>, Error> {
+        let http_validation = self
+            .evaluation_ctx
+            .request_ctx
+            .server
+            .get_enable_http_validation();
 
-        Ok(response)
+        reshape(self.request_template, http_validation, response)
+    }
+
+    async fn send(
+        &self,
+        req: DynamicRequest<String>,
+    ) -> Result<Response<async_graphql::Value>, Error> {
+        let ctx = &self.evaluation_ctx;
+        if self.data_loader.is_some() {
+            return execute_request_with_dl(ctx, req, self.data_loader).await;
+        }
+
+        // the batched requests are charged by the data loader
+        ctx.request_ctx.charge_upstream_call()?;
+        if let Some(redirect) = &self.request_template.redirect {
+            let http = &ctx.request_ctx.runtime.http;
+            let response = redirect
+                .execute(http, req.into_request())
+                .await
+                .map_err(Error::from)?;
+            if self.request_template.raw_body {
+                Ok(response.to_base64(self.request_template.max_size)?)
+            } else {
+                Ok(response.to_json()?)
+            }
+        } else if self.request_template.raw_body {
+            Ok(ctx
+                .request_ctx
+                .runtime
+                .http
+                .execute(req.into_request())
+                .await
+                .map_err(Error::from)?
+                .to_base64(self.request_template.max_size)?)
+        } else {
+            execute_raw_request(ctx, req).await
+        }
     }
 
     #[async_recursion::async_recursion]
@@ -144,6 +246,42 @@ impl<'a, 'ctx, Context: ResolverContextLike + Sync> EvalHttp<'a, 'ctx, Context>
     }
 }
 
+/// Shapes the response of the upstream into the output of the field.
+fn reshape(
+    request_template: &RequestTemplate,
+    http_validation: bool,
+    mut response: Response<async_graphql::Value>,
+) -> Result<Response<async_graphql::Value>, Error> {
+    if let Some(is_list) = request_template.coerce_list {
+        response.body = coerce_list(std::mem::take(&mut response.body), is_list);
+    }
+
+    // the schema of the API is validated before the keys are renamed, the
+    // output of the field after
+    if let Some(response_schema) = &request_template.response_schema {
+        response_schema.validate(&request_template.endpoint.path, &response.body)?;
+    }
+
+    if let Some(key_case) = request_template.key_case {
+        response.body = decode_keys(key_case, std::mem::take(&mut response.body));
+    }
+
+    if let Some(connection) = &request_template.connection {
+        response.body = connection.to_connection(std::mem::take(&mut response.body));
+    }
+
+    if http_validation {
+        request_template
+            .endpoint
+            .output
+            .validate(&response.body)
+            .to_result()
+            .map_err(Error::from)?;
+    }
+
+    Ok(response)
+}
+
 pub async fn execute_request_with_dl<
     'ctx,
     Ctx: ResolverContextLike,
@@ -162,15 +300,25 @@ pub async fn execute_request_with_dl<
         .unwrap_or_default();
 
     let (req, batching_value) = req.into_parts();
-    let endpoint_key =
-        crate::core::http::DataLoaderRequest::new(req, headers).with_batching_value(batching_value);
+    let budget = ctx.request_ctx.upstream_budget();
+    let endpoint_key = crate::core::http::DataLoaderRequest::new(req, headers)
+        .with_batching_value(batching_value)
+        .with_budget(budget.clone());
 
-    Ok(data_loader
-        .unwrap()
-        .load_one(endpoint_key)
+    let data_loader = data_loader.unwrap();
+    let (response, batch_size) = data_loader
+        .load_one_batched(endpoint_key)
         .await
-        .map_err(Error::from)?
-        .unwrap_or_default())
+        .map_err(Error::from)?;
+    if data_loader.loader().is_batched() {
+        ctx.record_stats(|stats, path| stats.batched(path, batch_size));
+    }
+
+    // the key is left out of the batch when the request is out of budget
+    match response {
+        Some(response) => Ok(response),
+        None => budget.check().map(|_| Response::default()),
+    }
 }
 
 pub fn set_headers<Ctx: ResolverContextLike>(
@@ -209,6 +357,18 @@ fn set_cookie_headers<Ctx: ResolverContextLike>(
     }
 }
 
+/// Wraps a single value into a list, or keeps only the first element of a
+/// list, for the upstreams that don't return the shape of the schema.
+fn coerce_list(value: async_graphql::Value, is_list: bool) -> async_graphql::Value {
+    match value {
+        async_graphql::Value::List(list) if !is_list => list.into_iter().next().unwrap_or_default(),
+        value @ async_graphql::Value::Object(_) if is_list => {
+            async_graphql::Value::List(vec![value])
+        }
+        value => value,
+    }
+}
+
 pub async fn execute_raw_request<Ctx: ResolverContextLike>(
     ctx: &EvalContext<'_, Ctx>,
     req: DynamicRequest<String>,
@@ -225,6 +385,25 @@ pub async fn execute_raw_request<Ctx: ResolverContextLike>(
     Ok(response)
 }
 
+/// Calls the method of the operation on its REST form exposed by a gRPC-JSON
+/// transcoding proxy.
+pub async fn execute_transcoded_request<Ctx: ResolverContextLike>(
+    ctx: &EvalContext<'_, Ctx>,
+    req: DynamicRequest<String>,
+    operation: &ProtobufOperation,
+) -> Result<Response<async_graphql::Value>, Error> {
+    let response = ctx
+        .request_ctx
+        .runtime
+        .http
+        .execute(req.into_request())
+        .await
+        .map_err(Error::from)?
+        .to_transcoded_value(operation)?;
+
+    Ok(response)
+}
+
 pub async fn execute_raw_grpc_request<Ctx: ResolverContextLike>(
     ctx: &EvalContext<'_, Ctx>,
     req: Request,
@@ -254,31 +433,260 @@ pub async fn execute_grpc_request_with_dl<
         .clone()
         .map(|s| s.headers)
         .unwrap_or_default();
-    let endpoint_key = grpc::DataLoaderRequest::new(rendered, headers);
+    let budget = ctx.request_ctx.upstream_budget();
+    let endpoint_key = grpc::DataLoaderRequest::new(rendered, headers).with_budget(budget.clone());
 
-    Ok(data_loader
-        .unwrap()
-        .load_one(endpoint_key)
+    let data_loader = data_loader.unwrap();
+    let (response, batch_size) = data_loader
+        .load_one_batched(endpoint_key)
         .await
-        .map_err(Error::from)?
-        .unwrap_or_default())
+        .map_err(Error::from)?;
+    if data_loader.loader().is_batched() {
+        ctx.record_stats(|stats, path| stats.batched(path, batch_size));
+    }
+
+    // the key is left out of the batch when the request is out of budget
+    match response {
+        Some(response) => Ok(response),
+        None => budget.check().map(|_| Response::default()),
+    }
 }
 
 pub fn parse_graphql_response<Ctx: ResolverContextLike>(
     ctx: &EvalContext<'_, Ctx>,
     res: Response<async_graphql::Value>,
     field_name: &str,
+    error_policy: ErrorPolicy,
 ) -> Result<async_graphql::Value, Error> {
     let res: async_graphql::Response =
         from_value(res.body).map_err(|err| Error::Deserialize(err.to_string()))?;
-
-    for error in res.errors {
-        ctx.add_error(error);
-    }
-
-    Ok(res
+    let data = res
         .data
         .get_key(field_name)
         .map(|v| v.to_owned())
-        .unwrap_or_default())
+        .unwrap_or_default();
+
+    if res.errors.is_empty() {
+        return Ok(data);
+    }
+
+    match error_policy {
+        ErrorPolicy::Propagate => {
+            let path = ctx.response_path();
+            for error in res.errors {
+                ctx.add_error(remap_error_path(error, field_name, &path));
+            }
+        }
+        ErrorPolicy::Ignore if !data.is_null() => {}
+        ErrorPolicy::Ignore | ErrorPolicy::Fail => {
+            let errors = res.errors.into_iter().map(|error| error.message);
+            return Err(Error::GraphQL(errors.collect()));
+        }
+    }
+
+    Ok(data)
+}
+
+/// Replaces the upstream field at the start of the path of the error with the
+/// path of the field that it's resolved for, eg. `user.name` of the upstream
+/// becomes `posts.0.author.name`. Errors without a path are reported for the
+/// field, the locations refer to the upstream query and are dropped.
+fn remap_error_path(error: ServerError, field_name: &str, path: &[PathSegment]) -> ServerError {
+    let mut upstream_path = error.path.into_iter().peekable();
+    upstream_path
+        .next_if(|segment| matches!(segment, PathSegment::Field(name) if name == field_name));
+    let path = path.iter().cloned().chain(upstream_path).collect();
+
+    ServerError { path, locations: vec![], ..error }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_graphql::{Name, Value};
+    use indexmap::IndexMap;
+    use serde_json::json;
+
+    use super::*;
+    use crate::core::http::RequestContext;
+    use crate::core::ir::SelectionField;
+
+    #[derive(Clone, Default)]
+    struct TestContext {
+        errors: Arc<Mutex<Vec<ServerError>>>,
+    }
+
+    impl ResolverContextLike for TestContext {
+        fn value(&self) -> Option<&Value> {
+            None
+        }
+
+        fn args(&self) -> Option<&IndexMap<Name, Value>> {
+            None
+        }
+
+        fn field(&self) -> Option<SelectionField> {
+            None
+        }
+
+        fn is_query(&self) -> bool {
+            true
+        }
+
+        fn operation_name(&self) -> Option<&str> {
+            None
+        }
+
+        fn operation_type(&self) -> Option<async_graphql::parser::types::OperationType> {
+            None
+        }
+
+        fn add_error(&self, error: ServerError) {
+            self.errors.lock().unwrap().push(error);
+        }
+
+        fn response_path(&self) -> Vec<PathSegment> {
+            vec![
+                PathSegment::Field("posts".to_string()),
+                PathSegment::Index(0),
+                PathSegment::Field("author".to_string()),
+            ]
+        }
+    }
+
+    fn parse(
+        body: serde_json::Value,
+        error_policy: ErrorPolicy,
+    ) -> (Result<Value, Error>, Vec<ServerError>) {
+        let req_ctx = RequestContext::default();
+        let graphql_ctx = TestContext::default();
+        let ctx = EvalContext::new(&req_ctx, &graphql_ctx);
+        let res = Response { body: Value::from_json(body).unwrap(), ..Default::default() };
+
+        let result = parse_graphql_response(&ctx, res, "user", error_policy);
+        let errors = graphql_ctx.errors.lock().unwrap().clone();
+
+        (result, errors)
+    }
+
+    fn partial() -> serde_json::Value {
+        json!({
+            "data": {"user": {"id": 1, "name": null}},
+            "errors": [{
+                "message": "Name is private",
+                "locations": [{"line": 1, "column": 12}],
+                "path": ["user", "name"]
+            }]
+        })
+    }
+
+    #[test]
+    fn test_propagate() {
+        let (result, errors) = parse(partial(), ErrorPolicy::Propagate);
+
+        let expected = Value::from_json(json!({"id": 1, "name": null})).unwrap();
+        assert_eq!(result.unwrap(), expected);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Name is private");
+        assert_eq!(
+            errors[0].path,
+            vec![
+                PathSegment::Field("posts".to_string()),
+                PathSegment::Index(0),
+                PathSegment::Field("author".to_string()),
+                PathSegment::Field("name".to_string()),
+            ]
+        );
+        assert!(errors[0].locations.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_propagate_list_index() {
+        use crate::core::app_context::test::{TestApp, TestHttp};
+
+        let sdl = r#"
+            schema @server @upstream { query: Query }
+            type Query {
+              posts: [Post] @http(url: "http://jsonplaceholder/posts")
+            }
+            type Post {
+              id: Int
+              userId: Int
+              user: User
+                @graphQL(
+                  url: "http://upstream/graphql"
+                  name: "user"
+                  args: [{key: "id", value: "{{.value.userId}}"}]
+                )
+            }
+            type User {
+              id: Int
+              name: String
+            }
+        "#;
+        let http = TestHttp::body(|request| {
+            if request.url().path() == "/posts" {
+                return Ok(r#"[{"id": 1, "userId": 1}, {"id": 2, "userId": 2}]"#.to_string());
+            }
+            let body = request.body().and_then(|body| body.as_bytes()).unwrap();
+            Ok(if String::from_utf8_lossy(body).contains("id: 2") {
+                r#"{"data": {"user": {"id": 2, "name": null}}, "errors": [{"message": "Name is private", "path": ["user", "name"]}]}"#
+            } else {
+                r#"{"data": {"user": {"id": 1, "name": "Leanne"}}}"#
+            }
+            .to_string())
+        });
+        let app = TestApp::new(sdl, http);
+
+        let response = app.execute("query { posts { id user { name } } }").await;
+
+        assert_eq!(response["data"]["posts"][1]["user"]["name"], json!(null));
+        assert_eq!(response["errors"][0]["message"], json!("Name is private"));
+        assert_eq!(
+            response["errors"][0]["path"],
+            json!(["posts", 1, "user", "name"])
+        );
+    }
+
+    #[test]
+    fn test_ignore() {
+        let (result, errors) = parse(partial(), ErrorPolicy::Ignore);
+
+        let expected = Value::from_json(json!({"id": 1, "name": null})).unwrap();
+        assert_eq!(result.unwrap(), expected);
+        assert!(errors.is_empty());
+
+        // without the data the errors fail the field
+        let body = json!({"data": {"user": null}, "errors": [{"message": "Not found"}]});
+        let (result, errors) = parse(body, ErrorPolicy::Ignore);
+
+        assert!(matches!(result, Err(Error::GraphQL(messages)) if messages == ["Not found"]));
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_coerce_list() {
+        let user = Value::from_json(json!({"id": 1})).unwrap();
+        let users = Value::from_json(json!([{"id": 1}, {"id": 2}])).unwrap();
+
+        assert_eq!(
+            coerce_list(user.clone(), true),
+            Value::List(vec![user.clone()])
+        );
+        assert_eq!(coerce_list(users.clone(), true), users);
+
+        assert_eq!(coerce_list(users, false), user);
+        assert_eq!(coerce_list(user.clone(), false), user);
+        assert_eq!(coerce_list(Value::List(vec![]), false), Value::Null);
+        assert_eq!(coerce_list(Value::Null, true), Value::Null);
+    }
+
+    #[test]
+    fn test_fail() {
+        let (result, errors) = parse(partial(), ErrorPolicy::Fail);
+
+        assert!(matches!(result, Err(Error::GraphQL(messages)) if messages == ["Name is private"]));
+        assert!(errors.is_empty());
+    }
 }
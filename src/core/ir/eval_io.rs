@@ -1,20 +1,67 @@
+use std::future::Future;
+
+use async_graphql::ServerError;
 use async_graphql_value::ConstValue;
+use futures_util::stream::{self, BoxStream};
+use futures_util::{StreamExt, TryStreamExt};
+use tailcall_valid::Validator;
+use url::Url;
 
 use super::eval_http::{
     execute_grpc_request_with_dl, execute_raw_grpc_request, execute_raw_request,
-    execute_request_with_dl, parse_graphql_response, set_headers, EvalHttp, WorkerContext,
+    execute_request_with_dl, execute_transcoded_request, parse_graphql_response, set_headers,
+    EvalHttp, WorkerContext,
 };
 use super::model::{CacheKey, IO};
-use super::{DynamicRequest, EvalContext, ResolverContextLike};
+use super::{DetachedContext, DynamicRequest, EvalContext, ResolverContextLike};
+use crate::core::blueprint::Canary;
 use crate::core::config::GraphQLOperationType;
 use crate::core::data_loader::DataLoader;
-use crate::core::graphql::GraphqlDataLoader;
+use crate::core::graphql::{self, GraphqlDataLoader};
 use crate::core::grpc;
 use crate::core::grpc::data_loader::GrpcDataLoader;
-use crate::core::http::DataLoaderRequest;
+use crate::core::http::{
+    decode_keys, ndjson, upstream_size, with_priority, DataLoaderRequest, RequestContext, Response,
+    ResponseSchema, Vary,
+};
 use crate::core::ir::Error;
+use crate::core::json::JsonSchema;
 
 pub async fn eval_io<Ctx>(io: &IO, ctx: &mut EvalContext<'_, Ctx>) -> Result<ConstValue, Error>
+where
+    Ctx: ResolverContextLike + Sync,
+{
+    // A skipped call resolves to null before it's deduped or batched, so that
+    // its key never reaches the data loader.
+    if io.is_skipped(ctx) {
+        return Ok(ConstValue::Null);
+    }
+
+    // Only the current request stops waiting on cancellation. A deduped call
+    // keeps running while the other requests are waiting on it.
+    let cancellation = ctx.request_ctx.cancellation.clone();
+    let field = ctx
+        .request_ctx
+        .upstream_size_metrics
+        .then(|| ctx.field().map(|field| field.name().to_string()))
+        .flatten();
+    cancellation
+        .run_until_cancelled(upstream_size::with_field(field, eval_io_deduped(io, ctx)))
+        .await
+        .unwrap_or(Err(Error::Cancelled))
+}
+
+/// Value of an IO that's deduped across the requests, along with what its
+/// evaluation reported to the context of the field.
+#[derive(Clone)]
+pub struct DedupedValue {
+    value: ConstValue,
+    status: Option<u16>,
+    vary: Option<Vary>,
+    errors: Vec<ServerError>,
+}
+
+async fn eval_io_deduped<Ctx>(io: &IO, ctx: &mut EvalContext<'_, Ctx>) -> Result<ConstValue, Error>
 where
     Ctx: ResolverContextLike + Sync,
 {
@@ -26,41 +73,120 @@ where
         return eval_io_inner(io, ctx).await;
     }
     if let Some(key) = io.cache_key(ctx) {
-        ctx.request_ctx
+        let mut evaluated = false;
+        let mut reported = None;
+        let result = ctx
+            .request_ctx
             .cache
             .dedupe(&key, || async {
-                ctx.request_ctx
+                let deduped = ctx
+                    .request_ctx
                     .dedupe_handler
-                    .dedupe(&key, || eval_io_inner(io, ctx))
-                    .await
+                    .dedupe_shared(&key, || {
+                        evaluated = true;
+                        eval_io_detached(io.clone(), ctx.request_ctx.detached(), ctx.detach())
+                    })
+                    .await?;
+                if evaluated {
+                    deduped
+                        .errors
+                        .iter()
+                        .for_each(|error| ctx.add_error(error.clone()));
+                }
+                reported = Some((deduped.status, deduped.vary));
+                Ok(deduped.value)
             })
-            .await
+            .await;
+        if let Some((status, vary)) = reported {
+            if let Some(status) = status {
+                ctx.set_upstream_status(status);
+            }
+            if vary.is_some() {
+                ctx.set_upstream_vary(vary);
+            }
+        }
+        if !evaluated {
+            ctx.record_stats(|stats, path| stats.deduped(path));
+        }
+
+        result
     } else {
         eval_io_inner(io, ctx).await
     }
 }
 
+/// Evaluates the IO on an owned copy of the context, so that the call isn't
+/// tied to the caller that started it: it keeps running while any of the
+/// callers that it's deduped for is waiting on it.
+async fn eval_io_detached(
+    io: IO,
+    request_ctx: RequestContext,
+    graphql_ctx: DetachedContext,
+) -> Result<DedupedValue, Error> {
+    let mut ctx = EvalContext::attach(&request_ctx, &graphql_ctx);
+    let value = eval_io_inner(&io, &mut ctx).await?;
+
+    Ok(DedupedValue {
+        value,
+        status: ctx.upstream_status(),
+        vary: ctx.upstream_vary().cloned(),
+        errors: graphql_ctx.errors(),
+    })
+}
+
 async fn eval_io_inner<Ctx>(io: &IO, ctx: &mut EvalContext<'_, Ctx>) -> Result<ConstValue, Error>
 where
     Ctx: ResolverContextLike + Sync,
 {
+    ctx.record_stats(|stats, path| stats.upstream_call(path));
+
     match io {
         IO::Http { req_template, dl_id, hook, .. } => {
             let event_worker = &ctx.request_ctx.runtime.cmd_worker;
             let js_worker = &ctx.request_ctx.runtime.worker;
             let eval_http = EvalHttp::new(ctx, req_template, dl_id);
             let request = eval_http.init_request()?;
-            let response = match (&event_worker, js_worker, hook) {
-                (Some(worker), Some(js_worker), Some(hook)) => {
-                    let worker_ctx = WorkerContext::new(worker, js_worker, hook);
-                    eval_http.execute_with_worker(request, worker_ctx).await?
+            let url = request.request().url().clone();
+            let canary = ctx.request_ctx.upstream.canary.as_ref().and_then(|canary| {
+                let request = canary.request(request.request(), &ctx.request_ctx.runtime.rng)?;
+                Some((canary, request))
+            });
+            let execute = async {
+                match (&event_worker, js_worker, hook) {
+                    (Some(worker), Some(js_worker), Some(hook)) => {
+                        let worker_ctx = WorkerContext::new(worker, js_worker, hook);
+                        eval_http.execute_with_worker(request, worker_ctx).await
+                    }
+                    _ => eval_http.execute(request).await,
                 }
-                _ => eval_http.execute(request).await?,
             };
+            let response = with_priority(req_template.priority, execute).await?;
+            if let Some((canary, request)) = canary {
+                let field = ctx.field();
+                let field = field
+                    .as_ref()
+                    .map_or(url.path(), |field| field.name())
+                    .to_string();
+                shadow(
+                    canary.clone(),
+                    (field, url),
+                    response.body.clone(),
+                    eval_http.execute_canary(request),
+                );
+            }
+            ctx.set_upstream_status(response.status.as_u16());
+            ctx.set_upstream_vary(Some(Vary::from_headers(&response.headers)));
 
             Ok(response.body)
         }
-        IO::GraphQL { req_template, field_name, dl_id, .. } => {
+        IO::GraphQL { req_template, .. }
+            if req_template.operation_type == GraphQLOperationType::Subscription =>
+        {
+            Err(Error::IO(
+                "Subscriptions can only be resolved as a stream of events".to_string(),
+            ))
+        }
+        IO::GraphQL { req_template, field_name, dl_id, error_policy, .. } => {
             let req = req_template.to_request(ctx)?;
             let request = DynamicRequest::new(req);
             let res = if ctx.request_ctx.upstream.batch.is_some()
@@ -70,26 +196,54 @@ where
                     dl_id.and_then(|dl| ctx.request_ctx.gql_data_loaders.get(dl.as_usize()));
                 execute_request_with_dl(ctx, request, data_loader).await?
             } else {
+                ctx.request_ctx.charge_upstream_call()?;
                 execute_raw_request(ctx, request).await?
             };
 
             set_headers(ctx, &res);
-            parse_graphql_response(ctx, res, field_name)
+            ctx.set_upstream_vary(Some(Vary::from_headers(&res.headers)));
+            parse_graphql_response(ctx, res, field_name, *error_policy)
         }
         IO::Grpc { req_template, dl_id, hook, .. } => {
-            let rendered = req_template.render(ctx)?;
+            let selection = req_template.field_mask.as_ref().and_then(|_| ctx.field());
+            let rendered = req_template.render_with_selection(ctx, selection.as_ref())?;
             let worker = &ctx.request_ctx.runtime.worker;
 
-            let res = if ctx.request_ctx.upstream.batch.is_some() &&
-                    // TODO: share check for operation_type for resolvers
-                    matches!(req_template.operation_type, GraphQLOperationType::Query)
-            {
-                let data_loader: Option<&DataLoader<grpc::DataLoaderRequest, GrpcDataLoader>> =
-                    dl_id.and_then(|index| ctx.request_ctx.grpc_data_loaders.get(index.as_usize()));
-                execute_grpc_request_with_dl(ctx, rendered, data_loader).await?
-            } else {
-                let req = rendered.to_request()?;
-                execute_raw_grpc_request(ctx, req, &req_template.operation).await?
+            // every attempt of a retried call is sent on its own
+            let (eval_ctx, rendered) = (&*ctx, &rendered);
+            let attempt = move || {
+                let execute = async move {
+                    if let Some(transcoding) = &req_template.transcoding {
+                        eval_ctx.request_ctx.charge_upstream_call()?;
+                        let req = DynamicRequest::new(transcoding.to_request(rendered)?);
+                        return execute_transcoded_request(eval_ctx, req, &rendered.operation)
+                            .await;
+                    }
+
+                    // the data loaders call the method of the template, not the
+                    // dynamic one
+                    if eval_ctx.request_ctx.upstream.batch.is_some() &&
+                        req_template.dynamic_method.is_none() &&
+                        // TODO: share check for operation_type for resolvers
+                        matches!(req_template.operation_type, GraphQLOperationType::Query)
+                    {
+                        let data_loader: Option<
+                            &DataLoader<grpc::DataLoaderRequest, GrpcDataLoader>,
+                        > = dl_id.and_then(|index| {
+                            eval_ctx.request_ctx.grpc_data_loaders.get(index.as_usize())
+                        });
+                        execute_grpc_request_with_dl(eval_ctx, rendered.clone(), data_loader).await
+                    } else {
+                        eval_ctx.request_ctx.charge_upstream_call()?;
+                        let req = rendered.to_request()?;
+                        execute_raw_grpc_request(eval_ctx, req, &rendered.operation).await
+                    }
+                };
+                with_priority(req_template.priority, execute)
+            };
+            let res = match req_template.retry.as_ref() {
+                Some(retry) => retry.run(attempt).await?,
+                None => attempt().await?,
             };
 
             let res = match (worker.as_ref(), hook.as_ref()) {
@@ -101,6 +255,7 @@ where
             Ok(res.body)
         }
         IO::Js { name } => {
+            ctx.request_ctx.charge_upstream_call()?;
             if let Some((worker, value)) = ctx
                 .request_ctx
                 .runtime
@@ -116,3 +271,237 @@ where
         }
     }
 }
+
+/// Compares the result of the canary upstream with the primary one in a
+/// detached task, so that the client is served without waiting for the canary.
+/// The canary calls that exceed the timeout of the canary are dropped.
+fn shadow(
+    canary: Canary,
+    (field, url): (String, Url),
+    primary: ConstValue,
+    execute: impl Future<Output = Result<ConstValue, Error>> + Send + 'static,
+) {
+    let task = async move {
+        match tokio::time::timeout(canary.timeout, execute).await {
+            Ok(Ok(value)) => {
+                canary.diff.report(&field, &primary, &value);
+            }
+            Ok(Err(err)) => {
+                tracing::warn!("The canary request for {} failed: {}", url, err);
+            }
+            Err(_) => {
+                tracing::warn!(
+                    "The canary request for {} timed out after {}ms",
+                    url,
+                    canary.timeout.as_millis()
+                );
+            }
+        }
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    tokio::spawn(task);
+    #[cfg(target_arch = "wasm32")]
+    async_std::task::spawn_local(task);
+}
+
+/// Subscribes to the upstream of the IO, the events are produced by the
+/// `@graphQL` subscriptions and by the `@http` responses that stream the
+/// newline delimited JSON, one event per line.
+pub fn eval_subscription<Ctx>(
+    io: &IO,
+    ctx: &EvalContext<'_, Ctx>,
+) -> Result<BoxStream<'static, Result<ConstValue, Error>>, Error>
+where
+    Ctx: ResolverContextLike + Sync,
+{
+    match io {
+        IO::GraphQL { req_template, field_name, .. }
+            if req_template.operation_type == GraphQLOperationType::Subscription =>
+        {
+            let Some(websocket) = ctx.request_ctx.runtime.websocket.clone() else {
+                return Err(Error::IO(
+                    "Subscriptions are not supported by the runtime".to_string(),
+                ));
+            };
+            let request = req_template.to_request(ctx)?;
+
+            Ok(graphql::subscribe(websocket, request, field_name.clone())?.boxed())
+        }
+        IO::Http { req_template, .. } => {
+            let request = req_template.to_request(ctx)?.into_request();
+            let http = ctx.request_ctx.runtime.http.clone();
+            let events = stream::once(async move { http.execute_stream(request).await })
+                .map_ok(|response| ndjson::decode(response.body))
+                .try_flatten()
+                .map_err(Error::from);
+
+            Ok(events.boxed())
+        }
+        _ => Err(Error::IO(
+            "Subscription fields can only be resolved with @graphQL or @http".to_string(),
+        )),
+    }
+}
+
+/// Streams the elements of the list returned by an `@http` upstream or by a
+/// server-streaming `@grpc` method. The elements of the newline delimited JSON
+/// are produced as their lines are received, and the messages of the gRPC
+/// stream as they are received, any other response is parsed once it's
+/// received in full. The upstream call is made like any other call of the IO,
+/// the stream is opened once the head of the response is received. It's `None`
+/// when the call is skipped or its status resolves to null.
+pub async fn eval_stream<Ctx>(
+    io: &IO,
+    ctx: &mut EvalContext<'_, Ctx>,
+) -> Result<Option<BoxStream<'static, Result<ConstValue, Error>>>, Error>
+where
+    Ctx: ResolverContextLike + Sync,
+{
+    let req_template = match io {
+        IO::Http { req_template, .. } => req_template,
+        IO::Grpc { req_template, .. } => {
+            ctx.record_stats(|stats, path| stats.upstream_call(path));
+            let selection = req_template.field_mask.as_ref().and_then(|_| ctx.field());
+            let rendered = req_template.render_with_selection(ctx, selection.as_ref())?;
+            let request = rendered.to_request()?;
+            let http = ctx.request_ctx.runtime.http2_only.clone();
+            let execute = async {
+                ctx.request_ctx.charge_upstream_call()?;
+                http.execute_stream_with_trailers(request)
+                    .await
+                    .map_err(Error::from)
+            };
+            let (response, trailers) = ctx
+                .request_ctx
+                .cancellation
+                .run_until_cancelled(with_priority(req_template.priority, execute))
+                .await
+                .unwrap_or(Err(Error::Cancelled))?;
+
+            let head = Response {
+                status: response.status,
+                headers: response.headers.clone(),
+                body: ConstValue::Null,
+            };
+            set_headers(ctx, &head);
+
+            let messages =
+                grpc::stream::decode(rendered.operation, response, trailers).map_err(Error::from);
+            return Ok(Some(messages.boxed()));
+        }
+        _ => {
+            return Err(Error::IO(
+                "Only the @http and @grpc lists can be streamed".to_string(),
+            ))
+        }
+    };
+    if io.is_skipped(ctx) {
+        return Ok(None);
+    }
+    ctx.record_stats(|stats, path| stats.upstream_call(path));
+
+    let request = EvalHttp::new(ctx, req_template, &None)
+        .init_request()?
+        .into_request();
+    let http = ctx.request_ctx.runtime.http.clone();
+    let execute = async {
+        ctx.request_ctx.charge_upstream_call()?;
+        http.execute_stream(request).await.map_err(Error::from)
+    };
+    let response = ctx
+        .request_ctx
+        .cancellation
+        .run_until_cancelled(with_priority(req_template.priority, execute))
+        .await
+        .unwrap_or(Err(Error::Cancelled));
+    let response = match response {
+        // the statuses configured as successful resolve to null
+        Err(Error::HTTP { status, .. })
+            if req_template
+                .success_statuses
+                .iter()
+                .any(|range| range.contains(&status)) =>
+        {
+            ctx.set_upstream_status(status);
+            return Ok(None);
+        }
+        response => response?,
+    };
+
+    ctx.set_upstream_status(response.status.as_u16());
+    ctx.set_upstream_vary(Some(Vary::from_headers(&response.headers)));
+    let head = Response {
+        status: response.status,
+        headers: response.headers.clone(),
+        body: ConstValue::Null,
+    };
+    set_headers(ctx, &head);
+
+    // the elements are transformed and validated like the elements of the
+    // list resolved in full
+    let key_case = req_template.key_case;
+    let url = req_template.endpoint.path.clone();
+    let response_schema = req_template
+        .response_schema
+        .as_ref()
+        .map(|response_schema| {
+            ResponseSchema::new(
+                element_schema(&response_schema.schema).clone(),
+                response_schema.on_violation,
+            )
+        });
+    let schema = ctx
+        .request_ctx
+        .server
+        .get_enable_http_validation()
+        .then(|| element_schema(&req_template.endpoint.output).clone());
+    let items = if ndjson::is_ndjson(&response.headers) {
+        ndjson::decode(response.body)
+    } else {
+        stream::once(async move {
+            let body = response
+                .body
+                .try_fold(Vec::new(), |mut body, chunk| async move {
+                    body.extend_from_slice(&chunk);
+                    Ok(body)
+                })
+                .await?;
+
+            match serde_json::from_slice(&body)? {
+                ConstValue::List(items) => Ok(items),
+                ConstValue::Null => Ok(vec![]),
+                _ => Err(anyhow::anyhow!("Expected a list in the response")),
+            }
+        })
+        .map_ok(|items| stream::iter(items.into_iter().map(Ok::<_, anyhow::Error>)))
+        .try_flatten()
+        .boxed()
+    };
+    let items = items.map(move |item| {
+        let item = item?;
+        if let Some(response_schema) = &response_schema {
+            response_schema.validate(&url, &item)?;
+        }
+        let item = match key_case {
+            Some(key_case) => decode_keys(key_case, item),
+            None => item,
+        };
+        if let Some(schema) = &schema {
+            schema.validate(&item).to_result()?;
+        }
+
+        Ok::<_, Error>(item)
+    });
+
+    Ok(Some(items.boxed()))
+}
+
+/// The schema of the elements of a list, or the schema itself if it isn't one.
+fn element_schema(schema: &JsonSchema) -> &JsonSchema {
+    match schema {
+        JsonSchema::Opt(schema) => element_schema(schema),
+        JsonSchema::Arr(schema) => schema,
+        schema => schema,
+    }
+}
@@ -0,0 +1,228 @@
+use std::time::Instant;
+
+use async_graphql_value::ConstValue;
+use futures_util::future::BoxFuture;
+
+use super::model::IR;
+use super::Error;
+use crate::core::http::RequestContext;
+
+/// Evaluation of a node by the rest of the middlewares of the stack, the node
+/// itself is evaluated at the bottom of the stack.
+pub struct Next<'a> {
+    future: BoxFuture<'a, Result<ConstValue, Error>>,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(future: BoxFuture<'a, Result<ConstValue, Error>>) -> Self {
+        Self { future }
+    }
+
+    pub async fn run(self) -> Result<ConstValue, Error> {
+        self.future.await
+    }
+}
+
+/// Wraps the evaluation of every node of the IR, including the nested ones,
+/// eg. to log, measure or short-circuit it. The middlewares of the runtime are
+/// stacked in the order they are added, the first one is the outermost. A
+/// middleware that returns without running `next` skips the evaluation of the
+/// node along with the rest of the stack. The middlewares only have access to
+/// the context of the request, not to the values and the arguments of the
+/// fields.
+#[async_trait::async_trait]
+pub trait EvalMiddleware: Send + Sync {
+    async fn around(
+        &self,
+        node: &IR,
+        ctx: &RequestContext,
+        next: Next<'_>,
+    ) -> Result<ConstValue, Error>;
+}
+
+/// Logs the time taken to evaluate each node, the time of a node includes the
+/// time of its nested nodes.
+pub struct EvalTiming;
+
+#[async_trait::async_trait]
+impl EvalMiddleware for EvalTiming {
+    async fn around(
+        &self,
+        node: &IR,
+        _: &RequestContext,
+        next: Next<'_>,
+    ) -> Result<ConstValue, Error> {
+        let started = Instant::now();
+        let result = next.run().await;
+        tracing::debug!(
+            node = %node,
+            elapsed_ms = started.elapsed().as_secs_f64() * 1000.0,
+            "Evaluated the node"
+        );
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU64;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use hyper::body::Bytes;
+    use reqwest::StatusCode;
+
+    use super::*;
+    use crate::core::blueprint::DynamicValue;
+    use crate::core::http::{RequestTemplate, Response};
+    use crate::core::ir::model::{Cache, CacheOptions, IO};
+    use crate::core::ir::{EmptyResolverContext, EvalContext};
+    use crate::core::HttpIO;
+
+    /// Records the nodes it's entered for, under its label
+    struct Record {
+        label: &'static str,
+        nodes: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl EvalMiddleware for Record {
+        async fn around(
+            &self,
+            node: &IR,
+            _: &RequestContext,
+            next: Next<'_>,
+        ) -> Result<ConstValue, Error> {
+            let entry = format!("{}:{}", self.label, node);
+            self.nodes.lock().unwrap().push(entry);
+            next.run().await
+        }
+    }
+
+    /// Serves a value of its own for the cached nodes
+    struct Stub;
+
+    #[async_trait::async_trait]
+    impl EvalMiddleware for Stub {
+        async fn around(
+            &self,
+            node: &IR,
+            _: &RequestContext,
+            next: Next<'_>,
+        ) -> Result<ConstValue, Error> {
+            match node {
+                IR::Cache(_) => Ok(ConstValue::String("stub".to_string())),
+                _ => next.run().await,
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct CountingHttp {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl HttpIO for CountingHttp {
+        async fn execute(&self, _: reqwest::Request) -> anyhow::Result<Response<Bytes>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(Response {
+                status: StatusCode::OK,
+                headers: Default::default(),
+                body: Bytes::from_static(br#"{"id": 1}"#),
+            })
+        }
+    }
+
+    fn dynamic(value: serde_json::Value) -> IR {
+        IR::Dynamic(DynamicValue::Value(ConstValue::from_json(value).unwrap()))
+    }
+
+    fn cached() -> IR {
+        let io = IR::IO(IO::Http {
+            req_template: RequestTemplate::new("http://localhost:8080/me").unwrap(),
+            group_by: None,
+            dl_id: None,
+            is_list: false,
+            dedupe: false,
+            hook: None,
+        });
+
+        Cache::wrap(NonZeroU64::new(10000).unwrap(), CacheOptions::default(), io)
+    }
+
+    #[tokio::test]
+    async fn test_order() {
+        let nodes = Arc::new(Mutex::new(vec![]));
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.add_eval_middleware(Arc::new(Record { label: "outer", nodes: nodes.clone() }));
+        runtime.add_eval_middleware(Arc::new(Record { label: "inner", nodes: nodes.clone() }));
+        runtime.add_eval_middleware(Arc::new(EvalTiming));
+
+        let req_ctx = RequestContext::new(runtime);
+        let res_ctx = EmptyResolverContext {};
+        let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+        let ir = dynamic(serde_json::json!(1)).pipe(dynamic(serde_json::json!(2)));
+
+        let value = ir.eval(&mut eval_ctx).await.unwrap();
+
+        assert_eq!(value, ConstValue::from_json(serde_json::json!(2)).unwrap());
+        assert_eq!(
+            *nodes.lock().unwrap(),
+            [
+                "outer:Pipe",
+                "inner:Pipe",
+                "outer:Dynamic",
+                "inner:Dynamic",
+                "outer:Dynamic",
+                "inner:Dynamic",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cache() {
+        let nodes = Arc::new(Mutex::new(vec![]));
+        let http = Arc::new(CountingHttp::default());
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.http = http.clone();
+        runtime.add_eval_middleware(Arc::new(Record { label: "cache", nodes: nodes.clone() }));
+
+        let req_ctx = RequestContext::new(runtime);
+        let res_ctx = EmptyResolverContext {};
+        let ir = cached();
+
+        let first = ir
+            .eval(&mut EvalContext::new(&req_ctx, &res_ctx))
+            .await
+            .unwrap();
+        let second = ir
+            .eval(&mut EvalContext::new(&req_ctx, &res_ctx))
+            .await
+            .unwrap();
+
+        // the middleware wraps the cached node on the hits as well
+        assert_eq!(first, second);
+        assert_eq!(http.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(*nodes.lock().unwrap(), ["cache:Cache", "cache:Cache"]);
+    }
+
+    #[tokio::test]
+    async fn test_short_circuit() {
+        let http = Arc::new(CountingHttp::default());
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.http = http.clone();
+        runtime.add_eval_middleware(Arc::new(Stub));
+
+        let req_ctx = RequestContext::new(runtime);
+        let res_ctx = EmptyResolverContext {};
+        let mut eval_ctx = EvalContext::new(&req_ctx, &res_ctx);
+
+        let value = cached().eval(&mut eval_ctx).await.unwrap();
+
+        // neither the cache nor the upstream is consulted
+        assert_eq!(value, ConstValue::String("stub".to_string()));
+        assert_eq!(http.calls.load(Ordering::SeqCst), 0);
+    }
+}
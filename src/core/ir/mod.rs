@@ -1,28 +1,44 @@
+mod constraint;
+mod detached_context;
 mod discriminator;
 mod error;
 mod eval;
 mod eval_context;
 mod eval_http;
 mod eval_io;
+mod middleware;
+mod predicate;
 mod request;
 mod resolver_context_like;
+mod test_context;
+mod value_diff;
+mod visitor;
 
 pub mod model;
 use std::collections::HashMap;
 use std::ops::Deref;
 
+pub use constraint::{Constraint, Pattern};
+pub use detached_context::DetachedContext;
 pub use discriminator::*;
 pub use error::*;
 pub use eval_context::EvalContext;
+pub use eval_io::DedupedValue;
+pub use middleware::{EvalMiddleware, EvalTiming, Next};
+pub use predicate::{is_truthy, Predicate};
 pub(crate) use request::DynamicRequest;
 pub use resolver_context_like::{
     EmptyResolverContext, ResolverContext, ResolverContextLike, SelectionField,
 };
+use serde::{Deserialize, Serialize};
+pub use test_context::TestContext;
+pub use value_diff::{Differences, ValueDiff};
+pub use visitor::{IrVisitor, UpstreamHosts};
 
 /// Contains all the nested fields that are resolved with current parent
 /// resolver i.e. fields that don't have their own resolver and are resolved by
 /// the ancestor
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct RelatedFields(pub HashMap<String, (String, RelatedFields)>);
 
 impl Deref for RelatedFields {
@@ -1,19 +1,28 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
 use std::num::NonZeroU64;
+use std::ops::RangeInclusive;
 
-use async_graphql::Value;
+use async_graphql::{Name, Value};
+use derive_setters::Setters;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 use strum_macros::Display;
+use tailcall_hasher::TailcallHasher;
 
+use super::constraint::Constraint;
 use super::discriminator::Discriminator;
+use super::predicate::Predicate;
 use super::{EvalContext, ResolverContextLike};
 use crate::core::blueprint::{Auth, DynamicValue};
 use crate::core::config::group_by::GroupBy;
+use crate::core::config::{ErrorPolicy, GraphQLBatchStyle, JoinKind};
 use crate::core::graphql::{self};
 use crate::core::worker_hooks::WorkerHooks;
-use crate::core::{grpc, http};
+use crate::core::{grpc, http, Mustache};
 
-#[derive(Clone, Debug, Display)]
+#[derive(Clone, Debug, Display, Serialize, Deserialize)]
 pub enum IR {
     Dynamic(DynamicValue<Value>),
     #[strum(to_string = "{0}")]
@@ -22,11 +31,26 @@ pub enum IR {
     // TODO: Path can be implement using Pipe
     Path(Box<IR>, Vec<String>),
     ContextPath(Vec<String>),
-    Protect(Auth, Box<IR>),
+    Protect(Protect),
+    /// Masks the fields of the resolved value based on the auth scopes
+    Mask(Mask),
+    /// Compresses the resolved string for the clients that accept it
+    Compress(Compress),
     Map(Map),
+    /// Keeps the elements of the resolved list that satisfy the predicate
+    Filter(Filter),
+    /// Drops the null and the empty elements of the resolved list
+    Compact(Compact),
+    /// Fails with the violated constraints of the arguments before the input
+    /// is resolved
+    Validate(Validate),
     Pipe(Box<IR>, Box<IR>),
     /// Merges the result of multiple IRs together
-    Merge(Vec<IR>),
+    Merge(Merge),
+    /// Resolves the first IR that succeeds
+    Fallback(Fallback),
+    /// Joins the elements of two lists by a key
+    Join(Join),
     Discriminate(Discriminator, Box<IR>),
     /// Apollo Federation _entities resolver
     Entity(HashMap<String, IR>),
@@ -34,14 +58,144 @@ pub enum IR {
     Service(String),
 }
 
-#[derive(Clone, Debug)]
+/// Resolves the value only for the authenticated requests.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Protect {
+    pub auth: Auth,
+    pub authed: Box<IR>,
+    /// Resolves the requests without any credentials instead of failing them,
+    /// the requests with invalid credentials still fail.
+    pub anon: Option<Box<IR>>,
+}
+
+impl Protect {
+    pub fn new(auth: Auth, authed: IR) -> Self {
+        Self { auth, authed: Box::new(authed), anon: None }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Map {
     pub input: Box<IR>,
     // accept key return value instead of
     pub map: HashMap<String, String>,
+    pub on_missing: MapMissing,
+}
+
+/// Resolution of the keys that aren't in the map of [Map].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MapMissing {
+    /// The key resolves to null.
+    Null,
+    /// The key resolves to itself.
+    Passthrough,
+    /// The evaluation fails.
+    #[default]
+    Error,
+    /// The key resolves to the value.
+    Default(String),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Merge {
+    pub irs: Vec<IR>,
+    /// Skips the branches that fail instead of failing the merge, so that an
+    /// upstream that's down only leaves its fields null. The merge still fails
+    /// when all the branches fail.
+    pub tolerate_partial: bool,
+}
+
+impl Merge {
+    pub fn new(irs: Vec<IR>) -> Self {
+        Self { irs, tolerate_partial: false }
+    }
+}
+
+/// Evaluates the IRs in order until one of them succeeds, eg. to fail over to
+/// a secondary upstream. The error of the last IR is returned when all of them
+/// fail.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Fallback {
+    pub irs: Vec<IR>,
+    /// A `null` result is a failure too, the next IR is evaluated instead.
+    pub on_null: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Filter {
+    pub input: Box<IR>,
+    pub predicate: Predicate,
+}
+
+/// Drops the `null` elements of the resolved list, and optionally the ones
+/// that are empty, keeping the order of the others. The list is compacted
+/// before it's resolved to the field, so that the elements streamed with
+/// `@stream` are numbered by their position in the compacted list.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Compact {
+    pub input: Box<IR>,
+    /// Drops the elements that are objects without any key.
+    pub empty_objects: bool,
+    /// Drops the objects that miss any of the keys, or where it is `null`.
+    pub required: Vec<String>,
+}
+
+impl Compact {
+    /// Whether the element is kept in the list.
+    pub fn keeps(&self, value: &Value) -> bool {
+        match value {
+            Value::Null => false,
+            Value::Object(object) if self.empty_objects && object.is_empty() => false,
+            Value::Object(object) => self.required.iter().all(|key| {
+                object
+                    .get(key.as_str())
+                    .is_some_and(|value| !matches!(value, Value::Null))
+            }),
+            _ => true,
+        }
+    }
+}
+
+/// Checks the arguments of the field before the input is resolved, so that
+/// the invalid arguments fail the field without calling the upstreams, nor
+/// enrolling into their batches.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Validate {
+    pub input: Box<IR>,
+    pub constraints: Vec<Constraint>,
 }
 
-#[derive(Clone, Debug, strum_macros::Display)]
+/// Joins the elements of two lists by a key, like a SQL join, the matching
+/// right element is merged into the left one. The right side is resolved once
+/// for every distinct key of the left side with the left element as the
+/// value, so that a batched IO of the right side loads all the keys with a
+/// single request.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Join {
+    pub left: Box<IR>,
+    pub right: Box<IR>,
+    /// Paths to the key in the left and in the right elements
+    pub on: (Vec<String>, Vec<String>),
+    pub how: JoinKind,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Mask {
+    pub input: Box<IR>,
+    /// Providers that grant the scopes to the request
+    pub auth: Auth,
+    pub scope: String,
+    pub fields: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Compress {
+    pub input: Box<IR>,
+    /// Size in bytes of the shortest value that is compressed
+    pub min_size: usize,
+}
+
+#[derive(Clone, Debug, strum_macros::Display, Serialize, Deserialize)]
 pub enum IO {
     Http {
         req_template: http::RequestTemplate,
@@ -55,8 +209,12 @@ pub enum IO {
         req_template: graphql::RequestTemplate,
         field_name: String,
         batch: bool,
+        /// How the batched requests are composed and the name they're sent as
+        batch_style: GraphQLBatchStyle,
+        batch_name: Option<String>,
         dl_id: Option<DataLoaderId>,
         dedupe: bool,
+        error_policy: ErrorPolicy,
     },
     Grpc {
         req_template: grpc::RequestTemplate,
@@ -79,9 +237,36 @@ impl IO {
             IO::Js { .. } => false,
         }
     }
+
+    /// Whether the `skipIf` of the call holds for the value and the args of
+    /// the field, the call is then not made at all.
+    pub fn is_skipped<Ctx: ResolverContextLike>(&self, ctx: &EvalContext<'_, Ctx>) -> bool {
+        let IO::Http { req_template, .. } = self else {
+            return false;
+        };
+        let Some(skip_if) = &req_template.skip_if else {
+            return false;
+        };
+
+        let empty: &[&str] = &[];
+        let scope = Value::Object(
+            [
+                ("value", ctx.path_value(empty)),
+                ("args", ctx.path_arg(empty)),
+            ]
+            .into_iter()
+            .map(|(key, value)| {
+                let value = value.map(|value| value.into_owned()).unwrap_or_default();
+                (async_graphql::Name::new(key), value)
+            })
+            .collect(),
+        );
+
+        skip_if.test(&scope)
+    }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct DataLoaderId(usize);
 
 impl DataLoaderId {
@@ -94,16 +279,92 @@ impl DataLoaderId {
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Hash, Debug)]
-pub struct IoId(u64);
+/// Hash of a request, that its responses are cached and deduplicated by. In
+/// the debug builds it carries a second hash of the request, its fingerprint,
+/// that tells apart the distinct requests whose ids collide.
+#[derive(Clone, Debug)]
+pub struct IoId {
+    id: u64,
+    #[cfg(debug_assertions)]
+    fingerprint: u64,
+}
+
+impl PartialEq for IoId {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for IoId {}
+
+impl Hash for IoId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
 
 impl IoId {
     pub fn new(id: u64) -> Self {
-        Self(id)
+        Self {
+            id,
+            #[cfg(debug_assertions)]
+            fingerprint: id,
+        }
+    }
+
+    #[cfg(all(test, debug_assertions))]
+    pub(crate) fn with_fingerprint(id: u64, fingerprint: u64) -> Self {
+        Self { id, fingerprint }
     }
 
     pub fn as_u64(&self) -> u64 {
-        self.0
+        self.id
+    }
+
+    #[cfg(debug_assertions)]
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+}
+
+/// Hashes the parts of a request into its `IoId`, and into its fingerprint
+/// with a different algorithm in the debug builds.
+#[derive(Default)]
+pub struct IoIdHasher {
+    hasher: TailcallHasher,
+    #[cfg(debug_assertions)]
+    fingerprint: std::collections::hash_map::DefaultHasher,
+}
+
+impl IoIdHasher {
+    /// Starts the hash of a key derived from the given key.
+    pub fn derive(key: &IoId) -> Self {
+        let mut hasher = Self::default();
+        hasher.hasher.write_u64(key.id);
+        #[cfg(debug_assertions)]
+        hasher.fingerprint.write_u64(key.fingerprint);
+
+        hasher
+    }
+
+    pub fn finish_id(&self) -> IoId {
+        IoId {
+            id: self.hasher.finish(),
+            #[cfg(debug_assertions)]
+            fingerprint: self.fingerprint.finish(),
+        }
+    }
+}
+
+impl Hasher for IoIdHasher {
+    fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.hasher.write(bytes);
+        #[cfg(debug_assertions)]
+        self.fingerprint.write(bytes);
     }
 }
 
@@ -111,33 +372,343 @@ pub trait CacheKey<Ctx> {
     fn cache_key(&self, ctx: &Ctx) -> Option<IoId>;
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Cache {
     pub max_age: NonZeroU64,
+    /// Invalidation tags that are rendered against the resolved value
+    pub tags: Vec<Mustache>,
+    /// Namespace of the entries that is rendered against the context of the
+    /// request
+    pub namespace: Option<Mustache>,
+    /// Serves the last resolved value when the IO fails
+    pub serve_stale_on_error: bool,
+    /// Lowercase names of the request headers whose values isolate the
+    /// entries
+    pub vary: Vec<String>,
+    /// Results that are stored for a different duration
+    pub negative: Option<Negative>,
+    /// Names of the arguments the entries are keyed by, all of them if not
+    /// set
+    pub key_args: Option<Vec<String>>,
+    /// Large results that are stored for a shorter duration
+    pub size: Option<Size>,
+    /// Bounds of the duration that's adapted to how often the value changes
+    pub adaptive: Option<Adaptive>,
     pub io: Box<IO>,
 }
 
+/// Options of the [Cache] of every IO that is wrapped with [Cache::wrap], see
+/// the fields of [Cache].
+#[derive(Clone, Debug, Default, Setters)]
+#[setters(strip_option)]
+pub struct CacheOptions {
+    pub tags: Vec<Mustache>,
+    pub namespace: Option<Mustache>,
+    pub serve_stale_on_error: bool,
+    pub vary: Vec<String>,
+    pub negative: Option<Negative>,
+    pub key_args: Option<Vec<String>>,
+    pub size: Option<Size>,
+    pub adaptive: Option<Adaptive>,
+}
+
+/// The negative results of a cached IO, eg. the missing entities, along with
+/// the duration they are stored for.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Negative {
+    pub max_age: NonZeroU64,
+    pub null: bool,
+    pub empty: bool,
+    /// Status code ranges of the upstream responses whose results are negative
+    pub status: Vec<RangeInclusive<u16>>,
+}
+
+/// The large results of a cached IO along with the shortest duration they are
+/// stored for.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Size {
+    /// Size in bytes of the serialized value above which the duration is
+    /// reduced
+    pub threshold: u64,
+    pub min_max_age: NonZeroU64,
+}
+
+/// The bounds of the duration of a cached IO that's extended while its
+/// refreshes resolve the same value and shortened when they don't.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Adaptive {
+    pub min_max_age: NonZeroU64,
+    pub max_max_age: NonZeroU64,
+}
+
+impl Adaptive {
+    /// Hash of the serialized value that tells the refreshes apart.
+    pub fn hash(value: &Value) -> u64 {
+        let mut hasher = TailcallHasher::default();
+        serde_json::to_vec(value)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// The duration the refreshed value is stored for, along with the record
+    /// the next refresh is compared with. The duration is doubled when the
+    /// value is the same as the one of the last refresh and halved when it
+    /// isn't, within the bounds. The first refresh is stored for `max_age`.
+    pub fn max_age(
+        &self,
+        max_age: NonZeroU64,
+        record: Option<&Value>,
+        value: &Value,
+    ) -> (NonZeroU64, Value) {
+        let hash = Self::hash(value);
+        let max_age = match record.and_then(Self::read) {
+            Some((last, max_age)) if last == hash => max_age.saturating_mul(2),
+            Some((_, max_age)) => max_age / 2,
+            None => max_age.get(),
+        };
+        let max_age = max_age.clamp(self.min_max_age.get(), self.max_max_age.get());
+        let max_age = NonZeroU64::new(max_age).unwrap_or(self.min_max_age);
+
+        let mut record = IndexMap::new();
+        record.insert(Name::new("hash"), Value::from(hash));
+        record.insert(Name::new("maxAge"), Value::from(max_age.get()));
+
+        (max_age, Value::Object(record))
+    }
+
+    fn read(record: &Value) -> Option<(u64, u64)> {
+        let Value::Object(record) = record else {
+            return None;
+        };
+        let field = |name: &str| match record.get(name) {
+            Some(Value::Number(number)) => number.as_u64(),
+            _ => None,
+        };
+
+        Some((field("hash")?, field("maxAge")?))
+    }
+}
+
+impl Size {
+    /// Reduces the duration in inverse proportion to the size of the value
+    /// once it's serialized, down to the shortest duration.
+    pub fn max_age(&self, max_age: NonZeroU64, value: &Value) -> NonZeroU64 {
+        let size = serde_json::to_vec(value).map_or(0, |bytes| bytes.len() as u64);
+        if size <= self.threshold {
+            return max_age;
+        }
+
+        let reduced = (max_age.get() as u128 * self.threshold as u128 / size as u128) as u64;
+        let reduced = reduced.max(self.min_max_age.get()).min(max_age.get());
+
+        NonZeroU64::new(reduced).unwrap_or(max_age)
+    }
+}
+
+impl Negative {
+    pub fn matches_status(&self, status: u16) -> bool {
+        self.status.iter().any(|range| range.contains(&status))
+    }
+
+    /// Checks if the value, received with the status of the upstream response,
+    /// is negative.
+    pub fn matches(&self, value: &Value, status: Option<u16>) -> bool {
+        match value {
+            Value::Null if self.null => true,
+            Value::List(list) if list.is_empty() && self.empty => true,
+            _ => status.is_some_and(|status| self.matches_status(status)),
+        }
+    }
+}
+
+/// Time in milliseconds the last resolved value is kept for to be served on
+/// errors.
+const STALE_MAX_AGE: u64 = 24 * 60 * 60 * 1000;
+
 impl Cache {
     ///
     /// Wraps an expression with the cache primitive.
     /// Performance DFS on the cache on the expression and identifies all the IO
     /// nodes. Then wraps each IO node with the cache primitive.
-    pub fn wrap(max_age: NonZeroU64, expr: IR) -> IR {
+    pub fn wrap(max_age: NonZeroU64, options: CacheOptions, expr: IR) -> IR {
         expr.modify(&mut move |expr| match expr {
-            IR::IO(io) => Some(IR::Cache(Cache { max_age, io: Box::new(io.to_owned()) })),
+            IR::IO(io) => {
+                let options = options.clone();
+                Some(IR::Cache(Cache {
+                    max_age,
+                    tags: options.tags,
+                    namespace: options.namespace,
+                    serve_stale_on_error: options.serve_stale_on_error,
+                    vary: options.vary,
+                    negative: options.negative,
+                    key_args: options.key_args,
+                    size: options.size,
+                    adaptive: options.adaptive,
+                    io: Box::new(io.to_owned()),
+                }))
+            }
             _ => None,
         })
     }
+
+    /// Key of the last resolved value that is served on errors.
+    pub fn stale_key(key: &IoId) -> IoId {
+        let mut hasher = IoIdHasher::derive(key);
+        "stale".hash(&mut hasher);
+
+        hasher.finish_id()
+    }
+
+    /// Tag of everything that is stored for the key, the entries of the
+    /// headers it varies by, their stale copies and the records of their
+    /// refreshes, so that they're evicted together.
+    pub fn entry_tag(key: &IoId) -> String {
+        format!("tailcall:io:{}", key.as_u64())
+    }
+
+    /// Key of the record of the last refresh that the duration of the next
+    /// one is adapted by.
+    pub fn adaptive_key(key: &IoId) -> IoId {
+        let mut hasher = IoIdHasher::derive(key);
+        "adaptive".hash(&mut hasher);
+
+        hasher.finish_id()
+    }
+
+    /// Key of the names of the headers the last response of the IO varied by.
+    pub fn vary_key(key: &IoId) -> IoId {
+        let mut hasher = IoIdHasher::derive(key);
+        "vary".hash(&mut hasher);
+
+        hasher.finish_id()
+    }
+
+    /// Names of the configured headers along with the ones the upstream
+    /// response varies by, sorted and without duplicates.
+    pub fn vary_headers(&self, varied: &[String]) -> Vec<String> {
+        let mut names = self.vary.iter().chain(varied).cloned().collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Mixes the values of the request headers into the key, so that the
+    /// requests that differ in those headers are stored as different entries.
+    pub fn varied_key<Ctx: ResolverContextLike>(
+        key: IoId,
+        names: &[String],
+        ctx: &EvalContext<'_, Ctx>,
+    ) -> IoId {
+        if names.is_empty() {
+            return key;
+        }
+
+        let mut hasher = IoIdHasher::derive(&key);
+        for name in names {
+            name.hash(&mut hasher);
+            ctx.header(name).hash(&mut hasher);
+        }
+
+        hasher.finish_id()
+    }
+
+    /// Duration the value is stored for, the negative values are stored for
+    /// the negative duration if there's one and the large values for a
+    /// duration reduced by their size.
+    pub fn entry_max_age(&self, value: &Value, negative: bool) -> NonZeroU64 {
+        self.adapted_max_age(self.max_age, value, negative)
+    }
+
+    /// Same as `entry_max_age`, but with `max_age` adapted to the refreshes
+    /// of the value.
+    pub fn adapted_max_age(
+        &self,
+        max_age: NonZeroU64,
+        value: &Value,
+        negative: bool,
+    ) -> NonZeroU64 {
+        let max_age = match &self.negative {
+            Some(Negative { max_age, .. }) if negative => *max_age,
+            _ => max_age,
+        };
+
+        match &self.size {
+            Some(size) => size.max_age(max_age, value),
+            None => max_age,
+        }
+    }
+
+    /// The shortest duration any of the values could be stored for.
+    pub fn min_max_age(&self) -> NonZeroU64 {
+        let max_age = match &self.negative {
+            Some(negative) => negative.max_age.min(self.max_age),
+            None => self.max_age,
+        };
+        let max_age = match &self.adaptive {
+            Some(adaptive) => adaptive.min_max_age.min(max_age),
+            None => max_age,
+        };
+
+        match &self.size {
+            Some(size) => size.min_max_age.min(max_age),
+            None => max_age,
+        }
+    }
+
+    /// The last resolved value outlives the fresh one, so that it's still
+    /// there once the fresh one expires.
+    pub fn stale_max_age(&self) -> NonZeroU64 {
+        NonZeroU64::new(STALE_MAX_AGE).map_or(self.max_age, |max_age| max_age.max(self.max_age))
+    }
+}
+
+impl<'a, Ctx: ResolverContextLike + Sync> CacheKey<EvalContext<'a, Ctx>> for Cache {
+    /// Mixes the rendered namespace into the key of the IO, so that identical
+    /// requests of different namespaces are stored as different entries. The
+    /// key of the IO is rendered with only the key arguments, if they're set.
+    /// The cache salt of the server is mixed in too, so a new deploy doesn't
+    /// read the entries of the previous one.
+    fn cache_key(&self, ctx: &EvalContext<'a, Ctx>) -> Option<IoId> {
+        let key = match self.key_args.as_ref() {
+            Some(names) => {
+                let args = match ctx.path_arg::<&str>(&[]).as_deref() {
+                    Some(Value::Object(args)) => args
+                        .iter()
+                        .filter(|(name, _)| names.iter().any(|key| key == name.as_str()))
+                        .map(|(name, value)| (name.clone(), value.clone()))
+                        .collect(),
+                    _ => Default::default(),
+                };
+                self.io.cache_key(&ctx.with_args(Value::Object(args)))?
+            }
+            None => self.io.cache_key(ctx)?,
+        };
+        let mut hasher = IoIdHasher::derive(&key);
+        ctx.request_ctx.server.cache_salt.hash(&mut hasher);
+        if let Some(namespace) = self.namespace.as_ref() {
+            namespace.render(ctx).hash(&mut hasher);
+        }
+
+        Some(hasher.finish_id())
+    }
 }
 
 impl IR {
-    // allows to modify the IO node in the IR tree
+    /// Modifies every IO node of the tree in place, depth first and from the
+    /// left to the right, the IO of a [Cache] is visited too. The order of the
+    /// entities of [IR::Entity] is unspecified.
     pub fn modify_io(&mut self, io_modifier: &mut dyn FnMut(&mut IO)) {
         match self {
             IR::IO(io) => io_modifier(io),
             IR::Cache(cache) => io_modifier(&mut cache.io),
-            IR::Discriminate(_, ir) | IR::Protect(_, ir) | IR::Path(ir, _) => {
-                ir.modify_io(io_modifier)
+            IR::Discriminate(_, ir) | IR::Path(ir, _) => ir.modify_io(io_modifier),
+            IR::Protect(protect) => {
+                protect.authed.modify_io(io_modifier);
+                if let Some(anon) = protect.anon.as_mut() {
+                    anon.modify_io(io_modifier);
+                }
             }
             IR::Pipe(ir1, ir2) => {
                 ir1.modify_io(io_modifier);
@@ -149,7 +720,21 @@ impl IR {
                 }
             }
             IR::Map(map) => map.input.modify_io(io_modifier),
-            _ => {}
+            IR::Filter(filter) => filter.input.modify_io(io_modifier),
+            IR::Compact(compact) => compact.input.modify_io(io_modifier),
+            IR::Validate(validate) => validate.input.modify_io(io_modifier),
+            IR::Mask(mask) => mask.input.modify_io(io_modifier),
+            IR::Compress(compress) => compress.input.modify_io(io_modifier),
+            IR::Merge(Merge { irs, .. }) | IR::Fallback(Fallback { irs, .. }) => {
+                for ir in irs.iter_mut() {
+                    ir.modify_io(io_modifier);
+                }
+            }
+            IR::Join(join) => {
+                join.left.modify_io(io_modifier);
+                join.right.modify_io(io_modifier);
+            }
+            IR::Dynamic(_) | IR::ContextPath(_) | IR::Service(_) => {}
         }
     }
 
@@ -178,17 +763,62 @@ impl IR {
                     IR::ContextPath(path) => IR::ContextPath(path),
                     IR::Dynamic(_) => expr,
                     IR::IO(_) => expr,
-                    IR::Cache(Cache { io, max_age }) => {
+                    IR::Cache(Cache {
+                        io,
+                        max_age,
+                        tags,
+                        namespace,
+                        serve_stale_on_error,
+                        vary,
+                        negative,
+                        key_args,
+                        size,
+                        adaptive,
+                    }) => {
                         let expr = *IR::IO(*io).modify_box(modifier);
                         match expr {
-                            IR::IO(io) => IR::Cache(Cache { io: Box::new(io), max_age }),
+                            IR::IO(io) => IR::Cache(Cache {
+                                io: Box::new(io),
+                                max_age,
+                                tags,
+                                namespace,
+                                serve_stale_on_error,
+                                vary,
+                                negative,
+                                key_args,
+                                size,
+                                adaptive,
+                            }),
                             expr => expr,
                         }
                     }
                     IR::Path(expr, path) => IR::Path(expr.modify_box(modifier), path),
-                    IR::Protect(auth, expr) => IR::Protect(auth, expr.modify_box(modifier)),
-                    IR::Map(Map { input, map }) => {
-                        IR::Map(Map { input: input.modify_box(modifier), map })
+                    IR::Protect(Protect { auth, authed, anon }) => IR::Protect(Protect {
+                        auth,
+                        authed: authed.modify_box(modifier),
+                        anon: anon.map(|anon| anon.modify_box(modifier)),
+                    }),
+                    IR::Map(Map { input, map, on_missing }) => {
+                        IR::Map(Map { input: input.modify_box(modifier), map, on_missing })
+                    }
+                    IR::Filter(Filter { input, predicate }) => {
+                        IR::Filter(Filter { input: input.modify_box(modifier), predicate })
+                    }
+                    IR::Compact(Compact { input, empty_objects, required }) => {
+                        IR::Compact(Compact {
+                            input: input.modify_box(modifier),
+                            empty_objects,
+                            required,
+                        })
+                    }
+                    IR::Validate(Validate { input, constraints }) => {
+                        IR::Validate(Validate { input: input.modify_box(modifier), constraints })
+                    }
+                    IR::Mask(Mask { input, auth, scope, fields }) => {
+                        IR::Mask(Mask { input: input.modify_box(modifier), auth, scope, fields })
+                    }
+                    IR::Compress(Compress { input, min_size }) => {
+                        IR::Compress(Compress { input: input.modify_box(modifier), min_size })
                     }
                     IR::Discriminate(discriminator, expr) => {
                         IR::Discriminate(discriminator, expr.modify_box(modifier))
@@ -199,9 +829,20 @@ impl IR {
                             .collect(),
                     ),
                     IR::Service(sdl) => IR::Service(sdl),
-                    IR::Merge(vec) => {
-                        IR::Merge(vec.into_iter().map(|ir| ir.modify(modifier)).collect())
-                    }
+                    IR::Merge(Merge { irs, tolerate_partial }) => IR::Merge(Merge {
+                        irs: irs.into_iter().map(|ir| ir.modify(modifier)).collect(),
+                        tolerate_partial,
+                    }),
+                    IR::Fallback(Fallback { irs, on_null }) => IR::Fallback(Fallback {
+                        irs: irs.into_iter().map(|ir| ir.modify(modifier)).collect(),
+                        on_null,
+                    }),
+                    IR::Join(Join { left, right, on, how }) => IR::Join(Join {
+                        left: left.modify_box(modifier),
+                        right: right.modify_box(modifier),
+                        on,
+                        how,
+                    }),
                 }
             }
         }
@@ -210,11 +851,411 @@ impl IR {
 
 impl<'a, Ctx: ResolverContextLike + Sync> CacheKey<EvalContext<'a, Ctx>> for IO {
     fn cache_key(&self, ctx: &EvalContext<'a, Ctx>) -> Option<IoId> {
+        if self.is_skipped(ctx) {
+            return None;
+        }
+
         match self {
             IO::Http { req_template, .. } => req_template.cache_key(ctx),
+            IO::Grpc { req_template, .. } if req_template.field_mask.is_some() => req_template
+                .render_with_selection(ctx, ctx.field().as_ref())
+                .ok()
+                .map(|rendered| rendered.io_id()),
             IO::Grpc { req_template, .. } => req_template.cache_key(ctx),
             IO::GraphQL { req_template, .. } => req_template.cache_key(ctx),
             IO::Js { .. } => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use prost_reflect::prost_types::{
+        DescriptorProto, FileDescriptorProto, FileDescriptorSet, MethodDescriptorProto,
+        ServiceDescriptorProto,
+    };
+    use tailcall_valid::Validator;
+
+    use super::*;
+    use crate::core::blueprint::{Basic, GrpcMethod, Provider};
+    use crate::core::config::GraphQLOperationType;
+    use crate::core::grpc::protobuf::{ProtobufOperation, ProtobufSet};
+    use crate::core::ir::{Pattern, RelatedFields};
+
+    /// The operation of the generated gRPC IOs, `gen.Service.Call`.
+    fn operation() -> ProtobufOperation {
+        let file = FileDescriptorProto {
+            name: Some("gen.proto".to_string()),
+            package: Some("gen".to_string()),
+            message_type: vec![DescriptorProto {
+                name: Some("Empty".to_string()),
+                ..Default::default()
+            }],
+            service: vec![ServiceDescriptorProto {
+                name: Some("Service".to_string()),
+                method: vec![MethodDescriptorProto {
+                    name: Some("Call".to_string()),
+                    input_type: Some(".gen.Empty".to_string()),
+                    output_type: Some(".gen.Empty".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            syntax: Some("proto3".to_string()),
+            ..Default::default()
+        };
+        let method = GrpcMethod::try_from("gen.Service.Call").unwrap();
+
+        ProtobufSet::from_proto_file(FileDescriptorSet { file: vec![file] })
+            .unwrap()
+            .find_service(&method)
+            .unwrap()
+            .find_operation(&method)
+            .unwrap()
+    }
+
+    /// Generates arbitrary IR trees from a seed, every variant of [IR] and
+    /// every kind of node of [IO] can be generated.
+    struct Gen {
+        state: u64,
+        ios: usize,
+        operation: ProtobufOperation,
+    }
+
+    impl Gen {
+        fn new(seed: u64) -> Self {
+            Self {
+                state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+                ios: 0,
+                operation: operation(),
+            }
+        }
+
+        /// xorshift64
+        fn below(&mut self, n: u64) -> u64 {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+            self.state % n
+        }
+
+        fn child(&mut self, depth: usize) -> Box<IR> {
+            Box::new(self.ir(depth - 1))
+        }
+
+        fn auth(&self) -> Auth {
+            Auth::Provider(Provider::Basic(Basic { htpasswd: String::new() }))
+        }
+
+        /// The IOs are named uniquely, by the name of the script or the url
+        fn io(&mut self) -> IO {
+            self.ios += 1;
+            let name = format!("io{}", self.ios);
+            let url = format!("http://localhost/{name}");
+            match self.below(4) {
+                0 => IO::Js { name },
+                1 => IO::Grpc {
+                    req_template: grpc::RequestTemplate {
+                        url: Mustache::parse(&url),
+                        headers: vec![],
+                        body: None,
+                        operation: self.operation.clone(),
+                        operation_type: GraphQLOperationType::Query,
+                        field_mask: None,
+                        dynamic_method: None,
+                        routes: None,
+                        priority: Default::default(),
+                        retry: None,
+                        transcoding: None,
+                    },
+                    group_by: None,
+                    dl_id: None,
+                    dedupe: false,
+                    hook: None,
+                },
+                2 => IO::GraphQL {
+                    req_template: graphql::RequestTemplate::new(
+                        url,
+                        &GraphQLOperationType::Query,
+                        &name,
+                        None,
+                        vec![],
+                        RelatedFields::default(),
+                    )
+                    .unwrap(),
+                    field_name: name,
+                    batch: false,
+                    batch_style: GraphQLBatchStyle::default(),
+                    batch_name: None,
+                    dl_id: None,
+                    dedupe: false,
+                    error_policy: ErrorPolicy::default(),
+                },
+                _ => IO::Http {
+                    req_template: http::RequestTemplate::new(&url).unwrap(),
+                    group_by: None,
+                    dl_id: None,
+                    is_list: false,
+                    dedupe: false,
+                    hook: None,
+                },
+            }
+        }
+
+        fn ir(&mut self, depth: usize) -> IR {
+            let variants = if depth == 0 { 5 } else { 20 };
+            match self.below(variants) {
+                0 => IR::Dynamic(DynamicValue::Value(Value::from(self.below(100)))),
+                1 => IR::IO(self.io()),
+                2 => IR::Cache(Cache {
+                    max_age: NonZeroU64::new(1 + self.below(1000)).unwrap(),
+                    tags: vec![Mustache::parse("user:{{.value.id}}")],
+                    namespace: None,
+                    serve_stale_on_error: self.below(2) == 0,
+                    vary: vec![],
+                    negative: None,
+                    key_args: None,
+                    size: None,
+                    adaptive: None,
+                    io: Box::new(self.io()),
+                }),
+                3 => IR::ContextPath(vec!["value".to_string(), "id".to_string()]),
+                4 => IR::Service("type Query { id: Int }".to_string()),
+                5 => IR::Path(self.child(depth), vec!["data".to_string()]),
+                6 => IR::Protect(Protect {
+                    auth: self.auth(),
+                    authed: self.child(depth),
+                    anon: (self.below(2) == 0).then(|| self.child(depth)),
+                }),
+                7 => IR::Mask(Mask {
+                    input: self.child(depth),
+                    auth: self.auth(),
+                    scope: "admin".to_string(),
+                    fields: vec!["email".to_string()],
+                }),
+                8 => IR::Compress(Compress { input: self.child(depth), min_size: 10 }),
+                9 => IR::Map(Map {
+                    input: self.child(depth),
+                    map: HashMap::from([("A".to_string(), "ACTIVE".to_string())]),
+                    on_missing: MapMissing::Null,
+                }),
+                10 => IR::Filter(Filter {
+                    input: self.child(depth),
+                    predicate: Predicate::parse("active == true").unwrap(),
+                }),
+                11 => IR::Pipe(self.child(depth), self.child(depth)),
+                12 => IR::Merge(Merge {
+                    irs: (0..self.below(4)).map(|_| self.ir(depth - 1)).collect(),
+                    tolerate_partial: self.below(2) == 0,
+                }),
+                13 => IR::Join(Join {
+                    left: self.child(depth),
+                    right: self.child(depth),
+                    on: (vec!["id".to_string()], vec!["userId".to_string()]),
+                    how: JoinKind::Left,
+                }),
+                14 => IR::Discriminate(
+                    Discriminator::new(
+                        "Node".to_string(),
+                        BTreeSet::from(["User".to_string(), "Post".to_string()]),
+                        Some("type".to_string()),
+                    )
+                    .to_result()
+                    .unwrap(),
+                    self.child(depth),
+                ),
+                15 => IR::Entity(
+                    (0..self.below(4))
+                        .map(|i| (format!("Entity{i}"), self.ir(depth - 1)))
+                        .collect(),
+                ),
+                16 => IR::Fallback(Fallback {
+                    irs: (0..self.below(4)).map(|_| self.ir(depth - 1)).collect(),
+                    on_null: self.below(2) == 0,
+                }),
+                17 => IR::Compact(Compact {
+                    input: self.child(depth),
+                    empty_objects: self.below(2) == 0,
+                    required: vec!["id".to_string()],
+                }),
+                18 => IR::Validate(Validate {
+                    input: self.child(depth),
+                    constraints: vec![Constraint {
+                        arg: "limit".to_string(),
+                        min: Some(1),
+                        pattern: Some(Pattern::new("^\\d+$").unwrap()),
+                        ..Default::default()
+                    }],
+                }),
+                _ => IR::IO(self.io()),
+            }
+        }
+    }
+
+    /// Index of the variant, matched exhaustively so that a new variant has
+    /// to be generated and traversed by the tests below.
+    fn variant(ir: &IR) -> usize {
+        match ir {
+            IR::Dynamic(_) => 0,
+            IR::IO(_) => 1,
+            IR::Cache(_) => 2,
+            IR::ContextPath(_) => 3,
+            IR::Service(_) => 4,
+            IR::Path(_, _) => 5,
+            IR::Protect(_) => 6,
+            IR::Mask(_) => 7,
+            IR::Compress(_) => 8,
+            IR::Map(_) => 9,
+            IR::Filter(_) => 10,
+            IR::Pipe(_, _) => 11,
+            IR::Merge(_) => 12,
+            IR::Join(_) => 13,
+            IR::Discriminate(_, _) => 14,
+            IR::Entity(_) => 15,
+            IR::Fallback(_) => 16,
+            IR::Compact(_) => 17,
+            IR::Validate(_) => 18,
+        }
+    }
+
+    /// The nested IRs, independently of the traversals of the IR.
+    fn children(ir: &IR) -> Vec<&IR> {
+        match ir {
+            IR::Dynamic(_) | IR::IO(_) | IR::Cache(_) | IR::ContextPath(_) | IR::Service(_) => {
+                vec![]
+            }
+            IR::Path(ir, _) | IR::Discriminate(_, ir) => vec![&**ir],
+            IR::Protect(protect) => std::iter::once(&protect.authed)
+                .chain(protect.anon.as_ref())
+                .map(|ir| &**ir)
+                .collect(),
+            IR::Mask(Mask { input, .. })
+            | IR::Compress(Compress { input, .. })
+            | IR::Map(Map { input, .. })
+            | IR::Filter(Filter { input, .. })
+            | IR::Compact(Compact { input, .. })
+            | IR::Validate(Validate { input, .. }) => vec![&**input],
+            IR::Pipe(first, second) => vec![&**first, &**second],
+            IR::Merge(Merge { irs, .. }) | IR::Fallback(Fallback { irs, .. }) => {
+                irs.iter().collect()
+            }
+            IR::Join(join) => vec![&*join.left, &*join.right],
+            IR::Entity(entities) => entities.values().collect(),
+        }
+    }
+
+    /// Index of the kind of the IO, matched exhaustively like [variant].
+    fn io_kind(io: &IO) -> usize {
+        match io {
+            IO::Http { .. } => 0,
+            IO::GraphQL { .. } => 1,
+            IO::Grpc { .. } => 2,
+            IO::Js { .. } => 3,
+        }
+    }
+
+    fn io_name(io: &IO) -> String {
+        match io {
+            IO::Http { req_template, .. } => req_template.root_url.to_string(),
+            IO::Grpc { req_template, .. } => req_template.url.to_string(),
+            IO::GraphQL { req_template, .. } => req_template.url.clone(),
+            IO::Js { name } => name.clone(),
+        }
+    }
+
+    /// The names of the IOs of the tree, along with the number of the nodes
+    /// that `modify` visits, the IO of a cache is a node of its own.
+    fn walk(ir: &IR, ios: &mut BTreeSet<String>) -> usize {
+        let nodes = match ir {
+            IR::IO(io) => {
+                ios.insert(io_name(io));
+                1
+            }
+            IR::Cache(cache) => {
+                ios.insert(io_name(&cache.io));
+                2
+            }
+            _ => 1,
+        };
+
+        nodes
+            + children(ir)
+                .into_iter()
+                .map(|ir| walk(ir, ios))
+                .sum::<usize>()
+    }
+
+    fn to_json(ir: &IR) -> serde_json::Value {
+        serde_json::to_value(ir).unwrap()
+    }
+
+    const SEEDS: u64 = 500;
+
+    #[test]
+    fn test_generates_all_variants() {
+        let mut seen = BTreeSet::new();
+        let mut ios = BTreeSet::new();
+        for seed in 0..SEEDS {
+            let mut stack = vec![Gen::new(seed).ir(4)];
+            while let Some(ir) = stack.pop() {
+                seen.insert(variant(&ir));
+                match &ir {
+                    IR::IO(io) => ios.insert(io_kind(io)),
+                    IR::Cache(cache) => ios.insert(io_kind(&cache.io)),
+                    _ => false,
+                };
+                stack.extend(children(&ir).into_iter().cloned());
+            }
+        }
+
+        assert_eq!(seen, (0..19).collect());
+        assert_eq!(ios, (0..4).collect());
+    }
+
+    #[test]
+    fn test_modify_identity() {
+        for seed in 0..SEEDS {
+            let ir = Gen::new(seed).ir(4);
+            let mut expected = BTreeSet::new();
+            let nodes = walk(&ir, &mut expected);
+
+            let mut visited = 0;
+            let actual = ir.clone().modify(&mut |_| {
+                visited += 1;
+                None
+            });
+
+            assert_eq!(to_json(&actual), to_json(&ir), "seed {seed}");
+            assert_eq!(visited, nodes, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_modify_io_visits_all_ios() {
+        for seed in 0..SEEDS {
+            let mut ir = Gen::new(seed).ir(4);
+            let mut expected = BTreeSet::new();
+            walk(&ir, &mut expected);
+
+            let mut visited = vec![];
+            ir.modify_io(&mut |io| {
+                visited.push(io_name(io));
+                *io = IO::Js { name: format!("{}!", io_name(io)) };
+            });
+
+            // every IO is visited exactly once
+            assert_eq!(visited.len(), expected.len(), "seed {seed}");
+            assert_eq!(visited.into_iter().collect::<BTreeSet<_>>(), expected);
+
+            let mut modified = BTreeSet::new();
+            walk(&ir, &mut modified);
+            let expected = expected
+                .into_iter()
+                .map(|name| format!("{name}!"))
+                .collect();
+            assert_eq!(modified, expected, "seed {seed}");
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::iter::Peekable;
+use std::str::Chars;
+
+use async_graphql_value::ConstValue;
+use serde::{Deserialize, Serialize};
+
+/// Boolean expression that is evaluated against the elements of a list with
+/// `filter`, eg. `active == true && age >= 18`.
+///
+/// The operands are either literals (`true`, `false`, `null`, numbers and
+/// quoted strings) or paths to the fields of the element. Fields that are
+/// missing from the element are `null`. An operand without a comparison is
+/// tested for truthiness, `null`, `false`, `0` and `""` are falsy.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Predicate {
+    Not(Box<Predicate>),
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+    Compare(Operand, Operator, Operand),
+    Truthy(Operand),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Operand {
+    Path(Vec<String>),
+    Literal(ConstValue),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Operator {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Predicate {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, position: 0 };
+        let predicate = parser.or()?;
+
+        match parser.next() {
+            None => Ok(predicate),
+            Some(token) => Err(format!("Unexpected {} in `{}`", token, input)),
+        }
+    }
+
+    /// Tests the element against the predicate.
+    pub fn test(&self, value: &ConstValue) -> bool {
+        match self {
+            Predicate::Not(predicate) => !predicate.test(value),
+            Predicate::And(left, right) => left.test(value) && right.test(value),
+            Predicate::Or(left, right) => left.test(value) || right.test(value),
+            Predicate::Truthy(operand) => is_truthy(operand.resolve(value)),
+            Predicate::Compare(left, operator, right) => {
+                let left = left.resolve(value);
+                let right = right.resolve(value);
+
+                match operator {
+                    Operator::Eq => is_equal(left, right),
+                    Operator::Ne => !is_equal(left, right),
+                    Operator::Gt => compare(left, right) == Some(Ordering::Greater),
+                    Operator::Ge => matches!(
+                        compare(left, right),
+                        Some(Ordering::Greater | Ordering::Equal)
+                    ),
+                    Operator::Lt => compare(left, right) == Some(Ordering::Less),
+                    Operator::Le => {
+                        matches!(compare(left, right), Some(Ordering::Less | Ordering::Equal))
+                    }
+                }
+            }
+        }
+    }
+}
+
+static NULL: ConstValue = ConstValue::Null;
+
+impl Operand {
+    fn resolve<'a>(&'a self, value: &'a ConstValue) -> &'a ConstValue {
+        match self {
+            Operand::Literal(literal) => literal,
+            Operand::Path(path) => path
+                .iter()
+                .try_fold(value, |value, key| match value {
+                    ConstValue::Object(map) => map.get(key.as_str()),
+                    _ => None,
+                })
+                .unwrap_or(&NULL),
+        }
+    }
+}
+
+/// `null`, `false`, `0` and `""` are falsy, any other value is truthy.
+pub fn is_truthy(value: &ConstValue) -> bool {
+    match value {
+        ConstValue::Null => false,
+        ConstValue::Boolean(value) => *value,
+        ConstValue::Number(number) => number.as_f64().is_some_and(|number| number != 0.0),
+        ConstValue::String(value) => !value.is_empty(),
+        _ => true,
+    }
+}
+
+fn is_equal(left: &ConstValue, right: &ConstValue) -> bool {
+    match (left, right) {
+        (ConstValue::Number(left), ConstValue::Number(right)) => left.as_f64() == right.as_f64(),
+        (left, right) => left == right,
+    }
+}
+
+/// Only numbers and strings are ordered, comparing anything else is false.
+fn compare(left: &ConstValue, right: &ConstValue) -> Option<Ordering> {
+    match (left, right) {
+        (ConstValue::Number(left), ConstValue::Number(right)) => {
+            left.as_f64()?.partial_cmp(&right.as_f64()?)
+        }
+        (ConstValue::String(left), ConstValue::String(right)) => Some(left.cmp(right)),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Path(Vec<String>),
+    Literal(ConstValue),
+    Operator(Operator),
+    And,
+    Or,
+    Not,
+    LeftParen,
+    RightParen,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Path(path) => write!(f, "`{}`", path.join(".")),
+            Token::Literal(value) => write!(f, "`{}`", value),
+            Token::Operator(_) => write!(f, "operator"),
+            Token::And => write!(f, "`&&`"),
+            Token::Or => write!(f, "`||`"),
+            Token::Not => write!(f, "`!`"),
+            Token::LeftParen => write!(f, "`(`"),
+            Token::RightParen => write!(f, "`)`"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&char) = chars.peek() {
+        let token = match char {
+            char if char.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '(' => {
+                chars.next();
+                Token::LeftParen
+            }
+            ')' => {
+                chars.next();
+                Token::RightParen
+            }
+            '&' | '|' => {
+                chars.next();
+                if chars.next() != Some(char) {
+                    return Err(format!("Expected `{char}{char}` in `{input}`"));
+                }
+                if char == '&' {
+                    Token::And
+                } else {
+                    Token::Or
+                }
+            }
+            '!' | '=' | '<' | '>' => {
+                chars.next();
+                let is_eq = chars.next_if_eq(&'=').is_some();
+                match (char, is_eq) {
+                    ('!', false) => Token::Not,
+                    ('!', true) => Token::Operator(Operator::Ne),
+                    ('=', true) => Token::Operator(Operator::Eq),
+                    ('<', false) => Token::Operator(Operator::Lt),
+                    ('<', true) => Token::Operator(Operator::Le),
+                    ('>', false) => Token::Operator(Operator::Gt),
+                    ('>', true) => Token::Operator(Operator::Ge),
+                    _ => return Err(format!("Expected `==` in `{input}`")),
+                }
+            }
+            '"' | '\'' => Token::Literal(ConstValue::String(string(&mut chars, input)?)),
+            char if char.is_ascii_digit() || char == '-' => number(&mut chars, input)?,
+            _ => path(&mut chars, input)?,
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn string(chars: &mut Peekable<Chars>, input: &str) -> Result<String, String> {
+    let quote = chars.next();
+    let mut string = String::new();
+
+    loop {
+        match chars.next() {
+            Some('\\') => string.extend(chars.next()),
+            Some(char) if Some(char) == quote => return Ok(string),
+            Some(char) => string.push(char),
+            None => return Err(format!("Unterminated string in `{input}`")),
+        }
+    }
+}
+
+fn number(chars: &mut Peekable<Chars>, input: &str) -> Result<Token, String> {
+    let mut number = String::new();
+    while let Some(char) = chars.next_if(|char| char.is_ascii_digit() || ".-eE+".contains(*char)) {
+        number.push(char);
+    }
+
+    serde_json::from_str::<serde_json::Number>(&number)
+        .map(|number| Token::Literal(ConstValue::Number(number)))
+        .map_err(|_| format!("Invalid number `{number}` in `{input}`"))
+}
+
+fn path(chars: &mut Peekable<Chars>, input: &str) -> Result<Token, String> {
+    let mut path = String::new();
+    while let Some(char) = chars.next_if(|char| char.is_alphanumeric() || "_.".contains(*char)) {
+        path.push(char);
+    }
+
+    let token = match path.as_str() {
+        "" => {
+            return Err(format!(
+                "Unexpected `{}` in `{input}`",
+                chars.next().unwrap()
+            ))
+        }
+        "true" => Token::Literal(ConstValue::Boolean(true)),
+        "false" => Token::Literal(ConstValue::Boolean(false)),
+        "null" => Token::Literal(ConstValue::Null),
+        path => {
+            // paths can be written relative to the element as in the mustache
+            // templates, eg. `.user.name`
+            let keys: Vec<_> = path.trim_start_matches('.').split('.').collect();
+            if keys.iter().any(|key| key.is_empty()) {
+                return Err(format!("Invalid path `{path}` in `{input}`"));
+            }
+            Token::Path(keys.into_iter().map(String::from).collect())
+        }
+    };
+
+    Ok(token)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Predicate, String> {
+        let mut predicate = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            predicate = Predicate::Or(Box::new(predicate), Box::new(self.and()?));
+        }
+
+        Ok(predicate)
+    }
+
+    fn and(&mut self) -> Result<Predicate, String> {
+        let mut predicate = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            predicate = Predicate::And(Box::new(predicate), Box::new(self.unary()?));
+        }
+
+        Ok(predicate)
+    }
+
+    fn unary(&mut self) -> Result<Predicate, String> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.next();
+                Ok(Predicate::Not(Box::new(self.unary()?)))
+            }
+            Some(Token::LeftParen) => {
+                self.next();
+                let predicate = self.or()?;
+                match self.next() {
+                    Some(Token::RightParen) => Ok(predicate),
+                    _ => Err("Expected `)`".to_string()),
+                }
+            }
+            _ => self.comparison(),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Predicate, String> {
+        let left = self.operand()?;
+        match self.peek() {
+            Some(Token::Operator(operator)) => {
+                let operator = *operator;
+                self.next();
+                Ok(Predicate::Compare(left, operator, self.operand()?))
+            }
+            _ => Ok(Predicate::Truthy(left)),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        match self.next() {
+            Some(Token::Path(path)) => Ok(Operand::Path(path)),
+            Some(Token::Literal(value)) => Ok(Operand::Literal(value)),
+            Some(token) => Err(format!("Expected a field or a value, found {}", token)),
+            None => Err("Unexpected end of the expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn test(predicate: &str, value: serde_json::Value) -> bool {
+        let value = ConstValue::from_json(value).unwrap();
+        Predicate::parse(predicate).unwrap().test(&value)
+    }
+
+    #[test]
+    fn test_compare() {
+        let user = json!({"name": "foo", "age": 20, "active": true, "address": {"city": "bar"}});
+
+        assert!(test("active == true", user.clone()));
+        assert!(test("age >= 18 && name != 'bar'", user.clone()));
+        assert!(test(".address.city == \"bar\"", user.clone()));
+        assert!(test("age < 18 || (active && !deleted)", user.clone()));
+        assert!(!test("age > 20.5", user.clone()));
+        assert!(!test("name > 5", user));
+    }
+
+    #[test]
+    fn test_missing_field_is_falsy() {
+        let user = json!({"name": "foo"});
+
+        assert!(!test("active", user.clone()));
+        assert!(!test("active == true", user.clone()));
+        assert!(!test("address.city == 'bar'", user.clone()));
+        assert!(!test("age >= 18", user.clone()));
+        assert!(test("!active", user));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(Predicate::parse("active ==").is_err());
+        assert!(Predicate::parse("active = true").is_err());
+        assert!(Predicate::parse("(active").is_err());
+        assert!(Predicate::parse("name == 'foo").is_err());
+        assert!(Predicate::parse("").is_err());
+    }
+}
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use async_graphql::parser::types::{ConstDirective, OperationType};
-use async_graphql::{Name, ServerError, Value};
+use async_graphql::{Name, PathSegment, QueryPathSegment, ServerError, Value};
 use async_graphql_value::ConstValue;
 use indexmap::IndexMap;
 
@@ -10,7 +10,12 @@ pub trait ResolverContextLike: Clone {
     fn args(&self) -> Option<&IndexMap<Name, Value>>;
     fn field(&self) -> Option<SelectionField>;
     fn is_query(&self) -> bool;
+    /// Name of the operation, `None` when it's anonymous.
+    fn operation_name(&self) -> Option<&str>;
+    fn operation_type(&self) -> Option<OperationType>;
     fn add_error(&self, error: ServerError);
+    /// Path of the field in the response, eg. `users.0.name`.
+    fn response_path(&self) -> Vec<PathSegment>;
 }
 
 #[derive(Clone)]
@@ -33,7 +38,19 @@ impl ResolverContextLike for EmptyResolverContext {
         false
     }
 
+    fn operation_name(&self) -> Option<&str> {
+        None
+    }
+
+    fn operation_type(&self) -> Option<OperationType> {
+        None
+    }
+
     fn add_error(&self, _: ServerError) {}
+
+    fn response_path(&self) -> Vec<PathSegment> {
+        vec![]
+    }
 }
 
 #[derive(Clone)]
@@ -64,14 +81,38 @@ impl ResolverContextLike for ResolverContext<'_> {
         self.inner.ctx.query_env.operation.node.ty == OperationType::Query
     }
 
+    fn operation_name(&self) -> Option<&str> {
+        self.inner.ctx.query_env.operation_name.as_deref()
+    }
+
+    fn operation_type(&self) -> Option<OperationType> {
+        Some(self.inner.ctx.query_env.operation.node.ty)
+    }
+
     fn add_error(&self, error: ServerError) {
         self.inner.ctx.add_error(error)
     }
+
+    fn response_path(&self) -> Vec<PathSegment> {
+        let mut path = vec![];
+        let mut node = self.inner.ctx.path_node.as_ref();
+        while let Some(current) = node {
+            path.push(match current.segment {
+                QueryPathSegment::Name(name) => PathSegment::Field(name.to_string()),
+                QueryPathSegment::Index(index) => PathSegment::Index(index),
+            });
+            node = current.parent;
+        }
+        path.reverse();
+
+        path
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct SelectionField {
     name: String,
+    field_name: String,
     args: Vec<(String, String)>,
     directives: Option<Vec<ConstDirective>>,
     selection_set: Vec<SelectionField>,
@@ -90,8 +131,26 @@ impl<'a> From<&'a crate::core::jit::Field<ConstValue>> for SelectionField {
 }
 
 impl SelectionField {
+    #[cfg(test)]
+    pub fn new(name: &str, selection_set: Vec<SelectionField>) -> Self {
+        Self {
+            name: name.to_string(),
+            field_name: name.to_string(),
+            args: vec![],
+            directives: None,
+            selection_set,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_alias(mut self, alias: &str) -> Self {
+        self.name = alias.to_string();
+        self
+    }
+
     fn from_jit_field(field: &crate::core::jit::Field<ConstValue>) -> SelectionField {
         let name = field.output_name.to_string();
+        let field_name = field.name.to_string();
         let type_name = field.type_of.name();
         let selection_set = field
             .iter()
@@ -109,6 +168,7 @@ impl SelectionField {
 
         SelectionField {
             name,
+            field_name,
             args,
             directives: if field.directives.is_empty() {
                 None
@@ -144,6 +204,7 @@ impl SelectionField {
             .collect();
 
         Self {
+            field_name: name.clone(),
             name,
             args,
             selection_set,
@@ -163,6 +224,12 @@ impl SelectionField {
         &self.name
     }
 
+    /// Name of the field in the schema, unlike the `name` that is the alias
+    /// of the field when it's aliased in the query.
+    pub fn field_name(&self) -> &str {
+        &self.field_name
+    }
+
     /// Returns an iterator over the `selection_set` that yields
     /// `SelectionField` instances.
     pub fn selection_set(&self) -> std::slice::Iter<SelectionField> {
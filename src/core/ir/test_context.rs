@@ -0,0 +1,182 @@
+use std::sync::{Arc, Mutex};
+
+use async_graphql::parser::types::OperationType;
+use async_graphql::{Name, PathSegment, ServerError, Value};
+use http::header::HeaderMap;
+use indexmap::IndexMap;
+
+use super::model::IR;
+use super::{Error, EvalContext, ResolverContextLike, SelectionField};
+use crate::core::http::RequestContext;
+use crate::core::runtime::TargetRuntime;
+
+/// A resolver context that isn't backed by a GraphQL request, to evaluate an
+/// [IR] in isolation with [IR::eval_with], eg. to test the logic of a field.
+/// The args, the headers and the parent value are stubbed, and the errors
+/// that the IR adds are collected.
+#[derive(Clone)]
+pub struct TestContext {
+    runtime: TargetRuntime,
+    value: Option<Value>,
+    args: IndexMap<Name, Value>,
+    headers: HeaderMap,
+    is_query: bool,
+    errors: Arc<Mutex<Vec<ServerError>>>,
+}
+
+impl TestContext {
+    /// The IOs of the IR are made with the `runtime`, that can be a mock.
+    pub fn new(runtime: TargetRuntime) -> Self {
+        Self {
+            runtime,
+            value: None,
+            args: IndexMap::new(),
+            headers: HeaderMap::new(),
+            is_query: true,
+            errors: Arc::new(Mutex::new(vec![])),
+        }
+    }
+
+    pub fn with_value(self, value: Value) -> Self {
+        Self { value: Some(value), ..self }
+    }
+
+    pub fn with_args(self, args: IndexMap<Name, Value>) -> Self {
+        Self { args, ..self }
+    }
+
+    /// The headers of the request that are forwarded to the upstreams.
+    pub fn with_headers(self, headers: HeaderMap) -> Self {
+        Self { headers, ..self }
+    }
+
+    /// Evaluates the IR as a field of a mutation instead of a query.
+    pub fn with_mutation(self) -> Self {
+        Self { is_query: false, ..self }
+    }
+
+    /// The errors added by the IR, eg. the errors of an upstream that were
+    /// tolerated.
+    pub fn errors(&self) -> Vec<ServerError> {
+        self.errors.lock().unwrap().clone()
+    }
+}
+
+impl ResolverContextLike for TestContext {
+    fn value(&self) -> Option<&Value> {
+        self.value.as_ref()
+    }
+
+    fn args(&self) -> Option<&IndexMap<Name, Value>> {
+        Some(&self.args)
+    }
+
+    fn field(&self) -> Option<SelectionField> {
+        None
+    }
+
+    fn is_query(&self) -> bool {
+        self.is_query
+    }
+
+    fn operation_name(&self) -> Option<&str> {
+        None
+    }
+
+    fn operation_type(&self) -> Option<OperationType> {
+        Some(if self.is_query {
+            OperationType::Query
+        } else {
+            OperationType::Mutation
+        })
+    }
+
+    fn add_error(&self, error: ServerError) {
+        self.errors.lock().unwrap().push(error);
+    }
+
+    fn response_path(&self) -> Vec<PathSegment> {
+        vec![]
+    }
+}
+
+impl IR {
+    /// Evaluates the IR without a live request, `input` is the parent value
+    /// of the field and the args and the headers are taken from the `ctx`.
+    pub async fn eval_with(&self, input: Value, ctx: TestContext) -> Result<Value, Error> {
+        let req_ctx = RequestContext::new(ctx.runtime.clone()).allowed_headers(ctx.headers.clone());
+        let ctx = ctx.with_value(input);
+        let mut eval_ctx = EvalContext::new(&req_ctx, &ctx);
+
+        self.eval(&mut eval_ctx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use http::header::HeaderValue;
+    use serde_json::json;
+
+    use super::*;
+    use crate::core::blueprint::DynamicValue;
+    use crate::core::ir::model::{Map, MapMissing};
+    use crate::core::runtime::test::init;
+
+    fn dynamic(value: serde_json::Value) -> IR {
+        IR::Dynamic(DynamicValue::try_from(&value).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_pipe() {
+        // the result of the first step is the args of the second one
+        let ir = IR::Pipe(
+            Box::new(dynamic(
+                json!({ "id": "{{.args.id}}", "name": "{{.value.name}}" }),
+            )),
+            Box::new(dynamic(json!({
+                "user": "{{.args.name}}#{{.args.id}}",
+                "tenant": "{{.headers.tenant}}",
+            }))),
+        );
+        let ctx = TestContext::new(init(None))
+            .with_args(IndexMap::from([(Name::new("id"), Value::from(1))]))
+            .with_headers(HeaderMap::from_iter([(
+                "tenant".parse().unwrap(),
+                HeaderValue::from_static("acme"),
+            )]));
+
+        let actual = ir
+            .eval_with(Value::from_json(json!({ "name": "foo" })).unwrap(), ctx)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            actual.into_json().unwrap(),
+            json!({ "user": "foo#1", "tenant": "acme" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_map() {
+        let ir = IR::Map(Map {
+            input: Box::new(dynamic(json!("{{.value.status}}"))),
+            map: HashMap::from([("A".to_string(), "ACTIVE".to_string())]),
+            on_missing: MapMissing::Error,
+        });
+        let input = |status: &str| Value::from_json(json!({ "status": status })).unwrap();
+
+        let actual = ir
+            .eval_with(input("A"), TestContext::new(init(None)))
+            .await
+            .unwrap();
+        assert_eq!(actual, Value::from("ACTIVE"));
+
+        let actual = ir.eval_with(input("B"), TestContext::new(init(None))).await;
+        assert!(
+            matches!(&actual, Err(Error::ExprEval(message)) if message == "Can't find mapped key: B."),
+            "{actual:?}"
+        );
+    }
+}
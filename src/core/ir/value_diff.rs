@@ -0,0 +1,284 @@
+use async_graphql_value::ConstValue;
+use once_cell::sync::Lazy;
+use opentelemetry::metrics::Counter;
+use opentelemetry::KeyValue;
+use serde::{Deserialize, Serialize};
+
+static CANARY_MISMATCH_COUNT: Lazy<Counter<u64>> = Lazy::new(|| {
+    let meter = opentelemetry::global::meter("canary");
+
+    meter
+        .u64_counter("canary.mismatch.count")
+        .with_description("Number of canary results that differ from the primary ones")
+        .init()
+});
+
+/// Structural comparison of the result of a canary upstream with the one of
+/// the primary upstream, to validate a migration of the backend on the sampled
+/// requests without affecting the clients. The differences are reported as the
+/// normalized paths of the values that differ, eg. `$.user.posts[1].title`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ValueDiff {
+    /// The lists that are compared as sets, ignoring the order of their
+    /// elements. The paths leave out the indexes of the lists, eg.
+    /// `["user", "tags"]` matches the tags of the user even if the user is in
+    /// a list too.
+    pub unordered: Vec<Vec<String>>,
+    /// Maximum number of the values that are compared, so that the large
+    /// results don't blow up the CPU. The comparison stops once it's reached.
+    pub max_nodes: usize,
+}
+
+/// The differences between two results.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Differences {
+    pub paths: Vec<String>,
+    /// The comparison stopped at `max_nodes`, there could be more differences
+    pub truncated: bool,
+}
+
+impl Differences {
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty() && !self.truncated
+    }
+}
+
+impl Default for ValueDiff {
+    fn default() -> Self {
+        Self { unordered: vec![], max_nodes: 10_000 }
+    }
+}
+
+impl ValueDiff {
+    pub fn diff(&self, primary: &ConstValue, canary: &ConstValue) -> Differences {
+        let mut walk = Walk {
+            diff: self,
+            budget: self.max_nodes,
+            fields: vec![],
+            path: vec![],
+            differences: Differences::default(),
+        };
+        walk.compare(primary, canary);
+
+        walk.differences
+    }
+
+    /// Compares the results and logs the paths of the differences, counting
+    /// the mismatches of the field.
+    pub fn report(&self, field: &str, primary: &ConstValue, canary: &ConstValue) -> Differences {
+        let differences = self.diff(primary, canary);
+        if !differences.is_empty() {
+            tracing::warn!(
+                "The canary result of {} differs at {}{}",
+                field,
+                differences.paths.join(", "),
+                if differences.truncated {
+                    " (truncated)"
+                } else {
+                    ""
+                }
+            );
+            CANARY_MISMATCH_COUNT.add(1, &[KeyValue::new("field", field.to_string())]);
+        }
+
+        differences
+    }
+}
+
+enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+struct Walk<'a> {
+    diff: &'a ValueDiff,
+    budget: usize,
+    /// The path without the indexes, that the unordered lists are matched by
+    fields: Vec<&'a str>,
+    path: Vec<Segment<'a>>,
+    differences: Differences,
+}
+
+impl<'a> Walk<'a> {
+    /// Takes a value from the budget, `false` once it's exhausted.
+    fn spend(&mut self, nodes: usize) -> bool {
+        match self.budget.checked_sub(nodes) {
+            Some(budget) => {
+                self.budget = budget;
+                true
+            }
+            None => {
+                self.budget = 0;
+                self.differences.truncated = true;
+                false
+            }
+        }
+    }
+
+    fn report(&mut self) {
+        let mut path = String::from("$");
+        for segment in &self.path {
+            match segment {
+                Segment::Key(key) => {
+                    path.push('.');
+                    path.push_str(key);
+                }
+                Segment::Index(index) => path.push_str(&format!("[{index}]")),
+            }
+        }
+
+        self.differences.paths.push(path);
+    }
+
+    fn is_unordered(&self) -> bool {
+        self.diff.unordered.iter().any(|path| {
+            path.iter()
+                .map(String::as_str)
+                .eq(self.fields.iter().copied())
+        })
+    }
+
+    fn compare(&mut self, primary: &'a ConstValue, canary: &'a ConstValue) {
+        if !self.spend(1) {
+            return;
+        }
+
+        match (primary, canary) {
+            (ConstValue::Object(primary), ConstValue::Object(canary)) => {
+                let keys = primary
+                    .keys()
+                    .chain(canary.keys().filter(|key| !primary.contains_key(*key)));
+                for key in keys {
+                    if self.differences.truncated {
+                        break;
+                    }
+                    self.fields.push(key.as_str());
+                    self.path.push(Segment::Key(key.as_str()));
+                    match (primary.get(key), canary.get(key)) {
+                        (Some(primary), Some(canary)) => self.compare(primary, canary),
+                        _ => self.report(),
+                    }
+                    self.path.pop();
+                    self.fields.pop();
+                }
+            }
+            (ConstValue::List(primary), ConstValue::List(canary)) if self.is_unordered() => {
+                // every element is looked up in the other list
+                if self.spend(primary.len().saturating_mul(canary.len()))
+                    && !is_permutation(primary, canary)
+                {
+                    self.report();
+                }
+            }
+            (ConstValue::List(primary), ConstValue::List(canary)) => {
+                for index in 0..primary.len().max(canary.len()) {
+                    if self.differences.truncated {
+                        break;
+                    }
+                    self.path.push(Segment::Index(index));
+                    match (primary.get(index), canary.get(index)) {
+                        (Some(primary), Some(canary)) => self.compare(primary, canary),
+                        _ => self.report(),
+                    }
+                    self.path.pop();
+                }
+            }
+            (primary, canary) if primary != canary => self.report(),
+            _ => {}
+        }
+    }
+}
+
+fn is_permutation(primary: &[ConstValue], canary: &[ConstValue]) -> bool {
+    if primary.len() != canary.len() {
+        return false;
+    }
+
+    let mut matched = vec![false; canary.len()];
+    primary.iter().all(|value| {
+        let found = (0..canary.len()).find(|&index| !matched[index] && canary[index] == *value);
+        match found {
+            Some(index) => {
+                matched[index] = true;
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn value(json: serde_json::Value) -> ConstValue {
+        ConstValue::from_json(json).unwrap()
+    }
+
+    fn user() -> serde_json::Value {
+        json!({
+            "id": 1,
+            "name": "foo",
+            "posts": [{ "id": 1, "title": "bar" }, { "id": 2, "title": "baz" }],
+            "tags": ["a", "b"],
+        })
+    }
+
+    #[test]
+    fn test_identical() {
+        let differences = ValueDiff::default().diff(&value(user()), &value(user()));
+        assert!(differences.is_empty());
+    }
+
+    #[test]
+    fn test_changed_field() {
+        let mut canary = user();
+        canary["posts"][1]["title"] = json!("qux");
+
+        let differences = ValueDiff::default().diff(&value(user()), &value(canary));
+        assert_eq!(differences.paths, vec!["$.posts[1].title"]);
+        assert!(!differences.truncated);
+    }
+
+    #[test]
+    fn test_missing_field() {
+        let mut canary = user();
+        canary.as_object_mut().unwrap().remove("name");
+        canary["email"] = json!("foo@bar.com");
+
+        let differences = ValueDiff::default().diff(&value(user()), &value(canary));
+        assert_eq!(differences.paths, vec!["$.name", "$.email"]);
+    }
+
+    #[test]
+    fn test_unordered() {
+        let mut canary = user();
+        canary["tags"] = json!(["b", "a"]);
+
+        let differences = ValueDiff::default().diff(&value(user()), &value(canary.clone()));
+        assert_eq!(differences.paths, vec!["$.tags[0]", "$.tags[1]"]);
+
+        let diff = ValueDiff {
+            unordered: vec![vec!["tags".to_string()]],
+            ..Default::default()
+        };
+        assert!(diff.diff(&value(user()), &value(canary.clone())).is_empty());
+
+        canary["tags"] = json!(["b", "c"]);
+        let differences = diff.diff(&value(user()), &value(canary));
+        assert_eq!(differences.paths, vec!["$.tags"]);
+    }
+
+    #[test]
+    fn test_max_nodes() {
+        let primary = value(json!((0..100).collect::<Vec<_>>()));
+        let canary = value(json!((1..101).collect::<Vec<_>>()));
+
+        let diff = ValueDiff { max_nodes: 11, ..Default::default() };
+        let differences = diff.diff(&primary, &canary);
+        assert_eq!(differences.paths.len(), 10);
+        assert!(differences.truncated);
+    }
+}
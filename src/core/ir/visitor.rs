@@ -0,0 +1,172 @@
+use std::collections::BTreeSet;
+
+use super::model::{Cache, Fallback, Merge, IO, IR};
+use crate::core::blueprint::Auth;
+
+/// Read-only traversal over the [IR] tree.
+///
+/// Unlike [IR::modify], visiting doesn't clone or rebuild the tree, which makes
+/// it a better fit for analysis passes. Every method has an empty default
+/// implementation so a visitor only needs to implement the hooks it is
+/// interested in.
+pub trait IrVisitor {
+    /// Called for every node before its children are visited.
+    fn visit_ir(&mut self, _ir: &IR) {}
+
+    /// Called for every IO node, including the ones wrapped in a [Cache].
+    fn visit_io(&mut self, _io: &IO) {}
+
+    /// Called for every cache node, before the wrapped IO is visited.
+    fn visit_cache(&mut self, _cache: &Cache) {}
+
+    /// Called for every protected node, before the protected IR is visited.
+    fn visit_protect(&mut self, _auth: &Auth) {}
+}
+
+impl IR {
+    /// Walks the IR tree depth-first by reference, calling the matching hooks
+    /// of the visitor on each node.
+    pub fn accept<V: IrVisitor + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_ir(self);
+        match self {
+            IR::IO(io) => visitor.visit_io(io),
+            IR::Cache(cache) => {
+                visitor.visit_cache(cache);
+                visitor.visit_io(&cache.io);
+            }
+            IR::Protect(protect) => {
+                visitor.visit_protect(&protect.auth);
+                protect.authed.accept(visitor);
+                if let Some(anon) = &protect.anon {
+                    anon.accept(visitor);
+                }
+            }
+            IR::Discriminate(_, ir) | IR::Path(ir, _) => ir.accept(visitor),
+            IR::Map(map) => map.input.accept(visitor),
+            IR::Filter(filter) => filter.input.accept(visitor),
+            IR::Compact(compact) => compact.input.accept(visitor),
+            IR::Validate(validate) => validate.input.accept(visitor),
+            IR::Mask(mask) => mask.input.accept(visitor),
+            IR::Compress(compress) => compress.input.accept(visitor),
+            IR::Pipe(first, second) => {
+                first.accept(visitor);
+                second.accept(visitor);
+            }
+            IR::Merge(Merge { irs, .. }) | IR::Fallback(Fallback { irs, .. }) => {
+                for ir in irs {
+                    ir.accept(visitor);
+                }
+            }
+            IR::Join(join) => {
+                join.left.accept(visitor);
+                join.right.accept(visitor);
+            }
+            IR::Entity(map) => {
+                for ir in map.values() {
+                    ir.accept(visitor);
+                }
+            }
+            IR::Dynamic(_) | IR::ContextPath(_) | IR::Service(_) => {}
+        }
+    }
+}
+
+/// Collects all the distinct upstream hosts that are called from an IR tree.
+///
+/// Urls whose host is only known at runtime (eg. `http://{{.env.HOST}}`) are
+/// skipped.
+#[derive(Debug, Default)]
+pub struct UpstreamHosts {
+    hosts: BTreeSet<String>,
+}
+
+impl UpstreamHosts {
+    pub fn collect(ir: &IR) -> BTreeSet<String> {
+        let mut visitor = Self::default();
+        ir.accept(&mut visitor);
+        visitor.hosts
+    }
+
+    fn insert(&mut self, url: &str) {
+        let Ok(url) = url::Url::parse(url) else {
+            return;
+        };
+
+        if let Some(host) = url.host_str().filter(|host| !host.contains('{')) {
+            self.hosts.insert(host.to_string());
+        }
+    }
+}
+
+impl IrVisitor for UpstreamHosts {
+    fn visit_io(&mut self, io: &IO) {
+        match io {
+            IO::Http { req_template, .. } => self.insert(&req_template.root_url.to_string()),
+            IO::GraphQL { req_template, .. } => self.insert(&req_template.url),
+            IO::Grpc { req_template, .. } => self.insert(&req_template.url.to_string()),
+            IO::Js { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::num::NonZeroU64;
+
+    use super::*;
+    use crate::core::http::RequestTemplate;
+    use crate::core::ir::model::{CacheOptions, Merge};
+
+    fn http(url: &str) -> IR {
+        IR::IO(IO::Http {
+            req_template: RequestTemplate::new(url).unwrap(),
+            group_by: None,
+            dl_id: None,
+            is_list: false,
+            dedupe: false,
+            hook: None,
+        })
+    }
+
+    #[test]
+    fn test_upstream_hosts() {
+        let ir = IR::Merge(Merge::new(vec![
+            http("http://foo.com/users"),
+            Cache::wrap(
+                NonZeroU64::new(100).unwrap(),
+                CacheOptions::default(),
+                http("http://bar.com/posts").pipe(http("http://foo.com/posts")),
+            ),
+            http("http://{{.env.HOST}}/users"),
+        ]));
+
+        let actual = UpstreamHosts::collect(&ir);
+        let expected = BTreeSet::from(["bar.com".to_string(), "foo.com".to_string()]);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_visit_cache() {
+        #[derive(Default)]
+        struct CacheTtls(Vec<u64>);
+
+        impl IrVisitor for CacheTtls {
+            fn visit_cache(&mut self, cache: &Cache) {
+                self.0.push(cache.max_age.get());
+            }
+        }
+
+        let ir = Cache::wrap(
+            NonZeroU64::new(10).unwrap(),
+            CacheOptions::default(),
+            IR::Path(Box::new(http("http://foo.com/users")), vec!["data".into()]),
+        );
+
+        let mut visitor = CacheTtls::default();
+        ir.accept(&mut visitor);
+
+        assert_eq!(visitor.0, vec![10]);
+    }
+}
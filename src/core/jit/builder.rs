@@ -69,6 +69,8 @@ pub struct Builder<'a> {
     pub arg_id: Counter<usize>,
     pub field_id: Counter<usize>,
     pub document: &'a ExecutableDocument,
+    pub max_depth: Option<usize>,
+    pub max_fields: Option<usize>,
 }
 
 // TODO: make generic over Value (Input) type
@@ -80,6 +82,8 @@ impl<'a> Builder<'a> {
             index,
             arg_id: Counter::default(),
             field_id: Counter::default(),
+            max_depth: blueprint.server.max_query_depth,
+            max_fields: blueprint.server.max_query_fields,
         }
     }
 
@@ -285,6 +289,39 @@ impl<'a> Builder<'a> {
 
         fields
     }
+
+    /// Rejects the operations that select the fields deeper or more than the
+    /// limits of the server, before any of their IO is executed. The
+    /// introspection fields aren't a part of the plan, so the naturally deep
+    /// introspection queries aren't limited.
+    fn check_limits(&self, fields: &[Field<Value>]) -> Result<(), BuildError> {
+        fn measure(
+            fields: &[Field<Value>],
+            depth: usize,
+            max_depth: &mut usize,
+            count: &mut usize,
+        ) {
+            for field in fields {
+                *max_depth = (*max_depth).max(depth);
+                *count += 1;
+                measure(&field.selection, depth + 1, max_depth, count);
+            }
+        }
+
+        if self.max_depth.is_none() && self.max_fields.is_none() {
+            return Ok(());
+        }
+
+        let (mut depth, mut count) = (0, 0);
+        measure(fields, 1, &mut depth, &mut count);
+
+        match (self.max_depth, self.max_fields) {
+            (Some(max), _) if depth > max => Err(BuildError::QueryTooDeep { depth, max }),
+            (_, Some(max)) if count > max => Err(BuildError::QueryTooBroad { fields: count, max }),
+            _ => Ok(()),
+        }
+    }
+
     #[inline(always)]
     fn get_type(&self, ty: OperationType) -> Option<&str> {
         match ty {
@@ -294,27 +331,27 @@ impl<'a> Builder<'a> {
         }
     }
 
-    /// Resolves currently processed operation
+    /// Resolves currently processed operation, along with its name
     /// based on [spec](https://spec.graphql.org/October2021/#sec-Executing-Requests)
     #[inline(always)]
     fn get_operation(
         &self,
         operation_name: Option<&str>,
-    ) -> Result<&OperationDefinition, BuildError> {
+    ) -> Result<(Option<&str>, &OperationDefinition), BuildError> {
         if let Some(operation_name) = operation_name {
             match &self.document.operations {
                 DocumentOperations::Single(_) => None,
-                DocumentOperations::Multiple(operations) => {
-                    operations.get(operation_name).map(|op| &op.node)
-                }
+                DocumentOperations::Multiple(operations) => operations
+                    .get_key_value(operation_name)
+                    .map(|(name, op)| (Some(name.as_str()), &op.node)),
             }
             .ok_or_else(|| BuildError::OperationNotFound(operation_name.to_string()))
         } else {
             match &self.document.operations {
-                DocumentOperations::Single(operation) => Ok(&operation.node),
+                DocumentOperations::Single(operation) => Ok((None, &operation.node)),
                 DocumentOperations::Multiple(map) if map.len() == 1 => {
-                    let (_, operation) = map.iter().next().unwrap();
-                    Ok(&operation.node)
+                    let (name, operation) = map.iter().next().unwrap();
+                    Ok((Some(name.as_str()), &operation.node))
                 }
                 DocumentOperations::Multiple(_) => Err(BuildError::OperationNameRequired),
             }
@@ -329,12 +366,13 @@ impl<'a> Builder<'a> {
             fragments.insert(name.as_str(), &fragment.node);
         }
 
-        let operation = self.get_operation(operation_name)?;
+        let (operation_name, operation) = self.get_operation(operation_name)?;
 
         let name = self
             .get_type(operation.ty)
             .ok_or(BuildError::RootOperationTypeNotDefined { operation: operation.ty })?;
         let fields = self.iter(&operation.selection_set.node, name, &fragments);
+        self.check_limits(&fields)?;
 
         let is_introspection_query = operation.selection_set.node.items.iter().any(|f| {
             if let Selection::Field(Positioned { node: gql_field, .. }) = &f.node {
@@ -347,6 +385,7 @@ impl<'a> Builder<'a> {
 
         let plan = OperationPlan::new(
             name,
+            operation_name,
             fields,
             operation.ty,
             self.index.clone(),
@@ -679,4 +718,33 @@ mod tests {
         assert!(plan.is_query());
         insta::assert_debug_snapshot!(plan.selection);
     }
+
+    #[test]
+    fn test_limits() {
+        let config = Config::from_sdl(CONFIG).to_result().unwrap();
+        let mut blueprint = Blueprint::try_from(&config.into()).unwrap();
+        blueprint.server.max_query_depth = Some(2);
+        blueprint.server.max_query_fields = Some(3);
+
+        let build = |query: &str| {
+            let document = async_graphql::parser::parse_query(query).unwrap();
+            Builder::new(&blueprint, &document).build(None).map(|_| ())
+        };
+
+        assert_eq!(build("query { posts { id title } }"), Ok(()));
+        assert_eq!(
+            build("query { posts { user { id } } }"),
+            Err(BuildError::QueryTooDeep { depth: 3, max: 2 })
+        );
+        assert_eq!(
+            build("query { posts { id title } users { id } }"),
+            Err(BuildError::QueryTooBroad { fields: 5, max: 3 })
+        );
+
+        // the introspection queries aren't limited
+        assert_eq!(
+            build("query { __schema { types { fields { type { name } } } } }"),
+            Ok(())
+        );
+    }
 }
@@ -1,6 +1,7 @@
 use std::sync::{Arc, Mutex, MutexGuard};
 
-use async_graphql::{Name, ServerError};
+use async_graphql::parser::types::OperationType;
+use async_graphql::{Name, PathSegment, ServerError};
 use async_graphql_value::ConstValue;
 use indexmap::IndexMap;
 
@@ -38,10 +39,21 @@ pub struct Context<'a, Input, Output> {
     // default values.
     field: &'a Field<Input>,
     request: &'a RequestContext<'a, Input>,
+    parent: Option<&'a Context<'a, Input, Output>>,
+    /// Indices of the value in the lists that the parent fields have
+    /// resolved to, outermost first.
+    indices: Vec<usize>,
 }
 impl<'a, Input: Clone, Output> Context<'a, Input, Output> {
     pub fn new(field: &'a Field<Input>, request: &'a RequestContext<Input>) -> Self {
-        Self { request, value: None, args: Self::build_args(field), field }
+        Self {
+            request,
+            value: None,
+            args: Self::build_args(field),
+            field,
+            parent: None,
+            indices: vec![],
+        }
     }
 
     pub fn with_value(&self, value: &'a Output) -> Self {
@@ -51,18 +63,42 @@ impl<'a, Input: Clone, Output> Context<'a, Input, Output> {
             args: Self::build_args(self.field),
             value: Some(value),
             field: self.field,
+            parent: self.parent,
+            indices: self.indices.clone(),
         }
     }
 
-    pub fn with_value_and_field(&self, value: &'a Output, field: &'a Field<Input>) -> Self {
+    /// Sets the indices of the value in the lists of the parent fields, eg.
+    /// `[1]` for the `user` of the second of the `posts`.
+    pub fn with_indices(self, indices: Vec<usize>) -> Self {
+        Self { indices, ..self }
+    }
+
+    pub fn with_value_and_field(&'a self, value: &'a Output, field: &'a Field<Input>) -> Self {
         Self {
             request: self.request,
             args: Self::build_args(field),
             value: Some(value),
             field,
+            parent: Some(self),
+            indices: vec![],
         }
     }
 
+    /// Path of the field in the response without the list indices, eg.
+    /// `posts.user.name`
+    pub fn path(&self) -> String {
+        let mut path = vec![self.field.output_name.as_str()];
+        let mut parent = self.parent;
+        while let Some(ctx) = parent {
+            path.push(ctx.field.output_name.as_str());
+            parent = ctx.parent;
+        }
+        path.reverse();
+
+        path.join(".")
+    }
+
     pub fn value(&self) -> Option<&Output> {
         self.value
     }
@@ -103,13 +139,44 @@ impl ResolverContextLike for Context<'_, ConstValue, ConstValue> {
         self.request.plan().is_query()
     }
 
+    fn operation_name(&self) -> Option<&str> {
+        self.request.plan().operation_name()
+    }
+
+    fn operation_type(&self) -> Option<OperationType> {
+        Some(self.request.plan().operation_type())
+    }
+
     fn add_error(&self, error: ServerError) {
         self.request.add_error(error.into())
     }
+
+    /// Each of the parent fields is followed by the indices of as many lists
+    /// as its type is nested in, the root field takes the ones that are left.
+    fn response_path(&self) -> Vec<PathSegment> {
+        let mut indices = self.indices.as_slice();
+        let mut path = vec![PathSegment::Field(self.field.output_name.clone())];
+        let mut parent = self.parent;
+        while let Some(ctx) = parent {
+            let depth = match ctx.parent {
+                Some(_) => ctx.field.type_of.list_depth().min(indices.len()),
+                None => indices.len(),
+            };
+            let (rest, own) = indices.split_at(indices.len() - depth);
+            path.extend(own.iter().rev().map(|index| PathSegment::Index(*index)));
+            path.push(PathSegment::Field(ctx.field.output_name.clone()));
+            indices = rest;
+            parent = ctx.parent;
+        }
+        path.reverse();
+
+        path
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use async_graphql::PathSegment;
     use async_graphql_value::ConstValue;
     use tailcall_valid::Validator;
 
@@ -142,6 +209,44 @@ mod test {
         insta::assert_debug_snapshot!(expected);
     }
 
+    #[test]
+    fn test_path() {
+        let plan = setup("query {posts {id user {name}}}").unwrap();
+        let env = RequestContext::new(&plan);
+        let posts = &plan.selection[0];
+        let user = posts.iter().find(|field| field.name == "user").unwrap();
+        let name = &user.selection[0];
+        let value = ConstValue::Null;
+
+        let ctx = Context::<ConstValue, ConstValue>::new(posts, &env);
+        let ctx = ctx.with_value_and_field(&value, user);
+        let ctx = ctx.with_value_and_field(&value, name);
+
+        assert_eq!(ctx.path(), "posts.user.name");
+    }
+
+    #[test]
+    fn test_response_path() {
+        let plan = setup("query {posts {id user {name}}}").unwrap();
+        let env = RequestContext::new(&plan);
+        let posts = &plan.selection[0];
+        let user = posts.iter().find(|field| field.name == "user").unwrap();
+        let value = ConstValue::Null;
+
+        let ctx = Context::<ConstValue, ConstValue>::new(posts, &env);
+        let ctx = ctx.with_value_and_field(&value, user);
+        let ctx = ctx.with_value(&value).with_indices(vec![1]);
+
+        assert_eq!(
+            ctx.response_path(),
+            vec![
+                PathSegment::Field("posts".to_string()),
+                PathSegment::Index(1),
+                PathSegment::Field("user".to_string()),
+            ]
+        );
+    }
+
     #[test]
     fn test_is_query() {
         let plan = setup("query {posts {id title}}").unwrap();
@@ -14,6 +14,10 @@ pub enum BuildError {
     OperationNotFound(String),
     #[error("Operation name required in request")]
     OperationNameRequired,
+    #[error("Query depth {depth} exceeds the maximum depth of {max}")]
+    QueryTooDeep { depth: usize, max: usize },
+    #[error("Query selects {fields} fields, exceeding the maximum of {max}")]
+    QueryTooBroad { fields: usize, max: usize },
 }
 
 #[derive(Error, Debug, Clone, PartialEq, Eq)]
@@ -34,6 +38,8 @@ pub enum ValidationError {
     // with async_graphql error message for this case
     #[error(r#"internal: invalid value for scalar "{type_of}", expected "FieldValue::Value""#)]
     ScalarInvalid { type_of: String },
+    #[error(r#"invalid value for scalar "{type_of}": {message}"#)]
+    ScalarCodec { type_of: String, message: String },
     #[error(r#"internal: invalid item for enum "{type_of}""#)]
     EnumInvalid { type_of: String },
     #[error("internal: non-null types require a return value")]
@@ -52,6 +58,13 @@ pub enum Error {
     Validation(#[from] ValidationError),
     #[error("{0}")]
     ServerError(async_graphql::ServerError),
+    /// The error of a field resolved for an element of the lists, along with
+    /// the indices of the element in the lists.
+    #[error("{error}")]
+    Element {
+        indices: Vec<usize>,
+        error: Box<Error>,
+    },
     #[error("Unexpected error")]
     Unknown,
 }
@@ -64,6 +77,7 @@ impl ErrorExtensions for Error {
             Error::IR(error) => error.extend(),
             Error::Validation(error) => error.extend(),
             Error::ServerError(error) => error.extend(),
+            Error::Element { error, .. } => error.extend(),
             Error::Unknown => super::graphql_error::Error::new(self.to_string()),
         }
     }
@@ -1,20 +1,34 @@
+use std::borrow::Cow;
 use std::sync::Arc;
 
 use async_graphql_value::{ConstValue, Value};
-use futures_util::future::join_all;
+use derive_setters::Setters;
+use futures_util::future::{self, join_all};
+use futures_util::stream::{self, BoxStream};
+use futures_util::StreamExt;
+use serde::Serialize;
 use tailcall_valid::Validator;
+use tracing::Instrument;
 
-use super::context::Context;
+use super::context::{self, Context};
 use super::exec::{Executor, IRExecutor};
+use super::field_span::FieldSpan;
 use super::graphql_error::GraphQLError;
-use super::{transform, AnyResponse, BuildError, Error, OperationPlan, Request, Response, Result};
+use super::incremental::{InitialPayload, Items, StreamField, SubsequentPayload};
+use super::{
+    transform, AnyResponse, BuildError, Error, Field, OperationPlan, PathSegment, Positioned,
+    Request, Response, Result, ValidationError, Variables,
+};
 use crate::core::app_context::AppContext;
+use crate::core::blueprint::DynamicValue;
+use crate::core::config::FieldLogs;
 use crate::core::http::RequestContext;
 use crate::core::ir::model::IR;
 use crate::core::ir::{self, EmptyResolverContext, EvalContext};
 use crate::core::jit::synth::Synth;
 use crate::core::jit::transform::InputResolver;
-use crate::core::json::{JsonLike, JsonLikeList};
+use crate::core::json::JsonLike;
+use crate::core::scalar_codec::ScalarCodecs;
 use crate::core::Transform;
 
 /// A specialized executor that executes with async_graphql::Value
@@ -40,83 +54,325 @@ impl ConstValueExecutor {
         req_ctx: &RequestContext,
         request: Request<ConstValue>,
     ) -> AnyResponse<Vec<u8>> {
+        let is_introspection_query =
+            req_ctx.server.get_enable_introspection() && self.plan.is_introspection_query;
+
+        let plan = match self.resolve(req_ctx, &request.variables).await {
+            Ok(plan) => plan,
+            Err(response) => return AnyResponse::encode(response, req_ctx.response_encoding),
+        };
+
+        let operation_name = request.operation_name.clone();
+        let exec = ConstValueExec::new(&plan, req_ctx)
+            .field_logs(app_ctx.blueprint.telemetry.field_logs.as_ref())
+            .operation_name(operation_name.as_deref());
+        // PERF: remove this particular clone?
+        let vars = request.variables.clone();
+        let exe = Executor::new(&plan, exec);
+        let store = exe.store().await;
+        let synth = Synth::new(&plan, store, vars);
+
+        // outlives the response that borrows it
+        let debug_stats;
+        let mut resp: Response<serde_json_borrow::Value> = exe.execute(&synth).await;
+        if let Some(field_stats) = &req_ctx.field_stats {
+            debug_stats = field_stats.to_value();
+            resp.extensions
+                .push(("debug".to_string(), JsonLike::clone_from(&debug_stats)));
+        }
+
+        if is_introspection_query {
+            let async_req = async_graphql::Request::from(request).only_introspection();
+            let async_resp = app_ctx.execute(async_req).await;
+
+            AnyResponse::encode(resp.merge_with(&async_resp), req_ctx.response_encoding)
+        } else {
+            AnyResponse::encode(resp, req_ctx.response_encoding)
+        }
+    }
+
+    /// Executes the operation with the lists of the `@stream` fields delivered
+    /// incrementally. The initial payload holds the initial elements of the
+    /// lists along with the rest of the fields, and every next element is
+    /// delivered in a payload of its own once its fields are resolved.
+    /// Dropping the stream, eg. when the client disconnects, drops the
+    /// upstream calls that are still running.
+    pub fn execute_stream(
+        self,
+        app_ctx: Arc<AppContext>,
+        req_ctx: Arc<RequestContext>,
+        request: Request<ConstValue>,
+    ) -> BoxStream<'static, Vec<u8>> {
+        stream::once(async move {
+            let mut plan = match self.resolve(&req_ctx, &request.variables).await {
+                Ok(plan) => plan,
+                Err(response) => {
+                    let payload = InitialPayload { response, has_next: false };
+                    return stream::iter([to_bytes(&payload)]).boxed();
+                }
+            };
+
+            let mut streams = vec![];
+            let mut errors = vec![];
+            for stream_field in StreamField::find(&plan) {
+                let field = &plan.selection[stream_field.index];
+                let initial = match open_stream(&plan, &stream_field, &req_ctx).await {
+                    Ok(Some((initial, items))) => {
+                        // the stream can end or fail before the initial count
+                        let stream_field =
+                            StreamField { initial_count: initial.len(), ..stream_field.clone() };
+                        streams.push((Arc::new((stream_field, field.clone())), items));
+                        ConstValue::List(initial)
+                    }
+                    Ok(None) => ConstValue::Null,
+                    Err(err) => {
+                        errors.push(Positioned {
+                            value: Error::from(err),
+                            pos: field.pos,
+                            path: vec![PathSegment::Field(Cow::Owned(field.output_name.clone()))],
+                        });
+                        ConstValue::Null
+                    }
+                };
+
+                plan.selection[stream_field.index].ir =
+                    Some(IR::Dynamic(DynamicValue::Value(initial)));
+            }
+
+            let mut response = execute_plan(&plan, &app_ctx, &req_ctx, &request).await;
+            response.add_errors(errors);
+            let has_next = !streams.is_empty();
+            let initial = to_bytes(&InitialPayload { response, has_next });
+            if !has_next {
+                return stream::iter([initial]).boxed();
+            }
+
+            // every element is executed as the only element of the list of its
+            // field, with the rest of the root fields removed from the plan
+            let items = Arc::new(StreamItems {
+                plan: OperationPlan { selection: vec![], before: None, ..plan },
+                app_ctx,
+                req_ctx,
+                request,
+            });
+            let payloads = streams.into_iter().map(|(field, values)| {
+                let items = items.clone();
+                values
+                    .enumerate()
+                    .then(move |(index, value)| {
+                        let (items, field) = (items.clone(), field.clone());
+                        async move { items.execute(&field, index, value).await }
+                    })
+                    .boxed()
+            });
+            let last =
+                to_bytes(&SubsequentPayload::<ConstValue> { incremental: vec![], has_next: false });
+
+            stream::once(future::ready(initial))
+                .chain(stream::select_all(payloads))
+                .chain(stream::once(future::ready(last)))
+                .boxed()
+        })
+        .flatten()
+        .boxed()
+    }
+
+    /// Runs the `before` resolvers and resolves the plan with the variables
+    /// of the request.
+    async fn resolve(
+        self,
+        req_ctx: &RequestContext,
+        variables: &Variables<ConstValue>,
+    ) -> std::result::Result<OperationPlan<ConstValue>, Response<ConstValue>> {
         // Run all the IRs in the before chain
         if let Some(ir) = &self.plan.before {
             let mut eval_context = EvalContext::new(req_ctx, &EmptyResolverContext {});
-            match ir.eval(&mut eval_context).await {
-                Ok(_) => (),
-                Err(err) => {
-                    let resp: Response<ConstValue> = Response::default();
-                    return resp
-                        .with_errors(vec![GraphQLError::new(err.to_string(), None)])
-                        .into();
-                }
+            if let Err(err) = ir.eval(&mut eval_context).await {
+                return Err(
+                    Response::default().with_errors(vec![GraphQLError::new(err.to_string(), None)])
+                );
             }
         }
 
-        let is_introspection_query =
-            req_ctx.server.get_enable_introspection() && self.plan.is_introspection_query;
-        let variables = &request.variables;
-
         // Attempt to skip unnecessary fields
         let Ok(plan) = transform::Skip::new(variables)
             .transform(self.plan)
             .to_result()
         else {
-            let resp: Response<ConstValue> = Response::default();
             // this shouldn't actually ever happen
-            return resp
-                .with_errors(vec![GraphQLError::new(Error::Unknown.to_string(), None)])
-                .into();
+            return Err(Response::default()
+                .with_errors(vec![GraphQLError::new(Error::Unknown.to_string(), None)]));
         };
 
         // Attempt to replace variables in the plan with the actual values
         // TODO: operation from [ExecutableDocument] could contain definitions for
         // default values of arguments. That info should be passed to
         // [InputResolver] to resolve defaults properly
-        let result = InputResolver::new(plan).resolve_input(variables);
+        InputResolver::new(plan)
+            .resolve_input(variables)
+            .map_err(|err| {
+                Response::default().with_errors(vec![GraphQLError::new(
+                    BuildError::from(err).to_string(),
+                    None,
+                )])
+            })
+    }
+}
 
-        let plan = match result {
-            Ok(plan) => plan,
-            Err(err) => {
-                let resp: Response<ConstValue> = Response::default();
-                return resp
-                    .with_errors(vec![GraphQLError::new(
-                        BuildError::from(err).to_string(),
-                        None,
-                    )])
-                    .into();
-            }
-        };
+/// Executes the resolved plan and synthesizes the response.
+async fn execute_plan(
+    plan: &OperationPlan<ConstValue>,
+    app_ctx: &AppContext,
+    req_ctx: &RequestContext,
+    request: &Request<ConstValue>,
+) -> Response<ConstValue> {
+    let exec = ConstValueExec::new(plan, req_ctx)
+        .field_logs(app_ctx.blueprint.telemetry.field_logs.as_ref())
+        .operation_name(request.operation_name.as_deref());
+    let exe = Executor::new(plan, exec);
+    let store = exe.store().await;
+    let synth = Synth::new(plan, store, request.variables.clone());
 
-        let exec = ConstValueExec::new(&plan, req_ctx);
-        // PERF: remove this particular clone?
-        let vars = request.variables.clone();
-        let exe = Executor::new(&plan, exec);
-        let store = exe.store().await;
-        let synth = Synth::new(&plan, store, vars);
+    exe.execute(&synth).await
+}
 
-        let resp: Response<serde_json_borrow::Value> = exe.execute(&synth).await;
+/// Opens the stream of the list of the field and receives its initial
+/// elements, it's `None` when the list resolves to null. An error after some
+/// of the initial elements are received doesn't fail the field, it's delivered
+/// with the next payload instead.
+async fn open_stream(
+    plan: &OperationPlan<ConstValue>,
+    stream_field: &StreamField,
+    req_ctx: &RequestContext,
+) -> std::result::Result<
+    Option<(
+        Vec<ConstValue>,
+        BoxStream<'static, std::result::Result<ConstValue, ir::Error>>,
+    )>,
+    ir::Error,
+> {
+    let field = &plan.selection[stream_field.index];
+    let Some(ir) = field.ir.as_ref() else {
+        return Ok(Some((vec![], stream::empty().boxed())));
+    };
 
-        if is_introspection_query {
-            let async_req = async_graphql::Request::from(request).only_introspection();
-            let async_resp = app_ctx.execute(async_req).await;
+    let plan_ctx = context::RequestContext::new(plan);
+    let ctx: Context<'_, ConstValue, ConstValue> = Context::new(field, &plan_ctx);
+    let Some(mut values) = ir.stream(&mut EvalContext::new(req_ctx, &ctx)).await? else {
+        return Ok(None);
+    };
 
-            resp.merge_with(&async_resp).into()
-        } else {
-            resp.into()
+    let mut initial = Vec::with_capacity(stream_field.initial_count);
+    while initial.len() < stream_field.initial_count {
+        match values.next().await {
+            Some(Ok(value)) => initial.push(value),
+            Some(Err(err)) if !initial.is_empty() => {
+                return Ok(Some((initial, stream::iter([Err(err)]).boxed())));
+            }
+            Some(Err(err)) => return Err(err),
+            None => break,
         }
     }
+
+    Ok(Some((initial, values)))
+}
+
+fn to_bytes<T: Serialize>(payload: &T) -> Vec<u8> {
+    serde_json::to_vec(payload).unwrap_or_default()
+}
+
+/// Executes the elements of the streamed lists that are received after the
+/// initial payload.
+struct StreamItems {
+    plan: OperationPlan<ConstValue>,
+    app_ctx: Arc<AppContext>,
+    req_ctx: Arc<RequestContext>,
+    request: Request<ConstValue>,
+}
+
+impl StreamItems {
+    async fn execute(
+        &self,
+        (stream_field, field): &(StreamField, Field<ConstValue>),
+        index: usize,
+        value: std::result::Result<ConstValue, ir::Error>,
+    ) -> Vec<u8> {
+        let index = stream_field.initial_count + index;
+        let path = vec![
+            PathSegment::Field(Cow::Owned(field.output_name.clone())),
+            PathSegment::Index(index),
+        ];
+
+        let items = match value {
+            Ok(value) => {
+                let mut field = field.clone();
+                let name = field.output_name.clone();
+                field.ir = Some(IR::Dynamic(DynamicValue::Value(ConstValue::List(vec![
+                    value,
+                ]))));
+                let plan = OperationPlan { selection: vec![field], ..self.plan.clone() };
+                let response =
+                    execute_plan(&plan, &self.app_ctx, &self.req_ctx, &self.request).await;
+
+                let item = match response.data {
+                    ConstValue::Object(mut data) => match data.swap_remove(name.as_str()) {
+                        Some(ConstValue::List(mut list)) if !list.is_empty() => list.swap_remove(0),
+                        _ => ConstValue::Null,
+                    },
+                    _ => ConstValue::Null,
+                };
+                // the errors point to the element in the list of the response
+                let errors = response
+                    .errors
+                    .into_iter()
+                    .map(|mut error| {
+                        if let Some(segment) = error.path.get_mut(1) {
+                            *segment = PathSegment::Index(index);
+                        }
+                        error
+                    })
+                    .collect();
+
+                Items {
+                    items: vec![item],
+                    path,
+                    label: stream_field.label.clone(),
+                    errors,
+                }
+            }
+            Err(err) => {
+                let error = GraphQLError::from(Positioned {
+                    value: Error::from(err),
+                    pos: field.pos,
+                    path: path.clone(),
+                });
+
+                Items {
+                    items: vec![],
+                    path,
+                    label: stream_field.label.clone(),
+                    errors: vec![error],
+                }
+            }
+        };
+
+        to_bytes(&SubsequentPayload { incremental: vec![items], has_next: true })
+    }
 }
 
+#[derive(Setters)]
 struct ConstValueExec<'a> {
+    #[setters(skip)]
     plan: &'a OperationPlan<ConstValue>,
+    #[setters(skip)]
     req_context: &'a RequestContext,
+    field_logs: Option<&'a FieldLogs>,
+    operation_name: Option<&'a str>,
 }
 
 impl<'a> ConstValueExec<'a> {
     pub fn new(plan: &'a OperationPlan<ConstValue>, req_context: &'a RequestContext) -> Self {
-        Self { req_context, plan }
+        Self { req_context, plan, field_logs: None, operation_name: None }
     }
 
     async fn call(
@@ -135,8 +391,102 @@ impl<'a> ConstValueExec<'a> {
 
         let req_context = &self.req_context;
         let mut eval_ctx = EvalContext::new(req_context, ctx);
+        let value = match self.field_logs {
+            Some(logs) => {
+                let request_id = eval_ctx.request_id();
+                let span = FieldSpan::new(logs, ctx, self.operation_name, request_id);
+                let value = ir.eval(&mut eval_ctx).instrument(span.span()).await;
+                span.log(&value);
+
+                value?
+            }
+            None => ir.eval(&mut eval_ctx).await?,
+        };
+
+        let codecs = self.plan.index.scalar_codecs();
+        if codecs.is_empty() {
+            Ok(value)
+        } else {
+            convert_scalars(codecs, ctx.field(), value)
+        }
+    }
+}
+
+/// Converts the values of the custom scalars in the resolved value of the
+/// field with the codecs registered for them. Nested fields that have their
+/// own resolver are converted once they are resolved.
+fn convert_scalars(
+    codecs: &ScalarCodecs,
+    field: &Field<ConstValue>,
+    value: ConstValue,
+) -> Result<ConstValue> {
+    if field.scalar.is_some() {
+        let type_of = field.type_of.name();
+
+        return codecs.convert(type_of, value).map_err(|message| {
+            ValidationError::ScalarCodec { type_of: type_of.to_string(), message }.into()
+        });
+    }
 
-        Ok(ir.eval(&mut eval_ctx).await?)
+    match value {
+        ConstValue::Object(mut obj) => {
+            for child in field.iter().filter(|child| child.ir.is_none()) {
+                if let Some(value) = obj.get_mut(child.name.as_str()) {
+                    *value = convert_scalars(codecs, child, std::mem::take(value))?;
+                }
+            }
+
+            Ok(ConstValue::Object(obj))
+        }
+        ConstValue::List(list) => Ok(ConstValue::List(
+            list.into_iter()
+                .map(|value| convert_scalars(codecs, field, value))
+                .collect::<Result<_>>()?,
+        )),
+        value => Ok(value),
+    }
+}
+
+/// Maps the elements of the nested lists to the results of the field, like
+/// [crate::core::json::JsonLikeList::map_ref]. The error of an element
+/// carries the indices of the element, so that it points to the element in the
+/// response.
+fn map_elements(
+    value: &ConstValue,
+    indices: &mut Vec<usize>,
+    mapper: &mut impl FnMut(&ConstValue) -> Result<ConstValue>,
+) -> Result<ConstValue> {
+    match value {
+        ConstValue::List(list) => {
+            let mut items = Vec::with_capacity(list.len());
+            for (i, item) in list.iter().enumerate() {
+                indices.push(i);
+                items.push(map_elements(item, indices, mapper)?);
+                indices.pop();
+            }
+
+            Ok(ConstValue::List(items))
+        }
+        value => mapper(value)
+            .map_err(|error| Error::Element { indices: indices.clone(), error: Box::new(error) }),
+    }
+}
+
+/// Calls `f` with every element of the nested lists and the indices it's at.
+fn for_each_element<'a>(
+    value: &'a ConstValue,
+    indices: &mut Vec<usize>,
+    f: &mut impl FnMut(&[usize], &'a ConstValue),
+) {
+    match value {
+        ConstValue::List(list) => {
+            for (i, item) in list.iter().enumerate() {
+                indices.push(i);
+                for_each_element(item, indices, f);
+                indices.pop();
+            }
+        }
+        value => f(indices, value),
     }
 }
 
@@ -158,11 +508,11 @@ impl IRExecutor for ConstValueExec<'_> {
                 let mut tasks = Vec::new();
 
                 // collect the async tasks first before creating the final result
-                value.for_each(&mut |value| {
+                for_each_element(value, &mut vec![], &mut |indices, value| {
                     // execute the resolver only for fields that are related to current value
                     // for fragments on union/interface
                     if self.plan.field_is_part_of_value(field, value) {
-                        let ctx = ctx.with_value(value);
+                        let ctx = ctx.with_value(value).with_indices(indices.to_vec());
                         tasks.push(async move { self.call(&ctx, ir).await })
                     }
                 });
@@ -173,7 +523,7 @@ impl IRExecutor for ConstValueExec<'_> {
 
                 // map input value to the calculated results preserving the shape
                 // of the input
-                Ok(value.map_ref(&mut |value| {
+                map_elements(value, &mut vec![], &mut |value| {
                     // for fragments on union/interface we will
                     // have less entries for resolved values based on the type
                     // pull from the result only field is related and fill with null otherwise
@@ -185,9 +535,643 @@ impl IRExecutor for ConstValueExec<'_> {
                     } else {
                         Ok(Self::Output::default())
                     }
-                })?)
+                })
             }
             _ => Ok(self.call(ctx, ir).await?),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use async_graphql_value::ConstValue;
+    use hyper::body::Bytes;
+    use reqwest::StatusCode;
+    use tailcall_valid::Validator;
+
+    use super::*;
+    use crate::core::app_context::test::{TestApp, TestHttp};
+    use crate::core::blueprint::Blueprint;
+    use crate::core::config::{Config, ConfigModule};
+    use crate::core::http::Response;
+    use crate::core::scalar_codec::ScalarCodec;
+    use crate::core::HttpIO;
+
+    const CONFIG: &str = r#"
+        schema @server @upstream {
+            query: Query
+        }
+
+        scalar Money
+
+        type Query {
+            price: Money @http(url: "http://localhost:8080/price")
+            product: Product @http(url: "http://localhost:8080/product")
+        }
+
+        type Product {
+            name: String
+            prices: [Money]
+        }
+    "#;
+
+    struct Money;
+
+    impl ScalarCodec for Money {
+        fn parse(&self, value: ConstValue) -> std::result::Result<ConstValue, String> {
+            match value {
+                ConstValue::Number(cents) if cents.is_i64() => Ok(ConstValue::Number(cents)),
+                value => Err(format!("expected integer cents, got {}", value)),
+            }
+        }
+
+        fn serialize(&self, value: ConstValue) -> std::result::Result<ConstValue, String> {
+            let cents = match value {
+                ConstValue::Number(cents) => cents.as_i64().unwrap_or_default(),
+                _ => 0,
+            };
+
+            Ok(ConstValue::String(format!(
+                "{}.{:02}",
+                cents / 100,
+                cents % 100
+            )))
+        }
+    }
+
+    fn codec_app() -> TestApp {
+        let config = Config::from_sdl(CONFIG).to_result().unwrap();
+        let blueprint = Blueprint::try_from(&ConfigModule::from(config))
+            .unwrap()
+            .scalar_codecs(ScalarCodecs::default().register("Money", Money));
+
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.http = TestHttp::body(|request| {
+            Ok(match request.url().path() {
+                "/price" => "1234",
+                _ => r#"{"name": "pen", "prices": [250, 99]}"#,
+            })
+        });
+
+        TestApp::from_blueprint(blueprint, runtime)
+    }
+
+    const STREAM_CONFIG: &str = r#"
+        schema @server @upstream {
+            query: Query
+        }
+
+        type Query {
+            version: String @expr(body: "1.0")
+            users: [User] @http(url: "http://localhost:8080/users")
+        }
+
+        type User {
+            id: Int!
+            name: String
+            posts: [Post] @http(url: "http://localhost:8080/users/{{.value.id}}/posts")
+        }
+
+        type Post {
+            title: String
+        }
+    "#;
+
+    struct StreamHttp;
+
+    #[async_trait::async_trait]
+    impl HttpIO for StreamHttp {
+        async fn execute(&self, request: reqwest::Request) -> anyhow::Result<Response<Bytes>> {
+            let mut headers = reqwest::header::HeaderMap::new();
+            let body = match request.url().path() {
+                "/users" => {
+                    headers.insert(
+                        reqwest::header::CONTENT_TYPE,
+                        "application/x-ndjson".parse().unwrap(),
+                    );
+                    "{\"id\":1,\"name\":\"Leanne\"}\n{\"id\":2,\"name\":\"Ervin\"}\n{\"id\":3,\"name\":\"Clementine\"}\n"
+                        .to_string()
+                }
+                path => {
+                    let id = path.split('/').nth(2).unwrap_or_default();
+                    format!(r#"[{{"title": "post of {id}"}}]"#)
+                }
+            };
+
+            Ok(Response { status: StatusCode::OK, headers, body: Bytes::from(body) })
+        }
+    }
+
+    async fn execute_stream(query: &str) -> Vec<serde_json::Value> {
+        TestApp::new(STREAM_CONFIG, Arc::new(StreamHttp))
+            .execute_stream(query)
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_stream() {
+        let actual = execute_stream(
+            r#"query {
+                version
+                users @stream(initialCount: 1, label: "users") { name posts { title } }
+            }"#,
+        )
+        .await;
+        let expected = vec![
+            serde_json::json!({
+                "data": {
+                    "version": "1.0",
+                    "users": [{ "name": "Leanne", "posts": [{ "title": "post of 1" }] }]
+                },
+                "hasNext": true
+            }),
+            serde_json::json!({
+                "incremental": [{
+                    "items": [{ "name": "Ervin", "posts": [{ "title": "post of 2" }] }],
+                    "path": ["users", 1],
+                    "label": "users"
+                }],
+                "hasNext": true
+            }),
+            serde_json::json!({
+                "incremental": [{
+                    "items": [{ "name": "Clementine", "posts": [{ "title": "post of 3" }] }],
+                    "path": ["users", 2],
+                    "label": "users"
+                }],
+                "hasNext": true
+            }),
+            serde_json::json!({ "hasNext": false }),
+        ];
+
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_stream_disabled() {
+        let actual =
+            execute_stream("query { users @stream(initialCount: 1, if: false) { name } }").await;
+        let expected = vec![serde_json::json!({
+            "data": {
+                "users": [{ "name": "Leanne" }, { "name": "Ervin" }, { "name": "Clementine" }]
+            },
+            "hasNext": false
+        })];
+
+        assert_eq!(actual, expected);
+    }
+
+    const STREAM_PIPELINE_CONFIG: &str = r#"
+        schema @server @upstream {
+            query: Query
+        }
+
+        type Query {
+            users(skip: Boolean): [User]
+                @http(url: "http://localhost:8080/users", keyCase: CamelToSnake, skipIf: "args.skip == true")
+            missing: [User] @http(url: "http://localhost:8080/missing", successStatuses: ["404"])
+            invalid: [User]
+                @http(
+                    url: "http://localhost:8080/invalid"
+                    responseSchema: {type: "array", items: {type: "object", properties: {user_name: {type: "string"}}}}
+                )
+        }
+
+        type User {
+            userName: String
+        }
+    "#;
+
+    #[tokio::test]
+    async fn test_stream_pipeline() {
+        let http = TestHttp::new(|request| {
+            let body = match request.url().path() {
+                "/users" => r#"[{"user_name": "Leanne"}, {"user_name": "Ervin"}]"#,
+                "/invalid" => r#"[{"user_name": 1}]"#,
+                _ => Err(ir::Error::HTTP { status: 404, message: "Not Found".to_string() })?,
+            };
+            Ok(Response { body: Bytes::from(body), ..Default::default() })
+        });
+        let app = TestApp::new(STREAM_PIPELINE_CONFIG, http.clone());
+
+        // the streamed lists resolve to the lists resolved in full
+        for field in ["users", "users(skip: true)", "missing"] {
+            let expected = app
+                .execute(&format!("query {{ {field} {{ userName }} }}"))
+                .await;
+            let actual = app
+                .execute_stream(&format!(
+                    "query {{ {field} @stream(initialCount: 5) {{ userName }} }}"
+                ))
+                .await;
+
+            assert_eq!(actual[0]["data"], expected["data"], "{field}");
+            assert_eq!(actual[0].get("errors"), None, "{field}");
+        }
+        assert_eq!(http.paths(), ["/users", "/users", "/missing", "/missing"]);
+
+        // the elements are validated against the items of the response schema
+        let expected = app.execute("query { invalid { userName } }").await;
+        let actual = app
+            .execute_stream("query { invalid @stream(initialCount: 5) { userName } }")
+            .await;
+        assert_eq!(actual[0]["data"]["invalid"], serde_json::Value::Null);
+        assert_eq!(
+            actual[0]["errors"][0]["message"],
+            expected["errors"][0]["message"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scalar_codec() {
+        let actual = codec_app()
+            .execute("query { price product { name prices } }")
+            .await;
+        let expected = serde_json::json!({
+            "data": {
+                "price": "12.34",
+                "product": { "name": "pen", "prices": ["2.50", "0.99"] }
+            }
+        });
+
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_scalar_codec_without_jit() {
+        let app_ctx = codec_app().app_ctx;
+        let req_ctx = Arc::new(RequestContext::from(app_ctx.as_ref()));
+        let request =
+            async_graphql::Request::new("query { price product { name prices } }").data(req_ctx);
+
+        let response = app_ctx.execute(request).await;
+        let actual = serde_json::to_value(response).unwrap();
+        let expected = serde_json::json!({
+            "data": {
+                "price": "12.34",
+                "product": { "name": "pen", "prices": ["2.50", "0.99"] }
+            }
+        });
+
+        assert_eq!(actual, expected);
+    }
+
+    const ERROR_CONFIG: &str = r#"
+        schema @server @upstream {
+            query: Query
+        }
+
+        type Query {
+            users: [User] @http(url: "http://localhost:8080/users")
+        }
+
+        type User {
+            id: Int!
+            posts: [Post] @http(url: "http://localhost:8080/users/{{.value.id}}/posts")
+        }
+
+        type Post {
+            id: Int!
+            author: String @http(url: "http://localhost:8080/posts/{{.value.id}}/author")
+        }
+    "#;
+
+    #[tokio::test]
+    async fn test_error_path() {
+        let http = TestHttp::body(|request| {
+            Ok(match request.url().path() {
+                "/users" => r#"[{"id": 1}, {"id": 2}]"#,
+                "/users/1/posts" => r#"[{"id": 1}]"#,
+                "/users/2/posts" => r#"[{"id": 2}, {"id": 3}]"#,
+                "/posts/3/author" => anyhow::bail!("author not found"),
+                _ => r#""Leanne""#,
+            })
+        });
+        let response = TestApp::new(ERROR_CONFIG, http)
+            .execute("query { users { posts { author } } }")
+            .await;
+
+        // the error points to the element of the nested lists that failed
+        assert_eq!(
+            response["errors"][0]["path"],
+            serde_json::json!(["users", 1, "posts", 1, "author"])
+        );
+    }
+
+    const NOT_FOUND_CONFIG: &str = r#"
+        schema @server @upstream {
+            query: Query
+        }
+
+        type Query {
+            user: User @http(url: "http://localhost:8080/users/1", successStatuses: ["404"])
+            owner: User! @http(url: "http://localhost:8080/users/1", successStatuses: ["404"])
+            admin: User @http(url: "http://localhost:8080/users/1")
+        }
+
+        type User {
+            name: String
+        }
+    "#;
+
+    #[tokio::test]
+    async fn test_success_statuses() {
+        let http = TestHttp::new(|_| {
+            Err(ir::Error::HTTP { status: 404, message: "Not Found".to_string() }.into())
+        });
+        let app = TestApp::new(NOT_FOUND_CONFIG, http);
+
+        // the 404 resolves the nullable field to null
+        let response = app.execute("query { user { name } }").await;
+        assert_eq!(response, serde_json::json!({ "data": { "user": null } }));
+
+        // a non-nullable field can't be null
+        let response = app.execute("query { owner { name } }").await;
+        assert_eq!(response["data"], serde_json::Value::Null);
+        assert_eq!(response["errors"][0]["path"], serde_json::json!(["owner"]));
+
+        // the statuses that aren't configured are still errors
+        let response = app.execute("query { admin { name } }").await;
+        assert_eq!(response["data"], serde_json::Value::Null);
+        assert_eq!(response["errors"][0]["path"], serde_json::json!(["admin"]));
+    }
+
+    const GRPC_STREAM_CONFIG: &str = r#"
+        schema
+          @server
+          @upstream
+          @link(src: "{{FEED}}", type: Protobuf) {
+            query: Query
+        }
+
+        type Query {
+            posts: [Post] @grpc(url: "http://localhost:50051", method: "feed.FeedService.ListPosts")
+            broken: [Post] @grpc(url: "http://localhost:50052", method: "feed.FeedService.ListPosts")
+        }
+
+        type Post {
+            id: Int
+            title: String
+        }
+    "#;
+
+    /// A length-prefixed `feed.Post` message.
+    fn post(id: u8, title: &str) -> Vec<u8> {
+        let mut message = vec![0x08, id, 0x12, title.len() as u8];
+        message.extend(title.as_bytes());
+
+        let mut bytes = vec![0, 0, 0, 0, message.len() as u8];
+        bytes.extend(message);
+        bytes
+    }
+
+    /// Streams the posts of the server stream in chunks that end in the
+    /// middle of the messages, the stream of the second upstream is reset
+    /// after two posts.
+    struct GrpcStreamHttp;
+
+    #[async_trait::async_trait]
+    impl HttpIO for GrpcStreamHttp {
+        async fn execute(&self, request: reqwest::Request) -> anyhow::Result<Response<Bytes>> {
+            let body = [post(1, "a"), post(2, "b"), post(3, "c")].concat();
+
+            // the posts are only received in full by the fields that aren't streamed
+            assert_eq!(request.url().port(), Some(50051));
+            Ok(Response {
+                status: StatusCode::OK,
+                headers: Default::default(),
+                body: body.into(),
+            })
+        }
+
+        async fn execute_stream(
+            &self,
+            request: reqwest::Request,
+        ) -> anyhow::Result<Response<crate::core::http::BodyStream>> {
+            let body = [post(1, "a"), post(2, "b")].concat();
+            let (head, tail) = body.split_at(8);
+            let last = match request.url().port() {
+                Some(50051) => Ok(Bytes::from(post(3, "c"))),
+                _ => Err(anyhow::anyhow!("connection reset")),
+            };
+            let chunks = vec![
+                Ok(Bytes::from(head.to_vec())),
+                Ok(Bytes::from(tail.to_vec())),
+                last,
+            ];
+
+            Ok(Response {
+                status: StatusCode::OK,
+                headers: Default::default(),
+                body: stream::iter(chunks).boxed(),
+            })
+        }
+    }
+
+    async fn execute_grpc_stream(query: &str) -> Vec<serde_json::Value> {
+        let mut runtime = crate::core::runtime::test::init(None);
+        runtime.http2_only = Arc::new(GrpcStreamHttp);
+
+        let sdl = GRPC_STREAM_CONFIG.replace("{{FEED}}", tailcall_fixtures::protobuf::FEED);
+        let config = Config::from_sdl(&sdl).to_result().unwrap();
+        let config_module = crate::core::config::reader::ConfigReader::init(runtime.clone())
+            .resolve(config, None)
+            .await
+            .unwrap();
+        let blueprint = Blueprint::try_from(&config_module).unwrap();
+
+        TestApp::from_blueprint(blueprint, runtime)
+            .execute_stream(query)
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_grpc_stream() {
+        let actual =
+            execute_grpc_stream("query { posts @stream(initialCount: 1) { id title } }").await;
+        let expected = vec![
+            serde_json::json!({
+                "data": { "posts": [{ "id": 1, "title": "a" }] },
+                "hasNext": true
+            }),
+            serde_json::json!({
+                "incremental": [{ "items": [{ "id": 2, "title": "b" }], "path": ["posts", 1] }],
+                "hasNext": true
+            }),
+            serde_json::json!({
+                "incremental": [{ "items": [{ "id": 3, "title": "c" }], "path": ["posts", 2] }],
+                "hasNext": true
+            }),
+            serde_json::json!({ "hasNext": false }),
+        ];
+        assert_eq!(actual, expected);
+
+        // without @stream the messages are resolved to the list at once
+        let actual = execute_grpc_stream("query { posts { id } }").await;
+        let expected = vec![serde_json::json!({
+            "data": { "posts": [{ "id": 1 }, { "id": 2 }, { "id": 3 }] },
+            "hasNext": false
+        })];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_stream_error() {
+        let actual = execute_grpc_stream("query { broken @stream(initialCount: 1) { id } }").await;
+
+        // the posts delivered before the error are kept, and the list ends
+        assert_eq!(actual.len(), 4);
+        assert_eq!(
+            actual[0]["data"],
+            serde_json::json!({ "broken": [{ "id": 1 }] })
+        );
+        assert_eq!(
+            actual[1]["incremental"][0]["items"],
+            serde_json::json!([{ "id": 2 }])
+        );
+        let incremental = &actual[2]["incremental"][0];
+        assert_eq!(incremental["path"], serde_json::json!(["broken", 2]));
+        assert!(incremental["errors"][0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("connection reset"));
+        assert_eq!(actual[3], serde_json::json!({ "hasNext": false }));
+
+        // the error while the initial posts are received doesn't drop them
+        let actual = execute_grpc_stream("query { broken @stream(initialCount: 3) { id } }").await;
+        assert_eq!(actual.len(), 3);
+        assert_eq!(
+            actual[0]["data"],
+            serde_json::json!({ "broken": [{ "id": 1 }, { "id": 2 }] })
+        );
+        assert_eq!(
+            actual[1]["incremental"][0]["path"],
+            serde_json::json!(["broken", 2])
+        );
+        assert_eq!(actual[2], serde_json::json!({ "hasNext": false }));
+    }
+
+    const VALIDATE_CONFIG: &str = r#"
+        schema @server @upstream(batch: {delay: 1}) {
+            query: Query
+        }
+
+        type Query {
+            posts(limit: Int @validate(min: 1, max: 100)): [Post]
+                @http(url: "http://localhost:8080/posts", query: [{key: "limit", value: "{{.args.limit}}"}])
+        }
+
+        type Post {
+            id: Int
+            userId: Int
+            user(tag: String @validate(pattern: "^[a-z]+$")): User
+                @http(
+                    url: "http://localhost:8080/users"
+                    query: [{key: "id", value: "{{.value.userId}}"}]
+                    batchKey: ["id"]
+                )
+        }
+
+        type User {
+            id: Int
+            name: String
+        }
+    "#;
+
+    async fn execute_validate(query: &str) -> (serde_json::Value, Vec<String>) {
+        let http = TestHttp::body(|request| {
+            Ok(match request.url().path() {
+                "/posts" => r#"[{"id": 1, "userId": 1}, {"id": 2, "userId": 2}]"#,
+                _ => r#"[{"id": 1, "name": "Leanne"}, {"id": 2, "name": "Ervin"}]"#,
+            })
+        });
+        let response = TestApp::new(VALIDATE_CONFIG, http.clone())
+            .execute(query)
+            .await;
+
+        (response, http.paths())
+    }
+
+    #[tokio::test]
+    async fn test_validate_arguments() {
+        // the upstream isn't called for the out-of-range argument
+        // the response fails with the first error of its fields
+        let (response, paths) = execute_validate("query { posts(limit: 0) { id } }").await;
+        assert_eq!(response["data"], serde_json::Value::Null);
+        assert_eq!(response["errors"][0]["path"], serde_json::json!(["posts"]));
+        assert_eq!(
+            response["errors"][0]["extensions"]["code"],
+            serde_json::json!("BAD_USER_INPUT")
+        );
+        assert!(paths.is_empty());
+
+        // the invalid argument of a batched field doesn't enroll it in the batch
+        let query = r#"query { posts(limit: 10) { id user(tag: "A") { name } } }"#;
+        let (response, paths) = execute_validate(query).await;
+        assert_eq!(response["errors"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            response["errors"][0]["path"],
+            serde_json::json!(["posts", 0, "user"])
+        );
+        assert_eq!(paths, vec!["/posts"]);
+
+        let query = r#"query { posts(limit: 10) { id user(tag: "a") { name } } }"#;
+        let (response, paths) = execute_validate(query).await;
+        assert_eq!(
+            response["data"]["posts"][1]["user"],
+            serde_json::json!({ "name": "Ervin" })
+        );
+        assert_eq!(paths, vec!["/posts", "/users"]);
+    }
+
+    const OPERATION_CONFIG: &str = r#"
+        schema @server @upstream {
+            query: Query
+            mutation: Mutation
+        }
+
+        type Query {
+            name: String @expr(body: "{{.operation.name}}")
+            kind: String @expr(body: "{{.operation.type}}")
+        }
+
+        type Mutation {
+            kind: String @expr(body: "{{.operation.type}}")
+        }
+    "#;
+
+    async fn execute_operation(query: &str, operation_name: Option<&str>) -> serde_json::Value {
+        let runtime = crate::core::runtime::test::init(None);
+        let mut request = Request::new(query);
+        request.operation_name = operation_name.map(str::to_string);
+
+        TestApp::with_runtime(OPERATION_CONFIG, runtime)
+            .execute_request(request)
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_operation_name_and_type() {
+        let response = execute_operation("query Posts { name kind }", None).await;
+        assert_eq!(
+            response["data"],
+            serde_json::json!({ "name": "Posts", "kind": "query" })
+        );
+
+        // the name of an anonymous operation renders as empty
+        let response = execute_operation("{ name kind }", None).await;
+        assert_eq!(
+            response["data"],
+            serde_json::json!({ "name": "", "kind": "query" })
+        );
+
+        // the name is the one of the operation that's executed
+        let query = "query Posts { name } mutation Create { kind }";
+        let response = execute_operation(query, Some("Create")).await;
+        assert_eq!(response["data"], serde_json::json!({ "kind": "mutation" }));
+        let response = execute_operation(query, Some("Posts")).await;
+        assert_eq!(response["data"], serde_json::json!({ "name": "Posts" }));
+    }
+}
@@ -0,0 +1,83 @@
+use std::fmt::Display;
+
+use async_graphql_value::ConstValue;
+use tracing::{Level, Span};
+
+use super::context::Context;
+use crate::core::config::{FieldLogs, LogLevel};
+
+/// Span that is opened for every resolved field when the field logs are
+/// enabled. It ties the upstream calls made while resolving the field to the
+/// field path, the operation and the request id.
+pub struct FieldSpan {
+    span: Span,
+    level: LogLevel,
+}
+
+impl FieldSpan {
+    pub fn new(
+        logs: &FieldLogs,
+        ctx: &Context<'_, ConstValue, ConstValue>,
+        operation_name: Option<&str>,
+        request_id: Option<&str>,
+    ) -> Self {
+        let path = ctx.path();
+        let args = logs.include_args.then(|| {
+            ctx.field()
+                .args
+                .iter()
+                .filter_map(|arg| Some(format!("{}: {}", arg.name, arg.value.as_ref()?)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        });
+
+        macro_rules! span {
+            ($level:expr) => {
+                tracing::span!(
+                    $level,
+                    "field",
+                    graphql.field.path = %path,
+                    graphql.operation.name = operation_name,
+                    request.id = request_id,
+                    graphql.field.args = args.as_deref()
+                )
+            };
+        }
+
+        let span = match logs.level {
+            LogLevel::Trace => span!(Level::TRACE),
+            LogLevel::Debug => span!(Level::DEBUG),
+            LogLevel::Info => span!(Level::INFO),
+            LogLevel::Warn => span!(Level::WARN),
+            LogLevel::Error => span!(Level::ERROR),
+        };
+
+        Self { span, level: logs.level }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span.clone()
+    }
+
+    /// Logs the outcome of resolving the field within the span.
+    pub fn log<A, E: Display>(&self, result: &Result<A, E>) {
+        macro_rules! event {
+            ($level:expr) => {
+                match result {
+                    Ok(_) => tracing::event!($level, outcome = "ok", "field resolved"),
+                    Err(err) => {
+                        tracing::event!($level, outcome = "error", error = %err, "field failed")
+                    }
+                }
+            };
+        }
+
+        self.span.in_scope(|| match self.level {
+            LogLevel::Trace => event!(Level::TRACE),
+            LogLevel::Debug => event!(Level::DEBUG),
+            LogLevel::Info => event!(Level::INFO),
+            LogLevel::Warn => event!(Level::WARN),
+            LogLevel::Error => event!(Level::ERROR),
+        })
+    }
+}
@@ -156,6 +156,10 @@ pub struct Error {
     /// Extensions to the error.
     #[serde(skip_serializing_if = "error_extensions_is_empty")]
     pub extensions: Option<ErrorExtensionValues>,
+    /// Status code of the response of a REST endpoint that fails with the
+    /// error.
+    #[serde(skip)]
+    pub status: Option<u16>,
 }
 
 impl Debug for Error {
@@ -183,7 +187,12 @@ impl PartialEq for Error {
 impl Error {
     /// Create an error from the given error message.
     pub fn new(message: impl Into<String>) -> Self {
-        Self { message: message.into(), extensions: None }
+        Self { message: message.into(), extensions: None, status: None }
+    }
+
+    /// Sets the status code of the response of a REST endpoint.
+    pub fn status(self, status: Option<u16>) -> Self {
+        Self { status, ..self }
     }
 
     /// Convert the error to a server error.
@@ -211,12 +220,12 @@ pub trait ErrorExtensions: Sized {
         let mut new_extensions = Default::default();
         cb(&self, &mut new_extensions);
 
-        let Error { message, extensions } = self.extend();
+        let Error { message, extensions, status } = self.extend();
 
         let mut extensions = extensions.unwrap_or_default();
         extensions.0.extend(new_extensions.0);
 
-        Error { message, extensions: Some(extensions) }
+        Error { message, extensions: Some(extensions), status }
     }
 }
 
@@ -230,7 +239,7 @@ impl ErrorExtensions for Error {
 // for E which does not conflict with this implementation acting as a fallback.
 impl<E: Display> ErrorExtensions for &E {
     fn extend(&self) -> Error {
-        Error { message: self.to_string(), extensions: None }
+        Error { message: self.to_string(), extensions: None, status: None }
     }
 }
 
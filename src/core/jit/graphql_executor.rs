@@ -9,10 +9,11 @@ use futures_util::stream::FuturesOrdered;
 use futures_util::StreamExt;
 use tailcall_hasher::TailcallHasher;
 
-use super::{AnyResponse, BatchResponse, Response};
+use super::incremental::StreamField;
+use super::{AnyResponse, BatchResponse, IncrementalResponse, Response};
 use crate::core::app_context::AppContext;
 use crate::core::async_graphql_hyper::OperationId;
-use crate::core::http::RequestContext;
+use crate::core::http::{RequestContext, ResponseEncoding};
 use crate::core::jit::{self, ConstValueExecutor, OPHash, Pos, Positioned};
 
 #[derive(Clone)]
@@ -61,10 +62,19 @@ impl JITExecutor {
         out.unwrap_or_default()
     }
 
+    /// Format of the body of the responses.
+    pub fn response_encoding(&self) -> ResponseEncoding {
+        self.req_ctx.response_encoding
+    }
+
     #[inline(always)]
-    fn req_hash(request: &async_graphql::Request) -> OPHash {
+    fn req_hash(&self, request: &async_graphql::Request) -> OPHash {
         let mut hasher = TailcallHasher::default();
         request.query.hash(&mut hasher);
+        // the operations of a document are planned separately
+        request.operation_name.hash(&mut hasher);
+        // the constant responses are cached serialized
+        self.req_ctx.response_encoding.hash(&mut hasher);
 
         OPHash::new(hasher.finish())
     }
@@ -75,9 +85,9 @@ impl JITExecutor {
         &self,
         request: async_graphql::Request,
     ) -> impl Future<Output = AnyResponse<Vec<u8>>> + Send + '_ {
-        // TODO: hash considering only the query itself ignoring specified operation and
-        // variables that could differ for the same query
-        let hash = Self::req_hash(&request);
+        // TODO: hash considering only the query itself ignoring the variables that
+        // could differ for the same query
+        let hash = self.req_hash(&request);
 
         async move {
             if let Some(response) = self.app_ctx.const_execution_cache.get(&hash) {
@@ -85,43 +95,103 @@ impl JITExecutor {
             }
 
             let jit_request = jit::Request::from(request);
-            let exec = if let Some(op) = self.app_ctx.operation_plans.get(&hash) {
-                ConstValueExecutor::from(op.value().clone())
-            } else {
-                let exec = match ConstValueExecutor::try_new(&jit_request, &self.app_ctx) {
-                    Ok(exec) => exec,
-                    Err(error) => {
-                        return Response::<async_graphql::Value>::default()
-                            .with_errors(vec![Positioned::new(error, Pos::default())])
-                            .into()
-                    }
-                };
-                self.app_ctx
-                    .operation_plans
-                    .insert(hash.clone(), exec.plan.clone());
-                exec
-            };
-
-            let is_const = exec.plan.is_const;
-            let is_protected = exec.plan.is_protected;
-
-            let response = if exec.plan.can_dedupe() {
-                self.dedupe_and_exec(exec, jit_request).await
-            } else {
-                self.exec(exec, jit_request).await
-            };
-
-            // Cache the response if it's constant and not wrapped with protected.
-            if is_const && !is_protected {
-                self.app_ctx
-                    .const_execution_cache
-                    .insert(hash, response.clone());
+            match self.plan(&hash, &jit_request) {
+                Ok(exec) => self.run(hash, exec, jit_request).await,
+                Err(response) => response,
             }
+        }
+    }
 
-            response
+    /// Executes the request with the lists of its `@stream` fields delivered
+    /// incrementally, the operations without such fields are executed as
+    /// usual.
+    pub async fn execute_incremental(
+        &self,
+        request: async_graphql::Request,
+    ) -> IncrementalResponse<Vec<u8>> {
+        let hash = self.req_hash(&request);
+        let jit_request = jit::Request::from(request);
+        let exec = match self.plan(&hash, &jit_request) {
+            Ok(exec) => exec,
+            Err(response) => return IncrementalResponse::Single(response),
+        };
+
+        if exec.plan.selection.iter().any(StreamField::is_streamed) {
+            IncrementalResponse::Stream(exec.execute_stream(
+                self.app_ctx.clone(),
+                self.req_ctx.clone(),
+                jit_request,
+            ))
+        } else if let Some(response) = self.app_ctx.const_execution_cache.get(&hash) {
+            IncrementalResponse::Single(response.clone())
+        } else {
+            IncrementalResponse::Single(self.run(hash, exec, jit_request).await)
         }
     }
 
+    /// Returns the plan of the operation, it's built only once for a query.
+    fn plan(
+        &self,
+        hash: &OPHash,
+        jit_request: &jit::Request<ConstValue>,
+    ) -> Result<ConstValueExecutor, AnyResponse<Vec<u8>>> {
+        if let Some(op) = self.app_ctx.operation_plans.get(hash) {
+            let exec = ConstValueExecutor::from(op.value().clone());
+            return Ok(self.varied(exec));
+        }
+
+        let exec = match ConstValueExecutor::try_new(jit_request, &self.app_ctx) {
+            Ok(exec) => exec,
+            Err(error) => {
+                return Err(AnyResponse::encode(
+                    Response::<async_graphql::Value>::default()
+                        .with_errors(vec![Positioned::new(error, Pos::default())]),
+                    self.req_ctx.response_encoding,
+                ))
+            }
+        };
+        self.app_ctx
+            .operation_plans
+            .insert(hash.clone(), exec.plan.clone());
+
+        Ok(self.varied(exec))
+    }
+
+    /// Marks the response as varying on the compression of the fields when
+    /// the operation selects the `@compress` fields.
+    fn varied(&self, exec: ConstValueExecutor) -> ConstValueExecutor {
+        if exec.plan.is_compressed {
+            self.req_ctx.set_selects_compressed_fields();
+        }
+
+        exec
+    }
+
+    async fn run(
+        &self,
+        hash: OPHash,
+        exec: ConstValueExecutor,
+        jit_request: jit::Request<ConstValue>,
+    ) -> AnyResponse<Vec<u8>> {
+        let is_const = exec.plan.is_const;
+        let is_protected = exec.plan.is_protected;
+
+        let response = if exec.plan.can_dedupe() {
+            self.dedupe_and_exec(exec, jit_request).await
+        } else {
+            self.exec(exec, jit_request).await
+        };
+
+        // Cache the response if it's constant and not wrapped with protected.
+        if is_const && !is_protected {
+            self.app_ctx
+                .const_execution_cache
+                .insert(hash, response.clone());
+        }
+
+        response
+    }
+
     /// Execute a GraphQL batch query.
     pub async fn execute_batch(&self, batch_request: BatchRequest) -> BatchResponse<Vec<u8>> {
         match batch_request {
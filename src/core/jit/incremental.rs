@@ -0,0 +1,101 @@
+use async_graphql_value::ConstValue;
+use serde::Serialize;
+
+use super::graphql_error::GraphQLError;
+use super::{Field, OperationPlan, PathSegment, Response};
+
+const STREAM: &str = "stream";
+
+/// Root field of the operation whose list is delivered incrementally with
+/// `@stream(initialCount: Int, label: String, if: Boolean)`. Only the root
+/// list fields that have a resolver are streamed, the directive is ignored on
+/// the other fields.
+#[derive(Clone)]
+pub struct StreamField {
+    /// Position of the field in the selection of the root.
+    pub index: usize,
+    /// Number of the elements delivered with the initial payload.
+    pub initial_count: usize,
+    pub label: Option<String>,
+}
+
+impl StreamField {
+    pub fn is_streamed<Input>(field: &Field<Input>) -> bool {
+        field.ir.is_some()
+            && field.type_of.is_list()
+            && field
+                .directives
+                .iter()
+                .any(|directive| directive.name == STREAM)
+    }
+
+    /// Streamed fields of the plan resolved with the variables of the request.
+    pub fn find(plan: &OperationPlan<ConstValue>) -> Vec<StreamField> {
+        plan.selection
+            .iter()
+            .enumerate()
+            .filter(|(_, field)| Self::is_streamed(field))
+            .filter_map(|(index, field)| {
+                let directive = field
+                    .directives
+                    .iter()
+                    .find(|directive| directive.name == STREAM)?;
+                let argument = |name: &str| {
+                    directive
+                        .arguments
+                        .iter()
+                        .find(|(key, _)| key == name)
+                        .map(|(_, value)| value)
+                };
+
+                if let Some(ConstValue::Boolean(false)) = argument("if") {
+                    return None;
+                }
+
+                let initial_count = match argument("initialCount") {
+                    Some(ConstValue::Number(count)) => count.as_u64().unwrap_or_default() as usize,
+                    _ => 0,
+                };
+                let label = match argument("label") {
+                    Some(ConstValue::String(label)) => Some(label.clone()),
+                    _ => None,
+                };
+
+                Some(StreamField { index, initial_count, label })
+            })
+            .collect()
+    }
+}
+
+/// First payload of an incremental response, with the result of the fields
+/// that aren't streamed and the initial elements of the streamed lists.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitialPayload<Value> {
+    #[serde(flatten)]
+    pub response: Response<Value>,
+    pub has_next: bool,
+}
+
+/// Payload with the elements of the streamed lists that are resolved after
+/// the initial payload. The last payload only signals the end of the
+/// response.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubsequentPayload<Value> {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub incremental: Vec<Items<Value>>,
+    pub has_next: bool,
+}
+
+/// Elements of a streamed list, the path ends with the index of the first
+/// element eg. `["users", 2]`.
+#[derive(Serialize)]
+pub struct Items<Value> {
+    pub items: Vec<Value>,
+    pub path: Vec<PathSegment<'static>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<GraphQLError>,
+}
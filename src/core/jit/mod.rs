@@ -10,6 +10,8 @@ use store::*;
 mod context;
 mod error;
 mod exec_const;
+mod field_span;
+mod incremental;
 mod request;
 mod response;
 
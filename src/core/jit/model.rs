@@ -305,6 +305,8 @@ impl OPHash {
 #[derive(Debug, Clone)]
 pub struct OperationPlan<Input> {
     pub root_name: String,
+    /// Name of the operation in the document, `None` when it's anonymous
+    pub operation_name: Option<String>,
     pub operation_type: OperationType,
     // TODO: drop index from here. Embed all the necessary information in each field of the plan.
     pub index: Arc<Index>,
@@ -312,6 +314,9 @@ pub struct OperationPlan<Input> {
     pub is_dedupe: bool,
     pub is_const: bool,
     pub is_protected: bool,
+    /// Selects the `@compress` fields, the response varies on the
+    /// `x-compressed-fields` header of the request
+    pub is_compressed: bool,
     pub min_cache_ttl: Option<NonZeroU64>,
     pub selection: Vec<Field<Input>>,
     pub before: Option<IR>,
@@ -331,12 +336,14 @@ impl<Input> OperationPlan<Input> {
         Ok(OperationPlan {
             selection,
             root_name: self.root_name,
+            operation_name: self.operation_name,
             operation_type: self.operation_type,
             index: self.index,
             is_introspection_query: self.is_introspection_query,
             is_dedupe: self.is_dedupe,
             is_const: self.is_const,
             is_protected: self.is_protected,
+            is_compressed: self.is_compressed,
             min_cache_ttl: self.min_cache_ttl,
             before: self.before,
         })
@@ -347,6 +354,7 @@ impl<Input> OperationPlan<Input> {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         root_name: &str,
+        operation_name: Option<&str>,
         selection: Vec<Field<Input>>,
         operation_type: OperationType,
         index: Arc<Index>,
@@ -357,6 +365,7 @@ impl<Input> OperationPlan<Input> {
     {
         Self {
             root_name: root_name.to_string(),
+            operation_name: operation_name.map(str::to_string),
             selection,
             operation_type,
             index,
@@ -364,6 +373,7 @@ impl<Input> OperationPlan<Input> {
             is_dedupe: false,
             is_const: false,
             is_protected: false,
+            is_compressed: false,
             min_cache_ttl: None,
             before: Default::default(),
         }
@@ -379,6 +389,11 @@ impl<Input> OperationPlan<Input> {
         self.operation_type
     }
 
+    /// Returns the name of the operation, `None` when it's anonymous
+    pub fn operation_name(&self) -> Option<&str> {
+        self.operation_name.as_deref()
+    }
+
     /// Check if current graphQL operation is query
     pub fn is_query(&self) -> bool {
         self.operation_type == OperationType::Query
@@ -1,11 +1,13 @@
 use std::sync::Arc;
 
 use derive_setters::Setters;
+use futures_util::stream::BoxStream;
 use serde::Serialize;
 
 use super::graphql_error::GraphQLError;
 use super::Positioned;
 use crate::core::async_graphql_hyper::CacheControl;
+use crate::core::http::ResponseEncoding;
 use crate::core::jit;
 use crate::core::json::{JsonLike, JsonObjectLike};
 
@@ -15,13 +17,28 @@ pub struct Response<Value> {
     pub data: Value,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub errors: Vec<GraphQLError>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_extensions"
+    )]
     pub extensions: Vec<(String, Value)>,
 
     #[serde(skip)]
     pub cache_control: CacheControl,
 }
 
+/// The extensions are an object keyed by their names in the response.
+fn serialize_extensions<S, Value>(
+    extensions: &[(String, Value)],
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    Value: Serialize,
+{
+    serializer.collect_map(extensions.iter().map(|(name, value)| (name, value)))
+}
+
 impl<V: Default> Default for Response<V> {
     fn default() -> Self {
         Self {
@@ -119,21 +136,35 @@ where
 
 impl<V: Serialize> From<Response<V>> for AnyResponse<Vec<u8>> {
     fn from(response: Response<V>) -> Self {
+        Self::encode(response, ResponseEncoding::Json)
+    }
+}
+
+impl AnyResponse<Vec<u8>> {
+    /// Serializes the response in the format negotiated with the client.
+    pub fn encode<V: Serialize>(response: Response<V>, encoding: ResponseEncoding) -> Self {
         Self {
             cache_control: CacheControl {
                 max_age: response.cache_control.max_age,
                 public: response.cache_control.public,
             },
             is_ok: response.errors.is_empty(),
-            // Safely serialize the response to JSON bytes. Since the response is always valid,
+            // Safely serialize the response to bytes. Since the response is always valid,
             // serialization is expected to succeed. In the unlikely event of a failure,
             // default to an empty byte array. TODO: return error instead of default
             // value.
-            body: Arc::new(serde_json::to_vec(&response).unwrap_or_default()),
+            body: Arc::new(encoding.to_vec(&response).unwrap_or_default()),
         }
     }
 }
 
+/// Response of an operation whose `@stream` fields are delivered
+/// incrementally, the stream yields the serialized payloads.
+pub enum IncrementalResponse<Body> {
+    Single(AnyResponse<Body>),
+    Stream(BoxStream<'static, Body>),
+}
+
 pub enum BatchResponse<Body> {
     Single(AnyResponse<Body>),
     Batch(Vec<AnyResponse<Body>>),
@@ -72,7 +72,9 @@ where
 
         let result = match self.store.get(&node.id) {
             Some(value) => {
-                let mut value = value.as_ref().map_err(Clone::clone)?;
+                let mut value = value
+                    .as_ref()
+                    .map_err(|error| self.to_field_error(error, path))?;
 
                 for index in data_path.as_slice() {
                     if let Some(arr) = value.as_array() {
@@ -228,6 +230,34 @@ where
         eval_result.map_err(|e| self.to_location_error(e, node, path))
     }
 
+    /// Points the error the field is resolved with to the field in the
+    /// response. The errors of the elements of the lists point to the element
+    /// that failed, rather than to the one being synthesized.
+    fn to_field_error(
+        &'a self,
+        error: &Positioned<Error>,
+        path: &[PathSegment],
+    ) -> Positioned<Error> {
+        if !error.path.is_empty() {
+            return error.clone();
+        }
+
+        let (value, indices) = match &error.value {
+            Error::Element { indices, error } => (error.as_ref().clone(), indices.as_slice()),
+            value => (value.clone(), [].as_slice()),
+        };
+        let mut indices = indices.iter();
+        let path = path
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::Field(cow) => PathSegment::Field(Cow::Owned(cow.clone().into_owned())),
+                PathSegment::Index(i) => PathSegment::Index(indices.next().copied().unwrap_or(*i)),
+            })
+            .collect();
+
+        Positioned { value, pos: error.pos, path }
+    }
+
     fn to_location_error(
         &'a self,
         error: Error,
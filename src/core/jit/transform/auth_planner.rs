@@ -4,7 +4,7 @@ use std::fmt::Debug;
 use tailcall_valid::Valid;
 
 use crate::core::blueprint::{Auth, DynamicValue};
-use crate::core::ir::model::IR;
+use crate::core::ir::model::{Protect, IR};
 use crate::core::jit::{Field, OperationPlan};
 use crate::core::Transform;
 
@@ -31,15 +31,22 @@ impl<A: Debug> Transform for AuthPlanner<A> {
         plan.before = auth
             .into_iter()
             .reduce(|a, b| a.and(b))
-            .map(|auth| IR::Protect(auth, Box::new(IR::Dynamic(DynamicValue::default()))));
+            .map(|auth| IR::Protect(Protect::new(auth, IR::Dynamic(DynamicValue::default()))));
 
         Valid::succeed(plan)
     }
 }
 
 /// Used to recursively update the field ands its selections to remove
-/// IR::Protected
+/// IR::Protected. The fields that are skipped by a variable, ie. `@skip(if:
+/// $var)` or `@include(if: $var)`, and the fields nested in them keep their
+/// IR::Protect, as the plan is shared by the requests with other variables.
+/// It's evaluated only when the field is selected.
 fn update_field<A>(auth: &mut Vec<Auth>, field: &mut Field<A>) {
+    if field.skip.is_some() || field.include.is_some() {
+        return;
+    }
+
     if let Some(ref mut ir) = field.ir {
         update_ir(ir, auth);
 
@@ -65,11 +72,29 @@ pub fn update_ir(ir: &mut IR, vec: &mut Vec<Auth>) {
         IR::Path(ir, _) => {
             update_ir(ir, vec);
         }
-        IR::Protect(auth, ir_0) => {
-            vec.push(auth.clone());
+        IR::Mask(mask) => {
+            update_ir(&mut mask.input, vec);
+        }
+        IR::Compress(compress) => {
+            update_ir(&mut compress.input, vec);
+        }
+        IR::Filter(filter) => {
+            update_ir(&mut filter.input, vec);
+        }
+        IR::Compact(compact) => {
+            update_ir(&mut compact.input, vec);
+        }
+        IR::Validate(validate) => {
+            update_ir(&mut validate.input, vec);
+        }
+        // the anonymous requests aren't rejected before the execution, they
+        // fall back to the anonymous IR of the field
+        IR::Protect(protect) if protect.anon.is_some() => {}
+        IR::Protect(protect) => {
+            vec.push(protect.auth.clone());
 
-            update_ir(ir_0, vec);
-            *ir = *ir_0.clone();
+            update_ir(&mut protect.authed, vec);
+            *ir = *protect.authed.clone();
         }
         IR::Pipe(ir1, ir2) => {
             update_ir(ir1, vec);
@@ -78,8 +103,136 @@ pub fn update_ir(ir: &mut IR, vec: &mut Vec<Auth>) {
         IR::Discriminate(_, ir) => {
             update_ir(ir, vec);
         }
-        IR::Merge(irs) => {
-            irs.iter_mut().for_each(|ir| update_ir(ir, vec));
+        IR::Merge(merge) => {
+            merge.irs.iter_mut().for_each(|ir| update_ir(ir, vec));
+        }
+        IR::Fallback(fallback) => {
+            fallback.irs.iter_mut().for_each(|ir| update_ir(ir, vec));
+        }
+        IR::Join(join) => {
+            update_ir(&mut join.left, vec);
+            update_ir(&mut join.right, vec);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use async_graphql_value::ConstValue;
+    use headers::{Authorization, HeaderMapExt};
+    use serde_json::json;
+    use tailcall_valid::Validator;
+
+    use super::*;
+    use crate::core::app_context::test::TestApp;
+    use crate::core::blueprint::Blueprint;
+    use crate::core::config::{Config, ConfigModule, Content, Extensions};
+    use crate::core::http::RequestContext;
+    use crate::core::ir::IrVisitor;
+    use crate::core::jit::Request;
+
+    const CONFIG: &str = r#"
+        schema @server @upstream {
+            query: Query
+        }
+
+        type Query {
+            public: String @expr(body: "public")
+            secret: String @protected @expr(body: "secret")
+            nested: Nested @expr(body: {name: "nested"})
+        }
+
+        type Nested {
+            name: String
+            secret: String @protected @expr(body: "nested secret")
+        }
+    "#;
+
+    // testuser1:password123
+    const HTPASSWD: &str = "testuser1:$apr1$e3dp9qh2$fFIfHU9bilvVZBl8TxKzL/";
+
+    fn blueprint() -> Blueprint {
+        let config = Config::from_sdl(CONFIG).to_result().unwrap();
+        let htpasswd = Content { id: None, content: HTPASSWD.to_string() };
+        let config_module = ConfigModule::from(config)
+            .set_extensions(Extensions { htpasswd: vec![htpasswd], ..Default::default() });
+
+        Blueprint::try_from(&config_module).unwrap()
+    }
+
+    /// Counts the auth checks that the plan evaluates when all of its fields
+    /// are selected.
+    #[derive(Default)]
+    struct Protects(usize);
+
+    impl IrVisitor for Protects {
+        fn visit_protect(&mut self, _: &Auth) {
+            self.0 += 1;
+        }
+    }
+
+    fn protects(query: &str) -> (usize, usize) {
+        let plan = Request::<ConstValue>::new(query)
+            .create_plan(&blueprint())
+            .unwrap();
+        let before = plan.before.iter().count();
+        let mut protects = Protects::default();
+        plan.iter_dfs()
+            .filter_map(|field| field.ir.as_ref())
+            .for_each(|ir| ir.accept(&mut protects));
+
+        (before, protects.0)
+    }
+
+    #[test]
+    fn test_unselected_protected_field() {
+        assert_eq!(protects("query { public nested { name } }"), (0, 0));
+
+        // the selected ones are checked once before the execution
+        assert_eq!(
+            protects("query { public secret nested { secret } }"),
+            (1, 0)
+        );
+
+        // the conditional ones only when they are selected
+        let query = "query ($skip: Boolean!) { public secret @skip(if: $skip) }";
+        assert_eq!(protects(query), (0, 1));
+        let query = "query ($include: Boolean!) { nested @include(if: $include) { secret } }";
+        assert_eq!(protects(query), (0, 1));
+    }
+
+    async fn execute(skip: bool, authorized: bool) -> serde_json::Value {
+        let runtime = crate::core::runtime::test::init(None);
+        let app = TestApp::from_blueprint(blueprint(), runtime);
+        let mut req_ctx = RequestContext::from(app.app_ctx.as_ref());
+        if authorized {
+            req_ctx
+                .allowed_headers
+                .typed_insert(Authorization::basic("testuser1", "password123"));
+        }
+        let request = Request::new("query ($skip: Boolean!) { public secret @skip(if: $skip) }")
+            .variables([("skip".to_string(), ConstValue::Boolean(skip))]);
+
+        app.execute_with(&req_ctx, request).await
+    }
+
+    #[tokio::test]
+    async fn test_skipped_protected_field() {
+        // the anonymous request isn't rejected for the field it skips
+        let response = execute(true, false).await;
+        assert_eq!(response["data"], json!({ "public": "public" }));
+        assert!(response.get("errors").is_none(), "{response}");
+
+        let response = execute(false, true).await;
+        assert_eq!(
+            response["data"],
+            json!({ "public": "public", "secret": "secret" })
+        );
+        assert!(response.get("errors").is_none(), "{response}");
+
+        let response = execute(false, false).await;
+        assert_eq!(response["data"]["public"], json!("public"));
+        assert_eq!(response["data"]["secret"], json!(null));
+        assert!(response["errors"].is_array(), "{response}");
+    }
+}
@@ -20,14 +20,37 @@ impl<A> CheckCache<A> {
 fn check_cache(ir: &IR) -> Option<NonZeroU64> {
     match ir {
         IR::IO(_) => None,
-        IR::Cache(cache) => Some(cache.max_age),
+        // the value could be a negative or a large one that's stored for a
+        // shorter time
+        IR::Cache(cache) => Some(cache.min_max_age()),
         IR::Path(ir, _) => check_cache(ir),
-        IR::Protect(_, ir) => check_cache(ir),
+        IR::Protect(protect) => match &protect.anon {
+            Some(anon) => match (check_cache(&protect.authed), check_cache(anon)) {
+                (Some(age1), Some(age2)) => Some(age1.min(age2)),
+                _ => None,
+            },
+            None => check_cache(&protect.authed),
+        },
+        IR::Mask(mask) => check_cache(&mask.input),
+        IR::Compress(compress) => check_cache(&compress.input),
+        IR::Filter(filter) => check_cache(&filter.input),
+        IR::Compact(compact) => check_cache(&compact.input),
+        IR::Validate(validate) => check_cache(&validate.input),
         IR::Pipe(ir, ir1) => match (check_cache(ir), check_cache(ir1)) {
             (Some(age1), Some(age2)) => Some(age1.min(age2)),
             _ => None,
         },
-        IR::Merge(vec) => vec.iter().map(check_cache).min().unwrap_or_default(),
+        IR::Merge(merge) => merge.irs.iter().map(check_cache).min().unwrap_or_default(),
+        IR::Fallback(fallback) => fallback
+            .irs
+            .iter()
+            .map(check_cache)
+            .min()
+            .unwrap_or_default(),
+        IR::Join(join) => match (check_cache(&join.left), check_cache(&join.right)) {
+            (Some(age1), Some(age2)) => Some(age1.min(age2)),
+            _ => None,
+        },
         IR::Discriminate(_, ir) => check_cache(ir),
         IR::Entity(hash_map) => hash_map.values().map(check_cache).min().unwrap_or_default(),
         IR::Dynamic(_) | IR::ContextPath(_) | IR::Map(_) | IR::Service(_) => None,
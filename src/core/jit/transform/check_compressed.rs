@@ -0,0 +1,45 @@
+use std::convert::Infallible;
+use std::marker::PhantomData;
+
+use tailcall_valid::Valid;
+
+use crate::core::ir::model::IR;
+use crate::core::ir::IrVisitor;
+use crate::core::jit::OperationPlan;
+use crate::core::Transform;
+
+pub struct CheckCompressed<A>(PhantomData<A>);
+impl<A> CheckCompressed<A> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+/// Checks if the IR compresses its value for the clients that accept it
+pub fn is_compressed(ir: &IR) -> bool {
+    #[derive(Default)]
+    struct Compressed(bool);
+
+    impl IrVisitor for Compressed {
+        fn visit_ir(&mut self, ir: &IR) {
+            self.0 |= matches!(ir, IR::Compress(_));
+        }
+    }
+
+    let mut visitor = Compressed::default();
+    ir.accept(&mut visitor);
+    visitor.0
+}
+
+impl<A> Transform for CheckCompressed<A> {
+    type Value = OperationPlan<A>;
+    type Error = Infallible;
+
+    fn transform(&self, mut plan: Self::Value) -> Valid<Self::Value, Self::Error> {
+        plan.is_compressed = plan
+            .iter_dfs()
+            .any(|field| field.ir.as_ref().is_some_and(is_compressed));
+
+        Valid::succeed(plan)
+    }
+}
@@ -22,10 +22,21 @@ pub fn is_const(ir: &IR) -> bool {
         IR::Cache(_) => false,
         IR::Path(ir, _) => is_const(ir),
         IR::ContextPath(_) => false,
-        IR::Protect(_, ir) => is_const(ir),
+        // the value of the anonymous requests differs
+        IR::Protect(protect) => protect.anon.is_none() && is_const(&protect.authed),
+        // the masked value depends on the caller
+        IR::Mask(_) => false,
+        // the compression depends on the caller
+        IR::Compress(_) => false,
         IR::Map(map) => is_const(&map.input),
+        IR::Filter(filter) => is_const(&filter.input),
+        IR::Compact(compact) => is_const(&compact.input),
+        // the arguments are checked on every request
+        IR::Validate(_) => false,
         IR::Pipe(ir, ir1) => is_const(ir) && is_const(ir1),
-        IR::Merge(vec) => vec.iter().all(is_const),
+        IR::Merge(merge) => merge.irs.iter().all(is_const),
+        IR::Fallback(fallback) => fallback.irs.iter().all(is_const),
+        IR::Join(join) => is_const(&join.left) && is_const(&join.right),
         IR::Discriminate(_, ir) => is_const(ir),
         IR::Entity(hash_map) => hash_map.values().all(is_const),
         IR::Service(_) => true,
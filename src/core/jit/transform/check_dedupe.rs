@@ -19,9 +19,17 @@ fn check_dedupe(ir: &IR) -> bool {
         IR::IO(io) => io.dedupe(),
         IR::Cache(cache) => cache.io.dedupe(),
         IR::Path(ir, _) => check_dedupe(ir),
-        IR::Protect(_, ir) => check_dedupe(ir),
+        IR::Protect(protect) => protect.anon.is_none() && check_dedupe(&protect.authed),
+        // the masked value depends on the caller, so it can't be shared
+        IR::Mask(_) => false,
+        IR::Compress(_) => false,
+        IR::Filter(filter) => check_dedupe(&filter.input),
+        IR::Compact(compact) => check_dedupe(&compact.input),
+        IR::Validate(validate) => check_dedupe(&validate.input),
         IR::Pipe(ir, ir1) => check_dedupe(ir) && check_dedupe(ir1),
-        IR::Merge(vec) => vec.iter().all(check_dedupe),
+        IR::Merge(merge) => merge.irs.iter().all(check_dedupe),
+        IR::Fallback(fallback) => fallback.irs.iter().all(check_dedupe),
+        IR::Join(join) => check_dedupe(&join.left) && check_dedupe(&join.right),
         IR::Discriminate(_, ir) => check_dedupe(ir),
         IR::Entity(hash_map) => hash_map.values().all(check_dedupe),
         IR::Dynamic(_) => true,
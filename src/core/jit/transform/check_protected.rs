@@ -22,10 +22,18 @@ pub fn is_protected(ir: &IR) -> bool {
         IR::Cache(_) => false,
         IR::Path(ir, _) => is_protected(ir),
         IR::ContextPath(_) => false,
-        IR::Protect(_, _) => true,
+        IR::Protect(protect) => protect.anon.is_none(),
         IR::Map(map) => is_protected(&map.input),
+        // the masked value depends on the scopes of the caller
+        IR::Mask(_) => true,
+        IR::Compress(compress) => is_protected(&compress.input),
+        IR::Filter(filter) => is_protected(&filter.input),
+        IR::Compact(compact) => is_protected(&compact.input),
+        IR::Validate(validate) => is_protected(&validate.input),
         IR::Pipe(ir, ir1) => is_protected(ir) || is_protected(ir1),
-        IR::Merge(vec) => vec.iter().all(is_protected),
+        IR::Merge(merge) => merge.irs.iter().all(is_protected),
+        IR::Fallback(fallback) => fallback.irs.iter().all(is_protected),
+        IR::Join(join) => is_protected(&join.left) || is_protected(&join.right),
         IR::Discriminate(_, ir) => is_protected(ir),
         IR::Entity(hash_map) => hash_map.values().any(is_protected),
         IR::Service(_) => false,
@@ -77,12 +77,14 @@ where
 
         Ok(OperationPlan {
             root_name: self.plan.root_name.to_string(),
+            operation_name: self.plan.operation_name,
             operation_type: self.plan.operation_type,
             index,
             is_introspection_query: self.plan.is_introspection_query,
             is_dedupe: self.plan.is_dedupe,
             is_const: self.plan.is_const,
             is_protected: self.plan.is_protected,
+            is_compressed: self.plan.is_compressed,
             min_cache_ttl: self.plan.min_cache_ttl,
             selection,
             before: self.plan.before,
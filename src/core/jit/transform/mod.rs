@@ -1,5 +1,6 @@
 mod auth_planner;
 mod check_cache;
+mod check_compressed;
 mod check_const;
 mod check_dedupe;
 mod check_protected;
@@ -9,6 +10,7 @@ mod skip;
 
 pub use auth_planner::*;
 pub use check_cache::*;
+pub use check_compressed::*;
 pub use check_const::*;
 pub use check_dedupe::*;
 pub use check_protected::*;
@@ -226,6 +226,110 @@ impl JsonSchema {
         Valid::succeed(())
     }
 
+    /// Compiles a standard JSON Schema document into a [JsonSchema].
+    ///
+    /// Supports the `type` (incl. nullable types like `["string", "null"]`),
+    /// `properties`, `required`, `items` and `enum` keywords. Properties that
+    /// are not listed in `required` are treated as optional and a schema
+    /// without a `type` accepts any value.
+    pub fn from_json_schema(schema: &serde_json::Value) -> Valid<JsonSchema, String> {
+        let Some(schema) = schema.as_object() else {
+            return Valid::fail("expected schema to be an object".to_string());
+        };
+
+        if let Some(variants) = schema.get("enum") {
+            return Valid::from_option(
+                variants.as_array(),
+                "expected enum to be an array".to_string(),
+            )
+            .and_then(|variants| {
+                Valid::from_iter(variants.iter(), |variant| {
+                    Valid::from_option(
+                        variant.as_str().map(|variant| variant.to_string()),
+                        "expected enum variants to be strings".to_string(),
+                    )
+                })
+            })
+            .map(|variants| JsonSchema::Enum(variants.into_iter().collect()))
+            .trace("enum");
+        }
+
+        let types = match schema.get("type") {
+            None => return Valid::succeed(JsonSchema::Any),
+            Some(serde_json::Value::String(type_of)) => vec![type_of.as_str()],
+            Some(serde_json::Value::Array(types)) => types
+                .iter()
+                .filter_map(|type_of| type_of.as_str())
+                .collect(),
+            Some(_) => {
+                return Valid::fail("expected type to be a string or an array".to_string())
+                    .trace("type")
+            }
+        };
+
+        let nullable = types.contains(&"null");
+        let types = types
+            .into_iter()
+            .filter(|type_of| *type_of != "null")
+            .collect::<Vec<_>>();
+
+        let json_schema = match types.as_slice() {
+            [] => Valid::succeed(JsonSchema::Empty),
+            ["string"] => Valid::succeed(JsonSchema::Str),
+            ["number"] | ["integer"] => Valid::succeed(JsonSchema::Num),
+            ["boolean"] => Valid::succeed(JsonSchema::Bool),
+            ["array"] => match schema.get("items") {
+                Some(items) => JsonSchema::from_json_schema(items)
+                    .map(|items| JsonSchema::Arr(Box::new(items)))
+                    .trace("items"),
+                None => Valid::succeed(JsonSchema::Arr(Box::new(JsonSchema::Any))),
+            },
+            ["object"] => {
+                let required = schema
+                    .get("required")
+                    .and_then(|required| required.as_array())
+                    .map(|required| {
+                        required
+                            .iter()
+                            .filter_map(|name| name.as_str())
+                            .collect::<BTreeSet<_>>()
+                    })
+                    .unwrap_or_default();
+
+                let properties = schema
+                    .get("properties")
+                    .and_then(|properties| properties.as_object())
+                    .map(|properties| properties.iter().collect::<Vec<_>>())
+                    .unwrap_or_default();
+
+                Valid::from_iter(properties, |(name, property)| {
+                    JsonSchema::from_json_schema(property)
+                        .map(|property| {
+                            // the nullable properties are optional already
+                            if required.contains(name.as_str()) || property.is_optional() {
+                                (name.clone(), property)
+                            } else {
+                                (name.clone(), property.optional())
+                            }
+                        })
+                        .trace(name)
+                })
+                .map(|fields| JsonSchema::Obj(fields.into_iter().collect()))
+                .trace("properties")
+            }
+            // a value that can be of multiple types can't be narrowed down any further
+            _ => Valid::succeed(JsonSchema::Any),
+        };
+
+        json_schema.map(|json_schema| {
+            if nullable && !json_schema.is_optional() {
+                json_schema.optional()
+            } else {
+                json_schema
+            }
+        })
+    }
+
     pub fn optional(self) -> JsonSchema {
         JsonSchema::Opt(Box::new(self))
     }
@@ -569,4 +673,44 @@ mod tests {
 
         assert!(child.is_a(&base, "foo").is_succeed());
     }
+
+    #[test]
+    fn test_from_json_schema() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "required": ["id", "tags"],
+            "properties": {
+                "id": { "type": "integer" },
+                "name": { "type": ["string", "null"] },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "status": { "enum": ["ACTIVE", "INACTIVE"] },
+                "meta": {}
+            }
+        });
+
+        let actual = JsonSchema::from_json_schema(&schema).to_result().unwrap();
+        let expected = JsonSchema::from([
+            ("id", JsonSchema::Num),
+            ("name", JsonSchema::Str.optional()),
+            ("tags", JsonSchema::Arr(Box::new(JsonSchema::Str))),
+            (
+                "status",
+                JsonSchema::Enum(BTreeSet::from(["ACTIVE".into(), "INACTIVE".into()])).optional(),
+            ),
+            ("meta", JsonSchema::Any.optional()),
+        ]);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_from_json_schema_invalid() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": { "id": { "type": 1 } }
+        });
+
+        let actual = JsonSchema::from_json_schema(&schema);
+        assert!(actual.is_fail());
+    }
 }
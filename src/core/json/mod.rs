@@ -35,16 +35,53 @@ pub fn gather_path_matches<'json, J: JsonLike<'json>>(
     vector
 }
 
+/// Normalizes the number and string keys, so that `1` and `"1"` are the same
+/// key. The other values aren't keys.
+pub fn key_to_string<'json, J: JsonLike<'json>>(key: &J) -> Option<String> {
+    key.as_str()
+        .map(|a| a.to_string())
+        .or_else(|| key.as_f64().map(|a| a.to_string()))
+}
+
+/// Groups the objects found at the path by the tuple of the values of their
+/// `keys`. The objects missing any of the keys are skipped.
+pub fn group_by_keys<'json, J: JsonLike<'json>>(
+    root: &'json J,
+    path: &[String],
+    keys: &[&str],
+) -> HashMap<Vec<String>, Vec<&'json J>> {
+    let mut map: HashMap<Vec<String>, Vec<&'json J>> = HashMap::new();
+    let Some((first, rest)) = keys.split_first() else {
+        return map;
+    };
+
+    let path = path
+        .iter()
+        .cloned()
+        .chain(std::iter::once(first.to_string()))
+        .collect::<Vec<_>>();
+
+    for (key, value) in gather_path_matches(root, &path, vec![]) {
+        let key = std::iter::once(key_to_string(key))
+            .chain(
+                rest.iter()
+                    .map(|name| value.get_key(name).and_then(key_to_string)),
+            )
+            .collect::<Option<Vec<_>>>();
+
+        if let Some(key) = key {
+            map.entry(key).or_default().push(value);
+        }
+    }
+    map
+}
+
 fn group_by_key<'json, J: JsonLike<'json>>(
     src: Vec<(&'json J, &'json J)>,
 ) -> HashMap<String, Vec<&'json J>> {
     let mut map: HashMap<String, Vec<&'json J>> = HashMap::new();
     for (key, value) in src {
-        // Need to handle number and string keys
-        let key_str = key
-            .as_str()
-            .map(|a| a.to_string())
-            .or_else(|| key.as_f64().map(|a| a.to_string()));
+        let key_str = key_to_string(key);
 
         if let Some(key) = key_str {
             if let Some(values) = map.get_mut(&key) {
@@ -6,6 +6,7 @@ pub mod async_graphql_hyper;
 mod auth;
 pub mod blueprint;
 pub mod cache;
+pub mod cancellation;
 pub mod config;
 mod counter;
 pub mod data_loader;
@@ -32,8 +33,10 @@ pub mod print_schema;
 pub mod proto_reader;
 pub mod resource_reader;
 pub mod rest;
+pub mod rng;
 pub mod runtime;
 pub mod scalar;
+pub mod scalar_codec;
 pub mod schema_extension;
 mod serde_value_ext;
 pub mod tracing;
@@ -48,11 +51,13 @@ pub mod wrapping_type;
 use std::borrow::Cow;
 use std::hash::Hash;
 use std::num::NonZeroU64;
+use std::time::SystemTime;
 
 use async_graphql::{Pos, Positioned};
 use async_graphql_value::ConstValue;
 pub use errata::Errata;
 pub use error::{Error, Result};
+use futures_util::StreamExt;
 use http::Response;
 use ir::model::IoId;
 pub use mustache::Mustache;
@@ -67,6 +72,13 @@ pub const fn default_verify_ssl() -> Option<bool> {
 
 pub trait EnvIO: Send + Sync + 'static {
     fn get(&self, key: &str) -> Option<Cow<'_, str>>;
+
+    /// Reads the current value of the variable. Unlike [EnvIO::get], which
+    /// could be served from a snapshot taken at startup, it reflects the
+    /// changes made to the environment while the server is running.
+    fn get_live(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.get(key)
+    }
 }
 
 #[async_trait::async_trait]
@@ -75,6 +87,66 @@ pub trait HttpIO: Sync + Send + 'static {
         &self,
         request: reqwest::Request,
     ) -> anyhow::Result<Response<hyper::body::Bytes>>;
+
+    /// Executes the request without following the redirects, so that they
+    /// could be followed with a custom policy. Runtimes that can't control the
+    /// redirects execute the request as is.
+    async fn execute_without_redirects(
+        &self,
+        request: reqwest::Request,
+    ) -> anyhow::Result<Response<hyper::body::Bytes>> {
+        self.execute(request).await
+    }
+
+    /// Executes the request and streams the body of the response as it's
+    /// received. Runtimes that can't stream the body yield it as a single
+    /// chunk once it's received completely.
+    async fn execute_stream(
+        &self,
+        request: reqwest::Request,
+    ) -> anyhow::Result<Response<http::BodyStream>> {
+        let response = self.execute(request).await?;
+        let body = response.body;
+
+        Ok(Response {
+            status: response.status,
+            headers: response.headers,
+            body: futures_util::stream::once(async { Ok(body) }).boxed(),
+        })
+    }
+
+    /// Executes the request like `execute_stream`, along with the trailers of
+    /// the response. Runtimes that can't read the trailers resolve them to
+    /// none.
+    async fn execute_stream_with_trailers(
+        &self,
+        request: reqwest::Request,
+    ) -> anyhow::Result<(Response<http::BodyStream>, http::Trailers)> {
+        let response = self.execute_stream(request).await?;
+        Ok((response, Box::pin(futures_util::future::ready(None))))
+    }
+}
+
+/// A connection that exchanges text messages with an upstream, eg. a
+/// WebSocket.
+#[async_trait::async_trait]
+pub trait WebSocketConnection: Send {
+    async fn send(&mut self, message: String) -> anyhow::Result<()>;
+
+    /// Receives the next text message. Returns `None` once the upstream has
+    /// closed the connection, and an error if the connection was lost.
+    async fn recv(&mut self) -> anyhow::Result<Option<String>>;
+}
+
+#[async_trait::async_trait]
+pub trait WebSocketIO: Send + Sync + 'static {
+    /// Opens a connection to the url of the request, negotiating the given
+    /// subprotocol. The headers of the request are sent with the handshake.
+    async fn connect(
+        &self,
+        request: reqwest::Request,
+        protocol: &str,
+    ) -> anyhow::Result<Box<dyn WebSocketConnection>>;
 }
 
 #[async_trait::async_trait]
@@ -93,9 +165,66 @@ pub trait Cache: Send + Sync {
         value: Self::Value,
         ttl: NonZeroU64,
     ) -> Result<(), cache::Error>;
+    /// Stores the value and associates its key with the invalidation tags.
+    async fn set_with_tags<'a>(
+        &'a self,
+        key: Self::Key,
+        value: Self::Value,
+        ttl: NonZeroU64,
+        tags: Vec<String>,
+    ) -> Result<(), cache::Error>;
     async fn get<'a>(&'a self, key: &'a Self::Key) -> Result<Option<Self::Value>, cache::Error>;
+    /// Removes all the entries tagged with the given tag and returns the number
+    /// of removed entries.
+    async fn invalidate<'a>(&'a self, tag: &'a str) -> Result<usize, cache::Error>;
+    /// Removes the entry of the key and returns whether it was cached.
+    async fn evict<'a>(&'a self, key: &'a Self::Key) -> Result<bool, cache::Error>;
+    /// Removes all the entries and returns the number of removed entries.
+    async fn clear<'a>(&'a self) -> Result<usize, cache::Error>;
 
     fn hit_rate(&self) -> Option<f64>;
+
+    /// The number of the entries and the lookups, `None` when the store
+    /// doesn't track them.
+    fn stats(&self) -> Option<cache::Stats> {
+        None
+    }
+
+    /// The entries that haven't expired, `None` when the store can't list
+    /// them.
+    fn entries(&self) -> Option<Vec<cache::Entry<Self::Key, Self::Value>>> {
+        None
+    }
+
+    /// Stores the entries for the time they have left to live, the ones that
+    /// have expired are skipped. Returns the number of stored entries.
+    async fn restore<'a>(
+        &'a self,
+        entries: Vec<cache::Entry<Self::Key, Self::Value>>,
+    ) -> Result<usize, cache::Error>
+    where
+        Self::Key: Send,
+        Self::Value: Send,
+    {
+        let now = SystemTime::now();
+        let mut count = 0;
+        for entry in entries {
+            let ttl = entry
+                .expires_at
+                .duration_since(now)
+                .ok()
+                .and_then(|ttl| NonZeroU64::new(ttl.as_millis() as u64));
+            let Some(ttl) = ttl else {
+                continue;
+            };
+
+            self.set_with_tags(entry.key, entry.value, ttl, entry.tags)
+                .await?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
 }
 
 pub type EntityCache = dyn Cache<Key = IoId, Value = ConstValue>;
@@ -2,11 +2,16 @@
 //! structure.
 use std::borrow::Cow;
 
+use async_graphql::parser::types::OperationType;
 use serde_json::json;
 
 use crate::core::ir::{EvalContext, ResolverContextLike};
 use crate::core::json::JsonLike;
 
+/// Root of the env variables that are read from the environment at the time of
+/// evaluation, see [crate::core::EnvIO::get_live].
+pub const RUNTIME_ENV: &str = "runtimeEnv";
+
 ///
 /// The PathString trait provides a method for accessing values from a JSON-like
 /// structure. The returned value is encoded as a plain string.
@@ -38,6 +43,17 @@ impl PathString for serde_json::Value {
     }
 }
 
+impl PathValue for serde_json::Value {
+    fn raw_value<'a, T: AsRef<str>>(&'a self, path: &[T]) -> Option<ValueString<'a>> {
+        self.get_path(path).map(|value| match value {
+            serde_json::Value::String(s) => ValueString::String(Cow::Borrowed(s.as_str())),
+            value => ValueString::Value(Cow::Owned(
+                async_graphql::Value::from_json(value.clone()).unwrap_or_default(),
+            )),
+        })
+    }
+}
+
 fn convert_value(value: Cow<'_, async_graphql::Value>) -> Option<Cow<'_, str>> {
     match value {
         Cow::Owned(async_graphql::Value::String(s)) => Some(Cow::Owned(s)),
@@ -94,6 +110,22 @@ impl<Ctx: ResolverContextLike> EvalContext<'_, Ctx> {
                     ctx.var(tail[0].as_ref())?,
                 ))),
                 "env" => Some(ValueString::String(ctx.env_var(tail[0].as_ref())?)),
+                "operation" => match tail[0].as_ref() {
+                    "name" => Some(ValueString::String(Cow::Borrowed(ctx.operation_name()?))),
+                    "type" => Some(ValueString::String(Cow::Borrowed(
+                        match ctx.operation_type()? {
+                            OperationType::Query => "query",
+                            OperationType::Mutation => "mutation",
+                            OperationType::Subscription => "subscription",
+                        },
+                    ))),
+                    _ => None,
+                },
+                "request" => match tail[0].as_ref() {
+                    "id" => Some(ValueString::String(Cow::Borrowed(ctx.request_id()?))),
+                    _ => None,
+                },
+                RUNTIME_ENV => Some(ValueString::String(ctx.runtime_env_var(tail[0].as_ref())?)),
                 _ => None,
             })
     }
@@ -236,7 +268,19 @@ mod tests {
                 false
             }
 
+            fn operation_name(&self) -> Option<&str> {
+                None
+            }
+
+            fn operation_type(&self) -> Option<async_graphql::parser::types::OperationType> {
+                None
+            }
+
             fn add_error(&self, _: async_graphql::ServerError) {}
+
+            fn response_path(&self) -> Vec<async_graphql::PathSegment> {
+                vec![]
+            }
         }
 
         static REQ_CTX: Lazy<RequestContext> = Lazy::new(|| {
@@ -244,6 +288,7 @@ mod tests {
 
             req_ctx.server.vars = TEST_VARS.clone();
             req_ctx.runtime.env = Arc::new(Env::init(TEST_ENV_VARS.clone()));
+            req_ctx.request_id = Some(HeaderValue::from_static("req-1"));
 
             req_ctx
         });
@@ -373,6 +418,13 @@ mod tests {
             );
             assert_eq!(EVAL_CTX.raw_value(&["env", "x-missing"]), None);
 
+            // request
+            assert_eq!(
+                EVAL_CTX.raw_value(&["request", "id"]),
+                Some(ValueString::String(Cow::Borrowed("req-1")))
+            );
+            assert_eq!(EVAL_CTX.raw_value(&["request", "method"]), None);
+
             // other value types
             assert_eq!(EVAL_CTX.raw_value(&["foo", "key"]), None);
             assert_eq!(EVAL_CTX.raw_value(&["bar", "key"]), None);
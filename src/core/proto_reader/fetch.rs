@@ -157,8 +157,10 @@ impl GrpcReflection {
             HeaderName::from_static("content-type"),
             Mustache::parse("application/grpc+proto"),
         ));
+        // the reflection method streams its requests, the request is sent as a
+        // stream of a single message
         let body_ = Some(RequestBody {
-            mustache: Some(Mustache::parse(body.to_string().as_str())),
+            mustache: Some(Mustache::parse(json!([body]).to_string().as_str())),
             value: Default::default(),
         });
         let req_template = RequestTemplate {
@@ -167,6 +169,12 @@ impl GrpcReflection {
             body: body_,
             operation: operation.clone(),
             operation_type: Default::default(),
+            field_mask: None,
+            dynamic_method: None,
+            routes: None,
+            priority: Default::default(),
+            retry: None,
+            transcoding: None,
         };
 
         let ctx = ConfigReaderContext::new(&self.target_runtime);
@@ -175,7 +183,8 @@ impl GrpcReflection {
         let resp = self.target_runtime.http2_only.execute(req).await?;
         let body = resp.body.as_bytes();
 
-        let response: ReflectionResponse = operation.convert_output(body)?;
+        // and its response is a stream of a single message as well
+        let response: ReflectionResponse = operation.convert_message(body)?;
         Ok(response)
     }
 }
@@ -0,0 +1,87 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+
+use rand::rngs::StdRng;
+use rand::{Rng as _, RngCore, SeedableRng};
+
+/// Source of randomness for all the randomized decisions, eg. generating the
+/// request ids or splitting the traffic between the upstreams. The generator
+/// is seeded from the entropy of the OS by default, while a fixed seed makes
+/// the decisions reproducible, eg. in tests.
+#[derive(Clone)]
+pub struct Rng(Arc<Mutex<StdRng>>);
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(StdRng::from_entropy())
+    }
+}
+
+impl Rng {
+    fn new(rng: StdRng) -> Self {
+        Self(Arc::new(Mutex::new(rng)))
+    }
+
+    /// Creates a generator that yields the same sequence of values for the
+    /// same seed.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::new(StdRng::seed_from_u64(seed))
+    }
+
+    pub fn next_u64(&self) -> u64 {
+        self.0.lock().unwrap().next_u64()
+    }
+
+    pub fn fill_bytes(&self, bytes: &mut [u8]) {
+        self.0.lock().unwrap().fill_bytes(bytes)
+    }
+
+    /// Returns `true` with the given probability, the probability is clamped
+    /// into the `0.0..=1.0` range.
+    pub fn gen_bool(&self, probability: f64) -> bool {
+        let probability = probability.clamp(0.0, 1.0);
+        self.0.lock().unwrap().gen_bool(probability)
+    }
+}
+
+impl Debug for Rng {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Rng").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Routes a tenth of the requests to the canary.
+    fn canary_split(rng: &Rng) -> Vec<bool> {
+        (0..100).map(|_| rng.gen_bool(0.1)).collect()
+    }
+
+    #[test]
+    fn test_canary_split_with_seed() {
+        let first = canary_split(&Rng::with_seed(42));
+        let second = canary_split(&Rng::with_seed(42));
+
+        assert_eq!(first, second);
+        assert!(first.contains(&true));
+        assert!(first.contains(&false));
+        assert_ne!(first, canary_split(&Rng::with_seed(7)));
+    }
+
+    #[test]
+    fn test_shared_sequence() {
+        let rng = Rng::with_seed(42);
+        let clone = rng.clone();
+
+        // clones draw from the same sequence
+        let actual = [rng.next_u64(), clone.next_u64()];
+        let expected = {
+            let rng = Rng::with_seed(42);
+            [rng.next_u64(), rng.next_u64()]
+        };
+
+        assert_eq!(actual, expected);
+    }
+}
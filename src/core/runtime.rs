@@ -2,10 +2,13 @@ use std::sync::Arc;
 
 use async_graphql_value::ConstValue;
 
+use super::http::BodyTransformer;
 use super::ir::model::IoId;
+use super::ir::EvalMiddleware;
+use crate::core::rng::Rng;
 use crate::core::schema_extension::SchemaExtension;
 use crate::core::worker::{Command, Event};
-use crate::core::{Cache, EnvIO, FileIO, HttpIO, WorkerIO};
+use crate::core::{Cache, EnvIO, FileIO, HttpIO, WebSocketIO, WorkerIO};
 
 /// The TargetRuntime struct unifies the available runtime-specific
 /// IO implementations. This is used to reduce piping IO structs all
@@ -32,12 +35,43 @@ pub struct TargetRuntime {
     pub cmd_worker: Option<Arc<dyn WorkerIO<Event, Command>>>,
     /// Worker middleware for resolving data.
     pub worker: Option<Arc<dyn WorkerIO<ConstValue, ConstValue>>>,
+    /// Source of randomness for the randomized decisions.
+    pub rng: Rng,
+    /// WebSocket client for the subscriptions to the upstream GraphQL
+    /// servers, not available on all the targets.
+    pub websocket: Option<Arc<dyn WebSocketIO>>,
+    /// Transformers of the JSON bodies of the HTTP requests, applied in the
+    /// order they are added.
+    pub body_transformers: Arc<Vec<Arc<dyn BodyTransformer>>>,
+    /// Middlewares wrapping the evaluation of the nodes of the IR, the first
+    /// one added is the outermost.
+    pub eval_middlewares: Arc<Vec<Arc<dyn EvalMiddleware>>>,
 }
 
 impl TargetRuntime {
     pub fn add_extensions(&mut self, extensions: Vec<SchemaExtension>) {
         self.extensions = Arc::new(extensions);
     }
+
+    /// Adds a transformer to the end of the chain of the body transformers.
+    pub fn add_body_transformer(&mut self, transformer: Arc<dyn BodyTransformer>) {
+        let mut transformers = self.body_transformers.as_ref().clone();
+        transformers.push(transformer);
+        self.body_transformers = Arc::new(transformers);
+    }
+
+    /// Adds a middleware to the bottom of the stack of the eval middlewares.
+    pub fn add_eval_middleware(&mut self, middleware: Arc<dyn EvalMiddleware>) {
+        let mut middlewares = self.eval_middlewares.as_ref().clone();
+        middlewares.push(middleware);
+        self.eval_middlewares = Arc::new(middlewares);
+    }
+
+    /// Seeds the randomized decisions so that they are reproducible.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::with_seed(seed);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -60,6 +94,7 @@ pub mod test {
     use crate::core::blueprint::Upstream;
     use crate::core::cache::InMemoryCache;
     use crate::core::http::Response;
+    use crate::core::rng::Rng;
     use crate::core::runtime::TargetRuntime;
     use crate::core::worker::{Command, Event};
     use crate::core::{blueprint, EnvIO, FileIO, HttpIO};
@@ -87,7 +122,7 @@ pub mod test {
                 .pool_idle_timeout(Some(Duration::from_secs(upstream.pool_idle_timeout)))
                 .pool_max_idle_per_host(upstream.pool_max_idle_per_host)
                 .user_agent(upstream.user_agent.clone())
-                .danger_accept_invalid_certs(!upstream.verify_ssl);
+                .danger_accept_invalid_certs(upstream.accept_invalid_certs());
 
             // Add Http2 Prior Knowledge
             if upstream.http2_only {
@@ -188,6 +223,8 @@ pub mod test {
             file: Arc::new(file),
             cache: Arc::new(InMemoryCache::default()),
             extensions: Arc::new(vec![]),
+            body_transformers: Arc::new(vec![]),
+            eval_middlewares: Arc::new(vec![]),
             cmd_worker: match &script {
                 Some(script) => Some(init_worker_io::<Event, Command>(script.to_owned())),
                 None => None,
@@ -196,6 +233,9 @@ pub mod test {
                 Some(script) => Some(init_worker_io::<Value, Value>(script.to_owned())),
                 None => None,
             },
+            // tests are reproducible with a fixed seed
+            rng: Rng::with_seed(0),
+            websocket: None,
         }
     }
 }
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use async_graphql_value::ConstValue;
+
+/// Converts the values of a custom scalar between the representation used by
+/// the upstream and the one that is presented to the clients, eg. integer
+/// cents returned by an API could be presented as a `Money` string.
+pub trait ScalarCodec: Send + Sync {
+    /// Decodes the value received from the upstream.
+    fn parse(&self, value: ConstValue) -> Result<ConstValue, String>;
+
+    /// Encodes the decoded value into the one that is sent to the client.
+    fn serialize(&self, value: ConstValue) -> Result<ConstValue, String>;
+}
+
+/// Registry of the [ScalarCodec]s keyed by the name of the scalar. Codecs
+/// should be registered on the blueprint before the server is started.
+#[derive(Clone, Default)]
+pub struct ScalarCodecs(HashMap<String, Arc<dyn ScalarCodec>>);
+
+impl ScalarCodecs {
+    pub fn register(mut self, name: impl Into<String>, codec: impl ScalarCodec + 'static) -> Self {
+        self.0.insert(name.into(), Arc::new(codec));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn ScalarCodec>> {
+        self.0.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Decodes the upstream value and encodes it for the client with the codec
+    /// registered for the scalar. Lists are converted item by item and nulls
+    /// are left as is.
+    pub fn convert(&self, name: &str, value: ConstValue) -> Result<ConstValue, String> {
+        match self.get(name) {
+            Some(codec) => convert(codec.as_ref(), value),
+            None => Ok(value),
+        }
+    }
+}
+
+fn convert(codec: &dyn ScalarCodec, value: ConstValue) -> Result<ConstValue, String> {
+    match value {
+        ConstValue::Null => Ok(ConstValue::Null),
+        ConstValue::List(list) => Ok(ConstValue::List(
+            list.into_iter()
+                .map(|value| convert(codec, value))
+                .collect::<Result<_, _>>()?,
+        )),
+        value => codec.serialize(codec.parse(value)?),
+    }
+}
+
+impl Debug for ScalarCodecs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Money;
+
+    impl ScalarCodec for Money {
+        fn parse(&self, value: ConstValue) -> Result<ConstValue, String> {
+            match value {
+                ConstValue::Number(cents) if cents.is_i64() => Ok(ConstValue::Number(cents)),
+                value => Err(format!("expected integer cents, got {}", value)),
+            }
+        }
+
+        fn serialize(&self, value: ConstValue) -> Result<ConstValue, String> {
+            let ConstValue::Number(cents) = value else {
+                return Err(format!("expected integer cents, got {}", value));
+            };
+            let cents = cents.as_i64().unwrap_or_default();
+
+            Ok(ConstValue::String(format!(
+                "{}.{:02}",
+                cents / 100,
+                cents % 100
+            )))
+        }
+    }
+
+    #[test]
+    fn test_convert() {
+        let codecs = ScalarCodecs::default().register("Money", Money);
+
+        let actual = codecs.convert("Money", ConstValue::from(1234)).unwrap();
+        assert_eq!(actual, ConstValue::String("12.34".to_string()));
+
+        let actual = codecs
+            .convert(
+                "Money",
+                ConstValue::List(vec![ConstValue::from(5), ConstValue::Null]),
+            )
+            .unwrap();
+        let expected = ConstValue::List(vec![ConstValue::from("0.05"), ConstValue::Null]);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_convert_invalid() {
+        let codecs = ScalarCodecs::default().register("Money", Money);
+
+        let actual = codecs.convert("Money", ConstValue::from("abc"));
+        assert_eq!(
+            actual,
+            Err(r#"expected integer cents, got "abc""#.to_string())
+        );
+    }
+
+    #[test]
+    fn test_convert_unregistered() {
+        let codecs = ScalarCodecs::default();
+
+        let actual = codecs.convert("Money", ConstValue::from(1234)).unwrap();
+        assert_eq!(actual, ConstValue::from(1234));
+    }
+}
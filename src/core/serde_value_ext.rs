@@ -4,14 +4,16 @@ use async_graphql::{Name, Value as GraphQLValue};
 use indexmap::IndexMap;
 
 use crate::core::blueprint::DynamicValue;
-use crate::core::path::PathString;
+use crate::core::ir::is_truthy;
+use crate::core::mustache::Segment;
+use crate::core::path::{PathString, PathValue, ValueString};
 
 pub trait ValueExt {
-    fn render_value(&self, ctx: &impl PathString) -> GraphQLValue;
+    fn render_value(&self, ctx: &(impl PathString + PathValue)) -> GraphQLValue;
 }
 
 impl ValueExt for DynamicValue<async_graphql::Value> {
-    fn render_value<'a>(&self, ctx: &'a impl PathString) -> GraphQLValue {
+    fn render_value<'a>(&self, ctx: &'a (impl PathString + PathValue)) -> GraphQLValue {
         match self {
             DynamicValue::Value(value) => value.to_owned(),
             DynamicValue::Mustache(m) => {
@@ -40,15 +42,48 @@ impl ValueExt for DynamicValue<async_graphql::Value> {
                 let out: Vec<_> = arr.iter().map(|v| v.render_value(ctx)).collect();
                 GraphQLValue::List(out)
             }
+            DynamicValue::Cond(cond, then, otherwise) => {
+                if is_truthy(&render_operand(cond, ctx)) {
+                    render_operand(then, ctx)
+                } else {
+                    render_operand(otherwise, ctx)
+                }
+            }
+            DynamicValue::Coalesce(value, default) => match render_operand(value, ctx) {
+                GraphQLValue::Null => render_operand(default, ctx),
+                value => value,
+            },
         }
     }
 }
 
+/// Renders an operand of the operators, the paths resolve to their typed
+/// values and the paths that are missing are null rather than empty strings.
+fn render_operand(
+    value: &DynamicValue<GraphQLValue>,
+    ctx: &(impl PathString + PathValue),
+) -> GraphQLValue {
+    match value {
+        DynamicValue::Mustache(m) => match m.segments().as_slice() {
+            [Segment::Expression(parts)] => match ctx.raw_value(parts) {
+                Some(ValueString::Value(value)) => value.into_owned(),
+                Some(ValueString::String(s)) => GraphQLValue::String(s.into_owned()),
+                None => GraphQLValue::Null,
+            },
+            _ => value.render_value(ctx),
+        },
+        value => value.render_value(ctx),
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::borrow::Cow;
+
     use serde_json::json;
 
     use crate::core::blueprint::DynamicValue;
+    use crate::core::path::{PathString, PathValue, ValueString};
     use crate::core::serde_value_ext::ValueExt;
 
     #[test]
@@ -162,4 +197,96 @@ mod tests {
                 .unwrap();
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_render_coalesce() {
+        let value = DynamicValue::try_from(&json!({
+            "limit": "{{ .args.limit ?? 10 }}",
+            "name": "{{ .args.nick ?? .args.name ?? 'anonymous' }}",
+        }))
+        .unwrap();
+
+        let result = value.render_value(&json!({"args": {"limit": 5, "name": "foo"}}));
+        let expected = async_graphql::Value::from_json(json!({"limit": 5, "name": "foo"}));
+        assert_eq!(result, expected.unwrap());
+
+        let result = value.render_value(&json!({"args": {"nick": null}}));
+        let expected = json!({"limit": 10, "name": "anonymous"});
+        assert_eq!(result, async_graphql::Value::from_json(expected).unwrap());
+    }
+
+    #[test]
+    fn test_render_cond() {
+        let value = DynamicValue::try_from(&json!(
+            "{{ .args.admin ? 'full' : .args.guest ? 'none' : 'partial' }}"
+        ))
+        .unwrap();
+
+        let render = |args: serde_json::Value| value.render_value(&json!({ "args": args }));
+        assert_eq!(render(json!({"admin": true})), "full".into());
+        assert_eq!(render(json!({"admin": 0, "guest": "yes"})), "none".into());
+        assert_eq!(render(json!({"admin": ""})), "partial".into());
+    }
+
+    #[test]
+    fn test_render_operand_typed() {
+        let value = DynamicValue::try_from(&json!({
+            "s": "{{ .args.s ?? 'x' }}",
+            "n": "{{ .args.n ?? 'x' }}",
+        }))
+        .unwrap();
+
+        // the strings aren't parsed as JSON, so "null" isn't coalesced
+        let result = value.render_value(&json!({"args": {"s": "null", "n": "1"}}));
+        let expected = json!({"s": "null", "n": "1"});
+        assert_eq!(result, async_graphql::Value::from_json(expected).unwrap());
+    }
+
+    /// Records the paths that are read
+    #[derive(Default)]
+    struct Reads(std::sync::Mutex<Vec<String>>, serde_json::Value);
+
+    impl Reads {
+        fn read<T: AsRef<str>>(&self, path: &[T]) {
+            let path_str = path.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+            self.0.lock().unwrap().push(path_str.join("."));
+        }
+    }
+
+    impl PathString for Reads {
+        fn path_string<'a, T: AsRef<str>>(&'a self, path: &'a [T]) -> Option<Cow<'a, str>> {
+            self.read(path);
+            self.1.path_string(path)
+        }
+    }
+
+    impl PathValue for Reads {
+        fn raw_value<'a, T: AsRef<str>>(&'a self, path: &[T]) -> Option<ValueString<'a>> {
+            self.read(path);
+            self.1.raw_value(path)
+        }
+    }
+
+    #[test]
+    fn test_short_circuit() {
+        let value = DynamicValue::try_from(&json!({
+            "cond": "{{ .args.admin ? .value.secret : .value.public }}",
+            "coalesce": "{{ .args.name ?? .value.expensive }}",
+        }))
+        .unwrap();
+        let ctx = Reads(
+            Default::default(),
+            json!({"args": {"admin": false, "name": "foo"}, "value": {"public": 1}}),
+        );
+
+        let result = value.render_value(&ctx);
+        let expected = json!({"cond": 1, "coalesce": "foo"});
+        assert_eq!(result, async_graphql::Value::from_json(expected).unwrap());
+
+        // the untaken branches aren't evaluated
+        assert_eq!(
+            *ctx.0.lock().unwrap(),
+            vec!["args.admin", "value.public", "args.name"]
+        );
+    }
 }
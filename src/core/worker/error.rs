@@ -33,6 +33,10 @@ pub enum Error {
     #[debug("Runtime not initialized")]
     RuntimeNotInitialized,
 
+    #[debug("JS worker pool is saturated, {} calls are already queued", _0)]
+    #[from(ignore)]
+    PoolSaturated(usize),
+
     #[debug("{} is not a function", _0)]
     #[from(ignore)]
     InvalidFunction(String),
@@ -100,6 +104,11 @@ impl Display for Error {
             Error::CLI(msg) => write!(f, "CLI Error: {}", msg),
             Error::Join(error) => write!(f, "Join Error: {}", error),
             Error::RuntimeNotInitialized => write!(f, "Runtime not initialized"),
+            Error::PoolSaturated(queued) => write!(
+                f,
+                "JS worker pool is saturated, {} calls are already queued",
+                queued
+            ),
             Error::InvalidFunction(function_name) => {
                 write!(f, "{} is not a function", function_name)
             }
@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+use serde::{Deserialize, Serialize};
+
 use super::ir::Error;
 use super::worker::WorkerRequest;
 use super::{worker, WorkerIO};
@@ -7,7 +9,7 @@ use crate::core::http::Response;
 
 /// User can configure the hooks on directive
 /// for the requests.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct WorkerHooks {
     pub on_request: Option<String>,
     pub on_response: Option<String>,
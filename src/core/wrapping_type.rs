@@ -77,6 +77,14 @@ impl Type {
         matches!(self, Type::List { .. })
     }
 
+    /// number of the lists the type is nested in, eg. `2` for `[[Int]]`
+    pub fn list_depth(&self) -> usize {
+        match self {
+            Type::Named { .. } => 0,
+            Type::List { of_type, .. } => 1 + of_type.list_depth(),
+        }
+    }
+
     /// convert this type into NonNull type
     pub fn into_required(self) -> Self {
         match self {
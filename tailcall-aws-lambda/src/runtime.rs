@@ -60,7 +60,11 @@ pub fn init_runtime() -> TargetRuntime {
         env: init_env(),
         cache: init_cache(),
         extensions: Arc::new(vec![]),
+        body_transformers: Arc::new(vec![]),
+        eval_middlewares: Arc::new(vec![]),
         cmd_worker: None,
         worker: None,
+        rng: Default::default(),
+        websocket: None,
     }
 }
@@ -50,6 +50,17 @@ impl Cache for CloudflareChronoCache {
         .await
     }
 
+    // KV doesn't support querying the keys by tag, so the tags are ignored
+    async fn set_with_tags<'a>(
+        &'a self,
+        key: IoId,
+        value: ConstValue,
+        ttl: NonZeroU64,
+        _: Vec<String>,
+    ) -> Result<(), cache::Error> {
+        self.set(key, value, ttl).await
+    }
+
     async fn get<'a>(&'a self, key: &'a IoId) -> Result<Option<Self::Value>, cache::Error> {
         let kv_store = self.get_kv()?;
         let key = key.as_u64().to_string();
@@ -64,6 +75,30 @@ impl Cache for CloudflareChronoCache {
         .await
     }
 
+    async fn invalidate<'a>(&'a self, _: &'a str) -> Result<usize, cache::Error> {
+        Ok(0)
+    }
+
+    async fn evict<'a>(&'a self, key: &'a IoId) -> Result<bool, cache::Error> {
+        let kv_store = self.get_kv()?;
+        let key = key.as_u64().to_string();
+        async_std::task::spawn_local(async move {
+            kv_store
+                .delete(&key)
+                .await
+                .map_err(|e| cache::Error::Kv(e.to_string()))?;
+            // KV doesn't report whether the key existed
+            Ok(true)
+        })
+        .await
+    }
+
+    // listing the keys of KV is paginated and eventually consistent, so the
+    // namespace is expected to be flushed with wrangler instead
+    async fn clear<'a>(&'a self) -> Result<usize, cache::Error> {
+        Err(cache::Error::Kv("Flushing KV isn't supported".to_string()))
+    }
+
     fn hit_rate(&self) -> Option<f64> {
         None
     }
@@ -8,6 +8,7 @@ use std::sync::Arc;
 use moka::future::Cache;
 use moka::policy::EvictionPolicy;
 
+#[derive(Clone)]
 pub struct HttpCacheManager {
     pub cache: Arc<Cache<String, Store>>,
 }
@@ -25,6 +25,8 @@ pub fn from_directive_location(str: DirectiveLocation) -> String {
         DirectiveLocation::Schema => String::from("SCHEMA"),
         DirectiveLocation::Object => String::from("OBJECT"),
         DirectiveLocation::FieldDefinition => String::from("FIELD_DEFINITION"),
+        DirectiveLocation::ArgumentDefinition => String::from("ARGUMENT_DEFINITION"),
+        DirectiveLocation::Enum => String::from("ENUM"),
         DirectiveLocation::EnumValue => String::from("ENUM_VALUE"),
         _ => String::from("FIELD_DEFINITION"),
     }
@@ -35,6 +37,8 @@ fn into_directive_location(str: &str) -> DirectiveLocation {
         "Schema" => DirectiveLocation::Schema,
         "Object" => DirectiveLocation::Object,
         "FieldDefinition" => DirectiveLocation::FieldDefinition,
+        "ArgumentDefinition" => DirectiveLocation::ArgumentDefinition,
+        "Enum" => DirectiveLocation::Enum,
         "EnumValue" => DirectiveLocation::EnumValue,
         _ => DirectiveLocation::FieldDefinition,
     }
@@ -39,7 +39,11 @@ pub fn init_rt() -> TargetRuntime {
         file,
         cache,
         extensions: Arc::new(vec![]),
+        body_transformers: Arc::new(vec![]),
+        eval_middlewares: Arc::new(vec![]),
         cmd_worker: None,
         worker: None,
+        rng: Default::default(),
+        websocket: None,
     }
 }
@@ -17,6 +17,7 @@ use tailcall::core::blueprint::Blueprint;
 use tailcall::core::cache::InMemoryCache;
 use tailcall::core::config::{ConfigModule, Link, RuntimeConfig, Source};
 use tailcall::core::merge_right::MergeRight;
+use tailcall::core::rng::Rng;
 use tailcall::core::runtime::TargetRuntime;
 use tailcall::core::worker::{Command, Event};
 use tailcall::core::{EnvIO, WorkerIO};
@@ -310,8 +311,12 @@ impl ExecutionSpec {
             env: Arc::new(Env::init(env)),
             cache: Arc::new(InMemoryCache::default()),
             extensions: Arc::new(vec![]),
+            body_transformers: Arc::new(vec![]),
+            eval_middlewares: Arc::new(vec![]),
             cmd_worker: http_worker,
             worker,
+            rng: Rng::with_seed(0),
+            websocket: None,
         };
 
         let endpoints = config
@@ -11,6 +11,7 @@ use tailcall::cli::javascript::init_worker_io;
 use tailcall::core::blueprint::Script;
 use tailcall::core::cache::InMemoryCache;
 use tailcall::core::config::RuntimeConfig;
+use tailcall::core::rng::Rng;
 use tailcall::core::runtime::TargetRuntime;
 use tailcall::core::worker::{Command, Event};
 
@@ -81,6 +82,8 @@ pub fn create_runtime(
         file: Arc::new(file),
         cache: Arc::new(InMemoryCache::default()),
         extensions: Arc::new(vec![]),
+        body_transformers: Arc::new(vec![]),
+        eval_middlewares: Arc::new(vec![]),
         cmd_worker: match &script {
             Some(script) => Some(init_worker_io::<Event, Command>(script.to_owned())),
             None => None,
@@ -89,5 +92,7 @@ pub fn create_runtime(
             Some(script) => Some(init_worker_io::<Value, Value>(script.to_owned())),
             None => None,
         },
+        rng: Rng::with_seed(0),
+        websocket: None,
     }
 }
@@ -15,6 +15,7 @@ pub mod test {
     use tailcall::core::blueprint::{Script, Upstream};
     use tailcall::core::cache::InMemoryCache;
     use tailcall::core::http::Response;
+    use tailcall::core::rng::Rng;
     use tailcall::core::runtime::TargetRuntime;
     use tailcall::core::worker::{Command, Event};
     use tailcall::core::{EnvIO, FileIO, HttpIO};
@@ -145,6 +146,8 @@ pub mod test {
             file: Arc::new(file),
             cache: Arc::new(InMemoryCache::default()),
             extensions: Arc::new(vec![]),
+            body_transformers: Arc::new(vec![]),
+            eval_middlewares: Arc::new(vec![]),
             cmd_worker: match &script {
                 Some(script) => Some(init_worker_io::<Event, Command>(script.to_owned())),
                 None => None,
@@ -153,6 +156,8 @@ pub mod test {
                 Some(script) => Some(init_worker_io::<Value, Value>(script.to_owned())),
                 None => None,
             },
+            rng: Rng::with_seed(0),
+            websocket: None,
         }
     }
 }